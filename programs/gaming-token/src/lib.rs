@@ -1,8 +1,78 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, MintTo, Transfer, Burn};
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id, AssociatedToken};
+use anchor_spl::metadata::mpl_token_metadata::types::DataV2;
+use anchor_spl::metadata::mpl_token_metadata::MAX_URI_LENGTH;
+use anchor_spl::metadata::{self, CreateMetadataAccountsV3, Metadata, UpdateMetadataAccountsV2};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::system_program::{self, CreateAccount};
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::{
+    instruction::initialize_transfer_fee_config, MAX_FEE_BASIS_POINTS,
+};
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_interface::{
+    self, Mint, TokenAccount, TokenInterface, MintTo, TransferChecked, Burn, SetAuthority, InitializeMint2,
+    CloseAccount, Approve, Revoke, FreezeAccount, ThawAccount,
+};
 
 declare_id!("DhkqYC1mAnZ41dgPz6NDLovGM6zxE1j7wHLBAizYkNB8");
 
+pub const BPS_DENOMINATOR: u128 = 10_000;
+/// Fixed-point scale for `reward_per_token_stored`.
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+pub const MAX_LOCK_TIERS: usize = 4;
+/// Longest lock any pool accepts, whatever its own maximum.
+pub const MAX_LOCK_PERIOD_SECONDS: i64 = 10 * 365 * 24 * 60 * 60;
+/// Largest LP pool boost, on top of the base emission.
+pub const MAX_LP_BOOST_BPS: u16 = 10_000;
+/// Largest protocol cut of a reward payout.
+pub const MAX_CLAIM_FEE_BPS: u16 = 1_000;
+/// Largest referral bonus, as a share of the referee's claims.
+pub const MAX_REFERRAL_BPS: u16 = 1_000;
+/// Largest bonus for solitaire achievement holders, as a share of their claims.
+pub const MAX_ACHIEVEMENT_BOOST_BPS: u16 = 5_000;
+/// Shares locked away by a compound vault's first deposit, so its share
+/// price can't be inflated from a near-empty vault.
+pub const COMPOUND_VAULT_DEAD_SHARES: u64 = 1_000;
+/// Largest bonus a sunsetting pool can add to migrated stake.
+pub const MAX_MIGRATION_BONUS_BPS: u16 = 2_000;
+/// Longest reward warm-up a pool can impose on new stake.
+pub const MAX_REWARD_WARMUP_SECONDS: i64 = 30 * 24 * 60 * 60;
+/// Seed prefix of the solitaire program's `PlayerStats` PDA, followed by the player.
+pub const PLAYER_STATS_SEED: &[u8] = b"player_stats";
+/// Current `StakerStats` layout.
+pub const STAKER_STATS_VERSION: u8 = 1;
+/// Current `StakeAccount` layout; bump it with every change to the struct.
+pub const STAKE_ACCOUNT_VERSION: u8 = 5;
+/// Version of the cross-program staking interface (`assert_min_stake` and
+/// `StakeCheck`). Bump it with every change callers could notice; it is kept
+/// apart from `STAKE_ACCOUNT_VERSION` so layout changes don't reach them.
+pub const STAKE_INTERFACE_VERSION: u8 = 1;
+/// Notice stakers get of rate changes until the staking admin sets another.
+pub const DEFAULT_POOL_UPDATE_DELAY_SECONDS: i64 = 48 * 60 * 60;
+/// Pools listed per `PoolRegistryPage`.
+pub const POOL_REGISTRY_PAGE_SIZE: usize = 32;
+/// Partner reward tokens a pool can carry next to its own reward mint.
+pub const MAX_REWARD_SLOTS: usize = 3;
+/// Epochs one update may roll; further missed epochs are caught up by the next call.
+pub const MAX_EPOCH_ROLLS: u32 = 64;
+/// Entries kept in a pool's reward rate history before the oldest is overwritten.
+pub const RATE_HISTORY_LEN: usize = 64;
+/// Funding of at least this share of the existing reserve is recorded in the rate history.
+pub const RATE_HISTORY_FUNDING_BPS: u128 = 1_000;
+/// Shortest gap between two `snapshot_pool_rate` entries.
+pub const RATE_SNAPSHOT_INTERVAL_SECONDS: i64 = 60 * 60;
+pub const MAX_PROPOSAL_DESCRIPTION_LEN: usize = 200;
+/// Recipients one `batch_mint` can pay, bounded by compute and account limits.
+pub const MAX_BATCH_MINT_RECIPIENTS: usize = 25;
+/// Budget window for game server rewards.
+pub const GAME_SERVER_WINDOW_SECONDS: i64 = 24 * 60 * 60;
+
 #[program]
 pub mod gaming_token {
     use super::*;
@@ -12,20 +82,75 @@ pub mod gaming_token {
         token_name: String,
         token_symbol: String,
         decimals: u8,
+        max_supply: u64,
+        transfer_fee: Option<TransferFeeArgs>,
     ) -> Result<()> {
-        let mint_config = &mut ctx.accounts.mint_config;
         let clock = Clock::get()?;
 
         require!(token_name.len() <= 32, GamingTokenError::NameTooLong);
         require!(token_symbol.len() <= 10, GamingTokenError::SymbolTooLong);
         require!(decimals <= 9, GamingTokenError::InvalidDecimals);
 
+        // The mint is created by hand so Token-2022 extensions can go in before it is initialized
+        let token_program_id = ctx.accounts.token_program.key();
+        let mut extensions = Vec::new();
+        if let Some(fee) = transfer_fee {
+            require_keys_eq!(token_program_id, spl_token_2022::ID, GamingTokenError::TransferFeeUnsupported);
+            require!(
+                fee.transfer_fee_bps <= MAX_FEE_BASIS_POINTS,
+                GamingTokenError::InvalidTransferFee
+            );
+            extensions.push(ExtensionType::TransferFeeConfig);
+        }
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&extensions)?;
+
+        let cpi_accounts = CreateAccount {
+            from: ctx.accounts.authority.to_account_info(),
+            to: ctx.accounts.mint.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.system_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        system_program::create_account(
+            cpi_ctx,
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &token_program_id,
+        )?;
+
+        let authority_key = ctx.accounts.authority.key();
+        if let Some(fee) = transfer_fee {
+            let ix = initialize_transfer_fee_config(
+                &token_program_id,
+                &ctx.accounts.mint.key(),
+                Some(&authority_key),
+                Some(&authority_key),
+                fee.transfer_fee_bps,
+                fee.max_fee,
+            )?;
+            invoke(&ix, &[ctx.accounts.mint.to_account_info()])?;
+        }
+
+        let cpi_accounts = InitializeMint2 {
+            mint: ctx.accounts.mint.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::initialize_mint2(cpi_ctx, decimals, &authority_key, Some(&authority_key))?;
+
+        let mint_config = &mut ctx.accounts.mint_config;
+
         mint_config.authority = ctx.accounts.authority.key();
         mint_config.mint = ctx.accounts.mint.key();
         mint_config.token_name = token_name;
         mint_config.token_symbol = token_symbol;
         mint_config.decimals = decimals;
         mint_config.total_supply = 0;
+        mint_config.max_supply = max_supply;
+        mint_config.minting_paused = false;
+        mint_config.guardian = Pubkey::default();
+        mint_config.metadata_finalized = false;
+        mint_config.mint_authority = Some(ctx.accounts.authority.key());
+        mint_config.freeze_authority = Some(ctx.accounts.authority.key());
         mint_config.is_initialized = true;
         mint_config.created_at = clock.unix_timestamp;
 
@@ -34,13 +159,152 @@ pub mod gaming_token {
             name: mint_config.token_name.clone(),
             symbol: mint_config.token_symbol.clone(),
             decimals,
+            max_supply,
             authority: mint_config.authority,
+            token_program: token_program_id,
+            transfer_fee,
             timestamp: mint_config.created_at,
         });
 
         Ok(())
     }
 
+    /// Creates the Metaplex metadata so wallets show the configured name and symbol.
+    pub fn create_metadata(
+        ctx: Context<CreateMetadata>,
+        uri: String,
+    ) -> Result<()> {
+        let mint_config = &ctx.accounts.mint_config;
+
+        // Wallets need the off-chain JSON for the icon, so the URI can't be left empty
+        require!(!uri.is_empty(), GamingTokenError::InvalidUri);
+        require!(uri.len() <= MAX_URI_LENGTH, GamingTokenError::UriTooLong);
+
+        let mint_key = ctx.accounts.mint.key();
+        let mint_authority_seeds = &[
+            b"mint_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.mint_authority],
+        ];
+        let signer = &[&mint_authority_seeds[..]];
+
+        // After `enable_minters` the program PDA holds the mint authority
+        let mint_authority = if ctx.accounts.mint.mint_authority == COption::Some(ctx.accounts.mint_authority.key()) {
+            ctx.accounts.mint_authority.to_account_info()
+        } else {
+            ctx.accounts.authority.to_account_info()
+        };
+
+        let cpi_accounts = CreateMetadataAccountsV3 {
+            metadata: ctx.accounts.metadata.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            mint_authority,
+            payer: ctx.accounts.authority.to_account_info(),
+            update_authority: ctx.accounts.authority.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_metadata_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        metadata::create_metadata_accounts_v3(
+            cpi_ctx,
+            DataV2 {
+                name: mint_config.token_name.clone(),
+                symbol: mint_config.token_symbol.clone(),
+                uri: uri.clone(),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            },
+            true,
+            true,
+            None,
+        )?;
+
+        emit!(MetadataCreated {
+            mint: mint_config.mint,
+            metadata: ctx.accounts.metadata.key(),
+            uri,
+            update_authority: mint_config.authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Rewrites name, symbol and URI, keeping `MintConfig` in step with the metadata.
+    pub fn update_token_metadata(
+        ctx: Context<UpdateTokenMetadata>,
+        token_name: String,
+        token_symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        require!(token_name.len() <= 32, GamingTokenError::NameTooLong);
+        require!(token_symbol.len() <= 10, GamingTokenError::SymbolTooLong);
+        require!(!uri.is_empty(), GamingTokenError::InvalidUri);
+        require!(uri.len() <= MAX_URI_LENGTH, GamingTokenError::UriTooLong);
+
+        let cpi_accounts = UpdateMetadataAccountsV2 {
+            metadata: ctx.accounts.metadata.to_account_info(),
+            update_authority: ctx.accounts.authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_metadata_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        metadata::update_metadata_accounts_v2(
+            cpi_ctx,
+            None,
+            Some(DataV2 {
+                name: token_name.clone(),
+                symbol: token_symbol.clone(),
+                uri: uri.clone(),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            }),
+            None,
+            None,
+        )?;
+
+        let mint_config = &mut ctx.accounts.mint_config;
+        mint_config.token_name = token_name;
+        mint_config.token_symbol = token_symbol;
+
+        emit!(MetadataUpdated {
+            mint: mint_config.mint,
+            name: mint_config.token_name.clone(),
+            symbol: mint_config.token_symbol.clone(),
+            uri,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Makes the metadata immutable for good.
+    pub fn finalize_metadata(
+        ctx: Context<UpdateTokenMetadata>,
+    ) -> Result<()> {
+        let cpi_accounts = UpdateMetadataAccountsV2 {
+            metadata: ctx.accounts.metadata.to_account_info(),
+            update_authority: ctx.accounts.authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_metadata_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        metadata::update_metadata_accounts_v2(cpi_ctx, None, None, None, Some(false))?;
+
+        let mint_config = &mut ctx.accounts.mint_config;
+        mint_config.metadata_finalized = true;
+
+        emit!(MetadataFinalized {
+            mint: mint_config.mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
     pub fn mint_tokens(
         ctx: Context<MintTokens>,
         amount: u64,
@@ -53,380 +317,11444 @@ pub mod gaming_token {
             GamingTokenError::Unauthorized
         );
 
-        let cpi_accounts = MintTo {
-            mint: ctx.accounts.mint.to_account_info(),
-            to: ctx.accounts.token_account.to_account_info(),
-            authority: ctx.accounts.authority.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        token::mint_to(cpi_ctx, amount)?;
-
-        mint_config.total_supply += amount;
+        checked_mint(
+            mint_config,
+            &ctx.accounts.mint,
+            &ctx.accounts.mint_authority,
+            ctx.bumps.mint_authority,
+            Some(&ctx.accounts.authority.to_account_info()),
+            &ctx.accounts.token_program,
+            &[(ctx.accounts.token_account.to_account_info(), amount)],
+        )?;
 
         emit!(TokensMinted {
             mint: mint_config.mint,
+            minter: ctx.accounts.authority.key(),
             to: ctx.accounts.token_account.key(),
             amount,
             new_supply: mint_config.total_supply,
+            max_supply: mint_config.max_supply,
+            remaining_supply: mint_config.remaining_supply(),
             timestamp: Clock::get().unwrap().unix_timestamp,
         });
 
         Ok(())
     }
 
-    pub fn stake_tokens(
-        ctx: Context<StakeTokens>,
+    /// Mints `amounts[i]` to the i-th token account in `remaining_accounts`.
+    /// The supply cap applies to the batch total, so it mints all or nothing,
+    /// and one aggregated `BatchMinted` event is emitted for the whole batch.
+    pub fn batch_mint<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MintTokens<'info>>,
+        amounts: Vec<u64>,
+    ) -> Result<()> {
+        let mint_config = &mut ctx.accounts.mint_config;
+
+        require!(
+            !amounts.is_empty() && amounts.len() <= MAX_BATCH_MINT_RECIPIENTS,
+            GamingTokenError::InvalidBatchSize
+        );
+        require!(
+            amounts.len() == ctx.remaining_accounts.len(),
+            GamingTokenError::InvalidBatchSize
+        );
+
+        let mut total_amount: u64 = 0;
+        for amount in amounts.iter() {
+            require!(*amount > 0, GamingTokenError::InvalidAmount);
+            total_amount = total_amount
+                .checked_add(*amount)
+                .ok_or(GamingTokenError::MathOverflow)?;
+        }
+
+        let mut recipients = Vec::with_capacity(amounts.len());
+        for (amount, account) in amounts.iter().zip(ctx.remaining_accounts.iter()) {
+            let token_account = InterfaceAccount::<TokenAccount>::try_from(account)?;
+            require_keys_eq!(token_account.mint, mint_config.mint, GamingTokenError::MintMismatch);
+            recipients.push((account.clone(), *amount));
+        }
+        checked_mint(
+            mint_config,
+            &ctx.accounts.mint,
+            &ctx.accounts.mint_authority,
+            ctx.bumps.mint_authority,
+            Some(&ctx.accounts.authority.to_account_info()),
+            &ctx.accounts.token_program,
+            &recipients,
+        )?;
+
+        emit!(BatchMinted {
+            mint: mint_config.mint,
+            minter: ctx.accounts.authority.key(),
+            recipients: amounts.len() as u16,
+            total_amount,
+            new_supply: mint_config.total_supply,
+            max_supply: mint_config.max_supply,
+            remaining_supply: mint_config.remaining_supply(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// `mint_tokens` to a wallet's associated token account, which the
+    /// authority pays to create if it doesn't exist yet.
+    pub fn mint_tokens_to_wallet(
+        ctx: Context<MintTokensToWallet>,
         amount: u64,
-        lock_period: i64, // Lock period in seconds
     ) -> Result<()> {
-        let stake_account = &mut ctx.accounts.stake_account;
-        let clock = Clock::get()?;
+        let mint_config = &mut ctx.accounts.mint_config;
 
         require!(amount > 0, GamingTokenError::InvalidAmount);
-        require!(lock_period > 0, GamingTokenError::InvalidLockPeriod);
-
-        // Transfer tokens to stake vault
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            to: ctx.accounts.stake_vault.to_account_info(),
-            authority: ctx.accounts.authority.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        token::transfer(cpi_ctx, amount)?;
 
-        stake_account.owner = ctx.accounts.authority.key();
-        stake_account.amount = amount;
-        stake_account.lock_until = clock.unix_timestamp + lock_period;
-        stake_account.created_at = clock.unix_timestamp;
-        stake_account.last_reward_claim = clock.unix_timestamp;
-        stake_account.is_active = true;
+        checked_mint(
+            mint_config,
+            &ctx.accounts.mint,
+            &ctx.accounts.mint_authority,
+            ctx.bumps.mint_authority,
+            Some(&ctx.accounts.authority.to_account_info()),
+            &ctx.accounts.token_program,
+            &[(ctx.accounts.token_account.to_account_info(), amount)],
+        )?;
 
-        emit!(TokensStaked {
-            owner: stake_account.owner,
+        emit!(TokensMinted {
+            mint: mint_config.mint,
+            minter: ctx.accounts.authority.key(),
+            to: ctx.accounts.token_account.key(),
             amount,
-            lock_until: stake_account.lock_until,
-            timestamp: stake_account.created_at,
+            new_supply: mint_config.total_supply,
+            max_supply: mint_config.max_supply,
+            remaining_supply: mint_config.remaining_supply(),
+            timestamp: Clock::get()?.unix_timestamp,
         });
 
         Ok(())
     }
 
-    pub fn unstake_tokens(
-        ctx: Context<UnstakeTokens>,
+    /// `batch_mint` to wallets: `remaining_accounts` holds a `[wallet,
+    /// associated token account]` pair per amount, and any account that
+    /// doesn't exist yet is created at the authority's expense.
+    pub fn batch_mint_to_wallets<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchMintToWallets<'info>>,
+        amounts: Vec<u64>,
     ) -> Result<()> {
-        let stake_account = &mut ctx.accounts.stake_account;
-        let clock = Clock::get()?;
+        let mint_config = &mut ctx.accounts.mint_config;
 
-        require!(stake_account.is_active, GamingTokenError::StakeNotActive);
         require!(
-            ctx.accounts.owner.key() == stake_account.owner,
-            GamingTokenError::Unauthorized
+            !amounts.is_empty() && amounts.len() <= MAX_BATCH_MINT_RECIPIENTS,
+            GamingTokenError::InvalidBatchSize
         );
         require!(
-            clock.unix_timestamp >= stake_account.lock_until,
-            GamingTokenError::TokensStillLocked
+            amounts.len() * 2 == ctx.remaining_accounts.len(),
+            GamingTokenError::InvalidBatchSize
         );
 
-        // Calculate rewards (5% APY)
-        let time_staked = clock.unix_timestamp - stake_account.created_at;
-        let reward_amount = (stake_account.amount * 5 * time_staked as u64) / (100 * 365 * 24 * 60 * 60);
-        let total_amount = stake_account.amount + reward_amount;
+        let mut total_amount: u64 = 0;
+        for amount in amounts.iter() {
+            require!(*amount > 0, GamingTokenError::InvalidAmount);
+            total_amount = total_amount
+                .checked_add(*amount)
+                .ok_or(GamingTokenError::MathOverflow)?;
+        }
 
-        // Transfer staked tokens + rewards from vault
-        let mint_key = ctx.accounts.mint.key();
-        let vault_seeds = &[
-            b"stake_vault",
-            mint_key.as_ref(),
-            &[ctx.bumps.vault_authority],
-        ];
-        let signer = &[&vault_seeds[..]];
+        let mut recipients = Vec::with_capacity(amounts.len());
+        for (amount, accounts) in amounts.iter().zip(ctx.remaining_accounts.chunks(2)) {
+            let token_account = ensure_associated_token_account(
+                &accounts[0],
+                &accounts[1],
+                &ctx.accounts.mint,
+                &ctx.accounts.authority,
+                &ctx.accounts.token_program,
+                &ctx.accounts.associated_token_program,
+                &ctx.accounts.system_program,
+            )?;
+            recipients.push((token_account.to_account_info(), *amount));
+        }
+        checked_mint(
+            mint_config,
+            &ctx.accounts.mint,
+            &ctx.accounts.mint_authority,
+            ctx.bumps.mint_authority,
+            Some(&ctx.accounts.authority.to_account_info()),
+            &ctx.accounts.token_program,
+            &recipients,
+        )?;
 
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.stake_vault.to_account_info(),
-            to: ctx.accounts.user_token_account.to_account_info(),
-            authority: ctx.accounts.vault_authority.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-        token::transfer(cpi_ctx, total_amount)?;
+        emit!(BatchMinted {
+            mint: mint_config.mint,
+            minter: ctx.accounts.authority.key(),
+            recipients: amounts.len() as u16,
+            total_amount,
+            new_supply: mint_config.total_supply,
+            max_supply: mint_config.max_supply,
+            remaining_supply: mint_config.remaining_supply(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-        stake_account.is_active = false;
+        Ok(())
+    }
 
-        emit!(TokensUnstaked {
-            owner: stake_account.owner,
-            principal: stake_account.amount,
-            reward: reward_amount,
-            total: total_amount,
+    pub fn mint_tokens_as_minter(
+        ctx: Context<MintTokensAsMinter>,
+        amount: u64,
+    ) -> Result<()> {
+        let mint_config = &mut ctx.accounts.mint_config;
+        let minter_record = &mut ctx.accounts.minter_record;
+
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+
+        let clock = Clock::get()?;
+        minter_record.record_mint(amount, clock.unix_timestamp)?;
+
+        checked_mint(
+            mint_config,
+            &ctx.accounts.mint,
+            &ctx.accounts.mint_authority,
+            ctx.bumps.mint_authority,
+            None,
+            &ctx.accounts.token_program,
+            &[(ctx.accounts.token_account.to_account_info(), amount)],
+        )?;
+
+        emit!(TokensMinted {
+            mint: mint_config.mint,
+            minter: minter_record.minter,
+            to: ctx.accounts.token_account.key(),
+            amount,
+            new_supply: mint_config.total_supply,
+            max_supply: mint_config.max_supply,
+            remaining_supply: mint_config.remaining_supply(),
             timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
 
-    pub fn claim_rewards(
-        ctx: Context<ClaimRewards>,
+    /// Hands the SPL mint authority to the program so registered minters can mint.
+    pub fn enable_minters(
+        ctx: Context<ManageMintAuthority>,
     ) -> Result<()> {
-        let stake_account = &mut ctx.accounts.stake_account;
-        let clock = Clock::get()?;
-
-        require!(stake_account.is_active, GamingTokenError::StakeNotActive);
-        require!(
-            ctx.accounts.owner.key() == stake_account.owner,
-            GamingTokenError::Unauthorized
-        );
+        let mint_authority = ctx.accounts.mint_authority.key();
+        set_mint_authority(ctx.accounts, Some(mint_authority), ctx.bumps.mint_authority)?;
+        ctx.accounts.mint_config.mint_authority = Some(mint_authority);
 
-        // Calculate rewards since last claim
-        let time_since_last_claim = clock.unix_timestamp - stake_account.last_reward_claim;
-        let reward_amount = (stake_account.amount * 5 * time_since_last_claim as u64) / (100 * 365 * 24 * 60 * 60);
+        emit!(MintersEnabled {
+            mint: ctx.accounts.mint_config.mint,
+            mint_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-        if reward_amount > 0 {
-            // Transfer rewards from reward vault
-            let mint_key = ctx.accounts.mint.key();
-            let reward_vault_seeds = &[
-                b"reward_vault",
-                mint_key.as_ref(),
-                &[ctx.bumps.reward_vault_authority],
-            ];
-            let signer = &[&reward_vault_seeds[..]];
+        Ok(())
+    }
 
-            let cpi_accounts = Transfer {
-                from: ctx.accounts.reward_vault.to_account_info(),
-                to: ctx.accounts.user_token_account.to_account_info(),
-                authority: ctx.accounts.reward_vault_authority.to_account_info(),
-            };
-            let cpi_program = ctx.accounts.token_program.to_account_info();
-            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-            token::transfer(cpi_ctx, reward_amount)?;
+    pub fn transfer_mint_authority(
+        ctx: Context<ManageMintAuthority>,
+        new_mint_authority: Pubkey,
+    ) -> Result<()> {
+        set_mint_authority(ctx.accounts, Some(new_mint_authority), ctx.bumps.mint_authority)?;
 
-            stake_account.last_reward_claim = clock.unix_timestamp;
+        let mint_config = &mut ctx.accounts.mint_config;
+        let old_mint_authority = mint_config.mint_authority;
+        mint_config.mint_authority = Some(new_mint_authority);
 
-            emit!(RewardsClaimed {
-                owner: stake_account.owner,
-                amount: reward_amount,
-                timestamp: clock.unix_timestamp,
-            });
-        }
+        emit!(MintAuthorityTransferred {
+            mint: mint_config.mint,
+            old_mint_authority,
+            new_mint_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(decimals: u8)]
-pub struct InitializeMint<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 1 + 8,
-        seeds = [b"mint_config", mint.key().as_ref()],
-        bump
-    )]
-    pub mint_config: Account<'info, MintConfig>,
+    /// Drops the mint authority for good; the supply can never grow again.
+    pub fn revoke_mint_authority(
+        ctx: Context<ManageMintAuthority>,
+    ) -> Result<()> {
+        set_mint_authority(ctx.accounts, None, ctx.bumps.mint_authority)?;
 
-    #[account(
-        init,
-        payer = authority,
-        mint::decimals = decimals,
-        mint::authority = authority,
-        mint::freeze_authority = authority,
-    )]
-    pub mint: Account<'info, Mint>,
+        let mint_config = &mut ctx.accounts.mint_config;
+        mint_config.mint_authority = None;
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        emit!(MintAuthorityRevoked {
+            mint: mint_config.mint,
+            total_supply: mint_config.total_supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct MintTokens<'info> {
-    #[account(mut, has_one = authority)]
-    pub mint_config: Account<'info, MintConfig>,
+    /// Drops the freeze authority for good; no holder account can be frozen.
+    pub fn revoke_freeze_authority(
+        ctx: Context<ManageMintAuthority>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.mint_config.freeze_authority.is_some(),
+            GamingTokenError::FreezeAuthorityRevoked
+        );
 
-    #[account(mut)]
-    pub mint: Account<'info, Mint>,
+        let cpi_accounts = SetAuthority {
+            current_authority: ctx.accounts.authority.to_account_info(),
+            account_or_mint: ctx.accounts.mint.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::set_authority(cpi_ctx, AuthorityType::FreezeAccount, None)?;
 
-    #[account(mut)]
-    pub token_account: Account<'info, TokenAccount>,
+        let mint_config = &mut ctx.accounts.mint_config;
+        mint_config.freeze_authority = None;
 
-    pub authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-}
+        emit!(FreezeAuthorityRevoked {
+            mint: mint_config.mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-#[derive(Accounts)]
-pub struct StakeTokens<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1,
-        seeds = [b"stake", authority.key().as_ref(), mint.key().as_ref()],
-        bump
-    )]
-    pub stake_account: Account<'info, StakeAccount>,
+        Ok(())
+    }
 
-    #[account(
-        init,
-        payer = authority,
-        token::mint = mint,
-        token::authority = vault_authority,
+    pub fn add_minter(
+        ctx: Context<AddMinter>,
+        minter: Pubkey,
+        cap: u64,
+        allowance_per_epoch: u64,
+        epoch_length_seconds: i64,
+    ) -> Result<()> {
+        let minter_record = &mut ctx.accounts.minter_record;
+        let clock = Clock::get()?;
+
+        require!(
+            allowance_per_epoch > 0 && epoch_length_seconds > 0,
+            GamingTokenError::InvalidMinterAllowance
+        );
+
+        minter_record.mint = ctx.accounts.mint_config.mint;
+        minter_record.minter = minter;
+        minter_record.cap = cap;
+        minter_record.minted = 0;
+        minter_record.allowance_per_epoch = allowance_per_epoch;
+        minter_record.epoch_length_seconds = epoch_length_seconds;
+        minter_record.current_epoch_start = clock.unix_timestamp;
+        minter_record.minted_this_epoch = 0;
+        minter_record.added_at = clock.unix_timestamp;
+        minter_record.bump = ctx.bumps.minter_record;
+
+        emit!(MinterAdded {
+            mint: minter_record.mint,
+            minter,
+            cap,
+            allowance_per_epoch,
+            epoch_length_seconds,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_minter_cap(
+        ctx: Context<UpdateMinter>,
+        cap: u64,
+    ) -> Result<()> {
+        let minter_record = &mut ctx.accounts.minter_record;
+
+        // A cap below what was already minted simply stops the minter
+        let old_cap = minter_record.cap;
+        minter_record.cap = cap;
+
+        emit!(MinterCapUpdated {
+            mint: minter_record.mint,
+            minter: minter_record.minter,
+            old_cap,
+            new_cap: cap,
+            minted: minter_record.minted,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_minter_allowance(
+        ctx: Context<UpdateMinter>,
+        allowance_per_epoch: u64,
+        epoch_length_seconds: i64,
+    ) -> Result<()> {
+        let minter_record = &mut ctx.accounts.minter_record;
+        let clock = Clock::get()?;
+
+        require!(
+            allowance_per_epoch > 0 && epoch_length_seconds > 0,
+            GamingTokenError::InvalidMinterAllowance
+        );
+
+        // A new window starts now, still counting what this epoch already minted
+        minter_record.roll_epoch(clock.unix_timestamp);
+        minter_record.allowance_per_epoch = allowance_per_epoch;
+        minter_record.epoch_length_seconds = epoch_length_seconds;
+        minter_record.current_epoch_start = clock.unix_timestamp;
+
+        emit!(MinterAllowanceUpdated {
+            mint: minter_record.mint,
+            minter: minter_record.minter,
+            allowance_per_epoch,
+            epoch_length_seconds,
+            minted_this_epoch: minter_record.minted_this_epoch,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_minter(
+        ctx: Context<RemoveMinter>,
+    ) -> Result<()> {
+        let minter_record = &ctx.accounts.minter_record;
+
+        emit!(MinterRemoved {
+            mint: minter_record.mint,
+            minter: minter_record.minter,
+            minted: minter_record.minted,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Puts a registered minter under a game reward policy. The server mints
+    /// through `mint_game_reward`, which also counts against its minter record.
+    pub fn add_game_server(
+        ctx: Context<AddGameServer>,
+        server: Pubkey,
+        daily_budget: u64,
+        per_recipient_cap: u64,
+        player_registry: Pubkey,
+    ) -> Result<()> {
+        validate_game_server_policy(daily_budget, per_recipient_cap)?;
+
+        let game_server = &mut ctx.accounts.game_server;
+        let clock = Clock::get()?;
+
+        game_server.mint = ctx.accounts.mint_config.mint;
+        game_server.server = server;
+        game_server.daily_budget = daily_budget;
+        game_server.per_recipient_cap = per_recipient_cap;
+        game_server.player_registry = player_registry;
+        game_server.window_start = clock.unix_timestamp;
+        game_server.minted_in_window = 0;
+        game_server.minted = 0;
+        game_server.added_at = clock.unix_timestamp;
+        game_server.bump = ctx.bumps.game_server;
+
+        emit!(GameServerAdded {
+            mint: game_server.mint,
+            server,
+            daily_budget,
+            per_recipient_cap,
+            player_registry,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_game_server(
+        ctx: Context<UpdateGameServer>,
+        daily_budget: u64,
+        per_recipient_cap: u64,
+        player_registry: Pubkey,
+    ) -> Result<()> {
+        validate_game_server_policy(daily_budget, per_recipient_cap)?;
+
+        // The current window keeps counting what it already minted
+        let game_server = &mut ctx.accounts.game_server;
+        game_server.daily_budget = daily_budget;
+        game_server.per_recipient_cap = per_recipient_cap;
+        game_server.player_registry = player_registry;
+
+        emit!(GameServerUpdated {
+            mint: game_server.mint,
+            server: game_server.server,
+            daily_budget,
+            per_recipient_cap,
+            player_registry,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_game_server(
+        ctx: Context<RemoveGameServer>,
+    ) -> Result<()> {
+        let game_server = &ctx.accounts.game_server;
+
+        emit!(GameServerRemoved {
+            mint: game_server.mint,
+            server: game_server.server,
+            minted: game_server.minted,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Mints an in-game reward within the server's daily budget, the
+    /// recipient's daily cap and the server's minter cap and allowance.
+    pub fn mint_game_reward(
+        ctx: Context<MintGameReward>,
+        amount: u64,
+        reason_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+
+        let clock = Clock::get()?;
+        let game_server = &mut ctx.accounts.game_server;
+        let player = ctx.accounts.recipient.owner;
+
+        if game_server.player_registry != Pubkey::default() {
+            require_registered_player(&game_server.player_registry, &ctx.accounts.player_record, &player)?;
+        }
+
+        ctx.accounts.minter_record.record_mint(amount, clock.unix_timestamp)?;
+        game_server.record_reward(amount, clock.unix_timestamp)?;
+
+        // The recipient's allowance follows the server's window
+        let recipient_record = &mut ctx.accounts.recipient_record;
+        if recipient_record.game_server != game_server.key()
+            || recipient_record.window_start != game_server.window_start
+        {
+            recipient_record.game_server = game_server.key();
+            recipient_record.player = player;
+            recipient_record.window_start = game_server.window_start;
+            recipient_record.minted_in_window = 0;
+            recipient_record.bump = ctx.bumps.recipient_record;
+        }
+        let minted_in_window = recipient_record
+            .minted_in_window
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        require!(
+            minted_in_window <= game_server.per_recipient_cap,
+            GamingTokenError::RecipientCapExceeded
+        );
+        recipient_record.minted_in_window = minted_in_window;
+
+        let mint_config = &mut ctx.accounts.mint_config;
+        checked_mint(
+            mint_config,
+            &ctx.accounts.mint,
+            &ctx.accounts.mint_authority,
+            ctx.bumps.mint_authority,
+            None,
+            &ctx.accounts.token_program,
+            &[(ctx.accounts.recipient.to_account_info(), amount)],
+        )?;
+
+        emit!(GameRewardMinted {
+            mint: mint_config.mint,
+            server: game_server.server,
+            recipient: ctx.accounts.recipient.key(),
+            player,
+            amount,
+            reason_hash,
+            minted_in_window: game_server.minted_in_window,
+            new_supply: mint_config.total_supply,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_mint_guardian(
+        ctx: Context<UpdateMintConfig>,
+        guardian: Pubkey,
+    ) -> Result<()> {
+        let mint_config = &mut ctx.accounts.mint_config;
+        mint_config.guardian = guardian;
+
+        emit!(MintGuardianUpdated {
+            mint: mint_config.mint,
+            guardian,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// The authority can pause and unpause; the guardian can only pause.
+    pub fn set_minting_paused(
+        ctx: Context<SetMintingPaused>,
+        paused: bool,
+    ) -> Result<()> {
+        let mint_config = &mut ctx.accounts.mint_config;
+        let actor = ctx.accounts.actor.key();
+
+        let is_guardian = mint_config.guardian != Pubkey::default() && actor == mint_config.guardian;
+        require!(
+            actor == mint_config.authority || (paused && is_guardian),
+            GamingTokenError::Unauthorized
+        );
+
+        mint_config.minting_paused = paused;
+
+        emit!(MintingPauseUpdated {
+            mint: mint_config.mint,
+            paused,
+            actor,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn lower_max_supply(
+        ctx: Context<UpdateMintConfig>,
+        max_supply: u64,
+    ) -> Result<()> {
+        let mint_config = &mut ctx.accounts.mint_config;
+
+        // The cap can only ever come down, and never below what already exists
+        require!(max_supply > 0, GamingTokenError::InvalidSupplyCap);
+        require!(
+            mint_config.max_supply == 0 || max_supply <= mint_config.max_supply,
+            GamingTokenError::InvalidSupplyCap
+        );
+        require!(
+            max_supply >= mint_config.total_supply,
+            GamingTokenError::InvalidSupplyCap
+        );
+
+        let old_max_supply = mint_config.max_supply;
+        mint_config.max_supply = max_supply;
+
+        emit!(MaxSupplyLowered {
+            mint: mint_config.mint,
+            old_max_supply,
+            new_max_supply: max_supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn burn_tokens(
+        ctx: Context<BurnTokens>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+
+        // Pick up any raw SPL burns first so the two figures stay aligned
+        reconcile_supply(&mut ctx.accounts.mint_config, ctx.accounts.mint.supply)?;
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.mint.to_account_info(),
+            from: ctx.accounts.token_account.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::burn(cpi_ctx, amount)?;
+
+        let mint_config = &mut ctx.accounts.mint_config;
+        mint_config.total_supply = mint_config
+            .total_supply
+            .checked_sub(amount)
+            .ok_or(GamingTokenError::SupplyUnderflow)?;
+
+        emit!(TokensBurned {
+            mint: mint_config.mint,
+            from: ctx.accounts.token_account.key(),
+            amount,
+            new_supply: mint_config.total_supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Points `burn_to_redeem` at a memecoin redemption pool and sets the
+    /// rate, `amount * rate_numerator / rate_denominator` memecoin per burn.
+    pub fn configure_redemption(
+        ctx: Context<ConfigureRedemption>,
+        memecoin_program: Pubkey,
+        bridge_config: Pubkey,
+        rate_numerator: u64,
+        rate_denominator: u64,
+    ) -> Result<()> {
+        require!(
+            rate_numerator > 0 && rate_denominator > 0,
+            GamingTokenError::InvalidRedemptionRate
+        );
+
+        let redemption_config = &mut ctx.accounts.redemption_config;
+        redemption_config.mint = ctx.accounts.mint.key();
+        redemption_config.memecoin_program = memecoin_program;
+        redemption_config.bridge_config = bridge_config;
+        redemption_config.memecoin_mint = ctx.accounts.redemption_pool.mint;
+        redemption_config.redemption_pool = ctx.accounts.redemption_pool.key();
+        redemption_config.rate_numerator = rate_numerator;
+        redemption_config.rate_denominator = rate_denominator;
+        redemption_config.bump = ctx.bumps.redemption_config;
+
+        emit!(RedemptionConfigured {
+            mint: redemption_config.mint,
+            memecoin_program,
+            bridge_config,
+            redemption_pool: redemption_config.redemption_pool,
+            rate_numerator,
+            rate_denominator,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// One-way bridge: burns the caller's tokens and has the memecoin program
+    /// pay them out of its redemption pool, signed by this program's
+    /// `redemption_authority` PDA. A pool that can't cover it fails before the burn.
+    pub fn burn_to_redeem(
+        ctx: Context<BurnToRedeem>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+
+        let redemption_config = &ctx.accounts.redemption_config;
+        let redeemed = u64::try_from(
+            amount as u128 * redemption_config.rate_numerator as u128 / redemption_config.rate_denominator as u128,
+        )
+        .map_err(|_| GamingTokenError::MathOverflow)?;
+        require!(redeemed > 0, GamingTokenError::InvalidAmount);
+        require!(
+            ctx.accounts.redemption_pool.amount >= redeemed,
+            GamingTokenError::RedemptionPoolExhausted
+        );
+
+        // Pick up any raw SPL burns first so the two figures stay aligned
+        reconcile_supply(&mut ctx.accounts.mint_config, ctx.accounts.mint.supply)?;
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.mint.to_account_info(),
+            from: ctx.accounts.token_account.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::burn(cpi_ctx, amount)?;
+
+        let mint_config = &mut ctx.accounts.mint_config;
+        mint_config.total_supply = mint_config
+            .total_supply
+            .checked_sub(amount)
+            .ok_or(GamingTokenError::SupplyUnderflow)?;
+
+        // memecoin::redeem_for_gaming_burn(redeemed)
+        let mut data = hash(b"global:redeem_for_gaming_burn").to_bytes()[..8].to_vec();
+        data.extend_from_slice(&redeemed.to_le_bytes());
+        let ix = Instruction {
+            program_id: ctx.accounts.memecoin_program.key(),
+            accounts: vec![
+                AccountMeta::new(ctx.accounts.bridge_config.key(), false),
+                AccountMeta::new(ctx.accounts.redemption_pool.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.pool_authority.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.mint.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.redemption_authority.key(), true),
+                AccountMeta::new(ctx.accounts.recipient_account.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.memecoin_token_program.key(), false),
+            ],
+            data,
+        };
+        let mint_key = ctx.accounts.mint.key();
+        let redemption_authority_seeds = &[
+            b"redemption_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.redemption_authority],
+        ];
+        invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.bridge_config.to_account_info(),
+                ctx.accounts.redemption_pool.to_account_info(),
+                ctx.accounts.pool_authority.to_account_info(),
+                ctx.accounts.mint.to_account_info(),
+                ctx.accounts.redemption_authority.to_account_info(),
+                ctx.accounts.recipient_account.to_account_info(),
+                ctx.accounts.memecoin_token_program.to_account_info(),
+                ctx.accounts.memecoin_program.to_account_info(),
+            ],
+            &[&redemption_authority_seeds[..]],
+        )?;
+
+        let redemption_config = &mut ctx.accounts.redemption_config;
+        redemption_config.total_burned = redemption_config
+            .total_burned
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        redemption_config.total_redeemed = redemption_config
+            .total_redeemed
+            .checked_add(redeemed)
+            .ok_or(GamingTokenError::MathOverflow)?;
+
+        emit!(TokensRedeemed {
+            mint: mint_config.mint,
+            owner: ctx.accounts.owner.key(),
+            burned: amount,
+            redeemed,
+            recipient: ctx.accounts.recipient_account.key(),
+            new_supply: mint_config.total_supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn burn_from_treasury(
+        ctx: Context<BurnFromTreasury>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+        require!(
+            amount <= ctx.accounts.treasury_token_account.amount,
+            GamingTokenError::InsufficientFunds
+        );
+
+        // Pick up any raw SPL burns first so the two figures stay aligned
+        reconcile_supply(&mut ctx.accounts.mint_config, ctx.accounts.mint.supply)?;
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.mint.to_account_info(),
+            from: ctx.accounts.treasury_token_account.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::burn(cpi_ctx, amount)?;
+
+        let mint_config = &mut ctx.accounts.mint_config;
+        mint_config.total_supply = mint_config
+            .total_supply
+            .checked_sub(amount)
+            .ok_or(GamingTokenError::SupplyUnderflow)?;
+
+        emit!(TokensBurned {
+            mint: mint_config.mint,
+            from: ctx.accounts.treasury_token_account.key(),
+            amount,
+            new_supply: mint_config.total_supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless: adopts the SPL mint's supply as `total_supply`, correcting
+    /// drift from tokens burned outside this program.
+    pub fn sync_supply(ctx: Context<SyncSupply>) -> Result<()> {
+        reconcile_supply(&mut ctx.accounts.mint_config, ctx.accounts.mint.supply)
+    }
+
+    /// Re-syncs `decimals` from the SPL mint for a legacy config that drifted;
+    /// every other instruction taking both refuses a mismatch.
+    pub fn fix_decimals_mismatch(ctx: Context<FixDecimalsMismatch>) -> Result<()> {
+        let mint_config = &mut ctx.accounts.mint_config;
+        let old_decimals = mint_config.decimals;
+        mint_config.decimals = ctx.accounts.mint.decimals;
+
+        emit!(DecimalsResynced {
+            mint: mint_config.mint,
+            old_decimals,
+            new_decimals: mint_config.decimals,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Reclaims the config's rent once the token can no longer be minted:
+    /// the mint authority is revoked or nothing is in circulation. The mint
+    /// itself already exists, so `initialize_mint` can never recreate it.
+    pub fn close_mint_config(ctx: Context<CloseMintConfig>) -> Result<()> {
+        let mint = &ctx.accounts.mint;
+        require!(
+            mint.mint_authority.is_none() || mint.supply == 0,
+            GamingTokenError::MintStillActive
+        );
+
+        emit!(MintConfigClosed {
+            mint: mint.key(),
+            supply: mint.supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Mints an allocation into a vault that releases it to `beneficiary`
+    /// linearly over `duration_seconds` from `start`, with nothing released
+    /// before the cliff. `schedule_id` lets one beneficiary hold several.
+    pub fn create_vesting(
+        ctx: Context<CreateVesting>,
+        beneficiary: Pubkey,
+        schedule_id: u64,
+        total_amount: u64,
+        start: i64,
+        cliff_seconds: i64,
+        duration_seconds: i64,
+    ) -> Result<()> {
+        require!(total_amount > 0, GamingTokenError::InvalidAmount);
+        require!(
+            duration_seconds > 0 && (0..=duration_seconds).contains(&cliff_seconds),
+            GamingTokenError::InvalidVestingSchedule
+        );
+
+        let mint_config = &mut ctx.accounts.mint_config;
+        checked_mint(
+            mint_config,
+            &ctx.accounts.mint,
+            &ctx.accounts.mint_authority,
+            ctx.bumps.mint_authority,
+            Some(&ctx.accounts.authority.to_account_info()),
+            &ctx.accounts.token_program,
+            &[(ctx.accounts.vesting_vault.to_account_info(), total_amount)],
+        )?;
+
+        let vesting = &mut ctx.accounts.vesting;
+        vesting.mint = ctx.accounts.mint.key();
+        vesting.beneficiary = beneficiary;
+        vesting.schedule_id = schedule_id;
+        vesting.total_amount = total_amount;
+        vesting.released_amount = 0;
+        vesting.start = start;
+        vesting.cliff_seconds = cliff_seconds;
+        vesting.duration_seconds = duration_seconds;
+        vesting.revoked = false;
+        vesting.vault = ctx.accounts.vesting_vault.key();
+        vesting.bump = ctx.bumps.vesting;
+
+        emit!(VestingCreated {
+            mint: vesting.mint,
+            beneficiary,
+            schedule_id,
+            total_amount,
+            start,
+            cliff_seconds,
+            duration_seconds,
+            new_supply: ctx.accounts.mint_config.total_supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+        let clock = Clock::get()?;
+        let vesting = &mut ctx.accounts.vesting;
+
+        let amount = vesting.vested_amount(clock.unix_timestamp)? - vesting.released_amount;
+        require!(amount > 0, GamingTokenError::NothingVested);
+        vesting.released_amount += amount;
+
+        let mint_key = vesting.mint;
+        let beneficiary = vesting.beneficiary;
+        let schedule_id = vesting.schedule_id.to_le_bytes();
+        let vesting_seeds = &[
+            b"vesting",
+            mint_key.as_ref(),
+            beneficiary.as_ref(),
+            schedule_id.as_ref(),
+            &[vesting.bump],
+        ];
+        let signer = &[&vesting_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.vesting_vault.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.beneficiary_token_account.to_account_info(),
+            authority: vesting.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+        emit!(VestedTokensClaimed {
+            mint: mint_key,
+            beneficiary,
+            schedule_id: vesting.schedule_id,
+            amount,
+            released_amount: vesting.released_amount,
+            total_amount: vesting.total_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Stops vesting now. What has vested stays claimable by the beneficiary;
+    /// the unvested rest goes back to `destination`.
+    pub fn revoke_vesting(ctx: Context<RevokeVesting>) -> Result<()> {
+        let clock = Clock::get()?;
+        let vesting = &mut ctx.accounts.vesting;
+
+        let vested = vesting.vested_amount(clock.unix_timestamp)?;
+        let unvested = vesting.total_amount - vested;
+        vesting.total_amount = vested;
+        vesting.revoked = true;
+
+        if unvested > 0 {
+            let mint_key = vesting.mint;
+            let beneficiary = vesting.beneficiary;
+            let schedule_id = vesting.schedule_id.to_le_bytes();
+            let vesting_seeds = &[
+                b"vesting",
+                mint_key.as_ref(),
+                beneficiary.as_ref(),
+                schedule_id.as_ref(),
+                &[vesting.bump],
+            ];
+            let signer = &[&vesting_seeds[..]];
+
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.vesting_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: vesting.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::transfer_checked(cpi_ctx, unvested, ctx.accounts.mint.decimals)?;
+        }
+
+        emit!(VestingRevoked {
+            mint: vesting.mint,
+            beneficiary: vesting.beneficiary,
+            schedule_id: vesting.schedule_id,
+            vested_amount: vested,
+            returned_amount: unvested,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_staking_config(ctx: Context<InitializeStakingConfig>) -> Result<()> {
+        let staking_config = &mut ctx.accounts.staking_config;
+
+        staking_config.admin = ctx.accounts.admin.key();
+        staking_config.permissionless_pools = false;
+        staking_config.pool_creation_fee = 0;
+        staking_config.fee_recipient = ctx.accounts.admin.key();
+        staking_config.total_pools = 0;
+        staking_config.pool_update_delay_seconds = DEFAULT_POOL_UPDATE_DELAY_SECONDS;
+        staking_config.bump = ctx.bumps.staking_config;
+
+        Ok(())
+    }
+
+    pub fn update_pool_creation(
+        ctx: Context<UpdateStakingConfig>,
+        permissionless_pools: bool,
+        pool_creation_fee: u64,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        let staking_config = &mut ctx.accounts.staking_config;
+        staking_config.permissionless_pools = permissionless_pools;
+        staking_config.pool_creation_fee = pool_creation_fee;
+        staking_config.fee_recipient = fee_recipient;
+
+        emit!(PoolCreationUpdated {
+            permissionless_pools,
+            pool_creation_fee,
+            fee_recipient,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Opens a pool for any mint. Each pool's vaults and signers are derived from
+    /// its staked mint, so no pool can sign for another pool's tokens.
+    pub fn create_pool(
+        ctx: Context<CreatePool>,
+        reward_duration: i64,
+        min_stake_amount: u64,
+        min_lock_seconds: i64,
+        max_lock_seconds: i64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(reward_duration > 0, GamingTokenError::InvalidRewardDuration);
+
+        // The admin always may; anyone else only when creation is open, for the fee
+        let staking_config = &mut ctx.accounts.staking_config;
+        let creation_fee = if ctx.accounts.payer.key() == staking_config.admin {
+            0
+        } else {
+            require!(
+                staking_config.permissionless_pools,
+                GamingTokenError::PoolCreationRestricted
+            );
+            staking_config.pool_creation_fee
+        };
+        if creation_fee > 0 {
+            let cpi_accounts = system_program::Transfer {
+                from: ctx.accounts.payer.to_account_info(),
+                to: ctx.accounts.fee_recipient.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.system_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            system_program::transfer(cpi_ctx, creation_fee)?;
+        }
+        staking_config.total_pools = staking_config
+            .total_pools
+            .checked_add(1)
+            .ok_or(GamingTokenError::MathOverflow)?;
+
+        ctx.accounts.registry_page.entries.push(PoolRegistryEntry {
+            pool: ctx.accounts.stake_pool.key(),
+            mint: ctx.accounts.mint.key(),
+            reward_mint: ctx.accounts.reward_mint.key(),
+            created_at: clock.unix_timestamp,
+            deprecated: false,
+            closed: false,
+        });
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.authority = ctx.accounts.payer.key();
+        stake_pool.mint = ctx.accounts.mint.key();
+        stake_pool.reward_mint = ctx.accounts.reward_mint.key();
+        stake_pool.stake_vault = ctx.accounts.stake_vault.key();
+        stake_pool.reward_vault = ctx.accounts.reward_vault.key();
+        stake_pool.reward_duration = reward_duration;
+        stake_pool.lock_tiers = Vec::new();
+        stake_pool.early_unstake_penalty_bps = 0;
+        stake_pool.unstake_cooldown_seconds = 0;
+        stake_pool.set_stake_limits(min_stake_amount, min_lock_seconds, max_lock_seconds)?;
+        stake_pool.vote_lock_bonus_bps = 0;
+        stake_pool.max_slash_bps = 0;
+        stake_pool.slash_timelock_seconds = 0;
+        stake_pool.paused = false;
+        stake_pool.emergency_mode = false;
+        stake_pool.emergency_destination = Pubkey::default();
+        stake_pool.emergency_rewards_freeze = false;
+        stake_pool.rewards_frozen_at = 0;
+        stake_pool.reward_rate = 0;
+        stake_pool.period_finish = clock.unix_timestamp;
+        stake_pool.reward_per_token_stored = 0;
+        stake_pool.last_update_time = clock.unix_timestamp;
+        stake_pool.total_staked = 0;
+        stake_pool.total_positions = 0;
+        stake_pool.total_rewards_paid = 0;
+        stake_pool.reward_reserve = 0;
+        stake_pool.total_weight = 0;
+        stake_pool.rewards_owed = 0;
+        stake_pool.reward_slots = Vec::new();
+        stake_pool.emission_schedule = None;
+        stake_pool.lp_pool = false;
+        stake_pool.boost_bps = 0;
+        stake_pool.claim_fee_bps = 0;
+        stake_pool.fee_vault = Pubkey::default();
+        stake_pool.referral_bps = 0;
+        stake_pool.min_claim_interval_seconds = 0;
+        stake_pool.achievement_program = Pubkey::default();
+        stake_pool.achievement_min_wins = 0;
+        stake_pool.achievement_boost_bps = 0;
+        stake_pool.reward_warmup_seconds = 0;
+        stake_pool.successor_pool = Pubkey::default();
+        stake_pool.sunset_deadline = 0;
+        stake_pool.migration_bonus_bps = 0;
+        stake_pool.sunset_swept = false;
+        stake_pool.rate_history = Vec::new();
+        stake_pool.rate_history_next = 0;
+        stake_pool.low_reserve_threshold = 0;
+        stake_pool.reserve_low = false;
+        stake_pool.total_soft_staked = 0;
+        stake_pool.created_at = clock.unix_timestamp;
+        stake_pool.bump = ctx.bumps.stake_pool;
+
+        emit!(StakingPoolInitialized {
+            mint: stake_pool.mint,
+            reward_mint: stake_pool.reward_mint,
+            authority: stake_pool.authority,
+            stake_vault: stake_pool.stake_vault,
+            reward_vault: stake_pool.reward_vault,
+            reward_duration,
+            min_stake_amount,
+            min_lock_seconds,
+            max_lock_seconds,
+            creation_fee,
+            timestamp: stake_pool.created_at,
+        });
+
+        Ok(())
+    }
+
+    /// Creates the registry page the next pool will be listed on. Pages fill in
+    /// order, so only page `total_pools / POOL_REGISTRY_PAGE_SIZE` can be
+    /// allocated, page 0 included; anyone may pay for it.
+    pub fn allocate_registry_page(ctx: Context<AllocateRegistryPage>, page_index: u32) -> Result<()> {
+        require!(
+            page_index == ctx.accounts.staking_config.registry_page(),
+            GamingTokenError::RegistryPageNotNext
+        );
+
+        let registry_page = &mut ctx.accounts.registry_page;
+        registry_page.page_index = page_index;
+        registry_page.entries = Vec::new();
+        registry_page.bump = ctx.bumps.registry_page;
+
+        emit!(RegistryPageAllocated {
+            page_index,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Flags a pool in the registry so UIs stop offering it. Entries are never
+    /// removed, so indices stay stable.
+    pub fn set_pool_deprecated(ctx: Context<SetPoolDeprecated>, deprecated: bool) -> Result<()> {
+        let pool_key = ctx.accounts.stake_pool.key();
+        let entry = ctx
+            .accounts
+            .registry_page
+            .entries
+            .iter_mut()
+            .find(|entry| entry.pool == pool_key)
+            .ok_or(GamingTokenError::PoolNotRegistered)?;
+        entry.deprecated = deprecated;
+
+        emit!(PoolDeprecationUpdated {
+            pool: pool_key,
+            deprecated,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_pool_update_delay(
+        ctx: Context<UpdateStakingConfig>,
+        pool_update_delay_seconds: i64,
+    ) -> Result<()> {
+        require!(pool_update_delay_seconds >= 0, GamingTokenError::InvalidLockPeriod);
+
+        ctx.accounts.staking_config.pool_update_delay_seconds = pool_update_delay_seconds;
+
+        emit!(PoolUpdateDelayUpdated {
+            pool_update_delay_seconds,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Queues rate-affecting pool changes to apply no sooner than `effective_at`,
+    /// which must leave at least the configured delay. One update may be pending.
+    pub fn queue_pool_update(
+        ctx: Context<QueuePoolUpdate>,
+        update: PoolUpdate,
+        effective_at: i64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(
+            update.reward_duration.is_some()
+                || update.emission_schedule.is_some()
+                || update.claim_fee_bps.is_some(),
+            GamingTokenError::EmptyPoolUpdate
+        );
+        let earliest = clock
+            .unix_timestamp
+            .checked_add(ctx.accounts.staking_config.pool_update_delay_seconds)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        require!(effective_at >= earliest, GamingTokenError::UpdateTooSoon);
+        if let Some(claim_fee_bps) = update.claim_fee_bps {
+            require!(claim_fee_bps <= MAX_CLAIM_FEE_BPS, GamingTokenError::InvalidClaimFee);
+            require!(
+                ctx.accounts.stake_pool.fee_vault != Pubkey::default(),
+                GamingTokenError::FeeVaultRequired
+            );
+        }
+
+        let pending_update = &mut ctx.accounts.pending_update;
+        pending_update.stake_pool = ctx.accounts.stake_pool.key();
+        pending_update.update = update.clone();
+        pending_update.queued_at = clock.unix_timestamp;
+        pending_update.effective_at = effective_at;
+        pending_update.bump = ctx.bumps.pending_update;
+
+        emit!(PoolUpdateQueued {
+            mint: ctx.accounts.stake_pool.mint,
+            update,
+            effective_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Applies a queued update once its time has come. Rewards are checkpointed
+    /// first, so everything earned so far is kept at the old rate.
+    pub fn execute_pool_update(
+        ctx: Context<ExecutePoolUpdate>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let pending_update = &ctx.accounts.pending_update;
+
+        require!(
+            clock.unix_timestamp >= pending_update.effective_at,
+            GamingTokenError::UpdateNotReady
+        );
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.update_reward_per_token(clock.unix_timestamp)?;
+        stake_pool.update_reward_slots(clock.unix_timestamp)?;
+
+        let update = pending_update.update.clone();
+        if let Some(reward_duration) = update.reward_duration {
+            apply_reward_duration(stake_pool, reward_duration, clock.unix_timestamp)?;
+        }
+        if let Some(emission_schedule) = update.emission_schedule {
+            apply_emission_schedule(stake_pool, emission_schedule, clock.unix_timestamp)?;
+        }
+        if let Some(claim_fee_bps) = update.claim_fee_bps {
+            stake_pool.claim_fee_bps = claim_fee_bps;
+        }
+
+        emit!(PoolUpdateExecuted {
+            mint: stake_pool.mint,
+            update,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_pool_update(
+        ctx: Context<CancelPoolUpdate>,
+    ) -> Result<()> {
+        emit!(PoolUpdateCancelled {
+            mint: ctx.accounts.stake_pool.mint,
+            update: ctx.accounts.pending_update.update.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless crank for when nobody touches the pool across an epoch
+    /// boundary; every reward update rolls due epochs anyway.
+    pub fn roll_epoch(
+        ctx: Context<RollEpoch>,
+    ) -> Result<()> {
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let clock = Clock::get()?;
+
+        let schedule = stake_pool
+            .emission_schedule
+            .ok_or(GamingTokenError::EmissionScheduleUnset)?;
+        require!(
+            clock.unix_timestamp >= schedule.epoch_end(),
+            GamingTokenError::EpochNotEnded
+        );
+
+        stake_pool.update_reward_per_token(clock.unix_timestamp)?;
+
+        Ok(())
+    }
+
+    pub fn notify_reward_amount(
+        ctx: Context<NotifyRewardAmount>,
+        slot_index: u8,
+        amount: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+        // Slot 0 is the pool's own reward mint; partner slots follow
+        let expected_vault = match slot_index {
+            0 => ctx.accounts.stake_pool.reward_vault,
+            _ => {
+                let slot = ctx
+                    .accounts
+                    .stake_pool
+                    .reward_slots
+                    .get(slot_index as usize - 1)
+                    .ok_or(GamingTokenError::InvalidRewardSlot)?;
+                require!(slot.active, GamingTokenError::RewardSlotInactive);
+                slot.vault
+            }
+        };
+        require_keys_eq!(
+            ctx.accounts.reward_vault.key(),
+            expected_vault,
+            GamingTokenError::InvalidRewardSlot
+        );
+
+        let vault_balance = ctx.accounts.reward_vault.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.authority_token_account.to_account_info(),
+            mint: ctx.accounts.reward_mint.to_account_info(),
+            to: ctx.accounts.reward_vault.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.reward_mint.decimals)?;
+
+        // The period emits what reached the vault, net of any transfer fee
+        ctx.accounts.reward_vault.reload()?;
+        let amount = ctx.accounts.reward_vault.amount - vault_balance;
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let (reward_rate, period_finish, reward_reserve, liability) = match slot_index {
+            0 => {
+                // A schedule sets its own rate; fund it through `fund_rewards`
+                require!(
+                    stake_pool.emission_schedule.is_none(),
+                    GamingTokenError::EmissionScheduleActive
+                );
+                // The boost only spends reserve that is not already owed or scheduled
+                stake_pool.update_reward_per_token(clock.unix_timestamp)?;
+                let uncommitted = stake_pool
+                    .reward_reserve
+                    .saturating_sub(stake_pool.outstanding_liability(clock.unix_timestamp));
+                let boost = (stake_pool.boosted(amount) - amount).min(uncommitted);
+                stake_pool.reward_reserve = stake_pool
+                    .reward_reserve
+                    .checked_add(amount)
+                    .ok_or(GamingTokenError::MathOverflow)?;
+                stake_pool.notify_reward_amount(amount + boost, clock.unix_timestamp)?;
+                (
+                    stake_pool.reward_rate,
+                    stake_pool.period_finish,
+                    stake_pool.reward_reserve,
+                    stake_pool.outstanding_liability(clock.unix_timestamp),
+                )
+            }
+            _ => {
+                let total_weight = stake_pool.total_weight;
+                let slot = &mut stake_pool.reward_slots[slot_index as usize - 1];
+                slot.reward_reserve = slot
+                    .reward_reserve
+                    .checked_add(amount)
+                    .ok_or(GamingTokenError::MathOverflow)?;
+                slot.notify_reward_amount(amount, clock.unix_timestamp, total_weight)?;
+                (
+                    slot.reward_rate,
+                    slot.period_finish,
+                    slot.reward_reserve,
+                    slot.outstanding_liability(clock.unix_timestamp),
+                )
+            }
+        };
+
+        // Everything owed or still to be emitted must already be in the reserve
+        require!(reward_rate > 0, GamingTokenError::InvalidAmount);
+        require!(liability <= reward_reserve, GamingTokenError::InsufficientFunds);
+
+        emit!(RewardsNotified {
+            mint: stake_pool.mint,
+            slot: slot_index,
+            reward_mint: ctx.accounts.reward_vault.mint,
+            amount,
+            reward_rate,
+            period_finish,
+            reward_reserve,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_early_unstake_penalty(
+        ctx: Context<UpdateStakePool>,
+        early_unstake_penalty_bps: u16,
+    ) -> Result<()> {
+        require!(
+            early_unstake_penalty_bps as u128 <= BPS_DENOMINATOR,
+            GamingTokenError::InvalidPenalty
+        );
+        // Penalties are paid into the reward vault, which only takes the reward mint
+        require!(
+            early_unstake_penalty_bps == 0 || ctx.accounts.stake_pool.reward_mint == ctx.accounts.stake_pool.mint,
+            GamingTokenError::InvalidPenalty
+        );
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.early_unstake_penalty_bps = early_unstake_penalty_bps;
+
+        emit!(EarlyUnstakePenaltyUpdated {
+            mint: stake_pool.mint,
+            early_unstake_penalty_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_referral_bonus(
+        ctx: Context<UpdateStakePool>,
+        referral_bps: u16,
+    ) -> Result<()> {
+        require!(referral_bps <= MAX_REFERRAL_BPS, GamingTokenError::InvalidReferralBonus);
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.referral_bps = referral_bps;
+
+        emit!(ReferralBonusUpdated {
+            mint: stake_pool.mint,
+            referral_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Rewards solitaire players with at least `min_wins` wins in `program`
+    /// with `boost_bps` extra on their claims. Zero bps turns it off.
+    pub fn set_achievement_boost(
+        ctx: Context<UpdateStakePool>,
+        program: Pubkey,
+        min_wins: u64,
+        boost_bps: u16,
+    ) -> Result<()> {
+        require!(boost_bps <= MAX_ACHIEVEMENT_BOOST_BPS, GamingTokenError::InvalidBoost);
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.achievement_program = program;
+        stake_pool.achievement_min_wins = min_wins;
+        stake_pool.achievement_boost_bps = boost_bps;
+
+        emit!(AchievementBoostUpdated {
+            mint: stake_pool.mint,
+            program,
+            min_wins,
+            boost_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_min_claim_interval(
+        ctx: Context<UpdateStakePool>,
+        min_claim_interval_seconds: i64,
+    ) -> Result<()> {
+        require!(min_claim_interval_seconds >= 0, GamingTokenError::InvalidClaimInterval);
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.min_claim_interval_seconds = min_claim_interval_seconds;
+
+        emit!(MinClaimIntervalUpdated {
+            mint: stake_pool.mint,
+            min_claim_interval_seconds,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_reward_warmup(
+        ctx: Context<UpdateStakePool>,
+        reward_warmup_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            (0..=MAX_REWARD_WARMUP_SECONDS).contains(&reward_warmup_seconds),
+            GamingTokenError::InvalidRewardWarmup
+        );
+
+        // Positions already warming keep the end they were given
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.reward_warmup_seconds = reward_warmup_seconds;
+
+        emit!(RewardWarmupUpdated {
+            mint: stake_pool.mint,
+            reward_warmup_seconds,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_vote_lock_bonus(
+        ctx: Context<UpdateStakePool>,
+        vote_lock_bonus_bps: u16,
+    ) -> Result<()> {
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.vote_lock_bonus_bps = vote_lock_bonus_bps;
+
+        emit!(VoteLockBonusUpdated {
+            mint: stake_pool.mint,
+            vote_lock_bonus_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_lp_pool(
+        ctx: Context<SetLpPool>,
+        lp_pool: bool,
+        boost_bps: u16,
+    ) -> Result<()> {
+        require!(
+            boost_bps <= MAX_LP_BOOST_BPS && (lp_pool || boost_bps == 0),
+            GamingTokenError::InvalidBoost
+        );
+
+        // Emission so far keeps the old boost
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let clock = Clock::get()?;
+        stake_pool.update_reward_per_token(clock.unix_timestamp)?;
+        stake_pool.lp_pool = lp_pool;
+        stake_pool.boost_bps = boost_bps;
+
+        emit!(LpPoolUpdated {
+            mint: stake_pool.mint,
+            lp_pool,
+            boost_bps,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        proposal_id: u64,
+        description: String,
+        voting_period: i64,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let clock = Clock::get()?;
+
+        require!(
+            description.len() <= MAX_PROPOSAL_DESCRIPTION_LEN,
+            GamingTokenError::DescriptionTooLong
+        );
+        require!(voting_period > 0, GamingTokenError::InvalidVotingPeriod);
+
+        // Voting power is measured against the pool as it stood at creation
+        proposal.pool = ctx.accounts.stake_pool.key();
+        proposal.proposal_id = proposal_id;
+        proposal.proposer = ctx.accounts.authority.key();
+        proposal.description = description;
+        proposal.snapshot_at = clock.unix_timestamp;
+        proposal.voting_ends_at = clock
+            .unix_timestamp
+            .checked_add(voting_period)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        proposal.yes_power = 0;
+        proposal.no_power = 0;
+        proposal.finalized = false;
+        proposal.passed = false;
+        proposal.bump = ctx.bumps.proposal;
+
+        emit!(ProposalCreated {
+            pool: proposal.pool,
+            proposal_id,
+            proposer: proposal.proposer,
+            snapshot_at: proposal.snapshot_at,
+            voting_ends_at: proposal.voting_ends_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn snapshot_voting_power(
+        ctx: Context<SnapshotVotingPower>,
+    ) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        let stake_account = &ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        require!(
+            clock.unix_timestamp < proposal.voting_ends_at,
+            GamingTokenError::VotingClosed
+        );
+        // Positions opened, topped up or relocked after the proposal can't be
+        // used to swing it
+        require!(
+            stake_account.votes_at(proposal.snapshot_at),
+            GamingTokenError::NotEligibleToVote
+        );
+        let delegate = stake_account
+            .delegate_at(proposal.snapshot_at)
+            .ok_or(GamingTokenError::NotEligibleToVote)?;
+        require!(delegate.is_none(), GamingTokenError::PositionDelegated);
+
+        let power = ctx
+            .accounts
+            .stake_pool
+            .voting_power(stake_account.amount, stake_account.lock_until - proposal.snapshot_at)?;
+
+        let record = &mut ctx.accounts.voting_power_record;
+        record.proposal = proposal.key();
+        record.stake_account = stake_account.key();
+        record.delegate = None;
+        record.power = power;
+        record.voted = false;
+        record.bump = ctx.bumps.voting_power_record;
+
+        emit!(VotingPowerSnapshotted {
+            proposal: record.proposal,
+            stake_account: record.stake_account,
+            delegate: None,
+            power,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn snapshot_delegated_power(
+        ctx: Context<SnapshotDelegatedPower>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let stake_account = &ctx.accounts.stake_account;
+        let delegate_key = ctx.accounts.delegate.key();
+        let clock = Clock::get()?;
+
+        require!(
+            clock.unix_timestamp < proposal.voting_ends_at,
+            GamingTokenError::VotingClosed
+        );
+        require!(
+            stake_account.votes_at(proposal.snapshot_at),
+            GamingTokenError::NotEligibleToVote
+        );
+        // The delegate in effect at the proposal snapshot, not the current one
+        let delegate = stake_account
+            .delegate_at(proposal.snapshot_at)
+            .ok_or(GamingTokenError::NotEligibleToVote)?;
+        require!(
+            delegate == Some(delegate_key),
+            GamingTokenError::PositionNotDelegated
+        );
+
+        let power = ctx
+            .accounts
+            .stake_pool
+            .voting_power(stake_account.amount, stake_account.lock_until - proposal.snapshot_at)?;
+
+        let delegated_power = &mut ctx.accounts.delegated_power;
+        if delegated_power.proposal == Pubkey::default() {
+            delegated_power.proposal = proposal.key();
+            delegated_power.delegate = delegate_key;
+            delegated_power.bump = ctx.bumps.delegated_power;
+        }
+        delegated_power.power = delegated_power
+            .power
+            .checked_add(power)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        delegated_power.positions += 1;
+
+        // Positions counted after the delegate has voted follow that vote
+        if delegated_power.voted {
+            let tally = if delegated_power.support {
+                &mut proposal.yes_power
+            } else {
+                &mut proposal.no_power
+            };
+            *tally = tally
+                .checked_add(power)
+                .ok_or(GamingTokenError::MathOverflow)?;
+        }
+
+        let record = &mut ctx.accounts.voting_power_record;
+        record.proposal = proposal.key();
+        record.stake_account = stake_account.key();
+        record.delegate = Some(delegate_key);
+        record.power = power;
+        record.voted = delegated_power.voted;
+        record.bump = ctx.bumps.voting_power_record;
+
+        emit!(VotingPowerSnapshotted {
+            proposal: record.proposal,
+            stake_account: record.stake_account,
+            delegate: record.delegate,
+            power,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn cast_vote(
+        ctx: Context<CastVote>,
+        support: bool,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let record = &mut ctx.accounts.voting_power_record;
+        let clock = Clock::get()?;
+
+        require!(
+            clock.unix_timestamp < proposal.voting_ends_at,
+            GamingTokenError::VotingClosed
+        );
+        require!(!record.voted, GamingTokenError::AlreadyVoted);
+        require!(record.delegate.is_none(), GamingTokenError::PositionDelegated);
+
+        let tally = if support {
+            &mut proposal.yes_power
+        } else {
+            &mut proposal.no_power
+        };
+        *tally = tally
+            .checked_add(record.power)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        record.voted = true;
+
+        emit!(VoteCast {
+            proposal: proposal.key(),
+            stake_account: record.stake_account,
+            voter: ctx.accounts.owner.key(),
+            support,
+            power: record.power,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn cast_delegated_vote(
+        ctx: Context<CastDelegatedVote>,
+        support: bool,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let delegated_power = &mut ctx.accounts.delegated_power;
+        let clock = Clock::get()?;
+
+        require!(
+            clock.unix_timestamp < proposal.voting_ends_at,
+            GamingTokenError::VotingClosed
+        );
+        require!(!delegated_power.voted, GamingTokenError::AlreadyVoted);
+
+        let tally = if support {
+            &mut proposal.yes_power
+        } else {
+            &mut proposal.no_power
+        };
+        *tally = tally
+            .checked_add(delegated_power.power)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        delegated_power.voted = true;
+        delegated_power.support = support;
+
+        emit!(DelegatedVoteCast {
+            proposal: proposal.key(),
+            delegate: delegated_power.delegate,
+            support,
+            power: delegated_power.power,
+            positions: delegated_power.positions,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn finalize_proposal(
+        ctx: Context<FinalizeProposal>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let clock = Clock::get()?;
+
+        require!(!proposal.finalized, GamingTokenError::ProposalFinalized);
+        require!(
+            clock.unix_timestamp >= proposal.voting_ends_at,
+            GamingTokenError::VotingOpen
+        );
+
+        proposal.finalized = true;
+        proposal.passed = proposal.yes_power > proposal.no_power;
+
+        emit!(ProposalFinalized {
+            pool: proposal.pool,
+            proposal_id: proposal.proposal_id,
+            passed: proposal.passed,
+            yes_power: proposal.yes_power,
+            no_power: proposal.no_power,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_slash_config(
+        ctx: Context<UpdateStakePool>,
+        max_slash_bps: u16,
+        slash_timelock_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            max_slash_bps as u128 <= BPS_DENOMINATOR,
+            GamingTokenError::InvalidSlashAmount
+        );
+        // A proposal must always leave the staker time to dispute it
+        require!(slash_timelock_seconds > 0, GamingTokenError::InvalidSlashTimelock);
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.max_slash_bps = max_slash_bps;
+        stake_pool.slash_timelock_seconds = slash_timelock_seconds;
+
+        emit!(SlashConfigUpdated {
+            mint: stake_pool.mint,
+            max_slash_bps,
+            slash_timelock_seconds,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn propose_slash(
+        ctx: Context<ProposeSlash>,
+        slash_bps: u16,
+        justification_hash: [u8; 32],
+    ) -> Result<()> {
+        let stake_pool = &ctx.accounts.stake_pool;
+        let clock = Clock::get()?;
+
+        require!(
+            slash_bps > 0 && slash_bps <= stake_pool.max_slash_bps,
+            GamingTokenError::InvalidSlashAmount
+        );
+
+        let executable_at = clock
+            .unix_timestamp
+            .checked_add(stake_pool.slash_timelock_seconds)
+            .ok_or(GamingTokenError::MathOverflow)?;
+
+        let slash_proposal = &mut ctx.accounts.slash_proposal;
+        slash_proposal.stake_pool = stake_pool.key();
+        slash_proposal.stake_account = ctx.accounts.stake_account.key();
+        slash_proposal.slash_bps = slash_bps;
+        slash_proposal.destination = ctx.accounts.destination.key();
+        slash_proposal.justification_hash = justification_hash;
+        slash_proposal.proposed_at = clock.unix_timestamp;
+        slash_proposal.executable_at = executable_at;
+        slash_proposal.bump = ctx.bumps.slash_proposal;
+
+        // The position can't be withdrawn while the slash is pending
+        let stake_account = &mut ctx.accounts.stake_account;
+        stake_account.slash_pending = true;
+
+        emit!(SlashProposed {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            slash_bps,
+            destination: slash_proposal.destination,
+            justification_hash,
+            executable_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_slash(
+        ctx: Context<CancelSlash>,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        stake_account.slash_pending = false;
+
+        emit!(SlashCancelled {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            justification_hash: ctx.accounts.slash_proposal.justification_hash,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn execute_slash(
+        ctx: Context<ExecuteSlash>,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let slash_proposal = &ctx.accounts.slash_proposal;
+        let clock = Clock::get()?;
+
+        require!(
+            clock.unix_timestamp >= slash_proposal.executable_at,
+            GamingTokenError::SlashTimelockActive
+        );
+
+        // Rewards earned before the slash are kept at the old weight
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        update_rewards(stake_pool, stake_account, clock.unix_timestamp)?;
+
+        let amount = ((stake_account.amount as u128 * slash_proposal.slash_bps as u128
+            / BPS_DENOMINATOR) as u64)
+            .min(stake_account.amount);
+
+        let destination_balance = ctx.accounts.destination.amount;
+        if amount > 0 {
+            let mint_key = stake_pool.mint;
+            let vault_seeds = &[
+                b"vault_authority",
+                mint_key.as_ref(),
+                &[ctx.bumps.vault_authority],
+            ];
+            let signer = &[&vault_seeds[..]];
+
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.stake_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+        }
+        ctx.accounts.destination.reload()?;
+        let received = ctx.accounts.destination.amount - destination_balance;
+
+        let old_weight = stake_account.weight();
+        stake_account.amount -= amount;
+        stake_account.slash_pending = false;
+        stake_pool.remove_stake(amount)?;
+        stake_pool.reweight(old_weight, stake_account.weight())?;
+
+        // Slashing into the reward vault redistributes to the remaining
+        // stakers; the slashed position is kept out of its own slash
+        let mut redistributed = 0;
+        if ctx.accounts.destination.key() == stake_pool.reward_vault {
+            redistributed = stake_pool.distribute_now(received, stake_account.weight(), clock.unix_timestamp)?;
+            stake_account.reward_per_token_paid = stake_pool.reward_per_token_stored;
+        }
+
+        emit!(StakeSlashed {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            amount,
+            remaining_principal: stake_account.amount,
+            destination: ctx.accounts.destination.key(),
+            redistributed,
+            justification_hash: slash_proposal.justification_hash,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_stake_limits(
+        ctx: Context<UpdateStakePool>,
+        min_stake_amount: u64,
+        min_lock_seconds: i64,
+        max_lock_seconds: i64,
+    ) -> Result<()> {
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.set_stake_limits(min_stake_amount, min_lock_seconds, max_lock_seconds)?;
+
+        emit!(StakeLimitsUpdated {
+            mint: stake_pool.mint,
+            min_stake_amount,
+            min_lock_seconds,
+            max_lock_seconds,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_unstake_cooldown(
+        ctx: Context<UpdateStakePool>,
+        unstake_cooldown_seconds: i64,
+    ) -> Result<()> {
+        require!(unstake_cooldown_seconds >= 0, GamingTokenError::InvalidLockPeriod);
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.unstake_cooldown_seconds = unstake_cooldown_seconds;
+
+        emit!(UnstakeCooldownUpdated {
+            mint: stake_pool.mint,
+            unstake_cooldown_seconds,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_lock_tiers(
+        ctx: Context<UpdateStakePool>,
+        lock_tiers: Vec<LockTier>,
+    ) -> Result<()> {
+        require!(lock_tiers.len() <= MAX_LOCK_TIERS, GamingTokenError::InvalidLockTiers);
+        require!(
+            lock_tiers.windows(2).all(|w| w[0].min_lock_seconds < w[1].min_lock_seconds),
+            GamingTokenError::InvalidLockTiers
+        );
+        require!(
+            lock_tiers
+                .iter()
+                .all(|tier| tier.min_lock_seconds > 0 && tier.boost_bps as u128 <= BPS_DENOMINATOR),
+            GamingTokenError::InvalidLockTiers
+        );
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.lock_tiers = lock_tiers;
+
+        emit!(LockTiersUpdated {
+            mint: stake_pool.mint,
+            lock_tiers: stake_pool.lock_tiers.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_pool_paused(
+        ctx: Context<UpdateStakePool>,
+        paused: bool,
+    ) -> Result<()> {
+        let stake_pool = &mut ctx.accounts.stake_pool;
+
+        require!(
+            paused || !stake_pool.emergency_mode,
+            GamingTokenError::EmergencyModeActive
+        );
+        require!(paused || !stake_pool.is_sunset(), GamingTokenError::PoolSunset);
+        stake_pool.paused = paused;
+
+        emit!(PoolPauseUpdated {
+            mint: stake_pool.mint,
+            authority: stake_pool.authority,
+            paused,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_emergency_mode(
+        ctx: Context<UpdateStakePool>,
+        emergency_mode: bool,
+    ) -> Result<()> {
+        let stake_pool = &mut ctx.accounts.stake_pool;
+
+        // Emergency mode always implies a pause; leaving it keeps the pool paused
+        stake_pool.emergency_mode = emergency_mode;
+        if emergency_mode {
+            stake_pool.paused = true;
+        }
+
+        emit!(EmergencyModeUpdated {
+            mint: stake_pool.mint,
+            authority: stake_pool.authority,
+            emergency_mode,
+            paused: stake_pool.paused,
+            emergency_destination: stake_pool.emergency_destination,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Freezes reward accrual and every reward payout while leaving principal
+    /// free to leave, locks waived. Unfreezing resumes accrual from now; a
+    /// running reward period is pushed back by the frozen time so it still
+    /// emits in full, while epochs of an emission schedule keep their clock.
+    pub fn set_emergency_rewards_freeze(
+        ctx: Context<UpdateStakePool>,
+        frozen: bool,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let stake_pool = &mut ctx.accounts.stake_pool;
+
+        // Checkpoint up to the freeze, or up to the unfreeze, which adds nothing
+        stake_pool.update_reward_per_token(clock.unix_timestamp)?;
+        if frozen {
+            require!(!stake_pool.emergency_rewards_freeze, GamingTokenError::RewardsFrozen);
+            stake_pool.emergency_rewards_freeze = true;
+            stake_pool.rewards_frozen_at = clock.unix_timestamp;
+        } else {
+            require!(stake_pool.emergency_rewards_freeze, GamingTokenError::RewardsNotFrozen);
+            if stake_pool.emission_schedule.is_none() && stake_pool.period_finish > stake_pool.rewards_frozen_at {
+                stake_pool.period_finish = stake_pool
+                    .period_finish
+                    .checked_add(clock.unix_timestamp - stake_pool.rewards_frozen_at)
+                    .ok_or(GamingTokenError::MathOverflow)?;
+            }
+            stake_pool.last_update_time = clock.unix_timestamp;
+            stake_pool.emergency_rewards_freeze = false;
+        }
+
+        emit!(RewardsFreezeUpdated {
+            mint: stake_pool.mint,
+            authority: ctx.accounts.authority.key(),
+            frozen,
+            frozen_at: stake_pool.rewards_frozen_at,
+            period_finish: stake_pool.period_finish,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the reward reserve level that raises `RewardReserveLow`; 0 turns
+    /// the alarm off. The next payout under the new level raises it again.
+    pub fn set_low_reserve_threshold(
+        ctx: Context<UpdateStakePool>,
+        threshold: u64,
+    ) -> Result<()> {
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.low_reserve_threshold = threshold;
+        stake_pool.reserve_low = false;
+
+        emit!(LowReserveThresholdUpdated {
+            mint: stake_pool.mint,
+            authority: ctx.accounts.authority.key(),
+            threshold,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pays the reward debt left by positions closed during a rewards freeze;
+    /// a short vault pays what it can and the rest stays owed.
+    pub fn claim_reward_debt(ctx: Context<ClaimRewardDebt>) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(
+            !ctx.accounts.stake_pool.emergency_rewards_freeze,
+            GamingTokenError::RewardsFrozen
+        );
+        let reward_debt = ctx.accounts.stake_positions.reward_debt;
+        let reward_paid = reward_debt.min(ctx.accounts.reward_vault.amount);
+        require!(reward_paid > 0, GamingTokenError::NothingToClaim);
+        let (reward_fee, reward_net) = ctx.accounts.stake_pool.split_claim_fee(reward_paid);
+
+        let mint_key = ctx.accounts.mint.key();
+        let reward_vault_seeds = &[
+            b"reward_vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.reward_vault_authority],
+        ];
+        let signer = &[&reward_vault_seeds[..]];
+
+        if reward_fee > 0 {
+            let fee_vault = ctx
+                .accounts
+                .fee_vault
+                .as_ref()
+                .ok_or(GamingTokenError::FeeVaultRequired)?;
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                mint: ctx.accounts.reward_mint.to_account_info(),
+                to: fee_vault.to_account_info(),
+                authority: ctx.accounts.reward_vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::transfer_checked(cpi_ctx, reward_fee, ctx.accounts.reward_mint.decimals)?;
+        }
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            mint: ctx.accounts.reward_mint.to_account_info(),
+            to: ctx.accounts.user_reward_token_account.to_account_info(),
+            authority: ctx.accounts.reward_vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, reward_net, ctx.accounts.reward_mint.decimals)?;
+
+        ctx.accounts.stake_pool.settle_rewards(reward_paid, reward_paid)?;
+        let stake_positions = &mut ctx.accounts.stake_positions;
+        stake_positions.reward_debt -= reward_paid;
+
+        emit!(RewardDebtClaimed {
+            owner: stake_positions.owner,
+            mint: stake_positions.mint,
+            paid: reward_net,
+            fee: reward_fee,
+            remaining_debt: stake_positions.reward_debt,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_emergency_destination(
+        ctx: Context<SetEmergencyDestination>,
+    ) -> Result<()> {
+        let stake_pool = &mut ctx.accounts.stake_pool;
+
+        require!(
+            !stake_pool.emergency_mode,
+            GamingTokenError::EmergencyModeActive
+        );
+        let old_destination = stake_pool.emergency_destination;
+        stake_pool.emergency_destination = ctx.accounts.destination.key();
+
+        emit!(EmergencyDestinationUpdated {
+            mint: stake_pool.mint,
+            old_destination,
+            new_destination: stake_pool.emergency_destination,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn emergency_withdraw_vault(
+        ctx: Context<EmergencyWithdrawVault>,
+        amount: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+        require!(
+            amount <= ctx.accounts.reward_vault.amount,
+            GamingTokenError::InsufficientFunds
+        );
+
+        // Only the reward vault can be drained; staked principal stays claimable
+        let mint_key = ctx.accounts.stake_pool.mint;
+        let reward_vault_seeds = &[
+            b"reward_vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.reward_vault_authority],
+        ];
+        let signer = &[&reward_vault_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            mint: ctx.accounts.reward_mint.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.reward_vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.reward_mint.decimals)?;
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.update_reward_per_token(clock.unix_timestamp)?;
+        stake_pool.reward_reserve = stake_pool.reward_reserve.saturating_sub(amount);
+
+        emit!(EmergencyVaultWithdrawn {
+            mint: mint_key,
+            authority: ctx.accounts.authority.key(),
+            vault: ctx.accounts.reward_vault.key(),
+            destination: ctx.accounts.destination.key(),
+            amount,
+            vault_balance: ctx.accounts.reward_vault.amount - amount,
+            reward_reserve: stake_pool.reward_reserve,
+            outstanding_liability: stake_pool.outstanding_liability(clock.unix_timestamp),
+            total_staked: stake_pool.total_staked,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn add_reward_token(
+        ctx: Context<AddRewardToken>,
+        reward_duration: i64,
+    ) -> Result<()> {
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let clock = Clock::get()?;
+
+        require!(reward_duration > 0, GamingTokenError::InvalidRewardDuration);
+        require!(
+            stake_pool.reward_slots.len() < MAX_REWARD_SLOTS,
+            GamingTokenError::RewardSlotsFull
+        );
+        require!(
+            ctx.accounts.reward_mint.key() != stake_pool.reward_mint,
+            GamingTokenError::InvalidRewardSlot
+        );
+
+        // Slots are never reused, so positions' zeroed checkpoints match a new slot
+        stake_pool.reward_slots.push(RewardSlot {
+            mint: ctx.accounts.reward_mint.key(),
+            vault: ctx.accounts.slot_vault.key(),
+            reward_duration,
+            reward_rate: 0,
+            period_finish: clock.unix_timestamp,
+            reward_per_token_stored: 0,
+            last_update_time: clock.unix_timestamp,
+            reward_reserve: 0,
+            rewards_owed: 0,
+            total_rewards_paid: 0,
+            active: true,
+        });
+
+        emit!(RewardTokenAdded {
+            mint: stake_pool.mint,
+            slot: stake_pool.reward_slots.len() as u8,
+            reward_mint: ctx.accounts.reward_mint.key(),
+            vault: ctx.accounts.slot_vault.key(),
+            reward_duration,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_reward_token(
+        ctx: Context<RemoveRewardToken>,
+        slot_index: u8,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let total_weight = stake_pool.total_weight;
+        let slot = slot_index
+            .checked_sub(1)
+            .and_then(|index| stake_pool.reward_slots.get_mut(index as usize))
+            .ok_or(GamingTokenError::InvalidRewardSlot)?;
+
+        require!(slot.active, GamingTokenError::RewardSlotInactive);
+        require_keys_eq!(
+            ctx.accounts.slot_vault.key(),
+            slot.vault,
+            GamingTokenError::InvalidRewardSlot
+        );
+        slot.update_reward_per_token(clock.unix_timestamp, total_weight)?;
+        require!(
+            clock.unix_timestamp >= slot.period_finish,
+            GamingTokenError::RewardPeriodActive
+        );
+        // Everything stakers have accrued must still be in the vault to claim
+        require!(
+            ctx.accounts.slot_vault.amount >= slot.rewards_owed,
+            GamingTokenError::InsufficientFunds
+        );
+
+        // The slot stays claim-only; the rest of its vault goes back to the authority
+        let swept = ctx.accounts.slot_vault.amount - slot.rewards_owed;
+        slot.reward_reserve = slot.rewards_owed;
+        slot.active = false;
+        let reward_mint = slot.mint;
+        let rewards_owed = slot.rewards_owed;
+
+        if swept > 0 {
+            let mint_key = stake_pool.mint;
+            let reward_vault_seeds = &[
+                b"reward_vault_authority",
+                mint_key.as_ref(),
+                &[ctx.bumps.reward_vault_authority],
+            ];
+            let signer = &[&reward_vault_seeds[..]];
+
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.slot_vault.to_account_info(),
+                mint: ctx.accounts.reward_mint.to_account_info(),
+                to: ctx.accounts.authority_token_account.to_account_info(),
+                authority: ctx.accounts.reward_vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::transfer_checked(cpi_ctx, swept, ctx.accounts.reward_mint.decimals)?;
+        }
+
+        emit!(RewardTokenRemoved {
+            mint: stake_pool.mint,
+            slot: slot_index,
+            reward_mint,
+            swept,
+            rewards_owed,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn fund_rewards(
+        ctx: Context<FundRewards>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+
+        let vault_balance = ctx.accounts.reward_vault.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.funder_token_account.to_account_info(),
+            mint: ctx.accounts.reward_mint.to_account_info(),
+            to: ctx.accounts.reward_vault.to_account_info(),
+            authority: ctx.accounts.funder.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.reward_mint.decimals)?;
+
+        // Only what reached the vault backs rewards
+        ctx.accounts.reward_vault.reload()?;
+        let amount = ctx.accounts.reward_vault.amount - vault_balance;
+
+        let clock = Clock::get()?;
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let large = amount as u128 * BPS_DENOMINATOR
+            >= stake_pool.reward_reserve as u128 * RATE_HISTORY_FUNDING_BPS;
+        stake_pool.reward_reserve = stake_pool
+            .reward_reserve
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        if large {
+            // A scheduled epoch may have rolled at a rate the old reserve capped
+            stake_pool.update_reward_per_token(clock.unix_timestamp)?;
+            stake_pool.record_rate(clock.unix_timestamp);
+        }
+        stake_pool.check_reserve_recovered(ctx.accounts.reward_vault.amount, clock.unix_timestamp);
+
+        emit!(RewardsFunded {
+            mint: stake_pool.mint,
+            funder: ctx.accounts.funder.key(),
+            amount,
+            reward_reserve: stake_pool.reward_reserve,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless: records the current rate so the history stays fresh
+    /// between rate changes. At most one entry per `RATE_SNAPSHOT_INTERVAL_SECONDS`
+    /// so nobody can flush the buffer.
+    pub fn snapshot_pool_rate(ctx: Context<SnapshotPoolRate>) -> Result<()> {
+        let clock = Clock::get()?;
+        let stake_pool = &mut ctx.accounts.stake_pool;
+
+        stake_pool.update_reward_per_token(clock.unix_timestamp)?;
+        if let Some(latest) = stake_pool.latest_rate() {
+            require!(
+                clock.unix_timestamp - latest.timestamp >= RATE_SNAPSHOT_INTERVAL_SECONDS,
+                GamingTokenError::SnapshotTooSoon
+            );
+        }
+        let snapshot = stake_pool.record_rate(clock.unix_timestamp);
+
+        emit!(PoolRateSnapshot {
+            mint: stake_pool.mint,
+            reward_rate: snapshot.reward_rate,
+            total_staked: snapshot.total_staked,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Opens an airdrop snapshot of the pool as of now. Positions are then
+    /// written into it by `record_snapshot_entry` until `finalize_snapshot`
+    /// seals it with the merkle root computed off-chain from those entries.
+    pub fn create_snapshot(ctx: Context<CreateSnapshot>, snapshot_id: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        let stake_pool = &ctx.accounts.stake_pool;
+        let snapshot = &mut ctx.accounts.snapshot;
+
+        snapshot.stake_pool = stake_pool.key();
+        snapshot.mint = stake_pool.mint;
+        snapshot.snapshot_id = snapshot_id;
+        snapshot.timestamp = clock.unix_timestamp;
+        snapshot.slot = clock.slot;
+        snapshot.total_staked = stake_pool.total_staked;
+        snapshot.recorded_amount = 0;
+        snapshot.entry_count = 0;
+        snapshot.merkle_root = [0; 32];
+        snapshot.finalized = false;
+        snapshot.bump = ctx.bumps.snapshot;
+
+        emit!(SnapshotCreated {
+            mint: snapshot.mint,
+            snapshot: snapshot.key(),
+            snapshot_id,
+            slot: snapshot.slot,
+            total_staked: snapshot.total_staked,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless crank over `[stake account, entry]` pairs in
+    /// `remaining_accounts`. Each position that was active at the snapshot
+    /// gets an entry PDA holding its owner and principal; positions opened
+    /// later or already unbonding are skipped, as are ones already recorded.
+    /// Principal is read when the entry is written, so the crank should run
+    /// soon after `create_snapshot`.
+    pub fn record_snapshot_entry<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RecordSnapshotEntry<'info>>,
+    ) -> Result<()> {
+        let snapshot = &mut ctx.accounts.snapshot;
+        require!(!snapshot.finalized, GamingTokenError::SnapshotAlreadyFinalized);
+        require!(
+            !ctx.remaining_accounts.is_empty() && ctx.remaining_accounts.len().is_multiple_of(2),
+            GamingTokenError::InvalidSnapshotEntry
+        );
+
+        let snapshot_key = snapshot.key();
+        let rent = Rent::get()?;
+        let mut recorded = 0u32;
+        let mut skipped = 0u32;
+        for accounts in ctx.remaining_accounts.chunks(2) {
+            let stake_account = Account::<StakeAccount>::try_from(&accounts[0])?;
+            let entry = &accounts[1];
+            require_keys_eq!(stake_account.mint, snapshot.mint, GamingTokenError::InvalidSnapshotEntry);
+
+            let stake_account_key = stake_account.key();
+            let (entry_key, entry_bump) = Pubkey::find_program_address(
+                &[b"snapshot_entry", snapshot_key.as_ref(), stake_account_key.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(entry.key(), entry_key, GamingTokenError::InvalidSnapshotEntry);
+
+            let unbonded_before = stake_account
+                .unbonding_since
+                .is_some_and(|since| since <= snapshot.timestamp);
+            if entry.owner == ctx.program_id
+                || stake_account.created_at > snapshot.timestamp
+                || unbonded_before
+                || stake_account.amount == 0
+            {
+                skipped += 1;
+                continue;
+            }
+
+            let entry_seeds = &[
+                b"snapshot_entry",
+                snapshot_key.as_ref(),
+                stake_account_key.as_ref(),
+                &[entry_bump],
+            ];
+            let signer = &[&entry_seeds[..]];
+            let cpi_accounts = CreateAccount {
+                from: ctx.accounts.payer.to_account_info(),
+                to: entry.clone(),
+            };
+            let cpi_program = ctx.accounts.system_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            system_program::create_account(
+                cpi_ctx,
+                rent.minimum_balance(SnapshotEntry::SPACE),
+                SnapshotEntry::SPACE as u64,
+                ctx.program_id,
+            )?;
+
+            let record = SnapshotEntry {
+                snapshot: snapshot_key,
+                stake_account: stake_account_key,
+                owner: stake_account.owner,
+                amount: stake_account.amount,
+                bump: entry_bump,
+            };
+            record.try_serialize(&mut &mut entry.try_borrow_mut_data()?[..])?;
+
+            snapshot.recorded_amount = snapshot
+                .recorded_amount
+                .checked_add(stake_account.amount)
+                .ok_or(GamingTokenError::MathOverflow)?;
+            snapshot.entry_count += 1;
+            recorded += 1;
+        }
+
+        emit!(SnapshotEntriesRecorded {
+            mint: snapshot.mint,
+            snapshot: snapshot_key,
+            recorded,
+            skipped,
+            entry_count: snapshot.entry_count,
+            recorded_amount: snapshot.recorded_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Seals a snapshot with the merkle root built from its entries; no
+    /// further entries can be recorded afterwards.
+    pub fn finalize_snapshot(ctx: Context<FinalizeSnapshot>, merkle_root: [u8; 32]) -> Result<()> {
+        let clock = Clock::get()?;
+        let snapshot = &mut ctx.accounts.snapshot;
+        require!(!snapshot.finalized, GamingTokenError::SnapshotAlreadyFinalized);
+        require!(merkle_root != [0; 32], GamingTokenError::InvalidMerkleRoot);
+
+        snapshot.merkle_root = merkle_root;
+        snapshot.finalized = true;
+
+        emit!(SnapshotFinalized {
+            mint: snapshot.mint,
+            snapshot: snapshot.key(),
+            snapshot_id: snapshot.snapshot_id,
+            merkle_root,
+            entry_count: snapshot.entry_count,
+            recorded_amount: snapshot.recorded_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_excess_rewards(
+        ctx: Context<WithdrawExcessRewards>,
+        amount: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+
+        // Only the part of the reserve not owed or scheduled for stakers can leave
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.update_reward_per_token(clock.unix_timestamp)?;
+        let excess = stake_pool
+            .reward_reserve
+            .saturating_sub(stake_pool.outstanding_liability(clock.unix_timestamp));
+        require!(amount <= excess, GamingTokenError::InsufficientFunds);
+        stake_pool.reward_reserve -= amount;
+
+        let mint_key = stake_pool.mint;
+        let reward_vault_seeds = &[
+            b"reward_vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.reward_vault_authority],
+        ];
+        let signer = &[&reward_vault_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            mint: ctx.accounts.reward_mint.to_account_info(),
+            to: ctx.accounts.authority_token_account.to_account_info(),
+            authority: ctx.accounts.reward_vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.reward_mint.decimals)?;
+
+        emit!(ExcessRewardsWithdrawn {
+            mint: mint_key,
+            amount,
+            reward_reserve: ctx.accounts.stake_pool.reward_reserve,
+            outstanding_liability: ctx.accounts.stake_pool.outstanding_liability(clock.unix_timestamp),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Opens the vault claim fees are paid into; the fee itself goes through
+    /// `queue_pool_update`.
+    pub fn create_fee_vault(
+        ctx: Context<CreateFeeVault>,
+    ) -> Result<()> {
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.fee_vault = ctx.accounts.fee_vault.key();
+
+        emit!(FeeVaultCreated {
+            mint: stake_pool.mint,
+            fee_vault: stake_pool.fee_vault,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_collected_fees(
+        ctx: Context<WithdrawCollectedFees>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+        require!(
+            amount <= ctx.accounts.fee_vault.amount,
+            GamingTokenError::InsufficientFunds
+        );
+
+        let mint_key = ctx.accounts.stake_pool.mint;
+        let reward_vault_seeds = &[
+            b"reward_vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.reward_vault_authority],
+        ];
+        let signer = &[&reward_vault_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.fee_vault.to_account_info(),
+            mint: ctx.accounts.reward_mint.to_account_info(),
+            to: ctx.accounts.authority_token_account.to_account_info(),
+            authority: ctx.accounts.reward_vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.reward_mint.decimals)?;
+
+        emit!(CollectedFeesWithdrawn {
+            mint: mint_key,
+            amount,
+            destination: ctx.accounts.authority_token_account.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Starts retiring the pool in favour of `successor_pool`: new stake is
+    /// blocked, locks are waived, and until `deadline` positions can move over
+    /// with `migrate_position`. The successor stakes another mint, so the
+    /// authority funds the migration vault with successor tokens to pay for them.
+    pub fn begin_sunset(
+        ctx: Context<BeginSunset>,
+        deadline: i64,
+        migration_bonus_bps: u16,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(deadline > clock.unix_timestamp, GamingTokenError::InvalidSunsetDeadline);
+        require!(
+            migration_bonus_bps <= MAX_MIGRATION_BONUS_BPS,
+            GamingTokenError::InvalidMigrationBonus
+        );
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.successor_pool = ctx.accounts.successor_pool.key();
+        stake_pool.sunset_deadline = deadline;
+        stake_pool.migration_bonus_bps = migration_bonus_bps;
+        stake_pool.paused = true;
+
+        emit!(SunsetBegun {
+            mint: stake_pool.mint,
+            pool: stake_pool.key(),
+            successor_pool: stake_pool.successor_pool,
+            successor_mint: ctx.accounts.successor_mint.key(),
+            migration_vault: ctx.accounts.migration_vault.key(),
+            deadline,
+            migration_bonus_bps,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Moves a position from a sunsetting pool to its successor in one step:
+    /// rewards are settled and paid, the old principal is burned, and the
+    /// migration vault stakes its successor-mint equivalent, plus the bonus,
+    /// with the same lock expiry. Partner-slot rewards should be claimed first;
+    /// whatever is left of them is written off.
+    pub fn migrate_position(
+        ctx: Context<MigratePosition>,
+        new_position_index: u16,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let stake_account = &mut ctx.accounts.stake_account;
+
+        require!(stake_pool.is_sunset(), GamingTokenError::PoolNotSunset);
+        require!(!stake_pool.emergency_rewards_freeze, GamingTokenError::RewardsFrozen);
+        require!(
+            clock.unix_timestamp < stake_pool.sunset_deadline,
+            GamingTokenError::SunsetDeadlinePassed
+        );
+        require!(
+            !ctx.accounts.successor_pool.paused,
+            GamingTokenError::PoolPaused
+        );
+        require!(
+            stake_account.unbonding_since.is_none(),
+            GamingTokenError::PositionUnbonding
+        );
+
+        let reward_amount = update_rewards(stake_pool, stake_account, clock.unix_timestamp)?;
+        forfeit_slot_rewards(stake_pool, stake_account)?;
+        let reward_paid = reward_amount.min(ctx.accounts.reward_vault.amount);
+        let (reward_fee, reward_net) = stake_pool.split_claim_fee(reward_paid);
+        let mint_key = ctx.accounts.mint.key();
+        if reward_paid > 0 {
+            let reward_vault_seeds = &[
+                b"reward_vault_authority",
+                mint_key.as_ref(),
+                &[ctx.bumps.reward_vault_authority],
+            ];
+            let signer = &[&reward_vault_seeds[..]];
+
+            if reward_fee > 0 {
+                let fee_vault = ctx
+                    .accounts
+                    .fee_vault
+                    .as_ref()
+                    .ok_or(GamingTokenError::FeeVaultRequired)?;
+                let cpi_accounts = TransferChecked {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    mint: ctx.accounts.reward_mint.to_account_info(),
+                    to: fee_vault.to_account_info(),
+                    authority: ctx.accounts.reward_vault_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+                token_interface::transfer_checked(cpi_ctx, reward_fee, ctx.accounts.reward_mint.decimals)?;
+            }
+
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                mint: ctx.accounts.reward_mint.to_account_info(),
+                to: ctx.accounts.user_reward_token_account.to_account_info(),
+                authority: ctx.accounts.reward_vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::transfer_checked(cpi_ctx, reward_net, ctx.accounts.reward_mint.decimals)?;
+        }
+        stake_pool.settle_rewards(reward_amount, reward_paid)?;
+
+        // The old token is being retired, so its principal is burned
+        let amount = stake_account.amount;
+        let vault_seeds = &[
+            b"vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.vault_authority],
+        ];
+        let signer = &[&vault_seeds[..]];
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.mint.to_account_info(),
+            from: ctx.accounts.stake_vault.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::burn(cpi_ctx, amount)?;
+
+        stake_pool.remove_stake(amount)?;
+        stake_pool.reweight(stake_account.weight(), 0)?;
+        stake_pool.close_position()?;
+        stake_account.is_active = false;
+        ctx.accounts.stake_positions.open_positions -= 1;
+
+        let converted = convert_decimals(amount, ctx.accounts.mint.decimals, ctx.accounts.successor_mint.decimals)?;
+        let bonus = (converted as u128 * stake_pool.migration_bonus_bps as u128 / BPS_DENOMINATOR) as u64;
+        let funded = converted
+            .checked_add(bonus)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        require!(
+            ctx.accounts.migration_vault.amount >= funded,
+            GamingTokenError::MigrationVaultUnderfunded
+        );
+
+        let vault_balance = ctx.accounts.successor_stake_vault.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.migration_vault.to_account_info(),
+            mint: ctx.accounts.successor_mint.to_account_info(),
+            to: ctx.accounts.successor_stake_vault.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, funded, ctx.accounts.successor_mint.decimals)?;
+
+        ctx.accounts.successor_stake_vault.reload()?;
+        let migrated = ctx.accounts.successor_stake_vault.amount - vault_balance;
+
+        let positions = &mut ctx.accounts.successor_positions;
+        if positions.owner == Pubkey::default() {
+            positions.owner = ctx.accounts.owner.key();
+            positions.mint = ctx.accounts.successor_mint.key();
+            positions.reward_debt = 0;
+            positions.bump = ctx.bumps.successor_positions;
+        }
+        positions.open(new_position_index)?;
+
+        // Same lock expiry and length, boosted by the successor's own tiers
+        let successor_pool = &mut ctx.accounts.successor_pool;
+        successor_pool.update_reward_per_token(clock.unix_timestamp)?;
+        successor_pool.update_reward_slots(clock.unix_timestamp)?;
+        let new_stake_account = &mut ctx.accounts.new_stake_account;
+        new_stake_account.set_inner(StakeAccount {
+            version: STAKE_ACCOUNT_VERSION,
+            owner: ctx.accounts.owner.key(),
+            mint: successor_pool.mint,
+            position_index: new_position_index,
+            amount: migrated,
+            lock_until: stake_account.lock_until,
+            lock_period: stake_account.lock_period,
+            created_at: clock.unix_timestamp,
+            last_reward_claim: clock.unix_timestamp,
+            reward_per_token_paid: successor_pool.reward_per_token_stored,
+            boost_bps: successor_pool
+                .tier_for(stake_account.lock_period)
+                .map_or(0, |index| successor_pool.lock_tiers[index].boost_bps),
+            receipt_mint: None,
+            rewards_accrued: 0,
+            reward_dust: 0,
+            slot_rewards: std::array::from_fn(|index| SlotRewards {
+                reward_per_token_paid: successor_pool
+                    .reward_slots
+                    .get(index)
+                    .map_or(0, |slot| slot.reward_per_token_stored),
+                rewards_accrued: 0,
+                reward_dust: 0,
+            }),
+            unbonding_since: None,
+            available_at: 0,
+            delegate: stake_account.delegate,
+            delegated_at: clock.unix_timestamp,
+            previous_delegate: None,
+            previous_delegated_at: clock.unix_timestamp,
+            slash_pending: false,
+            is_active: true,
+            warmup_until: 0,
+            power_changed_at: clock.unix_timestamp,
+        });
+        successor_pool.add_stake(migrated)?;
+        successor_pool.reweight(0, new_stake_account.weight())?;
+        successor_pool.open_position()?;
+
+        emit!(PositionMigrated {
+            owner: new_stake_account.owner,
+            mint: stake_account.mint,
+            pool: stake_pool.key(),
+            stake_account: stake_account.key(),
+            successor_pool: successor_pool.key(),
+            new_stake_account: new_stake_account.key(),
+            new_position_index,
+            burned: amount,
+            migrated,
+            bonus,
+            rewards_paid: reward_net,
+            lock_until: new_stake_account.lock_until,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless once the deadline has passed: moves everything still
+    /// staked into the sunset escrow, where each leftover position can only
+    /// withdraw its principal, so the pool can be closed.
+    pub fn sweep_sunset(ctx: Context<SweepSunset>) -> Result<()> {
+        let clock = Clock::get()?;
+        let stake_pool = &mut ctx.accounts.stake_pool;
+
+        require!(stake_pool.is_sunset(), GamingTokenError::PoolNotSunset);
+        require!(
+            clock.unix_timestamp >= stake_pool.sunset_deadline,
+            GamingTokenError::SunsetNotOver
+        );
+
+        let escrowed = ctx.accounts.stake_vault.amount;
+        let mint_key = ctx.accounts.mint.key();
+        let vault_seeds = &[
+            b"vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.vault_authority],
+        ];
+        let signer = &[&vault_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.stake_vault.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.sunset_escrow.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, escrowed, ctx.accounts.mint.decimals)?;
+
+        // Any exit from the pool now fails on the empty stake total
+        let unmigrated = stake_pool.total_staked;
+        stake_pool.total_staked = 0;
+        stake_pool.sunset_swept = true;
+
+        emit!(SunsetSwept {
+            mint: stake_pool.mint,
+            pool: stake_pool.key(),
+            unmigrated,
+            escrowed,
+            positions: stake_pool.total_positions,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pays a position left behind by a sunset its principal out of the
+    /// escrow and closes it. Works after the pool itself is closed.
+    pub fn claim_sunset_escrow(ctx: Context<ClaimSunsetEscrow>) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let amount = stake_account.amount;
+
+        let mint_key = ctx.accounts.mint.key();
+        let vault_seeds = &[
+            b"vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.vault_authority],
+        ];
+        let signer = &[&vault_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.sunset_escrow.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.holder_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+        stake_account.is_active = false;
+        ctx.accounts.stake_positions.open_positions -= 1;
+
+        emit!(SunsetEscrowClaimed {
+            owner: stake_account.owner,
+            holder: ctx.accounts.holder.key(),
+            mint: stake_account.mint,
+            stake_account: stake_account.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Retires a pool nobody is staked in and closes its vaults for the rent.
+    /// With no positions left no reward is owed to anyone, so whatever the
+    /// reward, fee and partner-slot vaults still hold goes to the authority;
+    /// slot vaults come as `[slot vault, slot mint, authority token account]`
+    /// triples in `remaining_accounts`, one per slot. A `ClosedPool` tombstone
+    /// stops the mint from getting a new pool at the old addresses, which
+    /// stats, referrals and queued updates still point at; the registry entry
+    /// stays, marked closed.
+    pub fn close_pool<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClosePool<'info>>,
+    ) -> Result<()> {
+        let stake_pool = &ctx.accounts.stake_pool;
+        // Positions left after a sunset sweep are paid from the escrow instead
+        require!(
+            stake_pool.total_positions == 0 || stake_pool.sunset_swept,
+            GamingTokenError::PoolHasPositions
+        );
+        require!(
+            stake_pool.total_staked == 0 && ctx.accounts.stake_vault.amount == 0,
+            GamingTokenError::PoolNotEmpty
+        );
+        require!(
+            ctx.remaining_accounts.len() == 3 * stake_pool.reward_slots.len(),
+            GamingTokenError::InvalidRewardSlot
+        );
+
+        let mint_key = stake_pool.mint;
+        let authority_key = stake_pool.authority;
+        let vault_seeds = &[
+            b"vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.vault_authority],
+        ];
+        let reward_vault_seeds = &[
+            b"reward_vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.reward_vault_authority],
+        ];
+        let authority = ctx.accounts.authority.to_account_info();
+        let token_program = &ctx.accounts.token_program;
+
+        let mut swept_rewards = sweep_and_close_vault(
+            &ctx.accounts.reward_vault,
+            &ctx.accounts.reward_mint,
+            Some(&ctx.accounts.authority_token_account),
+            &authority,
+            &ctx.accounts.reward_vault_authority,
+            &[&reward_vault_seeds[..]],
+            token_program,
+        )?;
+
+        if stake_pool.fee_vault != Pubkey::default() {
+            let fee_vault = ctx
+                .accounts
+                .fee_vault
+                .as_ref()
+                .ok_or(GamingTokenError::FeeVaultRequired)?;
+            swept_rewards += sweep_and_close_vault(
+                fee_vault,
+                &ctx.accounts.reward_mint,
+                Some(&ctx.accounts.authority_token_account),
+                &authority,
+                &ctx.accounts.reward_vault_authority,
+                &[&reward_vault_seeds[..]],
+                token_program,
+            )?;
+        }
+
+        for (slot, accounts) in stake_pool
+            .reward_slots
+            .iter()
+            .zip(ctx.remaining_accounts.chunks(3))
+        {
+            let vault = InterfaceAccount::<TokenAccount>::try_from(&accounts[0])?;
+            let slot_mint = InterfaceAccount::<Mint>::try_from(&accounts[1])?;
+            let destination = InterfaceAccount::<TokenAccount>::try_from(&accounts[2])?;
+            require_keys_eq!(vault.key(), slot.vault, GamingTokenError::InvalidRewardSlot);
+            require_keys_eq!(slot_mint.key(), slot.mint, GamingTokenError::InvalidRewardSlot);
+            require_keys_eq!(destination.mint, slot.mint, GamingTokenError::InvalidRewardSlot);
+            require_keys_eq!(destination.owner, authority_key, GamingTokenError::Unauthorized);
+
+            sweep_and_close_vault(
+                &vault,
+                &slot_mint,
+                Some(&destination),
+                &authority,
+                &ctx.accounts.reward_vault_authority,
+                &[&reward_vault_seeds[..]],
+                token_program,
+            )?;
+        }
+
+        sweep_and_close_vault(
+            &ctx.accounts.stake_vault,
+            &ctx.accounts.mint,
+            None,
+            &authority,
+            &ctx.accounts.vault_authority,
+            &[&vault_seeds[..]],
+            token_program,
+        )?;
+
+        let clock = Clock::get()?;
+        let closed_pool = &mut ctx.accounts.closed_pool;
+        closed_pool.mint = mint_key;
+        closed_pool.closed_at = clock.unix_timestamp;
+        closed_pool.bump = ctx.bumps.closed_pool;
+
+        let pool_key = ctx.accounts.stake_pool.key();
+        ctx.accounts
+            .registry_page
+            .entries
+            .iter_mut()
+            .find(|entry| entry.pool == pool_key)
+            .ok_or(GamingTokenError::PoolNotRegistered)?
+            .closed = true;
+
+        emit!(PoolClosed {
+            mint: mint_key,
+            authority: authority_key,
+            swept_rewards,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn stake_tokens(
+        ctx: Context<StakeTokens>,
+        position_index: u16,
+        amount: u64,
+        lock_period: i64, // Lock period in seconds
+        with_receipt: bool,
+        referrer: Option<Pubkey>,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let positions = &mut ctx.accounts.stake_positions;
+        let clock = Clock::get()?;
+
+        require!(!ctx.accounts.stake_pool.paused, GamingTokenError::PoolPaused);
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+        require!(lock_period > 0, GamingTokenError::InvalidLockPeriod);
+        require!(
+            lock_period >= ctx.accounts.stake_pool.min_lock_seconds,
+            GamingTokenError::LockTooShort
+        );
+        require!(
+            ctx.accounts.stake_pool.allows_lock(lock_period),
+            GamingTokenError::LockTooLong
+        );
+
+        // Only a first-time staker can be referred, once, and never by themselves
+        let first_stake = positions.owner == Pubkey::default();
+        match (referrer, ctx.accounts.referral.as_mut()) {
+            (Some(referrer), Some(referral)) => {
+                require!(first_stake, GamingTokenError::AlreadyStaked);
+                require_keys_neq!(
+                    referrer,
+                    ctx.accounts.authority.key(),
+                    GamingTokenError::SelfReferral
+                );
+                referral.referee = ctx.accounts.authority.key();
+                referral.referrer = referrer;
+                referral.mint = ctx.accounts.mint.key();
+                referral.created_at = clock.unix_timestamp;
+                referral.bump = ctx.bumps.referral;
+
+                emit!(ReferralRecorded {
+                    mint: referral.mint,
+                    referee: referral.referee,
+                    referrer,
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+            (None, None) => {}
+            _ => return err!(GamingTokenError::ReferralAccountsRequired),
+        }
+
+        if first_stake {
+            positions.owner = ctx.accounts.authority.key();
+            positions.mint = ctx.accounts.mint.key();
+            positions.reward_debt = 0;
+            positions.bump = ctx.bumps.stake_positions;
+        }
+        positions.open(position_index)?;
+
+        // Transfer tokens to stake vault
+        let vault_balance = ctx.accounts.stake_vault.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.user_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.stake_vault.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+        // A transfer-fee mint delivers less than was sent; only what arrived is
+        // staked, and it alone has to meet the minimum
+        ctx.accounts.stake_vault.reload()?;
+        let amount = ctx.accounts.stake_vault.amount - vault_balance;
+        require!(
+            amount >= ctx.accounts.stake_pool.min_stake_amount,
+            GamingTokenError::StakeBelowMinimum
+        );
+
+        stake_account.version = STAKE_ACCOUNT_VERSION;
+        stake_account.owner = ctx.accounts.authority.key();
+        stake_account.mint = ctx.accounts.mint.key();
+        stake_account.position_index = position_index;
+        stake_account.amount = amount;
+        stake_account.lock_until = clock
+            .unix_timestamp
+            .checked_add(lock_period)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        stake_account.lock_period = lock_period;
+        stake_account.created_at = clock.unix_timestamp;
+        stake_account.power_changed_at = clock.unix_timestamp;
+        stake_account.unbonding_since = None;
+        stake_account.available_at = 0;
+        stake_account.delegate = None;
+        stake_account.delegated_at = clock.unix_timestamp;
+        stake_account.previous_delegate = None;
+        stake_account.previous_delegated_at = clock.unix_timestamp;
+        stake_account.slash_pending = false;
+        stake_account.is_active = true;
+
+        // Locks that reach a tier get its boost fixed for the life of the position
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_account.warmup_until = stake_pool.warmup_end(clock.unix_timestamp)?;
+        let tier = stake_pool.tier_for(lock_period);
+        stake_account.boost_bps = tier.map_or(0, |index| stake_pool.lock_tiers[index].boost_bps);
+
+        // A new position earns from the current accumulator onwards
+        stake_pool.update_reward_per_token(clock.unix_timestamp)?;
+        stake_account.reward_per_token_paid = stake_pool.reward_per_token_stored;
+        stake_account.rewards_accrued = 0;
+        stake_pool.update_reward_slots(clock.unix_timestamp)?;
+        for (checkpoint, slot) in stake_account.slot_rewards.iter_mut().zip(&stake_pool.reward_slots) {
+            checkpoint.reward_per_token_paid = slot.reward_per_token_stored;
+            checkpoint.rewards_accrued = 0;
+        }
+        stake_account.last_reward_claim = clock.unix_timestamp;
+        stake_pool.add_stake(amount)?;
+        stake_pool.reweight(0, stake_account.weight())?;
+        stake_pool.open_position()?;
+
+        // A receipt NFT makes the position transferable: whoever holds it controls it
+        stake_account.receipt_mint = None;
+        if with_receipt {
+            let (Some(receipt_mint), Some(receipt_token_account), Some(receipt_authority)) = (
+                &ctx.accounts.receipt_mint,
+                &ctx.accounts.receipt_token_account,
+                &ctx.accounts.receipt_authority,
+            ) else {
+                return err!(GamingTokenError::ReceiptAccountsRequired);
+            };
+
+            let receipt_seeds = &[b"receipt_authority".as_ref(), &[ctx.bumps.receipt_authority]];
+            let signer = &[&receipt_seeds[..]];
+
+            let cpi_accounts = MintTo {
+                mint: receipt_mint.to_account_info(),
+                to: receipt_token_account.to_account_info(),
+                authority: receipt_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::mint_to(cpi_ctx, 1)?;
+
+            // Drop the mint authority so the supply stays at exactly one
+            let cpi_accounts = SetAuthority {
+                current_authority: receipt_authority.to_account_info(),
+                account_or_mint: receipt_mint.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::set_authority(cpi_ctx, AuthorityType::MintTokens, None)?;
+
+            stake_account.receipt_mint = Some(receipt_mint.key());
+        }
+
+        emit!(TokensStaked {
+            owner: stake_account.owner,
+            position_index,
+            receipt_mint: stake_account.receipt_mint,
+            amount,
+            tier: tier.map(|index| index as u8),
+            boost_bps: stake_account.boost_bps,
+            lock_until: stake_account.lock_until,
+            timestamp: stake_account.created_at,
+            mint: stake_account.mint,
+            pool: ctx.accounts.stake_pool.key(),
+            stake_account: stake_account.key(),
+        });
+
+        let stake_pool_key = ctx.accounts.stake_pool.key();
+        let staker_stats = &mut ctx.accounts.staker_stats;
+        staker_stats.touch(ctx.accounts.authority.key(), stake_pool_key, ctx.bumps.staker_stats, stake_account.created_at);
+        staker_stats.record_stake(amount, lock_period)?;
+
+        Ok(())
+    }
+
+    pub fn add_to_stake(
+        ctx: Context<AddToStake>,
+        amount: u64,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        require!(!ctx.accounts.stake_pool.paused, GamingTokenError::PoolPaused);
+        require!(
+            !ctx.accounts.stake_pool.emergency_rewards_freeze,
+            GamingTokenError::RewardsFrozen
+        );
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+        require!(
+            stake_account.unbonding_since.is_none(),
+            GamingTokenError::PositionUnbonding
+        );
+
+        // Settle rewards on the old balance so the top-up only earns from now
+        let reward_amount = update_rewards(&mut ctx.accounts.stake_pool, stake_account, clock.unix_timestamp)?;
+        stake_account.rewards_accrued = 0;
+
+        let reward_paid = reward_amount.min(ctx.accounts.reward_vault.amount);
+        let (reward_fee, reward_net) = ctx.accounts.stake_pool.split_claim_fee(reward_paid);
+        if reward_paid > 0 {
+            let mint_key = ctx.accounts.mint.key();
+            let reward_vault_seeds = &[
+                b"reward_vault_authority",
+                mint_key.as_ref(),
+                &[ctx.bumps.reward_vault_authority],
+            ];
+            let signer = &[&reward_vault_seeds[..]];
+
+            if reward_fee > 0 {
+                let fee_vault = ctx
+                    .accounts
+                    .fee_vault
+                    .as_ref()
+                    .ok_or(GamingTokenError::FeeVaultRequired)?;
+                let cpi_accounts = TransferChecked {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    mint: ctx.accounts.reward_mint.to_account_info(),
+                    to: fee_vault.to_account_info(),
+                    authority: ctx.accounts.reward_vault_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+                token_interface::transfer_checked(cpi_ctx, reward_fee, ctx.accounts.reward_mint.decimals)?;
+            }
+
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                mint: ctx.accounts.reward_mint.to_account_info(),
+                to: ctx.accounts.user_reward_token_account.to_account_info(),
+                authority: ctx.accounts.reward_vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::transfer_checked(cpi_ctx, reward_net, ctx.accounts.reward_mint.decimals)?;
+
+            emit!(RewardsClaimed {
+                owner: stake_account.owner,
+                position_index: stake_account.position_index,
+                gross: reward_paid,
+                fee: reward_fee,
+                net: reward_net,
+                referrer: None,
+                referral_bonus: 0,
+                timestamp: clock.unix_timestamp,
+                mint: stake_account.mint,
+                pool: ctx.accounts.stake_pool.key(),
+                stake_account: stake_account.key(),
+                since_last_claim: clock.unix_timestamp - stake_account.last_reward_claim,
+                achievement_bonus: 0,
+            });
+        }
+
+        if reward_paid < reward_amount {
+            emit!(RewardShortfall {
+                owner: stake_account.owner,
+                owed: reward_amount,
+                paid: reward_paid,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        // Transfer the top-up to the stake vault
+        let vault_balance = ctx.accounts.stake_vault.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.user_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.stake_vault.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+        ctx.accounts.stake_vault.reload()?;
+        let amount = ctx.accounts.stake_vault.amount - vault_balance;
+        // A position opened before the minimum was raised tops up past it,
+        // counting only what arrived after any transfer fee
+        require!(
+            stake_account.amount.saturating_add(amount) >= ctx.accounts.stake_pool.min_stake_amount,
+            GamingTokenError::StakeBelowMinimum
+        );
+
+        // The lock restarts with its original length unless it already runs longer
+        let old_amount = stake_account.amount;
+        let old_weight = stake_account.weight();
+        stake_account.amount = stake_account
+            .amount
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+
+        // The warm-up restarts for the whole position, not just the top-up:
+        // a position is either earning on all of its stake or on none of it
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let warmup_until = stake_pool.warmup_end(clock.unix_timestamp)?;
+        if warmup_until != 0 {
+            stake_account.warmup_until = warmup_until;
+        }
+        stake_pool.settle_rewards(reward_amount, reward_paid)?;
+        stake_pool.add_stake(amount)?;
+        stake_pool.reweight(old_weight, stake_account.weight())?;
+        let relocked_until = clock
+            .unix_timestamp
+            .checked_add(stake_account.lock_period)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        stake_account.lock_until = stake_account.lock_until.max(relocked_until);
+        stake_account.power_changed_at = clock.unix_timestamp;
+
+        emit!(StakeIncreased {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            old_amount,
+            new_amount: stake_account.amount,
+            lock_until: stake_account.lock_until,
+            timestamp: clock.unix_timestamp,
+        });
+
+        let stake_pool_key = ctx.accounts.stake_pool.key();
+        let staker_stats = &mut ctx.accounts.staker_stats;
+        staker_stats.touch(stake_account.owner, stake_pool_key, ctx.bumps.staker_stats, stake_account.created_at);
+        staker_stats.record_stake(amount, stake_account.lock_period)?;
+        staker_stats.record_rewards(reward_net, false)?;
+
+        Ok(())
+    }
+
+    pub fn split_position(
+        ctx: Context<SplitPosition>,
+        new_position_index: u16,
+        amount: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let stake_account = &mut ctx.accounts.stake_account;
+
+        require!(!stake_pool.paused, GamingTokenError::PoolPaused);
+        require!(
+            stake_account.unbonding_since.is_none(),
+            GamingTokenError::PositionUnbonding
+        );
+        require!(
+            amount > 0 && amount < stake_account.amount,
+            GamingTokenError::InvalidAmount
+        );
+        require!(
+            amount >= stake_pool.min_stake_amount
+                && stake_account.amount - amount >= stake_pool.min_stake_amount,
+            GamingTokenError::StakeBelowMinimum
+        );
+
+        // Rewards earned so far stay with the original position
+        update_rewards(stake_pool, stake_account, clock.unix_timestamp)?;
+        let old_weight = stake_account.weight();
+        stake_account.amount -= amount;
+
+        ctx.accounts.stake_positions.open(new_position_index)?;
+
+        // Same lock and boost, earning from the checkpoint just taken. It counts
+        // as a new position for voting so the split-off stake can't vote twice.
+        let new_stake_account = &mut ctx.accounts.new_stake_account;
+        new_stake_account.set_inner(StakeAccount {
+            version: STAKE_ACCOUNT_VERSION,
+            owner: stake_account.owner,
+            mint: stake_account.mint,
+            position_index: new_position_index,
+            amount,
+            lock_until: stake_account.lock_until,
+            lock_period: stake_account.lock_period,
+            created_at: clock.unix_timestamp,
+            last_reward_claim: stake_account.last_reward_claim,
+            reward_per_token_paid: stake_account.reward_per_token_paid,
+            boost_bps: stake_account.boost_bps,
+            receipt_mint: None,
+            rewards_accrued: 0,
+            reward_dust: 0,
+            slot_rewards: stake_account.slot_rewards.map(|checkpoint| SlotRewards {
+                reward_per_token_paid: checkpoint.reward_per_token_paid,
+                rewards_accrued: 0,
+                reward_dust: 0,
+            }),
+            unbonding_since: None,
+            available_at: 0,
+            delegate: stake_account.delegate,
+            delegated_at: clock.unix_timestamp,
+            previous_delegate: None,
+            previous_delegated_at: clock.unix_timestamp,
+            slash_pending: false,
+            is_active: true,
+            warmup_until: stake_account.warmup_until,
+            power_changed_at: clock.unix_timestamp,
+        });
+
+        let new_weight = stake_account
+            .weight()
+            .checked_add(new_stake_account.weight())
+            .ok_or(GamingTokenError::MathOverflow)?;
+        stake_pool.reweight(old_weight, new_weight)?;
+        stake_pool.open_position()?;
+
+        emit!(PositionSplit {
+            owner: stake_account.owner,
+            mint: stake_account.mint,
+            pool: stake_pool.key(),
+            stake_account: stake_account.key(),
+            position_index: stake_account.position_index,
+            new_stake_account: new_stake_account.key(),
+            new_position_index,
+            amount,
+            remaining: stake_account.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn merge_positions(ctx: Context<MergePositions>) -> Result<()> {
+        let clock = Clock::get()?;
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let stake_account = &mut ctx.accounts.stake_account;
+        let merged = &mut ctx.accounts.merged_stake_account;
+
+        require!(!stake_pool.paused, GamingTokenError::PoolPaused);
+        require!(
+            stake_account.unbonding_since.is_none() && merged.unbonding_since.is_none(),
+            GamingTokenError::PositionUnbonding
+        );
+
+        // Checkpoint both against the same accumulators so their balances add up
+        update_rewards(stake_pool, stake_account, clock.unix_timestamp)?;
+        update_rewards(stake_pool, merged, clock.unix_timestamp)?;
+        let old_weight = stake_account
+            .weight()
+            .checked_add(merged.weight())
+            .ok_or(GamingTokenError::MathOverflow)?;
+
+        stake_account.amount = stake_account
+            .amount
+            .checked_add(merged.amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        (stake_account.rewards_accrued, stake_account.reward_dust) = merge_reward(
+            (stake_account.rewards_accrued, stake_account.reward_dust),
+            (merged.rewards_accrued, merged.reward_dust),
+        )?;
+        for (checkpoint, other) in stake_account.slot_rewards.iter_mut().zip(&merged.slot_rewards) {
+            (checkpoint.rewards_accrued, checkpoint.reward_dust) = merge_reward(
+                (checkpoint.rewards_accrued, checkpoint.reward_dust),
+                (other.rewards_accrued, other.reward_dust),
+            )?;
+        }
+
+        // The later lock binds the whole position, at the lower of the two boosts
+        if merged.boost_bps < stake_account.boost_bps {
+            stake_account.boost_bps = merged.boost_bps;
+            stake_account.lock_period = merged.lock_period;
+        }
+        stake_account.lock_until = stake_account.lock_until.max(merged.lock_until);
+        // Still warming if either half was
+        stake_account.warmup_until = stake_account.warmup_until.max(merged.warmup_until);
+        stake_account.last_reward_claim = stake_account.last_reward_claim.max(merged.last_reward_claim);
+        // Counts as a new position for voting so neither half's stake votes twice
+        stake_account.created_at = clock.unix_timestamp;
+        stake_account.power_changed_at = clock.unix_timestamp;
+
+        stake_pool.reweight(old_weight, stake_account.weight())?;
+        stake_pool.close_position()?;
+        merged.is_active = false;
+        ctx.accounts.stake_positions.open_positions -= 1;
+
+        emit!(PositionsMerged {
+            owner: stake_account.owner,
+            mint: stake_account.mint,
+            pool: stake_pool.key(),
+            stake_account: stake_account.key(),
+            position_index: stake_account.position_index,
+            merged_stake_account: merged.key(),
+            merged_position_index: merged.position_index,
+            amount: stake_account.amount,
+            rewards_accrued: stake_account.rewards_accrued,
+            lock_until: stake_account.lock_until,
+            boost_bps: stake_account.boost_bps,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless: adds a warmed-up position to the pool weight so it
+    /// earns from the end of its warm-up rather than from its next checkpoint.
+    pub fn end_warmup(ctx: Context<EndWarmup>) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        require!(
+            stake_account.warmup_until != 0 && clock.unix_timestamp >= stake_account.warmup_until,
+            GamingTokenError::WarmupActive
+        );
+        let warmup_until = stake_account.warmup_until;
+        update_rewards(&mut ctx.accounts.stake_pool, stake_account, clock.unix_timestamp)?;
+
+        emit!(WarmupEnded {
+            owner: stake_account.owner,
+            mint: stake_account.mint,
+            pool: ctx.accounts.stake_pool.key(),
+            stake_account: stake_account.key(),
+            warmup_until,
+            weight: stake_account.weight(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn extend_lock(
+        ctx: Context<UpdateStakePosition>,
+        new_lock_until: i64,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        require!(
+            stake_account.unbonding_since.is_none(),
+            GamingTokenError::PositionUnbonding
+        );
+        require!(
+            new_lock_until > stake_account.lock_until && new_lock_until > clock.unix_timestamp,
+            GamingTokenError::InvalidLockPeriod
+        );
+        let lock_period = new_lock_until
+            .checked_sub(stake_account.created_at)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        require!(
+            ctx.accounts.stake_pool.allows_lock(lock_period),
+            GamingTokenError::LockTooLong
+        );
+
+        // Rewards so far are carried at the old weight before the tier changes
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        update_rewards(stake_pool, stake_account, clock.unix_timestamp)?;
+
+        let old_lock_until = stake_account.lock_until;
+        let old_weight = stake_account.weight();
+        let tier = stake_pool.tier_for(lock_period);
+        stake_account.lock_until = new_lock_until;
+        stake_account.lock_period = lock_period;
+        stake_account.power_changed_at = clock.unix_timestamp;
+        stake_account.boost_bps = tier.map_or(0, |index| stake_pool.lock_tiers[index].boost_bps);
+        stake_pool.reweight(old_weight, stake_account.weight())?;
+
+        emit!(LockExtended {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            old_lock_until,
+            new_lock_until,
+            tier: tier.map(|index| index as u8),
+            boost_bps: stake_account.boost_bps,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn unstake_tokens<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UnstakeTokens<'info>>,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+        let emergency = ctx.accounts.stake_pool.emergency_mode;
+        let sunset = ctx.accounts.stake_pool.is_sunset();
+        let frozen = ctx.accounts.stake_pool.emergency_rewards_freeze;
+
+        // Pausing never blocks this; emergency mode and a rewards freeze also
+        // waive the lock and cooldown, a sunset only the lock
+        require!(stake_account.is_active, GamingTokenError::StakeNotActive);
+        require!(
+            emergency || sunset || frozen || clock.unix_timestamp >= stake_account.lock_until,
+            GamingTokenError::TokensStillLocked
+        );
+        require!(
+            emergency || frozen || ctx.accounts.stake_pool.unstake_cooldown_seconds == 0,
+            GamingTokenError::CooldownRequired
+        );
+        require!(
+            stake_account.unbonding_since.is_none(),
+            GamingTokenError::PositionUnbonding
+        );
+
+        // Checkpoint everything the position has earned up to now
+        let reward_amount = update_rewards(&mut ctx.accounts.stake_pool, stake_account, clock.unix_timestamp)?;
+
+        // Principal comes back from the stake vault
+        let mint_key = ctx.accounts.mint.key();
+        let vault_seeds = &[
+            b"vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.vault_authority],
+        ];
+        let signer = &[&vault_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.stake_vault.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, stake_account.amount, ctx.accounts.mint.decimals)?;
+
+        // Rewards come from the reward vault; an underfunded vault pays what it can.
+        // In emergency mode nothing is paid and the owed rewards are written off.
+        // Frozen rewards are kept as reward debt for `claim_reward_debt`
+        let reward_paid = if emergency || frozen {
+            0
+        } else {
+            reward_amount.min(ctx.accounts.reward_vault.amount)
+        };
+        let (reward_fee, reward_net) = ctx.accounts.stake_pool.split_claim_fee(reward_paid);
+        if reward_paid > 0 {
+            let reward_vault_seeds = &[
+                b"reward_vault_authority",
+                mint_key.as_ref(),
+                &[ctx.bumps.reward_vault_authority],
+            ];
+            let signer = &[&reward_vault_seeds[..]];
+
+            if reward_fee > 0 {
+                let fee_vault = ctx
+                    .accounts
+                    .fee_vault
+                    .as_ref()
+                    .ok_or(GamingTokenError::FeeVaultRequired)?;
+                let cpi_accounts = TransferChecked {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    mint: ctx.accounts.reward_mint.to_account_info(),
+                    to: fee_vault.to_account_info(),
+                    authority: ctx.accounts.reward_vault_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+                token_interface::transfer_checked(cpi_ctx, reward_fee, ctx.accounts.reward_mint.decimals)?;
+            }
+
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                mint: ctx.accounts.reward_mint.to_account_info(),
+                to: ctx.accounts.user_reward_token_account.to_account_info(),
+                authority: ctx.accounts.reward_vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::transfer_checked(cpi_ctx, reward_net, ctx.accounts.reward_mint.decimals)?;
+        }
+
+        if !emergency && !frozen && reward_paid < reward_amount {
+            emit!(RewardShortfall {
+                owner: stake_account.owner,
+                owed: reward_amount,
+                paid: reward_paid,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let reward_debt = if frozen && !emergency { reward_amount } else { 0 };
+        stake_pool.settle_rewards(reward_amount - reward_debt, reward_paid)?;
+        stake_pool.remove_stake(stake_account.amount)?;
+        record_reward_debt(&mut ctx.accounts.stake_positions, reward_debt, clock.unix_timestamp)?;
+        stake_pool.reweight(stake_account.weight(), 0)?;
+
+        let total_amount = stake_account.amount.saturating_add(reward_net);
+        // Partner slots pay from their own vaults; whatever isn't collected here is forfeited
+        if !emergency {
+            pay_slot_rewards(
+                &mut ctx.accounts.stake_pool,
+                stake_account,
+                ctx.remaining_accounts,
+                &ctx.accounts.owner.key(),
+                &ctx.accounts.reward_vault_authority,
+                ctx.bumps.reward_vault_authority,
+                &ctx.accounts.token_program,
+            )?;
+        }
+        forfeit_slot_rewards(&mut ctx.accounts.stake_pool, stake_account)?;
+        if reward_paid > 0 {
+            ctx.accounts.reward_vault.reload()?;
+            ctx.accounts
+                .stake_pool
+                .check_reserve_low(ctx.accounts.reward_vault.amount, clock.unix_timestamp);
+        }
+        stake_account.is_active = false;
+        ctx.accounts.stake_positions.open_positions -= 1;
+        ctx.accounts.stake_pool.close_position()?;
+        burn_receipt(
+            stake_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.receipt_token_account,
+            &ctx.accounts.owner,
+            &ctx.accounts.token_program,
+        )?;
+
+        emit!(StakeAccountClosed {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            rent_refunded: stake_account.to_account_info().lamports(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        emit!(TokensUnstaked {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            principal: stake_account.amount,
+            reward: reward_net,
+            reward_fee,
+            total: total_amount,
+            timestamp: clock.unix_timestamp,
+            mint: stake_account.mint,
+            pool: ctx.accounts.stake_pool.key(),
+            stake_account: stake_account.key(),
+            remaining: 0,
+        });
+
+        let stake_pool_key = ctx.accounts.stake_pool.key();
+        let staker_stats = &mut ctx.accounts.staker_stats;
+        staker_stats.touch(stake_account.owner, stake_pool_key, ctx.bumps.staker_stats, stake_account.created_at);
+        staker_stats.record_unstake(stake_account.amount)?;
+        staker_stats.record_rewards(reward_net, false)?;
+
+        Ok(())
+    }
+
+    pub fn request_unstake(
+        ctx: Context<UpdateStakePosition>,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        require!(
+            stake_account.unbonding_since.is_none(),
+            GamingTokenError::PositionUnbonding
+        );
+        require!(
+            ctx.accounts.stake_pool.is_sunset() || clock.unix_timestamp >= stake_account.lock_until,
+            GamingTokenError::TokensStillLocked
+        );
+
+        // Freeze accrual: what is owed now is carried until withdrawal
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let accrued = update_rewards(stake_pool, stake_account, clock.unix_timestamp)?;
+        let old_weight = stake_account.weight();
+        stake_account.unbonding_since = Some(clock.unix_timestamp);
+        stake_account.available_at = clock
+            .unix_timestamp
+            .checked_add(stake_pool.unstake_cooldown_seconds)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        stake_pool.reweight(old_weight, 0)?;
+
+        emit!(UnstakeRequested {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            amount: stake_account.amount,
+            accrued_rewards: accrued,
+            available_at: stake_account.available_at,
+            timestamp: clock.unix_timestamp,
+            mint: stake_account.mint,
+            pool: ctx.accounts.stake_pool.key(),
+            stake_account: stake_account.key(),
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_unstake(
+        ctx: Context<UpdateStakePosition>,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        require!(
+            stake_account.unbonding_since.is_some(),
+            GamingTokenError::PositionNotUnbonding
+        );
+
+        // Accrual restarts now; rewards carried from the request stay owed
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        update_rewards(stake_pool, stake_account, clock.unix_timestamp)?;
+        stake_account.unbonding_since = None;
+        stake_account.available_at = 0;
+        stake_pool.reweight(0, stake_account.weight())?;
+
+        emit!(UnstakeCancelled {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            amount: stake_account.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn delegate_votes(
+        ctx: Context<UpdateStakePosition>,
+        delegate: Pubkey,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        // Delegating to yourself, or to the current delegate, changes nothing
+        if delegate == ctx.accounts.owner.key() || stake_account.delegate == Some(delegate) {
+            return Ok(());
+        }
+
+        stake_account.set_delegate(Some(delegate), clock.unix_timestamp);
+
+        emit!(VotesDelegated {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            delegate,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn revoke_delegation(
+        ctx: Context<UpdateStakePosition>,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        let delegate = stake_account
+            .delegate
+            .ok_or(GamingTokenError::PositionNotDelegated)?;
+        stake_account.set_delegate(None, clock.unix_timestamp);
+
+        emit!(DelegationRevoked {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            delegate,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_unstaked<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UnstakeTokens<'info>>,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+        let emergency = ctx.accounts.stake_pool.emergency_mode;
+        let frozen = ctx.accounts.stake_pool.emergency_rewards_freeze;
+
+        require!(
+            stake_account.unbonding_since.is_some(),
+            GamingTokenError::PositionNotUnbonding
+        );
+        require!(
+            emergency || frozen || clock.unix_timestamp >= stake_account.available_at,
+            GamingTokenError::CooldownActive
+        );
+
+        let reward_amount = update_rewards(&mut ctx.accounts.stake_pool, stake_account, clock.unix_timestamp)?;
+
+        let mint_key = ctx.accounts.mint.key();
+        let vault_seeds = &[
+            b"vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.vault_authority],
+        ];
+        let signer = &[&vault_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.stake_vault.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, stake_account.amount, ctx.accounts.mint.decimals)?;
+
+        // Frozen rewards are kept as reward debt for `claim_reward_debt`
+        let reward_paid = if emergency || frozen {
+            0
+        } else {
+            reward_amount.min(ctx.accounts.reward_vault.amount)
+        };
+        let (reward_fee, reward_net) = ctx.accounts.stake_pool.split_claim_fee(reward_paid);
+        if reward_paid > 0 {
+            let reward_vault_seeds = &[
+                b"reward_vault_authority",
+                mint_key.as_ref(),
+                &[ctx.bumps.reward_vault_authority],
+            ];
+            let signer = &[&reward_vault_seeds[..]];
+
+            if reward_fee > 0 {
+                let fee_vault = ctx
+                    .accounts
+                    .fee_vault
+                    .as_ref()
+                    .ok_or(GamingTokenError::FeeVaultRequired)?;
+                let cpi_accounts = TransferChecked {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    mint: ctx.accounts.reward_mint.to_account_info(),
+                    to: fee_vault.to_account_info(),
+                    authority: ctx.accounts.reward_vault_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+                token_interface::transfer_checked(cpi_ctx, reward_fee, ctx.accounts.reward_mint.decimals)?;
+            }
+
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                mint: ctx.accounts.reward_mint.to_account_info(),
+                to: ctx.accounts.user_reward_token_account.to_account_info(),
+                authority: ctx.accounts.reward_vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::transfer_checked(cpi_ctx, reward_net, ctx.accounts.reward_mint.decimals)?;
+        }
+
+        if !emergency && !frozen && reward_paid < reward_amount {
+            emit!(RewardShortfall {
+                owner: stake_account.owner,
+                owed: reward_amount,
+                paid: reward_paid,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        // Accrual was already stopped at the request
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let reward_debt = if frozen && !emergency { reward_amount } else { 0 };
+        stake_pool.settle_rewards(reward_amount - reward_debt, reward_paid)?;
+        stake_pool.remove_stake(stake_account.amount)?;
+        record_reward_debt(&mut ctx.accounts.stake_positions, reward_debt, clock.unix_timestamp)?;
+
+        // Partner slots pay from their own vaults; whatever isn't collected here is forfeited
+        if !emergency {
+            pay_slot_rewards(
+                &mut ctx.accounts.stake_pool,
+                stake_account,
+                ctx.remaining_accounts,
+                &ctx.accounts.owner.key(),
+                &ctx.accounts.reward_vault_authority,
+                ctx.bumps.reward_vault_authority,
+                &ctx.accounts.token_program,
+            )?;
+        }
+        forfeit_slot_rewards(&mut ctx.accounts.stake_pool, stake_account)?;
+        if reward_paid > 0 {
+            ctx.accounts.reward_vault.reload()?;
+            ctx.accounts
+                .stake_pool
+                .check_reserve_low(ctx.accounts.reward_vault.amount, clock.unix_timestamp);
+        }
+        stake_account.is_active = false;
+        ctx.accounts.stake_positions.open_positions -= 1;
+        ctx.accounts.stake_pool.close_position()?;
+        burn_receipt(
+            stake_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.receipt_token_account,
+            &ctx.accounts.owner,
+            &ctx.accounts.token_program,
+        )?;
+
+        emit!(StakeAccountClosed {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            rent_refunded: stake_account.to_account_info().lamports(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        emit!(UnstakeWithdrawn {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            principal: stake_account.amount,
+            reward: reward_net,
+            reward_fee,
+            timestamp: clock.unix_timestamp,
+            mint: stake_account.mint,
+            pool: ctx.accounts.stake_pool.key(),
+            stake_account: stake_account.key(),
+        });
+
+        let stake_pool_key = ctx.accounts.stake_pool.key();
+        let staker_stats = &mut ctx.accounts.staker_stats;
+        staker_stats.touch(stake_account.owner, stake_pool_key, ctx.bumps.staker_stats, stake_account.created_at);
+        staker_stats.record_unstake(stake_account.amount)?;
+        staker_stats.record_rewards(reward_net, false)?;
+
+        Ok(())
+    }
+
+    pub fn early_unstake(
+        ctx: Context<UnstakeTokens>,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        // Emergency exits go through unstake_tokens without a penalty
+        require!(
+            !ctx.accounts.stake_pool.emergency_mode,
+            GamingTokenError::EmergencyModeActive
+        );
+        require!(
+            clock.unix_timestamp < stake_account.lock_until,
+            GamingTokenError::LockExpired
+        );
+
+        // The penalty shrinks linearly as the lock runs down
+        let remaining = (stake_account.lock_until - clock.unix_timestamp) as u128;
+        let lock_period = stake_account.lock_period.max(1) as u128;
+        let penalty = (stake_account.amount as u128
+            * ctx.accounts.stake_pool.early_unstake_penalty_bps as u128
+            * remaining.min(lock_period)
+            / (BPS_DENOMINATOR * lock_period)) as u64;
+        let refund = stake_account.amount - penalty;
+
+        let mint_key = ctx.accounts.mint.key();
+        let vault_seeds = &[
+            b"vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.vault_authority],
+        ];
+        let signer = &[&vault_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.stake_vault.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, refund, ctx.accounts.mint.decimals)?;
+
+        // Penalties fund the rewards of the stakers who stay
+        let mut penalty_received = 0;
+        if penalty > 0 {
+            let vault_balance = ctx.accounts.reward_vault.amount;
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.stake_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.reward_vault.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::transfer_checked(cpi_ctx, penalty, ctx.accounts.mint.decimals)?;
+
+            ctx.accounts.reward_vault.reload()?;
+            penalty_received = ctx.accounts.reward_vault.amount - vault_balance;
+        }
+
+        // Unclaimed rewards are forfeited
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        let forfeited_reward = update_rewards(stake_pool, stake_account, clock.unix_timestamp)?;
+        stake_pool.settle_rewards(forfeited_reward, 0)?;
+        stake_pool.remove_stake(stake_account.amount)?;
+        stake_pool.reweight(stake_account.weight(), 0)?;
+        // The exiting weight is already gone, so none of the penalty comes back
+        let redistributed = stake_pool.distribute_now(penalty_received, 0, clock.unix_timestamp)?;
+        forfeit_slot_rewards(stake_pool, stake_account)?;
+        stake_account.is_active = false;
+        ctx.accounts.stake_positions.open_positions -= 1;
+        ctx.accounts.stake_pool.close_position()?;
+        burn_receipt(
+            stake_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.receipt_token_account,
+            &ctx.accounts.owner,
+            &ctx.accounts.token_program,
+        )?;
+
+        emit!(StakeAccountClosed {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            rent_refunded: stake_account.to_account_info().lamports(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        emit!(EarlyUnstaked {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            principal: stake_account.amount,
+            penalty,
+            redistributed,
+            refund,
+            forfeited_reward,
+            lock_until: stake_account.lock_until,
+            timestamp: clock.unix_timestamp,
+        });
+
+        let stake_pool_key = ctx.accounts.stake_pool.key();
+        let staker_stats = &mut ctx.accounts.staker_stats;
+        staker_stats.touch(stake_account.owner, stake_pool_key, ctx.bumps.staker_stats, stake_account.created_at);
+        staker_stats.record_unstake(stake_account.amount)?;
+
+        Ok(())
+    }
+
+    pub fn partial_unstake(
+        ctx: Context<PartialUnstake>,
+        amount: u64,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        require!(
+            !ctx.accounts.stake_pool.emergency_mode,
+            GamingTokenError::EmergencyModeActive
+        );
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+        require!(amount <= stake_account.amount, GamingTokenError::InsufficientFunds);
+        // Withdraw everything or leave a position at least the minimum
+        let remaining = stake_account.amount - amount;
+        require!(
+            remaining == 0 || remaining >= ctx.accounts.stake_pool.min_stake_amount,
+            GamingTokenError::RemainderBelowMinimum
+        );
+        require!(
+            stake_account.unbonding_since.is_none(),
+            GamingTokenError::PositionUnbonding
+        );
+        require!(
+            ctx.accounts.stake_pool.is_sunset()
+                || ctx.accounts.stake_pool.emergency_rewards_freeze
+                || clock.unix_timestamp >= stake_account.lock_until,
+            GamingTokenError::TokensStillLocked
+        );
+
+        // Settle rewards accrued so far before the balance shrinks
+        let reward_amount = update_rewards(&mut ctx.accounts.stake_pool, stake_account, clock.unix_timestamp)?;
+        let frozen = ctx.accounts.stake_pool.emergency_rewards_freeze;
+        stake_account.rewards_accrued = 0;
+
+        let mint_key = ctx.accounts.mint.key();
+        let vault_seeds = &[
+            b"vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.vault_authority],
+        ];
+        let signer = &[&vault_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.stake_vault.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+        // Frozen rewards stay on the position, or become reward debt if it closes
+        let reward_paid = if frozen {
+            0
+        } else {
+            reward_amount.min(ctx.accounts.reward_vault.amount)
+        };
+        let (reward_fee, reward_net) = ctx.accounts.stake_pool.split_claim_fee(reward_paid);
+        if reward_paid > 0 {
+            let reward_vault_seeds = &[
+                b"reward_vault_authority",
+                mint_key.as_ref(),
+                &[ctx.bumps.reward_vault_authority],
+            ];
+            let signer = &[&reward_vault_seeds[..]];
+
+            if reward_fee > 0 {
+                let fee_vault = ctx
+                    .accounts
+                    .fee_vault
+                    .as_ref()
+                    .ok_or(GamingTokenError::FeeVaultRequired)?;
+                let cpi_accounts = TransferChecked {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    mint: ctx.accounts.reward_mint.to_account_info(),
+                    to: fee_vault.to_account_info(),
+                    authority: ctx.accounts.reward_vault_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+                token_interface::transfer_checked(cpi_ctx, reward_fee, ctx.accounts.reward_mint.decimals)?;
+            }
+
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                mint: ctx.accounts.reward_mint.to_account_info(),
+                to: ctx.accounts.user_reward_token_account.to_account_info(),
+                authority: ctx.accounts.reward_vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::transfer_checked(cpi_ctx, reward_net, ctx.accounts.reward_mint.decimals)?;
+        }
+
+        if !frozen && reward_paid < reward_amount {
+            emit!(RewardShortfall {
+                owner: stake_account.owner,
+                owed: reward_amount,
+                paid: reward_paid,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        let reward_kept = if frozen { reward_amount } else { 0 };
+        stake_account.rewards_accrued = reward_kept;
+        let old_weight = stake_account.weight();
+        stake_account.amount -= amount;
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.settle_rewards(reward_amount - reward_kept, reward_paid)?;
+        stake_pool.remove_stake(amount)?;
+        stake_pool.reweight(old_weight, stake_account.weight())?;
+        if reward_paid > 0 {
+            ctx.accounts.reward_vault.reload()?;
+            ctx.accounts
+                .stake_pool
+                .check_reserve_low(ctx.accounts.reward_vault.amount, clock.unix_timestamp);
+        }
+
+        emit!(TokensUnstaked {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            principal: amount,
+            reward: reward_net,
+            reward_fee,
+            total: amount.saturating_add(reward_net),
+            timestamp: clock.unix_timestamp,
+            mint: stake_account.mint,
+            pool: ctx.accounts.stake_pool.key(),
+            stake_account: stake_account.key(),
+            remaining: stake_account.amount,
+        });
+
+        let stake_pool_key = ctx.accounts.stake_pool.key();
+        let staker_stats = &mut ctx.accounts.staker_stats;
+        staker_stats.touch(stake_account.owner, stake_pool_key, ctx.bumps.staker_stats, stake_account.created_at);
+        staker_stats.record_unstake(amount)?;
+        staker_stats.record_rewards(reward_net, false)?;
+
+        // Withdrawing everything closes the position just like unstake_tokens
+        if stake_account.amount == 0 {
+            record_reward_debt(&mut ctx.accounts.stake_positions, reward_kept, clock.unix_timestamp)?;
+            forfeit_slot_rewards(&mut ctx.accounts.stake_pool, stake_account)?;
+            stake_account.is_active = false;
+            ctx.accounts.stake_positions.open_positions -= 1;
+            ctx.accounts.stake_pool.close_position()?;
+            emit!(StakeAccountClosed {
+                owner: stake_account.owner,
+                position_index: stake_account.position_index,
+                rent_refunded: stake_account.to_account_info().lamports(),
+                timestamp: clock.unix_timestamp,
+            });
+            ctx.accounts
+                .stake_account
+                .close(ctx.accounts.owner.to_account_info())?;
+        }
+
+        Ok(())
+    }
+
+    /// Stakes without moving the tokens: `vault_authority` is approved as
+    /// delegate for `amount` on the owner's own token account, which is also
+    /// frozen when the mint's freeze authority is that PDA. The position earns
+    /// like any other; `validate_soft_stake` catches tokens moved away.
+    pub fn soft_stake(
+        ctx: Context<SoftStake>,
+        amount: u64,
+        lock_period: i64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let stake_pool = &mut ctx.accounts.stake_pool;
+
+        require!(!stake_pool.paused, GamingTokenError::PoolPaused);
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+        require!(amount >= stake_pool.min_stake_amount, GamingTokenError::StakeBelowMinimum);
+        require!(
+            amount <= ctx.accounts.token_account.amount,
+            GamingTokenError::InsufficientFunds
+        );
+        require!(lock_period > 0, GamingTokenError::InvalidLockPeriod);
+        require!(lock_period >= stake_pool.min_lock_seconds, GamingTokenError::LockTooShort);
+        require!(stake_pool.allows_lock(lock_period), GamingTokenError::LockTooLong);
+
+        let cpi_accounts = Approve {
+            to: ctx.accounts.token_account.to_account_info(),
+            delegate: ctx.accounts.vault_authority.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::approve(cpi_ctx, amount)?;
+
+        // Freezing is only possible when the program holds the freeze authority
+        let vault_authority = ctx.accounts.vault_authority.key();
+        let frozen = ctx.accounts.mint.freeze_authority == COption::Some(vault_authority);
+        if frozen {
+            let mint_key = ctx.accounts.mint.key();
+            let vault_seeds = &[
+                b"vault_authority",
+                mint_key.as_ref(),
+                &[ctx.bumps.vault_authority],
+            ];
+            let signer = &[&vault_seeds[..]];
+
+            let cpi_accounts = FreezeAccount {
+                account: ctx.accounts.token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::freeze_account(cpi_ctx)?;
+        }
+
+        stake_pool.update_reward_per_token(clock.unix_timestamp)?;
+        stake_pool.update_reward_slots(clock.unix_timestamp)?;
+        let tier = stake_pool.tier_for(lock_period);
+        let position = StakeAccount {
+            version: STAKE_ACCOUNT_VERSION,
+            owner: ctx.accounts.owner.key(),
+            mint: stake_pool.mint,
+            position_index: 0,
+            amount,
+            lock_until: clock
+                .unix_timestamp
+                .checked_add(lock_period)
+                .ok_or(GamingTokenError::MathOverflow)?,
+            lock_period,
+            created_at: clock.unix_timestamp,
+            last_reward_claim: clock.unix_timestamp,
+            reward_per_token_paid: stake_pool.reward_per_token_stored,
+            boost_bps: tier.map_or(0, |index| stake_pool.lock_tiers[index].boost_bps),
+            receipt_mint: None,
+            rewards_accrued: 0,
+            reward_dust: 0,
+            slot_rewards: std::array::from_fn(|index| SlotRewards {
+                reward_per_token_paid: stake_pool
+                    .reward_slots
+                    .get(index)
+                    .map_or(0, |slot| slot.reward_per_token_stored),
+                rewards_accrued: 0,
+                reward_dust: 0,
+            }),
+            unbonding_since: None,
+            available_at: 0,
+            delegate: None,
+            delegated_at: clock.unix_timestamp,
+            previous_delegate: None,
+            previous_delegated_at: clock.unix_timestamp,
+            slash_pending: false,
+            is_active: true,
+            warmup_until: stake_pool.warmup_end(clock.unix_timestamp)?,
+            power_changed_at: clock.unix_timestamp,
+        };
+        stake_pool.total_soft_staked = stake_pool
+            .total_soft_staked
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        stake_pool.reweight(0, position.weight())?;
+        stake_pool.open_position()?;
+
+        let soft_position = &mut ctx.accounts.soft_position;
+        soft_position.set_inner(SoftStakePosition {
+            position,
+            token_account: ctx.accounts.token_account.key(),
+            frozen,
+            bump: ctx.bumps.soft_position,
+        });
+
+        emit!(SoftStaked {
+            owner: soft_position.position.owner,
+            mint: soft_position.position.mint,
+            soft_position: soft_position.key(),
+            token_account: soft_position.token_account,
+            amount,
+            tier: tier.map(|index| index as u8),
+            boost_bps: soft_position.position.boost_bps,
+            lock_until: soft_position.position.lock_until,
+            frozen,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless crank: if the owner moved delegated tokens away or
+    /// revoked the delegation, the position shrinks to what is still backed
+    /// and forfeits the rewards it has accrued.
+    pub fn validate_soft_stake(ctx: Context<ValidateSoftStake>) -> Result<()> {
+        let clock = Clock::get()?;
+        settle_soft_shortfall(
+            &mut ctx.accounts.stake_pool,
+            &mut ctx.accounts.soft_position,
+            &ctx.accounts.token_account,
+            &ctx.accounts.vault_authority.key(),
+            clock.unix_timestamp,
+        )?;
+        Ok(())
+    }
+
+    /// Ends a soft stake once the lock is over, or at once if nothing backs
+    /// it any more: pays the rewards still backed, thaws the account if it
+    /// was frozen and revokes the delegation. Partner-slot rewards are forfeited.
+    pub fn soft_unstake(ctx: Context<SoftUnstake>) -> Result<()> {
+        let clock = Clock::get()?;
+
+        // Rewards can't be set aside as debt here, and the tokens never left
+        // the wallet, so a freeze simply waits
+        require!(
+            !ctx.accounts.stake_pool.emergency_rewards_freeze,
+            GamingTokenError::RewardsFrozen
+        );
+        settle_soft_shortfall(
+            &mut ctx.accounts.stake_pool,
+            &mut ctx.accounts.soft_position,
+            &ctx.accounts.token_account,
+            &ctx.accounts.vault_authority.key(),
+            clock.unix_timestamp,
+        )?;
+
+        let emergency = ctx.accounts.stake_pool.emergency_mode;
+        let position = &mut ctx.accounts.soft_position.position;
+        require!(
+            emergency || position.amount == 0 || clock.unix_timestamp >= position.lock_until,
+            GamingTokenError::TokensStillLocked
+        );
+
+        let reward_amount = update_rewards(&mut ctx.accounts.stake_pool, position, clock.unix_timestamp)?;
+        let reward_paid = if emergency {
+            0
+        } else {
+            reward_amount.min(ctx.accounts.reward_vault.amount)
+        };
+        let (reward_fee, reward_net) = ctx.accounts.stake_pool.split_claim_fee(reward_paid);
+        let mint_key = ctx.accounts.mint.key();
+        if reward_paid > 0 {
+            let reward_vault_seeds = &[
+                b"reward_vault_authority",
+                mint_key.as_ref(),
+                &[ctx.bumps.reward_vault_authority],
+            ];
+            let signer = &[&reward_vault_seeds[..]];
+
+            if reward_fee > 0 {
+                let fee_vault = ctx
+                    .accounts
+                    .fee_vault
+                    .as_ref()
+                    .ok_or(GamingTokenError::FeeVaultRequired)?;
+                let cpi_accounts = TransferChecked {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    mint: ctx.accounts.reward_mint.to_account_info(),
+                    to: fee_vault.to_account_info(),
+                    authority: ctx.accounts.reward_vault_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+                token_interface::transfer_checked(cpi_ctx, reward_fee, ctx.accounts.reward_mint.decimals)?;
+            }
+
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                mint: ctx.accounts.reward_mint.to_account_info(),
+                to: ctx.accounts.user_reward_token_account.to_account_info(),
+                authority: ctx.accounts.reward_vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::transfer_checked(cpi_ctx, reward_net, ctx.accounts.reward_mint.decimals)?;
+        }
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.settle_rewards(reward_amount, reward_paid)?;
+        forfeit_slot_rewards(stake_pool, position)?;
+        stake_pool.reweight(position.weight(), 0)?;
+        stake_pool.total_soft_staked -= position.amount;
+        stake_pool.close_position()?;
+        position.is_active = false;
+        if reward_paid > 0 {
+            ctx.accounts.reward_vault.reload()?;
+            ctx.accounts
+                .stake_pool
+                .check_reserve_low(ctx.accounts.reward_vault.amount, clock.unix_timestamp);
+        }
+
+        if ctx.accounts.soft_position.frozen {
+            let vault_seeds = &[
+                b"vault_authority",
+                mint_key.as_ref(),
+                &[ctx.bumps.vault_authority],
+            ];
+            let signer = &[&vault_seeds[..]];
+
+            let cpi_accounts = ThawAccount {
+                account: ctx.accounts.token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::thaw_account(cpi_ctx)?;
+        }
+
+        // Only drop a delegation that is still ours
+        if ctx.accounts.token_account.delegate == COption::Some(ctx.accounts.vault_authority.key()) {
+            let cpi_accounts = Revoke {
+                source: ctx.accounts.token_account.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            token_interface::revoke(cpi_ctx)?;
+        }
+
+        let soft_position = &ctx.accounts.soft_position;
+        emit!(SoftUnstaked {
+            owner: soft_position.position.owner,
+            mint: soft_position.position.mint,
+            soft_position: soft_position.key(),
+            token_account: soft_position.token_account,
+            amount: soft_position.position.amount,
+            reward: reward_net,
+            reward_fee,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn compound_rewards(
+        ctx: Context<CompoundRewards>,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        require!(!ctx.accounts.stake_pool.paused, GamingTokenError::PoolPaused);
+        require!(
+            !ctx.accounts.stake_pool.emergency_rewards_freeze,
+            GamingTokenError::RewardsFrozen
+        );
+        require!(
+            stake_account.unbonding_since.is_none(),
+            GamingTokenError::PositionUnbonding
+        );
+
+        // Only what the reward vault covers is compounded; the rest stays owed
+        let reward_amount = update_rewards(&mut ctx.accounts.stake_pool, stake_account, clock.unix_timestamp)?;
+        let compounded = reward_amount.min(ctx.accounts.reward_vault.amount);
+        require!(compounded > 0, GamingTokenError::NothingToClaim);
+        stake_account.rewards_accrued = reward_amount - compounded;
+
+        let mint_key = ctx.accounts.mint.key();
+        let reward_vault_seeds = &[
+            b"reward_vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.reward_vault_authority],
+        ];
+        let signer = &[&reward_vault_seeds[..]];
+
+        let (fee, net) = ctx.accounts.stake_pool.split_claim_fee(compounded);
+        // Solitaire achievement holders compound a bonus out of uncommitted reserve
+        let achievement_bps = achievement_boost_bps(
+            &ctx.accounts.stake_pool,
+            &ctx.accounts.player_stats,
+            &ctx.accounts.owner.key(),
+        )?;
+        let achievement_bonus = ctx.accounts.stake_pool.reserve_bonus(
+            compounded,
+            achievement_bps,
+            ctx.accounts.reward_vault.amount - compounded,
+            clock.unix_timestamp,
+        );
+        if fee > 0 {
+            let fee_vault = ctx
+                .accounts
+                .fee_vault
+                .as_ref()
+                .ok_or(GamingTokenError::FeeVaultRequired)?;
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: fee_vault.to_account_info(),
+                authority: ctx.accounts.reward_vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::transfer_checked(cpi_ctx, fee, ctx.accounts.mint.decimals)?;
+        }
+
+        let vault_balance = ctx.accounts.stake_vault.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.stake_vault.to_account_info(),
+            authority: ctx.accounts.reward_vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, net + achievement_bonus, ctx.accounts.mint.decimals)?;
+
+        // Rewards are settled at what left the reward vault, principal at what arrived
+        ctx.accounts.stake_vault.reload()?;
+        let received = ctx.accounts.stake_vault.amount - vault_balance;
+
+        // The lock is left exactly as it was
+        let old_weight = stake_account.weight();
+        stake_account.amount = stake_account
+            .amount
+            .checked_add(received)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        stake_account.power_changed_at = clock.unix_timestamp;
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.settle_rewards(compounded, compounded)?;
+        stake_pool.settle_rewards(0, achievement_bonus)?;
+        stake_pool.add_stake(received)?;
+        stake_pool.reweight(old_weight, stake_account.weight())?;
+        ctx.accounts.reward_vault.reload()?;
+        ctx.accounts
+            .stake_pool
+            .check_reserve_low(ctx.accounts.reward_vault.amount, clock.unix_timestamp);
+
+        emit!(RewardsCompounded {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            amount: net,
+            fee,
+            new_principal: stake_account.amount,
+            timestamp: clock.unix_timestamp,
+            achievement_bonus,
+        });
+
+        let stake_pool_key = ctx.accounts.stake_pool.key();
+        let staker_stats = &mut ctx.accounts.staker_stats;
+        staker_stats.touch(stake_account.owner, stake_pool_key, ctx.bumps.staker_stats, stake_account.created_at);
+        staker_stats.record_rewards(net + achievement_bonus, false)?;
+
+        Ok(())
+    }
+
+    /// Opens the pool's auto-compounding vault: one aggregated position, owned
+    /// by the vault PDA, that depositors hold through its share mint.
+    pub fn create_compound_vault(ctx: Context<CreateCompoundVault>) -> Result<()> {
+        let clock = Clock::get()?;
+        let compound_vault = &mut ctx.accounts.compound_vault;
+        let stake_pool = &mut ctx.accounts.stake_pool;
+
+        compound_vault.mint = ctx.accounts.mint.key();
+        compound_vault.stake_pool = stake_pool.key();
+        compound_vault.share_mint = ctx.accounts.share_mint.key();
+        compound_vault.total_shares = 0;
+        compound_vault.last_harvest = clock.unix_timestamp;
+        compound_vault.created_at = clock.unix_timestamp;
+        compound_vault.bump = ctx.bumps.compound_vault;
+
+        // Unlocked and unboosted, so depositors can leave whenever a plain
+        // position past its lock could
+        stake_pool.update_reward_per_token(clock.unix_timestamp)?;
+        stake_pool.update_reward_slots(clock.unix_timestamp)?;
+        ctx.accounts.vault_position.set_inner(StakeAccount {
+            version: STAKE_ACCOUNT_VERSION,
+            owner: compound_vault.key(),
+            mint: compound_vault.mint,
+            position_index: 0,
+            amount: 0,
+            lock_until: clock.unix_timestamp,
+            lock_period: 0,
+            created_at: clock.unix_timestamp,
+            last_reward_claim: clock.unix_timestamp,
+            reward_per_token_paid: stake_pool.reward_per_token_stored,
+            boost_bps: 0,
+            receipt_mint: None,
+            rewards_accrued: 0,
+            reward_dust: 0,
+            slot_rewards: std::array::from_fn(|index| SlotRewards {
+                reward_per_token_paid: stake_pool
+                    .reward_slots
+                    .get(index)
+                    .map_or(0, |slot| slot.reward_per_token_stored),
+                rewards_accrued: 0,
+                reward_dust: 0,
+            }),
+            unbonding_since: None,
+            available_at: 0,
+            delegate: None,
+            delegated_at: clock.unix_timestamp,
+            previous_delegate: None,
+            previous_delegated_at: clock.unix_timestamp,
+            slash_pending: false,
+            is_active: true,
+            warmup_until: stake_pool.warmup_end(clock.unix_timestamp)?,
+            power_changed_at: clock.unix_timestamp,
+        });
+        stake_pool.open_position()?;
+
+        emit!(CompoundVaultCreated {
+            mint: compound_vault.mint,
+            pool: compound_vault.stake_pool,
+            compound_vault: compound_vault.key(),
+            share_mint: compound_vault.share_mint,
+            vault_position: ctx.accounts.vault_position.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Stakes `amount` through the compound vault for shares at the current
+    /// price, after compounding what the vault has earned so far.
+    pub fn deposit_to_vault(
+        ctx: Context<DepositToVault>,
+        amount: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(!ctx.accounts.stake_pool.paused, GamingTokenError::PoolPaused);
+        require!(
+            !ctx.accounts.stake_pool.emergency_rewards_freeze,
+            GamingTokenError::RewardsFrozen
+        );
+        require!(
+            !ctx.accounts.stake_pool.emergency_mode,
+            GamingTokenError::EmergencyModeActive
+        );
+        require!(amount > 0, GamingTokenError::InvalidAmount);
+
+        // Earnings so far belong to the existing shares
+        compound_vault_position(
+            &mut ctx.accounts.stake_pool,
+            &mut ctx.accounts.vault_position,
+            &ctx.accounts.reward_vault,
+            &mut ctx.accounts.stake_vault,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.reward_vault_authority,
+            ctx.bumps.reward_vault_authority,
+            &ctx.accounts.token_program,
+            clock.unix_timestamp,
+        )?;
+
+        let vault_balance = ctx.accounts.stake_vault.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.depositor_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.stake_vault.to_account_info(),
+            authority: ctx.accounts.depositor.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+        ctx.accounts.stake_vault.reload()?;
+        let received = ctx.accounts.stake_vault.amount - vault_balance;
+
+        // Shares round down, so a deposit never lowers the share price
+        let compound_vault = &mut ctx.accounts.compound_vault;
+        let vault_position = &mut ctx.accounts.vault_position;
+        let shares = if compound_vault.total_shares == 0 {
+            require!(
+                received > COMPOUND_VAULT_DEAD_SHARES,
+                GamingTokenError::SharesTooSmall
+            );
+            compound_vault.total_shares = COMPOUND_VAULT_DEAD_SHARES;
+            received - COMPOUND_VAULT_DEAD_SHARES
+        } else {
+            vault_shares(received, compound_vault.total_shares, vault_position.amount)?
+        };
+        require!(shares > 0, GamingTokenError::SharesTooSmall);
+
+        // Deposits join the running position without restarting its warm-up
+        let old_weight = vault_position.weight();
+        vault_position.amount = vault_position
+            .amount
+            .checked_add(received)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        compound_vault.total_shares = compound_vault
+            .total_shares
+            .checked_add(shares)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.add_stake(received)?;
+        stake_pool.reweight(old_weight, vault_position.weight())?;
+
+        let mint_key = ctx.accounts.mint.key();
+        let compound_vault_seeds = &[
+            b"compound_vault",
+            mint_key.as_ref(),
+            &[compound_vault.bump],
+        ];
+        let signer = &[&compound_vault_seeds[..]];
+
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            to: ctx.accounts.depositor_share_account.to_account_info(),
+            authority: compound_vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::mint_to(cpi_ctx, shares)?;
+
+        emit!(VaultDeposited {
+            mint: compound_vault.mint,
+            depositor: ctx.accounts.depositor.key(),
+            amount: received,
+            shares,
+            total_shares: compound_vault.total_shares,
+            total_underlying: vault_position.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Burns `shares` for their part of the vault's principal, after
+    /// compounding what the vault has earned so far.
+    pub fn withdraw_from_vault(
+        ctx: Context<WithdrawFromVault>,
+        shares: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(shares > 0, GamingTokenError::InvalidAmount);
+
+        // Nothing is paid out in an emergency or a freeze; principal still leaves
+        if !ctx.accounts.stake_pool.emergency_mode && !ctx.accounts.stake_pool.emergency_rewards_freeze {
+            compound_vault_position(
+                &mut ctx.accounts.stake_pool,
+                &mut ctx.accounts.vault_position,
+                &ctx.accounts.reward_vault,
+                &mut ctx.accounts.stake_vault,
+                &ctx.accounts.fee_vault,
+                &ctx.accounts.mint,
+                &ctx.accounts.reward_vault_authority,
+                ctx.bumps.reward_vault_authority,
+                &ctx.accounts.token_program,
+                clock.unix_timestamp,
+            )?;
+        } else {
+            update_rewards(&mut ctx.accounts.stake_pool, &mut ctx.accounts.vault_position, clock.unix_timestamp)?;
+        }
+
+        // Rounds down, so a withdrawal never lowers the share price
+        let compound_vault = &mut ctx.accounts.compound_vault;
+        let vault_position = &mut ctx.accounts.vault_position;
+        let amount = vault_shares(vault_position.amount, shares, compound_vault.total_shares)?;
+        require!(amount > 0, GamingTokenError::SharesTooSmall);
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            from: ctx.accounts.withdrawer_share_account.to_account_info(),
+            authority: ctx.accounts.withdrawer.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::burn(cpi_ctx, shares)?;
+
+        let mint_key = ctx.accounts.mint.key();
+        let vault_seeds = &[
+            b"vault_authority",
+            mint_key.as_ref(),
+            &[ctx.bumps.vault_authority],
+        ];
+        let signer = &[&vault_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.stake_vault.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.withdrawer_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+        let old_weight = vault_position.weight();
+        vault_position.amount -= amount;
+        compound_vault.total_shares -= shares;
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.remove_stake(amount)?;
+        stake_pool.reweight(old_weight, vault_position.weight())?;
+
+        emit!(VaultWithdrawn {
+            mint: compound_vault.mint,
+            withdrawer: ctx.accounts.withdrawer.key(),
+            amount,
+            shares,
+            total_shares: compound_vault.total_shares,
+            total_underlying: vault_position.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless crank: compounds the vault position's rewards into its
+    /// principal, raising the share price.
+    pub fn harvest(ctx: Context<HarvestVault>) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(
+            !ctx.accounts.stake_pool.emergency_mode,
+            GamingTokenError::EmergencyModeActive
+        );
+        require!(
+            !ctx.accounts.stake_pool.emergency_rewards_freeze,
+            GamingTokenError::RewardsFrozen
+        );
+
+        let (compounded, fee) = compound_vault_position(
+            &mut ctx.accounts.stake_pool,
+            &mut ctx.accounts.vault_position,
+            &ctx.accounts.reward_vault,
+            &mut ctx.accounts.stake_vault,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.reward_vault_authority,
+            ctx.bumps.reward_vault_authority,
+            &ctx.accounts.token_program,
+            clock.unix_timestamp,
+        )?;
+        require!(compounded > 0, GamingTokenError::NothingToClaim);
+
+        let compound_vault = &mut ctx.accounts.compound_vault;
+        compound_vault.last_harvest = clock.unix_timestamp;
+
+        emit!(VaultHarvested {
+            mint: compound_vault.mint,
+            compounded,
+            fee,
+            total_shares: compound_vault.total_shares,
+            total_underlying: ctx.accounts.vault_position.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_rewards<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimRewards<'info>>,
+    ) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        require!(stake_account.is_active, GamingTokenError::StakeNotActive);
+        require!(
+            !ctx.accounts.stake_pool.emergency_rewards_freeze,
+            GamingTokenError::RewardsFrozen
+        );
+        require!(
+            !ctx.accounts.stake_pool.emergency_mode,
+            GamingTokenError::EmergencyModeActive
+        );
+        // Only explicit claims are rate limited; compounding and unstaking settle freely
+        let since_last_claim = clock.unix_timestamp - stake_account.last_reward_claim;
+        let min_claim_interval = ctx.accounts.stake_pool.min_claim_interval_seconds;
+        require!(
+            min_claim_interval == 0 || since_last_claim >= min_claim_interval,
+            GamingTokenError::ClaimTooSoon
+        );
+
+        // Checkpoint rewards earned since the last update
+        let reward_amount = update_rewards(&mut ctx.accounts.stake_pool, stake_account, clock.unix_timestamp)?;
+        stake_account.last_reward_claim = clock.unix_timestamp;
+
+        // Each reward token pays what its own vault holds; the rest stays owed
+        let reward_paid = reward_amount.min(ctx.accounts.reward_vault.amount);
+        let (reward_fee, reward_net) = ctx.accounts.stake_pool.split_claim_fee(reward_paid);
+        let mut reward_bonus = 0;
+        if reward_paid > 0 {
+            // Transfer rewards from reward vault
+            let mint_key = ctx.accounts.mint.key();
+            let reward_vault_seeds = &[
+                b"reward_vault_authority",
+                mint_key.as_ref(),
+                &[ctx.bumps.reward_vault_authority],
+            ];
+            let signer = &[&reward_vault_seeds[..]];
+
+            if reward_fee > 0 {
+                let fee_vault = ctx
+                    .accounts
+                    .fee_vault
+                    .as_ref()
+                    .ok_or(GamingTokenError::FeeVaultRequired)?;
+                let cpi_accounts = TransferChecked {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    mint: ctx.accounts.reward_mint.to_account_info(),
+                    to: fee_vault.to_account_info(),
+                    authority: ctx.accounts.reward_vault_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+                token_interface::transfer_checked(cpi_ctx, reward_fee, ctx.accounts.reward_mint.decimals)?;
+            }
+
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                mint: ctx.accounts.reward_mint.to_account_info(),
+                to: ctx.accounts.user_reward_token_account.to_account_info(),
+                authority: ctx.accounts.reward_vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token_interface::transfer_checked(cpi_ctx, reward_net, ctx.accounts.reward_mint.decimals)?;
+
+            stake_account.rewards_accrued = reward_amount - reward_paid;
+            ctx.accounts.stake_pool.settle_rewards(reward_paid, reward_paid)?;
+
+            // The referrer's bonus is extra to the claim, so it only comes out of
+            // reserve no staker is owed or scheduled to earn
+            let mut referral_bonus = 0;
+            let referrer = ctx.accounts.referral.as_ref().map(|referral| referral.referrer);
+            if let (Some(referrer), Some(referrer_token_account)) =
+                (referrer, ctx.accounts.referrer_token_account.as_ref())
+            {
+                require_keys_eq!(
+                    referrer_token_account.owner,
+                    referrer,
+                    GamingTokenError::Unauthorized
+                );
+                let stake_pool = &mut ctx.accounts.stake_pool;
+                referral_bonus = stake_pool.reserve_bonus(
+                    reward_paid,
+                    stake_pool.referral_bps,
+                    ctx.accounts.reward_vault.amount - reward_paid,
+                    clock.unix_timestamp,
+                );
+
+                if referral_bonus > 0 {
+                    let cpi_accounts = TransferChecked {
+                        from: ctx.accounts.reward_vault.to_account_info(),
+                        mint: ctx.accounts.reward_mint.to_account_info(),
+                        to: referrer_token_account.to_account_info(),
+                        authority: ctx.accounts.reward_vault_authority.to_account_info(),
+                    };
+                    let cpi_program = ctx.accounts.token_program.to_account_info();
+                    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+                    token_interface::transfer_checked(cpi_ctx, referral_bonus, ctx.accounts.reward_mint.decimals)?;
+                    stake_pool.settle_rewards(0, referral_bonus)?;
+                }
+            }
+
+            // Solitaire achievement holders get a bonus on the same terms
+            let achievement_bps = achievement_boost_bps(
+                &ctx.accounts.stake_pool,
+                &ctx.accounts.player_stats,
+                &ctx.accounts.owner.key(),
+            )?;
+            let achievement_bonus = ctx.accounts.stake_pool.reserve_bonus(
+                reward_paid,
+                achievement_bps,
+                ctx.accounts.reward_vault.amount - reward_paid - referral_bonus,
+                clock.unix_timestamp,
+            );
+            if achievement_bonus > 0 {
+                let cpi_accounts = TransferChecked {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    mint: ctx.accounts.reward_mint.to_account_info(),
+                    to: ctx.accounts.user_reward_token_account.to_account_info(),
+                    authority: ctx.accounts.reward_vault_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+                token_interface::transfer_checked(cpi_ctx, achievement_bonus, ctx.accounts.reward_mint.decimals)?;
+                ctx.accounts.stake_pool.settle_rewards(0, achievement_bonus)?;
+                reward_bonus = achievement_bonus;
+            }
+
+            // Read back after every transfer so the alarm sees what is really left
+            ctx.accounts.reward_vault.reload()?;
+            ctx.accounts
+                .stake_pool
+                .check_reserve_low(ctx.accounts.reward_vault.amount, clock.unix_timestamp);
+
+            emit!(RewardsClaimed {
+                owner: stake_account.owner,
+                position_index: stake_account.position_index,
+                gross: reward_paid,
+                fee: reward_fee,
+                net: reward_net,
+                referrer,
+                referral_bonus,
+                timestamp: clock.unix_timestamp,
+                mint: stake_account.mint,
+                pool: ctx.accounts.stake_pool.key(),
+                stake_account: stake_account.key(),
+                since_last_claim,
+                achievement_bonus,
+            });
+        }
+
+        // Partner slots to claim are passed as `[slot vault, slot mint, token account]` triples
+        let slots_paid = pay_slot_rewards(
+            &mut ctx.accounts.stake_pool,
+            stake_account,
+            ctx.remaining_accounts,
+            &ctx.accounts.owner.key(),
+            &ctx.accounts.reward_vault_authority,
+            ctx.bumps.reward_vault_authority,
+            &ctx.accounts.token_program,
+        )?;
+        // A claim that pays nothing fails rather than passing as a claim; the
+        // revert also leaves `last_reward_claim` where it was
+        require!(reward_paid > 0 || slots_paid, GamingTokenError::NothingToClaim);
+
+        let stake_pool_key = ctx.accounts.stake_pool.key();
+        let staker_stats = &mut ctx.accounts.staker_stats;
+        staker_stats.touch(stake_account.owner, stake_pool_key, ctx.bumps.staker_stats, stake_account.created_at);
+        staker_stats.record_rewards(reward_net + reward_bonus, reward_paid > 0)?;
+
+        Ok(())
+    }
+
+    /// Read-only: what `claim_rewards` would owe and pay right now, worked out by
+    /// running the same checkpoint on copies of the accounts, plus the owner's
+    /// reward debt. Meant for `simulateTransaction`; the value comes back as
+    /// return data.
+    pub fn get_pending_rewards(ctx: Context<GetPendingRewards>) -> Result<PendingRewards> {
+        let clock = Clock::get()?;
+
+        let mut stake_pool = ctx.accounts.stake_pool.clone().into_inner();
+        let mut stake_account = ctx.accounts.stake_account.clone().into_inner();
+        let pending = update_rewards(&mut stake_pool, &mut stake_account, clock.unix_timestamp)?;
+
+        // Claims pay nothing in an emergency or a freeze and otherwise what the vault can cover, less the fee
+        let claimable = if stake_pool.emergency_mode || stake_pool.emergency_rewards_freeze {
+            0
+        } else {
+            stake_pool.split_claim_fee(pending.min(ctx.accounts.reward_vault.amount)).1
+        };
+        let pending_rewards = PendingRewards {
+            pending,
+            claimable,
+            slot_rewards: stake_account.slot_rewards.map(|slot| slot.rewards_accrued),
+            reward_debt: ctx.accounts.stake_positions.reward_debt,
+        };
+
+        emit!(PendingRewardsComputed {
+            stake_account: ctx.accounts.stake_account.key(),
+            pending,
+            claimable,
+            slot_rewards: pending_rewards.slot_rewards,
+            reward_debt: pending_rewards.reward_debt,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(pending_rewards)
+    }
+
+    /// Read-only check for other programs to CPI into, through the `cpi`
+    /// feature: whether `owner` holds an active position of at least
+    /// `min_amount` in the pool. A failed check is not an error; the answer
+    /// comes back as return data. Callers name the interface version they
+    /// were built against and are refused if it is not this one.
+    pub fn assert_min_stake(
+        ctx: Context<AssertMinStake>,
+        interface_version: u8,
+        owner: Pubkey,
+        min_amount: u64,
+    ) -> Result<StakeCheck> {
+        require!(
+            interface_version == STAKE_INTERFACE_VERSION,
+            GamingTokenError::UnsupportedInterfaceVersion
+        );
+
+        let stake_account = &ctx.accounts.stake_account;
+        let active = stake_account.owner == owner
+            && stake_account.mint == ctx.accounts.stake_pool.mint
+            && stake_account.is_active
+            && stake_account.unbonding_since.is_none();
+        let amount = if active { stake_account.amount } else { 0 };
+
+        Ok(StakeCheck {
+            interface_version: STAKE_INTERFACE_VERSION,
+            meets_minimum: active && amount >= min_amount,
+            amount,
+            lock_until: stake_account.lock_until,
+        })
+    }
+
+    /// Rewrites a stake account saved in an older layout into the current one,
+    /// growing it at the payer's expense. Every field carries over,
+    /// `reward_per_token_paid` included, so the position keeps exactly what it
+    /// had earned and nothing more. Anyone may call it.
+    pub fn migrate_stake_account(ctx: Context<MigrateStakeAccount>) -> Result<()> {
+        let info = ctx.accounts.stake_account.to_account_info();
+        let (from_version, migrated) = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == StakeAccount::DISCRIMINATOR,
+                ErrorCode::AccountDiscriminatorMismatch
+            );
+            // Version 1 is the version 0 body behind a version byte; versions 2
+            // and 3 are the size of the current layout without the trailing
+            // `warmup_until` and `power_changed_at`, version 4 without the last
+            let from_version = if data.len() == StakeAccountV0::SPACE {
+                0
+            } else if data.len() == StakeAccountV0::SPACE + 1 && data[8] == 1 {
+                1
+            } else if data.len() == StakeAccount::SPACE - 16 && (data[8] == 2 || data[8] == 3) {
+                data[8]
+            } else if data.len() == StakeAccount::SPACE - 8 && data[8] == 4 {
+                4
+            } else {
+                return err!(GamingTokenError::AlreadyMigrated);
+            };
+            let migrated = match from_version {
+                0 | 1 => StakeAccountV0::deserialize(&mut &data[8 + from_version as usize..])?.migrate(),
+                2 => StakeAccountV2::deserialize(&mut &data[9..])?.migrate(),
+                _ => {
+                    // Versions 3 and 4 only lack trailing fields: a missing
+                    // `warmup_until` stays zero, already warmed up, and the power
+                    // checkpoint starts where the position did
+                    let mut body = data[8..].to_vec();
+                    body.resize(StakeAccount::SPACE - 8, 0);
+                    let body = StakeAccount::deserialize(&mut &body[..])?;
+                    StakeAccount {
+                        version: STAKE_ACCOUNT_VERSION,
+                        power_changed_at: body.created_at,
+                        ..body
+                    }
+                }
+            };
+            (from_version, migrated)
+        };
+
+        // Length and version byte only name a candidate layout; the body read
+        // with it has to describe the position stored at this address
+        let (expected, _) = Pubkey::find_program_address(
+            &[
+                b"stake",
+                migrated.owner.as_ref(),
+                migrated.mint.as_ref(),
+                &migrated.position_index.to_le_bytes(),
+            ],
+            ctx.program_id,
+        );
+        require_keys_eq!(expected, info.key(), GamingTokenError::UnrecognizedStakeLayout);
+
+        let rent_due = Rent::get()?
+            .minimum_balance(StakeAccount::SPACE)
+            .saturating_sub(info.lamports());
+        if rent_due > 0 {
+            let cpi_accounts = system_program::Transfer {
+                from: ctx.accounts.payer.to_account_info(),
+                to: info.clone(),
+            };
+            let cpi_program = ctx.accounts.system_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            system_program::transfer(cpi_ctx, rent_due)?;
+        }
+        info.realloc(StakeAccount::SPACE, false)?;
+
+        let owner = migrated.owner;
+        let mut data = info.try_borrow_mut_data()?;
+        data.fill(0);
+        migrated.try_serialize(&mut &mut data[..])?;
+
+        emit!(StakeAccountMigrated {
+            stake_account: info.key(),
+            owner,
+            from_version,
+            to_version: STAKE_ACCOUNT_VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+/// Shared reward checkpoint: brings the pool accumulator up to `now` and moves
+/// what the position has earned into `rewards_accrued`, returning the total owed.
+/// Ends a finished warm-up. Call it before changing a position's balance,
+/// weight or rewards.
+fn update_rewards(stake_pool: &mut StakePool, stake_account: &mut StakeAccount, now: i64) -> Result<u64> {
+    stake_pool.update_reward_per_token(now)?;
+    (stake_account.rewards_accrued, stake_account.reward_dust) =
+        stake_account.pending_rewards(stake_pool, now)?;
+    stake_account.reward_per_token_paid = stake_pool.reward_per_token_stored;
+
+    stake_pool.update_reward_slots(now)?;
+    let weight = stake_account.weight();
+    for (checkpoint, slot) in stake_account.slot_rewards.iter_mut().zip(&stake_pool.reward_slots) {
+        let (earned, dust) = split_reward(
+            weight,
+            slot.reward_per_token_stored.saturating_sub(checkpoint.reward_per_token_paid),
+            checkpoint.reward_dust,
+        )?;
+        checkpoint.rewards_accrued = earned
+            .checked_add(checkpoint.rewards_accrued)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        checkpoint.reward_dust = dust;
+        checkpoint.reward_per_token_paid = slot.reward_per_token_stored;
+    }
+
+    // A warmed-up position joins the pool weight at the checkpoint just taken,
+    // so it earns from here on and nothing for the warm-up itself
+    if stake_account.warmup_until != 0 && now >= stake_account.warmup_until {
+        stake_account.warmup_until = 0;
+        stake_pool.reweight(0, stake_account.weight())?;
+    }
+    Ok(stake_account.rewards_accrued)
+}
+
+/// `wallet`'s associated token account for `mint`, created with `payer`'s
+/// lamports if it doesn't exist. An existing account must belong to `wallet`
+/// and hold `mint`.
+fn ensure_associated_token_account<'info>(
+    wallet: &AccountInfo<'info>,
+    token_account: &'info AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    payer: &Signer<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    associated_token_program: &Program<'info, AssociatedToken>,
+    system_program: &Program<'info, System>,
+) -> Result<InterfaceAccount<'info, TokenAccount>> {
+    require_keys_eq!(
+        token_account.key(),
+        get_associated_token_address_with_program_id(wallet.key, &mint.key(), &token_program.key()),
+        GamingTokenError::InvalidAssociatedTokenAccount
+    );
+
+    if token_account.data_is_empty() {
+        let cpi_accounts = associated_token::Create {
+            payer: payer.to_account_info(),
+            associated_token: token_account.clone(),
+            authority: wallet.clone(),
+            mint: mint.to_account_info(),
+            system_program: system_program.to_account_info(),
+            token_program: token_program.to_account_info(),
+        };
+        let cpi_program = associated_token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        associated_token::create(cpi_ctx)?;
+    }
+
+    let token_account = InterfaceAccount::<TokenAccount>::try_from(token_account)?;
+    require_keys_eq!(token_account.mint, mint.key(), GamingTokenError::MintMismatch);
+    require_keys_eq!(
+        token_account.owner,
+        wallet.key(),
+        GamingTokenError::InvalidAssociatedTokenAccount
+    );
+    Ok(token_account)
+}
+
+/// Shrinks a soft stake to the part of `amount` still delegated to
+/// `vault_authority` and held in its token account. Any shortfall also
+/// forfeits the rewards accrued so far, since they were earned on tokens the
+/// pool could no longer count on.
+fn settle_soft_shortfall(
+    stake_pool: &mut StakePool,
+    soft_position: &mut SoftStakePosition,
+    token_account: &InterfaceAccount<TokenAccount>,
+    vault_authority: &Pubkey,
+    now: i64,
+) -> Result<()> {
+    let position = &mut soft_position.position;
+    let backed = if token_account.delegate == COption::Some(*vault_authority) {
+        token_account.delegated_amount.min(token_account.amount)
+    } else {
+        0
+    };
+    if backed >= position.amount {
+        return Ok(());
+    }
+
+    let forfeited = update_rewards(stake_pool, position, now)?;
+    stake_pool.settle_rewards(forfeited, 0)?;
+    position.rewards_accrued = 0;
+    forfeit_slot_rewards(stake_pool, position)?;
+
+    let previous_amount = position.amount;
+    let old_weight = position.weight();
+    position.amount = backed;
+    stake_pool.reweight(old_weight, position.weight())?;
+    stake_pool.total_soft_staked -= previous_amount - backed;
+
+    emit!(SoftStakeShortfall {
+        owner: position.owner,
+        mint: position.mint,
+        token_account: soft_position.token_account,
+        previous_amount,
+        backed_amount: backed,
+        forfeited_rewards: forfeited,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+/// Adds a closed position's frozen rewards to its owner's reward debt.
+fn record_reward_debt(stake_positions: &mut StakePositions, amount: u64, now: i64) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    stake_positions.reward_debt = stake_positions
+        .reward_debt
+        .checked_add(amount)
+        .ok_or(GamingTokenError::MathOverflow)?;
+
+    emit!(RewardDebtRecorded {
+        owner: stake_positions.owner,
+        mint: stake_positions.mint,
+        amount,
+        reward_debt: stake_positions.reward_debt,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+/// Rounding policy for every position payout: `weight * delta` is in units of
+/// `1 / REWARD_PRECISION` tokens, and only whole tokens are paid, floored in
+/// the pool's favour. The remainder is returned as dust, to be passed back in
+/// as `dust` next time so it is paid once it adds up to a whole token. Payable
+/// plus dust always equals what was earned, so rounding neither creates nor
+/// destroys value.
+fn split_reward(weight: u128, delta: u128, dust: u64) -> Result<(u64, u64)> {
+    let earned = weight
+        .checked_mul(delta)
+        .and_then(|earned| earned.checked_add(dust as u128))
+        .ok_or(GamingTokenError::MathOverflow)?;
+    let payable = u64::try_from(earned / REWARD_PRECISION).map_err(|_| GamingTokenError::MathOverflow)?;
+    Ok((payable, (earned % REWARD_PRECISION) as u64))
+}
+
+/// Adds two `(accrued, dust)` checkpoints, carrying dust that makes up a whole token.
+fn merge_reward(a: (u64, u64), b: (u64, u64)) -> Result<(u64, u64)> {
+    let dust = a.1 as u128 + b.1 as u128;
+    let accrued = a
+        .0
+        .checked_add(b.0)
+        .and_then(|accrued| accrued.checked_add((dust / REWARD_PRECISION) as u64))
+        .ok_or(GamingTokenError::MathOverflow)?;
+    Ok((accrued, (dust % REWARD_PRECISION) as u64))
+}
+
+/// Sets the period length for the next `notify_reward_amount`.
+fn apply_reward_duration(stake_pool: &mut StakePool, reward_duration: i64, now: i64) -> Result<()> {
+    require!(reward_duration > 0, GamingTokenError::InvalidRewardDuration);
+    // The running period keeps the rate it was funded at
+    require!(now >= stake_pool.period_finish, GamingTokenError::RewardPeriodActive);
+
+    let old_reward_duration = stake_pool.reward_duration;
+    stake_pool.reward_duration = reward_duration;
+
+    emit!(RewardDurationUpdated {
+        mint: stake_pool.mint,
+        old_reward_duration,
+        new_reward_duration: reward_duration,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Switches the pool to halving epochs, starting at `now`. Accrual must
+/// already be checkpointed.
+fn apply_emission_schedule(stake_pool: &mut StakePool, args: EmissionScheduleArgs, now: i64) -> Result<()> {
+    let EmissionScheduleArgs {
+        epoch_length,
+        initial_emission,
+        halving_interval,
+    } = args;
+    require!(epoch_length > 0, GamingTokenError::InvalidRewardDuration);
+    require!(halving_interval > 0, GamingTokenError::InvalidEmissionSchedule);
+    require!(
+        initial_emission >= epoch_length as u64,
+        GamingTokenError::InvalidEmissionSchedule
+    );
+    require!(
+        stake_pool.emission_schedule.is_none(),
+        GamingTokenError::EmissionScheduleActive
+    );
+    require!(now >= stake_pool.period_finish, GamingTokenError::RewardPeriodActive);
+
+    stake_pool.emission_schedule = Some(EmissionSchedule {
+        epoch_length,
+        initial_emission,
+        halving_interval,
+        current_epoch: 0,
+        epoch_start: now,
+    });
+    stake_pool.reward_rate = initial_emission / epoch_length as u64;
+    stake_pool.last_update_time = now;
+    stake_pool.period_finish = now
+        .checked_add(epoch_length)
+        .ok_or(GamingTokenError::MathOverflow)?;
+    stake_pool.record_rate(now);
+
+    // The first epoch must be funded up front; later ones emit what the reserve holds
+    require!(
+        stake_pool.outstanding_liability(now) <= stake_pool.reward_reserve,
+        GamingTokenError::InsufficientFunds
+    );
+
+    emit!(EmissionScheduleSet {
+        mint: stake_pool.mint,
+        epoch_length,
+        initial_emission,
+        halving_interval,
+        reward_rate: stake_pool.reward_rate,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Mints each `(token account, amount)` in `recipients` under the supply cap,
+/// which applies to the mint's real supply, raw SPL burns included. Signs as
+/// the program PDA once `enable_minters` has handed it the mint authority,
+/// and as `authority` before that; minter paths pass none and only ever mint
+/// through the PDA.
+fn checked_mint<'info>(
+    mint_config: &mut MintConfig,
+    mint: &InterfaceAccount<'info, Mint>,
+    mint_authority: &AccountInfo<'info>,
+    mint_authority_bump: u8,
+    authority: Option<&AccountInfo<'info>>,
+    token_program: &Interface<'info, TokenInterface>,
+    recipients: &[(AccountInfo<'info>, u64)],
+) -> Result<()> {
+    reconcile_supply(mint_config, mint.supply)?;
+    let new_supply = recipients
+        .iter()
+        .try_fold(mint_config.total_supply, |supply, (_, amount)| supply.checked_add(*amount))
+        .ok_or(GamingTokenError::MathOverflow)?;
+    require!(
+        mint_config.max_supply == 0 || new_supply <= mint_config.max_supply,
+        GamingTokenError::SupplyCapExceeded
+    );
+
+    let mint_key = mint.key();
+    let mint_authority_seeds = &[b"mint_authority", mint_key.as_ref(), &[mint_authority_bump]];
+    let signer = &[&mint_authority_seeds[..]];
+
+    let signing_authority = match authority {
+        Some(authority) if mint.mint_authority != COption::Some(mint_authority.key()) => authority,
+        _ => mint_authority,
+    };
+
+    for (token_account, amount) in recipients {
+        let cpi_accounts = MintTo {
+            mint: mint.to_account_info(),
+            to: token_account.clone(),
+            authority: signing_authority.clone(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+        token_interface::mint_to(cpi_ctx, *amount)?;
+    }
+
+    mint_config.total_supply = new_supply;
+    Ok(())
+}
+
+/// Resets `total_supply` to the SPL mint's supply, which moves without us when
+/// tokens are burned through the token program directly.
+fn reconcile_supply(mint_config: &mut MintConfig, mint_supply: u64) -> Result<()> {
+    let previous_supply = mint_config.total_supply;
+    if previous_supply == mint_supply {
+        return Ok(());
+    }
+    mint_config.total_supply = mint_supply;
+
+    emit!(SupplyReconciled {
+        mint: mint_config.mint,
+        previous_supply,
+        new_supply: mint_supply,
+        delta: mint_supply as i128 - previous_supply as i128,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Moves the SPL mint authority, signing as whichever of the config
+/// authority or the program PDA currently holds it.
+fn set_mint_authority(
+    accounts: &ManageMintAuthority,
+    new_authority: Option<Pubkey>,
+    mint_authority_bump: u8,
+) -> Result<()> {
+    require!(
+        accounts.mint_config.mint_authority.is_some(),
+        GamingTokenError::MintAuthorityRevoked
+    );
+
+    let mint_key = accounts.mint.key();
+    let mint_authority_seeds = &[
+        b"mint_authority",
+        mint_key.as_ref(),
+        &[mint_authority_bump],
+    ];
+    let signer = &[&mint_authority_seeds[..]];
+
+    let current_authority = if accounts.mint.mint_authority == COption::Some(accounts.mint_authority.key()) {
+        accounts.mint_authority.to_account_info()
+    } else {
+        accounts.authority.to_account_info()
+    };
+
+    let cpi_accounts = SetAuthority {
+        current_authority,
+        account_or_mint: accounts.mint.to_account_info(),
+    };
+    let cpi_program = accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+    token_interface::set_authority(cpi_ctx, AuthorityType::MintTokens, new_authority)
+}
+
+/// Pays checkpointed partner-slot rewards to the `[slot vault, slot mint, holder
+/// token account]` triples in `remaining_accounts`. Each slot pays what its own
+/// vault holds. Returns whether any slot paid out.
+fn pay_slot_rewards<'info>(
+    stake_pool: &mut StakePool,
+    stake_account: &mut StakeAccount,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    holder: &Pubkey,
+    reward_vault_authority: &AccountInfo<'info>,
+    reward_vault_authority_bump: u8,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<bool> {
+    require!(remaining_accounts.len().is_multiple_of(3), GamingTokenError::InvalidRewardSlot);
+
+    let mint_key = stake_pool.mint;
+    let reward_vault_seeds = &[
+        b"reward_vault_authority",
+        mint_key.as_ref(),
+        &[reward_vault_authority_bump],
+    ];
+    let signer = &[&reward_vault_seeds[..]];
+
+    let mut paid_any = false;
+    for accounts in remaining_accounts.chunks(3) {
+        let vault = InterfaceAccount::<TokenAccount>::try_from(&accounts[0])?;
+        let reward_mint = InterfaceAccount::<Mint>::try_from(&accounts[1])?;
+        let destination = InterfaceAccount::<TokenAccount>::try_from(&accounts[2])?;
+        let index = stake_pool
+            .reward_slots
+            .iter()
+            .position(|slot| slot.vault == vault.key())
+            .ok_or(GamingTokenError::InvalidRewardSlot)?;
+        let slot = &mut stake_pool.reward_slots[index];
+        require_keys_eq!(reward_mint.key(), slot.mint, GamingTokenError::InvalidRewardSlot);
+        require_keys_eq!(destination.mint, slot.mint, GamingTokenError::InvalidRewardSlot);
+        require_keys_eq!(destination.owner, *holder, GamingTokenError::Unauthorized);
+
+        let checkpoint = &mut stake_account.slot_rewards[index];
+        let paid = checkpoint.rewards_accrued.min(vault.amount);
+        if paid == 0 {
+            continue;
+        }
+
+        let cpi_accounts = TransferChecked {
+            from: vault.to_account_info(),
+            mint: reward_mint.to_account_info(),
+            to: destination.to_account_info(),
+            authority: reward_vault_authority.clone(),
+        };
+        let cpi_program = token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, paid, reward_mint.decimals)?;
+
+        checkpoint.rewards_accrued -= paid;
+        slot.settle_rewards(paid, paid)?;
+        paid_any = true;
+
+        emit!(SlotRewardsClaimed {
+            owner: stake_account.owner,
+            position_index: stake_account.position_index,
+            slot: index as u8 + 1,
+            reward_mint: slot.mint,
+            amount: paid,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+
+    Ok(paid_any)
+}
+
+/// Sends everything `vault` holds to `destination`, then closes it and
+/// returns the rent to `rent_receiver`. A vault passed without a destination
+/// must already be empty.
+fn sweep_and_close_vault<'info>(
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    destination: Option<&InterfaceAccount<'info, TokenAccount>>,
+    rent_receiver: &AccountInfo<'info>,
+    vault_authority: &AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<u64> {
+    let swept = vault.amount;
+    if swept > 0 {
+        let destination = destination.ok_or(GamingTokenError::VaultNotEmpty)?;
+        let cpi_accounts = TransferChecked {
+            from: vault.to_account_info(),
+            mint: mint.to_account_info(),
+            to: destination.to_account_info(),
+            authority: vault_authority.clone(),
+        };
+        let cpi_program = token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, swept, mint.decimals)?;
+    }
+
+    let cpi_accounts = CloseAccount {
+        account: vault.to_account_info(),
+        destination: rent_receiver.clone(),
+        authority: vault_authority.clone(),
+    };
+    let cpi_program = token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+    token_interface::close_account(cpi_ctx)?;
+
+    Ok(swept)
+}
+
+/// False for a stake account still in an older layout. Length is checked as
+/// well as the version, since old bytes can parse as the new struct; check it
+/// before any constraint that reads the body.
+fn is_current_layout(stake_account: &Account<StakeAccount>) -> bool {
+    stake_account.version == STAKE_ACCOUNT_VERSION
+        && stake_account.to_account_info().data_len() == StakeAccount::SPACE
+}
+
+/// The achievement bonus `player` earns on `stake_pool`: its bps when
+/// `player_stats` is the player's `PlayerStats` in the configured solitaire
+/// program with enough wins, nothing when it is missing or short of wins.
+fn achievement_boost_bps(
+    stake_pool: &StakePool,
+    player_stats: &Option<UncheckedAccount>,
+    player: &Pubkey,
+) -> Result<u16> {
+    let Some(player_stats) = player_stats else {
+        return Ok(0);
+    };
+    if stake_pool.achievement_boost_bps == 0 {
+        return Ok(0);
+    }
+
+    require_keys_eq!(
+        *player_stats.owner,
+        stake_pool.achievement_program,
+        GamingTokenError::InvalidPlayerStats
+    );
+    let (expected, _) = Pubkey::find_program_address(
+        &[PLAYER_STATS_SEED, player.as_ref()],
+        &stake_pool.achievement_program,
+    );
+    require_keys_eq!(player_stats.key(), expected, GamingTokenError::InvalidPlayerStats);
+
+    let data = player_stats.try_borrow_data()?;
+    require!(
+        data.len() >= 8 && data[..8] == hash(b"account:PlayerStats").to_bytes()[..8],
+        GamingTokenError::InvalidPlayerStats
+    );
+    let stats = SolitairePlayerStats::deserialize(&mut &data[8..])?;
+    require_keys_eq!(stats.player, *player, GamingTokenError::InvalidPlayerStats);
+
+    Ok(if stats.games_won >= stake_pool.achievement_min_wins {
+        stake_pool.achievement_boost_bps
+    } else {
+        0
+    })
+}
+
+fn validate_game_server_policy(daily_budget: u64, per_recipient_cap: u64) -> Result<()> {
+    require!(
+        daily_budget > 0 && per_recipient_cap > 0 && per_recipient_cap <= daily_budget,
+        GamingTokenError::InvalidGameServerPolicy
+    );
+    Ok(())
+}
+
+/// Requires `player_record` to be `player`'s record in `registry`: the PDA at
+/// `[PLAYER_STATS_SEED, player]`, which only the registry can have created.
+fn require_registered_player(
+    registry: &Pubkey,
+    player_record: &Option<UncheckedAccount>,
+    player: &Pubkey,
+) -> Result<()> {
+    let Some(player_record) = player_record else {
+        return err!(GamingTokenError::RecipientNotRegistered);
+    };
+    require_keys_eq!(*player_record.owner, *registry, GamingTokenError::RecipientNotRegistered);
+    let (expected, _) = Pubkey::find_program_address(&[PLAYER_STATS_SEED, player.as_ref()], registry);
+    require_keys_eq!(player_record.key(), expected, GamingTokenError::RecipientNotRegistered);
+    require!(!player_record.data_is_empty(), GamingTokenError::RecipientNotRegistered);
+    Ok(())
+}
+
+/// Moves what the compound vault's position has earned, as far as the reward
+/// vault covers it, into the position's principal, less the claim fee.
+/// Partner-slot rewards can't be compounded and are written off to their
+/// slots. Returns what left the reward vault and the fee.
+#[allow(clippy::too_many_arguments)]
+fn compound_vault_position<'info>(
+    stake_pool: &mut StakePool,
+    vault_position: &mut StakeAccount,
+    reward_vault: &InterfaceAccount<'info, TokenAccount>,
+    stake_vault: &mut InterfaceAccount<'info, TokenAccount>,
+    fee_vault: &Option<InterfaceAccount<'info, TokenAccount>>,
+    mint: &InterfaceAccount<'info, Mint>,
+    reward_vault_authority: &AccountInfo<'info>,
+    reward_vault_authority_bump: u8,
+    token_program: &Interface<'info, TokenInterface>,
+    now: i64,
+) -> Result<(u64, u64)> {
+    let reward_amount = update_rewards(stake_pool, vault_position, now)?;
+    forfeit_slot_rewards(stake_pool, vault_position)?;
+    let compounded = reward_amount.min(reward_vault.amount);
+    if compounded == 0 {
+        return Ok((0, 0));
+    }
+    vault_position.rewards_accrued = reward_amount - compounded;
+
+    let mint_key = mint.key();
+    let reward_vault_seeds = &[
+        b"reward_vault_authority",
+        mint_key.as_ref(),
+        &[reward_vault_authority_bump],
+    ];
+    let signer = &[&reward_vault_seeds[..]];
+
+    let (fee, net) = stake_pool.split_claim_fee(compounded);
+    if fee > 0 {
+        let fee_vault = fee_vault.as_ref().ok_or(GamingTokenError::FeeVaultRequired)?;
+        let cpi_accounts = TransferChecked {
+            from: reward_vault.to_account_info(),
+            mint: mint.to_account_info(),
+            to: fee_vault.to_account_info(),
+            authority: reward_vault_authority.clone(),
+        };
+        let cpi_program = token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, fee, mint.decimals)?;
+    }
+
+    let vault_balance = stake_vault.amount;
+    let cpi_accounts = TransferChecked {
+        from: reward_vault.to_account_info(),
+        mint: mint.to_account_info(),
+        to: stake_vault.to_account_info(),
+        authority: reward_vault_authority.clone(),
+    };
+    let cpi_program = token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+    token_interface::transfer_checked(cpi_ctx, net, mint.decimals)?;
+
+    // Rewards are settled at what left the reward vault, principal at what arrived
+    stake_vault.reload()?;
+    let received = stake_vault.amount - vault_balance;
+
+    let old_weight = vault_position.weight();
+    vault_position.amount = vault_position
+        .amount
+        .checked_add(received)
+        .ok_or(GamingTokenError::MathOverflow)?;
+    stake_pool.settle_rewards(compounded, compounded)?;
+    stake_pool.add_stake(received)?;
+    stake_pool.reweight(old_weight, vault_position.weight())?;
+    // The fee and the compounded amount both left the reward vault in full
+    stake_pool.check_reserve_low(reward_vault.amount - compounded, now);
+
+    Ok((compounded, fee))
+}
+
+/// `amount` of a mint with `from` decimals in units of one with `to` decimals,
+/// rounded down.
+fn convert_decimals(amount: u64, from: u8, to: u8) -> Result<u64> {
+    let amount = amount as u128;
+    let converted = if to >= from {
+        10u128
+            .checked_pow((to - from) as u32)
+            .and_then(|scale| amount.checked_mul(scale))
+    } else {
+        10u128.checked_pow((from - to) as u32).map(|scale| amount / scale)
+    };
+    converted
+        .and_then(|converted| u64::try_from(converted).ok())
+        .ok_or(error!(GamingTokenError::MathOverflow))
+}
+
+/// `amount * numerator / denominator` in u128, rounded down: shares for a
+/// deposit, or the underlying for a withdrawal.
+fn vault_shares(amount: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    let result = (amount as u128)
+        .checked_mul(numerator as u128)
+        .and_then(|scaled| scaled.checked_div(denominator as u128))
+        .ok_or(GamingTokenError::MathOverflow)?;
+    u64::try_from(result).map_err(|_| error!(GamingTokenError::MathOverflow))
+}
+
+/// Writes off partner-slot rewards a closing position leaves behind.
+fn forfeit_slot_rewards(stake_pool: &mut StakePool, stake_account: &mut StakeAccount) -> Result<()> {
+    for (checkpoint, slot) in stake_account.slot_rewards.iter_mut().zip(stake_pool.reward_slots.iter_mut()) {
+        slot.settle_rewards(checkpoint.rewards_accrued, 0)?;
+        checkpoint.rewards_accrued = 0;
+    }
+    Ok(())
+}
+
+/// Burns a position's receipt NFT so it cannot be presented again.
+fn burn_receipt<'info>(
+    stake_account: &StakeAccount,
+    receipt_mint: &Option<InterfaceAccount<'info, Mint>>,
+    receipt_token_account: &Option<InterfaceAccount<'info, TokenAccount>>,
+    holder: &Signer<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    let Some(expected_mint) = stake_account.receipt_mint else {
+        return Ok(());
+    };
+    let (Some(receipt_mint), Some(receipt_token_account)) = (receipt_mint, receipt_token_account)
+    else {
+        return err!(GamingTokenError::ReceiptAccountsRequired);
+    };
+    require_keys_eq!(receipt_mint.key(), expected_mint, GamingTokenError::ReceiptAccountsRequired);
+
+    let cpi_accounts = Burn {
+        mint: receipt_mint.to_account_info(),
+        from: receipt_token_account.to_account_info(),
+        authority: holder.to_account_info(),
+    };
+    let cpi_program = token_program.to_account_info();
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    token_interface::burn(cpi_ctx, 1)
+}
+
+#[derive(Accounts)]
+pub struct InitializeMint<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 1 + 32 + 1 + (1 + 32) + (1 + 32) + 1 + 8,
+        seeds = [b"mint_config", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    /// Created in the handler, sized for any requested extensions
+    #[account(mut)]
+    pub mint: Signer<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CreateMetadata<'info> {
+    #[account(has_one = authority, has_one = mint @ GamingTokenError::ConfigMintMismatch)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(constraint = mint.decimals == mint_config.decimals @ GamingTokenError::DecimalsMismatch)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: PDA that holds the mint authority once minters are enabled
+    #[account(seeds = [b"mint_authority", mint.key().as_ref()], bump)]
+    pub mint_authority: AccountInfo<'info>,
+
+    /// CHECK: Created by the Token Metadata program at its canonical PDA
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// Pays for the metadata account and becomes its update authority
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTokenMetadata<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !mint_config.metadata_finalized @ GamingTokenError::MetadataFinalized
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    /// CHECK: Owned and validated by the Token Metadata program
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint_config.mint.as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// Update authority set by `create_metadata`
+    pub authority: Signer<'info>,
+
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+#[derive(Accounts)]
+pub struct MintTokens<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !mint_config.minting_paused @ GamingTokenError::MintingPaused,
+        constraint = mint_config.mint_authority.is_some() @ GamingTokenError::MintAuthorityRevoked
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        address = mint_config.mint @ GamingTokenError::ConfigMintMismatch,
+        constraint = mint.decimals == mint_config.decimals @ GamingTokenError::DecimalsMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: PDA that holds the mint authority once minters are enabled
+    #[account(seeds = [b"mint_authority", mint.key().as_ref()], bump)]
+    pub mint_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = token_account.mint == mint_config.mint @ GamingTokenError::MintMismatch
+    )]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct MintTokensToWallet<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !mint_config.minting_paused @ GamingTokenError::MintingPaused,
+        constraint = mint_config.mint_authority.is_some() @ GamingTokenError::MintAuthorityRevoked
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        address = mint_config.mint @ GamingTokenError::ConfigMintMismatch,
+        constraint = mint.decimals == mint_config.decimals @ GamingTokenError::DecimalsMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: PDA that holds the mint authority once minters are enabled
+    #[account(seeds = [b"mint_authority", mint.key().as_ref()], bump)]
+    pub mint_authority: AccountInfo<'info>,
+
+    /// CHECK: Any wallet; only used to derive its associated token account
+    pub recipient: UncheckedAccount<'info>,
+
+    /// An existing account must be the recipient's ATA for this mint
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BatchMintToWallets<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !mint_config.minting_paused @ GamingTokenError::MintingPaused,
+        constraint = mint_config.mint_authority.is_some() @ GamingTokenError::MintAuthorityRevoked
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        address = mint_config.mint @ GamingTokenError::ConfigMintMismatch,
+        constraint = mint.decimals == mint_config.decimals @ GamingTokenError::DecimalsMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: PDA that holds the mint authority once minters are enabled
+    #[account(seeds = [b"mint_authority", mint.key().as_ref()], bump)]
+    pub mint_authority: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MintTokensAsMinter<'info> {
+    #[account(
+        mut,
+        seeds = [b"mint_config", mint.key().as_ref()],
+        bump,
+        constraint = !mint_config.minting_paused @ GamingTokenError::MintingPaused,
+        constraint = mint_config.mint_authority.is_some() @ GamingTokenError::MintAuthorityRevoked
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        has_one = minter,
+        seeds = [b"minter", mint.key().as_ref(), minter.key().as_ref()],
+        bump = minter_record.bump
+    )]
+    pub minter_record: Account<'info, MinterRecord>,
+
+    #[account(
+        mut,
+        address = mint_config.mint @ GamingTokenError::ConfigMintMismatch,
+        constraint = mint.decimals == mint_config.decimals @ GamingTokenError::DecimalsMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: PDA signer for the mint authority
+    #[account(seeds = [b"mint_authority", mint.key().as_ref()], bump)]
+    pub mint_authority: AccountInfo<'info>,
+
+    #[account(mut, token::mint = mint)]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub minter: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ManageMintAuthority<'info> {
+    #[account(mut, has_one = authority)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        address = mint_config.mint @ GamingTokenError::ConfigMintMismatch,
+        constraint = mint.decimals == mint_config.decimals @ GamingTokenError::DecimalsMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Program PDA that may hold the mint authority
+    #[account(seeds = [b"mint_authority", mint.key().as_ref()], bump)]
+    pub mint_authority: AccountInfo<'info>,
+
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(minter: Pubkey)]
+pub struct AddMinter<'info> {
+    #[account(has_one = authority)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"minter", mint_config.mint.as_ref(), minter.as_ref()],
+        bump
+    )]
+    pub minter_record: Account<'info, MinterRecord>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMinter<'info> {
+    #[account(has_one = authority)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"minter", mint_config.mint.as_ref(), minter_record.minter.as_ref()],
+        bump = minter_record.bump
+    )]
+    pub minter_record: Account<'info, MinterRecord>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveMinter<'info> {
+    #[account(has_one = authority)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"minter", mint_config.mint.as_ref(), minter_record.minter.as_ref()],
+        bump = minter_record.bump
+    )]
+    pub minter_record: Account<'info, MinterRecord>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(server: Pubkey)]
+pub struct AddGameServer<'info> {
+    #[account(has_one = authority)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 8 + 32 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"game_server", mint_config.mint.as_ref(), server.as_ref()],
+        bump
+    )]
+    pub game_server: Account<'info, GameServerRecord>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGameServer<'info> {
+    #[account(has_one = authority)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"game_server", mint_config.mint.as_ref(), game_server.server.as_ref()],
+        bump = game_server.bump
+    )]
+    pub game_server: Account<'info, GameServerRecord>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveGameServer<'info> {
+    #[account(has_one = authority)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"game_server", mint_config.mint.as_ref(), game_server.server.as_ref()],
+        bump = game_server.bump
+    )]
+    pub game_server: Account<'info, GameServerRecord>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MintGameReward<'info> {
+    #[account(
+        mut,
+        seeds = [b"mint_config", mint.key().as_ref()],
+        bump,
+        constraint = !mint_config.minting_paused @ GamingTokenError::MintingPaused,
+        constraint = mint_config.mint_authority.is_some() @ GamingTokenError::MintAuthorityRevoked
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"minter", mint.key().as_ref(), server.key().as_ref()],
+        bump = minter_record.bump
+    )]
+    pub minter_record: Account<'info, MinterRecord>,
+
+    #[account(
+        mut,
+        seeds = [b"game_server", mint.key().as_ref(), server.key().as_ref()],
+        bump = game_server.bump
+    )]
+    pub game_server: Account<'info, GameServerRecord>,
+
+    #[account(
+        init_if_needed,
+        payer = server,
+        space = 8 + 32 + 32 + 8 + 8 + 1,
+        seeds = [b"game_reward_recipient", game_server.key().as_ref(), recipient.owner.as_ref()],
+        bump
+    )]
+    pub recipient_record: Account<'info, GameRewardRecipient>,
+
+    #[account(
+        mut,
+        address = mint_config.mint @ GamingTokenError::ConfigMintMismatch,
+        constraint = mint.decimals == mint_config.decimals @ GamingTokenError::DecimalsMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: PDA signer for the mint authority
+    #[account(seeds = [b"mint_authority", mint.key().as_ref()], bump)]
+    pub mint_authority: AccountInfo<'info>,
+
+    #[account(mut, token::mint = mint)]
+    pub recipient: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Recipient owner's record in the player registry; required when
+    /// the server only rewards registered players, checked in the handler
+    pub player_record: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub server: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetMintingPaused<'info> {
+    #[account(mut)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    /// The mint authority or the guardian
+    pub actor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMintConfig<'info> {
+    #[account(mut, has_one = authority)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SyncSupply<'info> {
+    #[account(mut, seeds = [b"mint_config", mint.key().as_ref()], bump)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(constraint = mint.decimals == mint_config.decimals @ GamingTokenError::DecimalsMismatch)]
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct FixDecimalsMismatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"mint_config", mint.key().as_ref()],
+        bump,
+        has_one = authority
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseMintConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"mint_config", mint.key().as_ref()],
+        bump,
+        has_one = authority,
+        close = authority
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(constraint = mint.decimals == mint_config.decimals @ GamingTokenError::DecimalsMismatch)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BurnTokens<'info> {
+    #[account(mut, seeds = [b"mint_config", mint.key().as_ref()], bump)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        address = mint_config.mint,
+        constraint = mint.decimals == mint_config.decimals @ GamingTokenError::DecimalsMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureRedemption<'info> {
+    #[account(has_one = authority, seeds = [b"mint_config", mint.key().as_ref()], bump)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(address = mint_config.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"redemption_config", mint.key().as_ref()],
+        bump
+    )]
+    pub redemption_config: Account<'info, RedemptionConfig>,
+
+    /// Memecoin token account `burn_to_redeem` pays out of
+    pub redemption_pool: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BurnToRedeem<'info> {
+    #[account(mut, seeds = [b"mint_config", mint.key().as_ref()], bump)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        address = mint_config.mint,
+        constraint = mint.decimals == mint_config.decimals @ GamingTokenError::DecimalsMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        has_one = mint,
+        has_one = memecoin_program,
+        has_one = bridge_config,
+        has_one = redemption_pool,
+        seeds = [b"redemption_config", mint.key().as_ref()],
+        bump = redemption_config.bump
+    )]
+    pub redemption_config: Account<'info, RedemptionConfig>,
+
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    /// CHECK: PDA the memecoin program accepts as the only redemption caller
+    #[account(seeds = [b"redemption_authority", mint.key().as_ref()], bump)]
+    pub redemption_authority: AccountInfo<'info>,
+
+    /// CHECK: Matches `redemption_config`
+    #[account(executable)]
+    pub memecoin_program: AccountInfo<'info>,
+
+    /// CHECK: Matches `redemption_config`; validated by the memecoin program
+    #[account(mut)]
+    pub bridge_config: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub redemption_pool: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Validated by the memecoin program
+    pub pool_authority: AccountInfo<'info>,
+
+    #[account(mut, token::mint = redemption_config.memecoin_mint)]
+    pub recipient_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    /// CHECK: Token program of the memecoin mint; validated by the memecoin program
+    pub memecoin_token_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BurnFromTreasury<'info> {
+    #[account(mut, has_one = authority, seeds = [b"mint_config", mint.key().as_ref()], bump)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        address = mint_config.mint,
+        constraint = mint.decimals == mint_config.decimals @ GamingTokenError::DecimalsMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut, token::mint = mint, token::authority = authority)]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(beneficiary: Pubkey, schedule_id: u64)]
+pub struct CreateVesting<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !mint_config.minting_paused @ GamingTokenError::MintingPaused,
+        constraint = mint_config.mint_authority.is_some() @ GamingTokenError::MintAuthorityRevoked
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        address = mint_config.mint @ GamingTokenError::ConfigMintMismatch,
+        constraint = mint.decimals == mint_config.decimals @ GamingTokenError::DecimalsMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: PDA that holds the mint authority once minters are enabled
+    #[account(seeds = [b"mint_authority", mint.key().as_ref()], bump)]
+    pub mint_authority: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 32 + 1,
+        seeds = [b"vesting", mint.key().as_ref(), beneficiary.as_ref(), &schedule_id.to_le_bytes()],
+        bump
+    )]
+    pub vesting: Account<'info, VestingSchedule>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vesting,
+        seeds = [b"vesting_vault", vesting.key().as_ref()],
+        bump
+    )]
+    pub vesting_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    #[account(mut, has_one = beneficiary, has_one = mint)]
+    pub vesting: Account<'info, VestingSchedule>,
+
+    #[account(mut, address = vesting.vault)]
+    pub vesting_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = mint, token::authority = beneficiary)]
+    pub beneficiary_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub beneficiary: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeVesting<'info> {
+    #[account(has_one = authority, has_one = mint)]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        has_one = mint,
+        constraint = !vesting.revoked @ GamingTokenError::VestingRevoked
+    )]
+    pub vesting: Account<'info, VestingSchedule>,
+
+    #[account(mut, address = vesting.vault)]
+    pub vesting_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Receives the unvested remainder.
+    #[account(mut, token::mint = mint)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+    #[account(constraint = mint.decimals == mint_config.decimals @ GamingTokenError::DecimalsMismatch)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeStakingConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 1 + 8 + 32 + 8 + 8 + 1,
+        seeds = [b"staking_config"],
+        bump
+    )]
+    pub staking_config: Account<'info, StakingConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateStakingConfig<'info> {
+    #[account(mut, seeds = [b"staking_config"], bump = staking_config.bump, has_one = admin)]
+    pub staking_config: Account<'info, StakingConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePool<'info> {
+    #[account(mut, seeds = [b"staking_config"], bump = staking_config.bump)]
+    pub staking_config: Account<'info, StakingConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_registry", staking_config.registry_page().to_le_bytes().as_ref()],
+        bump = registry_page.bump
+    )]
+    pub registry_page: Account<'info, PoolRegistryPage>,
+
+    /// CHECK: receives the creation fee in lamports
+    #[account(mut, address = staking_config.fee_recipient)]
+    pub fee_recipient: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + 32 + 32 + 32 + 8 + (4 + MAX_LOCK_TIERS * LockTier::SIZE) + 2 + 8 + 8 + 8 + 8 + 2 + 2 + 8 + 1 + 1 + 32 + 8 + 8 + 16 + 8
+            + 8 + 8 + 8 + 8 + 16 + 8
+            + (4 + MAX_REWARD_SLOTS * RewardSlot::SIZE)
+            + (1 + EmissionSchedule::SIZE)
+            + 1 + 2
+            + 2 + 32
+            + 2
+            + 8
+            + 32 + 8 + 2
+            + 8
+            + 32 + 8 + 2 + 1
+            + (4 + RATE_HISTORY_LEN * RateSnapshot::SIZE) + 2
+            + 1 + 8
+            + 8 + 1
+            + 8
+            + 8 + 1,
+        seeds = [b"stake_pool", mint.key().as_ref()],
+        bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = vault_authority,
         seeds = [b"stake_vault", mint.key().as_ref()],
         bump
     )]
-    pub stake_vault: Account<'info, TokenAccount>,
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        token::mint = reward_mint,
+        token::authority = reward_vault_authority,
+        seeds = [b"reward_vault", mint.key().as_ref()],
+        bump
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"vault_authority", mint.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"reward_vault_authority", mint.key().as_ref()],
+        bump
+    )]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    /// CHECK: exists only once this mint's pool has been closed
+    #[account(
+        seeds = [b"closed_pool", mint.key().as_ref()],
+        bump,
+        constraint = closed_pool.data_is_empty() @ GamingTokenError::PoolClosed
+    )]
+    pub closed_pool: AccountInfo<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Paid out as rewards; pass `mint` again for a single-token pool.
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewardDebt<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = mint,
+        seeds = [b"stake_positions", owner.key().as_ref(), mint.key().as_ref()],
+        bump = stake_positions.bump
+    )]
+    pub stake_positions: Account<'info, StakePositions>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", mint.key().as_ref()],
+        bump,
+        token::mint = stake_pool.reward_mint
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA that owns the reward vault
+    #[account(seeds = [b"reward_vault_authority", mint.key().as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    /// Required while the pool charges a claim fee.
+    #[account(mut, address = stake_pool.fee_vault)]
+    pub fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = stake_pool.reward_mint, token::authority = owner)]
+    pub user_reward_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(address = stake_pool.reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SnapshotPoolRate<'info> {
+    #[account(mut, seeds = [b"stake_pool", stake_pool.mint.as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+}
+
+#[derive(Accounts)]
+#[instruction(snapshot_id: u64)]
+pub struct CreateSnapshot<'info> {
+    #[account(has_one = authority, seeds = [b"stake_pool", stake_pool.mint.as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 4 + 32 + 1 + 1,
+        seeds = [b"snapshot", stake_pool.key().as_ref(), &snapshot_id.to_le_bytes()],
+        bump
+    )]
+    pub snapshot: Account<'info, StakeSnapshot>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordSnapshotEntry<'info> {
+    #[account(
+        mut,
+        seeds = [b"snapshot", snapshot.stake_pool.as_ref(), &snapshot.snapshot_id.to_le_bytes()],
+        bump = snapshot.bump
+    )]
+    pub snapshot: Account<'info, StakeSnapshot>,
+
+    /// Pays rent for the entries
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeSnapshot<'info> {
+    #[account(has_one = authority)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        has_one = stake_pool,
+        seeds = [b"snapshot", stake_pool.key().as_ref(), &snapshot.snapshot_id.to_le_bytes()],
+        bump = snapshot.bump
+    )]
+    pub snapshot: Account<'info, StakeSnapshot>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    #[account(mut, seeds = [b"stake_pool", stake_pool.mint.as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(mut, address = stake_pool.reward_vault)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = stake_pool.reward_mint, token::authority = funder)]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub funder: Signer<'info>,
+    #[account(address = stake_pool.reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct NotifyRewardAmount<'info> {
+    #[account(mut, has_one = authority)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// The funded slot's vault; checked against the slot index
+    #[account(mut)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = reward_vault.mint, token::authority = authority)]
+    pub authority_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+    #[account(address = reward_vault.mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RollEpoch<'info> {
+    #[account(mut)]
+    pub stake_pool: Account<'info, StakePool>,
+}
+
+#[derive(Accounts)]
+pub struct AddRewardToken<'info> {
+    #[account(mut, has_one = authority)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = reward_mint,
+        token::authority = reward_vault_authority,
+        seeds = [b"reward_slot_vault", stake_pool.mint.as_ref(), reward_mint.key().as_ref()],
+        bump
+    )]
+    pub slot_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", stake_pool.mint.as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveRewardToken<'info> {
+    #[account(mut, has_one = authority)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Checked against the slot index
+    #[account(mut)]
+    pub slot_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", stake_pool.mint.as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    #[account(mut, token::mint = slot_vault.mint)]
+    pub authority_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+    #[account(address = slot_vault.mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawExcessRewards<'info> {
+    #[account(mut, has_one = authority, has_one = reward_vault)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(mut)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", stake_pool.mint.as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    #[account(mut, token::mint = stake_pool.reward_mint)]
+    pub authority_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+    #[account(address = stake_pool.reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CreateFeeVault<'info> {
+    #[account(mut, has_one = authority)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = reward_mint,
+        token::authority = reward_vault_authority,
+        seeds = [b"fee_vault", stake_pool.mint.as_ref()],
+        bump
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", stake_pool.mint.as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    #[account(address = stake_pool.reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCollectedFees<'info> {
+    #[account(has_one = authority, has_one = fee_vault)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(mut)]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", stake_pool.mint.as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    #[account(mut, token::mint = stake_pool.reward_mint)]
+    pub authority_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+    #[account(address = stake_pool.reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(page_index: u32)]
+pub struct AllocateRegistryPage<'info> {
+    #[account(seeds = [b"staking_config"], bump = staking_config.bump)]
+    pub staking_config: Account<'info, StakingConfig>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 4 + (4 + POOL_REGISTRY_PAGE_SIZE * PoolRegistryEntry::SIZE) + 1,
+        seeds = [b"pool_registry", page_index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub registry_page: Account<'info, PoolRegistryPage>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPoolDeprecated<'info> {
+    #[account(has_one = authority)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_registry", registry_page.page_index.to_le_bytes().as_ref()],
+        bump = registry_page.bump
+    )]
+    pub registry_page: Account<'info, PoolRegistryPage>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BeginSunset<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        has_one = mint,
+        seeds = [b"stake_pool", mint.key().as_ref()],
+        bump = stake_pool.bump,
+        constraint = !stake_pool.is_sunset() @ GamingTokenError::PoolSunset
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        constraint = successor_pool.key() != stake_pool.key() @ GamingTokenError::InvalidSuccessorPool,
+        constraint = !successor_pool.is_sunset() @ GamingTokenError::InvalidSuccessorPool,
+        constraint = successor_pool.mint == successor_mint.key() @ GamingTokenError::InvalidSuccessorPool
+    )]
+    pub successor_pool: Account<'info, StakePool>,
+
+    pub successor_mint: InterfaceAccount<'info, Mint>,
+
+    /// Funded by the authority with successor tokens for migrating positions
+    #[account(
+        init,
+        payer = authority,
+        token::mint = successor_mint,
+        token::authority = vault_authority,
+        seeds = [b"migration_vault", mint.key().as_ref()],
+        bump
+    )]
+    pub migration_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA that owns the stake and migration vaults
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: AccountInfo<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_position_index: u16)]
+pub struct MigratePosition<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = mint,
+        close = owner,
+        seeds = [
+            b"stake",
+            owner.key().as_ref(),
+            mint.key().as_ref(),
+            &stake_account.position_index.to_le_bytes()
+        ],
+        bump,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive,
+        constraint = !stake_account.slash_pending @ GamingTokenError::SlashPending,
+        constraint = stake_account.receipt_mint.is_none() @ GamingTokenError::ReceiptHolderOnly
+    )]
+    pub stake_account: Box<Account<'info, StakeAccount>>,
+
+    #[account(
+        mut,
+        has_one = successor_pool @ GamingTokenError::InvalidSuccessorPool,
+        seeds = [b"stake_pool", mint.key().as_ref()],
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Box<Account<'info, StakePool>>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_positions", owner.key().as_ref(), mint.key().as_ref()],
+        bump = stake_positions.bump
+    )]
+    pub stake_positions: Account<'info, StakePositions>,
+
+    #[account(mut, seeds = [b"stake_vault", mint.key().as_ref()], bump)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA that owns the stake and migration vaults
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", mint.key().as_ref()],
+        bump,
+        token::mint = stake_pool.reward_mint
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", mint.key().as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    /// Required while the pool charges a claim fee.
+    #[account(mut, address = stake_pool.fee_vault)]
+    pub fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = stake_pool.reward_mint)]
+    pub user_reward_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(address = stake_pool.reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut, seeds = [b"stake_pool", successor_mint.key().as_ref()], bump = successor_pool.bump)]
+    pub successor_pool: Box<Account<'info, StakePool>>,
+
+    #[account(mut, seeds = [b"stake_vault", successor_mint.key().as_ref()], bump)]
+    pub successor_stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"migration_vault", mint.key().as_ref()], bump)]
+    pub migration_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + 32 + 32 + 2 + 2 + 8 + 1,
+        seeds = [b"stake_positions", owner.key().as_ref(), successor_mint.key().as_ref()],
+        bump
+    )]
+    pub successor_positions: Account<'info, StakePositions>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = StakeAccount::SPACE,
+        seeds = [
+            b"stake",
+            owner.key().as_ref(),
+            successor_mint.key().as_ref(),
+            &new_position_index.to_le_bytes()
+        ],
+        bump
+    )]
+    pub new_stake_account: Box<Account<'info, StakeAccount>>,
+
+    pub successor_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SweepSunset<'info> {
+    #[account(
+        mut,
+        has_one = mint,
+        has_one = stake_vault,
+        seeds = [b"stake_pool", mint.key().as_ref()],
+        bump = stake_pool.bump,
+        constraint = !stake_pool.sunset_swept @ GamingTokenError::SunsetSwept
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(mut)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = vault_authority,
+        seeds = [b"sunset_escrow", mint.key().as_ref()],
+        bump
+    )]
+    pub sunset_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA that owns the stake vault and the escrow
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: AccountInfo<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSunsetEscrow<'info> {
+    #[account(
+        mut,
+        has_one = mint,
+        close = holder,
+        seeds = [
+            b"stake",
+            stake_account.owner.as_ref(),
+            mint.key().as_ref(),
+            &stake_account.position_index.to_le_bytes()
+        ],
+        bump,
+        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive,
+        constraint = !stake_account.slash_pending @ GamingTokenError::SlashPending,
+        constraint = stake_account.is_controlled_by(&holder.key(), &receipt_token_account)
+            @ GamingTokenError::Unauthorized,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_positions", stake_account.owner.as_ref(), mint.key().as_ref()],
+        bump = stake_positions.bump
+    )]
+    pub stake_positions: Account<'info, StakePositions>,
+
+    /// Only exists once `sweep_sunset` has run
+    #[account(mut, seeds = [b"sunset_escrow", mint.key().as_ref()], bump)]
+    pub sunset_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA that owns the escrow
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: AccountInfo<'info>,
+
+    /// Proves control of a receipt position; ignored for plain ones.
+    pub receipt_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = mint)]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        has_one = mint,
+        has_one = reward_mint,
+        has_one = stake_vault,
+        has_one = reward_vault,
+        close = authority
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_registry", registry_page.page_index.to_le_bytes().as_ref()],
+        bump = registry_page.bump
+    )]
+    pub registry_page: Account<'info, PoolRegistryPage>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 1,
+        seeds = [b"closed_pool", mint.key().as_ref()],
+        bump
+    )]
+    pub closed_pool: Account<'info, ClosedPool>,
+
+    #[account(mut)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, address = stake_pool.fee_vault)]
+    pub fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(seeds = [b"reward_vault_authority", mint.key().as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        token::mint = reward_mint,
+        token::authority = authority
+    )]
+    pub authority_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeSlash<'info> {
+    #[account(has_one = authority)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = stake_account.mint == stake_pool.mint @ GamingTokenError::Unauthorized,
+        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 2 + 32 + 32 + 8 + 8 + 1,
+        seeds = [b"slash_proposal", stake_account.key().as_ref()],
+        bump
+    )]
+    pub slash_proposal: Account<'info, SlashProposal>,
+
+    #[account(token::mint = stake_pool.mint)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSlash<'info> {
+    #[account(has_one = authority)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(
+        mut,
+        close = authority,
+        has_one = stake_pool,
+        has_one = stake_account,
+        seeds = [b"slash_proposal", stake_account.key().as_ref()],
+        bump = slash_proposal.bump
+    )]
+    pub slash_proposal: Account<'info, SlashProposal>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteSlash<'info> {
+    #[account(mut, has_one = authority, has_one = stake_vault)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(
+        mut,
+        close = authority,
+        has_one = stake_pool,
+        has_one = stake_account,
+        has_one = destination,
+        seeds = [b"slash_proposal", stake_account.key().as_ref()],
+        bump = slash_proposal.bump
+    )]
+    pub slash_proposal: Account<'info, SlashProposal>,
+
+    #[account(mut)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"vault_authority", stake_pool.mint.as_ref()], bump)]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(address = stake_pool.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetEmergencyDestination<'info> {
+    #[account(mut, has_one = authority)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(token::mint = stake_pool.reward_mint)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyWithdrawVault<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        has_one = reward_vault,
+        constraint = stake_pool.emergency_mode @ GamingTokenError::EmergencyModeInactive,
+        constraint = stake_pool.emergency_destination != Pubkey::default()
+            @ GamingTokenError::EmergencyDestinationUnset
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(mut)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", stake_pool.mint.as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    #[account(mut, address = stake_pool.emergency_destination @ GamingTokenError::InvalidEmergencyDestination)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+    #[account(address = stake_pool.reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(proposal_id: u64)]
+pub struct CreateProposal<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 32 + (4 + MAX_PROPOSAL_DESCRIPTION_LEN) + 8 + 8 + 8 + 8 + 1 + 1 + 1,
+        seeds = [b"proposal", stake_pool.key().as_ref(), &proposal_id.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(has_one = authority)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SnapshotVotingPower<'info> {
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(address = proposal.pool)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = stake_account.mint == stake_pool.mint @ GamingTokenError::NotEligibleToVote,
+        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    /// One record per position and proposal, so a position can only be counted once
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + (1 + 32) + 8 + 1 + 1,
+        seeds = [b"voting_power", proposal.key().as_ref(), stake_account.key().as_ref()],
+        bump
+    )]
+    pub voting_power_record: Account<'info, VotingPowerRecord>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SnapshotDelegatedPower<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(address = proposal.pool)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = stake_account.mint == stake_pool.mint @ GamingTokenError::NotEligibleToVote,
+        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    /// Shares the per-position seeds with direct snapshots, so each position counts once
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + (1 + 32) + 8 + 1 + 1,
+        seeds = [b"voting_power", proposal.key().as_ref(), stake_account.key().as_ref()],
+        bump
+    )]
+    pub voting_power_record: Account<'info, VotingPowerRecord>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + 32 + 32 + 8 + 4 + 1 + 1 + 1,
+        seeds = [b"delegated_power", proposal.key().as_ref(), delegate.key().as_ref()],
+        bump
+    )]
+    pub delegated_power: Account<'info, DelegatedPower>,
+
+    /// CHECK: Only used as a seed; checked against the position's delegate
+    pub delegate: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"voting_power", proposal.key().as_ref(), stake_account.key().as_ref()],
+        bump = voting_power_record.bump
+    )]
+    pub voting_power_record: Account<'info, VotingPowerRecord>,
+
+    #[account(
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = stake_account.is_controlled_by(&owner.key(), &receipt_token_account)
+            @ GamingTokenError::Unauthorized
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    /// Holder's account for the position receipt, when the position has one.
+    pub receipt_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CastDelegatedVote<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"delegated_power", proposal.key().as_ref(), delegate.key().as_ref()],
+        bump = delegated_power.bump
+    )]
+    pub delegated_power: Account<'info, DelegatedPower>,
+
+    pub delegate: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeProposal<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+pub struct QueuePoolUpdate<'info> {
+    #[account(seeds = [b"staking_config"], bump = staking_config.bump)]
+    pub staking_config: Account<'info, StakingConfig>,
+
+    #[account(has_one = authority)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + (1 + 8) + (1 + 8 + 8 + 4) + (1 + 2) + 8 + 8 + 1,
+        seeds = [b"pool_update", stake_pool.key().as_ref()],
+        bump
+    )]
+    pub pending_update: Account<'info, PendingPoolUpdate>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecutePoolUpdate<'info> {
+    #[account(mut, has_one = authority)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        close = authority,
+        has_one = stake_pool,
+        seeds = [b"pool_update", stake_pool.key().as_ref()],
+        bump = pending_update.bump
+    )]
+    pub pending_update: Account<'info, PendingPoolUpdate>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPoolUpdate<'info> {
+    #[account(has_one = authority)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        close = authority,
+        has_one = stake_pool,
+        seeds = [b"pool_update", stake_pool.key().as_ref()],
+        bump = pending_update.bump
+    )]
+    pub pending_update: Account<'info, PendingPoolUpdate>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetLpPool<'info> {
+    #[account(seeds = [b"staking_config"], bump = staking_config.bump, has_one = admin)]
+    pub staking_config: Account<'info, StakingConfig>,
+
+    #[account(mut)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateStakePool<'info> {
+    #[account(mut, has_one = authority)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_index: u16)]
+pub struct StakeTokens<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = StakeAccount::SPACE,
+        seeds = [
+            b"stake",
+            authority.key().as_ref(),
+            mint.key().as_ref(),
+            &position_index.to_le_bytes()
+        ],
+        bump
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 32 + 2 + 2 + 8 + 1,
+        seeds = [b"stake_positions", authority.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub stake_positions: Account<'info, StakePositions>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(mut, address = stake_pool.stake_vault)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = mint)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Fresh keypairs for the optional position receipt NFT and its first holder account.
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = receipt_authority,
+    )]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = receipt_mint,
+        token::authority = authority,
+    )]
+    pub receipt_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: PDA mint authority for receipt NFTs
+    #[account(seeds = [b"receipt_authority"], bump)]
+    pub receipt_authority: Option<AccountInfo<'info>>,
+
+    /// Created when the first stake names a referrer.
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 8 + 1,
+        seeds = [b"referral", mint.key().as_ref(), authority.key().as_ref()],
+        bump
+    )]
+    pub referral: Option<Account<'info, Referral>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 1 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 64,
+        seeds = [b"staker_stats", stake_pool.key().as_ref(), authority.key().as_ref()],
+        bump
+    )]
+    pub staker_stats: Account<'info, StakerStats>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AddToStake<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = mint,
+        seeds = [
+            b"stake",
+            owner.key().as_ref(),
+            mint.key().as_ref(),
+            &stake_account.position_index.to_le_bytes()
+        ],
+        bump,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive,
+        constraint = stake_account.receipt_mint.is_none() @ GamingTokenError::ReceiptHolderOnly
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(mut, seeds = [b"stake_vault", mint.key().as_ref()], bump)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", mint.key().as_ref()],
+        bump,
+        token::mint = stake_pool.reward_mint
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", mint.key().as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    /// Required while the pool charges a claim fee.
+    #[account(mut, address = stake_pool.fee_vault)]
+    pub fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = mint)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = stake_pool.reward_mint)]
+    pub user_reward_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + 1 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 64,
+        seeds = [b"staker_stats", stake_pool.key().as_ref(), stake_account.owner.as_ref()],
+        bump
+    )]
+    pub staker_stats: Account<'info, StakerStats>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(address = stake_pool.reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_position_index: u16)]
+pub struct SplitPosition<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = mint,
+        seeds = [
+            b"stake",
+            owner.key().as_ref(),
+            mint.key().as_ref(),
+            &stake_account.position_index.to_le_bytes()
+        ],
+        bump,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive,
+        constraint = !stake_account.slash_pending @ GamingTokenError::SlashPending,
+        constraint = stake_account.receipt_mint.is_none() @ GamingTokenError::ReceiptHolderOnly
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = StakeAccount::SPACE,
+        seeds = [
+            b"stake",
+            owner.key().as_ref(),
+            mint.key().as_ref(),
+            &new_position_index.to_le_bytes()
+        ],
+        bump
+    )]
+    pub new_stake_account: Account<'info, StakeAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_positions", owner.key().as_ref(), mint.key().as_ref()],
+        bump = stake_positions.bump
+    )]
+    pub stake_positions: Account<'info, StakePositions>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MergePositions<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = mint,
+        seeds = [
+            b"stake",
+            owner.key().as_ref(),
+            mint.key().as_ref(),
+            &stake_account.position_index.to_le_bytes()
+        ],
+        bump,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive,
+        constraint = !stake_account.slash_pending @ GamingTokenError::SlashPending,
+        constraint = stake_account.receipt_mint.is_none() @ GamingTokenError::ReceiptHolderOnly
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    /// Folded into `stake_account` and closed.
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        has_one = mint,
+        seeds = [
+            b"stake",
+            owner.key().as_ref(),
+            mint.key().as_ref(),
+            &merged_stake_account.position_index.to_le_bytes()
+        ],
+        bump,
+        constraint = is_current_layout(&merged_stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = merged_stake_account.key() != stake_account.key() @ GamingTokenError::SamePosition,
+        constraint = merged_stake_account.is_active @ GamingTokenError::StakeNotActive,
+        constraint = !merged_stake_account.slash_pending @ GamingTokenError::SlashPending,
+        constraint = merged_stake_account.receipt_mint.is_none() @ GamingTokenError::ReceiptHolderOnly
+    )]
+    pub merged_stake_account: Account<'info, StakeAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_positions", owner.key().as_ref(), mint.key().as_ref()],
+        bump = stake_positions.bump
+    )]
+    pub stake_positions: Account<'info, StakePositions>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EndWarmup<'info> {
+    #[account(
+        mut,
+        has_one = mint,
+        seeds = [
+            b"stake",
+            stake_account.owner.as_ref(),
+            mint.key().as_ref(),
+            &stake_account.position_index.to_le_bytes()
+        ],
+        bump,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateStakePosition<'info> {
+    #[account(
+        mut,
+        has_one = mint,
+        seeds = [
+            b"stake",
+            stake_account.owner.as_ref(),
+            mint.key().as_ref(),
+            &stake_account.position_index.to_le_bytes()
+        ],
+        bump,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive,
+        constraint = stake_account.is_controlled_by(&owner.key(), &receipt_token_account)
+            @ GamingTokenError::Unauthorized
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Holder's account for the position receipt, when the position has one.
+    pub receipt_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub owner: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeTokens<'info> {
+    #[account(
+        mut,
+        has_one = mint,
+        close = owner,
+        seeds = [
+            b"stake",
+            stake_account.owner.as_ref(),
+            mint.key().as_ref(),
+            &stake_account.position_index.to_le_bytes()
+        ],
+        bump,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive,
+        constraint = !stake_account.slash_pending @ GamingTokenError::SlashPending,
+        constraint = stake_account.is_controlled_by(&owner.key(), &receipt_token_account)
+            @ GamingTokenError::Unauthorized
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_positions", stake_account.owner.as_ref(), mint.key().as_ref()],
+        bump = stake_positions.bump
+    )]
+    pub stake_positions: Account<'info, StakePositions>,
+
+    #[account(mut, seeds = [b"stake_vault", mint.key().as_ref()], bump)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", mint.key().as_ref()],
+        bump,
+        token::mint = stake_pool.reward_mint
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", mint.key().as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    /// Required while the pool charges a claim fee.
+    #[account(mut, address = stake_pool.fee_vault)]
+    pub fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = stake_pool.reward_mint, token::authority = owner)]
+    pub user_reward_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Holder's account for the position receipt, when the position has one.
+    #[account(mut)]
+    pub receipt_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: The stake owner, or the receipt holder for receipt positions
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + 1 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 64,
+        seeds = [b"staker_stats", stake_pool.key().as_ref(), stake_account.owner.as_ref()],
+        bump
+    )]
+    pub staker_stats: Account<'info, StakerStats>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(address = stake_pool.reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PartialUnstake<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = mint,
+        seeds = [
+            b"stake",
+            owner.key().as_ref(),
+            mint.key().as_ref(),
+            &stake_account.position_index.to_le_bytes()
+        ],
+        bump,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive,
+        constraint = !stake_account.slash_pending @ GamingTokenError::SlashPending,
+        constraint = stake_account.receipt_mint.is_none() @ GamingTokenError::ReceiptHolderOnly
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_positions", owner.key().as_ref(), mint.key().as_ref()],
+        bump = stake_positions.bump
+    )]
+    pub stake_positions: Account<'info, StakePositions>,
+
+    #[account(mut, seeds = [b"stake_vault", mint.key().as_ref()], bump)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", mint.key().as_ref()],
+        bump,
+        token::mint = stake_pool.reward_mint
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", mint.key().as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    /// Required while the pool charges a claim fee.
+    #[account(mut, address = stake_pool.fee_vault)]
+    pub fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = stake_pool.reward_mint, token::authority = owner)]
+    pub user_reward_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + 1 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 64,
+        seeds = [b"staker_stats", stake_pool.key().as_ref(), stake_account.owner.as_ref()],
+        bump
+    )]
+    pub staker_stats: Account<'info, StakerStats>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(address = stake_pool.reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SoftStake<'info> {
+    /// One soft stake per token account, as SPL allows only one delegate
+    #[account(
+        init,
+        payer = owner,
+        space = SoftStakePosition::SPACE,
+        seeds = [b"soft_stake", token_account.key().as_ref()],
+        bump
+    )]
+    pub soft_position: Box<Account<'info, SoftStakePosition>>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA approved as delegate over the staked tokens
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: AccountInfo<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ValidateSoftStake<'info> {
+    #[account(
+        mut,
+        has_one = token_account,
+        seeds = [b"soft_stake", token_account.key().as_ref()],
+        bump = soft_position.bump,
+        constraint = soft_position.position.is_active @ GamingTokenError::StakeNotActive
+    )]
+    pub soft_position: Box<Account<'info, SoftStakePosition>>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_pool", soft_position.position.mint.as_ref()],
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA the delegation must still point at
+    #[account(seeds = [b"vault_authority", soft_position.position.mint.as_ref()], bump)]
+    pub vault_authority: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SoftUnstake<'info> {
+    #[account(
+        mut,
+        has_one = token_account,
+        close = owner,
+        seeds = [b"soft_stake", token_account.key().as_ref()],
+        bump = soft_position.bump,
+        constraint = soft_position.position.owner == owner.key() @ GamingTokenError::Unauthorized,
+        constraint = soft_position.position.is_active @ GamingTokenError::StakeNotActive
+    )]
+    pub soft_position: Box<Account<'info, SoftStakePosition>>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(mut, token::mint = mint, token::authority = owner)]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA the tokens are delegated to
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", mint.key().as_ref()],
+        bump,
+        token::mint = stake_pool.reward_mint
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA that owns the reward vault
+    #[account(seeds = [b"reward_vault_authority", mint.key().as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    /// Required while the pool charges a claim fee.
+    #[account(mut, address = stake_pool.fee_vault)]
+    pub fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = stake_pool.reward_mint)]
+    pub user_reward_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(address = stake_pool.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(address = stake_pool.reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CompoundRewards<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = mint,
+        seeds = [
+            b"stake",
+            owner.key().as_ref(),
+            mint.key().as_ref(),
+            &stake_account.position_index.to_le_bytes()
+        ],
+        bump,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive,
+        constraint = stake_account.receipt_mint.is_none() @ GamingTokenError::ReceiptHolderOnly
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    /// Rewards in another mint can only be claimed, not restaked
+    #[account(
+        mut,
+        seeds = [b"stake_pool", mint.key().as_ref()],
+        bump = stake_pool.bump,
+        constraint = stake_pool.reward_mint == stake_pool.mint @ GamingTokenError::CompoundUnavailable
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(mut, seeds = [b"stake_vault", mint.key().as_ref()], bump)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", mint.key().as_ref()],
+        bump,
+        token::mint = stake_pool.reward_mint
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", mint.key().as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    /// Required while the pool charges a claim fee.
+    #[account(mut, address = stake_pool.fee_vault)]
+    pub fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + 1 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 64,
+        seeds = [b"staker_stats", stake_pool.key().as_ref(), stake_account.owner.as_ref()],
+        bump
+    )]
+    pub staker_stats: Account<'info, StakerStats>,
+
+    /// CHECK: the owner's solitaire `PlayerStats`, for the achievement bonus;
+    /// program, address and layout are checked in the handler
+    pub player_stats: Option<UncheckedAccount<'info>>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateCompoundVault<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 1,
+        seeds = [b"compound_vault", mint.key().as_ref()],
+        bump
+    )]
+    pub compound_vault: Account<'info, CompoundVault>,
+
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = mint.decimals,
+        mint::authority = compound_vault,
+        seeds = [b"compound_shares", mint.key().as_ref()],
+        bump
+    )]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = StakeAccount::SPACE,
+        seeds = [
+            b"stake",
+            compound_vault.key().as_ref(),
+            mint.key().as_ref(),
+            &0u16.to_le_bytes()
+        ],
+        bump
+    )]
+    pub vault_position: Account<'info, StakeAccount>,
+
+    /// Rewards in another mint can't be compounded
+    #[account(
+        mut,
+        has_one = authority,
+        seeds = [b"stake_pool", mint.key().as_ref()],
+        bump = stake_pool.bump,
+        constraint = stake_pool.reward_mint == stake_pool.mint @ GamingTokenError::CompoundUnavailable
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositToVault<'info> {
+    #[account(
+        mut,
+        has_one = mint,
+        has_one = share_mint,
+        seeds = [b"compound_vault", mint.key().as_ref()],
+        bump = compound_vault.bump
+    )]
+    pub compound_vault: Account<'info, CompoundVault>,
+
+    #[account(
+        mut,
+        has_one = mint,
+        seeds = [
+            b"stake",
+            compound_vault.key().as_ref(),
+            mint.key().as_ref(),
+            &0u16.to_le_bytes()
+        ],
+        bump,
+        constraint = is_current_layout(&vault_position) @ GamingTokenError::MigrationRequired,
+        constraint = !vault_position.slash_pending @ GamingTokenError::SlashPending
+    )]
+    pub vault_position: Account<'info, StakeAccount>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(mut, seeds = [b"stake_vault", mint.key().as_ref()], bump)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", mint.key().as_ref()],
+        bump,
+        token::mint = stake_pool.reward_mint
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", mint.key().as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    /// Required while the pool charges a claim fee.
+    #[account(mut, address = stake_pool.fee_vault)]
+    pub fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut, token::mint = mint)]
+    pub depositor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = share_mint)]
+    pub depositor_share_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub depositor: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFromVault<'info> {
+    #[account(
+        mut,
+        has_one = mint,
+        has_one = share_mint,
+        seeds = [b"compound_vault", mint.key().as_ref()],
+        bump = compound_vault.bump
+    )]
+    pub compound_vault: Account<'info, CompoundVault>,
+
+    #[account(
+        mut,
+        has_one = mint,
+        seeds = [
+            b"stake",
+            compound_vault.key().as_ref(),
+            mint.key().as_ref(),
+            &0u16.to_le_bytes()
+        ],
+        bump,
+        constraint = is_current_layout(&vault_position) @ GamingTokenError::MigrationRequired,
+        constraint = !vault_position.slash_pending @ GamingTokenError::SlashPending
+    )]
+    pub vault_position: Account<'info, StakeAccount>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(mut, seeds = [b"stake_vault", mint.key().as_ref()], bump)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", mint.key().as_ref()],
+        bump,
+        token::mint = stake_pool.reward_mint
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", mint.key().as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    /// Required while the pool charges a claim fee.
+    #[account(mut, address = stake_pool.fee_vault)]
+    pub fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: PDA that owns the stake vault
+    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut, token::mint = share_mint)]
+    pub withdrawer_share_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = mint)]
+    pub withdrawer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub withdrawer: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct HarvestVault<'info> {
+    #[account(
+        mut,
+        has_one = mint,
+        seeds = [b"compound_vault", mint.key().as_ref()],
+        bump = compound_vault.bump
+    )]
+    pub compound_vault: Account<'info, CompoundVault>,
+
+    #[account(
+        mut,
+        has_one = mint,
+        seeds = [
+            b"stake",
+            compound_vault.key().as_ref(),
+            mint.key().as_ref(),
+            &0u16.to_le_bytes()
+        ],
+        bump,
+        constraint = is_current_layout(&vault_position) @ GamingTokenError::MigrationRequired,
+        constraint = !vault_position.slash_pending @ GamingTokenError::SlashPending
+    )]
+    pub vault_position: Account<'info, StakeAccount>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(mut, seeds = [b"stake_vault", mint.key().as_ref()], bump)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", mint.key().as_ref()],
+        bump,
+        token::mint = stake_pool.reward_mint
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", mint.key().as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    /// Required while the pool charges a claim fee.
+    #[account(mut, address = stake_pool.fee_vault)]
+    pub fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateStakeAccount<'info> {
+    /// CHECK: an old-layout stake account; discriminator, layout and address are checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub stake_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetPendingRewards<'info> {
+    #[account(
+        has_one = mint,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(
+        seeds = [b"stake_positions", stake_account.owner.as_ref(), mint.key().as_ref()],
+        bump = stake_positions.bump
+    )]
+    pub stake_positions: Account<'info, StakePositions>,
+
+    #[account(seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(address = stake_pool.reward_vault)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct AssertMinStake<'info> {
+    #[account(seeds = [b"stake_pool", stake_pool.mint.as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(
+        mut,
+        has_one = mint,
+        seeds = [
+            b"stake",
+            stake_account.owner.as_ref(),
+            mint.key().as_ref(),
+            &stake_account.position_index.to_le_bytes()
+        ],
+        bump,
+        constraint = is_current_layout(&stake_account) @ GamingTokenError::MigrationRequired,
+        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive,
+        constraint = stake_account.is_controlled_by(&owner.key(), &receipt_token_account)
+            @ GamingTokenError::Unauthorized
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    #[account(mut, seeds = [b"stake_pool", mint.key().as_ref()], bump = stake_pool.bump)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_vault", mint.key().as_ref()],
+        bump,
+        token::mint = stake_pool.reward_mint
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"reward_vault_authority", mint.key().as_ref()], bump)]
+    pub reward_vault_authority: AccountInfo<'info>,
+
+    /// Required while the pool charges a claim fee.
+    #[account(mut, address = stake_pool.fee_vault)]
+    pub fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = stake_pool.reward_mint, token::authority = owner)]
+    pub user_reward_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Holder's account for the position receipt, when the position has one.
+    #[account(mut)]
+    pub receipt_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: The stake owner, or the receipt holder for receipt positions
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + 1 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 64,
+        seeds = [b"staker_stats", stake_pool.key().as_ref(), stake_account.owner.as_ref()],
+        bump
+    )]
+    pub staker_stats: Account<'info, StakerStats>,
+
+    /// The position owner's referral, if they were referred; pass it with the
+    /// referrer's token account to pay the referral bonus.
+    #[account(
+        seeds = [b"referral", mint.key().as_ref(), stake_account.owner.as_ref()],
+        bump = referral.bump
+    )]
+    pub referral: Option<Account<'info, Referral>>,
+
+    #[account(mut, token::mint = stake_pool.reward_mint)]
+    pub referrer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: the claimer's solitaire `PlayerStats`, for the achievement bonus;
+    /// program, address and layout are checked in the handler
+    pub player_stats: Option<UncheckedAccount<'info>>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(address = stake_pool.reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+pub struct MintConfig {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub token_name: String,
+    pub token_symbol: String,
+    pub decimals: u8,
+    pub total_supply: u64,
+    /// Hard cap on `total_supply`; 0 means uncapped. Can only be lowered.
+    pub max_supply: u64,
+    /// Stops every mint path; burns and staking are unaffected.
+    pub minting_paused: bool,
+    /// Cold key that can pause minting but not unpause or mint; default means none.
+    pub guardian: Pubkey,
+    /// Set by `finalize_metadata`; name and symbol can no longer change.
+    pub metadata_finalized: bool,
+    /// Mirrors the SPL mint's authorities; `None` once revoked, which is permanent.
+    pub mint_authority: Option<Pubkey>,
+    pub freeze_authority: Option<Pubkey>,
+    pub is_initialized: bool,
+    pub created_at: i64,
+}
+
+/// Memecoin redemption for one gaming mint, used by `burn_to_redeem`.
+#[account]
+pub struct RedemptionConfig {
+    pub mint: Pubkey,
+    pub memecoin_program: Pubkey,
+    /// The memecoin program's bridge config that names this mint.
+    pub bridge_config: Pubkey,
+    pub memecoin_mint: Pubkey,
+    pub redemption_pool: Pubkey,
+    pub rate_numerator: u64,
+    pub rate_denominator: u64,
+    pub total_burned: u64,
+    pub total_redeemed: u64,
+    pub bump: u8,
+}
+
+/// Airdrop snapshot header; its entries are `SnapshotEntry` PDAs.
+#[account]
+pub struct StakeSnapshot {
+    pub stake_pool: Pubkey,
+    pub mint: Pubkey,
+    pub snapshot_id: u64,
+    pub timestamp: i64,
+    pub slot: u64,
+    /// Pool principal when the snapshot was taken.
+    pub total_staked: u64,
+    /// Sum of the entries recorded so far.
+    pub recorded_amount: u64,
+    pub entry_count: u32,
+    /// Zero until `finalize_snapshot`.
+    pub merkle_root: [u8; 32],
+    pub finalized: bool,
+    pub bump: u8,
+}
+
+/// One position's balance in a snapshot, at
+/// `[b"snapshot_entry", snapshot, stake_account]`. Never modified once written.
+#[account]
+pub struct SnapshotEntry {
+    pub snapshot: Pubkey,
+    pub stake_account: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+impl SnapshotEntry {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 1;
+}
+
+/// Returned by `assert_min_stake`. Part of the versioned interface: fields
+/// only change together with `STAKE_INTERFACE_VERSION`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct StakeCheck {
+    pub interface_version: u8,
+    pub meets_minimum: bool,
+    /// Principal of the position; 0 unless it is the owner's and active.
+    pub amount: u64,
+    pub lock_until: i64,
+}
+
+/// Returned by `get_pending_rewards`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PendingRewards {
+    /// Everything owed in the pool's reward mint.
+    pub pending: u64,
+    /// What a claim would hand the staker now, after the vault cap and claim fee.
+    pub claimable: u64,
+    /// Owed per partner slot, before each slot vault's balance is checked.
+    pub slot_rewards: [u64; MAX_REWARD_SLOTS],
+    /// The owner's reward debt from positions closed during a rewards freeze,
+    /// paid separately by `claim_reward_debt`.
+    pub reward_debt: u64,
+}
+
+/// Token-2022 transfer fee set on the mint at creation.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct TransferFeeArgs {
+    pub transfer_fee_bps: u16,
+    pub max_fee: u64,
+}
+
+/// A key allowed to mint through the program up to `cap` over its lifetime,
+/// and no more than `allowance_per_epoch` in any one epoch.
+#[account]
+pub struct MinterRecord {
+    pub mint: Pubkey,
+    pub minter: Pubkey,
+    pub cap: u64,
+    pub minted: u64,
+    pub allowance_per_epoch: u64,
+    pub epoch_length_seconds: i64,
+    pub current_epoch_start: i64,
+    pub minted_this_epoch: u64,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+impl MinterRecord {
+    /// Moves the window to the epoch containing `now`, skipping idle epochs.
+    pub fn roll_epoch(&mut self, now: i64) {
+        let elapsed = now - self.current_epoch_start;
+        if elapsed >= self.epoch_length_seconds {
+            self.current_epoch_start += elapsed - elapsed % self.epoch_length_seconds;
+            self.minted_this_epoch = 0;
+        }
+    }
+
+    /// Counts `amount` against both the lifetime cap and the current epoch.
+    pub fn record_mint(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.roll_epoch(now);
+
+        let minted = self
+            .minted
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        require!(minted <= self.cap, GamingTokenError::MinterCapExceeded);
+
+        let minted_this_epoch = self
+            .minted_this_epoch
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        require!(
+            minted_this_epoch <= self.allowance_per_epoch,
+            GamingTokenError::MinterAllowanceExceeded
+        );
+
+        self.minted = minted;
+        self.minted_this_epoch = minted_this_epoch;
+        Ok(())
+    }
+}
+
+/// Reward policy for a game server minting through its minter record.
+#[account]
+pub struct GameServerRecord {
+    pub mint: Pubkey,
+    pub server: Pubkey,
+    /// Most the server can mint per `GAME_SERVER_WINDOW_SECONDS`.
+    pub daily_budget: u64,
+    /// Most one player can receive from the server per window.
+    pub per_recipient_cap: u64,
+    /// Only owners with a record in this program are paid; default pays any account.
+    pub player_registry: Pubkey,
+    pub window_start: i64,
+    pub minted_in_window: u64,
+    pub minted: u64,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+impl GameServerRecord {
+    /// Moves the window to the one containing `now`, skipping idle days.
+    pub fn roll_window(&mut self, now: i64) {
+        let elapsed = now - self.window_start;
+        if elapsed >= GAME_SERVER_WINDOW_SECONDS {
+            self.window_start += elapsed - elapsed % GAME_SERVER_WINDOW_SECONDS;
+            self.minted_in_window = 0;
+        }
+    }
+
+    /// Counts `amount` against the daily budget.
+    pub fn record_reward(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.roll_window(now);
+
+        let minted_in_window = self
+            .minted_in_window
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        require!(
+            minted_in_window <= self.daily_budget,
+            GamingTokenError::GameServerBudgetExceeded
+        );
+
+        self.minted_in_window = minted_in_window;
+        self.minted = self
+            .minted
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// What one player has received from a game server in its current window.
+#[account]
+pub struct GameRewardRecipient {
+    pub game_server: Pubkey,
+    pub player: Pubkey,
+    /// The server window `minted_in_window` belongs to.
+    pub window_start: i64,
+    pub minted_in_window: u64,
+    pub bump: u8,
+}
+
+impl MintConfig {
+    /// Tokens that can still be minted under the cap, `None` when uncapped.
+    pub fn remaining_supply(&self) -> Option<u64> {
+        (self.max_supply > 0).then(|| self.max_supply.saturating_sub(self.total_supply))
+    }
+}
+
+/// A minted allocation released to one beneficiary over time.
+#[account]
+pub struct VestingSchedule {
+    pub mint: Pubkey,
+    pub beneficiary: Pubkey,
+    /// Tells apart schedules held by the same beneficiary.
+    pub schedule_id: u64,
+    /// Cut down to what had vested when the schedule is revoked.
+    pub total_amount: u64,
+    pub released_amount: u64,
+    pub start: i64,
+    /// Nothing vests before `start + cliff_seconds`; vesting then catches up linearly.
+    pub cliff_seconds: i64,
+    pub duration_seconds: i64,
+    pub revoked: bool,
+    pub vault: Pubkey,
+    pub bump: u8,
+}
+
+impl VestingSchedule {
+    /// Released plus claimable at `now`; a revoked schedule has fully vested what it kept.
+    pub fn vested_amount(&self, now: i64) -> Result<u64> {
+        if self.revoked {
+            return Ok(self.total_amount);
+        }
+        let elapsed = now.saturating_sub(self.start);
+        if elapsed < self.cliff_seconds {
+            return Ok(0);
+        }
+        let elapsed = elapsed.min(self.duration_seconds) as u128;
+        let vested = self.total_amount as u128 * elapsed / self.duration_seconds as u128;
+        Ok(u64::try_from(vested).map_err(|_| GamingTokenError::MathOverflow)?)
+    }
+}
+
+/// Rate-affecting pool settings; `None` leaves a setting as it is.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PoolUpdate {
+    pub reward_duration: Option<i64>,
+    pub emission_schedule: Option<EmissionScheduleArgs>,
+    pub claim_fee_bps: Option<u16>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct EmissionScheduleArgs {
+    pub epoch_length: i64,
+    pub initial_emission: u64,
+    pub halving_interval: u32,
+}
+
+/// Leading fields of the solitaire program's `PlayerStats`, enough to read wins.
+#[derive(AnchorDeserialize)]
+pub struct SolitairePlayerStats {
+    pub player: Pubkey,
+    pub games_won: u64,
+}
+
+/// Left behind by `close_pool` so the mint can never get a second pool.
+#[account]
+pub struct ClosedPool {
+    pub mint: Pubkey,
+    pub closed_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct PendingPoolUpdate {
+    pub stake_pool: Pubkey,
+    pub update: PoolUpdate,
+    pub queued_at: i64,
+    pub effective_at: i64,
+    pub bump: u8,
+}
+
+/// Who may open staking pools, and what it costs.
+#[account]
+pub struct StakingConfig {
+    pub admin: Pubkey,
+    /// Lets anyone create a pool for `pool_creation_fee`; the admin never pays it.
+    pub permissionless_pools: bool,
+    /// Lamports sent to `fee_recipient` per permissionless pool.
+    pub pool_creation_fee: u64,
+    pub fee_recipient: Pubkey,
+    pub total_pools: u64,
+    /// Shortest notice `queue_pool_update` may give before a rate change.
+    pub pool_update_delay_seconds: i64,
+    pub bump: u8,
+}
+
+impl StakingConfig {
+    /// Registry page the next created pool is listed on.
+    pub fn registry_page(&self) -> u32 {
+        (self.total_pools / POOL_REGISTRY_PAGE_SIZE as u64) as u32
+    }
+}
+
+/// One page of the pool list, at `[b"pool_registry", page_index]`. Walk pages
+/// from 0 until one is missing to enumerate every pool.
+#[account]
+pub struct PoolRegistryPage {
+    pub page_index: u32,
+    pub entries: Vec<PoolRegistryEntry>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PoolRegistryEntry {
+    pub pool: Pubkey,
+    pub mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub created_at: i64,
+    /// Set by the pool authority; the pool still works.
+    pub deprecated: bool,
+    pub closed: bool,
+}
+
+impl PoolRegistryEntry {
+    pub const SIZE: usize = 32 + 32 + 32 + 8 + 1 + 1;
+}
+
+#[account]
+pub struct StakePool {
+    pub authority: Pubkey,
+    /// Mint that is staked; also keys the pool and vault PDAs.
+    pub mint: Pubkey,
+    /// Mint rewards are paid in; may differ from `mint`.
+    pub reward_mint: Pubkey,
+    pub stake_vault: Pubkey,
+    /// Holds `reward_mint`, never staked principal.
+    pub reward_vault: Pubkey,
+    /// Length of the emission period started by `notify_reward_amount`.
+    pub reward_duration: i64,
+    /// Reward weight boosts by lock length, sorted by `min_lock_seconds`.
+    pub lock_tiers: Vec<LockTier>,
+    /// Early unstake penalty on the full lock, scaled down as it runs out.
+    pub early_unstake_penalty_bps: u16,
+    /// Unbonding period for `request_unstake`; 0 keeps single-step unstaking.
+    pub unstake_cooldown_seconds: i64,
+    /// Longest total lock a position may commit to; 0 means no limit.
+    pub max_lock_seconds: i64,
+    /// Shortest lock a new position may open with.
+    pub min_lock_seconds: i64,
+    /// Smallest position that may be opened or left behind by a partial unstake.
+    pub min_stake_amount: u64,
+    /// Extra voting weight for a lock with `max_lock_seconds` left, scaled linearly.
+    pub vote_lock_bonus_bps: u16,
+    /// Most of a position's principal one slash may take; 0 disables slashing.
+    pub max_slash_bps: u16,
+    /// Dispute window between `propose_slash` and `execute_slash`.
+    pub slash_timelock_seconds: i64,
+    /// Blocks new stake while leaving every exit open.
+    pub paused: bool,
+    /// Waives locks and cooldowns so principal can leave at once; rewards are not paid.
+    pub emergency_mode: bool,
+    /// Only account `emergency_withdraw_vault` can send to; fixed outside emergencies.
+    pub emergency_destination: Pubkey,
+    /// Stops reward accrual and payouts at `rewards_frozen_at` while principal
+    /// leaves freely; unstaked rewards become the owner's reward debt.
+    pub emergency_rewards_freeze: bool,
+    pub rewards_frozen_at: i64,
+    /// Tokens emitted per second, shared by weight, until `period_finish`.
+    pub reward_rate: u64,
+    pub period_finish: i64,
+    /// Rewards per unit of weight up to `last_update_time`, scaled by `REWARD_PRECISION`.
+    pub reward_per_token_stored: u128,
+    pub last_update_time: i64,
+    pub total_staked: u64,
+    /// Positions currently open across all owners.
+    pub total_positions: u64,
+    /// Rewards paid out or compounded over the life of the pool.
+    pub total_rewards_paid: u64,
+    /// Tokens deposited for rewards that have not been paid out or withdrawn.
+    pub reward_reserve: u64,
+    /// Sum of position weights; unbonding stake is left out.
+    pub total_weight: u128,
+    /// Rewards emitted to stakers and not yet paid.
+    pub rewards_owed: u64,
+    /// Partner reward tokens, each with its own vault and emission.
+    pub reward_slots: Vec<RewardSlot>,
+    /// Halving epochs that drive `reward_rate` in place of `notify_reward_amount`.
+    pub emission_schedule: Option<EmissionSchedule>,
+    /// Set by the staking admin for liquidity pools; enables `boost_bps`.
+    pub lp_pool: bool,
+    /// Extra emission on each notified amount or epoch, paid only out of
+    /// reserve nothing else has claimed.
+    pub boost_bps: u16,
+    /// Protocol cut of every reward payout, sent to `fee_vault`.
+    pub claim_fee_bps: u16,
+    /// Collects claim fees in `reward_mint`; unset until a fee is first configured.
+    pub fee_vault: Pubkey,
+    /// Bonus paid to a referrer on each of their referee's claims, on top of the claim.
+    pub referral_bps: u16,
+    /// Shortest gap between two `claim_rewards` on a position; 0 disables it.
+    pub min_claim_interval_seconds: i64,
+    /// Solitaire program whose `PlayerStats` qualify for the achievement bonus.
+    pub achievement_program: Pubkey,
+    pub achievement_min_wins: u64,
+    /// Bonus on claims and compounds for players with enough wins, paid only
+    /// out of reserve nothing else has claimed.
+    pub achievement_boost_bps: u16,
+    /// New and topped-up stake carries no weight and earns nothing for this long.
+    pub reward_warmup_seconds: i64,
+    /// Pool `migrate_position` moves stakers to once the sunset has begun.
+    pub successor_pool: Pubkey,
+    /// Last moment to migrate; 0 while the pool is not sunsetting.
+    pub sunset_deadline: i64,
+    /// Extra successor stake paid to migrating positions, out of the migration vault.
+    pub migration_bonus_bps: u16,
+    /// Unmigrated principal has moved to the sunset escrow.
+    pub sunset_swept: bool,
+    /// Ring buffer of the effective reward rate, in write order until full.
+    pub rate_history: Vec<RateSnapshot>,
+    /// Entry the next snapshot overwrites once `rate_history` is full.
+    pub rate_history_next: u16,
+    /// Payouts that leave less than this in the reward vault or reserve raise
+    /// `RewardReserveLow`; 0 disables the alarm.
+    pub low_reserve_threshold: u64,
+    /// Alarm raised and not yet cleared by funding.
+    pub reserve_low: bool,
+    /// Principal of soft-stake positions, left in owners' wallets under a
+    /// delegation; counted in `total_weight` but not in `total_staked`.
+    pub total_soft_staked: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl StakePool {
+    /// Emission stops at `period_finish` until the next `notify_reward_amount`,
+    /// and at `rewards_frozen_at` while rewards are frozen.
+    pub fn last_time_reward_applicable(&self, now: i64) -> i64 {
+        let now = if self.emergency_rewards_freeze {
+            now.min(self.rewards_frozen_at)
+        } else {
+            now
+        };
+        now.min(self.period_finish)
+    }
+
+    /// Rewards per unit of weight at `now`, scaled by `REWARD_PRECISION`.
+    pub fn reward_per_token(&self, now: i64) -> Result<u128> {
+        let elapsed = (self.last_time_reward_applicable(now) - self.last_update_time).max(0) as u128;
+        if self.total_weight == 0 || elapsed == 0 {
+            return Ok(self.reward_per_token_stored);
+        }
+        let emitted = elapsed
+            .checked_mul(self.reward_rate as u128)
+            .and_then(|emitted| emitted.checked_mul(REWARD_PRECISION))
+            .ok_or(GamingTokenError::MathOverflow)?;
+        Ok(self
+            .reward_per_token_stored
+            .checked_add(emitted / self.total_weight)
+            .ok_or(GamingTokenError::MathOverflow)?)
+    }
+
+    /// Folds emissions up to `now` into the accumulator, rolling any emission
+    /// epochs that ended on the way.
+    pub fn update_reward_per_token(&mut self, now: i64) -> Result<()> {
+        self.roll_epochs(now)?;
+        self.accrue_rewards(now)
+    }
+
+    /// Time with no weight staked emits nothing; those tokens stay in the
+    /// reserve as excess.
+    fn accrue_rewards(&mut self, now: i64) -> Result<()> {
+        let applicable = self.last_time_reward_applicable(now).max(self.last_update_time);
+        if self.total_weight > 0 {
+            let emitted = (applicable - self.last_update_time) as u128 * self.reward_rate as u128;
+            self.rewards_owed = u64::try_from(emitted)
+                .ok()
+                .and_then(|emitted| emitted.checked_add(self.rewards_owed))
+                .ok_or(GamingTokenError::MathOverflow)?;
+        }
+        self.reward_per_token_stored = self.reward_per_token(now)?;
+        self.last_update_time = applicable;
+        Ok(())
+    }
+
+    /// Closes every ended epoch at its own boundary and rate, so a late roll
+    /// accrues exactly what timely ones would have. An epoch emits its halved
+    /// amount, or whatever the reserve still has uncommitted if that is less.
+    fn roll_epochs(&mut self, now: i64) -> Result<()> {
+        let Some(mut schedule) = self.emission_schedule else {
+            return Ok(());
+        };
+
+        let mut rolled = 0;
+        while rolled < MAX_EPOCH_ROLLS && now >= schedule.epoch_end() {
+            let epoch_end = schedule.epoch_end();
+            self.accrue_rewards(epoch_end)?;
+
+            schedule.current_epoch = schedule
+                .current_epoch
+                .checked_add(1)
+                .ok_or(GamingTokenError::MathOverflow)?;
+            schedule.epoch_start = epoch_end;
+            let emission = self
+                .boosted(schedule.emission())
+                .min(self.reward_reserve.saturating_sub(self.rewards_owed));
+            self.reward_rate = emission / schedule.epoch_length as u64;
+            self.last_update_time = epoch_end;
+            self.period_finish = schedule.epoch_end();
+            self.record_rate(epoch_end);
+            rolled += 1;
+
+            emit!(EpochRolled {
+                mint: self.mint,
+                epoch: schedule.current_epoch,
+                emission,
+                reward_rate: self.reward_rate,
+                epoch_start: schedule.epoch_start,
+                epoch_end: self.period_finish,
+                timestamp: now,
+            });
+        }
+
+        self.emission_schedule = Some(schedule);
+        Ok(())
+    }
+
+    /// Splits a reward payout into the claim fee, rounded down, and the staker's share.
+    pub fn split_claim_fee(&self, gross: u64) -> (u64, u64) {
+        let fee = (gross as u128 * self.claim_fee_bps as u128 / BPS_DENOMINATOR) as u64;
+        (fee, gross - fee)
+    }
+
+    /// `amount` with the LP boost on top, before any cap.
+    pub fn boosted(&self, amount: u64) -> u64 {
+        if !self.lp_pool {
+            return amount;
+        }
+        let boosted = amount as u128 * (BPS_DENOMINATOR + self.boost_bps as u128) / BPS_DENOMINATOR;
+        u64::try_from(boosted).unwrap_or(u64::MAX)
+    }
+
+    /// Starts a period of `reward_duration` paying out `amount` plus whatever
+    /// the running period had not emitted yet.
+    pub fn notify_reward_amount(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.update_reward_per_token(now)?;
+        let leftover = (self.period_finish - now).max(0) as u128 * self.reward_rate as u128;
+        let reward_rate = (amount as u128 + leftover) / self.reward_duration as u128;
+        self.reward_rate = u64::try_from(reward_rate).map_err(|_| GamingTokenError::MathOverflow)?;
+        self.last_update_time = now;
+        self.period_finish = now
+            .checked_add(self.reward_duration)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        self.record_rate(now);
+        Ok(())
+    }
+
+    /// Hands `amount`, already in the reward vault, straight to current
+    /// stakers through the accumulator instead of stretching it over a
+    /// reward period. `excluded_weight` is left out of the split; its holder
+    /// must be checkpointed past the bump. With no other weight the tokens
+    /// stay in the reserve as excess. Returns what was distributed.
+    pub fn distribute_now(&mut self, amount: u64, excluded_weight: u128, now: i64) -> Result<u64> {
+        self.update_reward_per_token(now)?;
+        self.reward_reserve = self
+            .reward_reserve
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+
+        let weight = self.total_weight.saturating_sub(excluded_weight);
+        if weight == 0 || amount == 0 {
+            return Ok(0);
+        }
+        let per_weight = (amount as u128)
+            .checked_mul(REWARD_PRECISION)
+            .ok_or(GamingTokenError::MathOverflow)?
+            / weight;
+        self.reward_per_token_stored = self
+            .reward_per_token_stored
+            .checked_add(per_weight)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        self.rewards_owed = self
+            .rewards_owed
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        Ok(amount)
+    }
+
+    /// Rewards owed to stakers plus what the running period has yet to emit.
+    pub fn outstanding_liability(&self, now: i64) -> u64 {
+        let remaining = (self.period_finish - now.max(self.last_update_time)).max(0) as u128;
+        let scheduled = remaining * self.reward_rate as u128;
+        u64::try_from(self.rewards_owed as u128 + scheduled).unwrap_or(u64::MAX)
+    }
+
+    /// `bps` of `base`, paid on top of a payout: limited to reserve no staker
+    /// is owed or scheduled to earn, and to what the vault still holds.
+    pub fn reserve_bonus(&self, base: u64, bps: u16, vault_available: u64, now: i64) -> u64 {
+        let uncommitted = self.reward_reserve.saturating_sub(self.outstanding_liability(now));
+        ((base as u128 * bps as u128 / BPS_DENOMINATOR) as u64)
+            .min(uncommitted)
+            .min(vault_available)
+    }
+
+    /// Releases `owed` from the owed total, of which `paid` left the vault.
+    pub fn settle_rewards(&mut self, owed: u64, paid: u64) -> Result<()> {
+        self.rewards_owed = self.rewards_owed.saturating_sub(owed);
+        self.reward_reserve = self.reward_reserve.saturating_sub(paid);
+        self.total_rewards_paid = self
+            .total_rewards_paid
+            .checked_add(paid)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Brings every partner slot's accumulator up to `now`.
+    pub fn update_reward_slots(&mut self, now: i64) -> Result<()> {
+        let total_weight = self.total_weight;
+        for slot in self.reward_slots.iter_mut() {
+            slot.update_reward_per_token(now, total_weight)?;
+        }
+        Ok(())
+    }
+
+    pub fn open_position(&mut self) -> Result<()> {
+        self.total_positions = self
+            .total_positions
+            .checked_add(1)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn close_position(&mut self) -> Result<()> {
+        self.total_positions = self
+            .total_positions
+            .checked_sub(1)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn add_stake(&mut self, amount: u64) -> Result<()> {
+        self.total_staked = self
+            .total_staked
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn remove_stake(&mut self, amount: u64) -> Result<()> {
+        self.total_staked = self
+            .total_staked
+            .checked_sub(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Swaps a position's weight after its balance, boost or unbonding state changed.
+    /// The accumulator must already be up to date.
+    pub fn reweight(&mut self, old_weight: u128, new_weight: u128) -> Result<()> {
+        self.total_weight = self
+            .total_weight
+            .checked_sub(old_weight)
+            .and_then(|weight| weight.checked_add(new_weight))
+            .ok_or(GamingTokenError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// `amount` weighted by the remaining lock: 1x unlocked, up to
+    /// 1x + `vote_lock_bonus_bps` with `max_lock_seconds` or more left.
+    pub fn voting_power(&self, amount: u64, remaining_lock: i64) -> Result<u64> {
+        let remaining = remaining_lock.max(0) as u128;
+        let bonus_bps = match self.max_lock_seconds {
+            0 => 0,
+            max_lock => {
+                let max_lock = max_lock as u128;
+                self.vote_lock_bonus_bps as u128 * remaining.min(max_lock) / max_lock
+            }
+        };
+        let power = amount as u128 * (BPS_DENOMINATOR + bonus_bps) / BPS_DENOMINATOR;
+        Ok(u64::try_from(power).map_err(|_| GamingTokenError::MathOverflow)?)
+    }
+
+    /// Appends the rate in effect at `now`, overwriting the oldest entry once
+    /// the history is full.
+    pub fn record_rate(&mut self, now: i64) -> RateSnapshot {
+        let snapshot = RateSnapshot {
+            timestamp: now,
+            reward_rate: if now < self.period_finish { self.reward_rate } else { 0 },
+            total_staked: self.total_staked,
+        };
+        let next = self.rate_history_next as usize;
+        if self.rate_history.len() < RATE_HISTORY_LEN {
+            self.rate_history.push(snapshot);
+        } else {
+            self.rate_history[next] = snapshot;
+        }
+        self.rate_history_next = ((next + 1) % RATE_HISTORY_LEN) as u16;
+        snapshot
+    }
+
+    /// Most recent `rate_history` entry.
+    pub fn latest_rate(&self) -> Option<&RateSnapshot> {
+        let len = self.rate_history.len();
+        if len == 0 {
+            return None;
+        }
+        self.rate_history.get((self.rate_history_next as usize + len - 1) % len)
+    }
+
+    /// Raises `RewardReserveLow` once per crossing when a payout has left
+    /// `vault_balance` or the reserve under `low_reserve_threshold`.
+    pub fn check_reserve_low(&mut self, vault_balance: u64, now: i64) {
+        let remaining = vault_balance.min(self.reward_reserve);
+        if self.low_reserve_threshold == 0 || self.reserve_low || remaining >= self.low_reserve_threshold {
+            return;
+        }
+        self.reserve_low = true;
+
+        emit!(RewardReserveLow {
+            mint: self.mint,
+            vault_balance,
+            reward_reserve: self.reward_reserve,
+            threshold: self.low_reserve_threshold,
+            runway_seconds: self.runway(remaining, now),
+            timestamp: now,
+        });
+    }
+
+    /// Clears the alarm once funding has lifted both figures back over the threshold.
+    pub fn check_reserve_recovered(&mut self, vault_balance: u64, now: i64) {
+        let remaining = vault_balance.min(self.reward_reserve);
+        if !self.reserve_low || remaining < self.low_reserve_threshold {
+            return;
+        }
+        self.reserve_low = false;
+
+        emit!(RewardReserveRecovered {
+            mint: self.mint,
+            vault_balance,
+            reward_reserve: self.reward_reserve,
+            threshold: self.low_reserve_threshold,
+            runway_seconds: self.runway(remaining, now),
+            timestamp: now,
+        });
+    }
+
+    /// Seconds `remaining` lasts at the current emission rate; `None` while
+    /// nothing is being emitted.
+    fn runway(&self, remaining: u64, now: i64) -> Option<i64> {
+        if self.reward_rate == 0 || now >= self.period_finish {
+            return None;
+        }
+        Some(i64::try_from(remaining / self.reward_rate).unwrap_or(i64::MAX))
+    }
+
+    /// Sunsetting pools take no new stake and hold nobody to their locks.
+    pub fn is_sunset(&self) -> bool {
+        self.sunset_deadline != 0
+    }
+
+    /// `warmup_until` for stake added at `now`, 0 when the pool has no warm-up.
+    pub fn warmup_end(&self, now: i64) -> Result<i64> {
+        if self.reward_warmup_seconds == 0 {
+            return Ok(0);
+        }
+        now.checked_add(self.reward_warmup_seconds)
+            .ok_or(error!(GamingTokenError::MathOverflow))
+    }
+
+    pub fn allows_lock(&self, lock_period: i64) -> bool {
+        lock_period <= MAX_LOCK_PERIOD_SECONDS
+            && (self.max_lock_seconds == 0 || lock_period <= self.max_lock_seconds)
+    }
+
+    /// A `max_lock_seconds` of 0 leaves only the program-wide cap.
+    pub fn set_stake_limits(
+        &mut self,
+        min_stake_amount: u64,
+        min_lock_seconds: i64,
+        max_lock_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            (0..=MAX_LOCK_PERIOD_SECONDS).contains(&min_lock_seconds)
+                && (0..=MAX_LOCK_PERIOD_SECONDS).contains(&max_lock_seconds)
+                && (max_lock_seconds == 0 || max_lock_seconds >= min_lock_seconds),
+            GamingTokenError::InvalidLockPeriod
+        );
+        self.min_stake_amount = min_stake_amount;
+        self.min_lock_seconds = min_lock_seconds;
+        self.max_lock_seconds = max_lock_seconds;
+        Ok(())
+    }
+
+    /// Highest tier whose minimum lock `lock_period` reaches.
+    pub fn tier_for(&self, lock_period: i64) -> Option<usize> {
+        self.lock_tiers
+            .iter()
+            .rposition(|tier| lock_period >= tier.min_lock_seconds)
+    }
+}
+
+/// A partner reward token with the same emission model as the pool's own rewards.
+/// Removed slots stay claim-only and are never reused.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct RewardSlot {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub reward_duration: i64,
+    pub reward_rate: u64,
+    pub period_finish: i64,
+    pub reward_per_token_stored: u128,
+    pub last_update_time: i64,
+    pub reward_reserve: u64,
+    pub rewards_owed: u64,
+    pub total_rewards_paid: u64,
+    pub active: bool,
+}
+
+impl RewardSlot {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 8 + 16 + 8 + 8 + 8 + 8 + 1;
+
+    /// Same as `StakePool::update_reward_per_token`, against the pool's weight.
+    pub fn update_reward_per_token(&mut self, now: i64, total_weight: u128) -> Result<()> {
+        let applicable = now.min(self.period_finish).max(self.last_update_time);
+        if total_weight > 0 {
+            let emitted = (applicable - self.last_update_time) as u128 * self.reward_rate as u128;
+            self.rewards_owed = u64::try_from(emitted)
+                .ok()
+                .and_then(|emitted| emitted.checked_add(self.rewards_owed))
+                .ok_or(GamingTokenError::MathOverflow)?;
+            let increase = emitted
+                .checked_mul(REWARD_PRECISION)
+                .and_then(|scaled| scaled.checked_div(total_weight))
+                .ok_or(GamingTokenError::MathOverflow)?;
+            self.reward_per_token_stored = self
+                .reward_per_token_stored
+                .checked_add(increase)
+                .ok_or(GamingTokenError::MathOverflow)?;
+        }
+        self.last_update_time = applicable;
+        Ok(())
+    }
+
+    pub fn notify_reward_amount(&mut self, amount: u64, now: i64, total_weight: u128) -> Result<()> {
+        self.update_reward_per_token(now, total_weight)?;
+        let leftover = (self.period_finish - now).max(0) as u128 * self.reward_rate as u128;
+        let reward_rate = (amount as u128 + leftover) / self.reward_duration as u128;
+        self.reward_rate = u64::try_from(reward_rate).map_err(|_| GamingTokenError::MathOverflow)?;
+        self.last_update_time = now;
+        self.period_finish = now
+            .checked_add(self.reward_duration)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn outstanding_liability(&self, now: i64) -> u64 {
+        let remaining = (self.period_finish - now.max(self.last_update_time)).max(0) as u128;
+        let scheduled = remaining * self.reward_rate as u128;
+        u64::try_from(self.rewards_owed as u128 + scheduled).unwrap_or(u64::MAX)
+    }
+
+    pub fn settle_rewards(&mut self, owed: u64, paid: u64) -> Result<()> {
+        self.rewards_owed = self.rewards_owed.saturating_sub(owed);
+        self.reward_reserve = self.reward_reserve.saturating_sub(paid);
+        self.total_rewards_paid = self
+            .total_rewards_paid
+            .checked_add(paid)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct EmissionSchedule {
+    pub epoch_length: i64,
+    /// Emission of epoch 0, halved every `halving_interval` epochs.
+    pub initial_emission: u64,
+    pub halving_interval: u32,
+    pub current_epoch: u32,
+    pub epoch_start: i64,
+}
+
+impl EmissionSchedule {
+    pub const SIZE: usize = 8 + 8 + 4 + 4 + 8;
+
+    pub fn epoch_end(&self) -> i64 {
+        self.epoch_start.saturating_add(self.epoch_length)
+    }
+
+    /// Scheduled emission for `current_epoch`.
+    pub fn emission(&self) -> u64 {
+        self.initial_emission
+            .checked_shr(self.current_epoch / self.halving_interval)
+            .unwrap_or(0)
+    }
+}
+
+/// A position's checkpoint in one partner reward slot.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct SlotRewards {
+    pub reward_per_token_paid: u128,
+    pub rewards_accrued: u64,
+    /// Sub-token remainder, in `1 / REWARD_PRECISION` units; see `split_reward`.
+    pub reward_dust: u64,
+}
+
+impl SlotRewards {
+    pub const SIZE: usize = 16 + 8 + 8;
+}
+
+/// `SlotRewards` before dust was carried. Frozen: part of `StakeAccountV0`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct SlotRewardsV0 {
+    pub reward_per_token_paid: u128,
+    pub rewards_accrued: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct RateSnapshot {
+    pub timestamp: i64,
+    /// Tokens emitted per second; 0 outside a reward period.
+    pub reward_rate: u64,
+    pub total_staked: u64,
+}
+
+impl RateSnapshot {
+    pub const SIZE: usize = 8 + 8 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct LockTier {
+    pub min_lock_seconds: i64,
+    /// Extra reward weight on top of the staked amount.
+    pub boost_bps: u16,
+}
+
+impl LockTier {
+    pub const SIZE: usize = 8 + 2;
+}
+
+#[account]
+pub struct Proposal {
+    pub pool: Pubkey,
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub description: String,
+    /// Positions opened or grown after this time can't vote; lock bonuses are measured from it.
+    pub snapshot_at: i64,
+    pub voting_ends_at: i64,
+    pub yes_power: u64,
+    pub no_power: u64,
+    pub finalized: bool,
+    pub passed: bool,
+    pub bump: u8,
+}
+
+#[account]
+pub struct VotingPowerRecord {
+    pub proposal: Pubkey,
+    pub stake_account: Pubkey,
+    /// Set when the power is cast through a delegate's aggregate instead.
+    pub delegate: Option<Pubkey>,
+    pub power: u64,
+    pub voted: bool,
+    pub bump: u8,
+}
+
+#[account]
+pub struct DelegatedPower {
+    pub proposal: Pubkey,
+    pub delegate: Pubkey,
+    pub power: u64,
+    pub positions: u32,
+    pub voted: bool,
+    pub support: bool,
+    pub bump: u8,
+}
+
+#[account]
+pub struct StakePositions {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    /// Positions live at indices `0..next_index`.
+    pub next_index: u16,
+    pub open_positions: u16,
+    /// Rewards of positions closed while the pool's rewards were frozen,
+    /// paid by `claim_reward_debt`.
+    pub reward_debt: u64,
+    pub bump: u8,
+}
+
+/// Position whose tokens stay in the owner's token account, delegated to the
+/// pool's `vault_authority`. Its own account type keeps it out of every
+/// instruction that pays principal from the stake vault.
+#[account]
+pub struct SoftStakePosition {
+    /// Accounted exactly like a vault position; `amount` is what is delegated.
+    pub position: StakeAccount,
+    pub token_account: Pubkey,
+    /// Frozen by the program, so the tokens can't move until `soft_unstake`.
+    pub frozen: bool,
+    pub bump: u8,
+}
+
+impl SoftStakePosition {
+    pub const SPACE: usize = StakeAccount::SPACE + 32 + 1 + 1;
+}
+
+/// Auto-compounding vault over one pool: a single position owned by this
+/// PDA, held by depositors through `share_mint`.
+#[account]
+pub struct CompoundVault {
+    pub mint: Pubkey,
+    pub stake_pool: Pubkey,
+    /// The vault PDA is its mint authority.
+    pub share_mint: Pubkey,
+    /// Share supply plus the `COMPOUND_VAULT_DEAD_SHARES` nobody holds.
+    pub total_shares: u64,
+    pub last_harvest: i64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl StakePositions {
+    /// Indices are handed out in order; closed positions can be reopened.
+    pub fn open(&mut self, position_index: u16) -> Result<()> {
+        require!(
+            position_index <= self.next_index,
+            GamingTokenError::InvalidPositionIndex
+        );
+        if position_index == self.next_index {
+            self.next_index = self
+                .next_index
+                .checked_add(1)
+                .ok_or(GamingTokenError::MathOverflow)?;
+        }
+        self.open_positions = self
+            .open_positions
+            .checked_add(1)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// Lifetime totals for one owner in one pool, for loyalty and sybil checks in
+/// other programs. The layout is fixed: new fields come out of `reserved`
+/// and bump `version`.
+#[account]
+pub struct StakerStats {
+    pub version: u8,
+    pub owner: Pubkey,
+    pub stake_pool: Pubkey,
+    /// Principal deposited by stakes and top-ups; compounding is not counted.
+    pub total_staked: u64,
+    /// Principal taken out of positions, early-unstake penalties included.
+    pub total_unstaked: u64,
+    /// Rewards received or compounded, net of claim fees.
+    pub total_rewards_earned: u64,
+    /// `claim_rewards` calls that paid out.
+    pub total_claims: u64,
+    pub longest_lock_seconds: i64,
+    /// Creation time of the earliest position these stats were opened with.
+    pub first_stake_at: i64,
+    pub bump: u8,
+    pub reserved: [u8; 64],
+}
+
+impl StakerStats {
+    /// Fills in a freshly created account; positions from before stats existed
+    /// open it on their first update.
+    pub fn touch(&mut self, owner: Pubkey, stake_pool: Pubkey, bump: u8, first_stake_at: i64) {
+        if self.version == 0 {
+            self.version = STAKER_STATS_VERSION;
+            self.owner = owner;
+            self.stake_pool = stake_pool;
+            self.first_stake_at = first_stake_at;
+            self.bump = bump;
+        }
+    }
+
+    pub fn record_stake(&mut self, amount: u64, lock_period: i64) -> Result<()> {
+        self.total_staked = self
+            .total_staked
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        self.longest_lock_seconds = self.longest_lock_seconds.max(lock_period);
+        Ok(())
+    }
+
+    pub fn record_unstake(&mut self, amount: u64) -> Result<()> {
+        self.total_unstaked = self
+            .total_unstaked
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn record_rewards(&mut self, amount: u64, claimed: bool) -> Result<()> {
+        self.total_rewards_earned = self
+            .total_rewards_earned
+            .checked_add(amount)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        if claimed {
+            self.total_claims = self
+                .total_claims
+                .checked_add(1)
+                .ok_or(GamingTokenError::MathOverflow)?;
+        }
+        Ok(())
+    }
+}
+
+/// Links a staker to whoever referred their first stake in a pool.
+#[account]
+pub struct Referral {
+    pub referee: Pubkey,
+    pub referrer: Pubkey,
+    pub mint: Pubkey,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+/// An admin slash waiting out the pool's dispute window.
+#[account]
+pub struct SlashProposal {
+    pub stake_pool: Pubkey,
+    pub stake_account: Pubkey,
+    pub slash_bps: u16,
+    /// Token account the slashed principal goes to; the reward vault redistributes it.
+    pub destination: Pubkey,
+    /// Hash of the off-chain evidence bundle.
+    pub justification_hash: [u8; 32],
+    pub proposed_at: i64,
+    pub executable_at: i64,
+    pub bump: u8,
+}
+
+/// `version` through `is_active` are a stable view other programs read (the
+/// solitaire fee discount): every layout from version 3 on keeps them first
+/// and in this order.
+#[account]
+pub struct StakeAccount {
+    /// Layout version, `STAKE_ACCOUNT_VERSION` once migrated.
+    pub version: u8,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub position_index: u16,
+    pub amount: u64,
+    /// Set by `request_unstake`; rewards stop accruing from this time.
+    pub unbonding_since: Option<i64>,
+    pub is_active: bool,
+    pub lock_until: i64,
+    /// Lock length chosen at stake time, reapplied on top-ups.
+    pub lock_period: i64,
+    pub created_at: i64,
+    /// Last `claim_rewards`, or stake time before the first one.
+    pub last_reward_claim: i64,
+    /// Pool `reward_per_token_stored` at the last checkpoint.
+    pub reward_per_token_paid: u128,
+    /// Lock-tier weight boost fixed when the lock was chosen.
+    pub boost_bps: u16,
+    /// Receipt NFT whose holder controls the position instead of `owner`.
+    pub receipt_mint: Option<Pubkey>,
+    /// Rewards checkpointed by `update_rewards` and still owed.
+    pub rewards_accrued: u64,
+    /// Sub-token remainder, in `1 / REWARD_PRECISION` units; see `split_reward`.
+    pub reward_dust: u64,
+    /// Same checkpoint per partner slot, indexed like `StakePool::reward_slots`.
+    pub slot_rewards: [SlotRewards; MAX_REWARD_SLOTS],
+    pub available_at: i64,
+    /// Votes on the position's behalf; gets no control over the tokens.
+    pub delegate: Option<Pubkey>,
+    pub delegated_at: i64,
+    /// Delegate replaced at `delegated_at`, kept for proposals snapshotted earlier.
+    pub previous_delegate: Option<Pubkey>,
+    pub previous_delegated_at: i64,
+    /// A slash proposal is open; withdrawals wait until it is executed or cancelled.
+    pub slash_pending: bool,
+    /// Carries no weight and earns nothing until then; 0 once warmed up.
+    pub warmup_until: i64,
+    /// Last top-up, compound, merge or lock extension. Proposals snapshotted
+    /// earlier don't count the position.
+    pub power_changed_at: i64,
+}
+
+impl StakeAccount {
+    pub const SPACE: usize = 8 + 1 + 32 + 32 + 2 + 8 + (1 + 8) + 1 + 8 + 8 + 8 + 8 + 16 + 2 + (1 + 32)
+        + 8 + 8 + MAX_REWARD_SLOTS * SlotRewards::SIZE + 8 + (1 + 32) + 8 + (1 + 32) + 8 + 1 + 8 + 8;
+
+    /// Share of emissions: stake scaled by its lock-tier boost, nothing while
+    /// warming up or unbonding.
+    pub fn weight(&self) -> u128 {
+        if self.unbonding_since.is_some() || self.warmup_until != 0 {
+            return 0;
+        }
+        self.amount as u128 * (BPS_DENOMINATOR + self.boost_bps as u128) / BPS_DENOMINATOR
+    }
+
+    /// Rewards earned since the last checkpoint plus those already carried,
+    /// and the dust to carry into the next checkpoint.
+    pub fn pending_rewards(&self, pool: &StakePool, now: i64) -> Result<(u64, u64)> {
+        let delta = pool
+            .reward_per_token(now)?
+            .saturating_sub(self.reward_per_token_paid);
+        let (reward, dust) = split_reward(self.weight(), delta, self.reward_dust)?;
+        let owed = reward
+            .checked_add(self.rewards_accrued)
+            .ok_or(GamingTokenError::MathOverflow)?;
+        Ok((owed, dust))
+    }
+
+    /// Whether the position held the same stake and lock at `at`, so the
+    /// voting power it has now is the power it had then.
+    pub fn votes_at(&self, at: i64) -> bool {
+        self.created_at <= at && self.power_changed_at <= at
+    }
+
+    /// The owner signs for plain positions; receipt positions need the NFT holder.
+    pub fn is_controlled_by(
+        &self,
+        signer: &Pubkey,
+        receipt_token_account: &Option<InterfaceAccount<TokenAccount>>,
+    ) -> bool {
+        match self.receipt_mint {
+            None => *signer == self.owner,
+            Some(receipt_mint) => receipt_token_account.as_ref().is_some_and(|account| {
+                account.mint == receipt_mint && account.owner == *signer && account.amount == 1
+            }),
+        }
+    }
+
+    /// Delegate in effect at `at`, or `None` when delegation changed twice since.
+    pub fn delegate_at(&self, at: i64) -> Option<Option<Pubkey>> {
+        if self.delegated_at <= at {
+            Some(self.delegate)
+        } else if self.previous_delegated_at <= at {
+            Some(self.previous_delegate)
+        } else {
+            None
+        }
+    }
+
+    pub fn set_delegate(&mut self, delegate: Option<Pubkey>, now: i64) {
+        self.previous_delegate = self.delegate;
+        self.previous_delegated_at = self.delegated_at;
+        self.delegate = delegate;
+        self.delegated_at = now;
+    }
+}
+
+/// `StakeAccount` as laid out before it had a version byte. Version 1 is the
+/// same behind a leading version byte. Frozen: only `migrate_stake_account`
+/// reads it.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct StakeAccountV0 {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub position_index: u16,
+    pub amount: u64,
+    pub lock_until: i64,
+    pub lock_period: i64,
+    pub created_at: i64,
+    pub last_reward_claim: i64,
+    pub reward_per_token_paid: u128,
+    pub boost_bps: u16,
+    pub receipt_mint: Option<Pubkey>,
+    pub rewards_accrued: u64,
+    pub slot_rewards: [SlotRewardsV0; MAX_REWARD_SLOTS],
+    pub unbonding_since: Option<i64>,
+    pub available_at: i64,
+    pub delegate: Option<Pubkey>,
+    pub delegated_at: i64,
+    pub previous_delegate: Option<Pubkey>,
+    pub previous_delegated_at: i64,
+    pub slash_pending: bool,
+    pub is_active: bool,
+}
+
+impl StakeAccountV0 {
+    pub const SPACE: usize = 8 + 32 + 32 + 2 + 8 + 8 + 8 + 8 + 8 + 16 + 2 + (1 + 32) + 8
+        + MAX_REWARD_SLOTS * (16 + 8) + (1 + 8) + 8 + (1 + 32) + 8 + (1 + 32) + 8 + 1 + 1;
+
+    /// Version 1 added the version byte, version 2 the reward dust and version
+    /// 4 the warm-up, which all start at zero; version 3 only reordered.
+    /// Version 5's power checkpoint starts at `created_at`. Everything else
+    /// carries over.
+    pub fn migrate(self) -> StakeAccount {
+        StakeAccount {
+            version: STAKE_ACCOUNT_VERSION,
+            owner: self.owner,
+            mint: self.mint,
+            position_index: self.position_index,
+            amount: self.amount,
+            lock_until: self.lock_until,
+            lock_period: self.lock_period,
+            created_at: self.created_at,
+            last_reward_claim: self.last_reward_claim,
+            reward_per_token_paid: self.reward_per_token_paid,
+            boost_bps: self.boost_bps,
+            receipt_mint: self.receipt_mint,
+            rewards_accrued: self.rewards_accrued,
+            reward_dust: 0,
+            slot_rewards: self.slot_rewards.map(|slot| SlotRewards {
+                reward_per_token_paid: slot.reward_per_token_paid,
+                rewards_accrued: slot.rewards_accrued,
+                reward_dust: 0,
+            }),
+            unbonding_since: self.unbonding_since,
+            available_at: self.available_at,
+            delegate: self.delegate,
+            delegated_at: self.delegated_at,
+            previous_delegate: self.previous_delegate,
+            previous_delegated_at: self.previous_delegated_at,
+            slash_pending: self.slash_pending,
+            is_active: self.is_active,
+            warmup_until: 0,
+            power_changed_at: self.created_at,
+        }
+    }
+}
+
+/// `StakeAccount` version 2, before `unbonding_since` and `is_active` moved
+/// up into the cross-program view. The body after the version byte. Frozen:
+/// only `migrate_stake_account` reads it.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct StakeAccountV2 {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub position_index: u16,
+    pub amount: u64,
+    pub lock_until: i64,
+    pub lock_period: i64,
+    pub created_at: i64,
+    pub last_reward_claim: i64,
+    pub reward_per_token_paid: u128,
+    pub boost_bps: u16,
+    pub receipt_mint: Option<Pubkey>,
+    pub rewards_accrued: u64,
+    pub reward_dust: u64,
+    pub slot_rewards: [SlotRewards; MAX_REWARD_SLOTS],
+    pub unbonding_since: Option<i64>,
+    pub available_at: i64,
+    pub delegate: Option<Pubkey>,
+    pub delegated_at: i64,
+    pub previous_delegate: Option<Pubkey>,
+    pub previous_delegated_at: i64,
+    pub slash_pending: bool,
+    pub is_active: bool,
+}
+
+impl StakeAccountV2 {
+    pub fn migrate(self) -> StakeAccount {
+        StakeAccount {
+            version: STAKE_ACCOUNT_VERSION,
+            owner: self.owner,
+            mint: self.mint,
+            position_index: self.position_index,
+            amount: self.amount,
+            unbonding_since: self.unbonding_since,
+            is_active: self.is_active,
+            lock_until: self.lock_until,
+            lock_period: self.lock_period,
+            created_at: self.created_at,
+            last_reward_claim: self.last_reward_claim,
+            reward_per_token_paid: self.reward_per_token_paid,
+            boost_bps: self.boost_bps,
+            receipt_mint: self.receipt_mint,
+            rewards_accrued: self.rewards_accrued,
+            reward_dust: self.reward_dust,
+            slot_rewards: self.slot_rewards,
+            available_at: self.available_at,
+            delegate: self.delegate,
+            delegated_at: self.delegated_at,
+            previous_delegate: self.previous_delegate,
+            previous_delegated_at: self.previous_delegated_at,
+            slash_pending: self.slash_pending,
+            warmup_until: 0,
+            power_changed_at: self.created_at,
+        }
+    }
+}
+
+#[event]
+pub struct BatchMinted {
+    pub mint: Pubkey,
+    pub minter: Pubkey,
+    pub recipients: u16,
+    pub total_amount: u64,
+    pub new_supply: u64,
+    pub max_supply: u64,
+    pub remaining_supply: Option<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintGuardianUpdated {
+    pub mint: Pubkey,
+    pub guardian: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintingPauseUpdated {
+    pub mint: Pubkey,
+    pub paused: bool,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintAuthorityTransferred {
+    pub mint: Pubkey,
+    pub old_mint_authority: Option<Pubkey>,
+    pub new_mint_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintAuthorityRevoked {
+    pub mint: Pubkey,
+    pub total_supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FreezeAuthorityRevoked {
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintersEnabled {
+    pub mint: Pubkey,
+    pub mint_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MinterAdded {
+    pub mint: Pubkey,
+    pub minter: Pubkey,
+    pub cap: u64,
+    pub allowance_per_epoch: u64,
+    pub epoch_length_seconds: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MinterAllowanceUpdated {
+    pub mint: Pubkey,
+    pub minter: Pubkey,
+    pub allowance_per_epoch: u64,
+    pub epoch_length_seconds: i64,
+    pub minted_this_epoch: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MinterCapUpdated {
+    pub mint: Pubkey,
+    pub minter: Pubkey,
+    pub old_cap: u64,
+    pub new_cap: u64,
+    pub minted: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MinterRemoved {
+    pub mint: Pubkey,
+    pub minter: Pubkey,
+    pub minted: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameServerAdded {
+    pub mint: Pubkey,
+    pub server: Pubkey,
+    pub daily_budget: u64,
+    pub per_recipient_cap: u64,
+    pub player_registry: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameServerUpdated {
+    pub mint: Pubkey,
+    pub server: Pubkey,
+    pub daily_budget: u64,
+    pub per_recipient_cap: u64,
+    pub player_registry: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameServerRemoved {
+    pub mint: Pubkey,
+    pub server: Pubkey,
+    pub minted: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameRewardMinted {
+    pub mint: Pubkey,
+    pub server: Pubkey,
+    /// Token account credited.
+    pub recipient: Pubkey,
+    /// Owner of `recipient`, whom the per-recipient cap applies to.
+    pub player: Pubkey,
+    pub amount: u64,
+    /// Hash of the server's off-chain reason, for audit.
+    pub reason_hash: [u8; 32],
+    /// Server total for the current window, this mint included.
+    pub minted_in_window: u64,
+    pub new_supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintInitialized {
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub max_supply: u64,
+    pub authority: Pubkey,
+    pub token_program: Pubkey,
+    pub transfer_fee: Option<TransferFeeArgs>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MetadataCreated {
+    pub mint: Pubkey,
+    pub metadata: Pubkey,
+    pub uri: String,
+    pub update_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MetadataUpdated {
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MetadataFinalized {
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensMinted {
+    pub mint: Pubkey,
+    /// The mint authority or registered minter that signed.
+    pub minter: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+    pub new_supply: u64,
+    pub max_supply: u64,
+    pub remaining_supply: Option<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestingCreated {
+    pub mint: Pubkey,
+    pub beneficiary: Pubkey,
+    pub schedule_id: u64,
+    pub total_amount: u64,
+    pub start: i64,
+    pub cliff_seconds: i64,
+    pub duration_seconds: i64,
+    pub new_supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestedTokensClaimed {
+    pub mint: Pubkey,
+    pub beneficiary: Pubkey,
+    pub schedule_id: u64,
+    pub amount: u64,
+    pub released_amount: u64,
+    pub total_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestingRevoked {
+    pub mint: Pubkey,
+    pub beneficiary: Pubkey,
+    pub schedule_id: u64,
+    pub vested_amount: u64,
+    pub returned_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MaxSupplyLowered {
+    pub mint: Pubkey,
+    pub old_max_supply: u64,
+    pub new_max_supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsFunded {
+    pub mint: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub reward_reserve: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ExcessRewardsWithdrawn {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub reward_reserve: u64,
+    pub outstanding_liability: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeVaultCreated {
+    pub mint: Pubkey,
+    pub fee_vault: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CollectedFeesWithdrawn {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub destination: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RegistryPageAllocated {
+    pub page_index: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolDeprecationUpdated {
+    pub pool: Pubkey,
+    pub deprecated: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolClosed {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    /// Leftover reward and fee tokens sent to the authority.
+    pub swept_rewards: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RedemptionConfigured {
+    pub mint: Pubkey,
+    pub memecoin_program: Pubkey,
+    pub bridge_config: Pubkey,
+    pub redemption_pool: Pubkey,
+    pub rate_numerator: u64,
+    pub rate_denominator: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensRedeemed {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub burned: u64,
+    pub redeemed: u64,
+    pub recipient: Pubkey,
+    pub new_supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintConfigClosed {
+    pub mint: Pubkey,
+    pub supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DecimalsResynced {
+    pub mint: Pubkey,
+    pub old_decimals: u8,
+    pub new_decimals: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SupplyReconciled {
+    pub mint: Pubkey,
+    pub previous_supply: u64,
+    pub new_supply: u64,
+    /// `new_supply - previous_supply`; negative after outside burns.
+    pub delta: i128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensBurned {
+    pub mint: Pubkey,
+    pub from: Pubkey,
+    pub amount: u64,
+    pub new_supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakingPoolInitialized {
+    pub mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub authority: Pubkey,
+    pub stake_vault: Pubkey,
+    pub reward_vault: Pubkey,
+    pub reward_duration: i64,
+    pub min_stake_amount: u64,
+    pub min_lock_seconds: i64,
+    pub max_lock_seconds: i64,
+    /// Lamports paid to open the pool; 0 for the admin.
+    pub creation_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolCreationUpdated {
+    pub permissionless_pools: bool,
+    pub pool_creation_fee: u64,
+    pub fee_recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LockTiersUpdated {
+    pub mint: Pubkey,
+    pub lock_tiers: Vec<LockTier>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardTokenAdded {
+    pub mint: Pubkey,
+    pub slot: u8,
+    pub reward_mint: Pubkey,
+    pub vault: Pubkey,
+    pub reward_duration: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardTokenRemoved {
+    pub mint: Pubkey,
+    pub slot: u8,
+    pub reward_mint: Pubkey,
+    pub swept: u64,
+    pub rewards_owed: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SlotRewardsClaimed {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub slot: u8,
+    pub reward_mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardDurationUpdated {
+    pub mint: Pubkey,
+    pub old_reward_duration: i64,
+    pub new_reward_duration: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmissionScheduleSet {
+    pub mint: Pubkey,
+    pub epoch_length: i64,
+    pub initial_emission: u64,
+    pub halving_interval: u32,
+    pub reward_rate: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EpochRolled {
+    pub mint: Pubkey,
+    pub epoch: u32,
+    pub emission: u64,
+    pub reward_rate: u64,
+    pub epoch_start: i64,
+    pub epoch_end: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsNotified {
+    pub mint: Pubkey,
+    pub slot: u8,
+    pub reward_mint: Pubkey,
+    pub amount: u64,
+    pub reward_rate: u64,
+    pub period_finish: i64,
+    pub reward_reserve: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensStaked {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub receipt_mint: Option<Pubkey>,
+    pub amount: u64,
+    /// Lock tier applied, or `None` for no boost.
+    pub tier: Option<u8>,
+    pub boost_bps: u16,
+    pub lock_until: i64,
+    pub timestamp: i64,
+    pub mint: Pubkey,
+    pub pool: Pubkey,
+    pub stake_account: Pubkey,
+}
+
+#[event]
+pub struct StakeIncreased {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub old_amount: u64,
+    pub new_amount: u64,
+    pub lock_until: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensUnstaked {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub principal: u64,
+    /// Net of `reward_fee`.
+    pub reward: u64,
+    pub reward_fee: u64,
+    /// `principal + reward`, saturating; only for display.
+    pub total: u64,
+    pub timestamp: i64,
+    pub mint: Pubkey,
+    pub pool: Pubkey,
+    pub stake_account: Pubkey,
+    /// Principal left in the position; 0 once it is closed.
+    pub remaining: u64,
+}
+
+#[event]
+pub struct UnstakeRequested {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub amount: u64,
+    pub accrued_rewards: u64,
+    pub available_at: i64,
+    pub timestamp: i64,
+    pub mint: Pubkey,
+    pub pool: Pubkey,
+    pub stake_account: Pubkey,
+}
+
+#[event]
+pub struct UnstakeCancelled {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnstakeWithdrawn {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub principal: u64,
+    /// Net of `reward_fee`.
+    pub reward: u64,
+    pub reward_fee: u64,
+    pub timestamp: i64,
+    pub mint: Pubkey,
+    pub pool: Pubkey,
+    pub stake_account: Pubkey,
+}
+
+#[event]
+pub struct PoolUpdateDelayUpdated {
+    pub pool_update_delay_seconds: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolUpdateQueued {
+    pub mint: Pubkey,
+    pub update: PoolUpdate,
+    pub effective_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolUpdateExecuted {
+    pub mint: Pubkey,
+    pub update: PoolUpdate,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolUpdateCancelled {
+    pub mint: Pubkey,
+    pub update: PoolUpdate,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LpPoolUpdated {
+    pub mint: Pubkey,
+    pub lp_pool: bool,
+    pub boost_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AchievementBoostUpdated {
+    pub mint: Pubkey,
+    pub program: Pubkey,
+    pub min_wins: u64,
+    pub boost_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WarmupEnded {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub pool: Pubkey,
+    pub stake_account: Pubkey,
+    pub warmup_until: i64,
+    /// Added to the pool weight.
+    pub weight: u128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardWarmupUpdated {
+    pub mint: Pubkey,
+    pub reward_warmup_seconds: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MinClaimIntervalUpdated {
+    pub mint: Pubkey,
+    pub min_claim_interval_seconds: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralBonusUpdated {
+    pub mint: Pubkey,
+    pub referral_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralRecorded {
+    pub mint: Pubkey,
+    pub referee: Pubkey,
+    pub referrer: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VoteLockBonusUpdated {
+    pub mint: Pubkey,
+    pub vote_lock_bonus_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub pool: Pubkey,
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub snapshot_at: i64,
+    pub voting_ends_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VotingPowerSnapshotted {
+    pub proposal: Pubkey,
+    pub stake_account: Pubkey,
+    pub delegate: Option<Pubkey>,
+    pub power: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VoteCast {
+    pub proposal: Pubkey,
+    pub stake_account: Pubkey,
+    pub voter: Pubkey,
+    pub support: bool,
+    pub power: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolPauseUpdated {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyModeUpdated {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub emergency_mode: bool,
+    pub paused: bool,
+    pub emergency_destination: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyDestinationUpdated {
+    pub mint: Pubkey,
+    pub old_destination: Pubkey,
+    pub new_destination: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyVaultWithdrawn {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub vault: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub vault_balance: u64,
+    pub reward_reserve: u64,
+    pub outstanding_liability: u64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+}
 
-    #[account(
-        init,
-        payer = authority,
-        token::mint = mint,
-        token::authority = reward_vault_authority,
-        seeds = [b"reward_vault", mint.key().as_ref()],
-        bump
-    )]
-    pub reward_vault: Account<'info, TokenAccount>,
+#[event]
+pub struct DelegatedVoteCast {
+    pub proposal: Pubkey,
+    pub delegate: Pubkey,
+    pub support: bool,
+    pub power: u64,
+    pub positions: u32,
+    pub timestamp: i64,
+}
 
-    #[account(
-        seeds = [b"vault_authority", mint.key().as_ref()],
-        bump
-    )]
-    pub vault_authority: AccountInfo<'info>,
+#[event]
+pub struct VotesDelegated {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub delegate: Pubkey,
+    pub timestamp: i64,
+}
 
-    #[account(
-        seeds = [b"reward_vault_authority", mint.key().as_ref()],
-        bump
-    )]
-    pub reward_vault_authority: AccountInfo<'info>,
+#[event]
+pub struct DelegationRevoked {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub delegate: Pubkey,
+    pub timestamp: i64,
+}
 
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+#[event]
+pub struct ProposalFinalized {
+    pub pool: Pubkey,
+    pub proposal_id: u64,
+    pub passed: bool,
+    pub yes_power: u64,
+    pub no_power: u64,
+    pub timestamp: i64,
+}
 
-    pub mint: Account<'info, Mint>,
+#[event]
+pub struct LockExtended {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub old_lock_until: i64,
+    pub new_lock_until: i64,
+    pub tier: Option<u8>,
+    pub boost_bps: u16,
+    pub timestamp: i64,
+}
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+#[event]
+pub struct StakeLimitsUpdated {
+    pub mint: Pubkey,
+    pub min_stake_amount: u64,
+    pub min_lock_seconds: i64,
+    pub max_lock_seconds: i64,
+    pub timestamp: i64,
+}
 
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
+#[event]
+pub struct UnstakeCooldownUpdated {
+    pub mint: Pubkey,
+    pub unstake_cooldown_seconds: i64,
+    pub timestamp: i64,
 }
 
-#[derive(Accounts)]
-pub struct UnstakeTokens<'info> {
-    #[account(
-        mut,
-        has_one = owner,
-        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive
-    )]
-    pub stake_account: Account<'info, StakeAccount>,
+#[event]
+pub struct EarlyUnstaked {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub principal: u64,
+    pub penalty: u64,
+    /// Share of the penalty paid straight to the remaining stakers.
+    pub redistributed: u64,
+    pub refund: u64,
+    pub forfeited_reward: u64,
+    pub lock_until: i64,
+    pub timestamp: i64,
+}
 
-    #[account(mut)]
-    pub stake_vault: Account<'info, TokenAccount>,
+#[event]
+pub struct EarlyUnstakePenaltyUpdated {
+    pub mint: Pubkey,
+    pub early_unstake_penalty_bps: u16,
+    pub timestamp: i64,
+}
 
-    #[account(seeds = [b"vault_authority", mint.key().as_ref()], bump)]
-    pub vault_authority: AccountInfo<'info>,
+#[event]
+pub struct SlashConfigUpdated {
+    pub mint: Pubkey,
+    pub max_slash_bps: u16,
+    pub slash_timelock_seconds: i64,
+    pub timestamp: i64,
+}
 
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+#[event]
+pub struct SlashProposed {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub slash_bps: u16,
+    pub destination: Pubkey,
+    pub justification_hash: [u8; 32],
+    pub executable_at: i64,
+    pub timestamp: i64,
+}
 
-    /// CHECK: This is the owner of the stake account
-    pub owner: Signer<'info>,
+#[event]
+pub struct SlashCancelled {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub justification_hash: [u8; 32],
+    pub timestamp: i64,
+}
 
-    pub mint: Account<'info, Mint>,
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct StakeSlashed {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub amount: u64,
+    pub remaining_principal: u64,
+    pub destination: Pubkey,
+    pub redistributed: u64,
+    pub justification_hash: [u8; 32],
+    pub timestamp: i64,
 }
 
-#[derive(Accounts)]
-pub struct ClaimRewards<'info> {
-    #[account(
-        mut,
-        has_one = owner,
-        constraint = stake_account.is_active @ GamingTokenError::StakeNotActive
-    )]
-    pub stake_account: Account<'info, StakeAccount>,
+#[event]
+pub struct StakeAccountClosed {
+    pub owner: Pubkey,
+    pub position_index: u16,
+    pub rent_refunded: u64,
+    pub timestamp: i64,
+}
 
-    #[account(mut)]
-    pub reward_vault: Account<'info, TokenAccount>,
+#[event]
+pub struct PositionSplit {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub pool: Pubkey,
+    pub stake_account: Pubkey,
+    pub position_index: u16,
+    pub new_stake_account: Pubkey,
+    pub new_position_index: u16,
+    /// Moved to the new position.
+    pub amount: u64,
+    /// Left in the original position.
+    pub remaining: u64,
+    pub timestamp: i64,
+}
 
-    #[account(seeds = [b"reward_vault_authority", mint.key().as_ref()], bump)]
-    pub reward_vault_authority: AccountInfo<'info>,
+#[event]
+pub struct PositionsMerged {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub pool: Pubkey,
+    pub stake_account: Pubkey,
+    pub position_index: u16,
+    /// Closed into `stake_account`.
+    pub merged_stake_account: Pubkey,
+    pub merged_position_index: u16,
+    /// Combined principal.
+    pub amount: u64,
+    pub rewards_accrued: u64,
+    pub lock_until: i64,
+    pub boost_bps: u16,
+    pub timestamp: i64,
+}
 
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+#[event]
+pub struct SoftStaked {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub soft_position: Pubkey,
+    pub token_account: Pubkey,
+    pub amount: u64,
+    pub tier: Option<u8>,
+    pub boost_bps: u16,
+    pub lock_until: i64,
+    pub frozen: bool,
+    pub timestamp: i64,
+}
 
-    /// CHECK: This is the owner of the stake account
-    pub owner: Signer<'info>,
+#[event]
+pub struct SoftStakeShortfall {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub previous_amount: u64,
+    pub backed_amount: u64,
+    pub forfeited_rewards: u64,
+    pub timestamp: i64,
+}
 
-    pub mint: Account<'info, Mint>,
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct SoftUnstaked {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub soft_position: Pubkey,
+    pub token_account: Pubkey,
+    pub amount: u64,
+    pub reward: u64,
+    pub reward_fee: u64,
+    pub timestamp: i64,
 }
 
-#[account]
-pub struct MintConfig {
+#[event]
+pub struct LowReserveThresholdUpdated {
+    pub mint: Pubkey,
     pub authority: Pubkey,
+    pub threshold: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardReserveLow {
     pub mint: Pubkey,
-    pub token_name: String,
-    pub token_symbol: String,
-    pub decimals: u8,
-    pub total_supply: u64,
-    pub is_initialized: bool,
-    pub created_at: i64,
+    /// Reward vault balance after the payout.
+    pub vault_balance: u64,
+    pub reward_reserve: u64,
+    pub threshold: u64,
+    /// How long the lower of the two lasts at the current rate.
+    pub runway_seconds: Option<i64>,
+    pub timestamp: i64,
 }
 
-#[account]
-pub struct StakeAccount {
+#[event]
+pub struct RewardReserveRecovered {
+    pub mint: Pubkey,
+    pub vault_balance: u64,
+    pub reward_reserve: u64,
+    pub threshold: u64,
+    pub runway_seconds: Option<i64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsFreezeUpdated {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub frozen: bool,
+    pub frozen_at: i64,
+    pub period_finish: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardDebtRecorded {
     pub owner: Pubkey,
     pub mint: Pubkey,
     pub amount: u64,
+    pub reward_debt: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardDebtClaimed {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub paid: u64,
+    pub fee: u64,
+    pub remaining_debt: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolRateSnapshot {
+    pub mint: Pubkey,
+    pub reward_rate: u64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SnapshotCreated {
+    pub mint: Pubkey,
+    pub snapshot: Pubkey,
+    pub snapshot_id: u64,
+    pub slot: u64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SnapshotEntriesRecorded {
+    pub mint: Pubkey,
+    pub snapshot: Pubkey,
+    pub recorded: u32,
+    pub skipped: u32,
+    pub entry_count: u32,
+    pub recorded_amount: u64,
+}
+
+#[event]
+pub struct SnapshotFinalized {
+    pub mint: Pubkey,
+    pub snapshot: Pubkey,
+    pub snapshot_id: u64,
+    pub merkle_root: [u8; 32],
+    pub entry_count: u32,
+    pub recorded_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SunsetBegun {
+    pub mint: Pubkey,
+    pub pool: Pubkey,
+    pub successor_pool: Pubkey,
+    pub successor_mint: Pubkey,
+    pub migration_vault: Pubkey,
+    pub deadline: i64,
+    pub migration_bonus_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionMigrated {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub pool: Pubkey,
+    pub stake_account: Pubkey,
+    pub successor_pool: Pubkey,
+    pub new_stake_account: Pubkey,
+    pub new_position_index: u16,
+    /// Old principal, burned.
+    pub burned: u64,
+    /// Staked in the successor, `bonus` included.
+    pub migrated: u64,
+    pub bonus: u64,
+    /// Rewards paid out of the old pool, net of the claim fee.
+    pub rewards_paid: u64,
     pub lock_until: i64,
-    pub created_at: i64,
-    pub last_reward_claim: i64,
-    pub is_active: bool,
+    pub timestamp: i64,
 }
 
 #[event]
-pub struct MintInitialized {
+pub struct SunsetSwept {
     pub mint: Pubkey,
-    pub name: String,
-    pub symbol: String,
-    pub decimals: u8,
-    pub authority: Pubkey,
+    pub pool: Pubkey,
+    /// Principal of the positions that never migrated.
+    pub unmigrated: u64,
+    /// Moved to the escrow, anything stray in the stake vault included.
+    pub escrowed: u64,
+    pub positions: u64,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct TokensMinted {
+pub struct SunsetEscrowClaimed {
+    pub owner: Pubkey,
+    pub holder: Pubkey,
     pub mint: Pubkey,
-    pub to: Pubkey,
+    pub stake_account: Pubkey,
     pub amount: u64,
-    pub new_supply: u64,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct TokensStaked {
+pub struct StakeAccountMigrated {
+    pub stake_account: Pubkey,
+    pub owner: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardShortfall {
     pub owner: Pubkey,
+    pub owed: u64,
+    pub paid: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CompoundVaultCreated {
+    pub mint: Pubkey,
+    pub pool: Pubkey,
+    pub compound_vault: Pubkey,
+    pub share_mint: Pubkey,
+    pub vault_position: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultDeposited {
+    pub mint: Pubkey,
+    pub depositor: Pubkey,
+    /// Staked, as received by the stake vault.
     pub amount: u64,
-    pub lock_until: i64,
+    pub shares: u64,
+    pub total_shares: u64,
+    pub total_underlying: u64,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct TokensUnstaked {
+pub struct VaultWithdrawn {
+    pub mint: Pubkey,
+    pub withdrawer: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub total_shares: u64,
+    pub total_underlying: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultHarvested {
+    pub mint: Pubkey,
+    /// Left the reward vault, `fee` included.
+    pub compounded: u64,
+    pub fee: u64,
+    pub total_shares: u64,
+    pub total_underlying: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsCompounded {
     pub owner: Pubkey,
-    pub principal: u64,
-    pub reward: u64,
-    pub total: u64,
+    pub position_index: u16,
+    /// Moved into principal, net of `fee`.
+    pub amount: u64,
+    pub fee: u64,
+    pub new_principal: u64,
+    pub timestamp: i64,
+    /// Compounded on top of `amount` for solitaire achievements.
+    pub achievement_bonus: u64,
+}
+
+#[event]
+pub struct PendingRewardsComputed {
+    pub stake_account: Pubkey,
+    pub pending: u64,
+    pub claimable: u64,
+    pub slot_rewards: [u64; MAX_REWARD_SLOTS],
+    pub reward_debt: u64,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct RewardsClaimed {
     pub owner: Pubkey,
-    pub amount: u64,
+    pub position_index: u16,
+    /// Paid out of the reward vault.
+    pub gross: u64,
+    /// Sent to the pool's fee vault.
+    pub fee: u64,
+    /// Received by the staker.
+    pub net: u64,
+    pub referrer: Option<Pubkey>,
+    /// Paid to `referrer` on top of `gross`.
+    pub referral_bonus: u64,
     pub timestamp: i64,
+    pub mint: Pubkey,
+    pub pool: Pubkey,
+    pub stake_account: Pubkey,
+    /// Seconds since the position's previous claim, or since it was staked.
+    pub since_last_claim: i64,
+    /// Paid to the owner on top of `net` for solitaire achievements.
+    pub achievement_bonus: u64,
 }
 
 #[error_code]
@@ -449,4 +11777,236 @@ pub enum GamingTokenError {
     TokensStillLocked,
     #[msg("Insufficient funds")]
     InsufficientFunds,
+    #[msg("Invalid position index")]
+    InvalidPositionIndex,
+    #[msg("Invalid reward duration")]
+    InvalidRewardDuration,
+    #[msg("Invalid lock tiers")]
+    InvalidLockTiers,
+    #[msg("Invalid penalty")]
+    InvalidPenalty,
+    #[msg("Lock has expired, use unstake")]
+    LockExpired,
+    #[msg("Burn exceeds recorded supply")]
+    SupplyUnderflow,
+    #[msg("Mint would exceed the supply cap")]
+    SupplyCapExceeded,
+    #[msg("Supply cap can only be lowered, and not below the current supply")]
+    InvalidSupplyCap,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Pool has an unstake cooldown, use request_unstake")]
+    CooldownRequired,
+    #[msg("Unstake cooldown still running")]
+    CooldownActive,
+    #[msg("Position is unbonding")]
+    PositionUnbonding,
+    #[msg("Position is not unbonding")]
+    PositionNotUnbonding,
+    #[msg("Lock exceeds the pool or program maximum")]
+    LockTooLong,
+    #[msg("Lock is below the pool minimum")]
+    LockTooShort,
+    #[msg("Stake is below the pool minimum")]
+    StakeBelowMinimum,
+    #[msg("Partial unstake would leave less than the pool minimum")]
+    RemainderBelowMinimum,
+    #[msg("Pool creation is restricted to the admin")]
+    PoolCreationRestricted,
+    #[msg("Invalid LP boost")]
+    InvalidBoost,
+    #[msg("Claim fee exceeds the maximum")]
+    InvalidClaimFee,
+    #[msg("Fee vault required")]
+    FeeVaultRequired,
+    #[msg("Receipt accounts required")]
+    ReceiptAccountsRequired,
+    #[msg("Position is controlled by its receipt holder")]
+    ReceiptHolderOnly,
+    #[msg("Description too long")]
+    DescriptionTooLong,
+    #[msg("Invalid voting period")]
+    InvalidVotingPeriod,
+    #[msg("Voting has closed")]
+    VotingClosed,
+    #[msg("Voting is still open")]
+    VotingOpen,
+    #[msg("Position is not eligible to vote on this proposal")]
+    NotEligibleToVote,
+    #[msg("Position has already voted")]
+    AlreadyVoted,
+    #[msg("Proposal already finalized")]
+    ProposalFinalized,
+    #[msg("Position's votes are delegated")]
+    PositionDelegated,
+    #[msg("Position is not delegated")]
+    PositionNotDelegated,
+    #[msg("Pool is paused")]
+    PoolPaused,
+    #[msg("Pool is in emergency mode")]
+    EmergencyModeActive,
+    #[msg("Pool is not in emergency mode")]
+    EmergencyModeInactive,
+    #[msg("Emergency destination not set")]
+    EmergencyDestinationUnset,
+    #[msg("Invalid emergency destination")]
+    InvalidEmergencyDestination,
+    #[msg("Reward period still running")]
+    RewardPeriodActive,
+    #[msg("Rewards in another mint can only be claimed")]
+    CompoundUnavailable,
+    #[msg("All reward slots are in use")]
+    RewardSlotsFull,
+    #[msg("Invalid reward slot")]
+    InvalidRewardSlot,
+    #[msg("Reward slot has been removed")]
+    RewardSlotInactive,
+    #[msg("Invalid emission schedule")]
+    InvalidEmissionSchedule,
+    #[msg("Pool emissions follow a schedule")]
+    EmissionScheduleActive,
+    #[msg("Pool has no emission schedule")]
+    EmissionScheduleUnset,
+    #[msg("Epoch has not ended yet")]
+    EpochNotEnded,
+    #[msg("Invalid slash amount")]
+    InvalidSlashAmount,
+    #[msg("Invalid slash timelock")]
+    InvalidSlashTimelock,
+    #[msg("Slash dispute window has not passed")]
+    SlashTimelockActive,
+    #[msg("Position has a pending slash")]
+    SlashPending,
+    #[msg("Minter cap exceeded")]
+    MinterCapExceeded,
+    #[msg("Minter epoch allowance exceeded")]
+    MinterAllowanceExceeded,
+    #[msg("Invalid minter allowance")]
+    InvalidMinterAllowance,
+    #[msg("Invalid batch size")]
+    InvalidBatchSize,
+    #[msg("Token account is for another mint")]
+    MintMismatch,
+    #[msg("Mint does not match the mint config")]
+    ConfigMintMismatch,
+    #[msg("Mint decimals do not match the mint config")]
+    DecimalsMismatch,
+    #[msg("Minting is paused")]
+    MintingPaused,
+    #[msg("Metadata URI required")]
+    InvalidUri,
+    #[msg("URI too long")]
+    UriTooLong,
+    #[msg("Metadata has been finalized")]
+    MetadataFinalized,
+    #[msg("Mint authority has been revoked")]
+    MintAuthorityRevoked,
+    #[msg("Freeze authority has been revoked")]
+    FreezeAuthorityRevoked,
+    #[msg("Transfer fees need the Token-2022 program")]
+    TransferFeeUnsupported,
+    #[msg("Invalid transfer fee")]
+    InvalidTransferFee,
+    #[msg("Invalid vesting schedule")]
+    InvalidVestingSchedule,
+    #[msg("Nothing vested to claim")]
+    NothingVested,
+    #[msg("Vesting already revoked")]
+    VestingRevoked,
+    #[msg("Referral bonus exceeds the maximum")]
+    InvalidReferralBonus,
+    #[msg("Referrer and referral account must be passed together")]
+    ReferralAccountsRequired,
+    #[msg("Cannot refer yourself")]
+    SelfReferral,
+    #[msg("Only a first stake can be referred")]
+    AlreadyStaked,
+    #[msg("Pool update changes nothing")]
+    EmptyPoolUpdate,
+    #[msg("Pool update must respect the minimum delay")]
+    UpdateTooSoon,
+    #[msg("Pool update is not yet effective")]
+    UpdateNotReady,
+    #[msg("Pool still has open positions")]
+    PoolHasPositions,
+    #[msg("Pool still holds staked tokens")]
+    PoolNotEmpty,
+    #[msg("Vault still holds tokens")]
+    VaultNotEmpty,
+    #[msg("Pool for this mint has been closed")]
+    PoolClosed,
+    #[msg("Mint authority is live and supply is outstanding")]
+    MintStillActive,
+    #[msg("Stake account must be migrated first")]
+    MigrationRequired,
+    #[msg("Stake account is already in the current layout")]
+    AlreadyMigrated,
+    #[msg("Stake account data does not match any known layout")]
+    UnrecognizedStakeLayout,
+    #[msg("Only the registry page for the next pool can be allocated")]
+    RegistryPageNotNext,
+    #[msg("Pool is not on this registry page")]
+    PoolNotRegistered,
+    #[msg("Invalid claim interval")]
+    InvalidClaimInterval,
+    #[msg("Claimed too soon after the last claim")]
+    ClaimTooSoon,
+    #[msg("Not the claimer's solitaire player stats")]
+    InvalidPlayerStats,
+    #[msg("Cannot merge a position into itself")]
+    SamePosition,
+    #[msg("Invalid game server policy")]
+    InvalidGameServerPolicy,
+    #[msg("Game server daily budget exceeded")]
+    GameServerBudgetExceeded,
+    #[msg("Recipient daily cap exceeded")]
+    RecipientCapExceeded,
+    #[msg("Recipient is not a registered player")]
+    RecipientNotRegistered,
+    #[msg("Invalid reward warm-up")]
+    InvalidRewardWarmup,
+    #[msg("Position is not done warming up")]
+    WarmupActive,
+    #[msg("Nothing to claim")]
+    NothingToClaim,
+    #[msg("Amount too small for a whole share")]
+    SharesTooSmall,
+    #[msg("Pool is sunsetting")]
+    PoolSunset,
+    #[msg("Pool is not sunsetting")]
+    PoolNotSunset,
+    #[msg("Invalid successor pool")]
+    InvalidSuccessorPool,
+    #[msg("Sunset deadline must be in the future")]
+    InvalidSunsetDeadline,
+    #[msg("Invalid migration bonus")]
+    InvalidMigrationBonus,
+    #[msg("Sunset deadline has passed")]
+    SunsetDeadlinePassed,
+    #[msg("Sunset deadline has not passed")]
+    SunsetNotOver,
+    #[msg("Sunset has already been swept")]
+    SunsetSwept,
+    #[msg("Migration vault cannot cover this position")]
+    MigrationVaultUnderfunded,
+    #[msg("Rate was snapshotted too recently")]
+    SnapshotTooSoon,
+    #[msg("Pool rewards are frozen")]
+    RewardsFrozen,
+    #[msg("Pool rewards are not frozen")]
+    RewardsNotFrozen,
+    #[msg("Not the wallet's associated token account")]
+    InvalidAssociatedTokenAccount,
+    #[msg("Invalid redemption rate")]
+    InvalidRedemptionRate,
+    #[msg("Redemption pool cannot cover this burn")]
+    RedemptionPoolExhausted,
+    #[msg("Snapshot already finalized")]
+    SnapshotAlreadyFinalized,
+    #[msg("Invalid snapshot entry")]
+    InvalidSnapshotEntry,
+    #[msg("Invalid merkle root")]
+    InvalidMerkleRoot,
+    #[msg("Unsupported staking interface version")]
+    UnsupportedInterfaceVersion,
 }
\ No newline at end of file
@@ -0,0 +1,76 @@
+mod common;
+
+use common::*;
+use gaming_token::{RewardsClaimed, TokensUnstaked};
+
+const STAKE: u64 = 1_000_000_000;
+/// 10,000 a second over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+/// Emitted over ten days.
+const TEN_DAYS: u64 = REWARDS / 10;
+
+#[test]
+fn stakers_share_each_interval_by_the_weight_present() {
+    let (mut staking, pool) = Staking::with_pool();
+    let a = staking.staker(&pool, STAKE);
+    let b = staking.staker(&pool, 3 * STAKE);
+    let c = staking.staker(&pool, STAKE);
+
+    staking.stake(&pool, &a, 0, STAKE, DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(10 * DAY);
+    staking.stake(&pool, &b, 0, 3 * STAKE, DAY).unwrap();
+    staking.harness.warp(10 * DAY);
+    staking.stake(&pool, &c, 0, STAKE, DAY).unwrap();
+    staking.harness.warp(10 * DAY);
+
+    // A alone, then 1:3, then 1:3:1
+    staking.unstake(&pool, &b, 0).unwrap();
+    let b_reward = staking.harness.event::<TokensUnstaked>().reward;
+    assert_eq!(b_reward, TEN_DAYS / 4 * 3 + TEN_DAYS / 5 * 3);
+
+    // Then 1:1 once B has left
+    staking.harness.warp(10 * DAY);
+    staking.claim(&pool, &a, 0).unwrap();
+    let a_reward = staking.harness.event::<RewardsClaimed>().net;
+    assert_eq!(a_reward, TEN_DAYS + TEN_DAYS / 4 + TEN_DAYS / 5 + TEN_DAYS / 2);
+    staking.claim(&pool, &c, 0).unwrap();
+    let c_reward = staking.harness.event::<RewardsClaimed>().net;
+    assert_eq!(c_reward, TEN_DAYS / 5 + TEN_DAYS / 2);
+
+    // Forty days emitted, all of it paid
+    assert_eq!(a_reward + b_reward + c_reward, 4 * TEN_DAYS);
+    assert_eq!(staking.pool_state(&pool).total_rewards_paid, 4 * TEN_DAYS);
+}
+
+#[test]
+fn a_late_joiner_earns_nothing_from_before_it_joined() {
+    let (mut staking, pool) = Staking::with_pool();
+    let early = staking.staker(&pool, STAKE);
+    let late = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &early, 0, STAKE, DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+
+    staking.harness.warp(50 * DAY);
+    staking.stake(&pool, &late, 0, STAKE, DAY).unwrap();
+    assert_eq!(
+        staking.position(&pool, &late.wallet, 0).reward_per_token_paid,
+        staking.pool_state(&pool).reward_per_token_stored
+    );
+    staking.harness.warp(1);
+    staking.claim(&pool, &late, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().net, 5_000);
+}
+
+#[test]
+fn nothing_accrues_while_the_pool_is_empty() {
+    let (mut staking, pool) = Staking::with_pool();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(10 * DAY);
+
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    staking.harness.warp(DAY);
+    staking.claim(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().net, REWARDS / 100);
+}
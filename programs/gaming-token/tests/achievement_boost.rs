@@ -0,0 +1,170 @@
+mod common;
+
+use anchor_lang::prelude::{Pubkey, Rent};
+use anchor_lang::solana_program::{hash::hash, instruction::Instruction};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{
+    accounts, instruction, GamingTokenError, RewardsClaimed, MAX_ACHIEVEMENT_BOOST_BPS, PLAYER_STATS_SEED,
+};
+use program_harness::AccountState;
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+const MIN_WINS: u64 = 10;
+
+fn player_stats(program: &Pubkey, player: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[PLAYER_STATS_SEED, player.as_ref()], program).0
+}
+
+/// Stores a solitaire `PlayerStats` for `player` with `games_won`, owned by `program`.
+fn store_player_stats(staking: &mut Staking, program: &Pubkey, player: &Pubkey, games_won: u64) -> Pubkey {
+    let mut data = hash(b"account:PlayerStats").to_bytes()[..8].to_vec();
+    data.extend_from_slice(player.as_ref());
+    data.extend_from_slice(&games_won.to_le_bytes());
+    // active_games, fastest_win_seconds and bump
+    data.extend_from_slice(&[0; 4 + 1 + 1]);
+    let address = player_stats(program, player);
+    staking.harness.set_account(
+        address,
+        AccountState {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: *program,
+            executable: false,
+        },
+    );
+    address
+}
+
+/// `Staking::claim_ix` passing `player_stats` along.
+fn claim_with_stats_ix(pool: &Pool, staker: &Staker, player_stats: Pubkey) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::ClaimRewards {
+            stake_account: pool.stake_account(&staker.wallet, 0),
+            stake_pool: pool.address(),
+            reward_vault: pool.reward_vault(),
+            reward_vault_authority: pool.reward_vault_authority(),
+            fee_vault: None,
+            user_reward_token_account: staker.rewards,
+            receipt_token_account: None,
+            receipt_mint: None,
+            owner: staker.wallet,
+            staker_stats: pool.staker_stats(&staker.wallet),
+            referral: None,
+            referrer_token_account: None,
+            player_stats: Some(player_stats),
+            mint: pool.mint,
+            reward_mint: pool.reward_mint,
+            token_program: pool.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::ClaimRewards {}.data(),
+    }
+}
+
+/// Adds `amount` to the pool's reward reserve without starting a period.
+fn fund(staking: &mut Staking, pool: &Pool, amount: u64) {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::FundRewards {
+            stake_pool: pool.address(),
+            reward_vault: pool.reward_vault(),
+            funder_token_account: staking.admin_tokens(&pool.reward_mint),
+            funder: staking.admin,
+            reward_mint: pool.reward_mint,
+            token_program: pool.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::FundRewards { amount }.data(),
+    };
+    staking.admin(ix).unwrap();
+}
+
+/// A pool boosting `program`'s players, with a staker through a full period
+/// and `REWARDS` of reserve beyond what the period owes.
+fn boosted_pool(program: Pubkey) -> (Staking, Pool, Staker) {
+    let (mut staking, pool) = Staking::with_pool();
+    staking
+        .update_pool(
+            &pool,
+            instruction::SetAchievementBoost {
+                program,
+                min_wins: MIN_WINS,
+                boost_bps: MAX_ACHIEVEMENT_BOOST_BPS,
+            },
+        )
+        .unwrap();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    fund(&mut staking, &pool, REWARDS);
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.set_time(staking.pool_state(&pool).period_finish);
+    (staking, pool, staker)
+}
+
+#[test]
+fn enough_wins_earn_a_bonus_out_of_spare_reserve() {
+    let program = Pubkey::new_unique();
+    let (mut staking, pool, staker) = boosted_pool(program);
+    let stats = store_player_stats(&mut staking, &program, &staker.wallet, MIN_WINS);
+
+    let ix = claim_with_stats_ix(&pool, &staker, stats);
+    staking.harness.process(&[ix], &[staker.wallet]).unwrap();
+    let claimed = staking.harness.event::<RewardsClaimed>();
+    assert_eq!(claimed.net, REWARDS);
+    assert_eq!(claimed.achievement_bonus, REWARDS / 2);
+    assert_eq!(staking.balance(&staker.rewards), REWARDS + REWARDS / 2);
+}
+
+#[test]
+fn too_few_wins_or_no_stats_earn_nothing_extra() {
+    let program = Pubkey::new_unique();
+    let (mut staking, pool, staker) = boosted_pool(program);
+    let stats = store_player_stats(&mut staking, &program, &staker.wallet, MIN_WINS - 1);
+
+    let ix = claim_with_stats_ix(&pool, &staker, stats);
+    staking.harness.process(&[ix], &[staker.wallet]).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().achievement_bonus, 0);
+
+    let (mut staking, pool, staker) = boosted_pool(program);
+    staking.claim(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().achievement_bonus, 0);
+}
+
+#[test]
+fn only_the_claimers_stats_in_the_configured_program_count() {
+    let program = Pubkey::new_unique();
+    let (mut staking, pool, staker) = boosted_pool(program);
+    assert_error(
+        staking.update_pool(
+            &pool,
+            instruction::SetAchievementBoost {
+                program,
+                min_wins: MIN_WINS,
+                boost_bps: MAX_ACHIEVEMENT_BOOST_BPS + 1,
+            },
+        ),
+        GamingTokenError::InvalidBoost,
+    );
+
+    // Stats from another program
+    let foreign = store_player_stats(&mut staking, &Pubkey::new_unique(), &staker.wallet, MIN_WINS);
+    let ix = claim_with_stats_ix(&pool, &staker, foreign);
+    assert_error(
+        staking.harness.process(&[ix], &[staker.wallet]),
+        GamingTokenError::InvalidPlayerStats,
+    );
+
+    // Another player's stats
+    let other = staking.harness.funded_wallet();
+    let borrowed = store_player_stats(&mut staking, &program, &other, MIN_WINS);
+    let ix = claim_with_stats_ix(&pool, &staker, borrowed);
+    assert_error(
+        staking.harness.process(&[ix], &[staker.wallet]),
+        GamingTokenError::InvalidPlayerStats,
+    );
+}
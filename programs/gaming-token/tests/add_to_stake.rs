@@ -0,0 +1,78 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use gaming_token::{GamingTokenError, RewardsClaimed, StakeIncreased, TokensUnstaked};
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+#[test]
+fn top_up_pays_out_what_the_old_balance_earned() {
+    let (mut staking, pool) = Staking::with_pool();
+    let duration = PoolParams::default().reward_duration;
+    let alice = staking.staker(&pool, 3 * STAKE);
+    let bob = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &alice, 0, STAKE, duration).unwrap();
+    staking.stake(&pool, &bob, 0, STAKE, duration).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+
+    // Half the period split evenly, then Alice triples her stake
+    staking.harness.warp(duration / 2);
+    staking.add_to_stake(&pool, &alice, 0, 2 * STAKE).unwrap();
+    let claimed = staking.harness.event::<RewardsClaimed>();
+    assert_eq!(claimed.net, REWARDS / 4);
+    let increased = staking.harness.event::<StakeIncreased>();
+    assert_eq!(increased.owner, alice.wallet);
+    assert_eq!(increased.old_amount, STAKE);
+    assert_eq!(increased.new_amount, 3 * STAKE);
+    assert_eq!(staking.pool_state(&pool).total_staked, 4 * STAKE);
+
+    // The second half goes 3:1; the top-up relocked Alice past the period
+    staking.harness.set_time(staking.position(&pool, &alice.wallet, 0).lock_until);
+    staking.unstake(&pool, &alice, 0).unwrap();
+    let alice_rest = staking.harness.event::<TokensUnstaked>();
+    assert_eq!(alice_rest.principal, 3 * STAKE);
+    assert_eq!(alice_rest.reward, REWARDS / 8 * 3);
+    staking.unstake(&pool, &bob, 0).unwrap();
+    assert_eq!(staking.harness.event::<TokensUnstaked>().reward, REWARDS / 4 + REWARDS / 8);
+}
+
+#[test]
+fn top_up_restarts_the_lock_but_never_shortens_it() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, 3 * STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 10 * DAY).unwrap();
+    let lock_until = staking.position(&pool, &staker.wallet, 0).lock_until;
+
+    // Same second: the restarted lock ends where the old one does
+    staking.add_to_stake(&pool, &staker, 0, STAKE).unwrap();
+    assert_eq!(staking.position(&pool, &staker.wallet, 0).lock_until, lock_until);
+
+    staking.harness.warp(3 * DAY);
+    staking.add_to_stake(&pool, &staker, 0, STAKE).unwrap();
+    let position = staking.position(&pool, &staker.wallet, 0);
+    assert_eq!(position.lock_until, staking.harness.now() + 10 * DAY);
+    assert_eq!(position.lock_period, 10 * DAY);
+    assert_eq!(position.amount, 3 * STAKE);
+    assert_eq!(staking.harness.event::<StakeIncreased>().lock_until, position.lock_until);
+}
+
+#[test]
+fn top_up_is_rejected_for_nothing_or_another_owner() {
+    let (mut staking, pool) = Staking::with_pool();
+    let alice = staking.staker(&pool, 2 * STAKE);
+    let bob = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &alice, 0, STAKE, DAY).unwrap();
+
+    assert_error(staking.add_to_stake(&pool, &alice, 0, 0), GamingTokenError::InvalidAmount);
+
+    // Bob signing for Alice's position
+    let mut ix = staking.add_to_stake_ix(&pool, &bob, 0, STAKE);
+    ix.accounts[0].pubkey = pool.stake_account(&alice.wallet, 0);
+    assert_error(staking.harness.process(&[ix], &[bob.wallet]), ErrorCode::ConstraintSeeds);
+
+    assert_eq!(staking.position(&pool, &alice.wallet, 0).amount, STAKE);
+    assert_eq!(staking.balance(&bob.tokens), STAKE);
+}
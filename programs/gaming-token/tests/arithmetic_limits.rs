@@ -0,0 +1,80 @@
+mod common;
+
+use common::*;
+use gaming_token::{GamingTokenError, RewardsClaimed, TokensUnstaked, MAX_LOCK_PERIOD_SECONDS};
+
+/// A pool staking a fresh mint and paying a separately funded reward mint.
+fn dual_pool(staking: &mut Staking, params: PoolParams) -> Pool {
+    let admin = staking.admin;
+    let mint = staking.harness.create_mint(&TOKEN, &admin, 6);
+    let reward_mint = staking.create_mint(TOKEN);
+    staking.create_pool(mint, reward_mint, params).unwrap()
+}
+
+#[test]
+fn the_whole_u64_range_can_be_staked_and_paid() {
+    let mut staking = Staking::new();
+    let pool = dual_pool(&mut staking, PoolParams::default());
+    let staker = staking.staker(&pool, 0);
+    let admin = staking.admin;
+    staking.harness.mint_to(&pool.mint, &staker.tokens, &admin, u64::MAX);
+
+    staking.stake(&pool, &staker, 0, u64::MAX, DAY).unwrap();
+    assert_eq!(staking.pool_state(&pool).total_staked, u64::MAX);
+    staking.notify(&pool, ADMIN_SUPPLY).unwrap();
+
+    staking.harness.set_time(staking.pool_state(&pool).period_finish);
+    staking.unstake(&pool, &staker, 0).unwrap();
+    let unstaked = staking.harness.event::<TokensUnstaked>();
+    assert_eq!(unstaked.principal, u64::MAX);
+    assert_eq!(staking.balance(&staker.tokens), u64::MAX);
+    assert!(unstaked.reward > 0 && unstaked.reward <= ADMIN_SUPPLY);
+    assert_eq!(unstaked.total, u64::MAX);
+    assert_eq!(staking.balance(&staker.rewards), unstaked.reward);
+}
+
+#[test]
+fn a_single_unit_can_collect_a_whole_period() {
+    let mut staking = Staking::new();
+    let pool = dual_pool(&mut staking, PoolParams::default());
+    let staker = staking.staker(&pool, 0);
+    let admin = staking.admin;
+    staking.harness.mint_to(&pool.mint, &staker.tokens, &admin, 1);
+
+    staking.stake(&pool, &staker, 0, 1, DAY).unwrap();
+    staking.notify(&pool, ADMIN_SUPPLY).unwrap();
+    staking.harness.set_time(staking.pool_state(&pool).period_finish);
+    staking.claim(&pool, &staker, 0).unwrap();
+
+    // The rate floors to whole tokens a second
+    let duration = PoolParams::default().reward_duration as u64;
+    assert_eq!(staking.harness.event::<RewardsClaimed>().net, ADMIN_SUPPLY / duration * duration);
+}
+
+#[test]
+fn ten_year_locks_and_periods_fit() {
+    let mut staking = Staking::new();
+    let params = PoolParams {
+        reward_duration: MAX_LOCK_PERIOD_SECONDS,
+        ..PoolParams::default()
+    };
+    let pool = staking.single_token_pool(TOKEN, params);
+    let staker = staking.staker(&pool, 2_000_000);
+
+    assert_error(
+        staking.stake(&pool, &staker, 0, 1_000_000, MAX_LOCK_PERIOD_SECONDS + 1),
+        GamingTokenError::LockTooLong,
+    );
+    staking
+        .stake(&pool, &staker, 0, 1_000_000, MAX_LOCK_PERIOD_SECONDS)
+        .unwrap();
+    staking.notify(&pool, ADMIN_SUPPLY / 2).unwrap();
+    let state = staking.pool_state(&pool);
+    assert_eq!(state.period_finish, staking.harness.now() + MAX_LOCK_PERIOD_SECONDS);
+
+    staking.harness.set_time(staking.position(&pool, &staker.wallet, 0).lock_until);
+    staking.unstake(&pool, &staker, 0).unwrap();
+    let unstaked = staking.harness.event::<TokensUnstaked>();
+    assert_eq!(unstaked.principal, 1_000_000);
+    assert_eq!(unstaked.reward, state.reward_rate * MAX_LOCK_PERIOD_SECONDS as u64);
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use gaming_token::{BatchMinted, GamingTokenError, TokensMinted, MAX_BATCH_MINT_RECIPIENTS};
+
+const MAX_SUPPLY: u64 = 1_000;
+
+fn holders(minting: &mut Minting, count: usize) -> Vec<Pubkey> {
+    (0..count).map(|_| minting.holder()).collect()
+}
+
+#[test]
+fn one_aggregated_event_covers_the_batch() {
+    let mut minting = Minting::new(MAX_SUPPLY);
+    let holders = holders(&mut minting, 3);
+    let entries: Vec<_> = holders.iter().copied().zip([100, 200, 300]).collect();
+
+    let ix = minting.batch_mint_ix(&entries);
+    minting.process(ix).unwrap();
+
+    let event = minting.harness.event::<BatchMinted>();
+    assert_eq!(event.mint, minting.mint);
+    assert_eq!(event.minter, minting.authority);
+    assert_eq!(event.recipients, 3);
+    assert_eq!(event.total_amount, 600);
+    assert_eq!(event.new_supply, 600);
+    assert_eq!(event.max_supply, MAX_SUPPLY);
+    assert_eq!(event.remaining_supply, Some(400));
+    assert!(minting.harness.events::<TokensMinted>().is_empty());
+
+    for (holder, amount) in entries {
+        assert_eq!(minting.balance(&holder), amount);
+    }
+    assert_eq!(minting.config_state().total_supply, 600);
+}
+
+#[test]
+fn crossing_the_cap_partway_mints_nothing() {
+    let mut minting = Minting::new(MAX_SUPPLY);
+    let holders = holders(&mut minting, 3);
+    // The first two fit under the cap on their own
+    let entries: Vec<_> = holders.iter().copied().zip([400, 400, 201]).collect();
+
+    let ix = minting.batch_mint_ix(&entries);
+    assert_error(minting.process(ix), GamingTokenError::SupplyCapExceeded);
+
+    assert!(holders.iter().all(|holder| minting.balance(holder) == 0));
+    assert_eq!(minting.supply(), 0);
+    assert_eq!(minting.config_state().total_supply, 0);
+
+    let entries: Vec<_> = holders.iter().copied().zip([400, 400, 200]).collect();
+    let ix = minting.batch_mint_ix(&entries);
+    minting.process(ix).unwrap();
+    assert_eq!(minting.harness.event::<BatchMinted>().remaining_supply, Some(0));
+}
+
+#[test]
+fn the_batch_size_is_bounded() {
+    let mut minting = Minting::new(0);
+    let holders = holders(&mut minting, MAX_BATCH_MINT_RECIPIENTS + 1);
+    let entries: Vec<_> = holders.iter().map(|holder| (*holder, 1)).collect();
+
+    let ix = minting.batch_mint_ix(&entries);
+    assert_error(minting.process(ix), GamingTokenError::InvalidBatchSize);
+
+    let ix = minting.batch_mint_ix(&entries[..MAX_BATCH_MINT_RECIPIENTS]);
+    minting.process(ix).unwrap();
+    assert_eq!(
+        minting.harness.event::<BatchMinted>().recipients as usize,
+        MAX_BATCH_MINT_RECIPIENTS
+    );
+}
+
+#[test]
+fn amounts_must_match_the_accounts() {
+    let mut minting = Minting::new(0);
+    let holders = holders(&mut minting, 2);
+
+    let mut ix = minting.batch_mint_ix(&[(holders[0], 1), (holders[1], 1)]);
+    ix.accounts.pop();
+    assert_error(minting.process(ix), GamingTokenError::InvalidBatchSize);
+
+    let ix = minting.batch_mint_ix(&[(holders[0], 1), (holders[1], 0)]);
+    assert_error(minting.process(ix), GamingTokenError::InvalidAmount);
+}
+
+#[test]
+fn a_recipient_for_another_mint_fails_the_batch() {
+    let mut minting = Minting::new(0);
+    let holder = minting.holder();
+    let authority = minting.authority;
+    let other_mint = minting.harness.create_mint(&TOKEN, &authority, 6);
+    let foreign = minting.harness.create_token_account(&other_mint, &authority);
+
+    let ix = minting.batch_mint_ix(&[(holder, 5), (foreign, 5)]);
+    assert_error(minting.process(ix), GamingTokenError::MintMismatch);
+    assert_eq!(minting.balance(&holder), 0);
+}
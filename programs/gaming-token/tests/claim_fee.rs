@@ -0,0 +1,162 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{instruction::Instruction, sysvar};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{
+    accounts, instruction, GamingTokenError, PoolUpdate, RewardsClaimed, DEFAULT_POOL_UPDATE_DELAY_SECONDS,
+    MAX_CLAIM_FEE_BPS,
+};
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+fn fee_vault(pool: &Pool) -> Pubkey {
+    pda(&[b"fee_vault", pool.mint.as_ref()])
+}
+
+fn create_fee_vault_ix(staking: &Staking, pool: &Pool) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::CreateFeeVault {
+            stake_pool: pool.address(),
+            fee_vault: fee_vault(pool),
+            reward_vault_authority: pool.reward_vault_authority(),
+            reward_mint: pool.reward_mint,
+            authority: staking.admin,
+            system_program: system_program::ID,
+            token_program: pool.token_program,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::CreateFeeVault {}.data(),
+    }
+}
+
+fn fee_update(claim_fee_bps: u16) -> PoolUpdate {
+    PoolUpdate {
+        reward_duration: None,
+        emission_schedule: None,
+        claim_fee_bps: Some(claim_fee_bps),
+    }
+}
+
+/// Queues `claim_fee_bps` and executes it once the delay has passed.
+fn set_claim_fee(staking: &mut Staking, pool: &Pool, claim_fee_bps: u16) {
+    let effective_at = staking.harness.now() + DEFAULT_POOL_UPDATE_DELAY_SECONDS;
+    let ix = staking.queue_pool_update_ix(pool, fee_update(claim_fee_bps), effective_at);
+    staking.admin(ix).unwrap();
+    staking.harness.set_time(effective_at);
+    let ix = staking.execute_pool_update_ix(pool);
+    staking.admin(ix).unwrap();
+}
+
+/// `Staking::claim_ix` with the pool's fee vault passed along.
+fn claim_with_fee_ix(pool: &Pool, staker: &Staker) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::ClaimRewards {
+            stake_account: pool.stake_account(&staker.wallet, 0),
+            stake_pool: pool.address(),
+            reward_vault: pool.reward_vault(),
+            reward_vault_authority: pool.reward_vault_authority(),
+            fee_vault: Some(fee_vault(pool)),
+            user_reward_token_account: staker.rewards,
+            receipt_token_account: None,
+            receipt_mint: None,
+            owner: staker.wallet,
+            staker_stats: pool.staker_stats(&staker.wallet),
+            referral: None,
+            referrer_token_account: None,
+            player_stats: None,
+            mint: pool.mint,
+            reward_mint: pool.reward_mint,
+            token_program: pool.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::ClaimRewards {}.data(),
+    }
+}
+
+fn withdraw_fees_ix(staking: &Staking, pool: &Pool, amount: u64) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::WithdrawCollectedFees {
+            stake_pool: pool.address(),
+            fee_vault: fee_vault(pool),
+            reward_vault_authority: pool.reward_vault_authority(),
+            authority_token_account: staking.admin_tokens(&pool.reward_mint),
+            authority: staking.admin,
+            reward_mint: pool.reward_mint,
+            token_program: pool.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::WithdrawCollectedFees { amount }.data(),
+    }
+}
+
+#[test]
+fn a_claim_fee_splits_the_payout_into_the_fee_vault() {
+    let (mut staking, pool) = Staking::with_pool();
+    let ix = create_fee_vault_ix(&staking, &pool);
+    staking.admin(ix).unwrap();
+    set_claim_fee(&mut staking, &pool, MAX_CLAIM_FEE_BPS);
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.set_time(staking.pool_state(&pool).period_finish);
+
+    // The fee vault is required while a fee is set
+    assert_error(
+        staking.claim(&pool, &staker, 0),
+        GamingTokenError::FeeVaultRequired,
+    );
+
+    let ix = claim_with_fee_ix(&pool, &staker);
+    staking.harness.process(&[ix], &[staker.wallet]).unwrap();
+    let claimed = staking.harness.event::<RewardsClaimed>();
+    assert_eq!(claimed.gross, REWARDS);
+    assert_eq!(claimed.fee, REWARDS / 10);
+    assert_eq!(claimed.net, REWARDS - REWARDS / 10);
+    assert_eq!(staking.balance(&staker.rewards), claimed.net);
+    assert_eq!(staking.balance(&fee_vault(&pool)), claimed.fee);
+    assert_eq!(staking.balance(&pool.reward_vault()), 0);
+}
+
+#[test]
+fn only_the_pool_authority_opens_the_vault_and_withdraws_a_bounded_fee() {
+    let (mut staking, pool) = Staking::with_pool();
+    let effective_at = staking.harness.now() + DEFAULT_POOL_UPDATE_DELAY_SECONDS;
+    let ix = staking.queue_pool_update_ix(&pool, fee_update(100), effective_at);
+    assert_error(staking.admin(ix), GamingTokenError::FeeVaultRequired);
+
+    let stranger = staking.harness.funded_wallet();
+    let mut ix = create_fee_vault_ix(&staking, &pool);
+    swap_account(&mut ix, staking.admin, stranger);
+    assert_error(
+        staking.harness.process(&[ix], &[stranger]),
+        ErrorCode::ConstraintHasOne,
+    );
+    let ix = create_fee_vault_ix(&staking, &pool);
+    staking.admin(ix).unwrap();
+
+    let ix = staking.queue_pool_update_ix(&pool, fee_update(MAX_CLAIM_FEE_BPS + 1), effective_at);
+    assert_error(staking.admin(ix), GamingTokenError::InvalidClaimFee);
+    set_claim_fee(&mut staking, &pool, 100);
+    assert_eq!(staking.pool_state(&pool).claim_fee_bps, 100);
+
+    let admin_tokens = staking.admin_tokens(&pool.reward_mint);
+    staking.give(&pool.reward_mint, &fee_vault(&pool), 500);
+    let before = staking.balance(&admin_tokens);
+
+    let ix = withdraw_fees_ix(&staking, &pool, 501);
+    assert_error(staking.admin(ix), GamingTokenError::InsufficientFunds);
+    let ix = withdraw_fees_ix(&staking, &pool, 500);
+    staking.admin(ix).unwrap();
+    assert_eq!(staking.balance(&fee_vault(&pool)), 0);
+    assert_eq!(staking.balance(&admin_tokens), before + 500);
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::*;
+use gaming_token::{instruction, GamingTokenError, RewardsClaimed};
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+#[test]
+fn claims_wait_out_the_interval_but_compounding_does_not() {
+    let (mut staking, pool) = Staking::with_pool();
+    assert_error(
+        staking.update_pool(&pool, instruction::UpdateMinClaimInterval { min_claim_interval_seconds: -1 }),
+        GamingTokenError::InvalidClaimInterval,
+    );
+    staking
+        .update_pool(&pool, instruction::UpdateMinClaimInterval { min_claim_interval_seconds: DAY })
+        .unwrap();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+
+    // Stake time counts as the first claim
+    staking.harness.warp(DAY - 1);
+    assert_error(staking.claim(&pool, &staker, 0), GamingTokenError::ClaimTooSoon);
+    staking.compound(&pool, &staker, 0).unwrap();
+
+    staking.harness.warp(1);
+    staking.claim(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().since_last_claim, DAY);
+    staking.harness.warp(DAY - 1);
+    assert_error(staking.claim(&pool, &staker, 0), GamingTokenError::ClaimTooSoon);
+
+    // 0 turns the limit off
+    staking
+        .update_pool(&pool, instruction::UpdateMinClaimInterval { min_claim_interval_seconds: 0 })
+        .unwrap();
+    staking.claim(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().since_last_claim, DAY - 1);
+}
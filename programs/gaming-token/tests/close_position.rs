@@ -0,0 +1,64 @@
+mod common;
+
+use common::*;
+use gaming_token::{GamingTokenError, StakeAccountClosed, TokensStaked};
+
+const STAKE: u64 = 1_000_000;
+
+#[test]
+fn an_unstaked_position_is_closed_and_can_be_reopened() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    let position = pool.stake_account(&staker.wallet, 0);
+
+    let before = staking.harness.lamports(&staker.wallet);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    let rent = staking.harness.lamports(&position);
+    assert!(rent > 0);
+
+    staking.harness.warp(DAY);
+    staking.unstake(&pool, &staker, 0).unwrap();
+    let closed = staking.harness.event::<StakeAccountClosed>();
+    assert_eq!(closed.owner, staker.wallet);
+    assert_eq!(closed.position_index, 0);
+    assert_eq!(closed.rent_refunded, rent);
+    assert!(staking.harness.account(&position).is_none());
+
+    // Only the positions list and the stats account keep their rent
+    let kept = staking.harness.lamports(&pool.stake_positions(&staker.wallet))
+        + staking.harness.lamports(&pool.staker_stats(&staker.wallet));
+    assert_eq!(staking.harness.lamports(&staker.wallet), before - kept);
+
+    let positions = staking.positions(&pool, &staker.wallet);
+    assert_eq!(positions.open_positions, 0);
+    assert_eq!(positions.next_index, 1);
+    assert_eq!(staking.pool_state(&pool).total_positions, 0);
+
+    // The same index opens again from scratch
+    staking.harness.warp(DAY);
+    staking.stake(&pool, &staker, 0, STAKE, 2 * DAY).unwrap();
+    assert_eq!(staking.harness.event::<TokensStaked>().stake_account, position);
+    let reopened = staking.position(&pool, &staker.wallet, 0);
+    assert_eq!(reopened.amount, STAKE);
+    assert_eq!(reopened.created_at, staking.harness.now());
+    assert_eq!(reopened.lock_until, staking.harness.now() + 2 * DAY);
+    let positions = staking.positions(&pool, &staker.wallet);
+    assert_eq!(positions.open_positions, 1);
+    assert_eq!(positions.next_index, 1);
+}
+
+#[test]
+fn an_open_index_cannot_be_staked_twice_or_skipped() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, 3 * STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+
+    assert!(staking.stake(&pool, &staker, 0, STAKE, DAY).is_err());
+    assert_error(
+        staking.stake(&pool, &staker, 2, STAKE, DAY),
+        GamingTokenError::InvalidPositionIndex,
+    );
+    staking.stake(&pool, &staker, 1, STAKE, DAY).unwrap();
+    assert_eq!(staking.positions(&pool, &staker.wallet).open_positions, 2);
+    assert_eq!(staking.balance(&staker.tokens), STAKE);
+}
@@ -0,0 +1,862 @@
+//! Shared setup for the gaming-token program tests.
+#![allow(dead_code, unused_imports)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, program_error::ProgramError, sysvar};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use gaming_token::{
+    accounts, instruction, MintConfig, MinterRecord, PoolUpdate, StakeAccount, StakePool, StakePositions,
+};
+use program_harness::Harness;
+
+pub use anchor_spl::token::ID as TOKEN;
+pub use anchor_spl::token_2022::ID as TOKEN_2022;
+pub use program_harness::{account_bytes, anchor_error, assert_error, FUNDED_LAMPORTS};
+
+pub type TxResult = std::result::Result<(), ProgramError>;
+
+/// Minted to the admin for every mint `Staking` creates.
+pub const ADMIN_SUPPLY: u64 = 1_000_000_000_000_000;
+pub const DAY: i64 = 24 * 60 * 60;
+
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &gaming_token::ID).0
+}
+
+/// Points every use of `from` in `ix` at `to` instead.
+pub fn swap_account(ix: &mut Instruction, from: Pubkey, to: Pubkey) {
+    for meta in ix.accounts.iter_mut().filter(|meta| meta.pubkey == from) {
+        meta.pubkey = to;
+    }
+}
+
+/// Limits a pool is created with.
+#[derive(Clone, Copy, Debug)]
+pub struct PoolParams {
+    pub reward_duration: i64,
+    pub min_stake_amount: u64,
+    pub min_lock_seconds: i64,
+    pub max_lock_seconds: i64,
+}
+
+impl Default for PoolParams {
+    fn default() -> Self {
+        Self {
+            reward_duration: 100 * DAY,
+            min_stake_amount: 1,
+            min_lock_seconds: 1,
+            max_lock_seconds: 0,
+        }
+    }
+}
+
+/// A stake pool's keys; everything else is derived from its staked mint.
+#[derive(Clone, Copy, Debug)]
+pub struct Pool {
+    pub mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub token_program: Pubkey,
+}
+
+impl Pool {
+    pub fn address(&self) -> Pubkey {
+        pda(&[b"stake_pool", self.mint.as_ref()])
+    }
+
+    pub fn stake_vault(&self) -> Pubkey {
+        pda(&[b"stake_vault", self.mint.as_ref()])
+    }
+
+    pub fn reward_vault(&self) -> Pubkey {
+        pda(&[b"reward_vault", self.mint.as_ref()])
+    }
+
+    pub fn vault_authority(&self) -> Pubkey {
+        pda(&[b"vault_authority", self.mint.as_ref()])
+    }
+
+    pub fn reward_vault_authority(&self) -> Pubkey {
+        pda(&[b"reward_vault_authority", self.mint.as_ref()])
+    }
+
+    pub fn stake_account(&self, owner: &Pubkey, index: u16) -> Pubkey {
+        pda(&[b"stake", owner.as_ref(), self.mint.as_ref(), &index.to_le_bytes()])
+    }
+
+    pub fn stake_positions(&self, owner: &Pubkey) -> Pubkey {
+        pda(&[b"stake_positions", owner.as_ref(), self.mint.as_ref()])
+    }
+
+    pub fn staker_stats(&self, owner: &Pubkey) -> Pubkey {
+        pda(&[b"staker_stats", self.address().as_ref(), owner.as_ref()])
+    }
+}
+
+/// A wallet with token accounts for a pool's staked and reward mints; the
+/// two are the same account for single-token pools.
+#[derive(Clone, Copy, Debug)]
+pub struct Staker {
+    pub wallet: Pubkey,
+    pub tokens: Pubkey,
+    pub rewards: Pubkey,
+}
+
+/// The gaming-token program loaded into a harness, with its staking config
+/// and first registry page in place.
+pub struct Staking {
+    pub harness: Harness,
+    pub admin: Pubkey,
+}
+
+impl Staking {
+    pub fn new() -> Self {
+        let mut harness = Harness::new();
+        harness.add_program(gaming_token::ID, gaming_token::entry);
+        let admin = harness.funded_wallet();
+        let mut staking = Self { harness, admin };
+
+        let config = staking.staking_config();
+        let init = Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::InitializeStakingConfig {
+                staking_config: config,
+                admin,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializeStakingConfig {}.data(),
+        };
+        let page = Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::AllocateRegistryPage {
+                staking_config: config,
+                registry_page: staking.registry_page(0),
+                payer: admin,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::AllocateRegistryPage { page_index: 0 }.data(),
+        };
+        staking
+            .harness
+            .process(&[init, page], &[admin])
+            .expect("staking config initializes");
+        staking
+    }
+
+    /// A new single-token pool over a fresh SPL Token mint, default limits.
+    pub fn with_pool() -> (Self, Pool) {
+        let mut staking = Self::new();
+        let pool = staking.single_token_pool(TOKEN, PoolParams::default());
+        (staking, pool)
+    }
+
+    pub fn staking_config(&self) -> Pubkey {
+        pda(&[b"staking_config"])
+    }
+
+    pub fn registry_page(&self, page: u32) -> Pubkey {
+        pda(&[b"pool_registry", &page.to_le_bytes()])
+    }
+
+    /// The admin's token account for `mint`.
+    pub fn admin_tokens(&self, mint: &Pubkey) -> Pubkey {
+        pda(&[b"test_admin_tokens", mint.as_ref()])
+    }
+
+    /// A mint under `token_program` with `ADMIN_SUPPLY` in the admin's account.
+    pub fn create_mint(&mut self, token_program: Pubkey) -> Pubkey {
+        let admin = self.admin;
+        let mint = self.harness.create_mint(&token_program, &admin, 6);
+        self.fund_admin(&mint);
+        mint
+    }
+
+    /// A Token-2022 mint charging `fee_bps` per transfer, funded like `create_mint`.
+    pub fn create_fee_mint(&mut self, fee_bps: u16, max_fee: u64) -> Pubkey {
+        let admin = self.admin;
+        let mint = self.harness.create_fee_mint(&admin, 6, fee_bps, max_fee);
+        self.fund_admin(&mint);
+        mint
+    }
+
+    fn fund_admin(&mut self, mint: &Pubkey) {
+        let admin = self.admin;
+        let account = self.admin_tokens(mint);
+        self.harness.create_token_account_at(&account, mint, &admin);
+        self.harness.mint_to(mint, &account, &admin, ADMIN_SUPPLY);
+    }
+
+    /// Another pool staking and paying its own fresh mint under `token_program`.
+    pub fn single_token_pool(&mut self, token_program: Pubkey, params: PoolParams) -> Pool {
+        let mint = self.create_mint(token_program);
+        self.create_pool(mint, mint, params).expect("pool creates")
+    }
+
+    pub fn create_pool_ix(&self, pool: &Pool, payer: Pubkey, params: PoolParams) -> Instruction {
+        let config: gaming_token::StakingConfig = self.harness.get(&self.staking_config());
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::CreatePool {
+                staking_config: self.staking_config(),
+                registry_page: self.registry_page(config.registry_page()),
+                fee_recipient: config.fee_recipient,
+                stake_pool: pool.address(),
+                stake_vault: pool.stake_vault(),
+                reward_vault: pool.reward_vault(),
+                vault_authority: pool.vault_authority(),
+                reward_vault_authority: pool.reward_vault_authority(),
+                closed_pool: pda(&[b"closed_pool", pool.mint.as_ref()]),
+                mint: pool.mint,
+                reward_mint: pool.reward_mint,
+                payer,
+                system_program: system_program::ID,
+                token_program: pool.token_program,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::CreatePool {
+                reward_duration: params.reward_duration,
+                min_stake_amount: params.min_stake_amount,
+                min_lock_seconds: params.min_lock_seconds,
+                max_lock_seconds: params.max_lock_seconds,
+            }
+            .data(),
+        }
+    }
+
+    pub fn create_pool(
+        &mut self,
+        mint: Pubkey,
+        reward_mint: Pubkey,
+        params: PoolParams,
+    ) -> std::result::Result<Pool, ProgramError> {
+        let pool = Pool {
+            mint,
+            reward_mint,
+            token_program: self.harness.account(&mint).unwrap().owner,
+        };
+        let admin = self.admin;
+        let ix = self.create_pool_ix(&pool, admin, params);
+        self.harness.process(&[ix], &[admin])?;
+        Ok(pool)
+    }
+
+    /// Runs `ix` signed by the admin.
+    pub fn admin(&mut self, ix: Instruction) -> TxResult {
+        let admin = self.admin;
+        self.harness.process(&[ix], &[admin])
+    }
+
+    pub fn pool_state(&self, pool: &Pool) -> StakePool {
+        self.harness.get(&pool.address())
+    }
+
+    pub fn position(&self, pool: &Pool, owner: &Pubkey, index: u16) -> StakeAccount {
+        self.harness.get(&pool.stake_account(owner, index))
+    }
+
+    pub fn positions(&self, pool: &Pool, owner: &Pubkey) -> StakePositions {
+        self.harness.get(&pool.stake_positions(owner))
+    }
+
+    pub fn balance(&self, account: &Pubkey) -> u64 {
+        self.harness.token_balance(account)
+    }
+
+    /// Sends `amount` of `mint` from the admin to `destination`.
+    pub fn give(&mut self, mint: &Pubkey, destination: &Pubkey, amount: u64) {
+        let token_program = self.harness.account(mint).unwrap().owner;
+        let decimals = self.harness.mint(mint).decimals;
+        let ix = anchor_spl::token_2022::spl_token_2022::instruction::transfer_checked(
+            &token_program,
+            &self.admin_tokens(mint),
+            mint,
+            destination,
+            &self.admin,
+            &[],
+            amount,
+            decimals,
+        )
+        .unwrap();
+        self.admin(ix).expect("admin transfer succeeds");
+    }
+
+    /// A funded wallet holding `amount` of the pool's staked mint.
+    pub fn staker(&mut self, pool: &Pool, amount: u64) -> Staker {
+        let wallet = self.harness.funded_wallet();
+        let tokens = self.harness.create_token_account(&pool.mint, &wallet);
+        if amount > 0 {
+            self.give(&pool.mint, &tokens, amount);
+        }
+        let rewards = if pool.reward_mint == pool.mint {
+            tokens
+        } else {
+            self.harness.create_token_account(&pool.reward_mint, &wallet)
+        };
+        Staker {
+            wallet,
+            tokens,
+            rewards,
+        }
+    }
+
+    pub fn notify_ix(&self, pool: &Pool, amount: u64) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::NotifyRewardAmount {
+                stake_pool: pool.address(),
+                reward_vault: pool.reward_vault(),
+                authority_token_account: self.admin_tokens(&pool.reward_mint),
+                authority: self.admin,
+                reward_mint: pool.reward_mint,
+                token_program: pool.token_program,
+            }
+            .to_account_metas(None),
+            data: instruction::NotifyRewardAmount {
+                slot_index: 0,
+                amount,
+            }
+            .data(),
+        }
+    }
+
+    /// Starts a reward period emitting `amount` over the pool's duration.
+    pub fn notify(&mut self, pool: &Pool, amount: u64) -> TxResult {
+        let ix = self.notify_ix(pool, amount);
+        self.admin(ix)
+    }
+
+    /// A pool setting change signed by the pool authority, the admin here.
+    pub fn update_pool_ix(&self, pool: &Pool, data: impl InstructionData) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::UpdateStakePool {
+                stake_pool: pool.address(),
+                authority: self.admin,
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        }
+    }
+
+    pub fn update_pool(&mut self, pool: &Pool, data: impl InstructionData) -> TxResult {
+        let ix = self.update_pool_ix(pool, data);
+        self.admin(ix)
+    }
+
+    pub fn pool_update(&self, pool: &Pool) -> Pubkey {
+        pda(&[b"pool_update", pool.address().as_ref()])
+    }
+
+    pub fn queue_pool_update_ix(&self, pool: &Pool, update: PoolUpdate, effective_at: i64) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::QueuePoolUpdate {
+                staking_config: self.staking_config(),
+                stake_pool: pool.address(),
+                pending_update: self.pool_update(pool),
+                authority: self.admin,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::QueuePoolUpdate { update, effective_at }.data(),
+        }
+    }
+
+    pub fn execute_pool_update_ix(&self, pool: &Pool) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::ExecutePoolUpdate {
+                stake_pool: pool.address(),
+                pending_update: self.pool_update(pool),
+                authority: self.admin,
+            }
+            .to_account_metas(None),
+            data: instruction::ExecutePoolUpdate {}.data(),
+        }
+    }
+
+    pub fn stake_ix(&self, pool: &Pool, staker: &Staker, index: u16, amount: u64, lock: i64) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::StakeTokens {
+                stake_account: pool.stake_account(&staker.wallet, index),
+                stake_positions: pool.stake_positions(&staker.wallet),
+                stake_pool: pool.address(),
+                stake_vault: pool.stake_vault(),
+                user_token_account: staker.tokens,
+                mint: pool.mint,
+                receipt_mint: None,
+                receipt_token_account: None,
+                receipt_authority: None,
+                referral: None,
+                authority: staker.wallet,
+                staker_stats: pool.staker_stats(&staker.wallet),
+                system_program: system_program::ID,
+                token_program: pool.token_program,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::StakeTokens {
+                position_index: index,
+                amount,
+                lock_period: lock,
+                with_receipt: false,
+                referrer: None,
+            }
+            .data(),
+        }
+    }
+
+    pub fn stake(&mut self, pool: &Pool, staker: &Staker, index: u16, amount: u64, lock: i64) -> TxResult {
+        let ix = self.stake_ix(pool, staker, index, amount, lock);
+        self.harness.process(&[ix], &[staker.wallet])
+    }
+
+    pub fn add_to_stake_ix(&self, pool: &Pool, staker: &Staker, index: u16, amount: u64) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::AddToStake {
+                stake_account: pool.stake_account(&staker.wallet, index),
+                stake_pool: pool.address(),
+                stake_vault: pool.stake_vault(),
+                reward_vault: pool.reward_vault(),
+                reward_vault_authority: pool.reward_vault_authority(),
+                fee_vault: None,
+                user_token_account: staker.tokens,
+                user_reward_token_account: staker.rewards,
+                owner: staker.wallet,
+                staker_stats: pool.staker_stats(&staker.wallet),
+                mint: pool.mint,
+                reward_mint: pool.reward_mint,
+                token_program: pool.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::AddToStake { amount }.data(),
+        }
+    }
+
+    pub fn add_to_stake(&mut self, pool: &Pool, staker: &Staker, index: u16, amount: u64) -> TxResult {
+        let ix = self.add_to_stake_ix(pool, staker, index, amount);
+        self.harness.process(&[ix], &[staker.wallet])
+    }
+
+    pub fn partial_unstake_ix(&self, pool: &Pool, staker: &Staker, index: u16, amount: u64) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::PartialUnstake {
+                stake_account: pool.stake_account(&staker.wallet, index),
+                stake_pool: pool.address(),
+                stake_positions: pool.stake_positions(&staker.wallet),
+                stake_vault: pool.stake_vault(),
+                vault_authority: pool.vault_authority(),
+                reward_vault: pool.reward_vault(),
+                reward_vault_authority: pool.reward_vault_authority(),
+                fee_vault: None,
+                user_token_account: staker.tokens,
+                user_reward_token_account: staker.rewards,
+                owner: staker.wallet,
+                staker_stats: pool.staker_stats(&staker.wallet),
+                mint: pool.mint,
+                reward_mint: pool.reward_mint,
+                token_program: pool.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::PartialUnstake { amount }.data(),
+        }
+    }
+
+    pub fn partial_unstake(&mut self, pool: &Pool, staker: &Staker, index: u16, amount: u64) -> TxResult {
+        let ix = self.partial_unstake_ix(pool, staker, index, amount);
+        self.harness.process(&[ix], &[staker.wallet])
+    }
+
+    /// A position change signed by its owner, such as `extend_lock`.
+    pub fn update_position_ix(&self, pool: &Pool, staker: &Staker, index: u16, data: impl InstructionData) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::UpdateStakePosition {
+                stake_account: pool.stake_account(&staker.wallet, index),
+                stake_pool: pool.address(),
+                receipt_token_account: None,
+                owner: staker.wallet,
+                mint: pool.mint,
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        }
+    }
+
+    pub fn update_position(&mut self, pool: &Pool, staker: &Staker, index: u16, data: impl InstructionData) -> TxResult {
+        let ix = self.update_position_ix(pool, staker, index, data);
+        self.harness.process(&[ix], &[staker.wallet])
+    }
+
+    pub fn claim_ix(&self, pool: &Pool, staker: &Staker, index: u16) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::ClaimRewards {
+                stake_account: pool.stake_account(&staker.wallet, index),
+                stake_pool: pool.address(),
+                reward_vault: pool.reward_vault(),
+                reward_vault_authority: pool.reward_vault_authority(),
+                fee_vault: None,
+                user_reward_token_account: staker.rewards,
+                receipt_token_account: None,
+                receipt_mint: None,
+                owner: staker.wallet,
+                staker_stats: pool.staker_stats(&staker.wallet),
+                referral: None,
+                referrer_token_account: None,
+                player_stats: None,
+                mint: pool.mint,
+                reward_mint: pool.reward_mint,
+                token_program: pool.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ClaimRewards {}.data(),
+        }
+    }
+
+    pub fn claim(&mut self, pool: &Pool, staker: &Staker, index: u16) -> TxResult {
+        let ix = self.claim_ix(pool, staker, index);
+        self.harness.process(&[ix], &[staker.wallet])
+    }
+
+    pub fn unstake_ix(&self, pool: &Pool, staker: &Staker, index: u16) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::UnstakeTokens {
+                stake_account: pool.stake_account(&staker.wallet, index),
+                stake_pool: pool.address(),
+                stake_positions: pool.stake_positions(&staker.wallet),
+                stake_vault: pool.stake_vault(),
+                vault_authority: pool.vault_authority(),
+                reward_vault: pool.reward_vault(),
+                reward_vault_authority: pool.reward_vault_authority(),
+                fee_vault: None,
+                user_token_account: staker.tokens,
+                user_reward_token_account: staker.rewards,
+                receipt_token_account: None,
+                receipt_mint: None,
+                owner: staker.wallet,
+                staker_stats: pool.staker_stats(&staker.wallet),
+                mint: pool.mint,
+                reward_mint: pool.reward_mint,
+                token_program: pool.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::UnstakeTokens {}.data(),
+        }
+    }
+
+    pub fn unstake(&mut self, pool: &Pool, staker: &Staker, index: u16) -> TxResult {
+        let ix = self.unstake_ix(pool, staker, index);
+        self.harness.process(&[ix], &[staker.wallet])
+    }
+
+    /// `early_unstake` takes the same accounts as `unstake_tokens`.
+    pub fn early_unstake_ix(&self, pool: &Pool, staker: &Staker, index: u16) -> Instruction {
+        Instruction {
+            data: instruction::EarlyUnstake {}.data(),
+            ..self.unstake_ix(pool, staker, index)
+        }
+    }
+
+    pub fn early_unstake(&mut self, pool: &Pool, staker: &Staker, index: u16) -> TxResult {
+        let ix = self.early_unstake_ix(pool, staker, index);
+        self.harness.process(&[ix], &[staker.wallet])
+    }
+
+    pub fn compound_ix(&self, pool: &Pool, staker: &Staker, index: u16) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::CompoundRewards {
+                stake_account: pool.stake_account(&staker.wallet, index),
+                stake_pool: pool.address(),
+                stake_vault: pool.stake_vault(),
+                reward_vault: pool.reward_vault(),
+                reward_vault_authority: pool.reward_vault_authority(),
+                fee_vault: None,
+                owner: staker.wallet,
+                staker_stats: pool.staker_stats(&staker.wallet),
+                player_stats: None,
+                mint: pool.mint,
+                token_program: pool.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::CompoundRewards {}.data(),
+        }
+    }
+
+    pub fn compound(&mut self, pool: &Pool, staker: &Staker, index: u16) -> TxResult {
+        let ix = self.compound_ix(pool, staker, index);
+        self.harness.process(&[ix], &[staker.wallet])
+    }
+}
+
+/// A gaming token created through `initialize_mint`, with its config.
+pub struct Minting {
+    pub harness: Harness,
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+}
+
+impl Minting {
+    /// A 6-decimal SPL Token mint capped at `max_supply`, 0 for uncapped.
+    pub fn new(max_supply: u64) -> Self {
+        Self::with_program(TOKEN, max_supply)
+    }
+
+    pub fn with_program(token_program: Pubkey, max_supply: u64) -> Self {
+        let mut harness = Harness::new();
+        harness.add_program(gaming_token::ID, gaming_token::entry);
+        let authority = harness.funded_wallet();
+        let mut minting = Self {
+            harness,
+            authority,
+            mint: Pubkey::new_unique(),
+            token_program,
+        };
+        let ix = minting.initialize_mint_ix(minting.mint, max_supply);
+        let mint = minting.mint;
+        minting
+            .harness
+            .process(&[ix], &[authority, mint])
+            .expect("mint initializes");
+        minting
+    }
+
+    pub fn initialize_mint_ix(&self, mint: Pubkey, max_supply: u64) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::InitializeMint {
+                mint_config: self.config_for(&mint),
+                mint,
+                authority: self.authority,
+                system_program: system_program::ID,
+                token_program: self.token_program,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializeMint {
+                token_name: "Sol-itaire Gold".to_string(),
+                token_symbol: "GOLD".to_string(),
+                decimals: 6,
+                max_supply,
+                transfer_fee: None,
+            }
+            .data(),
+        }
+    }
+
+    pub fn config_for(&self, mint: &Pubkey) -> Pubkey {
+        pda(&[b"mint_config", mint.as_ref()])
+    }
+
+    pub fn config(&self) -> Pubkey {
+        self.config_for(&self.mint)
+    }
+
+    pub fn config_state(&self) -> MintConfig {
+        self.harness.get(&self.config())
+    }
+
+    /// Rewrites the stored config in place, as a legacy or drifted one would be.
+    pub fn edit_config(&mut self, edit: impl FnOnce(&mut MintConfig)) {
+        let key = self.config();
+        let mut config = self.config_state();
+        edit(&mut config);
+        let mut state = self.harness.account(&key).unwrap().clone();
+        let bytes = account_bytes(&config);
+        state.data[..bytes.len()].copy_from_slice(&bytes);
+        self.harness.set_account(key, state);
+    }
+
+    pub fn mint_authority(&self) -> Pubkey {
+        pda(&[b"mint_authority", self.mint.as_ref()])
+    }
+
+    pub fn minter_record(&self, minter: &Pubkey) -> Pubkey {
+        pda(&[b"minter", self.mint.as_ref(), minter.as_ref()])
+    }
+
+    pub fn minter_state(&self, minter: &Pubkey) -> MinterRecord {
+        self.harness.get(&self.minter_record(minter))
+    }
+
+    /// Runs `ix` signed by the authority.
+    pub fn process(&mut self, ix: Instruction) -> TxResult {
+        let authority = self.authority;
+        self.harness.process(&[ix], &[authority])
+    }
+
+    /// A token account for the gaming token, owned by a fresh wallet.
+    pub fn holder(&mut self) -> Pubkey {
+        let owner = self.harness.funded_wallet();
+        let mint = self.mint;
+        self.harness.create_token_account(&mint, &owner)
+    }
+
+    pub fn balance(&self, account: &Pubkey) -> u64 {
+        self.harness.token_balance(account)
+    }
+
+    pub fn supply(&self) -> u64 {
+        self.harness.mint(&self.mint).supply
+    }
+
+    pub fn mint_tokens_ix(&self, token_account: Pubkey, amount: u64) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::MintTokens {
+                mint_config: self.config(),
+                mint: self.mint,
+                mint_authority: self.mint_authority(),
+                token_account,
+                authority: self.authority,
+                token_program: self.token_program,
+            }
+            .to_account_metas(None),
+            data: instruction::MintTokens { amount }.data(),
+        }
+    }
+
+    pub fn mint_tokens(&mut self, token_account: Pubkey, amount: u64) -> TxResult {
+        let ix = self.mint_tokens_ix(token_account, amount);
+        self.process(ix)
+    }
+
+    /// `batch_mint` paying `(token account, amount)` entries in order.
+    pub fn batch_mint_ix(&self, entries: &[(Pubkey, u64)]) -> Instruction {
+        let mut ix = self.mint_tokens_ix(entries.first().map_or(Pubkey::default(), |entry| entry.0), 1);
+        // `MintTokens` wants a destination; the batch only reads remaining accounts
+        ix.accounts
+            .extend(entries.iter().map(|(account, _)| AccountMeta::new(*account, false)));
+        ix.data = instruction::BatchMint {
+            amounts: entries.iter().map(|entry| entry.1).collect(),
+        }
+        .data();
+        ix
+    }
+
+    pub fn manage_authority_ix(&self, data: impl InstructionData) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::ManageMintAuthority {
+                mint_config: self.config(),
+                mint: self.mint,
+                mint_authority: self.mint_authority(),
+                authority: self.authority,
+                token_program: self.token_program,
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        }
+    }
+
+    pub fn enable_minters(&mut self) -> TxResult {
+        let ix = self.manage_authority_ix(instruction::EnableMinters {});
+        self.process(ix)
+    }
+
+    pub fn add_minter_ix(&self, minter: Pubkey, cap: u64, allowance_per_epoch: u64, epoch_length_seconds: i64) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::AddMinter {
+                mint_config: self.config(),
+                minter_record: self.minter_record(&minter),
+                authority: self.authority,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::AddMinter {
+                minter,
+                cap,
+                allowance_per_epoch,
+                epoch_length_seconds,
+            }
+            .data(),
+        }
+    }
+
+    /// A funded minter key registered with the given limits, minters enabled.
+    pub fn minter(&mut self, cap: u64, allowance_per_epoch: u64, epoch_length_seconds: i64) -> Pubkey {
+        if self.harness.mint(&self.mint).mint_authority != Some(self.mint_authority()).into() {
+            self.enable_minters().expect("minters enable");
+        }
+        let minter = self.harness.funded_wallet();
+        let ix = self.add_minter_ix(minter, cap, allowance_per_epoch, epoch_length_seconds);
+        self.process(ix).expect("minter is added");
+        minter
+    }
+
+    pub fn mint_as_minter_ix(&self, minter: Pubkey, token_account: Pubkey, amount: u64) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::MintTokensAsMinter {
+                mint_config: self.config(),
+                minter_record: self.minter_record(&minter),
+                mint: self.mint,
+                mint_authority: self.mint_authority(),
+                token_account,
+                minter,
+                token_program: self.token_program,
+            }
+            .to_account_metas(None),
+            data: instruction::MintTokensAsMinter { amount }.data(),
+        }
+    }
+
+    pub fn mint_as_minter(&mut self, minter: Pubkey, token_account: Pubkey, amount: u64) -> TxResult {
+        let ix = self.mint_as_minter_ix(minter, token_account, amount);
+        self.harness.process(&[ix], &[minter])
+    }
+
+    /// A minter record change signed by the authority.
+    pub fn update_minter_ix(&self, minter: &Pubkey, data: impl InstructionData) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::UpdateMinter {
+                mint_config: self.config(),
+                minter_record: self.minter_record(minter),
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        }
+    }
+
+    /// A `MintConfig` setting change signed by the authority.
+    pub fn update_config_ix(&self, data: impl InstructionData) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::UpdateMintConfig {
+                mint_config: self.config(),
+                authority: self.authority,
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        }
+    }
+
+    pub fn set_minting_paused(&mut self, actor: Pubkey, paused: bool) -> TxResult {
+        let ix = Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::SetMintingPaused {
+                mint_config: self.config(),
+                actor,
+            }
+            .to_account_metas(None),
+            data: instruction::SetMintingPaused { paused }.data(),
+        };
+        self.harness.process(&[ix], &[actor])
+    }
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::*;
+use gaming_token::{GamingTokenError, RewardsClaimed, RewardsCompounded};
+
+const STAKE: u64 = 1_000_000_000;
+/// 10,000 a second over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+#[derive(Clone, Copy)]
+enum Strategy {
+    Compound,
+    ClaimAndRestake,
+    Claim,
+}
+
+/// What a staker sharing the pool with an equal, passive staker walks away
+/// with after a full period, acting on their rewards every 25 days.
+fn run(strategy: Strategy) -> u64 {
+    let (mut staking, pool) = Staking::with_pool();
+    let active = staking.staker(&pool, STAKE);
+    let passive = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &active, 0, STAKE, DAY).unwrap();
+    staking.stake(&pool, &passive, 0, STAKE, DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+
+    let mut positions = 1;
+    for _ in 0..3 {
+        staking.harness.warp(25 * DAY);
+        match strategy {
+            Strategy::Compound => staking.compound(&pool, &active, 0).unwrap(),
+            Strategy::ClaimAndRestake => {
+                staking.claim(&pool, &active, 0).unwrap();
+                let claimed = staking.harness.event::<RewardsClaimed>().net;
+                staking.stake(&pool, &active, positions, claimed, DAY).unwrap();
+                positions += 1;
+            }
+            Strategy::Claim => staking.claim(&pool, &active, 0).unwrap(),
+        }
+    }
+
+    staking.harness.set_time(staking.pool_state(&pool).period_finish);
+    for index in 0..positions {
+        staking.unstake(&pool, &active, index).unwrap();
+    }
+    staking.balance(&active.tokens)
+}
+
+#[test]
+fn compounding_does_at_least_as_well_as_restaking_by_hand() {
+    let compounded = run(Strategy::Compound);
+    let restaked = run(Strategy::ClaimAndRestake);
+    let claimed = run(Strategy::Claim);
+
+    assert!(compounded >= restaked, "{compounded} < {restaked}");
+    // Both grow the stake, so both beat leaving rewards idle
+    assert!(restaked > claimed);
+    // Idle rewards earn exactly half the period
+    assert_eq!(claimed, STAKE + REWARDS / 2);
+}
+
+#[test]
+fn compounding_adds_to_principal_and_keeps_the_lock() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 30 * DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    let lock_until = staking.position(&pool, &staker.wallet, 0).lock_until;
+
+    staking.harness.warp(DAY);
+    staking.compound(&pool, &staker, 0).unwrap();
+    let event = staking.harness.event::<RewardsCompounded>();
+    assert_eq!(event.amount, REWARDS / 100);
+    assert_eq!(event.new_principal, STAKE + REWARDS / 100);
+
+    let position = staking.position(&pool, &staker.wallet, 0);
+    assert_eq!(position.amount, STAKE + REWARDS / 100);
+    assert_eq!(position.lock_until, lock_until);
+    assert_eq!(staking.pool_state(&pool).total_staked, position.amount);
+    assert_eq!(staking.balance(&pool.stake_vault()), position.amount);
+    assert_eq!(staking.balance(&staker.tokens), 0);
+
+    // Nothing new in the same second
+    assert_error(staking.compound(&pool, &staker, 0), GamingTokenError::NothingToClaim);
+}
+
+#[test]
+fn rewards_in_another_mint_cannot_be_compounded() {
+    let mut staking = Staking::new();
+    let mint = staking.create_mint(TOKEN);
+    let reward_mint = staking.create_mint(TOKEN);
+    let pool = staking.create_pool(mint, reward_mint, PoolParams::default()).unwrap();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(DAY);
+
+    assert_error(
+        staking.compound(&pool, &staker, 0),
+        GamingTokenError::CompoundUnavailable,
+    );
+}
@@ -0,0 +1,270 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{
+    accounts, instruction, CompoundVault, GamingTokenError, VaultDeposited, VaultHarvested, VaultWithdrawn,
+    COMPOUND_VAULT_DEAD_SHARES,
+};
+
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+/// A depositor's wallet with a token account and a share account.
+struct Depositor {
+    wallet: Pubkey,
+    tokens: Pubkey,
+    shares: Pubkey,
+}
+
+struct Vault {
+    staking: Staking,
+    pool: Pool,
+}
+
+impl Vault {
+    fn new() -> Self {
+        let (staking, pool) = Staking::with_pool();
+        let mut vault = Self { staking, pool };
+        let ix = Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::CreateCompoundVault {
+                compound_vault: vault.address(),
+                share_mint: vault.share_mint(),
+                vault_position: vault.position(),
+                stake_pool: vault.pool.address(),
+                mint: vault.pool.mint,
+                authority: vault.staking.admin,
+                token_program: TOKEN,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::CreateCompoundVault {}.data(),
+        };
+        vault.staking.admin(ix).unwrap();
+        vault
+    }
+
+    fn address(&self) -> Pubkey {
+        pda(&[b"compound_vault", self.pool.mint.as_ref()])
+    }
+
+    fn share_mint(&self) -> Pubkey {
+        pda(&[b"compound_shares", self.pool.mint.as_ref()])
+    }
+
+    fn position(&self) -> Pubkey {
+        self.pool.stake_account(&self.address(), 0)
+    }
+
+    fn depositor(&mut self, amount: u64) -> Depositor {
+        let staker = self.staking.staker(&self.pool, amount);
+        let share_mint = self.share_mint();
+        let shares = self.staking.harness.create_token_account(&share_mint, &staker.wallet);
+        Depositor {
+            wallet: staker.wallet,
+            tokens: staker.tokens,
+            shares,
+        }
+    }
+
+    fn deposit(&mut self, depositor: &Depositor, amount: u64) -> TxResult {
+        let ix = Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::DepositToVault {
+                compound_vault: self.address(),
+                vault_position: self.position(),
+                stake_pool: self.pool.address(),
+                stake_vault: self.pool.stake_vault(),
+                reward_vault: self.pool.reward_vault(),
+                reward_vault_authority: self.pool.reward_vault_authority(),
+                fee_vault: None,
+                share_mint: self.share_mint(),
+                depositor_token_account: depositor.tokens,
+                depositor_share_account: depositor.shares,
+                depositor: depositor.wallet,
+                mint: self.pool.mint,
+                token_program: TOKEN,
+            }
+            .to_account_metas(None),
+            data: instruction::DepositToVault { amount }.data(),
+        };
+        self.staking.harness.process(&[ix], &[depositor.wallet])
+    }
+
+    fn withdraw(&mut self, depositor: &Depositor, shares: u64) -> TxResult {
+        let ix = Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::WithdrawFromVault {
+                compound_vault: self.address(),
+                vault_position: self.position(),
+                stake_pool: self.pool.address(),
+                stake_vault: self.pool.stake_vault(),
+                reward_vault: self.pool.reward_vault(),
+                reward_vault_authority: self.pool.reward_vault_authority(),
+                fee_vault: None,
+                vault_authority: self.pool.vault_authority(),
+                share_mint: self.share_mint(),
+                withdrawer_share_account: depositor.shares,
+                withdrawer_token_account: depositor.tokens,
+                withdrawer: depositor.wallet,
+                mint: self.pool.mint,
+                token_program: TOKEN,
+            }
+            .to_account_metas(None),
+            data: instruction::WithdrawFromVault { shares }.data(),
+        };
+        self.staking.harness.process(&[ix], &[depositor.wallet])
+    }
+
+    fn harvest(&mut self) -> TxResult {
+        let ix = Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::HarvestVault {
+                compound_vault: self.address(),
+                vault_position: self.position(),
+                stake_pool: self.pool.address(),
+                stake_vault: self.pool.stake_vault(),
+                reward_vault: self.pool.reward_vault(),
+                reward_vault_authority: self.pool.reward_vault_authority(),
+                fee_vault: None,
+                mint: self.pool.mint,
+                token_program: TOKEN,
+            }
+            .to_account_metas(None),
+            data: instruction::Harvest {}.data(),
+        };
+        // Anyone may crank it
+        let cranker = self.staking.harness.funded_wallet();
+        self.staking.harness.process(&[ix], &[cranker])
+    }
+
+    /// `(total_underlying, total_shares)`.
+    fn rate(&self) -> (u128, u128) {
+        let vault: CompoundVault = self.staking.harness.get(&self.address());
+        let position = self.staking.position(&self.pool, &self.address(), 0);
+        (position.amount as u128, vault.total_shares as u128)
+    }
+
+    fn shares(&self, depositor: &Depositor) -> u64 {
+        self.staking.balance(&depositor.shares)
+    }
+}
+
+/// Panics unless the share price `underlying / shares` didn't fall.
+fn assert_not_lower(before: (u128, u128), after: (u128, u128)) {
+    assert!(
+        after.0 * before.1 >= before.0 * after.1,
+        "share price fell from {before:?} to {after:?}"
+    );
+}
+
+#[test]
+fn the_first_deposit_locks_dead_shares() {
+    let mut vault = Vault::new();
+    let alice = vault.depositor(10_000);
+
+    assert_error(
+        vault.deposit(&alice, COMPOUND_VAULT_DEAD_SHARES),
+        GamingTokenError::SharesTooSmall,
+    );
+    vault.deposit(&alice, 10_000).unwrap();
+
+    let deposited = vault.staking.harness.event::<VaultDeposited>();
+    assert_eq!(deposited.shares, 10_000 - COMPOUND_VAULT_DEAD_SHARES);
+    assert_eq!(deposited.total_shares, 10_000);
+    assert_eq!(vault.shares(&alice), 10_000 - COMPOUND_VAULT_DEAD_SHARES);
+    assert_eq!(vault.rate(), (10_000, 10_000));
+}
+
+#[test]
+fn harvesting_compounds_rewards_into_the_share_price() {
+    let mut vault = Vault::new();
+    let alice = vault.depositor(1_000_000);
+    vault.deposit(&alice, 1_000_000).unwrap();
+    assert_error(vault.harvest(), GamingTokenError::NothingToClaim);
+
+    vault.staking.notify(&vault.pool, REWARDS).unwrap();
+    vault.staking.harness.warp(DAY);
+    vault.harvest().unwrap();
+
+    let harvested = vault.staking.harness.event::<VaultHarvested>();
+    assert_eq!(harvested.compounded, REWARDS / 100);
+    assert_eq!(harvested.total_underlying, 1_000_000 + REWARDS / 100);
+    assert_eq!(vault.staking.pool_state(&vault.pool).total_staked, 1_000_000 + REWARDS / 100);
+    // Shares didn't change; each is worth more
+    assert_eq!(vault.rate(), ((1_000_000 + REWARDS / 100) as u128, 1_000_000));
+}
+
+#[test]
+fn the_exchange_rate_never_decreases() {
+    let mut vault = Vault::new();
+    let alice = vault.depositor(3_333_333);
+    let bob = vault.depositor(7_777_777);
+    let carol = vault.depositor(1_001);
+
+    vault.deposit(&alice, 3_333_333).unwrap();
+    vault.staking.notify(&vault.pool, REWARDS + 7).unwrap();
+    let mut rate = vault.rate();
+
+    let mut check = |vault: &Vault| {
+        let next = vault.rate();
+        assert_not_lower(rate, next);
+        rate = next;
+    };
+
+    vault.staking.harness.warp(DAY / 3);
+    vault.harvest().unwrap();
+    check(&vault);
+    vault.deposit(&bob, 7_777_777).unwrap();
+    check(&vault);
+    vault.staking.harness.warp(DAY + 17);
+    vault.deposit(&carol, 1_001).unwrap();
+    check(&vault);
+    let bob_shares = vault.shares(&bob);
+    vault.withdraw(&bob, bob_shares / 3).unwrap();
+    check(&vault);
+    vault.staking.harness.warp(5 * DAY);
+    vault.harvest().unwrap();
+    check(&vault);
+    let carol_shares = vault.shares(&carol);
+    vault.withdraw(&carol, carol_shares).unwrap();
+    check(&vault);
+    let alice_shares = vault.shares(&alice);
+    vault.withdraw(&alice, alice_shares).unwrap();
+    check(&vault);
+    let bob_shares = vault.shares(&bob);
+    vault.withdraw(&bob, bob_shares).unwrap();
+    check(&vault);
+
+    // Only the dead shares remain, backed by what they accrued
+    let (underlying, shares) = vault.rate();
+    assert_eq!(shares, COMPOUND_VAULT_DEAD_SHARES as u128);
+    assert!(underlying >= COMPOUND_VAULT_DEAD_SHARES as u128);
+}
+
+#[test]
+fn withdrawing_pays_a_proportional_share_of_the_growth() {
+    let mut vault = Vault::new();
+    let alice = vault.depositor(1_000_000);
+    let bob = vault.depositor(1_000_000);
+    vault.deposit(&alice, 1_000_000).unwrap();
+    vault.staking.notify(&vault.pool, REWARDS).unwrap();
+    vault.staking.harness.warp(DAY);
+    vault.harvest().unwrap();
+
+    // Bob buys in at the higher price, so he gets fewer shares for the same amount
+    vault.deposit(&bob, 1_000_000).unwrap();
+    assert!(vault.shares(&bob) < vault.shares(&alice));
+
+    let (underlying, total_shares) = vault.rate();
+    let shares = vault.shares(&alice);
+    vault.withdraw(&alice, shares).unwrap();
+    let withdrawn = vault.staking.harness.event::<VaultWithdrawn>();
+    assert_eq!(withdrawn.amount as u128, underlying * shares as u128 / total_shares);
+    assert_eq!(vault.staking.balance(&alice.tokens), withdrawn.amount);
+    assert!(withdrawn.amount > 1_000_000);
+    assert_eq!(vault.shares(&alice), 0);
+}
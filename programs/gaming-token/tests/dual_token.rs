@@ -0,0 +1,78 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use gaming_token::{instruction, GamingTokenError, RewardsClaimed, TokensUnstaked};
+
+const STAKE: u64 = 1_000_000_000;
+/// 10,000 a second over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+/// A pool staking one mint and paying another, with a staker in it.
+fn dual_pool() -> (Staking, Pool, Staker) {
+    let mut staking = Staking::new();
+    let mint = staking.create_mint(TOKEN);
+    let reward_mint = staking.create_mint(TOKEN);
+    let pool = staking.create_pool(mint, reward_mint, PoolParams::default()).unwrap();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    (staking, pool, staker)
+}
+
+#[test]
+fn rewards_are_paid_in_the_reward_mint() {
+    let (mut staking, pool, staker) = dual_pool();
+    let state = staking.pool_state(&pool);
+    assert_eq!(state.reward_mint, pool.reward_mint);
+    assert_ne!(state.reward_mint, state.mint);
+
+    staking.notify(&pool, REWARDS).unwrap();
+    assert_eq!(staking.balance(&pool.reward_vault()), REWARDS);
+    assert_eq!(staking.harness.token_account(&pool.reward_vault()).mint, pool.reward_mint);
+
+    staking.harness.warp(DAY);
+    staking.claim(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().net, REWARDS / 100);
+    assert_eq!(staking.balance(&staker.rewards), REWARDS / 100);
+    assert_eq!(staking.balance(&staker.tokens), 0);
+
+    staking.harness.warp(DAY);
+    staking.unstake(&pool, &staker, 0).unwrap();
+    let unstaked = staking.harness.event::<TokensUnstaked>();
+    assert_eq!(unstaked.principal, STAKE);
+    assert_eq!(unstaked.reward, REWARDS / 100);
+    assert_eq!(staking.balance(&staker.tokens), STAKE);
+    assert_eq!(staking.balance(&staker.rewards), REWARDS / 50);
+    assert_eq!(staking.balance(&pool.stake_vault()), 0);
+}
+
+#[test]
+fn the_staked_mint_cannot_fund_or_receive_rewards() {
+    let (mut staking, pool, staker) = dual_pool();
+
+    // Funding with the staked mint
+    let mut ix = staking.notify_ix(&pool, REWARDS);
+    swap_account(&mut ix, staking.admin_tokens(&pool.reward_mint), staking.admin_tokens(&pool.mint));
+    assert_error(staking.admin(ix), ErrorCode::ConstraintTokenMint);
+
+    // Claiming into the staked-mint account
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(DAY);
+    let mut ix = staking.claim_ix(&pool, &staker, 0);
+    swap_account(&mut ix, staker.rewards, staker.tokens);
+    assert_error(staking.harness.process(&[ix], &[staker.wallet]), ErrorCode::ConstraintTokenMint);
+}
+
+#[test]
+fn penalties_need_a_single_token_pool() {
+    let (mut staking, pool, _) = dual_pool();
+    assert_error(
+        staking.update_pool(
+            &pool,
+            instruction::UpdateEarlyUnstakePenalty {
+                early_unstake_penalty_bps: 1_000,
+            },
+        ),
+        GamingTokenError::InvalidPenalty,
+    );
+}
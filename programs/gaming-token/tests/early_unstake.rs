@@ -0,0 +1,88 @@
+mod common;
+
+use common::*;
+use gaming_token::{instruction, EarlyUnstaked, GamingTokenError, TokensUnstaked};
+
+const STAKE: u64 = 1_000_000_000;
+const LOCK: i64 = 10 * DAY;
+
+/// A pool charging 20% on the full lock, with one staker locked for `LOCK`.
+fn locked() -> (Staking, Pool, Staker) {
+    let (mut staking, pool) = Staking::with_pool();
+    staking
+        .update_pool(
+            &pool,
+            instruction::UpdateEarlyUnstakePenalty {
+                early_unstake_penalty_bps: 2_000,
+            },
+        )
+        .unwrap();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, LOCK).unwrap();
+    (staking, pool, staker)
+}
+
+#[test]
+fn the_penalty_shrinks_with_the_time_left() {
+    for (elapsed, penalty) in [
+        (0, STAKE / 5),
+        (LOCK / 2, STAKE / 10),
+        // 1e9 * 20% / 864,000 seconds, rounded down
+        (LOCK - 1, 231),
+    ] {
+        let (mut staking, pool, staker) = locked();
+        staking.harness.warp(elapsed);
+        staking.early_unstake(&pool, &staker, 0).unwrap();
+
+        let event = staking.harness.event::<EarlyUnstaked>();
+        assert_eq!(event.principal, STAKE);
+        assert_eq!(event.penalty, penalty, "after {elapsed}s");
+        assert_eq!(event.refund, STAKE - penalty);
+        assert_eq!(staking.balance(&staker.tokens), STAKE - penalty);
+        assert_eq!(staking.balance(&pool.reward_vault()), penalty);
+        assert!(staking.harness.account(&pool.stake_account(&staker.wallet, 0)).is_none());
+    }
+}
+
+#[test]
+fn the_lock_ends_exactly_at_lock_until() {
+    let (mut staking, pool, staker) = locked();
+    let lock_until = staking.position(&pool, &staker.wallet, 0).lock_until;
+
+    staking.harness.set_time(lock_until - 1);
+    assert_error(
+        staking.unstake(&pool, &staker, 0),
+        GamingTokenError::TokensStillLocked,
+    );
+
+    staking.harness.set_time(lock_until);
+    assert_error(staking.early_unstake(&pool, &staker, 0), GamingTokenError::LockExpired);
+    staking.unstake(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.harness.event::<TokensUnstaked>().principal, STAKE);
+    assert_eq!(staking.balance(&staker.tokens), STAKE);
+}
+
+#[test]
+fn without_a_penalty_leaving_early_is_free() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, LOCK).unwrap();
+
+    staking.early_unstake(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.harness.event::<EarlyUnstaked>().penalty, 0);
+    assert_eq!(staking.balance(&staker.tokens), STAKE);
+}
+
+#[test]
+fn the_penalty_is_bounded() {
+    let (mut staking, pool) = Staking::with_pool();
+    assert_error(
+        staking.update_pool(
+            &pool,
+            instruction::UpdateEarlyUnstakePenalty {
+                early_unstake_penalty_bps: 10_001,
+            },
+        ),
+        GamingTokenError::InvalidPenalty,
+    );
+}
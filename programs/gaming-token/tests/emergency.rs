@@ -0,0 +1,219 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{
+    accounts, instruction, EmergencyVaultWithdrawn, GamingTokenError, RewardDebtClaimed, RewardDebtRecorded, TokensUnstaked,
+};
+
+const STAKE: u64 = 1_000_000_000;
+/// 10,000 a second over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+/// A funded pool, a day into its period, with one staker locked for 30 days.
+fn running() -> (Staking, Pool, Staker) {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 30 * DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(DAY);
+    (staking, pool, staker)
+}
+
+fn set_emergency_destination_ix(staking: &Staking, pool: &Pool, destination: Pubkey) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::SetEmergencyDestination {
+            stake_pool: pool.address(),
+            destination,
+            authority: staking.admin,
+        }
+        .to_account_metas(None),
+        data: instruction::SetEmergencyDestination {}.data(),
+    }
+}
+
+fn emergency_withdraw_ix(staking: &Staking, pool: &Pool, destination: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::EmergencyWithdrawVault {
+            stake_pool: pool.address(),
+            reward_vault: pool.reward_vault(),
+            reward_vault_authority: pool.reward_vault_authority(),
+            destination,
+            authority: staking.admin,
+            reward_mint: pool.reward_mint,
+            token_program: pool.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::EmergencyWithdrawVault { amount }.data(),
+    }
+}
+
+fn claim_reward_debt(staking: &mut Staking, pool: &Pool, staker: &Staker) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::ClaimRewardDebt {
+            stake_positions: pool.stake_positions(&staker.wallet),
+            stake_pool: pool.address(),
+            reward_vault: pool.reward_vault(),
+            reward_vault_authority: pool.reward_vault_authority(),
+            fee_vault: None,
+            user_reward_token_account: staker.rewards,
+            owner: staker.wallet,
+            mint: pool.mint,
+            reward_mint: pool.reward_mint,
+            token_program: pool.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::ClaimRewardDebt {}.data(),
+    };
+    staking.harness.process(&[ix], &[staker.wallet])
+}
+
+#[test]
+fn a_paused_pool_still_lets_principal_out() {
+    let (mut staking, pool, staker) = running();
+    staking
+        .update_pool(&pool, instruction::SetPoolPaused { paused: true })
+        .unwrap();
+
+    let newcomer = staking.staker(&pool, STAKE);
+    assert_error(
+        staking.stake(&pool, &newcomer, 0, STAKE, DAY),
+        GamingTokenError::PoolPaused,
+    );
+    assert_error(
+        staking.add_to_stake(&pool, &staker, 0, 1),
+        GamingTokenError::PoolPaused,
+    );
+
+    // Claims and exits stay open; the lock still applies
+    staking.claim(&pool, &staker, 0).unwrap();
+    assert_error(staking.unstake(&pool, &staker, 0), GamingTokenError::TokensStillLocked);
+    staking.harness.warp(29 * DAY);
+    staking.partial_unstake(&pool, &staker, 0, STAKE / 2).unwrap();
+    staking.unstake(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.balance(&pool.stake_vault()), 0);
+    assert_eq!(staking.balance(&staker.tokens), STAKE + REWARDS * 30 / 100);
+}
+
+#[test]
+fn emergency_mode_waives_the_lock_and_pays_no_rewards() {
+    let (mut staking, pool, staker) = running();
+    staking
+        .update_pool(&pool, instruction::SetEmergencyMode { emergency_mode: true })
+        .unwrap();
+    assert!(staking.pool_state(&pool).paused);
+
+    assert_error(staking.claim(&pool, &staker, 0), GamingTokenError::EmergencyModeActive);
+    assert_error(
+        staking.partial_unstake(&pool, &staker, 0, STAKE / 2),
+        GamingTokenError::EmergencyModeActive,
+    );
+    assert_error(
+        staking.early_unstake(&pool, &staker, 0),
+        GamingTokenError::EmergencyModeActive,
+    );
+    staking.unstake(&pool, &staker, 0).unwrap();
+    let unstaked = staking.harness.event::<TokensUnstaked>();
+    assert_eq!(unstaked.principal, STAKE);
+    assert_eq!(unstaked.reward, 0);
+    assert_eq!(staking.balance(&staker.tokens), STAKE);
+
+    // Leaving emergency mode keeps the pool paused
+    staking
+        .update_pool(&pool, instruction::SetEmergencyMode { emergency_mode: false })
+        .unwrap();
+    assert!(staking.pool_state(&pool).paused);
+}
+
+#[test]
+fn a_rewards_freeze_releases_principal_and_keeps_rewards_as_debt() {
+    let (mut staking, pool, staker) = running();
+    staking
+        .update_pool(&pool, instruction::SetEmergencyRewardsFreeze { frozen: true })
+        .unwrap();
+
+    assert_error(staking.claim(&pool, &staker, 0), GamingTokenError::RewardsFrozen);
+    staking.harness.warp(DAY);
+    staking.unstake(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.harness.event::<TokensUnstaked>().reward, 0);
+    assert_eq!(staking.balance(&staker.tokens), STAKE);
+    // Only the day before the freeze counts
+    assert_eq!(staking.positions(&pool, &staker.wallet).reward_debt, REWARDS / 100);
+}
+
+#[test]
+fn emergency_withdrawal_only_drains_the_reward_vault() {
+    let (mut staking, pool, staker) = running();
+    let admin = staking.admin;
+    let destination = staking.harness.create_token_account(&pool.reward_mint, &admin);
+
+    let ix = emergency_withdraw_ix(&staking, &pool, destination, REWARDS);
+    assert_error(staking.admin(ix), GamingTokenError::EmergencyModeInactive);
+
+    let ix = set_emergency_destination_ix(&staking, &pool, destination);
+    staking.admin(ix).unwrap();
+    staking
+        .update_pool(&pool, instruction::SetEmergencyMode { emergency_mode: true })
+        .unwrap();
+
+    // The destination is fixed for the emergency
+    let ix = set_emergency_destination_ix(&staking, &pool, staker.tokens);
+    assert_error(staking.admin(ix), GamingTokenError::EmergencyModeActive);
+    let ix = emergency_withdraw_ix(&staking, &pool, staker.tokens, REWARDS);
+    assert_error(staking.admin(ix), GamingTokenError::InvalidEmergencyDestination);
+
+    let ix = emergency_withdraw_ix(&staking, &pool, destination, REWARDS + 1);
+    assert_error(staking.admin(ix), GamingTokenError::InsufficientFunds);
+    let ix = emergency_withdraw_ix(&staking, &pool, destination, REWARDS);
+    staking.admin(ix).unwrap();
+    let event = staking.harness.event::<EmergencyVaultWithdrawn>();
+    assert_eq!(event.amount, REWARDS);
+    assert_eq!(event.vault_balance, 0);
+    assert_eq!(event.total_staked, STAKE);
+    assert_eq!(staking.balance(&destination), REWARDS);
+    assert_eq!(staking.balance(&pool.stake_vault()), STAKE);
+
+    staking.unstake(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.balance(&staker.tokens), STAKE);
+}
+
+#[test]
+fn reward_debt_is_paid_once_the_freeze_lifts() {
+    let (mut staking, pool, staker) = running();
+    staking
+        .update_pool(&pool, instruction::SetEmergencyRewardsFreeze { frozen: true })
+        .unwrap();
+    staking.unstake(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardDebtRecorded>().amount, REWARDS / 100);
+    assert_eq!(staking.positions(&pool, &staker.wallet).reward_debt, REWARDS / 100);
+    assert_error(
+        claim_reward_debt(&mut staking, &pool, &staker),
+        GamingTokenError::RewardsFrozen,
+    );
+
+    // The debt is still owed out of the reserve until it is claimed
+    let before = staking.pool_state(&pool);
+    staking
+        .update_pool(&pool, instruction::SetEmergencyRewardsFreeze { frozen: false })
+        .unwrap();
+    claim_reward_debt(&mut staking, &pool, &staker).unwrap();
+    let claimed = staking.harness.event::<RewardDebtClaimed>();
+    assert_eq!((claimed.paid, claimed.remaining_debt), (REWARDS / 100, 0));
+    assert_eq!(staking.balance(&staker.tokens), STAKE + REWARDS / 100);
+    assert_eq!(staking.positions(&pool, &staker.wallet).reward_debt, 0);
+    let after = staking.pool_state(&pool);
+    assert_eq!(after.rewards_owed, before.rewards_owed - REWARDS / 100);
+    assert_eq!(after.reward_reserve, before.reward_reserve - REWARDS / 100);
+    assert_eq!(after.total_rewards_paid, before.total_rewards_paid + REWARDS / 100);
+    assert_eq!(staking.balance(&pool.reward_vault()), REWARDS - REWARDS / 100);
+
+    assert_error(
+        claim_reward_debt(&mut staking, &pool, &staker),
+        GamingTokenError::NothingToClaim,
+    );
+}
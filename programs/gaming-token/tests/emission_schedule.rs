@@ -0,0 +1,108 @@
+mod common;
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{
+    accounts, instruction, EmissionScheduleArgs, EpochRolled, GamingTokenError, PoolUpdate, RewardsClaimed,
+};
+
+const STAKE: u64 = 1_000_000_000;
+/// 16,000 a second over a one-day epoch, halving every epoch.
+const EMISSION: u64 = 16_000 * DAY as u64;
+
+/// A staked pool that switched to daily halving epochs; returns the first
+/// epoch's start.
+fn scheduled() -> (Staking, Pool, Staker, i64) {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::FundRewards {
+            stake_pool: pool.address(),
+            reward_vault: pool.reward_vault(),
+            funder_token_account: staking.admin_tokens(&pool.reward_mint),
+            funder: staking.admin,
+            reward_mint: pool.reward_mint,
+            token_program: pool.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::FundRewards { amount: 4 * EMISSION }.data(),
+    };
+    staking.admin(ix).unwrap();
+
+    let update = PoolUpdate {
+        reward_duration: None,
+        emission_schedule: Some(EmissionScheduleArgs {
+            epoch_length: DAY,
+            initial_emission: EMISSION,
+            halving_interval: 1,
+        }),
+        claim_fee_bps: None,
+    };
+    let effective_at = staking.harness.now() + 2 * DAY;
+    let ix = staking.queue_pool_update_ix(&pool, update, effective_at);
+    staking.admin(ix).unwrap();
+    staking.harness.set_time(effective_at);
+    let ix = staking.execute_pool_update_ix(&pool);
+    staking.admin(ix).unwrap();
+    (staking, pool, staker, effective_at)
+}
+
+fn roll_epoch(staking: &mut Staking, pool: &Pool) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::RollEpoch {
+            stake_pool: pool.address(),
+        }
+        .to_account_metas(None),
+        data: instruction::RollEpoch {}.data(),
+    };
+    let caller = staking.harness.funded_wallet();
+    staking.harness.process(&[ix], &[caller])
+}
+
+fn claimed(staking: &mut Staking, pool: &Pool, staker: &Staker) -> u64 {
+    staking.claim(pool, staker, 0).unwrap();
+    staking.harness.event::<RewardsClaimed>().net
+}
+
+#[test]
+fn a_late_crank_accrues_what_timely_cranks_would_have() {
+    // Four full epochs at 16k, 8k, 4k and 2k a second, then half of one at 1k
+    let expected = DAY as u64 * (16_000 + 8_000 + 4_000 + 2_000) + DAY as u64 / 2 * 1_000;
+
+    let (mut timely, pool, staker, start) = scheduled();
+    assert_error(roll_epoch(&mut timely, &pool), GamingTokenError::EpochNotEnded);
+    for epoch in 1..=4 {
+        timely.harness.set_time(start + epoch * DAY);
+        roll_epoch(&mut timely, &pool).unwrap();
+        assert_error(roll_epoch(&mut timely, &pool), GamingTokenError::EpochNotEnded);
+    }
+    timely.harness.set_time(start + 4 * DAY + DAY / 2);
+    assert_eq!(claimed(&mut timely, &pool, &staker), expected);
+    let timely_accumulator = timely.pool_state(&pool).reward_per_token_stored;
+
+    let (mut late, pool, staker, start) = scheduled();
+    late.harness.set_time(start + 4 * DAY + DAY / 2);
+    roll_epoch(&mut late, &pool).unwrap();
+    let rolled: Vec<_> = late
+        .harness
+        .events::<EpochRolled>()
+        .iter()
+        .map(|rolled| (rolled.epoch, rolled.reward_rate, rolled.epoch_start))
+        .collect();
+    assert_eq!(
+        rolled,
+        [
+            (1, 8_000, start + DAY),
+            (2, 4_000, start + 2 * DAY),
+            (3, 2_000, start + 3 * DAY),
+            (4, 1_000, start + 4 * DAY),
+        ]
+    );
+    assert_eq!(claimed(&mut late, &pool, &staker), expected);
+    assert_eq!(late.pool_state(&pool).reward_per_token_stored, timely_accumulator);
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::*;
+use gaming_token::{RewardsClaimed, TokensStaked, TokensUnstaked};
+
+const STAKE: u64 = 1_000_000;
+const REWARDS: u64 = 86_400_000_000;
+/// Past the first position, so the index isn't the default.
+const INDEX: u16 = 1;
+
+#[test]
+fn staking_events_name_their_pool_and_position() {
+    let (mut staking, first) = Staking::with_pool();
+    let second = staking.single_token_pool(TOKEN, PoolParams::default());
+
+    for pool in [&first, &second] {
+        let staker = staking.staker(pool, 2 * STAKE);
+        let stake_account = pool.stake_account(&staker.wallet, INDEX);
+
+        staking.stake(pool, &staker, 0, STAKE, 1).unwrap();
+        staking.stake(pool, &staker, INDEX, STAKE, 1).unwrap();
+        let staked = staking.harness.event::<TokensStaked>();
+        assert_eq!(staked.mint, pool.mint);
+        assert_eq!(staked.pool, pool.address());
+        assert_eq!(staked.stake_account, stake_account);
+        assert_eq!(staked.position_index, INDEX);
+
+        staking.notify(pool, REWARDS).unwrap();
+        staking.harness.warp(DAY);
+        staking.claim(pool, &staker, INDEX).unwrap();
+        let claimed = staking.harness.event::<RewardsClaimed>();
+        assert_eq!(claimed.mint, pool.mint);
+        assert_eq!(claimed.pool, pool.address());
+        assert_eq!(claimed.stake_account, stake_account);
+        assert_eq!(claimed.position_index, INDEX);
+    }
+}
+
+#[test]
+fn unstake_events_report_what_is_left() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, 2 * STAKE);
+    let stake_account = pool.stake_account(&staker.wallet, INDEX);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    staking.stake(&pool, &staker, INDEX, STAKE, 1).unwrap();
+    staking.harness.warp(1);
+
+    staking.partial_unstake(&pool, &staker, INDEX, STAKE / 4).unwrap();
+    let partial = staking.harness.event::<TokensUnstaked>();
+    assert_eq!(partial.mint, pool.mint);
+    assert_eq!(partial.pool, pool.address());
+    assert_eq!(partial.stake_account, stake_account);
+    assert_eq!(partial.position_index, INDEX);
+    assert_eq!(partial.principal, STAKE / 4);
+    assert_eq!(partial.remaining, STAKE - STAKE / 4);
+
+    staking.unstake(&pool, &staker, INDEX).unwrap();
+    let full = staking.harness.event::<TokensUnstaked>();
+    assert_eq!(full.stake_account, stake_account);
+    assert_eq!(full.principal, STAKE - STAKE / 4);
+    assert_eq!(full.remaining, 0);
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{Pubkey, Rent};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{accounts, instruction, GameRewardMinted, GameServerRecord, GamingTokenError, PLAYER_STATS_SEED};
+use program_harness::AccountState;
+
+const BUDGET: u64 = 1_000;
+const RECIPIENT_CAP: u64 = 600;
+
+fn game_server(minting: &Minting, server: &Pubkey) -> Pubkey {
+    pda(&[b"game_server", minting.mint.as_ref(), server.as_ref()])
+}
+
+fn recipient_record(minting: &Minting, server: &Pubkey, player: &Pubkey) -> Pubkey {
+    pda(&[b"game_reward_recipient", game_server(minting, server).as_ref(), player.as_ref()])
+}
+
+fn add_game_server_ix(minting: &Minting, server: Pubkey, per_recipient_cap: u64, player_registry: Pubkey) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::AddGameServer {
+            mint_config: minting.config(),
+            game_server: game_server(minting, &server),
+            authority: minting.authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::AddGameServer {
+            server,
+            daily_budget: BUDGET,
+            per_recipient_cap,
+            player_registry,
+        }
+        .data(),
+    }
+}
+
+/// A minter with plenty of allowance, put under a `BUDGET` a day policy.
+fn server(minting: &mut Minting, player_registry: Pubkey) -> Pubkey {
+    let server = minting.minter(u64::MAX, u64::MAX, DAY);
+    let ix = add_game_server_ix(minting, server, RECIPIENT_CAP, player_registry);
+    minting.process(ix).unwrap();
+    server
+}
+
+/// A fresh player wallet and its token account.
+fn player(minting: &mut Minting) -> (Pubkey, Pubkey) {
+    let wallet = minting.harness.funded_wallet();
+    let mint = minting.mint;
+    (wallet, minting.harness.create_token_account(&mint, &wallet))
+}
+
+fn reward(
+    minting: &mut Minting,
+    server: Pubkey,
+    (player, recipient): (Pubkey, Pubkey),
+    player_record: Option<Pubkey>,
+    amount: u64,
+) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::MintGameReward {
+            mint_config: minting.config(),
+            minter_record: minting.minter_record(&server),
+            game_server: game_server(minting, &server),
+            recipient_record: recipient_record(minting, &server, &player),
+            mint: minting.mint,
+            mint_authority: minting.mint_authority(),
+            recipient,
+            player_record,
+            server,
+            token_program: minting.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::MintGameReward {
+            amount,
+            reason_hash: [3; 32],
+        }
+        .data(),
+    };
+    minting.harness.process(&[ix], &[server])
+}
+
+#[test]
+fn the_daily_budget_and_each_players_cap_roll_over_together() {
+    let mut minting = Minting::new(0);
+    let server = server(&mut minting, Pubkey::default());
+    let (alice, bob) = (player(&mut minting), player(&mut minting));
+    let start = minting.harness.now();
+
+    reward(&mut minting, server, alice, None, RECIPIENT_CAP).unwrap();
+    let minted = minting.harness.event::<GameRewardMinted>();
+    assert_eq!((minted.player, minted.reason_hash), (alice.0, [3; 32]));
+    assert_error(
+        reward(&mut minting, server, alice, None, 1),
+        GamingTokenError::RecipientCapExceeded,
+    );
+    reward(&mut minting, server, bob, None, BUDGET - RECIPIENT_CAP).unwrap();
+    assert_error(
+        reward(&mut minting, server, bob, None, 1),
+        GamingTokenError::GameServerBudgetExceeded,
+    );
+
+    // A day later both the budget and the player's cap are fresh
+    minting.harness.set_time(start + DAY);
+    reward(&mut minting, server, alice, None, RECIPIENT_CAP).unwrap();
+    let record: GameServerRecord = minting.harness.get(&game_server(&minting, &server));
+    assert_eq!(record.window_start, start + DAY);
+    assert_eq!(record.minted_in_window, RECIPIENT_CAP);
+    assert_eq!(record.minted, BUDGET + RECIPIENT_CAP);
+    assert_eq!(minting.balance(&alice.1), 2 * RECIPIENT_CAP);
+    assert_eq!(minting.config_state().total_supply, BUDGET + RECIPIENT_CAP);
+}
+
+#[test]
+fn a_registry_limits_rewards_to_its_players() {
+    let mut minting = Minting::new(0);
+    let registry = Pubkey::new_unique();
+    let server = server(&mut minting, registry);
+    let alice = player(&mut minting);
+    let record = Pubkey::find_program_address(&[PLAYER_STATS_SEED, alice.0.as_ref()], &registry).0;
+
+    assert_error(
+        reward(&mut minting, server, alice, None, 1),
+        GamingTokenError::RecipientNotRegistered,
+    );
+    // The address is right but the registry never created it
+    assert_error(
+        reward(&mut minting, server, alice, Some(record), 1),
+        GamingTokenError::RecipientNotRegistered,
+    );
+
+    minting.harness.set_account(
+        record,
+        AccountState {
+            lamports: Rent::default().minimum_balance(8),
+            data: vec![1; 8],
+            owner: registry,
+            executable: false,
+        },
+    );
+    reward(&mut minting, server, alice, Some(record), 1).unwrap();
+
+    // Another player's record doesn't stand in
+    let bob = player(&mut minting);
+    assert_error(
+        reward(&mut minting, server, bob, Some(record), 1),
+        GamingTokenError::RecipientNotRegistered,
+    );
+}
+
+#[test]
+fn only_a_valid_policy_is_accepted_and_removal_stops_rewards() {
+    let mut minting = Minting::new(0);
+    let minter = minting.minter(u64::MAX, u64::MAX, DAY);
+    for cap in [0, BUDGET + 1] {
+        let ix = add_game_server_ix(&minting, minter, cap, Pubkey::default());
+        assert_error(minting.process(ix), GamingTokenError::InvalidGameServerPolicy);
+    }
+
+    let server = server(&mut minting, Pubkey::default());
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::RemoveGameServer {
+            mint_config: minting.config(),
+            game_server: game_server(&minting, &server),
+            authority: minting.authority,
+        }
+        .to_account_metas(None),
+        data: instruction::RemoveGameServer {}.data(),
+    };
+    minting.process(ix).unwrap();
+
+    // Still a minter, but no longer a game server
+    let alice = player(&mut minting);
+    assert_error(
+        reward(&mut minting, server, alice, None, 1),
+        ErrorCode::AccountNotInitialized,
+    );
+}
@@ -0,0 +1,249 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{
+    accounts, instruction, DelegatedVoteCast, GamingTokenError, Proposal, ProposalFinalized, VoteCast,
+};
+
+const STAKE: u64 = 1_000_000_000;
+const PROPOSAL_ID: u64 = 1;
+const VOTING_PERIOD: i64 = 3 * DAY;
+
+fn proposal(pool: &Pool) -> Pubkey {
+    pda(&[b"proposal", pool.address().as_ref(), &PROPOSAL_ID.to_le_bytes()])
+}
+
+fn voting_power_record(pool: &Pool, stake_account: &Pubkey) -> Pubkey {
+    pda(&[b"voting_power", proposal(pool).as_ref(), stake_account.as_ref()])
+}
+
+fn delegated_power(pool: &Pool, delegate: &Pubkey) -> Pubkey {
+    pda(&[b"delegated_power", proposal(pool).as_ref(), delegate.as_ref()])
+}
+
+fn create_proposal(staking: &mut Staking, pool: &Pool) {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::CreateProposal {
+            proposal: proposal(pool),
+            stake_pool: pool.address(),
+            authority: staking.admin,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::CreateProposal {
+            proposal_id: PROPOSAL_ID,
+            description: "Raise the lock bonus".to_string(),
+            voting_period: VOTING_PERIOD,
+        }
+        .data(),
+    };
+    staking.admin(ix).unwrap();
+}
+
+fn snapshot(staking: &mut Staking, pool: &Pool, staker: &Staker, index: u16) -> TxResult {
+    let stake_account = pool.stake_account(&staker.wallet, index);
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::SnapshotVotingPower {
+            proposal: proposal(pool),
+            stake_pool: pool.address(),
+            stake_account,
+            voting_power_record: voting_power_record(pool, &stake_account),
+            payer: staker.wallet,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::SnapshotVotingPower {}.data(),
+    };
+    staking.harness.process(&[ix], &[staker.wallet])
+}
+
+fn snapshot_delegated(staking: &mut Staking, pool: &Pool, staker: &Staker, index: u16, delegate: &Pubkey) -> TxResult {
+    let stake_account = pool.stake_account(&staker.wallet, index);
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::SnapshotDelegatedPower {
+            proposal: proposal(pool),
+            stake_pool: pool.address(),
+            stake_account,
+            voting_power_record: voting_power_record(pool, &stake_account),
+            delegated_power: delegated_power(pool, delegate),
+            delegate: *delegate,
+            payer: staker.wallet,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::SnapshotDelegatedPower {}.data(),
+    };
+    staking.harness.process(&[ix], &[staker.wallet])
+}
+
+fn vote(staking: &mut Staking, pool: &Pool, staker: &Staker, index: u16, support: bool) -> TxResult {
+    let stake_account = pool.stake_account(&staker.wallet, index);
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::CastVote {
+            proposal: proposal(pool),
+            voting_power_record: voting_power_record(pool, &stake_account),
+            stake_account,
+            receipt_token_account: None,
+            owner: staker.wallet,
+        }
+        .to_account_metas(None),
+        data: instruction::CastVote { support }.data(),
+    };
+    staking.harness.process(&[ix], &[staker.wallet])
+}
+
+fn delegated_vote(staking: &mut Staking, pool: &Pool, delegate: Pubkey, support: bool) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::CastDelegatedVote {
+            proposal: proposal(pool),
+            delegated_power: delegated_power(pool, &delegate),
+            delegate,
+        }
+        .to_account_metas(None),
+        data: instruction::CastDelegatedVote { support }.data(),
+    };
+    staking.harness.process(&[ix], &[delegate])
+}
+
+fn finalize(staking: &mut Staking, pool: &Pool) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::FinalizeProposal { proposal: proposal(pool) }.to_account_metas(None),
+        data: instruction::FinalizeProposal {}.data(),
+    };
+    let caller = staking.harness.funded_wallet();
+    staking.harness.process(&[ix], &[caller])
+}
+
+fn tally(staking: &Staking, pool: &Pool) -> (u64, u64) {
+    let proposal: Proposal = staking.harness.get(&proposal(pool));
+    (proposal.yes_power, proposal.no_power)
+}
+
+#[test]
+fn votes_are_weighted_by_stake_and_counted_once() {
+    let (mut staking, pool) = Staking::with_pool();
+    let alice = staking.staker(&pool, 2 * STAKE);
+    let bob = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &alice, 0, 2 * STAKE, DAY).unwrap();
+    staking.stake(&pool, &bob, 0, STAKE, DAY).unwrap();
+    create_proposal(&mut staking, &pool);
+
+    snapshot(&mut staking, &pool, &alice, 0).unwrap();
+    snapshot(&mut staking, &pool, &bob, 0).unwrap();
+    assert!(snapshot(&mut staking, &pool, &bob, 0).is_err());
+    vote(&mut staking, &pool, &alice, 0, true).unwrap();
+    assert_eq!(staking.harness.event::<VoteCast>().power, 2 * STAKE);
+    vote(&mut staking, &pool, &bob, 0, false).unwrap();
+    assert_error(vote(&mut staking, &pool, &bob, 0, true), GamingTokenError::AlreadyVoted);
+    assert_eq!(tally(&staking, &pool), (2 * STAKE, STAKE));
+
+    assert_error(finalize(&mut staking, &pool), GamingTokenError::VotingOpen);
+    staking.harness.warp(VOTING_PERIOD);
+    assert_error(vote(&mut staking, &pool, &alice, 0, true), GamingTokenError::VotingClosed);
+    finalize(&mut staking, &pool).unwrap();
+    let finalized = staking.harness.event::<ProposalFinalized>();
+    assert!(finalized.passed);
+    assert_eq!((finalized.yes_power, finalized.no_power), (2 * STAKE, STAKE));
+    assert_error(finalize(&mut staking, &pool), GamingTokenError::ProposalFinalized);
+}
+
+#[test]
+fn positions_grown_after_the_snapshot_cannot_vote() {
+    let (mut staking, pool) = Staking::with_pool();
+    let topped_up = staking.staker(&pool, 2 * STAKE);
+    let relocked = staking.staker(&pool, STAKE);
+    let late = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &topped_up, 0, STAKE, DAY).unwrap();
+    staking.stake(&pool, &relocked, 0, STAKE, DAY).unwrap();
+    create_proposal(&mut staking, &pool);
+
+    staking.harness.warp(1);
+    staking.add_to_stake(&pool, &topped_up, 0, STAKE).unwrap();
+    let new_lock_until = staking.harness.now() + 10 * DAY;
+    staking
+        .update_position(&pool, &relocked, 0, instruction::ExtendLock { new_lock_until })
+        .unwrap();
+    staking.stake(&pool, &late, 0, STAKE, DAY).unwrap();
+
+    for staker in [&topped_up, &relocked, &late] {
+        assert_error(
+            snapshot(&mut staking, &pool, staker, 0),
+            GamingTokenError::NotEligibleToVote,
+        );
+    }
+}
+
+#[test]
+fn tokens_that_already_voted_cannot_vote_again_from_another_position() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, 2 * STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    staking.stake(&pool, &staker, 1, STAKE, 1).unwrap();
+    staking.harness.warp(1);
+    create_proposal(&mut staking, &pool);
+
+    snapshot(&mut staking, &pool, &staker, 0).unwrap();
+    vote(&mut staking, &pool, &staker, 0, true).unwrap();
+
+    // Out of the voted position and into the one that hasn't voted yet
+    staking.harness.warp(1);
+    staking.unstake(&pool, &staker, 0).unwrap();
+    staking.add_to_stake(&pool, &staker, 1, STAKE).unwrap();
+    assert_error(
+        snapshot(&mut staking, &pool, &staker, 1),
+        GamingTokenError::NotEligibleToVote,
+    );
+    assert_eq!(tally(&staking, &pool), (STAKE, 0));
+}
+
+#[test]
+fn delegates_vote_with_the_power_delegated_at_the_snapshot() {
+    let (mut staking, pool) = Staking::with_pool();
+    let alice = staking.staker(&pool, STAKE);
+    let bob = staking.staker(&pool, 2 * STAKE);
+    let delegate = staking.harness.funded_wallet();
+    staking.stake(&pool, &alice, 0, STAKE, DAY).unwrap();
+    staking.stake(&pool, &bob, 0, 2 * STAKE, DAY).unwrap();
+    for staker in [&alice, &bob] {
+        staking
+            .update_position(&pool, staker, 0, instruction::DelegateVotes { delegate })
+            .unwrap();
+    }
+    create_proposal(&mut staking, &pool);
+
+    // Revoking after the snapshot doesn't take the power back for this proposal
+    staking.harness.warp(1);
+    staking
+        .update_position(&pool, &alice, 0, instruction::RevokeDelegation {})
+        .unwrap();
+    assert_error(
+        snapshot(&mut staking, &pool, &alice, 0),
+        GamingTokenError::PositionDelegated,
+    );
+    let stranger = staking.harness.funded_wallet();
+    assert_error(
+        snapshot_delegated(&mut staking, &pool, &alice, 0, &stranger),
+        GamingTokenError::PositionNotDelegated,
+    );
+    snapshot_delegated(&mut staking, &pool, &alice, 0, &delegate).unwrap();
+    delegated_vote(&mut staking, &pool, delegate, false).unwrap();
+    let cast = staking.harness.event::<DelegatedVoteCast>();
+    assert_eq!((cast.power, cast.positions), (STAKE, 1));
+    assert_error(
+        delegated_vote(&mut staking, &pool, delegate, true),
+        GamingTokenError::AlreadyVoted,
+    );
+
+    // Positions counted after the delegate voted follow that vote
+    snapshot_delegated(&mut staking, &pool, &bob, 0, &delegate).unwrap();
+    assert_eq!(tally(&staking, &pool), (0, 3 * STAKE));
+}
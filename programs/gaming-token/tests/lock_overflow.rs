@@ -0,0 +1,70 @@
+mod common;
+
+use common::*;
+use gaming_token::{instruction, GamingTokenError};
+
+const STAKE: u64 = 1_000_000;
+
+#[test]
+fn an_i64_max_lock_is_rejected_cleanly() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+
+    assert_error(
+        staking.stake(&pool, &staker, 0, STAKE, i64::MAX),
+        GamingTokenError::LockTooLong,
+    );
+    assert!(staking.harness.account(&pool.stake_account(&staker.wallet, 0)).is_none());
+    assert_eq!(staking.balance(&staker.tokens), STAKE);
+}
+
+#[test]
+fn negative_locks_are_rejected() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+
+    for lock_period in [-1, i64::MIN] {
+        assert_error(
+            staking.stake(&pool, &staker, 0, STAKE, lock_period),
+            GamingTokenError::InvalidLockPeriod,
+        );
+    }
+}
+
+#[test]
+fn a_lock_ending_past_the_clock_range_overflows_cleanly() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.harness.set_time(i64::MAX - DAY);
+
+    assert_error(
+        staking.stake(&pool, &staker, 0, STAKE, 2 * DAY),
+        GamingTokenError::MathOverflow,
+    );
+}
+
+#[test]
+fn extending_to_i64_max_is_rejected() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    let lock_until = staking.position(&pool, &staker.wallet, 0).lock_until;
+
+    assert_error(
+        staking.update_position(
+            &pool,
+            &staker,
+            0,
+            instruction::ExtendLock {
+                new_lock_until: i64::MAX,
+            },
+        ),
+        GamingTokenError::LockTooLong,
+    );
+    // Still locked: the failed extension left nothing instantly unlockable
+    assert_eq!(staking.position(&pool, &staker.wallet, 0).lock_until, lock_until);
+    assert_error(
+        staking.unstake(&pool, &staker, 0),
+        GamingTokenError::TokensStillLocked,
+    );
+}
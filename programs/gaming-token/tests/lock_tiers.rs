@@ -0,0 +1,111 @@
+mod common;
+
+use common::*;
+use gaming_token::{instruction, GamingTokenError, LockTier, TokensStaked, MAX_LOCK_TIERS};
+
+const STAKE: u64 = 1_000_000_000;
+/// 10,000 a second over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+fn tiers() -> Vec<LockTier> {
+    vec![
+        LockTier {
+            min_lock_seconds: 7 * DAY,
+            boost_bps: 1_000,
+        },
+        LockTier {
+            min_lock_seconds: 30 * DAY,
+            boost_bps: 5_000,
+        },
+    ]
+}
+
+fn tiered_pool() -> (Staking, Pool) {
+    let (mut staking, pool) = Staking::with_pool();
+    staking
+        .update_pool(&pool, instruction::UpdateLockTiers { lock_tiers: tiers() })
+        .unwrap();
+    (staking, pool)
+}
+
+#[test]
+fn a_lock_gets_the_highest_tier_it_reaches() {
+    let (mut staking, pool) = tiered_pool();
+
+    for (lock, tier, boost_bps) in [
+        (7 * DAY - 1, None, 0),
+        (7 * DAY, Some(0), 1_000),
+        (30 * DAY - 1, Some(0), 1_000),
+        (30 * DAY, Some(1), 5_000),
+        (365 * DAY, Some(1), 5_000),
+    ] {
+        let staker = staking.staker(&pool, STAKE);
+        staking.stake(&pool, &staker, 0, STAKE, lock).unwrap();
+        let staked = staking.harness.event::<TokensStaked>();
+        assert_eq!(staked.tier, tier, "lock {lock}");
+        assert_eq!(staked.boost_bps, boost_bps, "lock {lock}");
+        assert_eq!(staking.position(&pool, &staker.wallet, 0).boost_bps, boost_bps);
+    }
+
+    let weight = STAKE as u128 * (10_000 + 11_000 + 11_000 + 15_000 + 15_000) / 10_000;
+    assert_eq!(staking.pool_state(&pool).total_weight, weight);
+}
+
+#[test]
+fn boosted_locks_earn_in_proportion_to_their_weight() {
+    let (mut staking, pool) = tiered_pool();
+    let short = staking.staker(&pool, STAKE);
+    let long = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &short, 0, STAKE, DAY).unwrap();
+    staking.stake(&pool, &long, 0, STAKE, 30 * DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+
+    // Dropping the tiers doesn't touch a boost already granted
+    staking
+        .update_pool(&pool, instruction::UpdateLockTiers { lock_tiers: vec![] })
+        .unwrap();
+
+    staking.harness.warp(DAY);
+    staking.claim(&pool, &short, 0).unwrap();
+    staking.claim(&pool, &long, 0).unwrap();
+    let day = 10_000 * DAY as u64;
+    assert_eq!(staking.balance(&short.tokens), day * 2 / 5);
+    assert_eq!(staking.balance(&long.tokens), day * 3 / 5);
+}
+
+#[test]
+fn tiers_must_be_sorted_bounded_and_few() {
+    let (mut staking, pool) = Staking::with_pool();
+    let reject = |staking: &mut Staking, lock_tiers: Vec<LockTier>| {
+        assert_error(
+            staking.update_pool(&pool, instruction::UpdateLockTiers { lock_tiers }),
+            GamingTokenError::InvalidLockTiers,
+        );
+    };
+
+    let mut unsorted = tiers();
+    unsorted.reverse();
+    reject(&mut staking, unsorted);
+
+    let mut duplicate = tiers();
+    duplicate[1].min_lock_seconds = duplicate[0].min_lock_seconds;
+    reject(&mut staking, duplicate);
+
+    let mut zero = tiers();
+    zero[0].min_lock_seconds = 0;
+    reject(&mut staking, zero);
+
+    let mut oversized = tiers();
+    oversized[1].boost_bps = 10_001;
+    reject(&mut staking, oversized);
+
+    let too_many = (1..=MAX_LOCK_TIERS as i64 + 1)
+        .map(|days| LockTier {
+            min_lock_seconds: days * DAY,
+            boost_bps: 100,
+        })
+        .collect();
+    reject(&mut staking, too_many);
+
+    assert!(staking.pool_state(&pool).lock_tiers.is_empty());
+}
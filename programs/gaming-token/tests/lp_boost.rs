@@ -0,0 +1,124 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{accounts, instruction, GamingTokenError, TokensUnstaked, MAX_LP_BOOST_BPS};
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+fn set_lp_pool_ix(staking: &Staking, pool: &Pool, lp_pool: bool, boost_bps: u16) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::SetLpPool {
+            staking_config: staking.staking_config(),
+            stake_pool: pool.address(),
+            admin: staking.admin,
+        }
+        .to_account_metas(None),
+        data: instruction::SetLpPool { lp_pool, boost_bps }.data(),
+    }
+}
+
+/// Adds `amount` to the pool's reward reserve without starting a period.
+fn fund(staking: &mut Staking, pool: &Pool, amount: u64) {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::FundRewards {
+            stake_pool: pool.address(),
+            reward_vault: pool.reward_vault(),
+            funder_token_account: staking.admin_tokens(&pool.reward_mint),
+            funder: staking.admin,
+            reward_mint: pool.reward_mint,
+            token_program: pool.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::FundRewards { amount }.data(),
+    };
+    staking.admin(ix).unwrap();
+}
+
+/// Gives `pool` `surplus` of unowed reserve and `boost_bps`, then stakes
+/// `STAKE` and notifies `REWARDS` into it.
+fn prepare(staking: &mut Staking, pool: &Pool, boost_bps: u16, surplus: u64) -> Staker {
+    if surplus > 0 {
+        fund(staking, pool, surplus);
+    }
+    if boost_bps > 0 {
+        let ix = set_lp_pool_ix(staking, pool, true, boost_bps);
+        staking.admin(ix).unwrap();
+    }
+    let staker = staking.staker(pool, STAKE);
+    staking.stake(pool, &staker, 0, STAKE, 1).unwrap();
+    staking.notify(pool, REWARDS).unwrap();
+    staker
+}
+
+/// Unstakes after the period and returns the reward, checking the vault
+/// covered it and kept only the unowed rest.
+fn settle(staking: &mut Staking, pool: &Pool, staker: &Staker, surplus: u64) -> u64 {
+    staking.unstake(pool, staker, 0).unwrap();
+    let reward = staking.harness.event::<TokensUnstaked>().reward;
+    assert_eq!(staking.balance(&pool.reward_vault()), REWARDS + surplus - reward);
+    reward
+}
+
+fn earned(boost_bps: u16, surplus: u64) -> u64 {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = prepare(&mut staking, &pool, boost_bps, surplus);
+    staking.harness.set_time(staking.pool_state(&pool).period_finish);
+    settle(&mut staking, &pool, &staker, surplus)
+}
+
+#[test]
+fn a_boosted_pool_earns_proportionally_more_per_token() {
+    let (mut staking, plain_pool) = Staking::with_pool();
+    let lp_pool = staking.single_token_pool(TOKEN, PoolParams::default());
+    let plain_staker = prepare(&mut staking, &plain_pool, 0, REWARDS / 2);
+    let lp_staker = prepare(&mut staking, &lp_pool, 5_000, REWARDS / 2);
+
+    let finish = staking.pool_state(&lp_pool).period_finish;
+    assert_eq!(staking.pool_state(&plain_pool).period_finish, finish);
+    staking.harness.set_time(finish);
+    let plain = settle(&mut staking, &plain_pool, &plain_staker, REWARDS / 2);
+    let boosted = settle(&mut staking, &lp_pool, &lp_staker, REWARDS / 2);
+
+    assert_eq!(plain, REWARDS);
+    assert_eq!(boosted, REWARDS + REWARDS / 2);
+    assert_eq!(boosted * 2, plain * 3);
+}
+
+#[test]
+fn the_boost_only_spends_reserve_nobody_is_owed() {
+    // Nothing spare: the boost has nothing to draw on
+    assert_eq!(earned(5_000, 0), REWARDS);
+    // A quarter spare caps a 50% boost at 25%
+    assert_eq!(earned(5_000, REWARDS / 4), REWARDS + REWARDS / 4);
+}
+
+#[test]
+fn only_the_admin_sets_a_bounded_boost_on_lp_pools() {
+    let (mut staking, pool) = Staking::with_pool();
+
+    let ix = set_lp_pool_ix(&staking, &pool, true, MAX_LP_BOOST_BPS + 1);
+    assert_error(staking.admin(ix), GamingTokenError::InvalidBoost);
+    let ix = set_lp_pool_ix(&staking, &pool, false, 1);
+    assert_error(staking.admin(ix), GamingTokenError::InvalidBoost);
+
+    let stranger = staking.harness.funded_wallet();
+    let mut ix = set_lp_pool_ix(&staking, &pool, true, 5_000);
+    swap_account(&mut ix, staking.admin, stranger);
+    assert_error(
+        staking.harness.process(&[ix], &[stranger]),
+        ErrorCode::ConstraintHasOne,
+    );
+
+    let ix = set_lp_pool_ix(&staking, &pool, true, MAX_LP_BOOST_BPS);
+    staking.admin(ix).unwrap();
+    let state = staking.pool_state(&pool);
+    assert!(state.lp_pool);
+    assert_eq!(state.boost_bps, MAX_LP_BOOST_BPS);
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use common::*;
+use gaming_token::{instruction, GamingTokenError, MaxSupplyLowered};
+
+const CAP: u64 = 1_000_000;
+const MINTED: u64 = 400_000;
+
+fn lower_max_supply(minting: &mut Minting, max_supply: u64) -> TxResult {
+    let ix = minting.update_config_ix(instruction::LowerMaxSupply { max_supply });
+    minting.process(ix)
+}
+
+#[test]
+fn the_cap_never_drops_below_the_current_supply() {
+    let mut minting = Minting::new(CAP);
+    let holder = minting.holder();
+    minting.mint_tokens(holder, MINTED).unwrap();
+
+    for max_supply in [0, MINTED - 1, CAP + 1] {
+        assert_error(
+            lower_max_supply(&mut minting, max_supply),
+            GamingTokenError::InvalidSupplyCap,
+        );
+    }
+    assert_eq!(minting.config_state().max_supply, CAP);
+
+    // Down to exactly what exists is allowed
+    lower_max_supply(&mut minting, MINTED).unwrap();
+    let event = minting.harness.event::<MaxSupplyLowered>();
+    assert_eq!((event.old_max_supply, event.new_max_supply), (CAP, MINTED));
+}
+
+#[test]
+fn a_lowered_cap_blocks_the_next_mint() {
+    let mut minting = Minting::new(CAP);
+    let holder = minting.holder();
+    minting.mint_tokens(holder, MINTED).unwrap();
+
+    lower_max_supply(&mut minting, MINTED + 100).unwrap();
+    assert_error(minting.mint_tokens(holder, 101), GamingTokenError::SupplyCapExceeded);
+    let ix = minting.batch_mint_ix(&[(holder, 50), (holder, 51)]);
+    assert_error(minting.process(ix), GamingTokenError::SupplyCapExceeded);
+
+    minting.mint_tokens(holder, 100).unwrap();
+    assert_eq!(minting.supply(), MINTED + 100);
+}
@@ -0,0 +1,233 @@
+mod common;
+
+use std::cell::RefCell;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::metadata::mpl_token_metadata::instructions::{
+    CreateMetadataAccountV3, CreateMetadataAccountV3InstructionArgs, UpdateMetadataAccountV2,
+    UpdateMetadataAccountV2InstructionArgs,
+};
+use anchor_spl::metadata::mpl_token_metadata::types::DataV2;
+use anchor_spl::metadata::mpl_token_metadata::{ID as METADATA_PROGRAM, MAX_URI_LENGTH};
+use common::*;
+use gaming_token::{accounts, instruction, GamingTokenError, MetadataCreated};
+
+const URI: &str = "https://sol-itaire.gg/token.json";
+
+thread_local! {
+    /// Every instruction the mock metadata program was invoked with.
+    static METADATA_CALLS: RefCell<Vec<Instruction>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Stands in for Token Metadata: accepts everything and records the call.
+fn mock_metadata_program(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let ix = Instruction {
+        program_id: *program_id,
+        accounts: accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: data.to_vec(),
+    };
+    METADATA_CALLS.with(|calls| calls.borrow_mut().push(ix));
+    Ok(())
+}
+
+fn metadata_calls() -> Vec<Instruction> {
+    METADATA_CALLS.with(|calls| calls.borrow_mut().drain(..).collect())
+}
+
+fn with_metadata_program() -> Minting {
+    let mut minting = Minting::new(0);
+    minting.harness.add_program(METADATA_PROGRAM, mock_metadata_program);
+    metadata_calls();
+    minting
+}
+
+fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", METADATA_PROGRAM.as_ref(), mint.as_ref()],
+        &METADATA_PROGRAM,
+    )
+    .0
+}
+
+fn data(name: &str, symbol: &str, uri: &str) -> DataV2 {
+    DataV2 {
+        name: name.to_string(),
+        symbol: symbol.to_string(),
+        uri: uri.to_string(),
+        seller_fee_basis_points: 0,
+        creators: None,
+        collection: None,
+        uses: None,
+    }
+}
+
+fn create_metadata_ix(minting: &Minting, uri: &str) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::CreateMetadata {
+            mint_config: minting.config(),
+            mint: minting.mint,
+            mint_authority: minting.mint_authority(),
+            metadata: metadata_address(&minting.mint),
+            authority: minting.authority,
+            token_metadata_program: METADATA_PROGRAM,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::CreateMetadata { uri: uri.to_string() }.data(),
+    }
+}
+
+fn update_metadata_ix(minting: &Minting, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::UpdateTokenMetadata {
+            mint_config: minting.config(),
+            metadata: metadata_address(&minting.mint),
+            authority: minting.authority,
+            token_metadata_program: METADATA_PROGRAM,
+        }
+        .to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// What `create_metadata` should hand Token Metadata with `mint_authority` signing.
+fn expected_create(minting: &Minting, mint_authority: Pubkey) -> Instruction {
+    CreateMetadataAccountV3 {
+        metadata: metadata_address(&minting.mint),
+        mint: minting.mint,
+        mint_authority,
+        payer: minting.authority,
+        update_authority: (minting.authority, true),
+        system_program: system_program::ID,
+        rent: None,
+    }
+    .instruction(CreateMetadataAccountV3InstructionArgs {
+        data: data("Sol-itaire Gold", "GOLD", URI),
+        is_mutable: true,
+        collection_details: None,
+    })
+}
+
+fn assert_same_call(actual: &Instruction, expected: &Instruction) {
+    assert_eq!(actual.program_id, expected.program_id);
+    assert_eq!(actual.data, expected.data);
+    let keys = |ix: &Instruction| -> Vec<(Pubkey, bool)> {
+        ix.accounts.iter().map(|meta| (meta.pubkey, meta.is_signer)).collect()
+    };
+    assert_eq!(keys(actual), keys(expected));
+}
+
+#[test]
+fn create_metadata_passes_the_configured_name_symbol_and_uri() {
+    let mut minting = with_metadata_program();
+
+    let ix = create_metadata_ix(&minting, URI);
+    minting.process(ix).unwrap();
+
+    let calls = metadata_calls();
+    assert_eq!(calls.len(), 1);
+    assert_same_call(&calls[0], &expected_create(&minting, minting.authority));
+
+    let event = minting.harness.event::<MetadataCreated>();
+    assert_eq!(event.metadata, metadata_address(&minting.mint));
+    assert_eq!(event.uri, URI);
+    assert_eq!(event.update_authority, minting.authority);
+}
+
+#[test]
+fn the_program_pda_signs_once_it_holds_the_mint_authority() {
+    let mut minting = with_metadata_program();
+    minting.enable_minters().unwrap();
+
+    let ix = create_metadata_ix(&minting, URI);
+    minting.process(ix).unwrap();
+
+    let calls = metadata_calls();
+    assert_same_call(&calls[0], &expected_create(&minting, minting.mint_authority()));
+}
+
+#[test]
+fn the_uri_must_be_present_and_bounded() {
+    let mut minting = with_metadata_program();
+
+    let ix = create_metadata_ix(&minting, "");
+    assert_error(minting.process(ix), GamingTokenError::InvalidUri);
+    let ix = create_metadata_ix(&minting, &"u".repeat(MAX_URI_LENGTH + 1));
+    assert_error(minting.process(ix), GamingTokenError::UriTooLong);
+    assert!(metadata_calls().is_empty());
+
+    let ix = create_metadata_ix(&minting, &"u".repeat(MAX_URI_LENGTH));
+    minting.process(ix).unwrap();
+}
+
+#[test]
+fn the_metadata_account_must_be_the_canonical_pda() {
+    let mut minting = with_metadata_program();
+
+    let mut ix = create_metadata_ix(&minting, URI);
+    swap_account(&mut ix, metadata_address(&minting.mint), Pubkey::new_unique());
+    assert_error(minting.process(ix), ErrorCode::ConstraintSeeds);
+    assert!(metadata_calls().is_empty());
+}
+
+#[test]
+fn updates_follow_the_config_until_finalized() {
+    let mut minting = with_metadata_program();
+    let ix = create_metadata_ix(&minting, URI);
+    minting.process(ix).unwrap();
+    metadata_calls();
+
+    let ix = update_metadata_ix(
+        &minting,
+        instruction::UpdateTokenMetadata {
+            token_name: "Sol-itaire Chips".to_string(),
+            token_symbol: "CHIPS".to_string(),
+            uri: URI.to_string(),
+        },
+    );
+    minting.process(ix).unwrap();
+
+    let expected = UpdateMetadataAccountV2 {
+        metadata: metadata_address(&minting.mint),
+        update_authority: minting.authority,
+    }
+    .instruction(UpdateMetadataAccountV2InstructionArgs {
+        data: Some(data("Sol-itaire Chips", "CHIPS", URI)),
+        new_update_authority: None,
+        primary_sale_happened: None,
+        is_mutable: None,
+    });
+    assert_same_call(&metadata_calls()[0], &expected);
+    let config = minting.config_state();
+    assert_eq!(config.token_name, "Sol-itaire Chips");
+    assert_eq!(config.token_symbol, "CHIPS");
+
+    let ix = update_metadata_ix(&minting, instruction::FinalizeMetadata {});
+    minting.process(ix).unwrap();
+    assert!(minting.config_state().metadata_finalized);
+
+    let ix = update_metadata_ix(
+        &minting,
+        instruction::UpdateTokenMetadata {
+            token_name: "Renamed".to_string(),
+            token_symbol: "RN".to_string(),
+            uri: URI.to_string(),
+        },
+    );
+    assert_error(minting.process(ix), GamingTokenError::MetadataFinalized);
+}
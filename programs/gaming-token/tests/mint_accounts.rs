@@ -0,0 +1,64 @@
+mod common;
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{accounts, instruction, GamingTokenError};
+
+const AMOUNT: u64 = 1_000;
+
+#[test]
+fn a_foreign_mint_is_refused_before_the_cpi() {
+    let mut minting = Minting::new(0);
+    let authority = minting.authority;
+    // The authority can mint this one directly, so only the constraint stops it
+    let foreign_mint = minting.harness.create_mint(&TOKEN, &authority, 6);
+    let foreign_account = minting.harness.create_token_account(&foreign_mint, &authority);
+
+    let mut ix = minting.mint_tokens_ix(foreign_account, AMOUNT);
+    swap_account(&mut ix, minting.mint, foreign_mint);
+    assert_error(minting.process(ix), GamingTokenError::ConfigMintMismatch);
+    assert_eq!(minting.balance(&foreign_account), 0);
+    assert_eq!(minting.config_state().total_supply, 0);
+}
+
+#[test]
+fn a_foreign_token_account_is_refused_before_the_cpi() {
+    let mut minting = Minting::new(0);
+    let authority = minting.authority;
+    let foreign_mint = minting.harness.create_mint(&TOKEN, &authority, 6);
+    let foreign_account = minting.harness.create_token_account(&foreign_mint, &authority);
+
+    let ix = minting.mint_tokens_ix(foreign_account, AMOUNT);
+    assert_error(minting.process(ix), GamingTokenError::MintMismatch);
+
+    let holder = minting.holder();
+    minting.mint_tokens(holder, AMOUNT).unwrap();
+    assert_eq!(minting.balance(&holder), AMOUNT);
+}
+
+#[test]
+fn a_drifted_decimals_config_is_caught_until_resynced() {
+    let mut minting = Minting::new(0);
+    let holder = minting.holder();
+    minting.edit_config(|config| config.decimals = 9);
+
+    assert_error(
+        minting.mint_tokens(holder, AMOUNT),
+        GamingTokenError::DecimalsMismatch,
+    );
+
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::FixDecimalsMismatch {
+            mint_config: minting.config(),
+            mint: minting.mint,
+            authority: minting.authority,
+        }
+        .to_account_metas(None),
+        data: instruction::FixDecimalsMismatch {}.data(),
+    };
+    minting.process(ix).unwrap();
+    assert_eq!(minting.config_state().decimals, 6);
+    minting.mint_tokens(holder, AMOUNT).unwrap();
+}
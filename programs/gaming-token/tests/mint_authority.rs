@@ -0,0 +1,104 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use gaming_token::{
+    instruction, FreezeAuthorityRevoked, GamingTokenError, MintAuthorityRevoked, MintAuthorityTransferred,
+};
+
+fn revoke_mint_authority(minting: &mut Minting) -> TxResult {
+    let ix = minting.manage_authority_ix(instruction::RevokeMintAuthority {});
+    minting.process(ix)
+}
+
+fn transfer_mint_authority(minting: &mut Minting, new_mint_authority: Pubkey) -> TxResult {
+    let ix = minting.manage_authority_ix(instruction::TransferMintAuthority { new_mint_authority });
+    minting.process(ix)
+}
+
+#[test]
+fn a_revoked_mint_authority_can_never_come_back() {
+    let mut minting = Minting::new(0);
+    let minter = minting.minter(u64::MAX, u64::MAX, DAY);
+    let holder = minting.holder();
+    minting.mint_tokens(holder, 500).unwrap();
+
+    revoke_mint_authority(&mut minting).unwrap();
+    assert_eq!(minting.harness.event::<MintAuthorityRevoked>().total_supply, 500);
+    assert!(minting.harness.mint(&minting.mint).mint_authority.is_none());
+    assert_eq!(minting.config_state().mint_authority, None);
+
+    assert_error(minting.mint_tokens(holder, 1), GamingTokenError::MintAuthorityRevoked);
+    let ix = minting.batch_mint_ix(&[(holder, 1)]);
+    assert_error(minting.process(ix), GamingTokenError::MintAuthorityRevoked);
+    assert_error(
+        minting.mint_as_minter(minter, holder, 1),
+        GamingTokenError::MintAuthorityRevoked,
+    );
+
+    let authority = minting.authority;
+    assert_error(
+        transfer_mint_authority(&mut minting, authority),
+        GamingTokenError::MintAuthorityRevoked,
+    );
+    assert_error(minting.enable_minters(), GamingTokenError::MintAuthorityRevoked);
+    assert_error(revoke_mint_authority(&mut minting), GamingTokenError::MintAuthorityRevoked);
+    assert_eq!(minting.supply(), 500);
+}
+
+#[test]
+fn revoking_works_while_the_program_holds_the_authority() {
+    let mut minting = Minting::new(0);
+    minting.enable_minters().unwrap();
+
+    revoke_mint_authority(&mut minting).unwrap();
+    assert!(minting.harness.mint(&minting.mint).mint_authority.is_none());
+}
+
+#[test]
+fn transferring_to_the_program_pda_keeps_minting_behind_the_caps() {
+    let mut minting = Minting::new(1_000);
+    let holder = minting.holder();
+    let pda = minting.mint_authority();
+
+    transfer_mint_authority(&mut minting, pda).unwrap();
+    let event = minting.harness.event::<MintAuthorityTransferred>();
+    assert_eq!(event.old_mint_authority, Some(minting.authority));
+    assert_eq!(event.new_mint_authority, pda);
+    assert_eq!(minting.harness.mint(&minting.mint).mint_authority, Some(pda).into());
+
+    minting.mint_tokens(holder, 1_000).unwrap();
+    assert_error(minting.mint_tokens(holder, 1), GamingTokenError::SupplyCapExceeded);
+}
+
+#[test]
+fn a_revoked_freeze_authority_stays_revoked() {
+    let mut minting = Minting::new(0);
+
+    let ix = minting.manage_authority_ix(instruction::RevokeFreezeAuthority {});
+    minting.process(ix.clone()).unwrap();
+    assert_eq!(minting.harness.event::<FreezeAuthorityRevoked>().mint, minting.mint);
+    assert!(minting.harness.mint(&minting.mint).freeze_authority.is_none());
+    assert_eq!(minting.config_state().freeze_authority, None);
+
+    assert_error(minting.process(ix), GamingTokenError::FreezeAuthorityRevoked);
+}
+
+#[test]
+fn only_the_config_authority_manages_authorities() {
+    let mut minting = Minting::new(0);
+    let stranger = minting.harness.funded_wallet();
+
+    for mut ix in [
+        minting.manage_authority_ix(instruction::RevokeMintAuthority {}),
+        minting.manage_authority_ix(instruction::RevokeFreezeAuthority {}),
+        minting.manage_authority_ix(instruction::TransferMintAuthority {
+            new_mint_authority: stranger,
+        }),
+    ] {
+        swap_account(&mut ix, minting.authority, stranger);
+        assert_error(minting.harness.process(&[ix], &[stranger]), ErrorCode::ConstraintHasOne);
+    }
+    assert!(minting.harness.mint(&minting.mint).mint_authority.is_some());
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{accounts, instruction, GamingTokenError, MintingPauseUpdated, TokensBurned};
+
+/// A mint with a guardian key and a registered minter.
+fn guarded() -> (Minting, Pubkey, Pubkey) {
+    let mut minting = Minting::new(0);
+    let guardian = minting.harness.funded_wallet();
+    let ix = minting.update_config_ix(instruction::SetMintGuardian { guardian });
+    minting.process(ix).unwrap();
+    let minter = minting.minter(u64::MAX, u64::MAX, DAY);
+    (minting, guardian, minter)
+}
+
+#[test]
+fn the_guardian_can_pause_but_not_unpause_or_mint() {
+    let (mut minting, guardian, _) = guarded();
+    let holder = minting.holder();
+
+    minting.set_minting_paused(guardian, true).unwrap();
+    let event = minting.harness.event::<MintingPauseUpdated>();
+    assert!(event.paused);
+    assert_eq!(event.actor, guardian);
+    assert!(minting.config_state().minting_paused);
+
+    assert_error(
+        minting.set_minting_paused(guardian, false),
+        GamingTokenError::Unauthorized,
+    );
+    assert!(minting.config_state().minting_paused);
+
+    let mut ix = minting.mint_tokens_ix(holder, 1);
+    swap_account(&mut ix, minting.authority, guardian);
+    assert_error(minting.harness.process(&[ix], &[guardian]), ErrorCode::ConstraintHasOne);
+
+    let authority = minting.authority;
+    minting.set_minting_paused(authority, false).unwrap();
+    assert_eq!(minting.harness.event::<MintingPauseUpdated>().actor, authority);
+    assert!(!minting.config_state().minting_paused);
+}
+
+#[test]
+fn anyone_else_can_do_neither() {
+    let (mut minting, _, minter) = guarded();
+    let stranger = minting.harness.funded_wallet();
+
+    for actor in [stranger, minter] {
+        assert_error(minting.set_minting_paused(actor, true), GamingTokenError::Unauthorized);
+    }
+}
+
+#[test]
+fn pausing_stops_every_mint_path_but_not_burns() {
+    let (mut minting, guardian, minter) = guarded();
+    let owner = minting.harness.funded_wallet();
+    let mint = minting.mint;
+    let holder = minting.harness.create_token_account(&mint, &owner);
+    minting.mint_as_minter(minter, holder, 1_000).unwrap();
+
+    minting.set_minting_paused(guardian, true).unwrap();
+
+    assert_error(minting.mint_tokens(holder, 1), GamingTokenError::MintingPaused);
+    let ix = minting.batch_mint_ix(&[(holder, 1)]);
+    assert_error(minting.process(ix), GamingTokenError::MintingPaused);
+    assert_error(
+        minting.mint_as_minter(minter, holder, 1),
+        GamingTokenError::MintingPaused,
+    );
+
+    let burn = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::BurnTokens {
+            mint_config: minting.config(),
+            mint,
+            token_account: holder,
+            owner,
+            token_program: TOKEN,
+        }
+        .to_account_metas(None),
+        data: instruction::BurnTokens { amount: 400 }.data(),
+    };
+    minting.harness.process(&[burn], &[owner]).unwrap();
+    assert_eq!(minting.harness.event::<TokensBurned>().new_supply, 600);
+    assert_eq!(minting.balance(&holder), 600);
+
+    let authority = minting.authority;
+    minting.set_minting_paused(authority, false).unwrap();
+    minting.mint_as_minter(minter, holder, 1).unwrap();
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use gaming_token::{instruction, GamingTokenError};
+
+const ALLOWANCE: u64 = 1_000;
+
+/// A minter allowed `ALLOWANCE` a day and effectively no lifetime cap.
+fn minter() -> (Minting, Pubkey, Pubkey, i64) {
+    let mut minting = Minting::new(0);
+    let minter = minting.minter(u64::MAX, ALLOWANCE, DAY);
+    let holder = minting.holder();
+    let start = minting.harness.now();
+    (minting, minter, holder, start)
+}
+
+#[test]
+fn a_mint_straddling_the_boundary_draws_on_the_new_epoch() {
+    let (mut minting, minter, holder, start) = minter();
+
+    minting.mint_as_minter(minter, holder, 700).unwrap();
+    minting.harness.set_time(start + DAY - 1);
+    assert_error(
+        minting.mint_as_minter(minter, holder, 301),
+        GamingTokenError::MinterAllowanceExceeded,
+    );
+    minting.mint_as_minter(minter, holder, 300).unwrap();
+    assert_error(
+        minting.mint_as_minter(minter, holder, 1),
+        GamingTokenError::MinterAllowanceExceeded,
+    );
+
+    // One second later the window has rolled and the full allowance is back
+    minting.harness.set_time(start + DAY);
+    minting.mint_as_minter(minter, holder, ALLOWANCE).unwrap();
+
+    let record = minting.minter_state(&minter);
+    assert_eq!(record.current_epoch_start, start + DAY);
+    assert_eq!(record.minted_this_epoch, ALLOWANCE);
+    assert_eq!(record.minted, 2 * ALLOWANCE);
+    assert_eq!(minting.balance(&holder), 2 * ALLOWANCE);
+}
+
+#[test]
+fn idle_epochs_are_skipped_and_the_window_stays_aligned() {
+    let (mut minting, minter, holder, start) = minter();
+    minting.mint_as_minter(minter, holder, ALLOWANCE).unwrap();
+
+    minting.harness.set_time(start + 4 * DAY + DAY / 2);
+    minting.mint_as_minter(minter, holder, 400).unwrap();
+
+    let record = minting.minter_state(&minter);
+    assert_eq!(record.current_epoch_start, start + 4 * DAY);
+    assert_eq!(record.minted_this_epoch, 400);
+}
+
+#[test]
+fn the_lifetime_cap_still_applies_across_epochs() {
+    let mut minting = Minting::new(0);
+    let minter = minting.minter(1_500, ALLOWANCE, DAY);
+    let holder = minting.holder();
+
+    minting.mint_as_minter(minter, holder, ALLOWANCE).unwrap();
+    minting.harness.warp(DAY);
+    assert_error(
+        minting.mint_as_minter(minter, holder, 501),
+        GamingTokenError::MinterCapExceeded,
+    );
+    minting.mint_as_minter(minter, holder, 500).unwrap();
+}
+
+#[test]
+fn a_new_allowance_restarts_the_window_but_keeps_this_epochs_mints() {
+    let (mut minting, minter, holder, start) = minter();
+    minting.mint_as_minter(minter, holder, 800).unwrap();
+
+    minting.harness.set_time(start + DAY / 2);
+    let ix = minting.update_minter_ix(
+        &minter,
+        instruction::UpdateMinterAllowance {
+            allowance_per_epoch: 1_000,
+            epoch_length_seconds: 2 * DAY,
+        },
+    );
+    minting.process(ix).unwrap();
+
+    let record = minting.minter_state(&minter);
+    assert_eq!(record.current_epoch_start, start + DAY / 2);
+    assert_eq!(record.minted_this_epoch, 800);
+    assert_error(
+        minting.mint_as_minter(minter, holder, 201),
+        GamingTokenError::MinterAllowanceExceeded,
+    );
+}
+
+#[test]
+fn a_zero_allowance_or_epoch_is_rejected() {
+    let mut minting = Minting::new(0);
+    for (allowance, epoch) in [(0, DAY), (ALLOWANCE, 0), (ALLOWANCE, -1)] {
+        let ix = minting.add_minter_ix(Pubkey::new_unique(), u64::MAX, allowance, epoch);
+        assert_error(minting.process(ix), GamingTokenError::InvalidMinterAllowance);
+    }
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use gaming_token::{GamingTokenError, StakeAccountClosed, TokensUnstaked};
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+#[test]
+fn partial_withdrawals_chain_down_to_zero() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 10 * DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+
+    assert_error(
+        staking.partial_unstake(&pool, &staker, 0, STAKE / 4),
+        GamingTokenError::TokensStillLocked,
+    );
+
+    staking.harness.warp(10 * DAY);
+    let mut rewards = 0;
+    let mut remaining = STAKE;
+    for amount in [STAKE / 4, STAKE / 4, STAKE / 2] {
+        staking.partial_unstake(&pool, &staker, 0, amount).unwrap();
+        remaining -= amount;
+        let event = staking.harness.event::<TokensUnstaked>();
+        assert_eq!(event.principal, amount);
+        assert_eq!(event.remaining, remaining);
+        rewards += event.reward;
+        assert_eq!(staking.pool_state(&pool).total_staked, remaining);
+        assert_eq!(staking.balance(&pool.stake_vault()), remaining);
+        staking.harness.warp(DAY);
+    }
+
+    // The last withdrawal closed the position; a lone staker earned every second of it
+    assert_eq!(rewards, REWARDS / 100 * 12);
+    assert_eq!(staking.balance(&staker.tokens), STAKE + rewards);
+    assert!(staking.harness.account(&pool.stake_account(&staker.wallet, 0)).is_none());
+    assert_eq!(staking.pool_state(&pool).total_positions, 0);
+
+    assert_error(staking.claim(&pool, &staker, 0), ErrorCode::AccountNotInitialized);
+    assert_error(
+        staking.partial_unstake(&pool, &staker, 0, 1),
+        ErrorCode::AccountNotInitialized,
+    );
+}
+
+#[test]
+fn closing_the_position_emits_its_refund() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    let rent = staking.harness.lamports(&pool.stake_account(&staker.wallet, 0));
+
+    staking.harness.warp(DAY);
+    staking.partial_unstake(&pool, &staker, 0, STAKE).unwrap();
+    let closed = staking.harness.event::<StakeAccountClosed>();
+    assert_eq!(closed.owner, staker.wallet);
+    assert_eq!(closed.rent_refunded, rent);
+}
+
+#[test]
+fn withdrawals_leave_at_least_the_minimum() {
+    let mut staking = Staking::new();
+    let params = PoolParams {
+        min_stake_amount: 1_000,
+        ..PoolParams::default()
+    };
+    let pool = staking.single_token_pool(TOKEN, params);
+    let staker = staking.staker(&pool, 5_000);
+    staking.stake(&pool, &staker, 0, 5_000, DAY).unwrap();
+    staking.harness.warp(DAY);
+
+    assert_error(staking.partial_unstake(&pool, &staker, 0, 0), GamingTokenError::InvalidAmount);
+    assert_error(
+        staking.partial_unstake(&pool, &staker, 0, 5_001),
+        GamingTokenError::InsufficientFunds,
+    );
+    assert_error(
+        staking.partial_unstake(&pool, &staker, 0, 4_001),
+        GamingTokenError::RemainderBelowMinimum,
+    );
+
+    staking.partial_unstake(&pool, &staker, 0, 4_000).unwrap();
+    assert_eq!(staking.position(&pool, &staker.wallet, 0).amount, 1_000);
+    staking.partial_unstake(&pool, &staker, 0, 1_000).unwrap();
+    assert_eq!(staking.balance(&staker.tokens), 5_000);
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use common::*;
+use gaming_token::{instruction, EarlyUnstaked, RewardsClaimed, REWARD_PRECISION};
+
+const STAKE: u64 = 1_000_000_000;
+const LOCK: i64 = 10 * DAY;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+/// A pool charging 20% on the full lock, with Alice, Bob and the leaver
+/// locked in at 1:3:2.
+fn three_stakers() -> (Staking, Pool, [Staker; 3]) {
+    let (mut staking, pool) = Staking::with_pool();
+    staking
+        .update_pool(
+            &pool,
+            instruction::UpdateEarlyUnstakePenalty {
+                early_unstake_penalty_bps: 2_000,
+            },
+        )
+        .unwrap();
+    let stakers = [STAKE, 3 * STAKE, 2 * STAKE].map(|amount| {
+        let staker = staking.staker(&pool, amount);
+        staking.stake(&pool, &staker, 0, amount, LOCK).unwrap();
+        staker
+    });
+    (staking, pool, stakers)
+}
+
+/// What the position would be paid if it claimed now, in `1 / REWARD_PRECISION` units.
+fn pending(staking: &Staking, pool: &Pool, staker: &Staker) -> u128 {
+    let position = staking.position(pool, &staker.wallet, 0);
+    let accumulator = staking.pool_state(pool).reward_per_token_stored;
+    position.rewards_accrued as u128 * REWARD_PRECISION
+        + position.reward_dust as u128
+        + position.weight() * (accumulator - position.reward_per_token_paid)
+}
+
+#[test]
+fn the_penalty_goes_to_the_stakers_who_remain() {
+    let (mut staking, pool, [alice, bob, leaver]) = three_stakers();
+
+    staking.early_unstake(&pool, &leaver, 0).unwrap();
+    let event = staking.harness.event::<EarlyUnstaked>();
+    let penalty = 2 * STAKE / 5;
+    assert_eq!((event.penalty, event.redistributed), (penalty, penalty));
+    // None of it comes back to the leaver
+    assert_eq!(staking.balance(&leaver.tokens), 2 * STAKE - penalty);
+
+    staking.claim(&pool, &alice, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().gross, penalty / 4);
+    staking.claim(&pool, &bob, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().gross, 3 * penalty / 4);
+    assert_eq!(staking.balance(&pool.reward_vault()), 0);
+}
+
+#[test]
+fn pending_rewards_jump_by_exactly_the_penalty_split() {
+    let (mut staking, pool, [alice, bob, leaver]) = three_stakers();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(DAY / 2);
+
+    // Bring the accumulator to now without touching the two stayers
+    let other = staking.staker(&pool, 1);
+    staking.stake(&pool, &other, 0, 1, 1).unwrap();
+    let before = [pending(&staking, &pool, &alice), pending(&staking, &pool, &bob)];
+    let schedule = staking.pool_state(&pool);
+
+    staking.early_unstake(&pool, &leaver, 0).unwrap();
+    let penalty = staking.harness.event::<EarlyUnstaked>().penalty as u128;
+    let after = [pending(&staking, &pool, &alice), pending(&staking, &pool, &bob)];
+
+    // Split over the 4 * STAKE + 1 of weight left, rounded down per unit of weight
+    let per_weight = penalty * REWARD_PRECISION / (4 * STAKE as u128 + 1);
+    assert_eq!(after[0] - before[0], STAKE as u128 * per_weight);
+    assert_eq!(after[1] - before[1], 3 * STAKE as u128 * per_weight);
+
+    // Paid out at once rather than stretching the emission
+    let pool_state = staking.pool_state(&pool);
+    assert_eq!(pool_state.reward_rate, schedule.reward_rate);
+    assert_eq!(pool_state.period_finish, schedule.period_finish);
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{AnchorDeserialize, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{
+    accounts, instruction, PendingRewards, PendingRewardsComputed, RewardsClaimed, UnstakeWithdrawn,
+};
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+fn pending_ix(pool: &Pool, staker: &Staker, index: u16) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::GetPendingRewards {
+            stake_account: pool.stake_account(&staker.wallet, index),
+            stake_positions: pool.stake_positions(&staker.wallet),
+            stake_pool: pool.address(),
+            reward_vault: pool.reward_vault(),
+            mint: pool.mint,
+        }
+        .to_account_metas(None),
+        data: instruction::GetPendingRewards {}.data(),
+    }
+}
+
+/// What a client reads back from simulating `get_pending_rewards`.
+fn simulate_pending(staking: &mut Staking, pool: &Pool, staker: &Staker, index: u16) -> PendingRewards {
+    staking
+        .harness
+        .simulate(&[pending_ix(pool, staker, index)], &[])
+        .unwrap();
+    let (program_id, data) = staking.harness.return_data().unwrap();
+    assert_eq!(program_id, gaming_token::ID);
+    let pending = PendingRewards::try_from_slice(&data).unwrap();
+
+    let event = staking.harness.event::<PendingRewardsComputed>();
+    assert_eq!(event.stake_account, pool.stake_account(&staker.wallet, index));
+    assert_eq!(event.pending, pending.pending);
+    assert_eq!(event.claimable, pending.claimable);
+    assert_eq!(event.reward_debt, pending.reward_debt);
+    pending
+}
+
+fn running(positions: u16) -> (Staking, Pool, Staker) {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE * positions as u64);
+    for index in 0..positions {
+        staking.stake(&pool, &staker, index, STAKE, 1).unwrap();
+    }
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(DAY);
+    (staking, pool, staker)
+}
+
+#[test]
+fn the_simulated_value_is_what_the_next_claim_pays() {
+    let (mut staking, pool, staker) = running(1);
+
+    let pending = simulate_pending(&mut staking, &pool, &staker, 0);
+    assert_eq!(pending.pending, REWARDS / 100);
+    assert_eq!(pending.claimable, pending.pending);
+    assert_eq!(pending.reward_debt, 0);
+
+    staking.claim(&pool, &staker, 0).unwrap();
+    let claimed = staking.harness.event::<RewardsClaimed>();
+    assert_eq!(claimed.gross, pending.pending);
+    assert_eq!(claimed.net, pending.claimable);
+
+    let pending = simulate_pending(&mut staking, &pool, &staker, 0);
+    assert_eq!(pending.pending, 0);
+}
+
+#[test]
+fn computing_pending_rewards_changes_no_account() {
+    let (mut staking, pool, staker) = running(1);
+    let keys = [
+        pool.stake_account(&staker.wallet, 0),
+        pool.stake_positions(&staker.wallet),
+        pool.address(),
+        pool.reward_vault(),
+    ];
+    let before: Vec<_> = keys.iter().map(|key| staking.harness.account(key).cloned()).collect();
+
+    // Even committed for real, the instruction leaves everything as it was
+    let ix = pending_ix(&pool, &staker, 0);
+    staking.harness.process(&[ix], &[]).unwrap();
+
+    let after: Vec<_> = keys.iter().map(|key| staking.harness.account(key).cloned()).collect();
+    assert_eq!(before, after);
+}
+
+#[test]
+fn an_unbonding_position_reports_its_frozen_accrual() {
+    let (mut staking, pool, staker) = running(1);
+    let request = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::UpdateStakePosition {
+            stake_account: pool.stake_account(&staker.wallet, 0),
+            stake_pool: pool.address(),
+            receipt_token_account: None,
+            owner: staker.wallet,
+            mint: pool.mint,
+        }
+        .to_account_metas(None),
+        data: instruction::RequestUnstake {}.data(),
+    };
+    staking.harness.process(&[request], &[staker.wallet]).unwrap();
+
+    let at_request = simulate_pending(&mut staking, &pool, &staker, 0);
+    assert_eq!(at_request.pending, REWARDS / 100);
+    staking.harness.warp(DAY);
+    let later = simulate_pending(&mut staking, &pool, &staker, 0);
+    assert_eq!(later.pending, at_request.pending);
+
+    let withdraw = Instruction {
+        data: instruction::WithdrawUnstaked {}.data(),
+        ..staking.unstake_ix(&pool, &staker, 0)
+    };
+    staking.harness.process(&[withdraw], &[staker.wallet]).unwrap();
+    assert_eq!(staking.harness.event::<UnstakeWithdrawn>().reward, later.claimable);
+}
+
+#[test]
+fn reward_debt_is_reported_alongside_a_frozen_claim() {
+    let (mut staking, pool, staker) = running(2);
+    staking
+        .update_pool(&pool, instruction::SetEmergencyRewardsFreeze { frozen: true })
+        .unwrap();
+    staking.unstake(&pool, &staker, 0).unwrap();
+    let debt = staking.positions(&pool, &staker.wallet).reward_debt;
+    assert_eq!(debt, REWARDS / 200);
+
+    let frozen = simulate_pending(&mut staking, &pool, &staker, 1);
+    assert_eq!(frozen.pending, REWARDS / 200);
+    assert_eq!(frozen.claimable, 0);
+    assert_eq!(frozen.reward_debt, debt);
+
+    staking
+        .update_pool(&pool, instruction::SetEmergencyRewardsFreeze { frozen: false })
+        .unwrap();
+    let thawed = simulate_pending(&mut staking, &pool, &staker, 1);
+    assert_eq!(thawed.claimable, thawed.pending);
+    staking.claim(&pool, &staker, 1).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().net, thawed.claimable);
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{accounts, instruction, GamingTokenError};
+
+const STAKE: u64 = 1_000_000;
+const REWARDS: u64 = 86_400_000_000;
+
+/// Two pools, each with one staker and a funded reward period.
+fn two_pools() -> (Staking, [(Pool, Staker); 2]) {
+    let (mut staking, first) = Staking::with_pool();
+    let second = staking.single_token_pool(TOKEN, PoolParams::default());
+    let pools = [first, second].map(|pool| {
+        let staker = staking.staker(&pool, STAKE);
+        staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+        staking.notify(&pool, REWARDS).unwrap();
+        (pool, staker)
+    });
+    staking.harness.warp(DAY);
+    (staking, pools)
+}
+
+#[test]
+fn every_vault_and_signer_is_derived_from_the_staked_mint() {
+    let (staking, [(a, _), (b, _)]) = two_pools();
+
+    for key in [
+        Pool::address,
+        Pool::stake_vault,
+        Pool::reward_vault,
+        Pool::vault_authority,
+        Pool::reward_vault_authority,
+    ] {
+        assert_ne!(key(&a), key(&b));
+    }
+    let state = staking.pool_state(&a);
+    assert_eq!(state.total_staked, STAKE);
+    assert_eq!(staking.balance(&a.stake_vault()), STAKE);
+    assert_eq!(staking.balance(&b.stake_vault()), STAKE);
+}
+
+#[test]
+fn a_pool_cannot_unstake_out_of_another_pools_vault() {
+    let (mut staking, [(a, alice), (b, _)]) = two_pools();
+
+    let mut ix = staking.unstake_ix(&a, &alice, 0);
+    swap_account(&mut ix, a.stake_vault(), b.stake_vault());
+    assert_error(
+        staking.harness.process(&[ix.clone()], &[alice.wallet]),
+        ErrorCode::ConstraintSeeds,
+    );
+
+    // Bringing the other pool's signer along doesn't help
+    swap_account(&mut ix, a.vault_authority(), b.vault_authority());
+    assert_error(
+        staking.harness.process(&[ix], &[alice.wallet]),
+        ErrorCode::ConstraintSeeds,
+    );
+    assert_eq!(staking.balance(&b.stake_vault()), STAKE);
+}
+
+#[test]
+fn a_pool_cannot_pay_rewards_out_of_another_pools_vault() {
+    let (mut staking, [(a, alice), (b, _)]) = two_pools();
+    let other_rewards = staking.balance(&b.reward_vault());
+
+    let mut ix = staking.claim_ix(&a, &alice, 0);
+    swap_account(&mut ix, a.reward_vault(), b.reward_vault());
+    swap_account(&mut ix, a.reward_vault_authority(), b.reward_vault_authority());
+    assert_error(
+        staking.harness.process(&[ix], &[alice.wallet]),
+        ErrorCode::ConstraintSeeds,
+    );
+    assert_eq!(staking.balance(&b.reward_vault()), other_rewards);
+}
+
+#[test]
+fn draining_one_pool_leaves_the_other_whole() {
+    let (mut staking, [(a, alice), (b, bob)]) = two_pools();
+
+    staking.harness.set_time(staking.pool_state(&a).period_finish);
+    staking.unstake(&a, &alice, 0).unwrap();
+    assert_eq!(staking.balance(&a.stake_vault()), 0);
+    assert_eq!(staking.balance(&a.reward_vault()), 0);
+
+    assert_eq!(staking.balance(&b.stake_vault()), STAKE);
+    assert_eq!(staking.balance(&b.reward_vault()), REWARDS);
+    staking.unstake(&b, &bob, 0).unwrap();
+    assert_eq!(staking.balance(&bob.tokens), STAKE + REWARDS);
+}
+
+#[test]
+fn pool_creation_is_admin_only_until_opened_for_a_fee() {
+    let mut staking = Staking::new();
+    let creator = staking.harness.funded_wallet();
+    let fee_recipient = staking.harness.funded_wallet();
+    let mint = staking.create_mint(TOKEN);
+    let pool = Pool {
+        mint,
+        reward_mint: mint,
+        token_program: TOKEN,
+    };
+
+    let ix = staking.create_pool_ix(&pool, creator, PoolParams::default());
+    assert_error(
+        staking.harness.process(&[ix], &[creator]),
+        GamingTokenError::PoolCreationRestricted,
+    );
+
+    let fee = 500_000;
+    let open = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::UpdateStakingConfig {
+            staking_config: staking.staking_config(),
+            admin: staking.admin,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdatePoolCreation {
+            permissionless_pools: true,
+            pool_creation_fee: fee,
+            fee_recipient,
+        }
+        .data(),
+    };
+    staking.admin(open).unwrap();
+
+    let ix = staking.create_pool_ix(&pool, creator, PoolParams::default());
+    staking.harness.process(&[ix], &[creator]).unwrap();
+    assert_eq!(staking.harness.lamports(&fee_recipient), FUNDED_LAMPORTS + fee);
+
+    // The admin still creates pools for free
+    let admin_mint = staking.create_mint(TOKEN);
+    staking.create_pool(admin_mint, admin_mint, PoolParams::default()).unwrap();
+    assert_eq!(staking.harness.lamports(&fee_recipient), FUNDED_LAMPORTS + fee);
+}
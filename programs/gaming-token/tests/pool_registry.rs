@@ -0,0 +1,144 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{
+    accounts, instruction, GamingTokenError, PoolRegistryEntry, PoolRegistryPage, RegistryPageAllocated,
+    POOL_REGISTRY_PAGE_SIZE,
+};
+
+/// Every registered pool, walking pages from 0 until one is missing, the way
+/// a UI would without `getProgramAccounts`.
+fn list(staking: &Staking) -> Vec<PoolRegistryEntry> {
+    let mut entries = Vec::new();
+    for page in 0.. {
+        let key = staking.registry_page(page);
+        if staking.harness.account(&key).is_none() {
+            break;
+        }
+        let page: PoolRegistryPage = staking.harness.get(&key);
+        entries.extend(page.entries);
+    }
+    entries
+}
+
+fn allocate_page(staking: &mut Staking, page_index: u32) -> TxResult {
+    let payer = staking.harness.funded_wallet();
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::AllocateRegistryPage {
+            staking_config: staking.staking_config(),
+            registry_page: staking.registry_page(page_index),
+            payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::AllocateRegistryPage { page_index }.data(),
+    };
+    staking.harness.process(&[ix], &[payer])
+}
+
+fn set_deprecated_ix(staking: &Staking, pool: &Pool, page: u32, deprecated: bool) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::SetPoolDeprecated {
+            stake_pool: pool.address(),
+            registry_page: staking.registry_page(page),
+            authority: staking.admin,
+        }
+        .to_account_metas(None),
+        data: instruction::SetPoolDeprecated { deprecated }.data(),
+    }
+}
+
+fn pools(staking: &mut Staking, count: usize) -> Vec<Pool> {
+    (0..count)
+        .map(|_| staking.single_token_pool(TOKEN, PoolParams::default()))
+        .collect()
+}
+
+#[test]
+fn list_finds_every_pool_across_pages() {
+    let mut staking = Staking::new();
+    let mut created = pools(&mut staking, POOL_REGISTRY_PAGE_SIZE);
+
+    // The next pool needs the next page first
+    let mint = staking.create_mint(TOKEN);
+    assert_error(
+        staking.create_pool(mint, mint, PoolParams::default()).map(drop),
+        ErrorCode::AccountNotInitialized,
+    );
+    assert_error(allocate_page(&mut staking, 2), GamingTokenError::RegistryPageNotNext);
+    allocate_page(&mut staking, 1).unwrap();
+    assert_eq!(staking.harness.event::<RegistryPageAllocated>().page_index, 1);
+    // Allocated pages are never reset
+    assert!(allocate_page(&mut staking, 0).is_err());
+    created.push(staking.create_pool(mint, mint, PoolParams::default()).unwrap());
+
+    let page: PoolRegistryPage = staking.harness.get(&staking.registry_page(1));
+    assert_eq!(page.page_index, 1);
+    assert_eq!(page.entries.len(), 1);
+
+    let listed = list(&staking);
+    assert_eq!(listed.len(), created.len());
+    for (entry, pool) in listed.iter().zip(&created) {
+        assert_eq!(entry.pool, pool.address());
+        assert_eq!(entry.mint, pool.mint);
+        assert_eq!(entry.reward_mint, pool.reward_mint);
+        assert_eq!(entry.created_at, staking.pool_state(pool).created_at);
+        assert!(!entry.deprecated && !entry.closed);
+    }
+}
+
+#[test]
+fn a_dual_token_pool_lists_both_mints() {
+    let mut staking = Staking::new();
+    let mint = staking.create_mint(TOKEN);
+    let reward_mint = staking.create_mint(TOKEN);
+    let pool = staking.create_pool(mint, reward_mint, PoolParams::default()).unwrap();
+
+    let [entry] = list(&staking)[..] else {
+        panic!("one pool listed");
+    };
+    assert_eq!(entry.pool, pool.address());
+    assert_eq!((entry.mint, entry.reward_mint), (mint, reward_mint));
+}
+
+#[test]
+fn deprecating_a_pool_marks_it_in_place() {
+    let mut staking = Staking::new();
+    let created = pools(&mut staking, 3);
+
+    let ix = set_deprecated_ix(&staking, &created[1], 0, true);
+    staking.admin(ix).unwrap();
+
+    let listed = list(&staking);
+    assert_eq!(listed.len(), 3);
+    let flags: Vec<_> = listed.iter().map(|entry| entry.deprecated).collect();
+    assert_eq!(flags, [false, true, false]);
+    assert_eq!(listed[1].pool, created[1].address());
+
+    // The pool keeps working and can be listed again
+    let staker = staking.staker(&created[1], 1);
+    staking.stake(&created[1], &staker, 0, 1, 1).unwrap();
+    let ix = set_deprecated_ix(&staking, &created[1], 0, false);
+    staking.admin(ix).unwrap();
+    assert!(!list(&staking)[1].deprecated);
+}
+
+#[test]
+fn only_the_pool_authority_deprecates_it() {
+    let mut staking = Staking::new();
+    let created = pools(&mut staking, 1);
+    let stranger = staking.harness.funded_wallet();
+
+    let mut ix = set_deprecated_ix(&staking, &created[0], 0, true);
+    swap_account(&mut ix, staking.admin, stranger);
+    assert_error(
+        staking.harness.process(&[ix], &[stranger]),
+        ErrorCode::ConstraintHasOne,
+    );
+    assert!(!list(&staking)[0].deprecated);
+}
@@ -0,0 +1,237 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{
+    accounts, instruction, GamingTokenError, PositionMigrated, SunsetEscrowClaimed, SunsetSwept,
+};
+
+const STAKE: u64 = 1_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+const BONUS_BPS: u16 = 1_000;
+
+/// An old pool with one locked staker, sunsetting into a fresh successor
+/// over ten days with a funded migration vault.
+struct Sunset {
+    staking: Staking,
+    old: Pool,
+    successor: Pool,
+    staker: Staker,
+    deadline: i64,
+}
+
+impl Sunset {
+    fn new() -> Self {
+        let (mut staking, old) = Staking::with_pool();
+        let successor = staking.single_token_pool(TOKEN, PoolParams::default());
+        let staker = staking.staker(&old, STAKE);
+        staking.stake(&old, &staker, 0, STAKE, 30 * DAY).unwrap();
+        staking.notify(&old, REWARDS).unwrap();
+        staking.harness.warp(DAY);
+
+        let deadline = staking.harness.now() + 10 * DAY;
+        let mut sunset = Self {
+            staking,
+            old,
+            successor,
+            staker,
+            deadline,
+        };
+        let ix = Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::BeginSunset {
+                stake_pool: old.address(),
+                successor_pool: successor.address(),
+                successor_mint: successor.mint,
+                migration_vault: sunset.migration_vault(),
+                vault_authority: old.vault_authority(),
+                mint: old.mint,
+                authority: sunset.staking.admin,
+                token_program: TOKEN,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::BeginSunset {
+                deadline,
+                migration_bonus_bps: BONUS_BPS,
+            }
+            .data(),
+        };
+        sunset.staking.admin(ix).unwrap();
+        sunset
+    }
+
+    fn migration_vault(&self) -> Pubkey {
+        pda(&[b"migration_vault", self.old.mint.as_ref()])
+    }
+
+    fn sunset_escrow(&self) -> Pubkey {
+        pda(&[b"sunset_escrow", self.old.mint.as_ref()])
+    }
+
+    fn fund_migration(&mut self, amount: u64) {
+        let vault = self.migration_vault();
+        self.staking.give(&self.successor.mint, &vault, amount);
+    }
+
+    fn migrate(&mut self, staker: &Staker) -> TxResult {
+        let ix = Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::MigratePosition {
+                stake_account: self.old.stake_account(&staker.wallet, 0),
+                stake_pool: self.old.address(),
+                stake_positions: self.old.stake_positions(&staker.wallet),
+                stake_vault: self.old.stake_vault(),
+                vault_authority: self.old.vault_authority(),
+                reward_vault: self.old.reward_vault(),
+                reward_vault_authority: self.old.reward_vault_authority(),
+                fee_vault: None,
+                user_reward_token_account: staker.rewards,
+                mint: self.old.mint,
+                reward_mint: self.old.reward_mint,
+                successor_pool: self.successor.address(),
+                successor_stake_vault: self.successor.stake_vault(),
+                migration_vault: self.migration_vault(),
+                successor_positions: self.successor.stake_positions(&staker.wallet),
+                new_stake_account: self.successor.stake_account(&staker.wallet, 0),
+                successor_mint: self.successor.mint,
+                owner: staker.wallet,
+                token_program: TOKEN,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::MigratePosition { new_position_index: 0 }.data(),
+        };
+        self.staking.harness.process(&[ix], &[staker.wallet])
+    }
+
+    fn sweep(&mut self) -> TxResult {
+        let payer = self.staking.harness.funded_wallet();
+        let ix = Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::SweepSunset {
+                stake_pool: self.old.address(),
+                stake_vault: self.old.stake_vault(),
+                sunset_escrow: self.sunset_escrow(),
+                vault_authority: self.old.vault_authority(),
+                mint: self.old.mint,
+                payer,
+                token_program: TOKEN,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::SweepSunset {}.data(),
+        };
+        self.staking.harness.process(&[ix], &[payer])
+    }
+
+    fn claim_escrow(&mut self, staker: &Staker) -> TxResult {
+        let ix = Instruction {
+            program_id: gaming_token::ID,
+            accounts: accounts::ClaimSunsetEscrow {
+                stake_account: self.old.stake_account(&staker.wallet, 0),
+                stake_positions: self.old.stake_positions(&staker.wallet),
+                sunset_escrow: self.sunset_escrow(),
+                vault_authority: self.old.vault_authority(),
+                receipt_token_account: None,
+                holder_token_account: staker.tokens,
+                holder: staker.wallet,
+                mint: self.old.mint,
+                token_program: TOKEN,
+            }
+            .to_account_metas(None),
+            data: instruction::ClaimSunsetEscrow {}.data(),
+        };
+        self.staking.harness.process(&[ix], &[staker.wallet])
+    }
+}
+
+#[test]
+fn migrating_before_the_deadline_moves_the_position_intact() {
+    let mut sunset = Sunset::new();
+    sunset.fund_migration(2 * STAKE);
+    let staker = sunset.staker;
+    let lock_until = sunset.staking.position(&sunset.old, &staker.wallet, 0).lock_until;
+    let old_supply = sunset.staking.harness.mint(&sunset.old.mint).supply;
+
+    sunset.staking.harness.set_time(sunset.deadline - 1);
+    sunset.migrate(&staker).unwrap();
+
+    let migrated = sunset.staking.harness.event::<PositionMigrated>();
+    let bonus = STAKE * BONUS_BPS as u64 / 10_000;
+    assert_eq!((migrated.burned, migrated.bonus, migrated.migrated), (STAKE, bonus, STAKE + bonus));
+    // Rewards up to the sunset were settled and paid in the old token
+    assert_eq!(migrated.rewards_paid, REWARDS / 100 * 11 - REWARDS / 100 / DAY as u64);
+    assert_eq!(sunset.staking.balance(&staker.rewards), migrated.rewards_paid);
+
+    let position = sunset.staking.position(&sunset.successor, &staker.wallet, 0);
+    assert_eq!(position.amount, STAKE + bonus);
+    assert_eq!(position.lock_until, lock_until);
+    assert_eq!(sunset.staking.pool_state(&sunset.successor).total_staked, STAKE + bonus);
+    assert_eq!(sunset.staking.balance(&sunset.successor.stake_vault()), STAKE + bonus);
+
+    assert!(sunset.staking.harness.account(&sunset.old.stake_account(&staker.wallet, 0)).is_none());
+    assert_eq!(sunset.staking.pool_state(&sunset.old).total_staked, 0);
+    assert_eq!(sunset.staking.harness.mint(&sunset.old.mint).supply, old_supply - STAKE);
+}
+
+#[test]
+fn migrating_after_the_deadline_leaves_only_the_escrow() {
+    let mut sunset = Sunset::new();
+    sunset.fund_migration(2 * STAKE);
+    let staker = sunset.staker;
+
+    assert_error(sunset.sweep(), GamingTokenError::SunsetNotOver);
+    sunset.staking.harness.set_time(sunset.deadline);
+    assert_error(sunset.migrate(&staker), GamingTokenError::SunsetDeadlinePassed);
+
+    sunset.sweep().unwrap();
+    let swept = sunset.staking.harness.event::<SunsetSwept>();
+    assert_eq!((swept.unmigrated, swept.escrowed, swept.positions), (STAKE, STAKE, 1));
+    assert_eq!(sunset.staking.balance(&sunset.sunset_escrow()), STAKE);
+    assert_eq!(sunset.staking.balance(&sunset.old.stake_vault()), 0);
+    // The escrow already exists, so a second sweep can't even allocate it
+    assert!(sunset.sweep().is_err());
+
+    // Principal only, out of the escrow
+    let balance = sunset.staking.balance(&staker.tokens);
+    sunset.claim_escrow(&staker).unwrap();
+    assert_eq!(sunset.staking.harness.event::<SunsetEscrowClaimed>().amount, STAKE);
+    assert_eq!(sunset.staking.balance(&staker.tokens), balance + STAKE);
+    assert!(sunset.staking.harness.account(&sunset.old.stake_account(&staker.wallet, 0)).is_none());
+    assert!(sunset.staking.harness.account(&sunset.successor.stake_account(&staker.wallet, 0)).is_none());
+}
+
+#[test]
+fn a_sunsetting_pool_takes_no_stake_and_waives_locks() {
+    let mut sunset = Sunset::new();
+    let newcomer = sunset.staking.staker(&sunset.old, STAKE);
+    assert_error(
+        sunset.staking.stake(&sunset.old, &newcomer, 0, STAKE, 1),
+        GamingTokenError::PoolPaused,
+    );
+    assert_error(
+        sunset
+            .staking
+            .update_pool(&sunset.old, instruction::SetPoolPaused { paused: false }),
+        GamingTokenError::PoolSunset,
+    );
+
+    // Locked for another 29 days, but free to leave instead of migrating
+    let staker = sunset.staker;
+    sunset.staking.unstake(&sunset.old, &staker, 0).unwrap();
+    assert!(sunset.staking.balance(&staker.tokens) > STAKE);
+}
+
+#[test]
+fn an_underfunded_migration_vault_fails_the_migration() {
+    let mut sunset = Sunset::new();
+    sunset.fund_migration(STAKE);
+    let staker = sunset.staker;
+
+    assert_error(sunset.migrate(&staker), GamingTokenError::MigrationVaultUnderfunded);
+    assert_eq!(sunset.staking.position(&sunset.old, &staker.wallet, 0).amount, STAKE);
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{
+    accounts, instruction, GamingTokenError, PoolRateSnapshot, RATE_HISTORY_LEN, RATE_SNAPSHOT_INTERVAL_SECONDS,
+};
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+/// `snapshot_pool_rate`, signed by a fresh wallet since anyone may call it.
+fn snapshot(staking: &mut Staking, pool: &Pool) -> TxResult {
+    let caller = staking.harness.funded_wallet();
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::SnapshotPoolRate {
+            stake_pool: pool.address(),
+        }
+        .to_account_metas(None),
+        data: instruction::SnapshotPoolRate {}.data(),
+    };
+    staking.harness.process(&[ix], &[caller])
+}
+
+#[test]
+fn rate_changes_and_snapshots_record_the_rate_in_effect() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    let state = staking.pool_state(&pool);
+    let entry = state.rate_history[0];
+    assert_eq!(state.rate_history.len(), 1);
+    assert_eq!(entry.timestamp, staking.harness.now());
+    assert_eq!((entry.reward_rate, entry.total_staked), (state.reward_rate, STAKE));
+
+    assert_error(snapshot(&mut staking, &pool), GamingTokenError::SnapshotTooSoon);
+    staking.harness.warp(RATE_SNAPSHOT_INTERVAL_SECONDS);
+    snapshot(&mut staking, &pool).unwrap();
+    assert_eq!(staking.harness.event::<PoolRateSnapshot>().reward_rate, state.reward_rate);
+
+    // Past the period nothing is emitted any more
+    staking.harness.set_time(state.period_finish);
+    snapshot(&mut staking, &pool).unwrap();
+    let latest = staking.harness.event::<PoolRateSnapshot>();
+    assert_eq!((latest.reward_rate, latest.total_staked), (0, STAKE));
+    assert_eq!(staking.pool_state(&pool).rate_history.len(), 3);
+}
+
+#[test]
+fn a_full_history_overwrites_its_oldest_entry() {
+    let (mut staking, pool) = Staking::with_pool();
+    let start = staking.harness.now();
+    for _ in 0..RATE_HISTORY_LEN + 2 {
+        snapshot(&mut staking, &pool).unwrap();
+        staking.harness.warp(RATE_SNAPSHOT_INTERVAL_SECONDS);
+    }
+
+    let state = staking.pool_state(&pool);
+    assert_eq!(state.rate_history.len(), RATE_HISTORY_LEN);
+    assert_eq!(state.rate_history_next, 2);
+    let hour = |n: usize| start + n as i64 * RATE_SNAPSHOT_INTERVAL_SECONDS;
+    assert_eq!(state.rate_history[1].timestamp, hour(RATE_HISTORY_LEN + 1));
+    assert_eq!(state.rate_history[2].timestamp, hour(2));
+    assert_eq!(state.latest_rate().unwrap().timestamp, hour(RATE_HISTORY_LEN + 1));
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{instruction::Instruction, sysvar};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{accounts, instruction, GamingTokenError, Referral, RewardsClaimed, MAX_REFERRAL_BPS};
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+fn referral(pool: &Pool, referee: &Pubkey) -> Pubkey {
+    pda(&[b"referral", pool.mint.as_ref(), referee.as_ref()])
+}
+
+/// A first stake at index 0 naming `referrer`.
+fn referred_stake_ix(pool: &Pool, staker: &Staker, referrer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::StakeTokens {
+            stake_account: pool.stake_account(&staker.wallet, 0),
+            stake_positions: pool.stake_positions(&staker.wallet),
+            stake_pool: pool.address(),
+            stake_vault: pool.stake_vault(),
+            user_token_account: staker.tokens,
+            mint: pool.mint,
+            receipt_mint: None,
+            receipt_token_account: None,
+            receipt_authority: None,
+            referral: Some(referral(pool, &staker.wallet)),
+            authority: staker.wallet,
+            staker_stats: pool.staker_stats(&staker.wallet),
+            system_program: system_program::ID,
+            token_program: pool.token_program,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::StakeTokens {
+            position_index: 0,
+            amount: STAKE,
+            lock_period: 1,
+            with_receipt: false,
+            referrer: Some(referrer),
+        }
+        .data(),
+    }
+}
+
+/// `Staking::claim_ix` paying the referral bonus to `referrer`.
+fn referred_claim_ix(pool: &Pool, staker: &Staker, referrer: &Staker) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::ClaimRewards {
+            stake_account: pool.stake_account(&staker.wallet, 0),
+            stake_pool: pool.address(),
+            reward_vault: pool.reward_vault(),
+            reward_vault_authority: pool.reward_vault_authority(),
+            fee_vault: None,
+            user_reward_token_account: staker.rewards,
+            receipt_token_account: None,
+            receipt_mint: None,
+            owner: staker.wallet,
+            staker_stats: pool.staker_stats(&staker.wallet),
+            referral: Some(referral(pool, &staker.wallet)),
+            referrer_token_account: Some(referrer.rewards),
+            player_stats: None,
+            mint: pool.mint,
+            reward_mint: pool.reward_mint,
+            token_program: pool.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::ClaimRewards {}.data(),
+    }
+}
+
+/// Adds `amount` to the pool's reward reserve without starting a period.
+fn fund(staking: &mut Staking, pool: &Pool, amount: u64) {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::FundRewards {
+            stake_pool: pool.address(),
+            reward_vault: pool.reward_vault(),
+            funder_token_account: staking.admin_tokens(&pool.reward_mint),
+            funder: staking.admin,
+            reward_mint: pool.reward_mint,
+            token_program: pool.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::FundRewards { amount }.data(),
+    };
+    staking.admin(ix).unwrap();
+}
+
+/// Stakes a referred staker through a full period with `surplus` of unowed
+/// reserve, then claims and returns the event.
+fn claim_referred(surplus: u64) -> (RewardsClaimed, u64) {
+    let (mut staking, pool) = Staking::with_pool();
+    staking
+        .update_pool(&pool, instruction::UpdateReferralBonus { referral_bps: MAX_REFERRAL_BPS })
+        .unwrap();
+    let referrer = staking.staker(&pool, 0);
+    let staker = staking.staker(&pool, STAKE);
+    let ix = referred_stake_ix(&pool, &staker, referrer.wallet);
+    staking.harness.process(&[ix], &[staker.wallet]).unwrap();
+    let recorded = staking.harness.get::<Referral>(&referral(&pool, &staker.wallet));
+    assert_eq!((recorded.referee, recorded.referrer), (staker.wallet, referrer.wallet));
+
+    if surplus > 0 {
+        fund(&mut staking, &pool, surplus);
+    }
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.set_time(staking.pool_state(&pool).period_finish);
+    let ix = referred_claim_ix(&pool, &staker, &referrer);
+    staking.harness.process(&[ix], &[staker.wallet]).unwrap();
+
+    let claimed = staking.harness.event::<RewardsClaimed>();
+    assert_eq!(claimed.referrer, Some(referrer.wallet));
+    assert_eq!(staking.balance(&referrer.rewards), claimed.referral_bonus);
+    (claimed, staking.balance(&pool.reward_vault()))
+}
+
+#[test]
+fn the_referrer_is_paid_on_top_of_the_claim() {
+    let (claimed, vault) = claim_referred(REWARDS);
+    assert_eq!(claimed.net, REWARDS);
+    assert_eq!(claimed.referral_bonus, REWARDS / 10);
+    assert_eq!(vault, REWARDS - REWARDS / 10);
+}
+
+#[test]
+fn the_bonus_only_spends_reserve_nobody_is_owed() {
+    // Nothing spare: the referee still gets the whole claim
+    let (claimed, vault) = claim_referred(0);
+    assert_eq!(claimed.net, REWARDS);
+    assert_eq!(claimed.referral_bonus, 0);
+    assert_eq!(vault, 0);
+
+    let (claimed, vault) = claim_referred(REWARDS / 20);
+    assert_eq!(claimed.referral_bonus, REWARDS / 20);
+    assert_eq!(vault, 0);
+}
+
+#[test]
+fn only_a_first_stake_by_someone_else_can_be_referred() {
+    let (mut staking, pool) = Staking::with_pool();
+    assert_error(
+        staking.update_pool(&pool, instruction::UpdateReferralBonus { referral_bps: MAX_REFERRAL_BPS + 1 }),
+        GamingTokenError::InvalidReferralBonus,
+    );
+    let referrer = staking.staker(&pool, 0);
+    let staker = staking.staker(&pool, 2 * STAKE);
+
+    let ix = referred_stake_ix(&pool, &staker, staker.wallet);
+    assert_error(
+        staking.harness.process(&[ix], &[staker.wallet]),
+        GamingTokenError::SelfReferral,
+    );
+
+    // A referrer without the referral account to record it in
+    let mut ix = staking.stake_ix(&pool, &staker, 0, STAKE, 1);
+    ix.data = instruction::StakeTokens {
+        position_index: 0,
+        amount: STAKE,
+        lock_period: 1,
+        with_receipt: false,
+        referrer: Some(referrer.wallet),
+    }
+    .data();
+    assert_error(
+        staking.harness.process(&[ix], &[staker.wallet]),
+        GamingTokenError::ReferralAccountsRequired,
+    );
+
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    let mut ix = referred_stake_ix(&pool, &staker, referrer.wallet);
+    swap_account(&mut ix, pool.stake_account(&staker.wallet, 0), pool.stake_account(&staker.wallet, 1));
+    ix.data = instruction::StakeTokens {
+        position_index: 1,
+        amount: STAKE,
+        lock_period: 1,
+        with_receipt: false,
+        referrer: Some(referrer.wallet),
+    }
+    .data();
+    assert_error(
+        staking.harness.process(&[ix], &[staker.wallet]),
+        GamingTokenError::AlreadyStaked,
+    );
+}
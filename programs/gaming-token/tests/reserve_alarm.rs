@@ -0,0 +1,89 @@
+mod common;
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{accounts, instruction, RewardReserveLow, RewardReserveRecovered};
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+/// `REWARDS` over 100 days.
+const RATE: u64 = 10_000;
+
+fn fund(staking: &mut Staking, pool: &Pool, amount: u64) {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::FundRewards {
+            stake_pool: pool.address(),
+            reward_vault: pool.reward_vault(),
+            funder_token_account: staking.admin_tokens(&pool.reward_mint),
+            funder: staking.admin,
+            reward_mint: pool.reward_mint,
+            token_program: pool.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::FundRewards { amount }.data(),
+    };
+    staking.admin(ix).unwrap();
+}
+
+#[test]
+fn the_alarm_fires_once_per_crossing_and_funding_clears_it() {
+    let (mut staking, pool) = Staking::with_pool();
+    staking
+        .update_pool(&pool, instruction::SetLowReserveThreshold { threshold: REWARDS / 2 })
+        .unwrap();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+
+    // Half the reserve paid out is still on the threshold
+    staking.harness.warp(50 * DAY);
+    staking.claim(&pool, &staker, 0).unwrap();
+    assert!(staking.harness.events::<RewardReserveLow>().is_empty());
+
+    staking.harness.warp(25 * DAY);
+    staking.claim(&pool, &staker, 0).unwrap();
+    let low = staking.harness.event::<RewardReserveLow>();
+    assert_eq!((low.vault_balance, low.reward_reserve), (REWARDS / 4, REWARDS / 4));
+    assert_eq!(low.threshold, REWARDS / 2);
+    assert_eq!(low.runway_seconds, Some((REWARDS / 4 / RATE) as i64));
+    assert!(staking.pool_state(&pool).reserve_low);
+
+    staking.harness.warp(DAY);
+    staking.claim(&pool, &staker, 0).unwrap();
+    assert!(staking.harness.events::<RewardReserveLow>().is_empty());
+
+    // Not enough to lift it back over the threshold
+    fund(&mut staking, &pool, REWARDS / 8);
+    assert!(staking.harness.events::<RewardReserveRecovered>().is_empty());
+    fund(&mut staking, &pool, REWARDS / 2);
+    let recovered = staking.harness.event::<RewardReserveRecovered>();
+    assert!(recovered.vault_balance >= REWARDS / 2);
+    assert!(!staking.pool_state(&pool).reserve_low);
+}
+
+#[test]
+fn no_threshold_means_no_alarm_and_no_emission_means_no_runway() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(100 * DAY);
+    staking.claim(&pool, &staker, 0).unwrap();
+    assert!(staking.harness.events::<RewardReserveLow>().is_empty());
+
+    let (mut staking, pool) = Staking::with_pool();
+    staking
+        .update_pool(&pool, instruction::SetLowReserveThreshold { threshold: 1 })
+        .unwrap();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(100 * DAY);
+    staking.claim(&pool, &staker, 0).unwrap();
+    let low = staking.harness.event::<RewardReserveLow>();
+    assert_eq!((low.vault_balance, low.reward_reserve), (0, 0));
+    assert_eq!(low.runway_seconds, None);
+}
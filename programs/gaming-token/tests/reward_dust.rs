@@ -0,0 +1,80 @@
+mod common;
+
+use common::*;
+use gaming_token::{RewardsClaimed, REWARD_PRECISION};
+
+/// One token a second over the default 100-day period.
+const REWARDS: u64 = 8_640_000;
+
+/// Alice stakes one base unit and Bob three, so every second Alice earns a
+/// quarter of a token and Bob three quarters.
+fn quarters() -> (Staking, Pool, Staker, Staker, i64) {
+    let (mut staking, pool) = Staking::with_pool();
+    let alice = staking.staker(&pool, 1);
+    let bob = staking.staker(&pool, 3);
+    staking.stake(&pool, &alice, 0, 1, 1).unwrap();
+    staking.stake(&pool, &bob, 0, 3, 1).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    assert_eq!(staking.pool_state(&pool).reward_rate, 1);
+    let start = staking.harness.now();
+    (staking, pool, alice, bob, start)
+}
+
+fn claim_at(staking: &mut Staking, pool: &Pool, staker: &Staker, at: i64) -> u64 {
+    staking.harness.set_time(at);
+    staking.claim(pool, staker, 0).unwrap();
+    staking.harness.event::<RewardsClaimed>().gross
+}
+
+fn dust(staking: &Staking, pool: &Pool, staker: &Staker) -> u128 {
+    staking.position(pool, &staker.wallet, 0).reward_dust as u128
+}
+
+#[test]
+fn the_fraction_left_behind_is_carried_not_dropped() {
+    let (mut staking, pool, alice, _, start) = quarters();
+
+    // 1.25 earned: one paid, a quarter carried
+    assert_eq!(claim_at(&mut staking, &pool, &alice, start + 5), 1);
+    assert_eq!(dust(&staking, &pool, &alice), REWARD_PRECISION / 4);
+
+    // 1.5 more plus the quarter: one paid, three quarters carried
+    assert_eq!(claim_at(&mut staking, &pool, &alice, start + 11), 1);
+    assert_eq!(dust(&staking, &pool, &alice), 3 * REWARD_PRECISION / 4);
+
+    // A single second's quarter completes the carried token
+    assert_eq!(claim_at(&mut staking, &pool, &alice, start + 12), 1);
+    assert_eq!(dust(&staking, &pool, &alice), 0);
+}
+
+#[test]
+fn awkward_claims_pay_out_exactly_what_was_emitted() {
+    let (mut staking, pool, alice, bob, start) = quarters();
+    let vault = staking.balance(&pool.reward_vault());
+
+    let mut paid = 0;
+    for (staker, at) in [(&alice, 5), (&bob, 7), (&bob, 9), (&alice, 11), (&alice, 12), (&bob, 12)] {
+        paid += claim_at(&mut staking, &pool, staker, start + at);
+    }
+
+    // Twelve seconds at a token a second, every fraction settled
+    assert_eq!(paid, 12);
+    assert_eq!(dust(&staking, &pool, &alice), 0);
+    assert_eq!(dust(&staking, &pool, &bob), 0);
+    assert_eq!(staking.balance(&alice.rewards), 3);
+    assert_eq!(staking.balance(&bob.rewards), 9);
+    assert_eq!(staking.balance(&pool.reward_vault()), vault - 12);
+}
+
+#[test]
+fn rounding_never_pays_ahead_of_what_was_earned() {
+    let (mut staking, pool, alice, bob, start) = quarters();
+
+    // At an odd second both hold a fraction; neither is rounded up
+    let alice_paid = claim_at(&mut staking, &pool, &alice, start + 7);
+    let bob_paid = claim_at(&mut staking, &pool, &bob, start + 7);
+    assert_eq!((alice_paid, bob_paid), (1, 5));
+
+    let carried = dust(&staking, &pool, &alice) + dust(&staking, &pool, &bob);
+    assert_eq!((alice_paid + bob_paid) as u128 * REWARD_PRECISION + carried, 7 * REWARD_PRECISION);
+}
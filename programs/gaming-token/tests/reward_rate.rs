@@ -0,0 +1,73 @@
+mod common;
+
+use common::*;
+use gaming_token::{GamingTokenError, PoolUpdate, RewardsClaimed, RewardsNotified};
+
+const STAKE: u64 = 1_000_000_000;
+/// 10,000 a second over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+fn claimed(staking: &mut Staking, pool: &Pool, staker: &Staker) -> u64 {
+    staking.claim(pool, staker, 0).unwrap();
+    staking.harness.event::<RewardsClaimed>().net
+}
+
+#[test]
+fn a_mid_period_top_up_only_changes_the_rate_from_then_on() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    assert_eq!(staking.harness.event::<RewardsNotified>().reward_rate, 10_000);
+
+    // Half of the first period left over plus three halves more, over a fresh period
+    staking.harness.warp(50 * DAY);
+    staking.notify(&pool, REWARDS / 2 * 3).unwrap();
+    let notified = staking.harness.event::<RewardsNotified>();
+    assert_eq!(notified.reward_rate, 20_000);
+    assert_eq!(notified.period_finish, staking.harness.now() + 100 * DAY);
+
+    // Everything before the top-up was earned at the old rate
+    assert_eq!(claimed(&mut staking, &pool, &staker), REWARDS / 2);
+    staking.harness.warp(10 * DAY);
+    assert_eq!(claimed(&mut staking, &pool, &staker), 20_000 * 10 * DAY as u64);
+}
+
+#[test]
+fn the_reward_duration_changes_between_periods_after_the_delay() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+
+    let update = PoolUpdate {
+        reward_duration: Some(50 * DAY),
+        emission_schedule: None,
+        claim_fee_bps: None,
+    };
+    let delay = 2 * DAY;
+    let ix = staking.queue_pool_update_ix(&pool, update.clone(), staking.harness.now() + delay - 1);
+    assert_error(staking.admin(ix), GamingTokenError::UpdateTooSoon);
+    let ix = staking.queue_pool_update_ix(&pool, update, staking.harness.now() + delay);
+    staking.admin(ix).unwrap();
+
+    let ix = staking.execute_pool_update_ix(&pool);
+    assert_error(staking.admin(ix), GamingTokenError::UpdateNotReady);
+
+    // Due, but the running period keeps the length it was funded for
+    staking.harness.warp(delay);
+    let ix = staking.execute_pool_update_ix(&pool);
+    assert_error(staking.admin(ix), GamingTokenError::RewardPeriodActive);
+
+    staking.harness.set_time(staking.pool_state(&pool).period_finish);
+    let ix = staking.execute_pool_update_ix(&pool);
+    staking.admin(ix).unwrap();
+    assert_eq!(staking.pool_state(&pool).reward_duration, 50 * DAY);
+    assert!(staking.harness.account(&staking.pool_update(&pool)).is_none());
+    assert_eq!(claimed(&mut staking, &pool, &staker), REWARDS);
+
+    staking.notify(&pool, REWARDS).unwrap();
+    assert_eq!(staking.harness.event::<RewardsNotified>().reward_rate, 20_000);
+    staking.harness.warp(DAY);
+    assert_eq!(claimed(&mut staking, &pool, &staker), 20_000 * DAY as u64);
+}
@@ -0,0 +1,195 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, program_pack::Pack, sysvar};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use common::*;
+use gaming_token::{accounts, instruction, GamingTokenError, RewardTokenRemoved, SlotRewardsClaimed};
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+/// A partner reward token in a pool slot: its mint and vault.
+#[derive(Clone, Copy)]
+struct Slot {
+    index: u8,
+    mint: Pubkey,
+    vault: Pubkey,
+}
+
+fn add_reward_token_ix(staking: &Staking, pool: &Pool, reward_mint: Pubkey) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::AddRewardToken {
+            stake_pool: pool.address(),
+            reward_mint,
+            slot_vault: pda(&[b"reward_slot_vault", pool.mint.as_ref(), reward_mint.as_ref()]),
+            reward_vault_authority: pool.reward_vault_authority(),
+            authority: staking.admin,
+            system_program: system_program::ID,
+            token_program: TOKEN,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::AddRewardToken {
+            reward_duration: PoolParams::default().reward_duration,
+        }
+        .data(),
+    }
+}
+
+/// Adds a fresh mint as the pool's next partner slot and funds a period of `REWARDS`.
+fn partner_slot(staking: &mut Staking, pool: &Pool) -> Slot {
+    let mint = staking.create_mint(TOKEN);
+    let ix = add_reward_token_ix(staking, pool, mint);
+    staking.admin(ix).unwrap();
+    let slot = Slot {
+        index: staking.pool_state(pool).reward_slots.len() as u8,
+        mint,
+        vault: pda(&[b"reward_slot_vault", pool.mint.as_ref(), mint.as_ref()]),
+    };
+    notify_slot(staking, pool, &slot).unwrap();
+    slot
+}
+
+fn notify_slot(staking: &mut Staking, pool: &Pool, slot: &Slot) -> TxResult {
+    let mut ix = staking.notify_ix(pool, REWARDS);
+    swap_account(&mut ix, pool.reward_vault(), slot.vault);
+    swap_account(&mut ix, staking.admin_tokens(&pool.reward_mint), staking.admin_tokens(&slot.mint));
+    swap_account(&mut ix, pool.reward_mint, slot.mint);
+    ix.data = instruction::NotifyRewardAmount {
+        slot_index: slot.index,
+        amount: REWARDS,
+    }
+    .data();
+    staking.admin(ix)
+}
+
+fn remove_ix(staking: &Staking, pool: &Pool, slot_index: u8, slot: &Slot) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::RemoveRewardToken {
+            stake_pool: pool.address(),
+            slot_vault: slot.vault,
+            reward_vault_authority: pool.reward_vault_authority(),
+            authority_token_account: staking.admin_tokens(&slot.mint),
+            authority: staking.admin,
+            reward_mint: slot.mint,
+            token_program: TOKEN,
+        }
+        .to_account_metas(None),
+        data: instruction::RemoveRewardToken { slot_index }.data(),
+    }
+}
+
+/// Claims the pool's reward plus each slot in `slots` into `payouts`.
+fn claim_slots(staking: &mut Staking, pool: &Pool, staker: &Staker, slots: &[(Slot, Pubkey)]) -> TxResult {
+    let mut ix = staking.claim_ix(pool, staker, 0);
+    for (slot, payout) in slots {
+        ix.accounts.extend([
+            AccountMeta::new(slot.vault, false),
+            AccountMeta::new_readonly(slot.mint, false),
+            AccountMeta::new(*payout, false),
+        ]);
+    }
+    staking.harness.process(&[ix], &[staker.wallet])
+}
+
+/// Rewrites the balance of token account `key`, as a vault short of what it owes.
+fn set_balance(staking: &mut Staking, key: &Pubkey, amount: u64) {
+    let mut token_account = staking.harness.token_account(key);
+    token_account.amount = amount;
+    let mut state = staking.harness.account(key).unwrap().clone();
+    spl_token::state::Account::pack(token_account, &mut state.data).unwrap();
+    staking.harness.set_account(*key, state);
+}
+
+#[test]
+fn an_empty_slot_vault_does_not_hold_up_the_other_tokens() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    let [empty, funded] = [(); 2].map(|_| partner_slot(&mut staking, &pool));
+    let payouts = [empty, funded].map(|slot| (slot, staking.harness.create_token_account(&slot.mint, &staker.wallet)));
+
+    staking.harness.warp(PoolParams::default().reward_duration / 2);
+    set_balance(&mut staking, &empty.vault, 0);
+    claim_slots(&mut staking, &pool, &staker, &payouts).unwrap();
+
+    let claimed = staking.harness.events::<SlotRewardsClaimed>();
+    assert_eq!(claimed.len(), 1);
+    assert_eq!((claimed[0].slot, claimed[0].amount), (funded.index, REWARDS / 2));
+    assert_eq!(staking.balance(&staker.rewards), REWARDS / 2);
+    assert_eq!(staking.balance(&payouts[1].1), REWARDS / 2);
+    assert_eq!(staking.balance(&payouts[0].1), 0);
+
+    // What the empty vault couldn't pay stays owed until it is refilled
+    let position = staking.position(&pool, &staker.wallet, 0);
+    assert_eq!(position.slot_rewards[0].rewards_accrued, REWARDS / 2);
+    let admin = staking.admin;
+    staking.harness.mint_to(&empty.mint, &empty.vault, &admin, REWARDS);
+    claim_slots(&mut staking, &pool, &staker, &payouts).unwrap();
+    assert_eq!(staking.balance(&payouts[0].1), REWARDS / 2);
+    assert_eq!(staking.position(&pool, &staker.wallet, 0).slot_rewards[0].rewards_accrued, 0);
+}
+
+#[test]
+fn slots_are_added_for_partner_mints_only() {
+    let (mut staking, pool) = Staking::with_pool();
+    let ix = add_reward_token_ix(&staking, &pool, pool.reward_mint);
+    assert_error(staking.admin(ix), GamingTokenError::InvalidRewardSlot);
+
+    for _ in 0..3 {
+        partner_slot(&mut staking, &pool);
+    }
+    let mint = staking.create_mint(TOKEN);
+    let ix = add_reward_token_ix(&staking, &pool, mint);
+    assert_error(staking.admin(ix), GamingTokenError::RewardSlotsFull);
+}
+
+#[test]
+fn a_slot_is_removed_only_once_its_period_ends_and_what_is_owed_is_covered() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    let slot = partner_slot(&mut staking, &pool);
+    let other = partner_slot(&mut staking, &pool);
+
+    for index in [0, 3] {
+        let ix = remove_ix(&staking, &pool, index, &slot);
+        assert_error(staking.admin(ix), GamingTokenError::InvalidRewardSlot);
+    }
+    let ix = remove_ix(&staking, &pool, slot.index, &other);
+    assert_error(staking.admin(ix), GamingTokenError::InvalidRewardSlot);
+    let ix = remove_ix(&staking, &pool, slot.index, &slot);
+    assert_error(staking.admin(ix), GamingTokenError::RewardPeriodActive);
+
+    // The whole period accrued to the one staker and must stay claimable
+    staking.harness.warp(PoolParams::default().reward_duration);
+    set_balance(&mut staking, &slot.vault, REWARDS - 1);
+    let ix = remove_ix(&staking, &pool, slot.index, &slot);
+    assert_error(staking.admin(ix), GamingTokenError::InsufficientFunds);
+
+    // Anything beyond what is owed goes back to the authority
+    let surplus = 1_000;
+    set_balance(&mut staking, &slot.vault, REWARDS + surplus);
+    let admin_before = staking.balance(&staking.admin_tokens(&slot.mint));
+    let ix = remove_ix(&staking, &pool, slot.index, &slot);
+    staking.admin(ix).unwrap();
+    let removed = staking.harness.event::<RewardTokenRemoved>();
+    assert_eq!((removed.swept, removed.rewards_owed), (surplus, REWARDS));
+    assert_eq!(staking.balance(&staking.admin_tokens(&slot.mint)), admin_before + surplus);
+
+    let ix = remove_ix(&staking, &pool, slot.index, &slot);
+    assert_error(staking.admin(ix), GamingTokenError::RewardSlotInactive);
+    assert_error(notify_slot(&mut staking, &pool, &slot), GamingTokenError::RewardSlotInactive);
+
+    // The removed slot is claim-only
+    let payout = staking.harness.create_token_account(&slot.mint, &staker.wallet);
+    claim_slots(&mut staking, &pool, &staker, &[(slot, payout)]).unwrap();
+    assert_eq!(staking.balance(&payout), REWARDS);
+    assert_eq!(staking.balance(&slot.vault), 0);
+}
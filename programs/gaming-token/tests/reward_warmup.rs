@@ -0,0 +1,162 @@
+mod common;
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{
+    accounts, instruction, GamingTokenError, RewardsClaimed, TokensUnstaked, UnstakeWithdrawn, WarmupEnded,
+    MAX_REWARD_WARMUP_SECONDS,
+};
+
+const STAKE: u64 = 1_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+/// A pool with a one-day warm-up and a long-term staker already through it.
+fn warmed_up() -> (Staking, Pool, Staker) {
+    let (mut staking, pool) = Staking::with_pool();
+    staking
+        .update_pool(&pool, instruction::UpdateRewardWarmup { reward_warmup_seconds: DAY })
+        .unwrap();
+    let long_term = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &long_term, 0, STAKE, 1).unwrap();
+    staking.harness.warp(DAY);
+    end_warmup(&mut staking, &pool, &long_term, 0).unwrap();
+    (staking, pool, long_term)
+}
+
+fn end_warmup(staking: &mut Staking, pool: &Pool, staker: &Staker, index: u16) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::EndWarmup {
+            stake_account: pool.stake_account(&staker.wallet, index),
+            stake_pool: pool.address(),
+            mint: pool.mint,
+        }
+        .to_account_metas(None),
+        data: instruction::EndWarmup {}.data(),
+    };
+    // Permissionless
+    let cranker = staking.harness.funded_wallet();
+    staking.harness.process(&[ix], &[cranker])
+}
+
+#[test]
+fn a_flash_stake_around_funding_earns_nothing() {
+    let (mut staking, pool, long_term) = warmed_up();
+    let flash = staking.staker(&pool, 100 * STAKE);
+
+    staking.stake(&pool, &flash, 0, 100 * STAKE, 1).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(60 * 60);
+    staking.unstake(&pool, &flash, 0).unwrap();
+    assert_eq!(staking.harness.event::<TokensUnstaked>().reward, 0);
+    assert_eq!(staking.balance(&flash.tokens), 100 * STAKE);
+
+    // The hour's emission went to the staker who was already earning
+    staking.claim(&pool, &long_term, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().gross, REWARDS / 100 / 24);
+}
+
+#[test]
+fn accrual_starts_at_the_end_of_the_warmup() {
+    let (mut staking, pool) = Staking::with_pool();
+    staking
+        .update_pool(&pool, instruction::UpdateRewardWarmup { reward_warmup_seconds: DAY })
+        .unwrap();
+    let staker = staking.staker(&pool, STAKE);
+    let start = staking.harness.now();
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    assert_eq!(staking.position(&pool, &staker.wallet, 0).warmup_until, start + DAY);
+    assert_eq!(staking.pool_state(&pool).total_weight, 0);
+    staking.notify(&pool, REWARDS).unwrap();
+
+    staking.harness.set_time(start + DAY - 1);
+    assert_error(end_warmup(&mut staking, &pool, &staker, 0), GamingTokenError::WarmupActive);
+
+    staking.harness.set_time(start + DAY);
+    end_warmup(&mut staking, &pool, &staker, 0).unwrap();
+    let ended = staking.harness.event::<WarmupEnded>();
+    assert_eq!(ended.warmup_until, start + DAY);
+    assert_eq!(ended.weight, STAKE as u128);
+    assert_eq!(staking.position(&pool, &staker.wallet, 0).warmup_until, 0);
+
+    // One day of earning after one day of warming; nothing retroactive
+    staking.harness.set_time(start + 2 * DAY);
+    staking.claim(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().gross, REWARDS / 100);
+}
+
+#[test]
+fn a_top_up_restarts_the_warmup_for_the_whole_position() {
+    let (mut staking, pool, long_term) = warmed_up();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(DAY);
+
+    let topped_up_at = staking.harness.now();
+    staking.give(&pool.mint, &long_term.tokens, STAKE);
+    staking.add_to_stake(&pool, &long_term, 0, STAKE).unwrap();
+    let position = staking.position(&pool, &long_term.wallet, 0);
+    assert_eq!(position.warmup_until, topped_up_at + DAY);
+    assert_eq!(position.weight(), 0);
+    // The day earned before the top-up was paid out rather than lost
+    assert_eq!(staking.balance(&long_term.tokens), REWARDS / 100);
+
+    // Nothing accrues while warming again, then all of it earns
+    staking.harness.warp(DAY);
+    end_warmup(&mut staking, &pool, &long_term, 0).unwrap();
+    assert_eq!(staking.position(&pool, &long_term.wallet, 0).rewards_accrued, 0);
+    assert_eq!(staking.pool_state(&pool).total_weight, 2 * STAKE as u128);
+    staking.harness.warp(DAY);
+    staking.claim(&pool, &long_term, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().gross, REWARDS / 100);
+}
+
+#[test]
+fn leaving_during_the_warmup_leaves_the_pool_weight_intact() {
+    let (mut staking, pool, long_term) = warmed_up();
+    let early = staking.staker(&pool, STAKE);
+    let unbonding = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &early, 0, STAKE, 10 * DAY).unwrap();
+    staking.stake(&pool, &unbonding, 0, STAKE, 1).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(DAY / 2);
+
+    staking.early_unstake(&pool, &early, 0).unwrap();
+    assert_eq!(staking.balance(&early.tokens), STAKE);
+    staking
+        .update_position(&pool, &unbonding, 0, instruction::RequestUnstake {})
+        .unwrap();
+
+    // Past its warm-up, the unbonding position still adds no weight
+    staking.harness.warp(DAY);
+    let withdraw = Instruction {
+        data: instruction::WithdrawUnstaked {}.data(),
+        ..staking.unstake_ix(&pool, &unbonding, 0)
+    };
+    staking.harness.process(&[withdraw], &[unbonding.wallet]).unwrap();
+    assert_eq!(staking.harness.event::<UnstakeWithdrawn>().reward, 0);
+
+    assert_eq!(staking.pool_state(&pool).total_weight, STAKE as u128);
+    staking.claim(&pool, &long_term, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().gross, 3 * REWARDS / 200);
+}
+
+#[test]
+fn the_warmup_is_bounded() {
+    let (mut staking, pool) = Staking::with_pool();
+    for reward_warmup_seconds in [-1, MAX_REWARD_WARMUP_SECONDS + 1] {
+        assert_error(
+            staking.update_pool(&pool, instruction::UpdateRewardWarmup { reward_warmup_seconds }),
+            GamingTokenError::InvalidRewardWarmup,
+        );
+    }
+    staking
+        .update_pool(
+            &pool,
+            instruction::UpdateRewardWarmup {
+                reward_warmup_seconds: MAX_REWARD_WARMUP_SECONDS,
+            },
+        )
+        .unwrap();
+}
@@ -0,0 +1,173 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{accounts, instruction, GamingTokenError, RewardsClaimed, StakeSlashed};
+
+const STAKE: u64 = 1_000_000_000;
+/// The most the pool lets one slash take: 10%.
+const MAX_SLASH_BPS: u16 = 1_000;
+const TIMELOCK: i64 = DAY;
+const EVIDENCE: [u8; 32] = [7; 32];
+
+/// A pool allowing 10% slashes after a day's dispute window, with Alice and
+/// Bob staked equally for a day.
+fn two_stakers() -> (Staking, Pool, [Staker; 2]) {
+    let (mut staking, pool) = Staking::with_pool();
+    staking
+        .update_pool(
+            &pool,
+            instruction::UpdateSlashConfig {
+                max_slash_bps: MAX_SLASH_BPS,
+                slash_timelock_seconds: TIMELOCK,
+            },
+        )
+        .unwrap();
+    let stakers = [(); 2].map(|_| {
+        let staker = staking.staker(&pool, STAKE);
+        staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+        staker
+    });
+    (staking, pool, stakers)
+}
+
+fn slash_proposal(pool: &Pool, staker: &Staker) -> Pubkey {
+    pda(&[b"slash_proposal", pool.stake_account(&staker.wallet, 0).as_ref()])
+}
+
+fn propose(staking: &mut Staking, pool: &Pool, staker: &Staker, slash_bps: u16, destination: Pubkey) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::ProposeSlash {
+            stake_pool: pool.address(),
+            stake_account: pool.stake_account(&staker.wallet, 0),
+            slash_proposal: slash_proposal(pool, staker),
+            destination,
+            authority: staking.admin,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::ProposeSlash {
+            slash_bps,
+            justification_hash: EVIDENCE,
+        }
+        .data(),
+    };
+    staking.admin(ix)
+}
+
+fn cancel(staking: &mut Staking, pool: &Pool, staker: &Staker) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::CancelSlash {
+            stake_pool: pool.address(),
+            stake_account: pool.stake_account(&staker.wallet, 0),
+            slash_proposal: slash_proposal(pool, staker),
+            authority: staking.admin,
+        }
+        .to_account_metas(None),
+        data: instruction::CancelSlash {}.data(),
+    };
+    staking.admin(ix)
+}
+
+fn execute(staking: &mut Staking, pool: &Pool, staker: &Staker, destination: Pubkey) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::ExecuteSlash {
+            stake_pool: pool.address(),
+            stake_account: pool.stake_account(&staker.wallet, 0),
+            slash_proposal: slash_proposal(pool, staker),
+            stake_vault: pool.stake_vault(),
+            vault_authority: pool.vault_authority(),
+            destination,
+            authority: staking.admin,
+            mint: pool.mint,
+            token_program: pool.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::ExecuteSlash {}.data(),
+    };
+    staking.admin(ix)
+}
+
+#[test]
+fn a_slash_is_capped_and_waits_out_the_timelock() {
+    let (mut staking, pool, [alice, _]) = two_stakers();
+    let treasury = staking.admin_tokens(&pool.mint);
+    for slash_bps in [0, MAX_SLASH_BPS + 1] {
+        assert_error(
+            propose(&mut staking, &pool, &alice, slash_bps, treasury),
+            GamingTokenError::InvalidSlashAmount,
+        );
+    }
+
+    propose(&mut staking, &pool, &alice, MAX_SLASH_BPS, treasury).unwrap();
+    staking.harness.warp(TIMELOCK - 1);
+    assert_error(
+        execute(&mut staking, &pool, &alice, treasury),
+        GamingTokenError::SlashTimelockActive,
+    );
+
+    staking.harness.warp(1);
+    let treasury_before = staking.balance(&treasury);
+    execute(&mut staking, &pool, &alice, treasury).unwrap();
+    let slashed = staking.harness.event::<StakeSlashed>();
+    assert_eq!(
+        (slashed.amount, slashed.remaining_principal, slashed.redistributed),
+        (STAKE / 10, STAKE - STAKE / 10, 0)
+    );
+    assert_eq!((slashed.destination, slashed.justification_hash), (treasury, EVIDENCE));
+    assert_eq!(staking.balance(&treasury), treasury_before + STAKE / 10);
+    assert_eq!(staking.balance(&pool.stake_vault()), 2 * STAKE - STAKE / 10);
+    assert_eq!(staking.pool_state(&pool).total_staked, 2 * STAKE - STAKE / 10);
+    assert!(!staking.position(&pool, &alice.wallet, 0).slash_pending);
+    assert!(staking.harness.account(&slash_proposal(&pool, &alice)).is_none());
+}
+
+#[test]
+fn a_pending_slash_blocks_withdrawal_until_cancelled() {
+    let (mut staking, pool, [alice, _]) = two_stakers();
+    let treasury = staking.admin_tokens(&pool.mint);
+    propose(&mut staking, &pool, &alice, MAX_SLASH_BPS, treasury).unwrap();
+
+    // Past the lock, the position still can't leave ahead of the slash
+    staking.harness.warp(DAY);
+    assert_error(staking.unstake(&pool, &alice, 0), GamingTokenError::SlashPending);
+    assert_error(
+        staking.partial_unstake(&pool, &alice, 0, STAKE / 2),
+        GamingTokenError::SlashPending,
+    );
+
+    cancel(&mut staking, &pool, &alice).unwrap();
+    assert_error(
+        execute(&mut staking, &pool, &alice, treasury),
+        ErrorCode::AccountNotInitialized,
+    );
+    staking.unstake(&pool, &alice, 0).unwrap();
+    assert_eq!(staking.balance(&alice.tokens), STAKE);
+}
+
+#[test]
+fn a_slash_into_the_reward_vault_goes_to_the_other_stakers() {
+    let (mut staking, pool, [alice, bob]) = two_stakers();
+    propose(&mut staking, &pool, &alice, MAX_SLASH_BPS, pool.reward_vault()).unwrap();
+    staking.harness.warp(TIMELOCK);
+
+    execute(&mut staking, &pool, &alice, pool.reward_vault()).unwrap();
+    let slashed = staking.harness.event::<StakeSlashed>();
+    assert_eq!((slashed.amount, slashed.redistributed), (STAKE / 10, STAKE / 10));
+
+    // None of it comes back to the slashed position
+    let position = staking.position(&pool, &alice.wallet, 0);
+    assert_eq!(position.rewards_accrued, 0);
+    assert_eq!(
+        position.reward_per_token_paid,
+        staking.pool_state(&pool).reward_per_token_stored
+    );
+    staking.claim(&pool, &bob, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().gross, STAKE / 10);
+    assert_eq!(staking.balance(&pool.reward_vault()), 0);
+}
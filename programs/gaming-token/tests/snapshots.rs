@@ -0,0 +1,121 @@
+mod common;
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{
+    accounts, instruction, GamingTokenError, SnapshotEntriesRecorded, SnapshotEntry, StakeSnapshot,
+};
+
+const STAKE: u64 = 1_000_000_000;
+const SNAPSHOT_ID: u64 = 7;
+
+fn snapshot_address(pool: &Pool) -> Pubkey {
+    pda(&[b"snapshot", pool.address().as_ref(), &SNAPSHOT_ID.to_le_bytes()])
+}
+
+fn entry_address(pool: &Pool, stake_account: &Pubkey) -> Pubkey {
+    pda(&[b"snapshot_entry", snapshot_address(pool).as_ref(), stake_account.as_ref()])
+}
+
+fn create_snapshot(staking: &mut Staking, pool: &Pool) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::CreateSnapshot {
+            stake_pool: pool.address(),
+            snapshot: snapshot_address(pool),
+            authority: staking.admin,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::CreateSnapshot { snapshot_id: SNAPSHOT_ID }.data(),
+    };
+    staking.admin(ix)
+}
+
+/// `record_snapshot_entry` over `stakers`' first positions, paid by a fresh wallet.
+fn record(staking: &mut Staking, pool: &Pool, stakers: &[&Staker]) -> TxResult {
+    let payer = staking.harness.funded_wallet();
+    let mut accounts = accounts::RecordSnapshotEntry {
+        snapshot: snapshot_address(pool),
+        payer,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    for staker in stakers {
+        let stake_account = pool.stake_account(&staker.wallet, 0);
+        accounts.push(AccountMeta::new_readonly(stake_account, false));
+        accounts.push(AccountMeta::new(entry_address(pool, &stake_account), false));
+    }
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts,
+        data: instruction::RecordSnapshotEntry {}.data(),
+    };
+    staking.harness.process(&[ix], &[payer])
+}
+
+fn finalize(staking: &mut Staking, pool: &Pool, merkle_root: [u8; 32]) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::FinalizeSnapshot {
+            stake_pool: pool.address(),
+            snapshot: snapshot_address(pool),
+            authority: staking.admin,
+        }
+        .to_account_metas(None),
+        data: instruction::FinalizeSnapshot { merkle_root }.data(),
+    };
+    staking.admin(ix)
+}
+
+#[test]
+fn positions_open_at_the_snapshot_are_recorded_once() {
+    let (mut staking, pool) = Staking::with_pool();
+    let early = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &early, 0, STAKE, 1).unwrap();
+    staking.harness.warp(1);
+    create_snapshot(&mut staking, &pool).unwrap();
+
+    staking.harness.warp(1);
+    let late = staking.staker(&pool, 2 * STAKE);
+    staking.stake(&pool, &late, 0, 2 * STAKE, 1).unwrap();
+
+    record(&mut staking, &pool, &[&early, &late]).unwrap();
+    let recorded = staking.harness.event::<SnapshotEntriesRecorded>();
+    assert_eq!((recorded.recorded, recorded.skipped), (1, 1));
+    let stake_account = pool.stake_account(&early.wallet, 0);
+    let entry: SnapshotEntry = staking.harness.get(&entry_address(&pool, &stake_account));
+    assert_eq!((entry.owner, entry.amount), (early.wallet, STAKE));
+    assert!(staking.harness.account(&entry_address(&pool, &pool.stake_account(&late.wallet, 0))).is_none());
+
+    // Recording again skips the existing entry
+    record(&mut staking, &pool, &[&early]).unwrap();
+    assert_eq!(staking.harness.event::<SnapshotEntriesRecorded>().skipped, 1);
+    let snapshot: StakeSnapshot = staking.harness.get(&snapshot_address(&pool));
+    assert_eq!((snapshot.total_staked, snapshot.recorded_amount), (STAKE, STAKE));
+    assert_eq!(snapshot.entry_count, 1);
+}
+
+#[test]
+fn a_finalized_snapshot_takes_no_more_entries() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    create_snapshot(&mut staking, &pool).unwrap();
+
+    assert_error(finalize(&mut staking, &pool, [0; 32]), GamingTokenError::InvalidMerkleRoot);
+    finalize(&mut staking, &pool, [9; 32]).unwrap();
+    let snapshot: StakeSnapshot = staking.harness.get(&snapshot_address(&pool));
+    assert_eq!(snapshot.merkle_root, [9; 32]);
+
+    assert_error(
+        record(&mut staking, &pool, &[&staker]),
+        GamingTokenError::SnapshotAlreadyFinalized,
+    );
+    assert_error(
+        finalize(&mut staking, &pool, [8; 32]),
+        GamingTokenError::SnapshotAlreadyFinalized,
+    );
+}
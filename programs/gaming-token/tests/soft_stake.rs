@@ -0,0 +1,136 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use common::*;
+use gaming_token::{
+    accounts, instruction, GamingTokenError, SoftStakePosition, SoftStakeShortfall, SoftUnstaked,
+};
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+fn soft_position(staker: &Staker) -> Pubkey {
+    pda(&[b"soft_stake", staker.tokens.as_ref()])
+}
+
+fn soft_stake(staking: &mut Staking, pool: &Pool, staker: &Staker, amount: u64, lock_period: i64) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::SoftStake {
+            soft_position: soft_position(staker),
+            stake_pool: pool.address(),
+            token_account: staker.tokens,
+            vault_authority: pool.vault_authority(),
+            mint: pool.mint,
+            owner: staker.wallet,
+            token_program: pool.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::SoftStake { amount, lock_period }.data(),
+    };
+    staking.harness.process(&[ix], &[staker.wallet])
+}
+
+/// `validate_soft_stake`, signed by a fresh wallet since anyone may call it.
+fn validate(staking: &mut Staking, pool: &Pool, staker: &Staker) -> TxResult {
+    let caller = staking.harness.funded_wallet();
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::ValidateSoftStake {
+            soft_position: soft_position(staker),
+            stake_pool: pool.address(),
+            token_account: staker.tokens,
+            vault_authority: pool.vault_authority(),
+        }
+        .to_account_metas(None),
+        data: instruction::ValidateSoftStake {}.data(),
+    };
+    staking.harness.process(&[ix], &[caller])
+}
+
+fn soft_unstake(staking: &mut Staking, pool: &Pool, staker: &Staker) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::SoftUnstake {
+            soft_position: soft_position(staker),
+            stake_pool: pool.address(),
+            token_account: staker.tokens,
+            vault_authority: pool.vault_authority(),
+            reward_vault: pool.reward_vault(),
+            reward_vault_authority: pool.reward_vault_authority(),
+            fee_vault: None,
+            user_reward_token_account: staker.rewards,
+            owner: staker.wallet,
+            mint: pool.mint,
+            reward_mint: pool.reward_mint,
+            token_program: pool.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::SoftUnstake {}.data(),
+    };
+    staking.harness.process(&[ix], &[staker.wallet])
+}
+
+#[test]
+fn tokens_stay_in_the_wallet_and_earn_like_a_vault_position() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    soft_stake(&mut staking, &pool, &staker, STAKE, DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+
+    let account = staking.harness.token_account(&staker.tokens);
+    assert_eq!(account.amount, STAKE);
+    assert_eq!(account.delegate, Some(pool.vault_authority()).into());
+    assert_eq!(account.delegated_amount, STAKE);
+    let state = staking.pool_state(&pool);
+    assert_eq!((state.total_staked, state.total_soft_staked), (0, STAKE));
+
+    // Fully backed, so the crank changes nothing
+    validate(&mut staking, &pool, &staker).unwrap();
+    assert!(staking.harness.events::<SoftStakeShortfall>().is_empty());
+
+    staking.harness.warp(DAY - 1);
+    assert_error(soft_unstake(&mut staking, &pool, &staker), GamingTokenError::TokensStillLocked);
+    staking.harness.set_time(staking.pool_state(&pool).period_finish);
+    soft_unstake(&mut staking, &pool, &staker).unwrap();
+    let unstaked = staking.harness.event::<SoftUnstaked>();
+    assert_eq!((unstaked.amount, unstaked.reward), (STAKE, REWARDS));
+
+    let account = staking.harness.token_account(&staker.tokens);
+    assert_eq!(account.amount, STAKE + REWARDS);
+    assert_eq!(account.delegate, None.into());
+    assert_eq!(staking.pool_state(&pool).total_soft_staked, 0);
+}
+
+#[test]
+fn moving_delegated_tokens_shrinks_the_position_and_forfeits_rewards() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    soft_stake(&mut staking, &pool, &staker, STAKE, DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(DAY);
+
+    let elsewhere = staking.harness.create_token_account(&pool.mint, &staker.wallet);
+    let ix = spl_token::instruction::transfer(&TOKEN, &staker.tokens, &elsewhere, &staker.wallet, &[], STAKE / 4)
+        .unwrap();
+    staking.harness.process(&[ix], &[staker.wallet]).unwrap();
+
+    validate(&mut staking, &pool, &staker).unwrap();
+    let shortfall = staking.harness.event::<SoftStakeShortfall>();
+    assert_eq!(shortfall.previous_amount, STAKE);
+    assert_eq!(shortfall.backed_amount, STAKE - STAKE / 4);
+    assert_eq!(shortfall.forfeited_rewards, REWARDS / 100);
+    let position: SoftStakePosition = staking.harness.get(&soft_position(&staker));
+    assert_eq!(position.position.amount, STAKE - STAKE / 4);
+    assert_eq!(position.position.rewards_accrued, 0);
+    assert_eq!(staking.pool_state(&pool).total_soft_staked, STAKE - STAKE / 4);
+
+    // Once checked, the remaining stake is backed again
+    validate(&mut staking, &pool, &staker).unwrap();
+    assert!(staking.harness.events::<SoftStakeShortfall>().is_empty());
+}
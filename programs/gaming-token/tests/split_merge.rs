@@ -0,0 +1,158 @@
+mod common;
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{accounts, instruction, GamingTokenError, PositionSplit, StakeAccount, REWARD_PRECISION};
+
+const STAKE: u64 = 1_000_001;
+/// Leaves a remainder at every checkpoint.
+const REWARDS: u64 = 86_400_000_007;
+
+fn split(staking: &mut Staking, pool: &Pool, staker: &Staker, index: u16, new_index: u16, amount: u64) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::SplitPosition {
+            stake_account: pool.stake_account(&staker.wallet, index),
+            new_stake_account: pool.stake_account(&staker.wallet, new_index),
+            stake_positions: pool.stake_positions(&staker.wallet),
+            stake_pool: pool.address(),
+            mint: pool.mint,
+            owner: staker.wallet,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::SplitPosition {
+            new_position_index: new_index,
+            amount,
+        }
+        .data(),
+    };
+    staking.harness.process(&[ix], &[staker.wallet])
+}
+
+fn merge(staking: &mut Staking, pool: &Pool, staker: &Staker, index: u16, merged_index: u16) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::MergePositions {
+            stake_account: pool.stake_account(&staker.wallet, index),
+            merged_stake_account: pool.stake_account(&staker.wallet, merged_index),
+            stake_positions: pool.stake_positions(&staker.wallet),
+            stake_pool: pool.address(),
+            mint: pool.mint,
+            owner: staker.wallet,
+        }
+        .to_account_metas(None),
+        data: instruction::MergePositions {}.data(),
+    };
+    staking.harness.process(&[ix], &[staker.wallet])
+}
+
+/// Everything the position is owed as of the pool's last checkpoint, in
+/// `1 / REWARD_PRECISION` units.
+fn owed(staking: &Staking, pool: &Pool, position: &StakeAccount) -> u128 {
+    let accumulator = staking.pool_state(pool).reward_per_token_stored;
+    position.rewards_accrued as u128 * REWARD_PRECISION
+        + position.reward_dust as u128
+        + position.weight() * (accumulator - position.reward_per_token_paid)
+}
+
+fn total_owed(staking: &Staking, pool: &Pool, staker: &Staker, indices: &[u16]) -> u128 {
+    indices
+        .iter()
+        .map(|index| owed(staking, pool, &staking.position(pool, &staker.wallet, *index)))
+        .sum()
+}
+
+#[test]
+fn splitting_preserves_principal_and_rewards_exactly() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 3 * DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(DAY);
+    let original = staking.position(&pool, &staker.wallet, 0);
+
+    let carved = STAKE / 3;
+    split(&mut staking, &pool, &staker, 0, 1, carved).unwrap();
+
+    let event = staking.harness.event::<PositionSplit>();
+    assert_eq!((event.amount, event.remaining), (carved, STAKE - carved));
+    let kept = staking.position(&pool, &staker.wallet, 0);
+    let split_off = staking.position(&pool, &staker.wallet, 1);
+    assert_eq!(kept.amount + split_off.amount, STAKE);
+    assert_eq!(staking.pool_state(&pool).total_staked, STAKE);
+    assert_eq!(staking.balance(&pool.stake_vault()), STAKE);
+
+    // The day's rewards stay with the original; the new half starts from the checkpoint
+    let earned = owed(&staking, &pool, &original);
+    assert_eq!(owed(&staking, &pool, &kept), earned);
+    assert_eq!(owed(&staking, &pool, &split_off), 0);
+    assert_eq!(split_off.reward_per_token_paid, kept.reward_per_token_paid);
+    assert_eq!(split_off.lock_until, original.lock_until);
+    assert_eq!(split_off.boost_bps, original.boost_bps);
+}
+
+#[test]
+fn merging_preserves_principal_and_rewards_exactly() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, 3 * STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(DAY / 3);
+    // Staked later, so the two sit at different checkpoints
+    staking.stake(&pool, &staker, 1, 2 * STAKE, 5 * DAY).unwrap();
+    staking.harness.warp(DAY);
+
+    // Bring the accumulator to now without touching either position
+    let other = staking.staker(&pool, 1);
+    staking.stake(&pool, &other, 0, 1, 1).unwrap();
+    let before = total_owed(&staking, &pool, &staker, &[0, 1]);
+    let lock_until = staking.position(&pool, &staker.wallet, 1).lock_until;
+
+    merge(&mut staking, &pool, &staker, 0, 1).unwrap();
+
+    let merged = staking.position(&pool, &staker.wallet, 0);
+    assert_eq!(merged.amount, 3 * STAKE);
+    assert_eq!(merged.lock_until, lock_until);
+    assert_eq!(merged.reward_dust as u128 + merged.rewards_accrued as u128 * REWARD_PRECISION, before);
+    assert!(staking.harness.account(&pool.stake_account(&staker.wallet, 1)).is_none());
+    assert_eq!(staking.positions(&pool, &staker.wallet).open_positions, 1);
+    assert_eq!(staking.pool_state(&pool).total_staked, 3 * STAKE + 1);
+}
+
+#[test]
+fn a_split_and_merge_round_trip_pays_what_one_position_would() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    let control = staking.staker(&pool, STAKE);
+    for staker in [&staker, &control] {
+        staking.stake(&pool, staker, 0, STAKE, 1).unwrap();
+    }
+    staking.notify(&pool, REWARDS).unwrap();
+
+    staking.harness.warp(DAY);
+    split(&mut staking, &pool, &staker, 0, 1, 7).unwrap();
+    staking.harness.warp(DAY);
+    merge(&mut staking, &pool, &staker, 0, 1).unwrap();
+    staking.harness.warp(DAY);
+
+    staking.unstake(&pool, &staker, 0).unwrap();
+    staking.unstake(&pool, &control, 0).unwrap();
+    let paid = staking.balance(&staker.tokens);
+    let control_paid = staking.balance(&control.tokens);
+    assert!(paid > STAKE);
+    // Splitting can strand at most a unit of rounding per checkpoint
+    assert!(control_paid - paid <= 2, "{paid} {control_paid}");
+}
+
+#[test]
+fn invalid_splits_and_merges_are_rejected() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+
+    assert_error(split(&mut staking, &pool, &staker, 0, 1, 0), GamingTokenError::InvalidAmount);
+    assert_error(split(&mut staking, &pool, &staker, 0, 1, STAKE), GamingTokenError::InvalidAmount);
+    assert_error(merge(&mut staking, &pool, &staker, 0, 0), GamingTokenError::SamePosition);
+}
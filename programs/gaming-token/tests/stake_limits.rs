@@ -0,0 +1,117 @@
+mod common;
+
+use common::*;
+use gaming_token::{instruction, GamingTokenError, MAX_LOCK_PERIOD_SECONDS};
+
+const MIN_STAKE: u64 = 1_000;
+const MIN_LOCK: i64 = DAY;
+const MAX_LOCK: i64 = 10 * DAY;
+
+fn limited() -> (Staking, Pool, Staker) {
+    let mut staking = Staking::new();
+    let pool = staking.single_token_pool(
+        TOKEN,
+        PoolParams {
+            min_stake_amount: MIN_STAKE,
+            min_lock_seconds: MIN_LOCK,
+            max_lock_seconds: MAX_LOCK,
+            ..PoolParams::default()
+        },
+    );
+    let staker = staking.staker(&pool, 10 * MIN_STAKE);
+    (staking, pool, staker)
+}
+
+#[test]
+fn the_minimum_stake_is_inclusive() {
+    let (mut staking, pool, staker) = limited();
+
+    assert_error(
+        staking.stake(&pool, &staker, 0, MIN_STAKE - 1, MIN_LOCK),
+        GamingTokenError::StakeBelowMinimum,
+    );
+    staking.stake(&pool, &staker, 0, MIN_STAKE, MIN_LOCK).unwrap();
+
+    // Only the position's total has to meet it
+    staking.add_to_stake(&pool, &staker, 0, 1).unwrap();
+    assert_eq!(staking.position(&pool, &staker.wallet, 0).amount, MIN_STAKE + 1);
+}
+
+#[test]
+fn both_lock_bounds_are_inclusive() {
+    let (mut staking, pool, staker) = limited();
+
+    assert_error(
+        staking.stake(&pool, &staker, 0, MIN_STAKE, MIN_LOCK - 1),
+        GamingTokenError::LockTooShort,
+    );
+    assert_error(
+        staking.stake(&pool, &staker, 0, MIN_STAKE, MAX_LOCK + 1),
+        GamingTokenError::LockTooLong,
+    );
+    staking.stake(&pool, &staker, 0, MIN_STAKE, MIN_LOCK).unwrap();
+    staking.stake(&pool, &staker, 1, MIN_STAKE, MAX_LOCK).unwrap();
+}
+
+#[test]
+fn extending_a_lock_stops_at_the_maximum() {
+    let (mut staking, pool, staker) = limited();
+    staking.stake(&pool, &staker, 0, MIN_STAKE, MIN_LOCK).unwrap();
+    let created_at = staking.position(&pool, &staker.wallet, 0).created_at;
+
+    assert_error(
+        staking.update_position(
+            &pool,
+            &staker,
+            0,
+            instruction::ExtendLock {
+                new_lock_until: created_at + MAX_LOCK + 1,
+            },
+        ),
+        GamingTokenError::LockTooLong,
+    );
+    staking
+        .update_position(
+            &pool,
+            &staker,
+            0,
+            instruction::ExtendLock {
+                new_lock_until: created_at + MAX_LOCK,
+            },
+        )
+        .unwrap();
+    assert_eq!(staking.position(&pool, &staker.wallet, 0).lock_period, MAX_LOCK);
+}
+
+#[test]
+fn an_uncapped_pool_still_has_the_program_wide_cap() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, 2);
+
+    assert_error(
+        staking.stake(&pool, &staker, 0, 1, MAX_LOCK_PERIOD_SECONDS + 1),
+        GamingTokenError::LockTooLong,
+    );
+    staking.stake(&pool, &staker, 0, 1, MAX_LOCK_PERIOD_SECONDS).unwrap();
+}
+
+#[test]
+fn pool_limits_must_be_consistent() {
+    let mut staking = Staking::new();
+    for (min_lock_seconds, max_lock_seconds) in [
+        (MAX_LOCK, MIN_LOCK),
+        (-1, 0),
+        (0, MAX_LOCK_PERIOD_SECONDS + 1),
+    ] {
+        let mint = staking.create_mint(TOKEN);
+        let params = PoolParams {
+            min_lock_seconds,
+            max_lock_seconds,
+            ..PoolParams::default()
+        };
+        assert_error(
+            staking.create_pool(mint, mint, params).map(drop),
+            GamingTokenError::InvalidLockPeriod,
+        );
+    }
+}
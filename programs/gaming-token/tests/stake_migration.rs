@@ -0,0 +1,304 @@
+mod common;
+
+use anchor_lang::prelude::{Pubkey, Rent};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, AnchorSerialize, Discriminator, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{
+    accounts, instruction, GamingTokenError, SlotRewardsV0, StakeAccount, StakeAccountMigrated,
+    StakeAccountV0, StakeAccountV2, STAKE_ACCOUNT_VERSION,
+};
+use program_harness::AccountState;
+
+const STAKE: u64 = 1_000_000;
+
+const V0_OWNER: [u8; 32] = [7; 32];
+const V0_MINT: [u8; 32] = [9; 32];
+
+/// A version 0 stake account exactly as the program wrote it before the
+/// layout was versioned. Spelled out byte by byte rather than serialized, so
+/// edits to the Rust structs can't quietly move it.
+fn v0_fixture(position_index: u16) -> Vec<u8> {
+    let parts: [&[u8]; 22] = [
+        &StakeAccount::DISCRIMINATOR,
+        &V0_OWNER,
+        &V0_MINT,
+        &position_index.to_le_bytes(),
+        &5_000u64.to_le_bytes(),          // amount
+        &1_700_086_400i64.to_le_bytes(),  // lock_until
+        &86_400i64.to_le_bytes(),         // lock_period
+        &1_700_000_000i64.to_le_bytes(),  // created_at
+        &1_700_000_000i64.to_le_bytes(),  // last_reward_claim
+        &42u128.to_le_bytes(),            // reward_per_token_paid
+        &2_500u16.to_le_bytes(),          // boost_bps
+        &[0],                             // receipt_mint: None
+        &77u64.to_le_bytes(),             // rewards_accrued
+        &[[0; 24]; 3].concat(),           // slot_rewards
+        &[0],                             // unbonding_since: None
+        &0i64.to_le_bytes(),              // available_at
+        &[0],                             // delegate: None
+        &0i64.to_le_bytes(),              // delegated_at
+        &[0],                             // previous_delegate: None
+        &0i64.to_le_bytes(),              // previous_delegated_at
+        &[0],                             // slash_pending
+        &[1],                             // is_active
+    ];
+    // Options were allocated at full size; the unused tail stays zeroed
+    let mut bytes = parts.concat();
+    bytes.resize(StakeAccountV0::SPACE, 0);
+    bytes
+}
+
+fn v0_address(position_index: u16) -> Pubkey {
+    pda(&[b"stake", &V0_OWNER, &V0_MINT, &position_index.to_le_bytes()])
+}
+
+/// Stores `data` at `key` as a rent-exempt program account.
+fn store(staking: &mut Staking, key: Pubkey, data: Vec<u8>) {
+    staking.harness.set_account(
+        key,
+        AccountState {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: gaming_token::ID,
+            executable: false,
+        },
+    );
+}
+
+fn migrate(staking: &mut Staking, stake_account: Pubkey) -> TxResult {
+    let payer = staking.harness.funded_wallet();
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::MigrateStakeAccount {
+            stake_account,
+            payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::MigrateStakeAccount {}.data(),
+    };
+    staking.harness.process(&[ix], &[payer])
+}
+
+#[test]
+fn the_frozen_v0_layout_migrates_in_place() {
+    let mut staking = Staking::new();
+    let key = v0_address(3);
+    store(&mut staking, key, v0_fixture(3));
+
+    migrate(&mut staking, key).unwrap();
+
+    let event = staking.harness.event::<StakeAccountMigrated>();
+    assert_eq!(event.stake_account, key);
+    assert_eq!(event.owner, Pubkey::new_from_array(V0_OWNER));
+    assert_eq!((event.from_version, event.to_version), (0, STAKE_ACCOUNT_VERSION));
+
+    let state = staking.harness.account(&key).unwrap();
+    assert_eq!(state.data.len(), StakeAccount::SPACE);
+    assert_eq!(state.lamports, Rent::default().minimum_balance(StakeAccount::SPACE));
+
+    let migrated: StakeAccount = staking.harness.get(&key);
+    assert_eq!(migrated.version, STAKE_ACCOUNT_VERSION);
+    assert_eq!(migrated.owner, Pubkey::new_from_array(V0_OWNER));
+    assert_eq!(migrated.mint, Pubkey::new_from_array(V0_MINT));
+    assert_eq!(migrated.position_index, 3);
+    assert_eq!(migrated.amount, 5_000);
+    assert_eq!(migrated.lock_until, 1_700_086_400);
+    assert_eq!(migrated.lock_period, 86_400);
+    assert_eq!(migrated.created_at, 1_700_000_000);
+    assert_eq!(migrated.reward_per_token_paid, 42);
+    assert_eq!(migrated.boost_bps, 2_500);
+    assert_eq!(migrated.receipt_mint, None);
+    assert_eq!(migrated.rewards_accrued, 77);
+    assert!(migrated.is_active);
+    assert_eq!(migrated.unbonding_since, None);
+    // Fields the old layout lacked start empty
+    assert_eq!(migrated.reward_dust, 0);
+
+    // Migrating twice is refused rather than reinterpreted
+    assert_error(migrate(&mut staking, key), GamingTokenError::AlreadyMigrated);
+}
+
+#[test]
+fn the_v1_layout_is_v0_behind_a_version_byte() {
+    let mut staking = Staking::new();
+    let key = v0_address(0);
+    let mut data = v0_fixture(0);
+    data.insert(8, 1);
+    store(&mut staking, key, data);
+
+    migrate(&mut staking, key).unwrap();
+
+    assert_eq!(staking.harness.event::<StakeAccountMigrated>().from_version, 1);
+    let migrated: StakeAccount = staking.harness.get(&key);
+    assert_eq!(migrated.amount, 5_000);
+    assert_eq!(migrated.rewards_accrued, 77);
+}
+
+#[test]
+fn a_body_that_does_not_match_its_address_is_rejected() {
+    let mut staking = Staking::new();
+    // Position 1's bytes at position 2's address
+    let key = v0_address(2);
+    store(&mut staking, key, v0_fixture(1));
+
+    assert_error(migrate(&mut staking, key), GamingTokenError::UnrecognizedStakeLayout);
+    assert_eq!(staking.harness.account(&key).unwrap().data, v0_fixture(1));
+}
+
+/// `position` as version 1 wrote it, before dust was carried.
+fn v1_bytes(position: &StakeAccount) -> Vec<u8> {
+    let body = StakeAccountV0 {
+        owner: position.owner,
+        mint: position.mint,
+        position_index: position.position_index,
+        amount: position.amount,
+        lock_until: position.lock_until,
+        lock_period: position.lock_period,
+        created_at: position.created_at,
+        last_reward_claim: position.last_reward_claim,
+        reward_per_token_paid: position.reward_per_token_paid,
+        boost_bps: position.boost_bps,
+        receipt_mint: position.receipt_mint,
+        rewards_accrued: position.rewards_accrued,
+        slot_rewards: position.slot_rewards.map(|slot| SlotRewardsV0 {
+            reward_per_token_paid: slot.reward_per_token_paid,
+            rewards_accrued: slot.rewards_accrued,
+        }),
+        unbonding_since: position.unbonding_since,
+        available_at: position.available_at,
+        delegate: position.delegate,
+        delegated_at: position.delegated_at,
+        previous_delegate: position.previous_delegate,
+        previous_delegated_at: position.previous_delegated_at,
+        slash_pending: position.slash_pending,
+        is_active: position.is_active,
+    }
+    .try_to_vec()
+    .unwrap();
+    let mut data = [&StakeAccount::DISCRIMINATOR[..], &[1], &body].concat();
+    data.resize(StakeAccountV0::SPACE + 1, 0);
+    data
+}
+
+#[test]
+fn a_v1_position_migrates_back_to_the_same_bytes() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    let key = pool.stake_account(&staker.wallet, 0);
+    let current = staking.harness.account(&key).unwrap().data.clone();
+
+    let position = staking.position(&pool, &staker.wallet, 0);
+    store(&mut staking, key, v1_bytes(&position));
+    migrate(&mut staking, key).unwrap();
+
+    assert_eq!(staking.harness.event::<StakeAccountMigrated>().from_version, 1);
+    assert_eq!(staking.harness.account(&key).unwrap().data, current);
+    staking.harness.warp(DAY);
+    staking.unstake(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.balance(&staker.tokens), STAKE);
+}
+
+/// `position` as version 2 wrote it, before the cross-program view gained
+/// `unbonding_since` and `is_active`.
+fn v2_bytes(position: &StakeAccount) -> Vec<u8> {
+    let body = StakeAccountV2 {
+        owner: position.owner,
+        mint: position.mint,
+        position_index: position.position_index,
+        amount: position.amount,
+        lock_until: position.lock_until,
+        lock_period: position.lock_period,
+        created_at: position.created_at,
+        last_reward_claim: position.last_reward_claim,
+        reward_per_token_paid: position.reward_per_token_paid,
+        boost_bps: position.boost_bps,
+        receipt_mint: position.receipt_mint,
+        rewards_accrued: position.rewards_accrued,
+        reward_dust: position.reward_dust,
+        slot_rewards: position.slot_rewards,
+        unbonding_since: position.unbonding_since,
+        available_at: position.available_at,
+        delegate: position.delegate,
+        delegated_at: position.delegated_at,
+        previous_delegate: position.previous_delegate,
+        previous_delegated_at: position.previous_delegated_at,
+        slash_pending: position.slash_pending,
+        is_active: position.is_active,
+    }
+    .try_to_vec()
+    .unwrap();
+    let mut data = [&StakeAccount::DISCRIMINATOR[..], &[2], &body].concat();
+    data.resize(StakeAccount::SPACE - 16, 0);
+    data
+}
+
+/// `position` as version 3 wrote it, before `warmup_until`.
+fn v3_bytes(position: &StakeAccount) -> Vec<u8> {
+    let mut data = account_bytes(&StakeAccount { version: 3, ..*position });
+    // `warmup_until` and `power_changed_at` are last; unused option space
+    // after them stays zeroed
+    data.truncate(data.len() - 16);
+    data.resize(StakeAccount::SPACE - 16, 0);
+    data
+}
+
+/// `position` as version 4 wrote it, before `power_changed_at`.
+fn v4_bytes(position: &StakeAccount) -> Vec<u8> {
+    let mut data = account_bytes(&StakeAccount { version: 4, ..*position });
+    data.truncate(data.len() - 8);
+    data.resize(StakeAccount::SPACE - 8, 0);
+    data
+}
+
+#[test]
+fn versions_two_to_four_migrate_back_to_the_same_position() {
+    for (version, old_bytes) in [(2, v2_bytes as fn(&StakeAccount) -> Vec<u8>), (3, v3_bytes), (4, v4_bytes)] {
+        let (mut staking, pool) = Staking::with_pool();
+        let staker = staking.staker(&pool, STAKE);
+        staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+        let key = pool.stake_account(&staker.wallet, 0);
+        let current = staking.harness.account(&key).unwrap().data.clone();
+
+        let position = staking.position(&pool, &staker.wallet, 0);
+        store(&mut staking, key, old_bytes(&position));
+        migrate(&mut staking, key).unwrap();
+
+        assert_eq!(staking.harness.event::<StakeAccountMigrated>().from_version, version);
+        assert_eq!(staking.harness.account(&key).unwrap().data, current);
+        staking.harness.warp(DAY);
+        staking.unstake(&pool, &staker, 0).unwrap();
+        assert_eq!(staking.balance(&staker.tokens), STAKE);
+    }
+}
+
+#[test]
+fn old_versions_are_blocked_until_migrated() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    staking.notify(&pool, 86_400_000_000).unwrap();
+    let key = pool.stake_account(&staker.wallet, 0);
+    let position = staking.position(&pool, &staker.wallet, 0);
+    store(&mut staking, key, v3_bytes(&position));
+    staking.harness.warp(DAY);
+
+    assert_error(
+        staking.claim(&pool, &staker, 0),
+        GamingTokenError::MigrationRequired,
+    );
+    assert_error(
+        staking.add_to_stake(&pool, &staker, 0, 1),
+        GamingTokenError::MigrationRequired,
+    );
+    assert_error(
+        staking.unstake(&pool, &staker, 0),
+        GamingTokenError::MigrationRequired,
+    );
+
+    migrate(&mut staking, key).unwrap();
+    staking.claim(&pool, &staker, 0).unwrap();
+    staking.unstake(&pool, &staker, 0).unwrap();
+}
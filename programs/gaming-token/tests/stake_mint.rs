@@ -0,0 +1,92 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+
+const STAKE: u64 = 1_000_000;
+const REWARDS: u64 = 86_400_000_000;
+
+/// Two single-token pools over different mints, with one staker in each.
+fn two_pools() -> (Staking, Pool, Pool, Staker, Staker) {
+    let mut staking = Staking::new();
+    let a = staking.single_token_pool(TOKEN, PoolParams::default());
+    let b = staking.single_token_pool(TOKEN, PoolParams::default());
+    let in_a = staking.staker(&a, STAKE);
+    let in_b = staking.staker(&b, STAKE);
+    staking.stake(&a, &in_a, 0, STAKE, DAY).unwrap();
+    staking.stake(&b, &in_b, 0, STAKE, DAY).unwrap();
+    staking.notify(&a, REWARDS).unwrap();
+    staking.notify(&b, REWARDS).unwrap();
+    staking.harness.warp(DAY);
+    (staking, a, b, in_a, in_b)
+}
+
+#[test]
+fn positions_record_their_mint() {
+    let (staking, a, b, in_a, in_b) = two_pools();
+    assert_eq!(staking.position(&a, &in_a.wallet, 0).mint, a.mint);
+    assert_eq!(staking.position(&b, &in_b.wallet, 0).mint, b.mint);
+    assert_eq!(staking.positions(&a, &in_a.wallet).mint, a.mint);
+}
+
+#[test]
+fn a_position_cannot_be_used_against_another_pool() {
+    let (mut staking, a, b, in_a, _) = two_pools();
+    let a_position = a.stake_account(&in_a.wallet, 0);
+
+    // The position with every other account from pool B
+    let b_tokens = staking.harness.create_token_account(&b.mint, &in_a.wallet);
+    let b_view = Staker {
+        wallet: in_a.wallet,
+        tokens: b_tokens,
+        rewards: b_tokens,
+    };
+    for mut ix in [
+        staking.claim_ix(&b, &b_view, 0),
+        staking.unstake_ix(&b, &b_view, 0),
+        staking.partial_unstake_ix(&b, &b_view, 0, 1),
+        staking.add_to_stake_ix(&b, &b_view, 0, 1),
+    ] {
+        swap_account(&mut ix, b.stake_account(&in_a.wallet, 0), a_position);
+        swap_account(&mut ix, b.stake_positions(&in_a.wallet), a.stake_positions(&in_a.wallet));
+        assert_error(staking.harness.process(&[ix], &[in_a.wallet]), ErrorCode::ConstraintSeeds);
+    }
+
+    assert_eq!(staking.balance(&a.stake_vault()), STAKE);
+    assert_eq!(staking.balance(&b.stake_vault()), STAKE);
+    assert_eq!(staking.balance(&b_view.tokens), 0);
+}
+
+#[test]
+fn vaults_must_belong_to_the_positions_pool() {
+    let (mut staking, a, b, in_a, _) = two_pools();
+
+    let mut ix = staking.unstake_ix(&a, &in_a, 0);
+    swap_account(&mut ix, a.stake_vault(), b.stake_vault());
+    assert_error(staking.harness.process(&[ix], &[in_a.wallet]), ErrorCode::ConstraintSeeds);
+
+    let mut ix = staking.claim_ix(&a, &in_a, 0);
+    swap_account(&mut ix, a.reward_vault(), b.reward_vault());
+    assert_error(staking.harness.process(&[ix], &[in_a.wallet]), ErrorCode::ConstraintSeeds);
+
+    let mut ix = staking.unstake_ix(&a, &in_a, 0);
+    swap_account(&mut ix, a.address(), b.address());
+    assert_error(staking.harness.process(&[ix], &[in_a.wallet]), ErrorCode::ConstraintSeeds);
+
+    staking.unstake(&a, &in_a, 0).unwrap();
+    assert_eq!(staking.balance(&b.stake_vault()), STAKE);
+}
+
+#[test]
+fn staking_takes_only_the_pools_mint() {
+    let (mut staking, a, b, _, _) = two_pools();
+
+    // B's tokens offered to pool A
+    let holder = staking.staker(&b, STAKE);
+    assert_error(
+        staking.stake(&a, &holder, 0, STAKE, DAY),
+        ErrorCode::ConstraintTokenMint,
+    );
+    assert_eq!(staking.balance(&holder.tokens), STAKE);
+    assert_eq!(staking.pool_state(&a).total_staked, STAKE);
+}
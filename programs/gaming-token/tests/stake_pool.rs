@@ -0,0 +1,51 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+
+#[test]
+fn two_stakers_share_one_pool() {
+    let (mut staking, pool) = Staking::with_pool();
+    let alice = staking.staker(&pool, 1_000);
+    let bob = staking.staker(&pool, 2_000);
+
+    staking.stake(&pool, &alice, 0, 1_000, DAY).unwrap();
+    staking.stake(&pool, &bob, 0, 2_000, DAY).unwrap();
+
+    assert_eq!(staking.position(&pool, &alice.wallet, 0).amount, 1_000);
+    assert_eq!(staking.position(&pool, &bob.wallet, 0).amount, 2_000);
+    let state = staking.pool_state(&pool);
+    assert_eq!(state.total_staked, 3_000);
+    assert_eq!(state.total_positions, 2);
+    assert_eq!(staking.balance(&pool.stake_vault()), 3_000);
+}
+
+#[test]
+fn a_pool_is_created_once() {
+    let (mut staking, pool) = Staking::with_pool();
+    let admin = staking.admin;
+    let ix = staking.create_pool_ix(&pool, admin, PoolParams::default());
+    assert!(staking.admin(ix).is_err());
+}
+
+#[test]
+fn staking_needs_a_pool() {
+    let mut staking = Staking::new();
+    let mint = staking.create_mint(TOKEN);
+    let pool = Pool {
+        mint,
+        reward_mint: mint,
+        token_program: TOKEN,
+    };
+    let staker = staking.staker(&pool, 1_000);
+    assert_error(
+        staking.stake(&pool, &staker, 0, 1_000, DAY),
+        ErrorCode::AccountNotInitialized,
+    );
+    assert!(staking.harness.account(&pool.stake_account(&staker.wallet, 0)).is_none());
+    assert_eq!(staking.balance(&staker.tokens), 1_000);
+
+    staking.create_pool(mint, mint, PoolParams::default()).unwrap();
+    staking.stake(&pool, &staker, 0, 1_000, DAY).unwrap();
+    assert_eq!(staking.balance(&pool.stake_vault()), 1_000);
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::*;
+use gaming_token::{RewardsClaimed, RewardsCompounded, StakerStats, STAKER_STATS_VERSION};
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+#[test]
+fn a_full_lifecycle_adds_up() {
+    let (mut staking, pool) = Staking::with_pool();
+    let deposits = STAKE + STAKE / 2 + STAKE / 4;
+    let staker = staking.staker(&pool, deposits);
+    let start = staking.harness.now();
+
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+
+    staking.harness.set_time(start + DAY);
+    staking.add_to_stake(&pool, &staker, 0, STAKE / 2).unwrap();
+
+    staking.harness.set_time(start + 2 * DAY);
+    staking.claim(&pool, &staker, 0).unwrap();
+    assert!(staking.harness.event::<RewardsClaimed>().net > 0);
+
+    staking.harness.set_time(start + 3 * DAY);
+    staking.compound(&pool, &staker, 0).unwrap();
+    let compounded = staking.harness.event::<RewardsCompounded>().amount;
+    staking.stake(&pool, &staker, 1, STAKE / 4, 5 * DAY).unwrap();
+
+    staking.harness.set_time(start + 10 * DAY);
+    staking.partial_unstake(&pool, &staker, 0, STAKE / 2).unwrap();
+
+    staking.harness.set_time(staking.pool_state(&pool).period_finish);
+    staking.unstake(&pool, &staker, 0).unwrap();
+    staking.unstake(&pool, &staker, 1).unwrap();
+
+    let stats: StakerStats = staking.harness.get(&pool.staker_stats(&staker.wallet));
+    assert_eq!(stats.version, STAKER_STATS_VERSION);
+    assert_eq!(stats.owner, staker.wallet);
+    assert_eq!(stats.stake_pool, pool.address());
+    assert_eq!(stats.first_stake_at, start);
+    assert_eq!(stats.longest_lock_seconds, 5 * DAY);
+    // Compounding grows principal without counting as a deposit
+    assert_eq!(stats.total_staked, deposits);
+    assert_eq!(stats.total_unstaked, deposits + compounded);
+    assert_eq!(stats.total_claims, 1);
+
+    // Everything that left the wallet came back with the rewards on top; the
+    // lone staker earned the whole emission but for a unit of rounding dust
+    let earned = staking.balance(&staker.tokens) - deposits;
+    assert_eq!(stats.total_rewards_earned, earned);
+    assert!(REWARDS - earned <= 1, "{earned}");
+}
+
+#[test]
+fn stats_are_kept_per_pool() {
+    let (mut staking, first) = Staking::with_pool();
+    let second = staking.single_token_pool(TOKEN, PoolParams::default());
+    let staker = staking.staker(&first, STAKE);
+    staking.stake(&first, &staker, 0, STAKE, DAY).unwrap();
+
+    assert!(staking.harness.account(&second.staker_stats(&staker.wallet)).is_none());
+    let stats: StakerStats = staking.harness.get(&first.staker_stats(&staker.wallet));
+    assert_eq!(stats.total_staked, STAKE);
+    assert_eq!(stats.longest_lock_seconds, DAY);
+}
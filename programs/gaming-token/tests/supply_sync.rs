@@ -0,0 +1,109 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use common::*;
+use gaming_token::{accounts, instruction, GamingTokenError, SupplyReconciled, TokensBurned};
+
+const MINTED: u64 = 1_000_000;
+const BURNED: u64 = 300_000;
+
+/// A capped mint with `MINTED` paid out to a wallet's token account.
+fn minted() -> (Minting, Pubkey, Pubkey) {
+    let mut minting = Minting::new(MINTED);
+    let owner = minting.harness.funded_wallet();
+    let mint = minting.mint;
+    let tokens = minting.harness.create_token_account(&mint, &owner);
+    minting.mint_tokens(tokens, MINTED).unwrap();
+    (minting, owner, tokens)
+}
+
+/// Burns straight through the token program, behind the config's back.
+fn raw_burn(minting: &mut Minting, owner: Pubkey, tokens: Pubkey, amount: u64) {
+    let ix = spl_token::instruction::burn(&TOKEN, &tokens, &minting.mint, &owner, &[], amount).unwrap();
+    minting.harness.process(&[ix], &[owner]).unwrap();
+}
+
+fn sync_supply_ix(minting: &Minting) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::SyncSupply {
+            mint_config: minting.config(),
+            mint: minting.mint,
+        }
+        .to_account_metas(None),
+        data: instruction::SyncSupply {}.data(),
+    }
+}
+
+#[test]
+fn a_raw_burn_is_detected_and_corrected_by_sync() {
+    let (mut minting, owner, tokens) = minted();
+    raw_burn(&mut minting, owner, tokens, BURNED);
+
+    // The token program moved; the config didn't
+    assert_eq!(minting.supply(), MINTED - BURNED);
+    assert_eq!(minting.config_state().total_supply, MINTED);
+
+    // Anyone may sync
+    let caller = minting.harness.funded_wallet();
+    let ix = sync_supply_ix(&minting);
+    minting.harness.process(&[ix], &[caller]).unwrap();
+
+    let event = minting.harness.event::<SupplyReconciled>();
+    assert_eq!(event.mint, minting.mint);
+    assert_eq!(event.previous_supply, MINTED);
+    assert_eq!(event.new_supply, MINTED - BURNED);
+    assert_eq!(event.delta, -(BURNED as i128));
+    assert_eq!(minting.config_state().total_supply, minting.supply());
+
+    // Once aligned, syncing again is a no-op
+    let ix = sync_supply_ix(&minting);
+    minting.harness.process(&[ix], &[caller]).unwrap();
+    assert!(minting.harness.events::<SupplyReconciled>().is_empty());
+}
+
+#[test]
+fn the_programs_own_burn_keeps_both_figures_aligned() {
+    let (mut minting, owner, tokens) = minted();
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::BurnTokens {
+            mint_config: minting.config(),
+            mint: minting.mint,
+            token_account: tokens,
+            owner,
+            token_program: TOKEN,
+        }
+        .to_account_metas(None),
+        data: instruction::BurnTokens { amount: BURNED }.data(),
+    };
+    minting.harness.process(&[ix], &[owner]).unwrap();
+
+    assert_eq!(minting.harness.event::<TokensBurned>().amount, BURNED);
+    assert!(minting.harness.events::<SupplyReconciled>().is_empty());
+    assert_eq!(minting.supply(), MINTED - BURNED);
+    assert_eq!(minting.config_state().total_supply, minting.supply());
+}
+
+#[test]
+fn the_cap_counts_the_reconciled_supply() {
+    let (mut minting, owner, tokens) = minted();
+    assert_error(
+        minting.mint_tokens(tokens, 1),
+        GamingTokenError::SupplyCapExceeded,
+    );
+    raw_burn(&mut minting, owner, tokens, BURNED);
+
+    // Minting reconciles first, so the burned room is usable without a sync
+    minting.mint_tokens(tokens, BURNED).unwrap();
+    assert_eq!(minting.harness.event::<SupplyReconciled>().delta, -(BURNED as i128));
+    assert_eq!(minting.supply(), MINTED);
+    assert_eq!(minting.config_state().total_supply, MINTED);
+    assert_error(
+        minting.mint_tokens(tokens, 1),
+        GamingTokenError::SupplyCapExceeded,
+    );
+}
@@ -0,0 +1,194 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{
+    accounts, instruction, ClosedPool, GamingTokenError, MintConfigClosed, PoolClosed, PoolRegistryPage,
+};
+
+const STAKE: u64 = 1_000_000;
+const REWARDS: u64 = 86_400_000_000;
+
+fn close_pool_ix(staking: &Staking, pool: &Pool) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::ClosePool {
+            stake_pool: pool.address(),
+            registry_page: staking.registry_page(0),
+            closed_pool: pda(&[b"closed_pool", pool.mint.as_ref()]),
+            stake_vault: pool.stake_vault(),
+            reward_vault: pool.reward_vault(),
+            fee_vault: None,
+            vault_authority: pool.vault_authority(),
+            reward_vault_authority: pool.reward_vault_authority(),
+            authority_token_account: staking.admin_tokens(&pool.reward_mint),
+            mint: pool.mint,
+            reward_mint: pool.reward_mint,
+            authority: staking.admin,
+            system_program: system_program::ID,
+            token_program: pool.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::ClosePool {}.data(),
+    }
+}
+
+fn close_pool(staking: &mut Staking, pool: &Pool) -> TxResult {
+    let ix = close_pool_ix(staking, pool);
+    staking.admin(ix)
+}
+
+#[test]
+fn a_pool_with_positions_cannot_close() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+
+    assert_error(close_pool(&mut staking, &pool), GamingTokenError::PoolHasPositions);
+    assert!(staking.harness.account(&pool.address()).is_some());
+}
+
+#[test]
+fn a_pool_with_tokens_in_its_stake_vault_cannot_close() {
+    let (mut staking, pool) = Staking::with_pool();
+    // Sent straight to the vault rather than staked
+    let admin = staking.admin;
+    staking.harness.mint_to(&pool.mint, &pool.stake_vault(), &admin, 1);
+
+    assert_error(close_pool(&mut staking, &pool), GamingTokenError::PoolNotEmpty);
+}
+
+#[test]
+fn only_the_pool_authority_closes_it() {
+    let (mut staking, pool) = Staking::with_pool();
+    let stranger = staking.harness.funded_wallet();
+    let mut ix = close_pool_ix(&staking, &pool);
+    swap_account(&mut ix, staking.admin, stranger);
+
+    assert_error(
+        staking.harness.process(&[ix], &[stranger]),
+        ErrorCode::ConstraintHasOne,
+    );
+}
+
+#[test]
+fn closing_an_emptied_pool_sweeps_rewards_and_recovers_rent() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(DAY);
+    staking.unstake(&pool, &staker, 0).unwrap();
+
+    let leftover = staking.balance(&pool.reward_vault());
+    let admin_tokens = staking.balance(&staking.admin_tokens(&pool.mint));
+    let rent: u64 = [pool.address(), pool.stake_vault(), pool.reward_vault()]
+        .iter()
+        .map(|key| staking.harness.lamports(key))
+        .sum();
+    let admin_lamports = staking.harness.lamports(&staking.admin);
+
+    close_pool(&mut staking, &pool).unwrap();
+
+    let event = staking.harness.event::<PoolClosed>();
+    assert_eq!(event.mint, pool.mint);
+    assert_eq!(event.authority, staking.admin);
+    assert_eq!(event.swept_rewards, leftover);
+    assert_eq!(staking.balance(&staking.admin_tokens(&pool.mint)), admin_tokens + leftover);
+    for key in [pool.address(), pool.stake_vault(), pool.reward_vault()] {
+        assert!(staking.harness.account(&key).is_none(), "{key}");
+    }
+
+    // The tombstone's rent comes out of what the pool gave back
+    let closed = pda(&[b"closed_pool", pool.mint.as_ref()]);
+    let tombstone = staking.harness.lamports(&closed);
+    assert_eq!(staking.harness.lamports(&staking.admin), admin_lamports + rent - tombstone);
+    let record: ClosedPool = staking.harness.get(&closed);
+    assert_eq!(record.mint, pool.mint);
+    assert_eq!(record.closed_at, staking.harness.now());
+
+    let page: PoolRegistryPage = staking.harness.get(&staking.registry_page(0));
+    assert!(page.entries.iter().find(|entry| entry.pool == pool.address()).unwrap().closed);
+}
+
+#[test]
+fn a_closed_pools_seeds_cannot_be_reinitialized() {
+    let (mut staking, pool) = Staking::with_pool();
+    close_pool(&mut staking, &pool).unwrap();
+
+    let admin = staking.admin;
+    let ix = staking.create_pool_ix(&pool, admin, PoolParams::default());
+    assert_error(staking.admin(ix), GamingTokenError::PoolClosed);
+
+    // Anything still pointing at the old pool finds nothing there
+    let staker = staking.staker(&pool, STAKE);
+    assert_error(
+        staking.stake(&pool, &staker, 0, STAKE, 1),
+        ErrorCode::AccountNotInitialized,
+    );
+}
+
+fn close_mint_config_ix(minting: &Minting) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::CloseMintConfig {
+            mint_config: minting.config(),
+            mint: minting.mint,
+            authority: minting.authority,
+        }
+        .to_account_metas(None),
+        data: instruction::CloseMintConfig {}.data(),
+    }
+}
+
+#[test]
+fn a_live_mint_keeps_its_config() {
+    let mut minting = Minting::new(0);
+    let holder = minting.holder();
+    minting.mint_tokens(holder, 1).unwrap();
+
+    let ix = close_mint_config_ix(&minting);
+    assert_error(minting.process(ix), GamingTokenError::MintStillActive);
+
+    let stranger = minting.harness.funded_wallet();
+    let mut ix = close_mint_config_ix(&minting);
+    swap_account(&mut ix, minting.authority, stranger);
+    assert_error(
+        minting.harness.process(&[ix], &[stranger]),
+        ErrorCode::ConstraintHasOne,
+    );
+}
+
+#[test]
+fn an_unused_mint_closes_its_config_to_the_authority() {
+    let mut minting = Minting::new(0);
+    let rent = minting.harness.lamports(&minting.config());
+    let before = minting.harness.lamports(&minting.authority);
+
+    let ix = close_mint_config_ix(&minting);
+    minting.process(ix).unwrap();
+
+    assert_eq!(minting.harness.event::<MintConfigClosed>().supply, 0);
+    assert!(minting.harness.account(&minting.config()).is_none());
+    assert_eq!(minting.harness.lamports(&minting.authority), before + rent);
+}
+
+#[test]
+fn a_revoked_mint_closes_its_config_with_supply_outstanding() {
+    let mut minting = Minting::new(0);
+    let holder = minting.holder();
+    minting.mint_tokens(holder, 1_000).unwrap();
+    let ix = minting.manage_authority_ix(instruction::RevokeMintAuthority {});
+    minting.process(ix).unwrap();
+
+    let ix = close_mint_config_ix(&minting);
+    minting.process(ix).unwrap();
+
+    let event = minting.harness.event::<MintConfigClosed>();
+    assert_eq!(event.mint, minting.mint);
+    assert_eq!(event.supply, 1_000);
+    assert!(minting.harness.account(&minting.config()).is_none());
+    assert_eq!(minting.balance(&holder), 1_000);
+}
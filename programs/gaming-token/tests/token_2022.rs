@@ -0,0 +1,113 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::InstructionData;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::{TransferFeeConfig, MAX_FEE_BASIS_POINTS};
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use anchor_spl::token_2022::spl_token_2022::state::Mint;
+use common::*;
+use gaming_token::{instruction, GamingTokenError, TokensStaked, TransferFeeArgs};
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+/// 1% on every transfer.
+const FEE_BPS: u16 = 100;
+
+/// `initialize_mint` for a fresh mint key, asking for a transfer fee.
+fn initialize_fee_mint(minting: &mut Minting, transfer_fee_bps: u16) -> (Pubkey, TxResult) {
+    let mint = Pubkey::new_unique();
+    let mut ix = minting.initialize_mint_ix(mint, 0);
+    ix.data = instruction::InitializeMint {
+        token_name: "Sol-itaire Gold".to_string(),
+        token_symbol: "GOLD".to_string(),
+        decimals: 6,
+        max_supply: 0,
+        transfer_fee: Some(TransferFeeArgs {
+            transfer_fee_bps,
+            max_fee: u64::MAX,
+        }),
+    }
+    .data();
+    let authority = minting.authority;
+    let result = minting.harness.process(&[ix], &[authority, mint]);
+    (mint, result)
+}
+
+#[test]
+fn both_token_programs_mint_and_stake() {
+    for token_program in [TOKEN, TOKEN_2022] {
+        let mut minting = Minting::with_program(token_program, 0);
+        let holder = minting.holder();
+        minting.mint_tokens(holder, STAKE).unwrap();
+        assert_eq!(minting.balance(&holder), STAKE);
+        assert_eq!(minting.harness.account(&minting.mint).unwrap().owner, token_program);
+
+        let mut staking = Staking::new();
+        let pool = staking.single_token_pool(token_program, PoolParams::default());
+        let staker = staking.staker(&pool, STAKE);
+        staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+        staking.notify(&pool, REWARDS).unwrap();
+        staking.harness.set_time(staking.pool_state(&pool).period_finish);
+        staking.unstake(&pool, &staker, 0).unwrap();
+        assert_eq!(staking.balance(&staker.tokens), STAKE + REWARDS, "{token_program}");
+    }
+}
+
+#[test]
+fn a_transfer_fee_needs_token_2022() {
+    let mut minting = Minting::new(0);
+    let (_, result) = initialize_fee_mint(&mut minting, FEE_BPS);
+    assert_error(result, GamingTokenError::TransferFeeUnsupported);
+
+    let mut minting = Minting::with_program(TOKEN_2022, 0);
+    let (_, result) = initialize_fee_mint(&mut minting, MAX_FEE_BASIS_POINTS + 1);
+    assert_error(result, GamingTokenError::InvalidTransferFee);
+
+    let (mint, result) = initialize_fee_mint(&mut minting, FEE_BPS);
+    result.unwrap();
+    let account = minting.harness.account(&mint).unwrap();
+    assert_eq!(account.owner, TOKEN_2022);
+    let state = StateWithExtensions::<Mint>::unpack(&account.data).unwrap();
+    let fee = state.get_extension::<TransferFeeConfig>().unwrap();
+    assert_eq!(u16::from(fee.newer_transfer_fee.transfer_fee_basis_points), FEE_BPS);
+    assert_eq!(state.base.decimals, 6);
+}
+
+#[test]
+fn a_fee_mint_stakes_only_what_arrives() {
+    let mut staking = Staking::new();
+    let mint = staking.create_fee_mint(FEE_BPS, u64::MAX);
+    let pool = staking.create_pool(mint, mint, PoolParams::default()).unwrap();
+    let staker = staking.staker(&pool, STAKE);
+    let held = staking.balance(&staker.tokens);
+    assert_eq!(held, STAKE - STAKE / 100);
+
+    staking.stake(&pool, &staker, 0, held, 1).unwrap();
+    let arrived = held - held / 100;
+    assert_eq!(staking.harness.event::<TokensStaked>().amount, arrived);
+    assert_eq!(staking.position(&pool, &staker.wallet, 0).amount, arrived);
+    assert_eq!(staking.pool_state(&pool).total_staked, arrived);
+    assert_eq!(staking.balance(&pool.stake_vault()), arrived);
+}
+
+#[test]
+fn the_minimum_applies_after_the_fee() {
+    let mut staking = Staking::new();
+    let mint = staking.create_fee_mint(FEE_BPS, u64::MAX);
+    let params = PoolParams {
+        min_stake_amount: 1_000,
+        ..PoolParams::default()
+    };
+    let pool = staking.create_pool(mint, mint, params).unwrap();
+    let staker = staking.staker(&pool, 2_000);
+
+    // 1% of 1,000 is taken in transit, leaving 990
+    assert_error(
+        staking.stake(&pool, &staker, 0, 1_000, 1),
+        GamingTokenError::StakeBelowMinimum,
+    );
+    // The fee on 1,011 rounds up to 11
+    staking.stake(&pool, &staker, 0, 1_011, 1).unwrap();
+    assert_eq!(staking.position(&pool, &staker.wallet, 0).amount, 1_000);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::*;
+use gaming_token::instruction;
+
+const STAKE: u64 = 1_000_000_000;
+/// 10,000 a second over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+fn assert_in_step(staking: &Staking, pool: &Pool, step: &str) {
+    let state = staking.pool_state(pool);
+    assert_eq!(state.total_staked, staking.balance(&pool.stake_vault()), "after {step}");
+}
+
+#[test]
+fn total_staked_tracks_the_vault_through_every_flow() {
+    let (mut staking, pool) = Staking::with_pool();
+    staking
+        .update_pool(
+            &pool,
+            instruction::UpdateEarlyUnstakePenalty {
+                early_unstake_penalty_bps: 1_000,
+            },
+        )
+        .unwrap();
+    let alice = staking.staker(&pool, 4 * STAKE);
+    let bob = staking.staker(&pool, 4 * STAKE);
+    staking.notify(&pool, REWARDS).unwrap();
+
+    staking.stake(&pool, &alice, 0, STAKE, DAY).unwrap();
+    assert_in_step(&staking, &pool, "stake");
+    staking.stake(&pool, &bob, 0, 2 * STAKE, 10 * DAY).unwrap();
+    staking.stake(&pool, &alice, 1, STAKE, 30 * DAY).unwrap();
+    assert_in_step(&staking, &pool, "more stakes");
+
+    staking.harness.warp(DAY);
+    staking.add_to_stake(&pool, &alice, 0, STAKE).unwrap();
+    assert_in_step(&staking, &pool, "add_to_stake");
+    staking.compound(&pool, &bob, 0).unwrap();
+    assert_in_step(&staking, &pool, "compound");
+    staking.claim(&pool, &alice, 1).unwrap();
+    assert_in_step(&staking, &pool, "claim");
+
+    staking.harness.warp(DAY);
+    staking.partial_unstake(&pool, &alice, 0, STAKE / 2).unwrap();
+    assert_in_step(&staking, &pool, "partial_unstake");
+    staking.early_unstake(&pool, &alice, 1).unwrap();
+    assert_in_step(&staking, &pool, "early_unstake");
+
+    staking.harness.warp(10 * DAY);
+    staking.unstake(&pool, &bob, 0).unwrap();
+    assert_in_step(&staking, &pool, "unstake");
+    staking.unstake(&pool, &alice, 0).unwrap();
+    assert_in_step(&staking, &pool, "last unstake");
+
+    let state = staking.pool_state(&pool);
+    assert_eq!(state.total_staked, 0);
+    assert_eq!(state.total_positions, 0);
+    assert_eq!(state.total_weight, 0);
+}
+
+#[test]
+fn a_donation_to_the_vault_is_not_stake() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    staking.give(&pool.mint, &pool.stake_vault(), 1_000);
+
+    assert_eq!(staking.pool_state(&pool).total_staked, STAKE);
+    assert_eq!(staking.balance(&pool.stake_vault()), STAKE + 1_000);
+
+    staking.harness.warp(DAY);
+    staking.unstake(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.balance(&staker.tokens), STAKE);
+    assert_eq!(staking.balance(&pool.stake_vault()), 1_000);
+}
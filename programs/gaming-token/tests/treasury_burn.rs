@@ -0,0 +1,71 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{accounts, instruction, GamingTokenError, TokensBurned};
+
+const TREASURY: u64 = 1_000_000;
+const BURNED: u64 = 250_000;
+
+/// A mint with `TREASURY` paid into a token account its authority owns.
+fn funded_treasury() -> (Minting, Pubkey) {
+    let mut minting = Minting::new(0);
+    let (mint, authority) = (minting.mint, minting.authority);
+    let treasury = minting.harness.create_token_account(&mint, &authority);
+    minting.mint_tokens(treasury, TREASURY).unwrap();
+    (minting, treasury)
+}
+
+fn burn_ix(minting: &Minting, treasury_token_account: Pubkey, authority: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::BurnFromTreasury {
+            mint_config: minting.config(),
+            mint: minting.mint,
+            treasury_token_account,
+            authority,
+            token_program: minting.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::BurnFromTreasury { amount }.data(),
+    }
+}
+
+#[test]
+fn a_treasury_burn_lowers_both_supply_figures() {
+    let (mut minting, treasury) = funded_treasury();
+
+    let ix = burn_ix(&minting, treasury, minting.authority, BURNED);
+    minting.process(ix).unwrap();
+    let event = minting.harness.event::<TokensBurned>();
+    assert_eq!((event.from, event.amount), (treasury, BURNED));
+    assert_eq!(event.new_supply, TREASURY - BURNED);
+    assert_eq!(minting.balance(&treasury), TREASURY - BURNED);
+    assert_eq!(minting.supply(), TREASURY - BURNED);
+    assert_eq!(minting.config_state().total_supply, TREASURY - BURNED);
+}
+
+#[test]
+fn only_the_config_authority_burns_and_only_what_it_holds() {
+    let (mut minting, treasury) = funded_treasury();
+
+    // A stranger burning their own tokens through the treasury path
+    let stranger = minting.harness.funded_wallet();
+    let mint = minting.mint;
+    let stranger_tokens = minting.harness.create_token_account(&mint, &stranger);
+    minting.mint_tokens(stranger_tokens, BURNED).unwrap();
+    let ix = burn_ix(&minting, stranger_tokens, stranger, BURNED);
+    assert_error(minting.harness.process(&[ix], &[stranger]), ErrorCode::ConstraintHasOne);
+
+    // The authority can't reach into someone else's account either
+    let ix = burn_ix(&minting, stranger_tokens, minting.authority, BURNED);
+    assert_error(minting.process(ix), ErrorCode::ConstraintTokenOwner);
+
+    let ix = burn_ix(&minting, treasury, minting.authority, TREASURY + 1);
+    assert_error(minting.process(ix), GamingTokenError::InsufficientFunds);
+    assert_eq!(minting.balance(&treasury), TREASURY);
+    assert_eq!(minting.config_state().total_supply, TREASURY + BURNED);
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::InstructionData;
+use common::*;
+use gaming_token::{instruction, GamingTokenError, RewardsClaimed, UnstakeRequested, UnstakeWithdrawn};
+
+const STAKE: u64 = 1_000_000_000;
+/// 10,000 a second over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+const COOLDOWN: i64 = 2 * DAY;
+
+/// A funded pool with a two-day cooldown, a day into its period, with Alice
+/// and Bob staked equally.
+fn two_stakers() -> (Staking, Pool, [Staker; 2]) {
+    let (mut staking, pool) = Staking::with_pool();
+    staking
+        .update_pool(
+            &pool,
+            instruction::UpdateUnstakeCooldown {
+                unstake_cooldown_seconds: COOLDOWN,
+            },
+        )
+        .unwrap();
+    let stakers = [(); 2].map(|_| {
+        let staker = staking.staker(&pool, STAKE);
+        staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+        staker
+    });
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(DAY);
+    (staking, pool, stakers)
+}
+
+fn request(staking: &mut Staking, pool: &Pool, staker: &Staker) -> TxResult {
+    staking.update_position(pool, staker, 0, instruction::RequestUnstake {})
+}
+
+fn cancel(staking: &mut Staking, pool: &Pool, staker: &Staker) -> TxResult {
+    staking.update_position(pool, staker, 0, instruction::CancelUnstake {})
+}
+
+fn withdraw(staking: &mut Staking, pool: &Pool, staker: &Staker) -> TxResult {
+    let ix = Instruction {
+        data: instruction::WithdrawUnstaked {}.data(),
+        ..staking.unstake_ix(pool, staker, 0)
+    };
+    staking.harness.process(&[ix], &[staker.wallet])
+}
+
+#[test]
+fn unbonded_tokens_leave_once_the_cooldown_is_over() {
+    let (mut staking, pool, [alice, bob]) = two_stakers();
+    let weight = staking.pool_state(&pool).total_weight;
+    assert_error(withdraw(&mut staking, &pool, &alice), GamingTokenError::PositionNotUnbonding);
+
+    request(&mut staking, &pool, &alice).unwrap();
+    let requested = staking.harness.event::<UnstakeRequested>();
+    assert_eq!(requested.available_at, staking.harness.now() + COOLDOWN);
+    assert_eq!(requested.accrued_rewards, REWARDS / 200);
+    assert_eq!(staking.pool_state(&pool).total_weight, weight / 2);
+    assert_error(request(&mut staking, &pool, &alice), GamingTokenError::PositionUnbonding);
+
+    staking.harness.warp(COOLDOWN - 1);
+    assert_error(withdraw(&mut staking, &pool, &alice), GamingTokenError::CooldownActive);
+    staking.harness.warp(1);
+    withdraw(&mut staking, &pool, &alice).unwrap();
+
+    // Accrual stopped at the request; Bob earned the whole rate meanwhile
+    let withdrawn = staking.harness.event::<UnstakeWithdrawn>();
+    assert_eq!((withdrawn.principal, withdrawn.reward), (STAKE, REWARDS / 200));
+    assert_eq!(staking.balance(&alice.tokens), STAKE + REWARDS / 200);
+    assert!(staking.harness.account(&pool.stake_account(&alice.wallet, 0)).is_none());
+    staking.claim(&pool, &bob, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().gross, REWARDS / 200 + REWARDS / 50);
+}
+
+#[test]
+fn a_cancelled_request_restores_the_weight_and_keeps_what_was_owed() {
+    let (mut staking, pool, [alice, _]) = two_stakers();
+    let weight = staking.pool_state(&pool).total_weight;
+    assert_error(cancel(&mut staking, &pool, &alice), GamingTokenError::PositionNotUnbonding);
+
+    request(&mut staking, &pool, &alice).unwrap();
+    staking.harness.warp(DAY);
+    cancel(&mut staking, &pool, &alice).unwrap();
+    let position = staking.position(&pool, &alice.wallet, 0);
+    assert_eq!((position.unbonding_since, position.available_at), (None, 0));
+    assert_eq!(staking.pool_state(&pool).total_weight, weight);
+    assert_error(withdraw(&mut staking, &pool, &alice), GamingTokenError::PositionNotUnbonding);
+
+    // The day before the request, nothing while unbonding, half the rate again after
+    staking.harness.warp(DAY);
+    staking.claim(&pool, &alice, 0).unwrap();
+    assert_eq!(staking.harness.event::<RewardsClaimed>().gross, REWARDS / 100);
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use gaming_token::GamingTokenError;
+
+const STAKE: u64 = 1_000_000;
+
+/// A dual-token pool with one staker past their lock.
+fn unlocked() -> (Staking, Pool, Staker) {
+    let mut staking = Staking::new();
+    let mint = staking.create_mint(TOKEN);
+    let reward_mint = staking.create_mint(TOKEN);
+    let pool = staking.create_pool(mint, reward_mint, PoolParams::default()).unwrap();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, DAY).unwrap();
+    staking.harness.warp(DAY);
+    (staking, pool, staker)
+}
+
+fn assert_untouched(staking: &Staking, pool: &Pool, staker: &Staker) {
+    assert_eq!(staking.balance(&pool.stake_vault()), STAKE);
+    assert_eq!(staking.position(pool, &staker.wallet, 0).amount, STAKE);
+}
+
+#[test]
+fn someone_else_cannot_unstake_the_position() {
+    let (mut staking, pool, staker) = unlocked();
+    let thief = staking.staker(&pool, 0);
+
+    let mut ix = staking.unstake_ix(&pool, &thief, 0);
+    swap_account(&mut ix, pool.stake_account(&thief.wallet, 0), pool.stake_account(&staker.wallet, 0));
+    swap_account(&mut ix, pool.stake_positions(&thief.wallet), pool.stake_positions(&staker.wallet));
+    swap_account(&mut ix, pool.staker_stats(&thief.wallet), pool.staker_stats(&staker.wallet));
+    assert_error(staking.harness.process(&[ix], &[thief.wallet]), GamingTokenError::Unauthorized);
+    assert_untouched(&staking, &pool, &staker);
+}
+
+#[test]
+fn principal_and_rewards_only_go_to_the_owner() {
+    let (mut staking, pool, staker) = unlocked();
+    let other = staking.staker(&pool, 0);
+
+    let mut ix = staking.unstake_ix(&pool, &staker, 0);
+    swap_account(&mut ix, staker.tokens, other.tokens);
+    assert_error(staking.harness.process(&[ix], &[staker.wallet]), ErrorCode::ConstraintTokenOwner);
+
+    let mut ix = staking.unstake_ix(&pool, &staker, 0);
+    swap_account(&mut ix, staker.rewards, other.rewards);
+    assert_error(staking.harness.process(&[ix], &[staker.wallet]), ErrorCode::ConstraintTokenOwner);
+
+    // Principal into the reward-mint account
+    let mut ix = staking.unstake_ix(&pool, &staker, 0);
+    swap_account(&mut ix, staker.tokens, staker.rewards);
+    assert_error(staking.harness.process(&[ix], &[staker.wallet]), ErrorCode::ConstraintTokenMint);
+
+    assert_untouched(&staking, &pool, &staker);
+    assert_eq!(staking.balance(&other.tokens), 0);
+}
+
+#[test]
+fn only_the_pool_vaults_and_their_authorities_are_accepted() {
+    let (mut staking, pool, staker) = unlocked();
+    let admin = staking.admin;
+    let decoy_vault = staking.harness.create_token_account(&pool.mint, &admin);
+    let decoy_rewards = staking.harness.create_token_account(&pool.reward_mint, &admin);
+
+    for (from, to) in [
+        (pool.stake_vault(), decoy_vault),
+        (pool.reward_vault(), decoy_rewards),
+        (pool.vault_authority(), admin),
+        (pool.reward_vault_authority(), admin),
+    ] {
+        let mut ix = staking.unstake_ix(&pool, &staker, 0);
+        swap_account(&mut ix, from, to);
+        assert_error(staking.harness.process(&[ix], &[staker.wallet]), ErrorCode::ConstraintSeeds);
+    }
+    assert_untouched(&staking, &pool, &staker);
+
+    staking.unstake(&pool, &staker, 0).unwrap();
+    assert_eq!(staking.balance(&staker.tokens), STAKE);
+}
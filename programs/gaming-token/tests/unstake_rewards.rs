@@ -0,0 +1,44 @@
+mod common;
+
+use common::*;
+use gaming_token::{RewardsClaimed, TokensUnstaked};
+
+const STAKE: u64 = 1_000_000_000;
+/// Divides evenly over the default 100-day period.
+const REWARDS: u64 = 86_400_000_000;
+
+/// Stakes `STAKE` for the whole reward period, optionally claiming halfway,
+/// and returns `(claimed midway, unstake reward)`.
+fn run(claim_midway: bool) -> (u64, u64) {
+    let (mut staking, pool) = Staking::with_pool();
+    let duration = PoolParams::default().reward_duration;
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, duration).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+
+    let mut claimed = 0;
+    if claim_midway {
+        staking.harness.warp(duration / 2);
+        staking.claim(&pool, &staker, 0).unwrap();
+        claimed = staking.harness.event::<RewardsClaimed>().net;
+        assert!(claimed > 0);
+    }
+
+    staking.harness.set_time(staking.pool_state(&pool).period_finish);
+    staking.unstake(&pool, &staker, 0).unwrap();
+    let unstaked = staking.harness.event::<TokensUnstaked>();
+    assert_eq!(unstaked.principal, STAKE);
+    assert_eq!(staking.balance(&staker.tokens), STAKE + claimed + unstaked.reward);
+    (claimed, unstaked.reward)
+}
+
+#[test]
+fn claiming_midway_does_not_pay_the_period_twice() {
+    let (_, full_period) = run(false);
+    let (claimed, remainder) = run(true);
+
+    // A lone staker earns the whole emission
+    assert_eq!(full_period, REWARDS);
+    assert_eq!(claimed + remainder, full_period);
+    assert!(remainder < full_period);
+}
@@ -0,0 +1,202 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{instruction::Instruction, sysvar};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{accounts, instruction, GamingTokenError, VestedTokensClaimed, VestingRevoked};
+
+const TOTAL: u64 = 4_000_000;
+const CLIFF: i64 = DAY;
+/// A quarter of `TOTAL` vests each day.
+const DURATION: i64 = 4 * DAY;
+
+/// A team member with a token account for the gaming token.
+struct Beneficiary {
+    wallet: Pubkey,
+    tokens: Pubkey,
+}
+
+fn beneficiary(minting: &mut Minting) -> Beneficiary {
+    let wallet = minting.harness.funded_wallet();
+    let mint = minting.mint;
+    let tokens = minting.harness.create_token_account(&mint, &wallet);
+    Beneficiary { wallet, tokens }
+}
+
+fn vesting(minting: &Minting, beneficiary: &Beneficiary, schedule_id: u64) -> Pubkey {
+    pda(&[
+        b"vesting",
+        minting.mint.as_ref(),
+        beneficiary.wallet.as_ref(),
+        &schedule_id.to_le_bytes(),
+    ])
+}
+
+fn vesting_vault(minting: &Minting, beneficiary: &Beneficiary, schedule_id: u64) -> Pubkey {
+    pda(&[b"vesting_vault", vesting(minting, beneficiary, schedule_id).as_ref()])
+}
+
+/// Vests `total_amount` over `DURATION` from now with a `CLIFF`; returns the start.
+fn create(minting: &mut Minting, beneficiary: &Beneficiary, schedule_id: u64, total_amount: u64) -> i64 {
+    let start = minting.harness.now();
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::CreateVesting {
+            mint_config: minting.config(),
+            mint: minting.mint,
+            mint_authority: minting.mint_authority(),
+            vesting: vesting(minting, beneficiary, schedule_id),
+            vesting_vault: vesting_vault(minting, beneficiary, schedule_id),
+            authority: minting.authority,
+            system_program: system_program::ID,
+            token_program: minting.token_program,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::CreateVesting {
+            beneficiary: beneficiary.wallet,
+            schedule_id,
+            total_amount,
+            start,
+            cliff_seconds: CLIFF,
+            duration_seconds: DURATION,
+        }
+        .data(),
+    };
+    minting.process(ix).unwrap();
+    start
+}
+
+fn claim(minting: &mut Minting, beneficiary: &Beneficiary, schedule_id: u64) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::ClaimVested {
+            vesting: vesting(minting, beneficiary, schedule_id),
+            vesting_vault: vesting_vault(minting, beneficiary, schedule_id),
+            beneficiary_token_account: beneficiary.tokens,
+            beneficiary: beneficiary.wallet,
+            mint: minting.mint,
+            token_program: minting.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::ClaimVested {}.data(),
+    };
+    minting.harness.process(&[ix], &[beneficiary.wallet])
+}
+
+fn claimed(minting: &mut Minting, beneficiary: &Beneficiary, schedule_id: u64) -> u64 {
+    claim(minting, beneficiary, schedule_id).unwrap();
+    minting.harness.event::<VestedTokensClaimed>().amount
+}
+
+fn revoke(minting: &mut Minting, beneficiary: &Beneficiary, schedule_id: u64, destination: Pubkey) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::RevokeVesting {
+            mint_config: minting.config(),
+            vesting: vesting(minting, beneficiary, schedule_id),
+            vesting_vault: vesting_vault(minting, beneficiary, schedule_id),
+            destination,
+            authority: minting.authority,
+            mint: minting.mint,
+            token_program: minting.token_program,
+        }
+        .to_account_metas(None),
+        data: instruction::RevokeVesting {}.data(),
+    };
+    minting.process(ix)
+}
+
+#[test]
+fn nothing_is_claimable_before_the_cliff_and_it_catches_up_on_it() {
+    let mut minting = Minting::new(0);
+    let alice = beneficiary(&mut minting);
+    let start = create(&mut minting, &alice, 0, TOTAL);
+    assert_eq!(minting.balance(&vesting_vault(&minting, &alice, 0)), TOTAL);
+    assert_eq!(minting.supply(), TOTAL);
+
+    minting.harness.set_time(start + CLIFF - 1);
+    assert_error(claim(&mut minting, &alice, 0), GamingTokenError::NothingVested);
+
+    // Exactly at the cliff the first day's worth is released at once
+    minting.harness.set_time(start + CLIFF);
+    assert_eq!(claimed(&mut minting, &alice, 0), TOTAL / 4);
+    assert_error(claim(&mut minting, &alice, 0), GamingTokenError::NothingVested);
+
+    minting.harness.set_time(start + CLIFF + DAY / 2);
+    assert_eq!(claimed(&mut minting, &alice, 0), TOTAL / 8);
+    assert_eq!(minting.balance(&alice.tokens), 3 * TOTAL / 8);
+}
+
+#[test]
+fn past_the_end_the_rest_is_released_once() {
+    let mut minting = Minting::new(0);
+    let alice = beneficiary(&mut minting);
+    let start = create(&mut minting, &alice, 0, TOTAL);
+
+    minting.harness.set_time(start + 2 * DAY);
+    assert_eq!(claimed(&mut minting, &alice, 0), TOTAL / 2);
+    minting.harness.set_time(start + DURATION + 10 * DAY);
+    assert_eq!(claimed(&mut minting, &alice, 0), TOTAL / 2);
+    let event = minting.harness.event::<VestedTokensClaimed>();
+    assert_eq!((event.released_amount, event.total_amount), (TOTAL, TOTAL));
+
+    minting.harness.warp(DAY);
+    assert_error(claim(&mut minting, &alice, 0), GamingTokenError::NothingVested);
+    assert_eq!(minting.balance(&alice.tokens), TOTAL);
+    assert_eq!(minting.balance(&vesting_vault(&minting, &alice, 0)), 0);
+}
+
+#[test]
+fn a_revoked_schedule_keeps_what_had_vested_claimable() {
+    let mut minting = Minting::new(0);
+    let alice = beneficiary(&mut minting);
+    let treasury = minting.holder();
+    let start = create(&mut minting, &alice, 0, TOTAL);
+
+    minting.harness.set_time(start + DAY);
+    assert_eq!(claimed(&mut minting, &alice, 0), TOTAL / 4);
+    minting.harness.set_time(start + 3 * DAY);
+    revoke(&mut minting, &alice, 0, treasury).unwrap();
+    let revoked = minting.harness.event::<VestingRevoked>();
+    assert_eq!((revoked.vested_amount, revoked.returned_amount), (3 * TOTAL / 4, TOTAL / 4));
+    assert_eq!(minting.balance(&treasury), TOTAL / 4);
+    assert_error(
+        revoke(&mut minting, &alice, 0, treasury),
+        GamingTokenError::VestingRevoked,
+    );
+
+    // Vesting stopped at the revoke; the half vested but unclaimed still pays out
+    minting.harness.set_time(start + DURATION + DAY);
+    assert_eq!(claimed(&mut minting, &alice, 0), TOTAL / 2);
+    assert_error(claim(&mut minting, &alice, 0), GamingTokenError::NothingVested);
+    assert_eq!(minting.balance(&alice.tokens), 3 * TOTAL / 4);
+    assert_eq!(minting.balance(&vesting_vault(&minting, &alice, 0)), 0);
+}
+
+#[test]
+fn one_beneficiary_holds_independent_schedules() {
+    let mut minting = Minting::new(0);
+    let alice = beneficiary(&mut minting);
+    let treasury = minting.holder();
+    let start = create(&mut minting, &alice, 0, TOTAL);
+    minting.harness.set_time(start + DAY);
+    let later = create(&mut minting, &alice, 1, 2 * TOTAL);
+    assert_eq!(minting.supply(), 3 * TOTAL);
+
+    // The first schedule is at its cliff, the second only starting
+    assert_eq!(claimed(&mut minting, &alice, 0), TOTAL / 4);
+    assert_error(claim(&mut minting, &alice, 1), GamingTokenError::NothingVested);
+
+    minting.harness.set_time(later + DAY);
+    revoke(&mut minting, &alice, 0, treasury).unwrap();
+    assert_eq!(minting.balance(&treasury), TOTAL / 2);
+    assert_eq!(claimed(&mut minting, &alice, 0), TOTAL / 4);
+    assert_eq!(claimed(&mut minting, &alice, 1), TOTAL / 2);
+
+    minting.harness.set_time(later + DURATION);
+    assert_error(claim(&mut minting, &alice, 0), GamingTokenError::NothingVested);
+    assert_eq!(claimed(&mut minting, &alice, 1), 3 * TOTAL / 2);
+    assert_eq!(minting.balance(&alice.tokens), TOTAL / 2 + 2 * TOTAL);
+}
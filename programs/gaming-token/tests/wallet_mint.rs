@@ -0,0 +1,106 @@
+mod common;
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id};
+use common::*;
+use gaming_token::{accounts, instruction, BatchMinted, GamingTokenError};
+
+fn ata(minting: &Minting, wallet: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(wallet, &minting.mint, &minting.token_program)
+}
+
+fn mint_to_wallet(minting: &mut Minting, recipient: Pubkey, amount: u64) -> TxResult {
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: accounts::MintTokensToWallet {
+            mint_config: minting.config(),
+            mint: minting.mint,
+            mint_authority: minting.mint_authority(),
+            recipient,
+            token_account: ata(minting, &recipient),
+            authority: minting.authority,
+            token_program: minting.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::MintTokensToWallet { amount }.data(),
+    };
+    minting.process(ix)
+}
+
+/// `batch_mint_to_wallets` paying `(wallet, token account, amount)` entries in order.
+fn batch_mint_to_wallets_ix(minting: &Minting, entries: &[(Pubkey, Pubkey, u64)]) -> Instruction {
+    let mut accounts = accounts::BatchMintToWallets {
+        mint_config: minting.config(),
+        mint: minting.mint,
+        mint_authority: minting.mint_authority(),
+        authority: minting.authority,
+        token_program: minting.token_program,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    for (wallet, token_account, _) in entries {
+        accounts.push(AccountMeta::new_readonly(*wallet, false));
+        accounts.push(AccountMeta::new(*token_account, false));
+    }
+    Instruction {
+        program_id: gaming_token::ID,
+        accounts,
+        data: instruction::BatchMintToWallets {
+            amounts: entries.iter().map(|entry| entry.2).collect(),
+        }
+        .data(),
+    }
+}
+
+#[test]
+fn a_missing_ata_is_created_and_then_reused() {
+    let mut minting = Minting::new(0);
+    let wallet = minting.harness.funded_wallet();
+    let token_account = ata(&minting, &wallet);
+    assert!(minting.harness.account(&token_account).is_none());
+
+    mint_to_wallet(&mut minting, wallet, 100).unwrap();
+    mint_to_wallet(&mut minting, wallet, 50).unwrap();
+    let state = minting.harness.token_account(&token_account);
+    assert_eq!((state.owner, state.mint), (wallet, minting.mint));
+    assert_eq!(state.amount, 150);
+    assert_eq!(minting.config_state().total_supply, 150);
+}
+
+#[test]
+fn a_batch_mixes_new_and_existing_atas() {
+    let mut minting = Minting::new(0);
+    let (alice, bob) = (minting.harness.funded_wallet(), minting.harness.funded_wallet());
+    mint_to_wallet(&mut minting, alice, 1).unwrap();
+
+    let entries = [(alice, ata(&minting, &alice), 100), (bob, ata(&minting, &bob), 200)];
+    let ix = batch_mint_to_wallets_ix(&minting, &entries);
+    minting.process(ix).unwrap();
+
+    let event = minting.harness.event::<BatchMinted>();
+    assert_eq!((event.recipients, event.total_amount), (2, 300));
+    assert_eq!(minting.balance(&entries[0].1), 101);
+    assert_eq!(minting.balance(&entries[1].1), 200);
+}
+
+#[test]
+fn a_batch_rejects_an_account_that_is_not_the_wallets_ata() {
+    let mut minting = Minting::new(0);
+    let (alice, bob) = (minting.harness.funded_wallet(), minting.harness.funded_wallet());
+
+    // Bob's ATA paired with Alice
+    let ix = batch_mint_to_wallets_ix(&minting, &[(alice, ata(&minting, &bob), 100)]);
+    assert_error(minting.process(ix), GamingTokenError::InvalidAssociatedTokenAccount);
+
+    // A plain token account Alice owns isn't her ATA either
+    let mint = minting.mint;
+    let plain = minting.harness.create_token_account(&mint, &alice);
+    let ix = batch_mint_to_wallets_ix(&minting, &[(alice, plain, 100)]);
+    assert_error(minting.process(ix), GamingTokenError::InvalidAssociatedTokenAccount);
+    assert_eq!(minting.supply(), 0);
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::*;
+use gaming_token::{GamingTokenError, RewardsClaimed};
+
+const STAKE: u64 = 1_000_000;
+const REWARDS: u64 = 86_400_000_000;
+
+#[test]
+fn claiming_nothing_fails_and_keeps_the_checkpoint() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    let staked_at = staking.position(&pool, &staker.wallet, 0).last_reward_claim;
+
+    // No reward period yet
+    staking.harness.warp(DAY);
+    assert_error(staking.claim(&pool, &staker, 0), GamingTokenError::NothingToClaim);
+    assert!(staking.harness.events::<RewardsClaimed>().is_empty());
+    assert_eq!(staking.position(&pool, &staker.wallet, 0).last_reward_claim, staked_at);
+
+    // The next real claim is measured from the last one that paid
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(DAY);
+    staking.claim(&pool, &staker, 0).unwrap();
+    let claimed = staking.harness.event::<RewardsClaimed>();
+    assert_eq!(claimed.gross, REWARDS / 100);
+    assert_eq!(claimed.since_last_claim, 2 * DAY);
+}
+
+#[test]
+fn claiming_twice_in_one_slot_fails_the_second_time() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    staking.notify(&pool, REWARDS).unwrap();
+    staking.harness.warp(DAY);
+
+    staking.claim(&pool, &staker, 0).unwrap();
+    let balance = staking.balance(&staker.tokens);
+    assert_error(staking.claim(&pool, &staker, 0), GamingTokenError::NothingToClaim);
+    assert_eq!(staking.balance(&staker.tokens), balance);
+}
+
+#[test]
+fn compounding_nothing_fails_the_same_way() {
+    let (mut staking, pool) = Staking::with_pool();
+    let staker = staking.staker(&pool, STAKE);
+    staking.stake(&pool, &staker, 0, STAKE, 1).unwrap();
+    staking.harness.warp(DAY);
+
+    assert_error(staking.compound(&pool, &staker, 0), GamingTokenError::NothingToClaim);
+    let position = staking.position(&pool, &staker.wallet, 0);
+    assert_eq!(position.amount, STAKE);
+}
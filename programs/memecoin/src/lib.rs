@@ -1,8 +1,23 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, MintTo, Transfer};
+use anchor_lang::solana_program::keccak;
+use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::spl_token::instruction::AuthorityType;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Mint, MintTo, SetAuthority, Transfer};
 
 declare_id!("A1WF2rG5Vs5tG6nhq2ZeDEN9hyESrWV3dtyq1XdBWkqT");
 
+/// The gaming-token program, whose `redemption_authority` PDA is the only
+/// caller `redeem_for_gaming_burn` accepts.
+pub const GAMING_TOKEN_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("DhkqYC1mAnZ41dgPz6NDLovGM6zxE1j7wHLBAizYkNB8");
+
+/// Rewards per `distribute_game_rewards_batch`, to stay inside the compute budget.
+pub const MAX_REWARD_BATCH: usize = 20;
+
+/// Deepest merkle proof `claim_merkle_airdrop` accepts; 2^24 leaves is plenty.
+pub const MAX_MERKLE_PROOF_LEN: usize = 24;
+
 #[program]
 pub mod memecoin {
     use super::*;
@@ -31,12 +46,22 @@ pub mod memecoin {
         memecoin_config.circulating_supply = 0;
         memecoin_config.is_initialized = true;
         memecoin_config.created_at = clock.unix_timestamp;
-
-        // Initialize distribution pools
-        memecoin_config.game_rewards_pool = total_supply * 40 / 100; // 40%
-        memecoin_config.liquidity_pool = total_supply * 30 / 100;   // 30%
-        memecoin_config.team_allocation = total_supply * 20 / 100;  // 20%
-        memecoin_config.community_allocation = total_supply * 10 / 100; // 10%
+        memecoin_config.supply_finalized = false;
+        memecoin_config.game_rewards_vault = Pubkey::default();
+        memecoin_config.liquidity_vault = Pubkey::default();
+        memecoin_config.team_vault = Pubkey::default();
+        memecoin_config.community_vault = Pubkey::default();
+        memecoin_config.distributed_at = 0;
+
+        // Initialize distribution pools; community takes whatever the
+        // rounding left, so the four always add up to the total supply
+        memecoin_config.game_rewards_pool = percent_of(total_supply, 40); // 40%
+        memecoin_config.liquidity_pool = percent_of(total_supply, 30);   // 30%
+        memecoin_config.team_allocation = percent_of(total_supply, 20);  // 20%
+        memecoin_config.community_allocation = total_supply
+            - memecoin_config.game_rewards_pool
+            - memecoin_config.liquidity_pool
+            - memecoin_config.team_allocation; // 10% plus rounding
 
         emit!(MemecoinInitialized {
             mint: memecoin_config.mint,
@@ -63,6 +88,13 @@ pub mod memecoin {
             memecoin_config.circulating_supply == 0,
             MemecoinError::AlreadyDistributed
         );
+        require!(!memecoin_config.supply_finalized, MemecoinError::SupplyFinalized);
+
+        // Every later instruction checks pool accounts against these
+        memecoin_config.game_rewards_vault = ctx.accounts.game_rewards_account.key();
+        memecoin_config.liquidity_vault = ctx.accounts.liquidity_account.key();
+        memecoin_config.team_vault = ctx.accounts.team_account.key();
+        memecoin_config.community_vault = ctx.accounts.community_account.key();
 
         // Distribute to game rewards pool
         let cpi_accounts = MintTo {
@@ -104,12 +136,35 @@ pub mod memecoin {
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
         token::mint_to(cpi_ctx, memecoin_config.community_allocation)?;
 
-        memecoin_config.circulating_supply = memecoin_config.total_supply;
+        let minted = memecoin_config
+            .game_rewards_pool
+            .checked_add(memecoin_config.liquidity_pool)
+            .and_then(|minted| minted.checked_add(memecoin_config.team_allocation))
+            .and_then(|minted| minted.checked_add(memecoin_config.community_allocation))
+            .ok_or(MemecoinError::InvalidSupply)?;
+        memecoin_config.circulating_supply = minted;
+
+        // Drop the mint authority for good so the supply can never grow
+        let cpi_accounts = SetAuthority {
+            current_authority: ctx.accounts.authority.to_account_info(),
+            account_or_mint: ctx.accounts.mint.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::set_authority(cpi_ctx, AuthorityType::MintTokens, None)?;
+        memecoin_config.supply_finalized = true;
+        memecoin_config.distributed_at = Clock::get()?.unix_timestamp;
 
         emit!(InitialSupplyDistributed {
             mint: memecoin_config.mint,
-            total_amount: memecoin_config.total_supply,
-            timestamp: Clock::get().unwrap().unix_timestamp,
+            total_amount: minted,
+            timestamp: memecoin_config.distributed_at,
+        });
+
+        emit!(SupplyFinalized {
+            mint: memecoin_config.mint,
+            total_supply: minted,
+            timestamp: Clock::get()?.unix_timestamp,
         });
 
         Ok(())
@@ -136,12 +191,16 @@ pub mod memecoin {
             ctx.accounts.game_rewards_account.amount >= amount,
             MemecoinError::InsufficientRewards
         );
+        memecoin_config.game_rewards_pool = memecoin_config
+            .game_rewards_pool
+            .checked_sub(amount)
+            .ok_or(MemecoinError::InsufficientRewards)?;
 
         // Transfer from game rewards pool to player
         let rewards_seeds = &[
             b"rewards_pool",
             memecoin_config.mint.as_ref(),
-            &[ctx.accounts.rewards_authority.bump],
+            &[ctx.bumps.rewards_authority],
         ];
         let signer = &[&rewards_seeds[..]];
 
@@ -170,6 +229,124 @@ pub mod memecoin {
         Ok(())
     }
 
+    /// `distribute_game_rewards` for many players at once. `remaining_accounts`
+    /// holds a `[player token account, receipt]` pair per entry, the receipt
+    /// being the `[b"reward", player, game_id_hash(game_id)]` PDA created here,
+    /// the same receipt `distribute_game_rewards` writes. Any
+    /// bad entry, including one already paid, fails the whole batch. Each
+    /// payout gets its own `BatchGameRewardDistributed` for the indexer,
+    /// followed by one `GameRewardsBatchDistributed` with the totals.
+    pub fn distribute_game_rewards_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DistributeGameRewardsBatch<'info>>,
+        players: Vec<Pubkey>,
+        amounts: Vec<u64>,
+        game_id_hashes: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let count = players.len();
+        require!(count > 0 && count <= MAX_REWARD_BATCH, MemecoinError::InvalidBatch);
+        require!(
+            amounts.len() == count
+                && game_id_hashes.len() == count
+                && ctx.remaining_accounts.len() == count * 2,
+            MemecoinError::InvalidBatch
+        );
+
+        let total = amounts
+            .iter()
+            .try_fold(0u64, |total, amount| total.checked_add(*amount))
+            .ok_or(MemecoinError::InvalidAmount)?;
+        require!(
+            ctx.accounts.game_rewards_account.amount >= total,
+            MemecoinError::InsufficientRewards
+        );
+
+        let memecoin_config = &mut ctx.accounts.memecoin_config;
+        memecoin_config.game_rewards_pool = memecoin_config
+            .game_rewards_pool
+            .checked_sub(total)
+            .ok_or(MemecoinError::InsufficientRewards)?;
+
+        let mint_key = memecoin_config.mint;
+        let rewards_seeds = &[
+            b"rewards_pool",
+            mint_key.as_ref(),
+            &[ctx.bumps.rewards_authority],
+        ];
+        let signer = &[&rewards_seeds[..]];
+        let rent = Rent::get()?;
+        let timestamp = Clock::get()?.unix_timestamp;
+
+        for (index, accounts) in ctx.remaining_accounts.chunks(2).enumerate() {
+            let player = players[index];
+            let amount = amounts[index];
+            let game_id_hash = game_id_hashes[index];
+            require!(amount > 0, MemecoinError::InvalidAmount);
+
+            let player_account = Account::<TokenAccount>::try_from(&accounts[0])?;
+            require_keys_eq!(player_account.mint, mint_key, MemecoinError::InvalidBatch);
+            require_keys_eq!(player_account.owner, player, MemecoinError::InvalidBatch);
+
+            // An existing receipt means the reward was already paid; allocating
+            // it again fails
+            let receipt = &accounts[1];
+            let (receipt_key, receipt_bump) = Pubkey::find_program_address(
+                &[b"reward", player.as_ref(), game_id_hash.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(receipt.key(), receipt_key, MemecoinError::InvalidBatch);
+
+            let receipt_seeds = &[
+                b"reward",
+                player.as_ref(),
+                game_id_hash.as_ref(),
+                &[receipt_bump],
+            ];
+            let receipt_signer = &[&receipt_seeds[..]];
+            create_pda_account(
+                &ctx.accounts.authority,
+                receipt,
+                &ctx.accounts.system_program,
+                receipt_signer,
+                rent.minimum_balance(BatchRewardReceipt::SPACE),
+                BatchRewardReceipt::SPACE,
+                ctx.program_id,
+            )?;
+            let record = BatchRewardReceipt {
+                player,
+                game_id_hash,
+                amount,
+                timestamp,
+            };
+            record.try_serialize(&mut &mut receipt.try_borrow_mut_data()?[..])?;
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.game_rewards_account.to_account_info(),
+                to: player_account.to_account_info(),
+                authority: ctx.accounts.rewards_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, amount)?;
+
+            emit!(BatchGameRewardDistributed {
+                player,
+                amount,
+                game_id_hash,
+                timestamp,
+            });
+        }
+
+        emit!(GameRewardsBatchDistributed {
+            mint: mint_key,
+            count: count as u16,
+            total_amount: total,
+            remaining_pool: ctx.accounts.memecoin_config.game_rewards_pool,
+            timestamp,
+        });
+
+        Ok(())
+    }
+
     pub fn claim_airdrop(
         ctx: Context<ClaimAirdrop>,
     ) -> Result<()> {
@@ -192,7 +369,7 @@ pub mod memecoin {
         let airdrop_seeds = &[
             b"airdrop_pool",
             airdrop_account.mint.as_ref(),
-            &[ctx.accounts.airdrop_pool.bump],
+            &[ctx.bumps.airdrop_authority],
         ];
         let signer = &[&airdrop_seeds[..]];
 
@@ -206,28 +383,36 @@ pub mod memecoin {
         token::transfer(cpi_ctx, airdrop_account.amount)?;
 
         airdrop_account.claimed = true;
-        airdrop_account.claimed_at = clock.unix_timestamp;
+        airdrop_account.claimed_at = Some(clock.unix_timestamp);
 
         emit!(AirdropClaimed {
             recipient: airdrop_account.recipient,
             amount: airdrop_account.amount,
-            timestamp: airdrop_account.claimed_at,
+            timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
 
+    /// Promises `recipient` `amount` of the community allocation from
+    /// `claimable_at`; the amount is reserved so promises can't outrun it.
     pub fn setup_airdrop_account(
         ctx: Context<SetupAirdropAccount>,
+        recipient: Pubkey,
         amount: u64,
         claimable_at: i64,
     ) -> Result<()> {
+        let memecoin_config = &mut ctx.accounts.memecoin_config;
         let airdrop_account = &mut ctx.accounts.airdrop_account;
 
         require!(amount > 0, MemecoinError::InvalidAmount);
         require!(claimable_at > Clock::get().unwrap().unix_timestamp, MemecoinError::InvalidClaimTime);
+        memecoin_config.community_allocation = memecoin_config
+            .community_allocation
+            .checked_sub(amount)
+            .ok_or(MemecoinError::InsufficientRewards)?;
 
-        airdrop_account.recipient = ctx.accounts.authority.key();
+        airdrop_account.recipient = recipient;
         airdrop_account.mint = ctx.accounts.mint.key();
         airdrop_account.amount = amount;
         airdrop_account.claimable_at = claimable_at;
@@ -243,15 +428,378 @@ pub mod memecoin {
 
         Ok(())
     }
+
+    /// Moves `total_amount` from the community allocation into a new campaign
+    /// vault that `claim_merkle_airdrop` pays out of between `claim_start`
+    /// and `claim_end`. See `merkle_leaf` for how the tree is built.
+    pub fn create_airdrop_campaign(
+        ctx: Context<CreateAirdropCampaign>,
+        merkle_root: [u8; 32],
+        total_amount: u64,
+        claim_start: i64,
+        claim_end: i64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(total_amount > 0, MemecoinError::InvalidAmount);
+        require!(merkle_root != [0; 32], MemecoinError::InvalidMerkleProof);
+        require!(
+            claim_start < claim_end && claim_end > clock.unix_timestamp,
+            MemecoinError::InvalidClaimTime
+        );
+        require!(
+            ctx.accounts.community_pool.amount >= total_amount,
+            MemecoinError::InsufficientRewards
+        );
+
+        let mint_key = ctx.accounts.mint.key();
+        let community_seeds = &[
+            b"airdrop_pool",
+            mint_key.as_ref(),
+            &[ctx.bumps.community_authority],
+        ];
+        let signer = &[&community_seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.community_pool.to_account_info(),
+            to: ctx.accounts.campaign_vault.to_account_info(),
+            authority: ctx.accounts.community_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, total_amount)?;
+
+        let campaign = &mut ctx.accounts.campaign;
+        campaign.mint = mint_key;
+        campaign.merkle_root = merkle_root;
+        campaign.vault = ctx.accounts.campaign_vault.key();
+        campaign.total_amount = total_amount;
+        campaign.claimed_amount = 0;
+        campaign.claim_start = claim_start;
+        campaign.claim_end = claim_end;
+        campaign.created_at = clock.unix_timestamp;
+        campaign.bump = ctx.bumps.campaign;
+
+        emit!(AirdropCampaignCreated {
+            mint: mint_key,
+            campaign: campaign.key(),
+            merkle_root,
+            total_amount,
+            claim_start,
+            claim_end,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pays the signer their leaf of a merkle campaign. The claim record PDA
+    /// is created here, so a second claim by the same wallet fails.
+    pub fn claim_merkle_airdrop(
+        ctx: Context<ClaimMerkleAirdrop>,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let campaign = &mut ctx.accounts.campaign;
+
+        require!(amount > 0, MemecoinError::InvalidAmount);
+        require!(
+            clock.unix_timestamp >= campaign.claim_start && clock.unix_timestamp <= campaign.claim_end,
+            MemecoinError::AirdropNotAvailable
+        );
+        require!(proof.len() <= MAX_MERKLE_PROOF_LEN, MemecoinError::InvalidMerkleProof);
+
+        let claimer = ctx.accounts.claimer.key();
+        require!(
+            verify_merkle_proof(&proof, campaign.merkle_root, merkle_leaf(&claimer, amount)),
+            MemecoinError::InvalidMerkleProof
+        );
+
+        // Bounds a bad root to what the campaign was funded with
+        let claimed_amount = campaign
+            .claimed_amount
+            .checked_add(amount)
+            .filter(|claimed| *claimed <= campaign.total_amount)
+            .ok_or(MemecoinError::InsufficientRewards)?;
+        campaign.claimed_amount = claimed_amount;
+
+        let mint_key = campaign.mint;
+        let merkle_root = campaign.merkle_root;
+        let campaign_seeds = &[
+            b"airdrop_campaign",
+            mint_key.as_ref(),
+            merkle_root.as_ref(),
+            &[campaign.bump],
+        ];
+        let signer = &[&campaign_seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.campaign_vault.to_account_info(),
+            to: ctx.accounts.recipient_account.to_account_info(),
+            authority: campaign.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        let claim_record = &mut ctx.accounts.claim_record;
+        claim_record.campaign = campaign.key();
+        claim_record.claimer = claimer;
+        claim_record.amount = amount;
+        claim_record.claimed_at = clock.unix_timestamp;
+        claim_record.bump = ctx.bumps.claim_record;
+
+        emit!(MerkleAirdropClaimed {
+            campaign: campaign.key(),
+            claimer,
+            amount,
+            claimed_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Once `claim_end` has passed, returns whatever was never claimed to the
+    /// community allocation and closes the campaign vault.
+    pub fn reclaim_airdrop_campaign(ctx: Context<ReclaimAirdropCampaign>) -> Result<()> {
+        let clock = Clock::get()?;
+        let campaign = &ctx.accounts.campaign;
+
+        require!(clock.unix_timestamp > campaign.claim_end, MemecoinError::InvalidClaimTime);
+
+        let mint_key = campaign.mint;
+        let merkle_root = campaign.merkle_root;
+        let campaign_seeds = &[
+            b"airdrop_campaign",
+            mint_key.as_ref(),
+            merkle_root.as_ref(),
+            &[campaign.bump],
+        ];
+        let signer = &[&campaign_seeds[..]];
+
+        let amount = ctx.accounts.campaign_vault.amount;
+        if amount > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.campaign_vault.to_account_info(),
+                to: ctx.accounts.community_pool.to_account_info(),
+                authority: campaign.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.campaign_vault.to_account_info(),
+            destination: ctx.accounts.authority.to_account_info(),
+            authority: campaign.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::close_account(cpi_ctx)?;
+
+        emit!(AirdropCampaignReclaimed {
+            campaign: campaign.key(),
+            amount,
+            claimed_amount: campaign.claimed_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Opens the redemption pool that gaming-token burns of `gaming_mint` are
+    /// paid from. It starts empty; see `fund_redemption_pool`.
+    pub fn configure_bridge(
+        ctx: Context<ConfigureBridge>,
+        gaming_mint: Pubkey,
+    ) -> Result<()> {
+        let bridge_config = &mut ctx.accounts.bridge_config;
+
+        bridge_config.mint = ctx.accounts.mint.key();
+        bridge_config.gaming_mint = gaming_mint;
+        bridge_config.redemption_pool = ctx.accounts.redemption_pool.key();
+        bridge_config.total_redeemed = 0;
+        bridge_config.bump = ctx.bumps.bridge_config;
+
+        emit!(BridgeConfigured {
+            mint: bridge_config.mint,
+            gaming_mint,
+            redemption_pool: bridge_config.redemption_pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Moves `amount` from the community allocation into the redemption pool.
+    pub fn fund_redemption_pool(ctx: Context<FundRedemptionPool>, amount: u64) -> Result<()> {
+        require!(amount > 0, MemecoinError::InvalidAmount);
+        require!(
+            ctx.accounts.community_pool.amount >= amount,
+            MemecoinError::InsufficientRewards
+        );
+
+        let mint_key = ctx.accounts.mint.key();
+        let community_seeds = &[
+            b"airdrop_pool",
+            mint_key.as_ref(),
+            &[ctx.bumps.community_authority],
+        ];
+        let signer = &[&community_seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.community_pool.to_account_info(),
+            to: ctx.accounts.redemption_pool.to_account_info(),
+            authority: ctx.accounts.community_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        ctx.accounts.redemption_pool.reload()?;
+
+        emit!(RedemptionPoolFunded {
+            mint: mint_key,
+            amount,
+            pool_balance: ctx.accounts.redemption_pool.amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pays out of the redemption pool for gaming tokens the gaming-token
+    /// program has just burned; only its `redemption_authority` PDA can sign.
+    pub fn redeem_for_gaming_burn(
+        ctx: Context<RedeemForGamingBurn>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, MemecoinError::InvalidAmount);
+        require!(
+            ctx.accounts.redemption_pool.amount >= amount,
+            MemecoinError::InsufficientRewards
+        );
+
+        let mint_key = ctx.accounts.bridge_config.mint;
+        let pool_seeds = &[
+            b"redemption_pool",
+            mint_key.as_ref(),
+            &[ctx.bumps.pool_authority],
+        ];
+        let signer = &[&pool_seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.redemption_pool.to_account_info(),
+            to: ctx.accounts.recipient_account.to_account_info(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        let bridge_config = &mut ctx.accounts.bridge_config;
+        bridge_config.total_redeemed = bridge_config
+            .total_redeemed
+            .checked_add(amount)
+            .ok_or(MemecoinError::InvalidAmount)?;
+
+        emit!(GamingBurnRedeemed {
+            mint: bridge_config.mint,
+            gaming_mint: bridge_config.gaming_mint,
+            recipient: ctx.accounts.recipient_account.key(),
+            amount,
+            total_redeemed: bridge_config.total_redeemed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+/// Creates the PDA `account` the way Anchor's `init` does. Anyone can send
+/// lamports to an address before it is created, which makes a plain
+/// `create_account` fail, so a funded address is only topped up to
+/// `lamports`, then allocated and assigned to `owner`.
+fn create_pda_account<'info>(
+    payer: &Signer<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    signer: &[&[&[u8]]],
+    lamports: u64,
+    space: usize,
+    owner: &Pubkey,
+) -> Result<()> {
+    let current_lamports = account.lamports();
+    if current_lamports == 0 {
+        let cpi_accounts = CreateAccount {
+            from: payer.to_account_info(),
+            to: account.clone(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(system_program.to_account_info(), cpi_accounts, signer);
+        return system_program::create_account(cpi_ctx, lamports, space as u64, owner);
+    }
+
+    let shortfall = lamports.saturating_sub(current_lamports);
+    if shortfall > 0 {
+        let cpi_accounts = system_program::Transfer {
+            from: payer.to_account_info(),
+            to: account.clone(),
+        };
+        let cpi_ctx = CpiContext::new(system_program.to_account_info(), cpi_accounts);
+        system_program::transfer(cpi_ctx, shortfall)?;
+    }
+    let cpi_accounts = Allocate {
+        account_to_allocate: account.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(system_program.to_account_info(), cpi_accounts, signer);
+    system_program::allocate(cpi_ctx, space as u64)?;
+    let cpi_accounts = Assign {
+        account_to_assign: account.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(system_program.to_account_info(), cpi_accounts, signer);
+    system_program::assign(cpi_ctx, owner)
+}
+
+/// `percent`% of `amount`, rounded down; widened so large supplies can't overflow.
+fn percent_of(amount: u64, percent: u64) -> u64 {
+    (amount as u128 * percent as u128 / 100) as u64
+}
+
+/// Seed a game's reward receipt is derived from: `keccak256(game_id)`.
+/// Both reward paths use it, so a game paid in a batch can't be paid again
+/// on its own and vice versa.
+pub fn game_id_hash(game_id: &str) -> [u8; 32] {
+    keccak::hash(game_id.as_bytes()).to_bytes()
+}
+
+/// Leaf of a merkle airdrop: `keccak256(0x00 || claimer || amount)`, with
+/// the claimer's 32-byte key and `amount` as a little-endian u64. Inner
+/// nodes are `keccak256(0x01 || min(a, b) || max(a, b))`, so proofs need no
+/// left/right flags and a leaf can never pass for a node.
+pub fn merkle_leaf(claimer: &Pubkey, amount: u64) -> [u8; 32] {
+    keccak::hashv(&[&[0x00], claimer.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+/// Folds `proof` over `leaf` as `merkle_leaf` describes and compares the
+/// result with `root`.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        let (first, second) = if node <= *sibling { (node, *sibling) } else { (*sibling, node) };
+        keccak::hashv(&[&[0x01], &first, &second]).to_bytes()
+    });
+    computed == root
 }
 
 #[derive(Accounts)]
-#[instruction(token_name: String, token_symbol: String)]
+#[instruction(token_name: String, token_symbol: String, decimals: u8)]
 pub struct InitializeMemecoin<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 8,
+        space = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 1 + 32 + 32 + 32 + 32 + 8,
         seeds = [b"memecoin_config", mint.key().as_ref()],
         bump
     )]
@@ -276,23 +824,68 @@ pub struct InitializeMemecoin<'info> {
 
 #[derive(Accounts)]
 pub struct DistributeInitialSupply<'info> {
-    #[account(mut, has_one = authority)]
+    #[account(mut, has_one = authority, has_one = mint)]
     pub memecoin_config: Account<'info, MemecoinConfig>,
 
     #[account(mut)]
     pub mint: Account<'info, Mint>,
 
-    #[account(init_if_needed, payer = authority, token::mint = mint)]
-    pub game_rewards_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = game_rewards_authority,
+        seeds = [b"pool", memecoin_config.key().as_ref(), b"game_rewards"],
+        bump
+    )]
+    pub game_rewards_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: PDA that owns the game rewards pool
+    #[account(seeds = [b"rewards_pool", mint.key().as_ref()], bump)]
+    pub game_rewards_authority: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = liquidity_authority,
+        seeds = [b"pool", memecoin_config.key().as_ref(), b"liquidity"],
+        bump
+    )]
+    pub liquidity_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: PDA that owns the liquidity pool
+    #[account(seeds = [b"liquidity_pool", mint.key().as_ref()], bump)]
+    pub liquidity_authority: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = team_authority,
+        seeds = [b"pool", memecoin_config.key().as_ref(), b"team"],
+        bump
+    )]
+    pub team_account: Box<Account<'info, TokenAccount>>,
 
-    #[account(init_if_needed, payer = authority, token::mint = mint)]
-    pub liquidity_account: Account<'info, TokenAccount>,
+    /// CHECK: PDA that owns the team allocation
+    #[account(seeds = [b"team_pool", mint.key().as_ref()], bump)]
+    pub team_authority: AccountInfo<'info>,
 
-    #[account(init_if_needed, payer = authority, token::mint = mint)]
-    pub team_account: Account<'info, TokenAccount>,
+    /// Pays out `claim_airdrop`, so it is owned by the airdrop PDA
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = community_authority,
+        seeds = [b"pool", memecoin_config.key().as_ref(), b"community"],
+        bump
+    )]
+    pub community_account: Box<Account<'info, TokenAccount>>,
 
-    #[account(init_if_needed, payer = authority, token::mint = mint)]
-    pub community_account: Account<'info, TokenAccount>,
+    /// CHECK: PDA that owns the community allocation
+    #[account(seeds = [b"airdrop_pool", mint.key().as_ref()], bump)]
+    pub community_authority: AccountInfo<'info>,
 
     #[account(mut)]
     pub authority: Signer<'info>,
@@ -303,6 +896,7 @@ pub struct DistributeInitialSupply<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(player: Pubkey, amount: u64, game_id: String)]
 pub struct DistributeGameRewards<'info> {
     #[account(mut, has_one = authority)]
     pub memecoin_config: Account<'info, MemecoinConfig>,
@@ -310,28 +904,61 @@ pub struct DistributeGameRewards<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 32 + 8 + 8 + 8,
-        seeds = [b"reward", player.as_ref(), game_id.as_bytes()],
+        space = 8 + 32 + 4 + 32 + 8 + 8,
+        seeds = [b"reward", player.as_ref(), game_id_hash(&game_id).as_ref()],
         bump
     )]
     pub rewards_account: Account<'info, RewardAccount>,
 
-    #[account(mut)]
+    #[account(mut, address = memecoin_config.game_rewards_vault @ MemecoinError::InvalidPoolAccount)]
     pub game_rewards_account: Account<'info, TokenAccount>,
 
-    #[account(init_if_needed, payer = authority, token::mint = memecoin_config.mint)]
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = player_wallet
+    )]
     pub player_account: Account<'info, TokenAccount>,
 
+    /// CHECK: the rewarded player, only used to derive their token account
+    #[account(address = player)]
+    pub player_wallet: AccountInfo<'info>,
+
+    /// CHECK: PDA that owns the game rewards pool
     #[account(seeds = [b"rewards_pool", memecoin_config.mint.as_ref()], bump)]
-    pub rewards_authority: Account<'info, MemecoinConfig>,
+    pub rewards_authority: AccountInfo<'info>,
 
+    #[account(address = memecoin_config.mint)]
     pub mint: Account<'info, Mint>,
+    #[account(mut)]
     pub authority: Signer<'info>,
     pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+pub struct DistributeGameRewardsBatch<'info> {
+    #[account(mut, has_one = authority)]
+    pub memecoin_config: Account<'info, MemecoinConfig>,
+
+    #[account(mut, address = memecoin_config.game_rewards_vault @ MemecoinError::InvalidPoolAccount)]
+    pub game_rewards_account: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA that owns the game rewards pool
+    #[account(seeds = [b"rewards_pool", memecoin_config.mint.as_ref()], bump)]
+    pub rewards_authority: AccountInfo<'info>,
+
+    /// Pays rent for the receipts
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct ClaimAirdrop<'info> {
     #[account(
@@ -341,9 +968,14 @@ pub struct ClaimAirdrop<'info> {
     )]
     pub airdrop_account: Account<'info, AirdropAccount>,
 
-    #[account(mut)]
+    #[account(seeds = [b"memecoin_config", mint.key().as_ref()], bump)]
+    pub memecoin_config: Account<'info, MemecoinConfig>,
+
+    /// Airdrops are paid from the community allocation
+    #[account(mut, address = memecoin_config.community_vault @ MemecoinError::InvalidPoolAccount)]
     pub airdrop_pool: Account<'info, TokenAccount>,
 
+    /// CHECK: PDA that owns the community allocation
     #[account(seeds = [b"airdrop_pool", airdrop_account.mint.as_ref()], bump)]
     pub airdrop_authority: AccountInfo<'info>,
 
@@ -358,23 +990,235 @@ pub struct ClaimAirdrop<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
 pub struct SetupAirdropAccount<'info> {
+    #[account(mut, has_one = authority, has_one = mint)]
+    pub memecoin_config: Account<'info, MemecoinConfig>,
+
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 32 + 8 + 8 + 1 + 8,
-        seeds = [b"airdrop", authority.key().as_ref()],
+        space = 8 + 32 + 32 + 8 + 8 + 1 + 8 + 9,
+        seeds = [b"airdrop", recipient.as_ref()],
         bump
     )]
     pub airdrop_account: Account<'info, AirdropAccount>,
 
     pub mint: Account<'info, Mint>,
+    #[account(mut)]
     pub authority: Signer<'info>,
 
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+#[instruction(merkle_root: [u8; 32])]
+pub struct CreateAirdropCampaign<'info> {
+    #[account(has_one = authority, has_one = mint)]
+    pub memecoin_config: Account<'info, MemecoinConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"airdrop_campaign", mint.key().as_ref(), merkle_root.as_ref()],
+        bump
+    )]
+    pub campaign: Account<'info, AirdropCampaign>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = campaign,
+        seeds = [b"campaign_vault", campaign.key().as_ref()],
+        bump
+    )]
+    pub campaign_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, address = memecoin_config.community_vault @ MemecoinError::InvalidPoolAccount)]
+    pub community_pool: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA that owns the community allocation
+    #[account(seeds = [b"airdrop_pool", mint.key().as_ref()], bump)]
+    pub community_authority: AccountInfo<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimMerkleAirdrop<'info> {
+    #[account(
+        mut,
+        seeds = [b"airdrop_campaign", campaign.mint.as_ref(), campaign.merkle_root.as_ref()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, AirdropCampaign>,
+
+    #[account(mut, address = campaign.vault)]
+    pub campaign_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = claimer,
+        space = 8 + 32 + 32 + 8 + 8 + 1,
+        seeds = [b"merkle_claim", campaign.key().as_ref(), claimer.key().as_ref()],
+        bump
+    )]
+    pub claim_record: Account<'info, MerkleClaim>,
+
+    #[account(mut, token::mint = campaign.mint)]
+    pub recipient_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimAirdropCampaign<'info> {
+    #[account(has_one = authority, constraint = memecoin_config.mint == campaign.mint)]
+    pub memecoin_config: Account<'info, MemecoinConfig>,
+
+    #[account(
+        seeds = [b"airdrop_campaign", campaign.mint.as_ref(), campaign.merkle_root.as_ref()],
+        bump = campaign.bump
+    )]
+    pub campaign: Account<'info, AirdropCampaign>,
+
+    #[account(mut, address = campaign.vault)]
+    pub campaign_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, address = memecoin_config.community_vault @ MemecoinError::InvalidPoolAccount)]
+    pub community_pool: Account<'info, TokenAccount>,
+
+    /// Receives the vault's rent
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureBridge<'info> {
+    #[account(has_one = authority, has_one = mint)]
+    pub memecoin_config: Account<'info, MemecoinConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 8 + 1,
+        seeds = [b"bridge_config", mint.key().as_ref()],
+        bump
+    )]
+    pub bridge_config: Account<'info, BridgeConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = pool_authority,
+        seeds = [b"redemption_vault", mint.key().as_ref()],
+        bump
+    )]
+    pub redemption_pool: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA that owns the redemption pool
+    #[account(seeds = [b"redemption_pool", mint.key().as_ref()], bump)]
+    pub pool_authority: AccountInfo<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct FundRedemptionPool<'info> {
+    #[account(has_one = authority, has_one = mint)]
+    pub memecoin_config: Account<'info, MemecoinConfig>,
+
+    #[account(
+        has_one = mint,
+        has_one = redemption_pool,
+        seeds = [b"bridge_config", mint.key().as_ref()],
+        bump = bridge_config.bump
+    )]
+    pub bridge_config: Account<'info, BridgeConfig>,
+
+    #[account(mut)]
+    pub redemption_pool: Account<'info, TokenAccount>,
+
+    #[account(mut, address = memecoin_config.community_vault @ MemecoinError::InvalidPoolAccount)]
+    pub community_pool: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA that owns the community allocation
+    #[account(seeds = [b"airdrop_pool", mint.key().as_ref()], bump)]
+    pub community_authority: AccountInfo<'info>,
+
+    pub mint: Account<'info, Mint>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Account order is relied on by gaming-token's `burn_to_redeem` CPI.
+#[derive(Accounts)]
+pub struct RedeemForGamingBurn<'info> {
+    #[account(
+        mut,
+        has_one = gaming_mint,
+        has_one = redemption_pool,
+        seeds = [b"bridge_config", bridge_config.mint.as_ref()],
+        bump = bridge_config.bump
+    )]
+    pub bridge_config: Account<'info, BridgeConfig>,
+
+    #[account(mut)]
+    pub redemption_pool: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA that owns the redemption pool
+    #[account(seeds = [b"redemption_pool", bridge_config.mint.as_ref()], bump)]
+    pub pool_authority: AccountInfo<'info>,
+
+    /// CHECK: Only its key is used, to derive the caller's PDA
+    pub gaming_mint: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"redemption_authority", gaming_mint.key().as_ref()],
+        bump,
+        seeds::program = GAMING_TOKEN_PROGRAM_ID
+    )]
+    pub redemption_authority: Signer<'info>,
+
+    #[account(mut, token::mint = bridge_config.mint)]
+    pub recipient_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+pub struct BridgeConfig {
+    pub mint: Pubkey,
+    /// Gaming-token mint whose burns this pool pays for.
+    pub gaming_mint: Pubkey,
+    pub redemption_pool: Pubkey,
+    pub total_redeemed: u64,
+    pub bump: u8,
+}
+
 #[account]
 pub struct MemecoinConfig {
     pub authority: Pubkey,
@@ -387,9 +1231,20 @@ pub struct MemecoinConfig {
     pub game_rewards_pool: u64,
     pub liquidity_pool: u64,
     pub team_allocation: u64,
+    /// Community tokens not yet promised through `setup_airdrop_account`.
     pub community_allocation: u64,
     pub is_initialized: bool,
     pub created_at: i64,
+    /// Set once the mint authority is revoked; nothing may mint afterwards.
+    pub supply_finalized: bool,
+    /// Program-owned token accounts holding each allocation, recorded by
+    /// `distribute_initial_supply`.
+    pub game_rewards_vault: Pubkey,
+    pub liquidity_vault: Pubkey,
+    pub team_vault: Pubkey,
+    pub community_vault: Pubkey,
+    /// When `distribute_initial_supply` ran.
+    pub distributed_at: i64,
 }
 
 #[account]
@@ -400,6 +1255,19 @@ pub struct RewardAccount {
     pub timestamp: i64,
 }
 
+/// Proof that a batched reward was paid, at `[b"reward", player, game_id_hash(game_id)]`.
+#[account]
+pub struct BatchRewardReceipt {
+    pub player: Pubkey,
+    pub game_id_hash: [u8; 32],
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+impl BatchRewardReceipt {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8;
+}
+
 #[account]
 pub struct AirdropAccount {
     pub recipient: Pubkey,
@@ -411,6 +1279,30 @@ pub struct AirdropAccount {
     pub claimed_at: Option<i64>,
 }
 
+/// Merkle airdrop at `[b"airdrop_campaign", mint, merkle_root]`; it owns its vault.
+#[account]
+pub struct AirdropCampaign {
+    pub mint: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub vault: Pubkey,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub claim_start: i64,
+    pub claim_end: i64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+/// Marks a claimer's leaf as paid, at `[b"merkle_claim", campaign, claimer]`.
+#[account]
+pub struct MerkleClaim {
+    pub campaign: Pubkey,
+    pub claimer: Pubkey,
+    pub amount: u64,
+    pub claimed_at: i64,
+    pub bump: u8,
+}
+
 #[event]
 pub struct MemecoinInitialized {
     pub mint: Pubkey,
@@ -428,6 +1320,13 @@ pub struct InitialSupplyDistributed {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct SupplyFinalized {
+    pub mint: Pubkey,
+    pub total_supply: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct GameRewardDistributed {
     pub player: Pubkey,
@@ -436,6 +1335,23 @@ pub struct GameRewardDistributed {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct BatchGameRewardDistributed {
+    pub player: Pubkey,
+    pub amount: u64,
+    pub game_id_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameRewardsBatchDistributed {
+    pub mint: Pubkey,
+    pub count: u16,
+    pub total_amount: u64,
+    pub remaining_pool: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct AirdropAccountSetup {
     pub recipient: Pubkey,
@@ -444,6 +1360,60 @@ pub struct AirdropAccountSetup {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct AirdropCampaignCreated {
+    pub mint: Pubkey,
+    pub campaign: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub total_amount: u64,
+    pub claim_start: i64,
+    pub claim_end: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerkleAirdropClaimed {
+    pub campaign: Pubkey,
+    pub claimer: Pubkey,
+    pub amount: u64,
+    pub claimed_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AirdropCampaignReclaimed {
+    pub campaign: Pubkey,
+    pub amount: u64,
+    pub claimed_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BridgeConfigured {
+    pub mint: Pubkey,
+    pub gaming_mint: Pubkey,
+    pub redemption_pool: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RedemptionPoolFunded {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub pool_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GamingBurnRedeemed {
+    pub mint: Pubkey,
+    pub gaming_mint: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub total_redeemed: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct AirdropClaimed {
     pub recipient: Pubkey,
@@ -477,4 +1447,12 @@ pub enum MemecoinError {
     AirdropNotAvailable,
     #[msg("Invalid claim time")]
     InvalidClaimTime,
+    #[msg("Supply is finalized")]
+    SupplyFinalized,
+    #[msg("Not the recorded pool account")]
+    InvalidPoolAccount,
+    #[msg("Invalid reward batch")]
+    InvalidBatch,
+    #[msg("Invalid merkle proof")]
+    InvalidMerkleProof,
 }
\ No newline at end of file
@@ -0,0 +1,64 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use memecoin::{AirdropClaimed, MemecoinError};
+
+const AMOUNT: u64 = 1_000_000;
+
+#[test]
+fn only_the_authority_can_promise_an_airdrop() {
+    let mut memecoin = Memecoin::distributed(TOTAL_SUPPLY);
+    let intruder = memecoin.harness.funded_wallet();
+    let claimable_at = memecoin.harness.now() + 1;
+
+    // Promising itself the whole community allocation, signed as itself
+    let community = memecoin.config().community_allocation;
+    let mut ix = memecoin.setup_airdrop_account_ix(intruder, community, claimable_at);
+    let signer = ix.accounts.iter_mut().find(|meta| meta.is_signer).unwrap();
+    signer.pubkey = intruder;
+    assert_error(memecoin.harness.process(&[ix], &[intruder]), ErrorCode::ConstraintHasOne);
+
+    memecoin.harness.warp(1);
+    let tokens = memecoin.token_account(&intruder);
+    let ix = memecoin.claim_airdrop_ix(intruder, tokens);
+    assert_error(
+        memecoin.harness.process(&[ix], &[intruder]),
+        ErrorCode::AccountNotInitialized,
+    );
+    assert_eq!(memecoin.balance(&memecoin.community_vault()), community);
+}
+
+#[test]
+fn promises_are_reserved_against_the_community_allocation() {
+    let mut memecoin = Memecoin::distributed(TOTAL_SUPPLY);
+    let community = memecoin.config().community_allocation;
+    let recipient = memecoin.harness.funded_wallet();
+    let other = memecoin.harness.funded_wallet();
+    let claimable_at = memecoin.harness.now() + DAY;
+
+    let ix = memecoin.setup_airdrop_account_ix(recipient, AMOUNT, claimable_at);
+    memecoin.process(ix).unwrap();
+    assert_eq!(memecoin.config().community_allocation, community - AMOUNT);
+    let ix = memecoin.setup_airdrop_account_ix(other, community - AMOUNT + 1, claimable_at);
+    assert_error(memecoin.process(ix), MemecoinError::InsufficientRewards);
+
+    let tokens = memecoin.token_account(&recipient);
+    let ix = memecoin.claim_airdrop_ix(recipient, tokens);
+    assert_error(
+        memecoin.harness.process(&[ix], &[recipient]),
+        MemecoinError::AirdropNotAvailable,
+    );
+    memecoin.harness.warp(DAY);
+    let ix = memecoin.claim_airdrop_ix(recipient, tokens);
+    memecoin.harness.process(&[ix], &[recipient]).unwrap();
+    assert_eq!(memecoin.harness.event::<AirdropClaimed>().amount, AMOUNT);
+    assert_eq!(memecoin.balance(&tokens), AMOUNT);
+    assert_eq!(memecoin.balance(&memecoin.community_vault()), community - AMOUNT);
+
+    let ix = memecoin.claim_airdrop_ix(recipient, tokens);
+    assert_error(
+        memecoin.harness.process(&[ix], &[recipient]),
+        MemecoinError::AlreadyClaimed,
+    );
+}
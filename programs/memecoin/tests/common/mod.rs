@@ -0,0 +1,345 @@
+//! Shared setup for the memecoin program tests.
+#![allow(dead_code, unused_imports)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, program_error::ProgramError, sysvar};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{get_associated_token_address, ID as ASSOCIATED_TOKEN_ID};
+use anchor_spl::token::ID as TOKEN_ID;
+use memecoin::{accounts, instruction, MemecoinConfig};
+use program_harness::Harness;
+
+pub use program_harness::{anchor_error, assert_error, FUNDED_LAMPORTS};
+
+pub type TxResult = std::result::Result<(), ProgramError>;
+
+pub const TOTAL_SUPPLY: u64 = 1_000_000_000_000;
+pub const DAY: i64 = 24 * 60 * 60;
+
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &memecoin::ID).0
+}
+
+/// A memecoin deployment: the program loaded into a harness plus the keys
+/// `initialize_memecoin` and `distribute_initial_supply` derive.
+pub struct Memecoin {
+    pub harness: Harness,
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub config: Pubkey,
+}
+
+impl Memecoin {
+    /// Initialized but not yet distributed.
+    pub fn initialized(total_supply: u64) -> Self {
+        let mut harness = Harness::new();
+        harness.add_program(memecoin::ID, memecoin::entry);
+        let authority = harness.funded_wallet();
+        let mint = Pubkey::new_unique();
+        let config = pda(&[b"memecoin_config", mint.as_ref()]);
+        let mut memecoin = Self {
+            harness,
+            authority,
+            mint,
+            config,
+        };
+        let ix = Instruction {
+            program_id: memecoin::ID,
+            accounts: accounts::InitializeMemecoin {
+                memecoin_config: config,
+                mint,
+                authority,
+                system_program: system_program::ID,
+                token_program: TOKEN_ID,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializeMemecoin {
+                token_name: "Sol-itaire Meme".to_string(),
+                token_symbol: "SOLM".to_string(),
+                decimals: 6,
+                total_supply,
+            }
+            .data(),
+        };
+        memecoin
+            .harness
+            .process(&[ix], &[authority, mint])
+            .expect("memecoin initializes");
+        memecoin
+    }
+
+    /// Initialized and distributed, with the supply finalized.
+    pub fn distributed(total_supply: u64) -> Self {
+        let mut memecoin = Self::initialized(total_supply);
+        let ix = memecoin.distribute_ix();
+        memecoin.process(ix).expect("supply distributes");
+        memecoin
+    }
+
+    pub fn distribute_ix(&self) -> Instruction {
+        Instruction {
+            program_id: memecoin::ID,
+            accounts: accounts::DistributeInitialSupply {
+                memecoin_config: self.config,
+                mint: self.mint,
+                game_rewards_account: self.game_rewards_vault(),
+                game_rewards_authority: self.rewards_authority(),
+                liquidity_account: self.liquidity_vault(),
+                liquidity_authority: self.liquidity_authority(),
+                team_account: self.team_vault(),
+                team_authority: self.team_authority(),
+                community_account: self.community_vault(),
+                community_authority: self.community_authority(),
+                authority: self.authority,
+                system_program: system_program::ID,
+                token_program: TOKEN_ID,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::DistributeInitialSupply {}.data(),
+        }
+    }
+
+    /// Runs `ix` signed by the authority.
+    pub fn process(&mut self, ix: Instruction) -> TxResult {
+        let authority = self.authority;
+        self.harness.process(&[ix], &[authority])
+    }
+
+    pub fn config(&self) -> MemecoinConfig {
+        self.harness.get(&self.config)
+    }
+
+    pub fn game_rewards_vault(&self) -> Pubkey {
+        pda(&[b"pool", self.config.as_ref(), b"game_rewards"])
+    }
+
+    pub fn liquidity_vault(&self) -> Pubkey {
+        pda(&[b"pool", self.config.as_ref(), b"liquidity"])
+    }
+
+    pub fn team_vault(&self) -> Pubkey {
+        pda(&[b"pool", self.config.as_ref(), b"team"])
+    }
+
+    pub fn community_vault(&self) -> Pubkey {
+        pda(&[b"pool", self.config.as_ref(), b"community"])
+    }
+
+    pub fn rewards_authority(&self) -> Pubkey {
+        pda(&[b"rewards_pool", self.mint.as_ref()])
+    }
+
+    pub fn liquidity_authority(&self) -> Pubkey {
+        pda(&[b"liquidity_pool", self.mint.as_ref()])
+    }
+
+    pub fn team_authority(&self) -> Pubkey {
+        pda(&[b"team_pool", self.mint.as_ref()])
+    }
+
+    pub fn community_authority(&self) -> Pubkey {
+        pda(&[b"airdrop_pool", self.mint.as_ref()])
+    }
+
+    /// A plain token account for the memecoin, owned by `owner`.
+    pub fn token_account(&mut self, owner: &Pubkey) -> Pubkey {
+        let mint = self.mint;
+        self.harness.create_token_account(&mint, owner)
+    }
+
+    pub fn balance(&self, account: &Pubkey) -> u64 {
+        self.harness.token_balance(account)
+    }
+
+    pub fn player_ata(&self, player: &Pubkey) -> Pubkey {
+        get_associated_token_address(player, &self.mint)
+    }
+
+    pub fn reward_receipt(&self, player: &Pubkey, game_id: &str) -> Pubkey {
+        pda(&[b"reward", player.as_ref(), &memecoin::game_id_hash(game_id)])
+    }
+
+    pub fn distribute_game_rewards_ix(&self, player: Pubkey, amount: u64, game_id: &str) -> Instruction {
+        Instruction {
+            program_id: memecoin::ID,
+            accounts: accounts::DistributeGameRewards {
+                memecoin_config: self.config,
+                rewards_account: self.reward_receipt(&player, game_id),
+                game_rewards_account: self.game_rewards_vault(),
+                player_account: self.player_ata(&player),
+                player_wallet: player,
+                rewards_authority: self.rewards_authority(),
+                mint: self.mint,
+                authority: self.authority,
+                token_program: TOKEN_ID,
+                associated_token_program: ASSOCIATED_TOKEN_ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::DistributeGameRewards {
+                player,
+                amount,
+                game_id: game_id.to_string(),
+            }
+            .data(),
+        }
+    }
+
+    /// `distribute_game_rewards_batch` paying `(player, player token account, amount)`
+    /// entries, all for `game_id`.
+    pub fn distribute_game_rewards_batch_ix(&self, entries: &[(Pubkey, Pubkey, u64)], game_id: &str) -> Instruction {
+        let hash = memecoin::game_id_hash(game_id);
+        let mut metas = accounts::DistributeGameRewardsBatch {
+            memecoin_config: self.config,
+            game_rewards_account: self.game_rewards_vault(),
+            rewards_authority: self.rewards_authority(),
+            authority: self.authority,
+            token_program: TOKEN_ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        for (player, player_account, _) in entries {
+            metas.push(AccountMeta::new(*player_account, false));
+            metas.push(AccountMeta::new(self.reward_receipt(player, game_id), false));
+        }
+        Instruction {
+            program_id: memecoin::ID,
+            accounts: metas,
+            data: instruction::DistributeGameRewardsBatch {
+                players: entries.iter().map(|entry| entry.0).collect(),
+                amounts: entries.iter().map(|entry| entry.2).collect(),
+                game_id_hashes: vec![hash; entries.len()],
+            }
+            .data(),
+        }
+    }
+
+    pub fn airdrop_account(&self, recipient: &Pubkey) -> Pubkey {
+        pda(&[b"airdrop", recipient.as_ref()])
+    }
+
+    pub fn setup_airdrop_account_ix(&self, recipient: Pubkey, amount: u64, claimable_at: i64) -> Instruction {
+        Instruction {
+            program_id: memecoin::ID,
+            accounts: accounts::SetupAirdropAccount {
+                memecoin_config: self.config,
+                airdrop_account: self.airdrop_account(&recipient),
+                mint: self.mint,
+                authority: self.authority,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::SetupAirdropAccount {
+                recipient,
+                amount,
+                claimable_at,
+            }
+            .data(),
+        }
+    }
+
+    pub fn claim_airdrop_ix(&self, recipient: Pubkey, recipient_account: Pubkey) -> Instruction {
+        Instruction {
+            program_id: memecoin::ID,
+            accounts: accounts::ClaimAirdrop {
+                airdrop_account: self.airdrop_account(&recipient),
+                memecoin_config: self.config,
+                airdrop_pool: self.community_vault(),
+                airdrop_authority: self.community_authority(),
+                recipient_account,
+                authority: recipient,
+                mint: self.mint,
+                token_program: TOKEN_ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ClaimAirdrop {}.data(),
+        }
+    }
+
+    pub fn campaign(&self, merkle_root: &[u8; 32]) -> Pubkey {
+        pda(&[b"airdrop_campaign", self.mint.as_ref(), merkle_root])
+    }
+
+    pub fn campaign_vault(&self, merkle_root: &[u8; 32]) -> Pubkey {
+        pda(&[b"campaign_vault", self.campaign(merkle_root).as_ref()])
+    }
+
+    pub fn merkle_claim(&self, merkle_root: &[u8; 32], claimer: &Pubkey) -> Pubkey {
+        pda(&[b"merkle_claim", self.campaign(merkle_root).as_ref(), claimer.as_ref()])
+    }
+
+    pub fn create_airdrop_campaign_ix(
+        &self,
+        merkle_root: [u8; 32],
+        total_amount: u64,
+        claim_start: i64,
+        claim_end: i64,
+    ) -> Instruction {
+        Instruction {
+            program_id: memecoin::ID,
+            accounts: accounts::CreateAirdropCampaign {
+                memecoin_config: self.config,
+                campaign: self.campaign(&merkle_root),
+                campaign_vault: self.campaign_vault(&merkle_root),
+                community_pool: self.community_vault(),
+                community_authority: self.community_authority(),
+                mint: self.mint,
+                authority: self.authority,
+                system_program: system_program::ID,
+                token_program: TOKEN_ID,
+            }
+            .to_account_metas(None),
+            data: instruction::CreateAirdropCampaign {
+                merkle_root,
+                total_amount,
+                claim_start,
+                claim_end,
+            }
+            .data(),
+        }
+    }
+
+    pub fn claim_merkle_airdrop_ix(
+        &self,
+        merkle_root: [u8; 32],
+        claimer: Pubkey,
+        recipient_account: Pubkey,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Instruction {
+        Instruction {
+            program_id: memecoin::ID,
+            accounts: accounts::ClaimMerkleAirdrop {
+                campaign: self.campaign(&merkle_root),
+                campaign_vault: self.campaign_vault(&merkle_root),
+                claim_record: self.merkle_claim(&merkle_root, &claimer),
+                recipient_account,
+                claimer,
+                system_program: system_program::ID,
+                token_program: TOKEN_ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ClaimMerkleAirdrop { amount, proof }.data(),
+        }
+    }
+
+    pub fn reclaim_airdrop_campaign_ix(&self, merkle_root: [u8; 32]) -> Instruction {
+        Instruction {
+            program_id: memecoin::ID,
+            accounts: accounts::ReclaimAirdropCampaign {
+                memecoin_config: self.config,
+                campaign: self.campaign(&merkle_root),
+                campaign_vault: self.campaign_vault(&merkle_root),
+                community_pool: self.community_vault(),
+                authority: self.authority,
+                token_program: TOKEN_ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ReclaimAirdropCampaign {}.data(),
+        }
+    }
+}
@@ -0,0 +1,222 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::keccak;
+use common::*;
+use memecoin::{
+    merkle_leaf, verify_merkle_proof, AirdropCampaignReclaimed, MemecoinError, MerkleAirdropClaimed, MerkleClaim,
+};
+
+/// A tree built the way the off-chain tooling builds it: sorted pairs, and
+/// an odd node at the end of a level carried up unchanged.
+struct Tree {
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl Tree {
+    fn new(leaves: Vec<[u8; 32]>) -> Self {
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => node(*a, *b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    fn root(&self) -> [u8; 32] {
+        self.levels.last().unwrap()[0]
+    }
+
+    fn proof(&self, mut index: usize) -> Vec<[u8; 32]> {
+        let mut proof = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        proof
+    }
+}
+
+fn node(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    keccak::hashv(&[&[0x01], &first, &second]).to_bytes()
+}
+
+/// `count` claimers owed `1_000 + index` each.
+fn claims(count: usize) -> Vec<(Pubkey, u64)> {
+    (0..count).map(|index| (Pubkey::new_unique(), 1_000 + index as u64)).collect()
+}
+
+fn tree(claims: &[(Pubkey, u64)]) -> Tree {
+    Tree::new(claims.iter().map(|(claimer, amount)| merkle_leaf(claimer, *amount)).collect())
+}
+
+/// A distributed memecoin with a campaign for `claims` open from now for a day.
+fn campaign(claims: &[(Pubkey, u64)]) -> (Memecoin, Tree) {
+    let mut memecoin = Memecoin::distributed(TOTAL_SUPPLY);
+    let tree = tree(claims);
+    let total = claims.iter().map(|(_, amount)| amount).sum();
+    let now = memecoin.harness.now();
+    let ix = memecoin.create_airdrop_campaign_ix(tree.root(), total, now, now + 86_400);
+    memecoin.process(ix).unwrap();
+    (memecoin, tree)
+}
+
+fn claim(memecoin: &mut Memecoin, tree: &Tree, claimer: Pubkey, amount: u64, proof: Vec<[u8; 32]>) -> TxResult {
+    memecoin.harness.airdrop(&claimer, FUNDED_LAMPORTS);
+    let recipient = memecoin.player_ata(&claimer);
+    if memecoin.harness.account(&recipient).is_none() {
+        let mint = memecoin.mint;
+        memecoin.harness.create_token_account_at(&recipient, &mint, &claimer);
+    }
+    let ix = memecoin.claim_merkle_airdrop_ix(tree.root(), claimer, recipient, amount, proof);
+    memecoin.harness.process(&[ix], &[claimer])
+}
+
+#[test]
+fn leaves_and_nodes_are_domain_separated() {
+    let claimer = Pubkey::new_unique();
+    let mut preimage = vec![0x00];
+    preimage.extend_from_slice(claimer.as_ref());
+    preimage.extend_from_slice(&42u64.to_le_bytes());
+    assert_eq!(merkle_leaf(&claimer, 42), keccak::hash(&preimage).to_bytes());
+
+    // Pair order doesn't matter
+    let other = merkle_leaf(&Pubkey::new_unique(), 7);
+    let leaf = merkle_leaf(&claimer, 42);
+    assert_eq!(node(leaf, other), node(other, leaf));
+    let root = node(leaf, other);
+    assert!(verify_merkle_proof(&[other], root, leaf));
+    assert!(verify_merkle_proof(&[leaf], root, other));
+    assert!(!verify_merkle_proof(&[other], root, merkle_leaf(&claimer, 43)));
+
+    // A single-leaf tree's root is the leaf itself
+    assert!(verify_merkle_proof(&[], leaf, leaf));
+
+    // The 0x01 prefix keeps a node apart from a plain hash of its pair
+    assert_ne!(root, keccak::hashv(&[&leaf.min(other), &leaf.max(other)]).to_bytes());
+}
+
+#[test]
+fn every_leaf_of_a_thousand_leaf_tree_claims() {
+    let claims = claims(1_000);
+    let (mut memecoin, tree) = campaign(&claims);
+    let total: u64 = claims.iter().map(|(_, amount)| amount).sum();
+    assert_eq!(memecoin.balance(&memecoin.campaign_vault(&tree.root())), total);
+
+    for (index, (claimer, amount)) in claims.iter().enumerate() {
+        let proof = tree.proof(index);
+        assert!(proof.len() <= memecoin::MAX_MERKLE_PROOF_LEN);
+        assert!(verify_merkle_proof(&proof, tree.root(), merkle_leaf(claimer, *amount)));
+    }
+
+    // First, middle, and the last two: 999 is carried up past the odd level
+    let mut claimed = 0;
+    for index in [0, 499, 998, 999] {
+        let (claimer, amount) = claims[index];
+        claim(&mut memecoin, &tree, claimer, amount, tree.proof(index)).unwrap();
+        claimed += amount;
+
+        let event = memecoin.harness.event::<MerkleAirdropClaimed>();
+        assert_eq!(event.claimer, claimer);
+        assert_eq!(event.amount, amount);
+        assert_eq!(event.claimed_amount, claimed);
+        assert_eq!(memecoin.balance(&memecoin.player_ata(&claimer)), amount);
+        let record: MerkleClaim = memecoin.harness.get(&memecoin.merkle_claim(&tree.root(), &claimer));
+        assert_eq!(record.amount, amount);
+    }
+    assert_eq!(memecoin.balance(&memecoin.campaign_vault(&tree.root())), total - claimed);
+}
+
+#[test]
+fn proofs_only_pay_their_own_leaf() {
+    let claims = claims(8);
+    let (mut memecoin, tree) = campaign(&claims);
+    let (claimer, amount) = claims[3];
+
+    assert_error(
+        claim(&mut memecoin, &tree, claimer, amount + 1, tree.proof(3)),
+        MemecoinError::InvalidMerkleProof,
+    );
+    assert_error(
+        claim(&mut memecoin, &tree, claimer, amount, tree.proof(4)),
+        MemecoinError::InvalidMerkleProof,
+    );
+    // Someone else's leaf and proof, signed by the wrong wallet
+    let (_, other_amount) = claims[4];
+    assert_error(
+        claim(&mut memecoin, &tree, claimer, other_amount, tree.proof(4)),
+        MemecoinError::InvalidMerkleProof,
+    );
+    assert_error(
+        claim(&mut memecoin, &tree, claimer, amount, vec![[0; 32]; memecoin::MAX_MERKLE_PROOF_LEN + 1]),
+        MemecoinError::InvalidMerkleProof,
+    );
+
+    claim(&mut memecoin, &tree, claimer, amount, tree.proof(3)).unwrap();
+    // The claim record already exists
+    assert!(claim(&mut memecoin, &tree, claimer, amount, tree.proof(3)).is_err());
+    assert_eq!(memecoin.balance(&memecoin.player_ata(&claimer)), amount);
+}
+
+#[test]
+fn claims_are_limited_to_the_window_and_the_rest_is_reclaimed() {
+    let claims = claims(4);
+    let mut memecoin = Memecoin::distributed(TOTAL_SUPPLY);
+    let tree = tree(&claims);
+    let total: u64 = claims.iter().map(|(_, amount)| amount).sum();
+    let start = memecoin.harness.now() + 100;
+    let end = start + 1_000;
+    let community = memecoin.balance(&memecoin.community_vault());
+
+    let ix = memecoin.create_airdrop_campaign_ix(tree.root(), total, end, start);
+    assert_error(memecoin.process(ix), MemecoinError::InvalidClaimTime);
+    let ix = memecoin.create_airdrop_campaign_ix(tree.root(), total, start, end);
+    memecoin.process(ix).unwrap();
+    assert_eq!(memecoin.balance(&memecoin.community_vault()), community - total);
+
+    memecoin.harness.set_time(start - 1);
+    assert_error(
+        claim(&mut memecoin, &tree, claims[0].0, claims[0].1, tree.proof(0)),
+        MemecoinError::AirdropNotAvailable,
+    );
+
+    memecoin.harness.set_time(start);
+    claim(&mut memecoin, &tree, claims[0].0, claims[0].1, tree.proof(0)).unwrap();
+    memecoin.harness.set_time(end);
+    claim(&mut memecoin, &tree, claims[1].0, claims[1].1, tree.proof(1)).unwrap();
+
+    let ix = memecoin.reclaim_airdrop_campaign_ix(tree.root());
+    assert_error(memecoin.process(ix), MemecoinError::InvalidClaimTime);
+
+    memecoin.harness.set_time(end + 1);
+    assert_error(
+        claim(&mut memecoin, &tree, claims[2].0, claims[2].1, tree.proof(2)),
+        MemecoinError::AirdropNotAvailable,
+    );
+
+    let ix = memecoin.reclaim_airdrop_campaign_ix(tree.root());
+    memecoin.process(ix).unwrap();
+    let unclaimed = claims[2].1 + claims[3].1;
+    let event = memecoin.harness.event::<AirdropCampaignReclaimed>();
+    assert_eq!(event.amount, unclaimed);
+    assert_eq!(event.claimed_amount, claims[0].1 + claims[1].1);
+    assert_eq!(memecoin.balance(&memecoin.community_vault()), community - total + unclaimed);
+    assert!(memecoin.harness.account(&memecoin.campaign_vault(&tree.root())).is_none());
+
+    // Nothing left to claim from or reclaim
+    assert!(claim(&mut memecoin, &tree, claims[3].0, claims[3].1, tree.proof(3)).is_err());
+    let ix = memecoin.reclaim_airdrop_campaign_ix(tree.root());
+    assert!(memecoin.process(ix).is_err());
+}
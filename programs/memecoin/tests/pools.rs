@@ -0,0 +1,98 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::spl_token::{self, error::TokenError};
+use anchor_spl::token::ID as TOKEN_ID;
+use common::*;
+use memecoin::MemecoinError;
+
+#[test]
+fn distribution_only_mints_into_the_program_pools() {
+    let mut memecoin = Memecoin::initialized(TOTAL_SUPPLY);
+    let wallet = memecoin.authority;
+    let personal = memecoin.token_account(&wallet);
+
+    // Every pool slot is checked against its PDA
+    for slot in 0..4 {
+        let mut ix = memecoin.distribute_ix();
+        let index = [2, 4, 6, 8][slot];
+        ix.accounts[index].pubkey = personal;
+        assert_error(memecoin.process(ix), ErrorCode::ConstraintSeeds);
+    }
+
+    let ix = memecoin.distribute_ix();
+    memecoin.process(ix).unwrap();
+    let config = memecoin.config();
+    assert_eq!(config.game_rewards_vault, memecoin.game_rewards_vault());
+    assert_eq!(config.liquidity_vault, memecoin.liquidity_vault());
+    assert_eq!(config.team_vault, memecoin.team_vault());
+    assert_eq!(config.community_vault, memecoin.community_vault());
+    assert_eq!(memecoin.balance(&personal), 0);
+}
+
+#[test]
+fn a_prime_supply_is_split_without_losing_a_token() {
+    // Divisible by none of the allocation percentages
+    let total_supply = 999_999_937;
+    let memecoin = Memecoin::distributed(total_supply);
+    let config = memecoin.config();
+    let pools = [
+        (memecoin.game_rewards_vault(), config.game_rewards_pool),
+        (memecoin.liquidity_vault(), config.liquidity_pool),
+        (memecoin.team_vault(), config.team_allocation),
+        (memecoin.community_vault(), config.community_allocation),
+    ];
+    for (vault, allocation) in pools {
+        assert_eq!(memecoin.balance(&vault), allocation);
+    }
+    let minted: u64 = pools.iter().map(|(_, allocation)| allocation).sum();
+    assert_eq!(minted, total_supply);
+    assert_eq!(memecoin.harness.mint(&memecoin.mint).supply, total_supply);
+    assert_eq!(config.circulating_supply, total_supply);
+}
+
+#[test]
+fn nothing_can_mint_once_the_supply_is_distributed() {
+    let mut memecoin = Memecoin::distributed(TOTAL_SUPPLY);
+    assert!(memecoin.config().supply_finalized);
+    assert_eq!(memecoin.harness.mint(&memecoin.mint).mint_authority, COption::None);
+
+    // Not the deployer through the token program, nor a second distribution
+    let (mint, authority) = (memecoin.mint, memecoin.authority);
+    let personal = memecoin.token_account(&authority);
+    let ix = spl_token::instruction::mint_to(&TOKEN_ID, &mint, &personal, &authority, &[], 1).unwrap();
+    assert_eq!(memecoin.process(ix), Err(TokenError::FixedSupply.into()));
+    let ix = memecoin.distribute_ix();
+    assert!(memecoin.process(ix).is_err());
+    assert_eq!(memecoin.harness.mint(&mint).supply, TOTAL_SUPPLY);
+}
+
+#[test]
+fn substitute_pool_accounts_are_rejected() {
+    let mut memecoin = Memecoin::distributed(TOTAL_SUPPLY);
+    let wallet = memecoin.authority;
+    let personal = memecoin.token_account(&wallet);
+    let player = Pubkey::new_unique();
+
+    // Game rewards paid from anything but the recorded vault
+    let mut ix = memecoin.distribute_game_rewards_ix(player, 1_000, "game-1");
+    ix.accounts[2].pubkey = memecoin.liquidity_vault();
+    assert_error(memecoin.process(ix), MemecoinError::InvalidPoolAccount);
+
+    // An airdrop claimed from the game rewards instead of the community pool
+    let claimable_at = memecoin.harness.now() + 1;
+    let ix = memecoin.setup_airdrop_account_ix(wallet, 1_000, claimable_at);
+    memecoin.process(ix).unwrap();
+    memecoin.harness.warp(1);
+    let mut ix = memecoin.claim_airdrop_ix(wallet, personal);
+    ix.accounts[2].pubkey = memecoin.game_rewards_vault();
+    assert_error(memecoin.process(ix), MemecoinError::InvalidPoolAccount);
+
+    assert_eq!(memecoin.balance(&personal), 0);
+    let config = memecoin.config();
+    assert_eq!(memecoin.balance(&memecoin.game_rewards_vault()), config.game_rewards_pool);
+    assert_eq!(memecoin.balance(&memecoin.liquidity_vault()), config.liquidity_pool);
+    assert_eq!(memecoin.balance(&memecoin.team_vault()), config.team_allocation);
+}
@@ -0,0 +1,297 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{instruction::Instruction, sysvar};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::token::ID as TOKEN_ID;
+use common::*;
+use gaming_token::{GamingTokenError, RedemptionConfig, TokensRedeemed};
+use memecoin::{accounts, instruction, BridgeConfig};
+
+const GAMING_TOKENS: u64 = 1_000_000;
+/// Enough for exactly 1,000 gaming tokens at 3:2.
+const POOL: u64 = 1_500;
+
+fn gaming_pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &gaming_token::ID).0
+}
+
+/// A distributed memecoin whose redemption pool pays for burns of a gaming
+/// token at 3 memecoin per 2 gaming tokens, plus a player holding both.
+struct Bridge {
+    memecoin: Memecoin,
+    gaming_mint: Pubkey,
+    player: Pubkey,
+    gaming_tokens: Pubkey,
+    memecoin_tokens: Pubkey,
+}
+
+impl Bridge {
+    fn new() -> Self {
+        let mut memecoin = Memecoin::distributed(TOTAL_SUPPLY);
+        memecoin.harness.add_program(gaming_token::ID, gaming_token::entry);
+        let gaming_mint = create_gaming_mint(&mut memecoin);
+
+        let ix = Instruction {
+            program_id: memecoin::ID,
+            accounts: accounts::ConfigureBridge {
+                memecoin_config: memecoin.config,
+                bridge_config: bridge_config(&memecoin),
+                redemption_pool: redemption_pool(&memecoin),
+                pool_authority: pda(&[b"redemption_pool", memecoin.mint.as_ref()]),
+                mint: memecoin.mint,
+                authority: memecoin.authority,
+                system_program: system_program::ID,
+                token_program: TOKEN_ID,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ConfigureBridge { gaming_mint }.data(),
+        };
+        memecoin.process(ix).unwrap();
+        let ix = Instruction {
+            program_id: memecoin::ID,
+            accounts: accounts::FundRedemptionPool {
+                memecoin_config: memecoin.config,
+                bridge_config: bridge_config(&memecoin),
+                redemption_pool: redemption_pool(&memecoin),
+                community_pool: memecoin.community_vault(),
+                community_authority: memecoin.community_authority(),
+                mint: memecoin.mint,
+                authority: memecoin.authority,
+                token_program: TOKEN_ID,
+            }
+            .to_account_metas(None),
+            data: instruction::FundRedemptionPool { amount: POOL }.data(),
+        };
+        memecoin.process(ix).unwrap();
+
+        let player = memecoin.harness.funded_wallet();
+        let gaming_tokens = memecoin.harness.create_token_account(&gaming_mint, &player);
+        let memecoin_tokens = memecoin.token_account(&player);
+        let mut bridge = Self {
+            memecoin,
+            gaming_mint,
+            player,
+            gaming_tokens,
+            memecoin_tokens,
+        };
+        bridge.configure_redemption(gaming_mint, 3, 2).unwrap();
+        bridge.mint_gaming(gaming_mint, gaming_tokens, GAMING_TOKENS);
+        bridge
+    }
+
+    fn mint_gaming(&mut self, gaming_mint: Pubkey, token_account: Pubkey, amount: u64) {
+        let ix = Instruction {
+            program_id: gaming_token::ID,
+            accounts: gaming_token::accounts::MintTokens {
+                mint_config: gaming_pda(&[b"mint_config", gaming_mint.as_ref()]),
+                mint: gaming_mint,
+                mint_authority: gaming_pda(&[b"mint_authority", gaming_mint.as_ref()]),
+                token_account,
+                authority: self.memecoin.authority,
+                token_program: TOKEN_ID,
+            }
+            .to_account_metas(None),
+            data: gaming_token::instruction::MintTokens { amount }.data(),
+        };
+        self.memecoin.process(ix).unwrap();
+    }
+
+    fn configure_redemption(&mut self, gaming_mint: Pubkey, rate_numerator: u64, rate_denominator: u64) -> TxResult {
+        let ix = Instruction {
+            program_id: gaming_token::ID,
+            accounts: gaming_token::accounts::ConfigureRedemption {
+                mint_config: gaming_pda(&[b"mint_config", gaming_mint.as_ref()]),
+                mint: gaming_mint,
+                redemption_config: gaming_pda(&[b"redemption_config", gaming_mint.as_ref()]),
+                redemption_pool: redemption_pool(&self.memecoin),
+                authority: self.memecoin.authority,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: gaming_token::instruction::ConfigureRedemption {
+                memecoin_program: memecoin::ID,
+                bridge_config: bridge_config(&self.memecoin),
+                rate_numerator,
+                rate_denominator,
+            }
+            .data(),
+        };
+        self.memecoin.process(ix)
+    }
+
+    fn burn_to_redeem_ix(&self, gaming_mint: Pubkey, token_account: Pubkey, amount: u64) -> Instruction {
+        Instruction {
+            program_id: gaming_token::ID,
+            accounts: gaming_token::accounts::BurnToRedeem {
+                mint_config: gaming_pda(&[b"mint_config", gaming_mint.as_ref()]),
+                mint: gaming_mint,
+                redemption_config: gaming_pda(&[b"redemption_config", gaming_mint.as_ref()]),
+                token_account,
+                owner: self.player,
+                redemption_authority: gaming_pda(&[b"redemption_authority", gaming_mint.as_ref()]),
+                memecoin_program: memecoin::ID,
+                bridge_config: bridge_config(&self.memecoin),
+                redemption_pool: redemption_pool(&self.memecoin),
+                pool_authority: pda(&[b"redemption_pool", self.memecoin.mint.as_ref()]),
+                recipient_account: self.memecoin_tokens,
+                token_program: TOKEN_ID,
+                memecoin_token_program: TOKEN_ID,
+            }
+            .to_account_metas(None),
+            data: gaming_token::instruction::BurnToRedeem { amount }.data(),
+        }
+    }
+
+    fn burn_to_redeem(&mut self, amount: u64) -> TxResult {
+        let ix = self.burn_to_redeem_ix(self.gaming_mint, self.gaming_tokens, amount);
+        let player = self.player;
+        self.memecoin.harness.process(&[ix], &[player])
+    }
+
+    /// `redeem_for_gaming_burn` called directly, signed by `caller`.
+    fn redeem_directly(&mut self, caller: Pubkey, amount: u64) -> TxResult {
+        let ix = Instruction {
+            program_id: memecoin::ID,
+            accounts: accounts::RedeemForGamingBurn {
+                bridge_config: bridge_config(&self.memecoin),
+                redemption_pool: redemption_pool(&self.memecoin),
+                pool_authority: pda(&[b"redemption_pool", self.memecoin.mint.as_ref()]),
+                gaming_mint: self.gaming_mint,
+                redemption_authority: caller,
+                recipient_account: self.memecoin_tokens,
+                token_program: TOKEN_ID,
+            }
+            .to_account_metas(None),
+            data: instruction::RedeemForGamingBurn { amount }.data(),
+        };
+        self.memecoin.harness.process(&[ix], &[caller])
+    }
+
+    fn gaming_supply(&self) -> u64 {
+        self.memecoin.harness.mint(&self.gaming_mint).supply
+    }
+
+    fn pool_balance(&self) -> u64 {
+        self.memecoin.balance(&redemption_pool(&self.memecoin))
+    }
+}
+
+/// A fresh gaming-token mint under the memecoin authority.
+fn create_gaming_mint(memecoin: &mut Memecoin) -> Pubkey {
+    let mint = Pubkey::new_unique();
+    let ix = Instruction {
+        program_id: gaming_token::ID,
+        accounts: gaming_token::accounts::InitializeMint {
+            mint_config: gaming_pda(&[b"mint_config", mint.as_ref()]),
+            mint,
+            authority: memecoin.authority,
+            system_program: system_program::ID,
+            token_program: TOKEN_ID,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None),
+        data: gaming_token::instruction::InitializeMint {
+            token_name: "Sol-itaire Gold".to_string(),
+            token_symbol: "GOLD".to_string(),
+            decimals: 6,
+            max_supply: 0,
+            transfer_fee: None,
+        }
+        .data(),
+    };
+    let authority = memecoin.authority;
+    memecoin.harness.process(&[ix], &[authority, mint]).unwrap();
+    mint
+}
+
+fn bridge_config(memecoin: &Memecoin) -> Pubkey {
+    pda(&[b"bridge_config", memecoin.mint.as_ref()])
+}
+
+fn redemption_pool(memecoin: &Memecoin) -> Pubkey {
+    pda(&[b"redemption_vault", memecoin.mint.as_ref()])
+}
+
+#[test]
+fn burns_are_paid_at_the_configured_rate_rounded_down() {
+    let mut bridge = Bridge::new();
+
+    bridge.burn_to_redeem(101).unwrap();
+    let redeemed = bridge.memecoin.harness.event::<TokensRedeemed>();
+    assert_eq!((redeemed.burned, redeemed.redeemed), (101, 151));
+    assert_eq!(redeemed.new_supply, GAMING_TOKENS - 101);
+    assert_eq!(bridge.gaming_supply(), GAMING_TOKENS - 101);
+    assert_eq!(bridge.memecoin.balance(&bridge.gaming_tokens), GAMING_TOKENS - 101);
+    assert_eq!(bridge.memecoin.balance(&bridge.memecoin_tokens), 151);
+    assert_eq!(bridge.pool_balance(), POOL - 151);
+
+    let gaming_mint = bridge.gaming_mint;
+    let config: RedemptionConfig = bridge
+        .memecoin
+        .harness
+        .get(&gaming_pda(&[b"redemption_config", gaming_mint.as_ref()]));
+    assert_eq!((config.total_burned, config.total_redeemed), (101, 151));
+    let bridge_state: BridgeConfig = bridge.memecoin.harness.get(&bridge_config(&bridge.memecoin));
+    assert_eq!(bridge_state.total_redeemed, 151);
+
+    // A burn too small to be worth a whole memecoin is refused, not swallowed
+    bridge.configure_redemption(gaming_mint, 1, 1_000).unwrap();
+    assert_error(bridge.burn_to_redeem(999), GamingTokenError::InvalidAmount);
+    bridge.burn_to_redeem(1_999).unwrap();
+    assert_eq!(bridge.memecoin.harness.event::<TokensRedeemed>().redeemed, 1);
+    assert_error(
+        bridge.configure_redemption(gaming_mint, 1, 0),
+        GamingTokenError::InvalidRedemptionRate,
+    );
+}
+
+#[test]
+fn a_dry_pool_fails_before_anything_is_burned() {
+    let mut bridge = Bridge::new();
+
+    // 1,001 gaming tokens would need 1,501 memecoin, one more than the pool holds
+    assert_error(
+        bridge.burn_to_redeem(1_001),
+        GamingTokenError::RedemptionPoolExhausted,
+    );
+    assert_eq!(bridge.gaming_supply(), GAMING_TOKENS);
+    assert_eq!(bridge.memecoin.balance(&bridge.gaming_tokens), GAMING_TOKENS);
+    assert_eq!(bridge.pool_balance(), POOL);
+
+    bridge.burn_to_redeem(1_000).unwrap();
+    assert_eq!(bridge.pool_balance(), 0);
+    assert_eq!(bridge.memecoin.balance(&bridge.memecoin_tokens), POOL);
+    assert_error(bridge.burn_to_redeem(1), GamingTokenError::RedemptionPoolExhausted);
+    assert_eq!(bridge.gaming_supply(), GAMING_TOKENS - 1_000);
+}
+
+#[test]
+fn only_the_gaming_token_pda_for_the_bridged_mint_can_redeem() {
+    let mut bridge = Bridge::new();
+
+    // Neither a wallet nor the same seeds under the memecoin program
+    let wallet = bridge.memecoin.harness.funded_wallet();
+    assert_error(bridge.redeem_directly(wallet, 1), ErrorCode::ConstraintSeeds);
+    let gaming_mint = bridge.gaming_mint;
+    let lookalike = pda(&[b"redemption_authority", gaming_mint.as_ref()]);
+    assert_error(bridge.redeem_directly(lookalike, 1), ErrorCode::ConstraintSeeds);
+
+    // Another gaming mint pointed at the pool signs with its own PDA, which
+    // the bridge doesn't accept
+    let other_mint = create_gaming_mint(&mut bridge.memecoin);
+    let player = bridge.player;
+    let other_tokens = bridge.memecoin.harness.create_token_account(&other_mint, &player);
+    bridge.configure_redemption(other_mint, 3, 2).unwrap();
+    bridge.mint_gaming(other_mint, other_tokens, GAMING_TOKENS);
+    let ix = bridge.burn_to_redeem_ix(other_mint, other_tokens, 1_000);
+    assert_error(
+        bridge.memecoin.harness.process(&[ix], &[player]),
+        ErrorCode::ConstraintHasOne,
+    );
+
+    assert_eq!(bridge.pool_balance(), POOL);
+    assert_eq!(bridge.memecoin.balance(&bridge.memecoin_tokens), 0);
+}
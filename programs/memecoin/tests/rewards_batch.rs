@@ -0,0 +1,154 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use memecoin::{
+    game_id_hash, BatchGameRewardDistributed, BatchRewardReceipt, GameRewardsBatchDistributed, MemecoinError,
+    RewardAccount,
+};
+
+/// Three players, each with their own memecoin token account.
+fn players(memecoin: &mut Memecoin) -> Vec<(Pubkey, Pubkey)> {
+    (0..3)
+        .map(|_| {
+            let player = Pubkey::new_unique();
+            (player, memecoin.token_account(&player))
+        })
+        .collect()
+}
+
+#[test]
+fn batch_pays_every_player_and_reports_each_payout() {
+    let mut memecoin = Memecoin::distributed(TOTAL_SUPPLY);
+    let players = players(&mut memecoin);
+    let pool = memecoin.config().game_rewards_pool;
+    let entries: Vec<_> = players
+        .iter()
+        .zip([100, 200, 300])
+        .map(|(&(player, account), amount)| (player, account, amount))
+        .collect();
+
+    let ix = memecoin.distribute_game_rewards_batch_ix(&entries, "round-1");
+    memecoin.process(ix).unwrap();
+
+    let now = memecoin.harness.now();
+    let payouts = memecoin.harness.events::<BatchGameRewardDistributed>();
+    assert_eq!(payouts.len(), entries.len());
+    for ((player, account, amount), payout) in entries.iter().zip(&payouts) {
+        assert_eq!(memecoin.balance(account), *amount);
+        assert_eq!(payout.player, *player);
+        assert_eq!(payout.amount, *amount);
+        assert_eq!(payout.game_id_hash, game_id_hash("round-1"));
+        assert_eq!(payout.timestamp, now);
+
+        let receipt: BatchRewardReceipt = memecoin.harness.get(&memecoin.reward_receipt(player, "round-1"));
+        assert_eq!(receipt.player, *player);
+        assert_eq!(receipt.amount, *amount);
+    }
+
+    let summary = memecoin.harness.event::<GameRewardsBatchDistributed>();
+    assert_eq!(summary.mint, memecoin.mint);
+    assert_eq!(summary.count, 3);
+    assert_eq!(summary.total_amount, 600);
+    assert_eq!(summary.remaining_pool, pool - 600);
+    assert_eq!(memecoin.config().game_rewards_pool, pool - 600);
+    assert_eq!(memecoin.balance(&memecoin.game_rewards_vault()), pool - 600);
+}
+
+#[test]
+fn one_bad_entry_aborts_the_whole_batch() {
+    let mut memecoin = Memecoin::distributed(TOTAL_SUPPLY);
+    let players = players(&mut memecoin);
+    let pool = memecoin.config().game_rewards_pool;
+    let good: Vec<_> = players.iter().map(|&(player, account)| (player, account, 100)).collect();
+
+    let check_untouched = |memecoin: &Memecoin| {
+        assert_eq!(memecoin.config().game_rewards_pool, pool);
+        assert_eq!(memecoin.balance(&memecoin.game_rewards_vault()), pool);
+        for (player, account) in &players {
+            assert_eq!(memecoin.balance(account), 0);
+            assert!(memecoin.harness.account(&memecoin.reward_receipt(player, "round-1")).is_none());
+        }
+    };
+
+    // Last entry pays into someone else's token account
+    let mut entries = good.clone();
+    entries[2].1 = players[0].1;
+    let ix = memecoin.distribute_game_rewards_batch_ix(&entries, "round-1");
+    assert_error(memecoin.process(ix), MemecoinError::InvalidBatch);
+    check_untouched(&memecoin);
+
+    // Last entry pays nothing
+    let mut entries = good.clone();
+    entries[2].2 = 0;
+    let ix = memecoin.distribute_game_rewards_batch_ix(&entries, "round-1");
+    assert_error(memecoin.process(ix), MemecoinError::InvalidAmount);
+    check_untouched(&memecoin);
+
+    // Same player twice: the second receipt already exists
+    let mut entries = good.clone();
+    entries[2] = entries[0];
+    let ix = memecoin.distribute_game_rewards_batch_ix(&entries, "round-1");
+    assert!(memecoin.process(ix).is_err());
+    check_untouched(&memecoin);
+
+    // More than the pool holds
+    let mut entries = good.clone();
+    entries[2].2 = pool;
+    let ix = memecoin.distribute_game_rewards_batch_ix(&entries, "round-1");
+    assert_error(memecoin.process(ix), MemecoinError::InsufficientRewards);
+    check_untouched(&memecoin);
+
+    let ix = memecoin.distribute_game_rewards_batch_ix(&good, "round-1");
+    memecoin.process(ix).unwrap();
+}
+
+#[test]
+fn single_and_batch_payouts_share_a_receipt() {
+    let mut memecoin = Memecoin::distributed(TOTAL_SUPPLY);
+    let player = Pubkey::new_unique();
+    let account = memecoin.token_account(&player);
+
+    // Paid singly first, then offered again in a batch
+    let ix = memecoin.distribute_game_rewards_ix(player, 500, "game-1");
+    memecoin.process(ix).unwrap();
+    let receipt: RewardAccount = memecoin.harness.get(&memecoin.reward_receipt(&player, "game-1"));
+    assert_eq!(receipt.amount, 500);
+
+    let ix = memecoin.distribute_game_rewards_batch_ix(&[(player, account, 500)], "game-1");
+    assert!(memecoin.process(ix).is_err());
+    assert_eq!(memecoin.balance(&account), 0);
+
+    // Paid in a batch first, then offered singly
+    let ix = memecoin.distribute_game_rewards_batch_ix(&[(player, account, 700)], "game-2");
+    memecoin.process(ix).unwrap();
+    let ix = memecoin.distribute_game_rewards_ix(player, 700, "game-2");
+    assert!(memecoin.process(ix).is_err());
+
+    assert_eq!(memecoin.balance(&account), 700);
+    assert_eq!(memecoin.balance(&memecoin.player_ata(&player)), 500);
+}
+
+#[test]
+fn a_prefunded_receipt_address_does_not_block_the_batch() {
+    let mut memecoin = Memecoin::distributed(TOTAL_SUPPLY);
+    let players = players(&mut memecoin);
+    let entries: Vec<_> = players.iter().map(|&(player, account)| (player, account, 100)).collect();
+
+    // Anyone can send lamports to a receipt address before it is created
+    let receipt = memecoin.reward_receipt(&players[1].0, "round-1");
+    memecoin.harness.airdrop(&receipt, 1);
+
+    let ix = memecoin.distribute_game_rewards_batch_ix(&entries, "round-1");
+    memecoin.process(ix).unwrap();
+    for (_, account) in &players {
+        assert_eq!(memecoin.balance(account), 100);
+    }
+    let record: BatchRewardReceipt = memecoin.harness.get(&receipt);
+    assert_eq!((record.player, record.amount), (players[1].0, 100));
+    assert_eq!(memecoin.harness.account(&receipt).unwrap().owner, memecoin::ID);
+
+    // The topped-up receipt still blocks paying the game twice
+    let ix = memecoin.distribute_game_rewards_batch_ix(&entries[1..2], "round-1");
+    assert!(memecoin.process(ix).is_err());
+}
@@ -1,5 +1,8 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, Transfer};
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::keccak;
+use anchor_spl::metadata::mpl_token_metadata;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Mint, Transfer};
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
@@ -7,31 +10,72 @@ declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 pub mod solitaire {
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_game(
         ctx: Context<InitializeGame>,
         game_id: String,
         stake_amount: u64,
         reward_mint: Pubkey,
+        mode: GameMode,
+        allow_foundation_to_tableau: Option<bool>,
+        partner: Option<Pubkey>,
+        variant: GameVariant,
+        deal_number: Option<u32>,
+        referrer: Option<Pubkey>,
+        insurance: bool,
+        practice: bool,
     ) -> Result<()> {
         let game = &mut ctx.accounts.game;
+        let config = &ctx.accounts.config;
         let clock = Clock::get()?;
 
         require!(stake_amount > 0, SolitaireError::InvalidStakeAmount);
         require!(game_id.len() <= 32, SolitaireError::GameIdTooLong);
+        require!(
+            partner != Some(ctx.accounts.authority.key()),
+            SolitaireError::InvalidPartner
+        );
+        require!(
+            referrer != Some(ctx.accounts.authority.key()),
+            SolitaireError::SelfReferral
+        );
 
-        game.authority = ctx.accounts.authority.key();
-        game.game_id = game_id;
+        // The first referrer recorded for a player is kept for good
+        let mut game_referrer = None;
+        if let Some(referral) = ctx.accounts.referral.as_mut() {
+            if referral.player == Pubkey::default() {
+                let referrer = referrer.ok_or(SolitaireError::InvalidReferrer)?;
+                referral.player = ctx.accounts.authority.key();
+                referral.referrer = referrer;
+                referral.created_at = clock.unix_timestamp;
+                referral.bump = ctx.bumps.referral;
+
+                emit!(ReferralRegistered {
+                    player: referral.player,
+                    referrer,
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+            game_referrer = Some(referral.referrer);
+        }
+
+        let game_key = game.key();
+        game.start(ctx.accounts.authority.key(), game_id, reward_mint, mode, config, &clock);
         game.stake_amount = stake_amount;
-        game.reward_mint = reward_mint;
-        game.status = GameStatus::Active;
-        game.moves = 0;
-        game.score = 0;
-        game.is_won = false;
-        game.created_at = clock.unix_timestamp;
-        game.updated_at = clock.unix_timestamp;
+        game.allow_foundation_to_tableau = allow_foundation_to_tableau.unwrap_or(true);
+        game.partner = partner;
+        game.referrer = game_referrer;
+        game.memecoin_boost_bps = config.memecoin_boost.boost_for(&reward_mint);
+        game.practice = practice;
+        game.deal(&game_key, &clock, variant, deal_number);
 
-        // Initialize game state
-        game.game_state = GameState::new(ctx.accounts.authority.key());
+        let player_stats = &mut ctx.accounts.player_stats;
+        player_stats.init_if_new(ctx.accounts.authority.key(), ctx.bumps.player_stats);
+        // Practice games don't count toward the cap
+        if !practice {
+            player_stats.open_game(config.max_active_games)?;
+            game.holds_active_slot = true;
+        }
 
         // Transfer stake to escrow
         let cpi_accounts = Transfer {
@@ -43,16 +87,126 @@ pub mod solitaire {
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
         token::transfer(cpi_ctx, stake_amount)?;
 
+        // Loss insurance: the premium goes to the insurance vault, which must
+        // already cover this policy on top of every one still open
+        if insurance {
+            let premium_bps = config.insurance_premium_bps;
+            require!(premium_bps > 0, SolitaireError::InsuranceUnavailable);
+            let (Some(insurance_vault), Some(insurance_ledger)) = (
+                ctx.accounts.insurance_vault.as_ref(),
+                ctx.accounts.insurance_ledger.as_mut(),
+            ) else {
+                return err!(SolitaireError::InsuranceUnavailable);
+            };
+            game.insured = true;
+            insurance_ledger.reserve(game.insured_liability(), insurance_vault.amount)?;
+
+            let premium = bps_of(stake_amount, premium_bps)?;
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.user_token_account.to_account_info(),
+                to: insurance_vault.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            token::transfer(cpi_ctx, premium)?;
+
+            game.insurance_premium = premium;
+        }
+
         emit!(GameStarted {
             game_id: game.game_id.clone(),
             player: game.authority,
             stake_amount,
+            allow_foundation_to_tableau: game.allow_foundation_to_tableau,
+            partner,
+            insured: game.insured,
+            insurance_premium: game.insurance_premium,
+            memecoin_boost_bps: game.memecoin_boost_bps,
+            free_game: false,
+            practice,
+            variant,
+            seed: game.seed,
+            timestamp: game.created_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_free_game(
+        ctx: Context<InitializeFreeGame>,
+        game_id: String,
+        day: u64,
+        mode: GameMode,
+        allow_foundation_to_tableau: Option<bool>,
+        variant: GameVariant,
+    ) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        let config = &ctx.accounts.config;
+        let clock = Clock::get()?;
+
+        require!(config.free_game_stake > 0, SolitaireError::FreeGamesDisabled);
+        require!(game_id.len() <= 32, SolitaireError::GameIdTooLong);
+        require!(
+            day == (clock.unix_timestamp / SECONDS_PER_DAY) as u64,
+            SolitaireError::InvalidFreeGameDay
+        );
+
+        let daily_free_game = &mut ctx.accounts.daily_free_game;
+        daily_free_game.player = ctx.accounts.authority.key();
+        daily_free_game.day = day;
+        daily_free_game.game = game.key();
+        daily_free_game.bump = ctx.bumps.daily_free_game;
+
+        let game_key = game.key();
+        let reward_mint = ctx.accounts.reward_mint_info.key();
+        game.start(ctx.accounts.authority.key(), game_id, reward_mint, mode, config, &clock);
+        game.stake_amount = config.free_game_stake;
+        game.allow_foundation_to_tableau = allow_foundation_to_tableau.unwrap_or(true);
+        game.free_game = true;
+        // Promo-funded games always get a fresh deal, never a chosen deal number
+        game.deal(&game_key, &clock, variant, None);
+
+        let player_stats = &mut ctx.accounts.player_stats;
+        player_stats.init_if_new(ctx.accounts.authority.key(), ctx.bumps.player_stats);
+        player_stats.open_game(config.max_active_games)?;
+        game.holds_active_slot = true;
+
+        // Front the stake from the promo vault
+        let promo_seeds = &[b"promo_authority".as_ref(), &[ctx.bumps.promo_authority]];
+        let signer = &[&promo_seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.promo_vault.to_account_info(),
+            to: ctx.accounts.escrow_token_account.to_account_info(),
+            authority: ctx.accounts.promo_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, game.stake_amount)?;
+
+        emit!(GameStarted {
+            game_id: game.game_id.clone(),
+            player: game.authority,
+            stake_amount: game.stake_amount,
+            allow_foundation_to_tableau: game.allow_foundation_to_tableau,
+            partner: None,
+            insured: false,
+            insurance_premium: 0,
+            memecoin_boost_bps: 0,
+            free_game: true,
+            practice: false,
+            variant,
+            seed: game.seed,
             timestamp: game.created_at,
         });
 
         Ok(())
     }
 
+    /// Validates and applies one move. A winning move only marks the game
+    /// won: it stays Active, refusing further moves, until `complete_game`
+    /// settles the payout.
     pub fn make_move(
         ctx: Context<MakeMove>,
         from_pile: String,
@@ -63,31 +217,42 @@ pub mod solitaire {
         let clock = Clock::get()?;
 
         require!(game.status == GameStatus::Active, SolitaireError::GameNotActive);
-        require!(
-            ctx.accounts.authority.key() == game.authority,
-            SolitaireError::Unauthorized
-        );
+        require!(!game.is_won, SolitaireError::GameAlreadyWon);
+        game.check_turn(&ctx.accounts.authority.key())?;
+        game.check_move_clock(clock.unix_timestamp)?;
+        game.check_move_rate(clock.slot)?;
+        require!(game.move_log.len() < MAX_MOVE_LOG, SolitaireError::MoveLogFull);
+        let capacity = GameAccount::move_log_capacity(game.to_account_info().data_len());
+        require!(game.move_log.len() < capacity, SolitaireError::GameAccountFull);
 
         // Validate and execute move
-        game.game_state.make_move(&from_pile, &to_pile, card_index)?;
+        let scoring = game.scoring;
+        let allow_foundation_to_tableau = game.allow_foundation_to_tableau;
+        let (record, outcome) = game.game_state.make_move(
+            &from_pile,
+            &to_pile,
+            card_index,
+            &scoring,
+            allow_foundation_to_tableau,
+        )?;
+        game.move_log.push(record);
         game.moves += 1;
+        game.score = game.game_state.score;
+        if let Some(partner) = game.partner {
+            game.next_to_move = if game.next_to_move == partner {
+                game.authority
+            } else {
+                partner
+            };
+        }
+        game.last_move_slot = clock.slot;
         game.updated_at = clock.unix_timestamp;
+        game.reset_move_clock(clock.unix_timestamp);
+        let state_hash = game.game_state.state_hash();
 
-        // Check for win condition
-        if game.game_state.is_won() {
-            game.is_won = true;
-            game.status = GameStatus::Completed;
-            game.updated_at = clock.unix_timestamp;
-
-            emit!(GameCompleted {
-                game_id: game.game_id.clone(),
-                player: game.authority,
-                won: true,
-                score: game.score,
-                moves: game.moves,
-                timestamp: game.updated_at,
-            });
-        }
+        // A won game stays Active until complete_game settles it, with
+        // `updated_at` left as the time of the winning move
+        game.is_won = game.game_state.is_won();
 
         emit!(MoveMade {
             game_id: game.game_id.clone(),
@@ -95,43 +260,203 @@ pub mod solitaire {
             from_pile,
             to_pile,
             card_index,
+            cards_moved: outcome.cards_moved,
+            flipped: outcome.flipped,
+            score_delta: outcome.score_delta,
+            from_pile_size: game.game_state.piles[record.from as usize].cards.len() as u8,
+            to_pile_size: game.game_state.piles[record.to as usize].cards.len() as u8,
             moves: game.moves,
+            state_hash,
             timestamp: game.updated_at,
         });
 
         Ok(())
     }
 
-    pub fn complete_game(
-        ctx: Context<CompleteGame>,
-        final_score: u64,
-    ) -> Result<()> {
+    /// Records that the player took a hint. Hints are worked out off-chain;
+    /// a game that used any is kept out of the speed records.
+    pub fn use_hint(ctx: Context<MakeMove>) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        let clock = Clock::get()?;
+
+        require!(game.status == GameStatus::Active, SolitaireError::GameNotActive);
+        require!(!game.is_won, SolitaireError::GameAlreadyWon);
+        game.check_turn(&ctx.accounts.authority.key())?;
+
+        game.hints_used = game.hints_used.saturating_add(1);
+
+        emit!(HintUsed {
+            game_id: game.game_id.clone(),
+            player: ctx.accounts.authority.key(),
+            hints_used: game.hints_used,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn complete_game(ctx: Context<CompleteGame>) -> Result<()> {
         let game = &mut ctx.accounts.game;
         let clock = Clock::get()?;
 
+        require!(!game.corrupted, SolitaireError::GameCorrupted);
         require!(game.status == GameStatus::Active, SolitaireError::GameNotActive);
         require!(
             ctx.accounts.authority.key() == game.authority,
             SolitaireError::Unauthorized
         );
+        // A lapsed clock forfeits through claim_timeout; a win landed in time
+        // and may be settled whenever
+        if !game.is_won {
+            game.check_move_clock(clock.unix_timestamp)?;
+        }
 
-        game.status = GameStatus::Completed;
-        game.score = final_score;
+        game.transition_to(GameStatus::Completed)?;
+        game.release_active_slot(ctx.accounts.player_stats.as_deref_mut())?;
+        game.release_insurance(ctx.accounts.insurance_ledger.as_deref_mut())?;
         game.is_won = game.game_state.is_won();
+        game.score = game.game_state.score;
+        // Measured to the last move, so settling late doesn't cost time bonus or records
+        let win_seconds = (game.updated_at - game.created_at).max(0) as u64;
+        if game.is_won {
+            game.score += game.scoring.time_bonus(win_seconds as i64);
+        }
         game.updated_at = clock.unix_timestamp;
 
         // Calculate rewards
         let reward_amount = if game.is_won {
             game.stake_amount * 2 // Double the stake for winning
+        } else if game.free_game {
+            0 // Fronted stakes go back to the promo vault
         } else {
             game.stake_amount / 2 // Return half for completing
         };
 
+        // Insured losses are topped up to the full stake from the insurance vault
+        let insurance_payout = if game.insured && !game.is_won {
+            game.stake_amount - reward_amount
+        } else {
+            0
+        };
+
+        // Hold part of the payout in escrow until the dispute window closes
+        let dispute = &ctx.accounts.config.dispute;
+        let bond_amount = bps_of(game.stake_amount, dispute.player_bond_bps)?.min(reward_amount);
+        game.bond_amount = bond_amount;
+        game.dispute_deadline = clock
+            .unix_timestamp
+            .checked_add(dispute.window_seconds)
+            .ok_or(SolitaireError::GameStateError)?;
+
+        // Protocol fee on the payout, shared with the player's referrer when
+        // their token account is supplied; otherwise it all goes to the treasury.
+        // Gaming-token stakers get part of it waived, by the stake held right now
+        let fees = &ctx.accounts.config.fees;
+        let fee_discount_bps = ctx.accounts.stake_account.as_ref().map_or(0, |stake_account| {
+            stake_discount_bps(&game.authority, &ctx.accounts.config.stake_discount, stake_account)
+        });
+        let protocol_fee_bps =
+            fees.protocol_fee_bps - bps_of(fees.protocol_fee_bps as u64, fee_discount_bps)? as u16;
+        let protocol_fee =
+            bps_of(reward_amount, protocol_fee_bps)?.min(reward_amount - bond_amount);
+        let referral_fee = match (&ctx.accounts.referrer_token_account, game.referrer) {
+            (Some(referrer_token_account), Some(referrer)) => {
+                require_keys_eq!(
+                    referrer_token_account.owner,
+                    referrer,
+                    SolitaireError::InvalidReferrer
+                );
+                bps_of(protocol_fee, fees.referral_share_bps)?
+            }
+            _ => 0,
+        };
+
+        // Co-op games split the released payout evenly with the partner
+        let payout = reward_amount - bond_amount - protocol_fee;
+        let partner_share = if game.partner.is_some() { payout / 2 } else { 0 };
+
+        // Treasury payouts are each capped by what the ones before them left;
+        // none can be paid without the treasury authority
+        let mut treasury_available = match (
+            &ctx.accounts.treasury_token_account,
+            &ctx.accounts.treasury_authority,
+        ) {
+            (Some(treasury_token_account), Some(_)) => treasury_token_account.amount,
+            _ => 0,
+        };
+
+        // Collection holders earn a bonus on wins, paid from the house treasury
+        let nft_bonus = match (&ctx.accounts.nft_token_account, &ctx.accounts.nft_metadata) {
+            (Some(nft_token_account), Some(nft_metadata))
+                if game.is_won
+                    && holds_collection_nft(
+                        &game.authority,
+                        &ctx.accounts.config.nft_bonus.collection,
+                        nft_token_account,
+                        nft_metadata,
+                    ) =>
+            {
+                bps_of(reward_amount, ctx.accounts.config.nft_bonus.bonus_bps)?
+                    .min(treasury_available)
+            }
+            _ => 0,
+        };
+        treasury_available -= nft_bonus;
+
+        // Wins staked in the ecosystem memecoin earn the boost snapshotted at start
+        let memecoin_boost = if game.is_won && game.memecoin_boost_bps > 0 {
+            bps_of(reward_amount, game.memecoin_boost_bps)?.min(treasury_available)
+        } else {
+            0
+        };
+        treasury_available -= memecoin_boost;
+
+        // Speed records; a tie never dethrones the existing holder, and
+        // practice or hint-assisted wins don't count
+        let mut record_bounty = 0;
+        let record_eligible = game.is_record_eligible();
+        if game.is_won {
+            if let Some(player_stats) = ctx.accounts.player_stats.as_mut() {
+                player_stats.games_won += 1;
+                let previous_seconds = player_stats.fastest_win_seconds;
+                if record_eligible && beats_record(win_seconds, previous_seconds) {
+                    player_stats.fastest_win_seconds = Some(win_seconds);
+                    emit!(NewPersonalBest {
+                        player: game.authority,
+                        game_id: game.game_id.clone(),
+                        win_seconds,
+                        previous_seconds,
+                        timestamp: clock.unix_timestamp,
+                    });
+                }
+            }
+
+            if let Some(global_stats) = ctx.accounts.global_stats.as_mut() {
+                let previous_seconds = global_stats.fastest_win_seconds;
+                if record_eligible && beats_record(win_seconds, previous_seconds) {
+                    global_stats.fastest_win_seconds = Some(win_seconds);
+                    global_stats.record_holder = game.authority;
+                    global_stats.record_set_at = clock.unix_timestamp;
+
+                    record_bounty = ctx.accounts.config.record_bounty.min(treasury_available);
+
+                    emit!(NewGlobalRecord {
+                        player: game.authority,
+                        game_id: game.game_id.clone(),
+                        win_seconds,
+                        previous_seconds,
+                        bounty: record_bounty,
+                        timestamp: clock.unix_timestamp,
+                    });
+                }
+            }
+        }
+
         // Transfer rewards back to user
         let escrow_seeds = &[
-            b"escrow",
+            b"escrow_authority",
             game.game_id.as_bytes(),
-            &[game.bump],
+            &[ctx.bumps.escrow_authority],
         ];
         let signer = &[&escrow_seeds[..]];
 
@@ -142,14 +467,133 @@ pub mod solitaire {
         };
         let cpi_program = ctx.accounts.token_program.to_account_info();
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-        token::transfer(cpi_ctx, reward_amount)?;
+        token::transfer(cpi_ctx, payout - partner_share)?;
+
+        if let Some(partner) = game.partner {
+            let partner_token_account = ctx
+                .accounts
+                .partner_token_account
+                .as_ref()
+                .ok_or(SolitaireError::InvalidPartner)?;
+            require_keys_eq!(
+                partner_token_account.owner,
+                partner,
+                SolitaireError::InvalidPartner
+            );
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: partner_token_account.to_account_info(),
+                authority: ctx.accounts.escrow_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, partner_share)?;
+        }
+
+        if game.free_game && !game.is_won {
+            let promo_vault = ctx
+                .accounts
+                .promo_vault
+                .as_ref()
+                .ok_or(SolitaireError::PromoVaultRequired)?;
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: promo_vault.to_account_info(),
+                authority: ctx.accounts.escrow_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, game.stake_amount)?;
+        }
+
+        if protocol_fee > 0 {
+            let treasury_token_account = ctx
+                .accounts
+                .treasury_token_account
+                .as_ref()
+                .ok_or(SolitaireError::TreasuryRequired)?;
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: treasury_token_account.to_account_info(),
+                authority: ctx.accounts.escrow_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, protocol_fee - referral_fee)?;
+        }
+
+        if referral_fee > 0 {
+            if let (Some(referrer_token_account), Some(referrer)) =
+                (&ctx.accounts.referrer_token_account, game.referrer)
+            {
+                let cpi_accounts = Transfer {
+                    from: ctx.accounts.escrow_token_account.to_account_info(),
+                    to: referrer_token_account.to_account_info(),
+                    authority: ctx.accounts.escrow_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+                token::transfer(cpi_ctx, referral_fee)?;
+
+                emit!(ReferralPaid {
+                    game_id: game.game_id.clone(),
+                    player: game.authority,
+                    referrer,
+                    amount: referral_fee,
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+        }
+
+        if insurance_payout > 0 {
+            pay_insurance(
+                &ctx.accounts.insurance_vault,
+                &ctx.accounts.insurance_authority,
+                ctx.bumps.insurance_authority,
+                &ctx.accounts.user_token_account,
+                &ctx.accounts.token_program,
+                insurance_payout,
+            )?;
+        }
+
+        // NFT bonus, memecoin boost and record bounty are all paid from the treasury
+        let treasury_payout = nft_bonus + memecoin_boost + record_bounty;
+        if treasury_payout > 0 {
+            if let (Some(treasury_token_account), Some(treasury_authority)) = (
+                &ctx.accounts.treasury_token_account,
+                &ctx.accounts.treasury_authority,
+            ) {
+                let treasury_seeds =
+                    &[b"treasury_authority".as_ref(), &[ctx.bumps.treasury_authority]];
+                let signer = &[&treasury_seeds[..]];
+
+                let cpi_accounts = Transfer {
+                    from: treasury_token_account.to_account_info(),
+                    to: ctx.accounts.user_token_account.to_account_info(),
+                    authority: treasury_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+                token::transfer(cpi_ctx, treasury_payout)?;
+            }
+        }
 
         emit!(GameCompleted {
             game_id: game.game_id.clone(),
             player: game.authority,
             won: game.is_won,
-            score: final_score,
+            score: game.score,
             moves: game.moves,
+            final_state_hash: game.game_state.state_hash(),
+            nft_bonus,
+            memecoin_boost,
+            protocol_fee,
+            fee_discount_bps,
+            insured: game.insured,
+            insurance_payout,
             timestamp: game.updated_at,
         });
 
@@ -160,30 +604,59 @@ pub mod solitaire {
         let game = &mut ctx.accounts.game;
         let clock = Clock::get()?;
 
+        require!(!game.corrupted, SolitaireError::GameCorrupted);
         require!(game.status == GameStatus::Active, SolitaireError::GameNotActive);
         require!(
             ctx.accounts.authority.key() == game.authority,
             SolitaireError::Unauthorized
         );
 
+        game.check_move_clock(clock.unix_timestamp)?;
+
         // Allow withdrawal after 24 hours of inactivity
         let time_since_update = clock.unix_timestamp - game.updated_at;
-        require!(time_since_update >= 86400, SolitaireError::WithdrawalTooEarly);
+        require!(
+            time_since_update >= WITHDRAWAL_DELAY_SECONDS,
+            SolitaireError::WithdrawalTooEarly
+        );
 
-        game.status = GameStatus::Abandoned;
+        game.transition_to(GameStatus::Abandoned)?;
+        game.release_active_slot(ctx.accounts.player_stats.as_deref_mut())?;
+        game.release_insurance(ctx.accounts.insurance_ledger.as_deref_mut())?;
         game.updated_at = clock.unix_timestamp;
 
-        // Return stake (minus penalty)
-        let penalty = game.stake_amount / 10; // 10% penalty
-        let refund_amount = game.stake_amount - penalty;
+        // Return stake (minus penalty); abandoned free games refund the promo vault
+        let (penalty, refund_amount) = if game.free_game {
+            (game.stake_amount, 0)
+        } else {
+            let penalty = game.stake_amount / 10; // 10% penalty
+            (penalty, game.stake_amount - penalty)
+        };
 
         let escrow_seeds = &[
-            b"escrow",
+            b"escrow_authority",
             game.game_id.as_bytes(),
-            &[game.bump],
+            &[ctx.bumps.escrow_authority],
         ];
         let signer = &[&escrow_seeds[..]];
 
+        if game.free_game {
+            let promo_vault = ctx
+                .accounts
+                .promo_vault
+                .as_ref()
+                .ok_or(SolitaireError::PromoVaultRequired)?;
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: promo_vault.to_account_info(),
+                authority: ctx.accounts.escrow_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, game.stake_amount)?;
+        }
+
         let cpi_accounts = Transfer {
             from: ctx.accounts.escrow_token_account.to_account_info(),
             to: ctx.accounts.user_token_account.to_account_info(),
@@ -193,6 +666,18 @@ pub mod solitaire {
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
         token::transfer(cpi_ctx, refund_amount)?;
 
+        // Insured surrenders get the penalty back from the insurance vault
+        if game.insured && penalty > 0 {
+            pay_insurance(
+                &ctx.accounts.insurance_vault,
+                &ctx.accounts.insurance_authority,
+                ctx.bumps.insurance_authority,
+                &ctx.accounts.user_token_account,
+                &ctx.accounts.token_program,
+                penalty,
+            )?;
+        }
+
         emit!(StakeWithdrawn {
             game_id: game.game_id.clone(),
             player: game.authority,
@@ -203,115 +688,2973 @@ pub mod solitaire {
 
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(game_id: String)]
-pub struct InitializeGame<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + 32 + 32 + 32 + 1 + 8 + 8 + 1 + 1 + 8 + 8 + 256 + 32 + 1,
-        seeds = [b"game", authority.key().as_ref(), game_id.as_bytes()],
-        bump
-    )]
-    pub game: Account<'info, GameAccount>,
+    /// Backs out of a game that has seen no move within `CANCEL_GRACE_SECONDS`
+    /// of creation. The deal is already readable on chain, so a staked game
+    /// leaves `CANCEL_PENALTY_BPS` of its stake in escrow; a free exit would
+    /// let players discard unwinnable boards. Fronted stakes go back to the
+    /// promo vault in full, and an insurance premium is not refunded.
+    pub fn cancel_game(ctx: Context<WithdrawStake>) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        let clock = Clock::get()?;
 
-    #[account(
-        init,
-        payer = authority,
-        token::mint = reward_mint,
-        token::authority = escrow_authority,
-        seeds = [b"escrow", game_id.as_bytes()],
-        bump
-    )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+        require!(!game.corrupted, SolitaireError::GameCorrupted);
+        require!(game.status == GameStatus::Active, SolitaireError::GameNotActive);
+        require!(
+            ctx.accounts.authority.key() == game.authority,
+            SolitaireError::Unauthorized
+        );
+        game.check_move_clock(clock.unix_timestamp)?;
+        require!(
+            game.moves == 0 && clock.unix_timestamp - game.created_at <= CANCEL_GRACE_SECONDS,
+            SolitaireError::CancelWindowClosed
+        );
 
-    #[account(
-        seeds = [b"escrow_authority", game_id.as_bytes()],
-        bump
-    )]
-    pub escrow_authority: AccountInfo<'info>,
+        game.transition_to(GameStatus::Cancelled)?;
+        game.release_active_slot(ctx.accounts.player_stats.as_deref_mut())?;
+        game.release_insurance(ctx.accounts.insurance_ledger.as_deref_mut())?;
+        game.updated_at = clock.unix_timestamp;
 
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+        let escrow_seeds = &[
+            b"escrow_authority",
+            game.game_id.as_bytes(),
+            &[ctx.bumps.escrow_authority],
+        ];
+        let signer = &[&escrow_seeds[..]];
 
-    #[account(constraint = reward_mint.key() == reward_mint_info.key())]
-    pub reward_mint_info: Account<'info, Mint>,
+        let (refund_to, penalty) = if game.free_game {
+            let promo_vault = ctx
+                .accounts
+                .promo_vault
+                .as_ref()
+                .ok_or(SolitaireError::PromoVaultRequired)?;
+            (promo_vault.to_account_info(), 0)
+        } else {
+            (
+                ctx.accounts.user_token_account.to_account_info(),
+                bps_of(game.stake_amount, CANCEL_PENALTY_BPS)?,
+            )
+        };
+        let refund_amount = game.stake_amount - penalty;
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.escrow_token_account.to_account_info(),
+            to: refund_to,
+            authority: ctx.accounts.escrow_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, refund_amount)?;
 
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
-}
+        emit!(GameCancelled {
+            game_id: game.game_id.clone(),
+            player: game.authority,
+            refunded: refund_amount,
+            penalty,
+            free_game: game.free_game,
+            timestamp: game.updated_at,
+        });
 
-#[derive(Accounts)]
-pub struct MakeMove<'info> {
-    #[account(mut)]
-    pub game: Account<'info, GameAccount>,
+        Ok(())
+    }
 
-    pub authority: Signer<'info>,
-}
+    /// Permissionless final disposition for games untouched for
+    /// `STALE_ESCROW_SECONDS`: whatever is left in escrow goes to the treasury
+    /// and the escrow account is closed back to the player.
+    pub fn sweep_stale_escrow(ctx: Context<SweepStaleEscrow>) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        let clock = Clock::get()?;
 
-#[derive(Accounts)]
-pub struct CompleteGame<'info> {
-    #[account(mut)]
-    pub game: Account<'info, GameAccount>,
+        require!(!game.corrupted, SolitaireError::GameCorrupted);
+        let time_since_update = clock.unix_timestamp - game.updated_at;
+        require!(
+            time_since_update >= STALE_ESCROW_SECONDS,
+            SolitaireError::EscrowNotStale
+        );
+        require!(
+            clock.unix_timestamp > game.dispute_deadline,
+            SolitaireError::DisputeWindowOpen
+        );
 
-    #[account(mut)]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+        game.transition_to(GameStatus::Swept)?;
+        game.release_active_slot(ctx.accounts.player_stats.as_deref_mut())?;
+        game.release_insurance(ctx.accounts.insurance_ledger.as_deref_mut())?;
+        game.bond_amount = 0;
+        game.updated_at = clock.unix_timestamp;
 
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+        let amount = ctx.accounts.escrow_token_account.amount;
+        let escrow_seeds = &[
+            b"escrow_authority",
+            game.game_id.as_bytes(),
+            &[ctx.bumps.escrow_authority],
+        ];
+        let signer = &[&escrow_seeds[..]];
 
-    #[account(seeds = [b"escrow_authority", game.game_id.as_bytes()], bump)]
-    pub escrow_authority: AccountInfo<'info>,
+        if amount > 0 {
+            // Fronted stakes go back to the promo vault, as on every other exit
+            let sweep_to = if game.free_game {
+                ctx.accounts
+                    .promo_vault
+                    .as_ref()
+                    .ok_or(SolitaireError::PromoVaultRequired)?
+                    .to_account_info()
+            } else {
+                ctx.accounts.treasury_token_account.to_account_info()
+            };
 
-    pub authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-}
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: sweep_to,
+                authority: ctx.accounts.escrow_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, amount)?;
+        }
 
-#[derive(Accounts)]
-pub struct WithdrawStake<'info> {
-    #[account(mut)]
-    pub game: Account<'info, GameAccount>,
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.escrow_token_account.to_account_info(),
+            destination: ctx.accounts.player.to_account_info(),
+            authority: ctx.accounts.escrow_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::close_account(cpi_ctx)?;
 
-    #[account(mut)]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+        emit!(StaleEscrowSwept {
+            game_id: game.game_id.clone(),
+            player: game.authority,
+            amount,
+            swept_by: ctx.accounts.caller.key(),
+            timestamp: clock.unix_timestamp,
+        });
 
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+        Ok(())
+    }
 
-    #[account(seeds = [b"escrow_authority", game.game_id.as_bytes()], bump)]
-    pub escrow_authority: AccountInfo<'info>,
+    pub fn claim_timeout(ctx: Context<ClaimTimeout>) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        let clock = Clock::get()?;
 
-    pub authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-}
+        require!(!game.corrupted, SolitaireError::GameCorrupted);
+        require!(game.status == GameStatus::Active, SolitaireError::GameNotActive);
+        require!(!game.is_won, SolitaireError::GameAlreadyWon);
+        require!(
+            game.move_deadline > 0 && clock.unix_timestamp > game.move_deadline,
+            SolitaireError::MoveClockRunning
+        );
 
-#[account]
-pub struct GameAccount {
-    pub authority: Pubkey,
-    pub game_id: String,
-    pub stake_amount: u64,
-    pub reward_mint: Pubkey,
-    pub status: GameStatus,
-    pub moves: u32,
-    pub score: u64,
-    pub is_won: bool,
-    pub created_at: i64,
-    pub updated_at: i64,
-    pub game_state: GameState,
-    pub bump: u8,
-}
+        game.transition_to(GameStatus::Expired)?;
+        game.release_active_slot(ctx.accounts.player_stats.as_deref_mut())?;
+        game.release_insurance(ctx.accounts.insurance_ledger.as_deref_mut())?;
+        game.updated_at = clock.unix_timestamp;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+        // The stake is forfeited: fronted stakes go back to the promo vault,
+        // everything else to the treasury
+        let forfeit_to = if game.free_game {
+            ctx.accounts
+                .promo_vault
+                .as_ref()
+                .ok_or(SolitaireError::PromoVaultRequired)?
+                .to_account_info()
+        } else {
+            ctx.accounts.treasury_token_account.to_account_info()
+        };
+
+        let escrow_seeds = &[
+            b"escrow_authority",
+            game.game_id.as_bytes(),
+            &[ctx.bumps.escrow_authority],
+        ];
+        let signer = &[&escrow_seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.escrow_token_account.to_account_info(),
+            to: forfeit_to,
+            authority: ctx.accounts.escrow_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, game.stake_amount)?;
+
+        // A timeout is a loss, so insured games get the share a finished loss
+        // would have been topped up by
+        let insurance_payout = game.insured_liability();
+        if insurance_payout > 0 {
+            let player_token_account = ctx
+                .accounts
+                .player_token_account
+                .as_ref()
+                .ok_or(SolitaireError::PlayerTokenAccountRequired)?;
+            pay_insurance(
+                &ctx.accounts.insurance_vault,
+                &ctx.accounts.insurance_authority,
+                ctx.bumps.insurance_authority,
+                player_token_account,
+                &ctx.accounts.token_program,
+                insurance_payout,
+            )?;
+        }
+
+        emit!(MoveClockExpired {
+            game_id: game.game_id.clone(),
+            player: game.authority,
+            move_deadline: game.move_deadline,
+            forfeited: game.stake_amount,
+            insurance_payout,
+            claimed_by: ctx.accounts.caller.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn challenge_game(ctx: Context<ChallengeGame>, move_index: u32) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        let dispute = &ctx.accounts.config.dispute;
+        let clock = Clock::get()?;
+
+        require!(!game.corrupted, SolitaireError::GameCorrupted);
+        require!(game.status == GameStatus::Completed, SolitaireError::GameNotCompleted);
+        require!(!game.fraudulent, SolitaireError::GameAlreadyFlagged);
+        require!(
+            clock.unix_timestamp <= game.dispute_deadline,
+            SolitaireError::DisputeWindowClosed
+        );
+        require!(
+            (move_index as usize) < game.move_log.len(),
+            SolitaireError::InvalidChallenge
+        );
+
+        // The challenger's bond goes to the treasury up front and is only
+        // returned if the challenge is upheld
+        let challenge_bond = bps_of(game.stake_amount, dispute.challenge_bond_bps)?;
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.challenger_token_account.to_account_info(),
+            to: ctx.accounts.treasury_token_account.to_account_info(),
+            authority: ctx.accounts.challenger.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, challenge_bond)?;
+
+        // Re-deal from the seed and replay the log up to the cited move, which
+        // must be the first illegal one: an earlier illegal move should be
+        // cited instead. The cited move is upheld if it is illegal or, as the
+        // last move, fails to reproduce the board, score and result the game
+        // was completed with, since that board is stored state, not a replay
+        let cited = move_index as usize;
+        let mut replayed = GameState::replay(
+            game.authority,
+            game.seed,
+            game.created_at,
+            &game.move_log[..cited],
+            &game.scoring,
+            game.allow_foundation_to_tableau,
+            game.game_state.variant,
+        )
+        .map_err(|_| SolitaireError::InvalidChallenge)?;
+        let upheld = match replayed.apply_move(
+            &game.move_log[cited],
+            &game.scoring,
+            game.allow_foundation_to_tableau,
+        ) {
+            Ok(_) if cited + 1 == game.move_log.len() => {
+                replayed.state_hash() != game.game_state.state_hash()
+                    || replayed.score != game.game_state.score
+                    || replayed.is_won() != game.is_won
+            }
+            Ok(_) => false,
+            Err(_) => true,
+        };
+
+        let mut clawback = 0;
+        let mut bounty = 0;
+        if upheld {
+            clawback = game.bond_amount;
+            // The bounty can't outrun the treasury, clawback included; the
+            // challenger's own bond is refunded rather than counted
+            let treasury_available = ctx.accounts.treasury_token_account.amount + clawback;
+            bounty = bps_of(game.stake_amount, dispute.challenger_bounty_bps)?.min(treasury_available);
+
+            // Claw back the held payout into the treasury
+            let escrow_seeds = &[
+                b"escrow_authority",
+                game.game_id.as_bytes(),
+                &[ctx.bumps.escrow_authority],
+            ];
+            let signer = &[&escrow_seeds[..]];
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                to: ctx.accounts.treasury_token_account.to_account_info(),
+                authority: ctx.accounts.escrow_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, clawback)?;
+
+            // Refund the challenger's bond plus the bounty
+            let treasury_seeds = &[b"treasury_authority".as_ref(), &[ctx.bumps.treasury_authority]];
+            let signer = &[&treasury_seeds[..]];
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.treasury_token_account.to_account_info(),
+                to: ctx.accounts.challenger_token_account.to_account_info(),
+                authority: ctx.accounts.treasury_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, challenge_bond + bounty)?;
+
+            game.fraudulent = true;
+            game.bond_amount = 0;
+            game.updated_at = clock.unix_timestamp;
+        }
+
+        emit!(GameChallenged {
+            game_id: game.game_id.clone(),
+            challenger: ctx.accounts.challenger.key(),
+            move_index,
+            upheld,
+            challenge_bond,
+            clawback,
+            bounty,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn release_bond(ctx: Context<ReleaseBond>) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        let clock = Clock::get()?;
+
+        require!(!game.corrupted, SolitaireError::GameCorrupted);
+        require!(game.status == GameStatus::Completed, SolitaireError::GameNotCompleted);
+        require!(!game.fraudulent, SolitaireError::GameAlreadyFlagged);
+        require!(game.bond_amount > 0, SolitaireError::NoBondHeld);
+        require!(
+            clock.unix_timestamp > game.dispute_deadline,
+            SolitaireError::DisputeWindowOpen
+        );
+
+        let amount = game.bond_amount;
+        let escrow_seeds = &[
+            b"escrow_authority",
+            game.game_id.as_bytes(),
+            &[ctx.bumps.escrow_authority],
+        ];
+        let signer = &[&escrow_seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.escrow_token_account.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.escrow_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        game.bond_amount = 0;
+
+        emit!(BondReleased {
+            game_id: game.game_id.clone(),
+            player: game.authority,
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_partner(ctx: Context<SetPartner>, partner: Option<Pubkey>) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+
+        require!(game.status == GameStatus::Active, SolitaireError::GameNotActive);
+        require!(game.moves == 0, SolitaireError::PartnerLocked);
+        require!(partner != Some(game.authority), SolitaireError::InvalidPartner);
+
+        game.partner = partner;
+        game.next_to_move = game.authority;
+
+        emit!(PartnerSet {
+            game_id: game.game_id.clone(),
+            player: game.authority,
+            partner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn grow_game_account(ctx: Context<GrowGameAccount>) -> Result<()> {
+        let game = &ctx.accounts.game;
+        let data_len = game.to_account_info().data_len();
+
+        require!(game.status == GameStatus::Active, SolitaireError::GameNotActive);
+        require!(game.move_log.len() < MAX_MOVE_LOG, SolitaireError::MoveLogFull);
+
+        emit!(GameAccountGrown {
+            game_id: game.game_id.clone(),
+            size: data_len as u32,
+            move_log_capacity: GameAccount::move_log_capacity(data_len) as u32,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_game(ctx: Context<CloseGame>) -> Result<()> {
+        let game = &ctx.accounts.game;
+        let clock = Clock::get()?;
+
+        require!(!game.corrupted, SolitaireError::GameCorrupted);
+        require!(game.status.is_terminal(), SolitaireError::GameNotClosable);
+        require!(game.bond_amount == 0, SolitaireError::GameNotClosable);
+        require!(
+            clock.unix_timestamp > game.dispute_deadline,
+            SolitaireError::DisputeWindowOpen
+        );
+
+        // Whatever settlement left in the escrow (a withdraw or cancel
+        // penalty) goes to the house before the escrow itself is closed, so
+        // neither outlives the game account. Swept games have no escrow left.
+        let escrow_info = ctx.accounts.escrow_token_account.to_account_info();
+        let mut escrow_swept = 0;
+        if *escrow_info.owner == token::ID && !escrow_info.data_is_empty() {
+            let escrow = TokenAccount::try_deserialize(&mut &escrow_info.try_borrow_data()?[..])?;
+            let escrow_seeds = &[
+                b"escrow_authority",
+                game.game_id.as_bytes(),
+                &[ctx.bumps.escrow_authority],
+            ];
+            let signer = &[&escrow_seeds[..]];
+
+            if escrow.amount > 0 {
+                let destination = if game.free_game {
+                    ctx.accounts
+                        .promo_vault
+                        .as_ref()
+                        .ok_or(SolitaireError::PromoVaultRequired)?
+                        .to_account_info()
+                } else {
+                    ctx.accounts
+                        .treasury_token_account
+                        .as_ref()
+                        .ok_or(SolitaireError::TreasuryRequired)?
+                        .to_account_info()
+                };
+
+                let cpi_accounts = Transfer {
+                    from: escrow_info.clone(),
+                    to: destination,
+                    authority: ctx.accounts.escrow_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+                token::transfer(cpi_ctx, escrow.amount)?;
+                escrow_swept = escrow.amount;
+            }
+
+            let cpi_accounts = CloseAccount {
+                account: escrow_info,
+                destination: ctx.accounts.authority.to_account_info(),
+                authority: ctx.accounts.escrow_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::close_account(cpi_ctx)?;
+        }
+
+        emit!(GameClosed {
+            game_id: game.game_id.clone(),
+            player: game.authority,
+            refunded_lamports: game.to_account_info().lamports(),
+            escrow_swept,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless self-check of the board. A failed audit flags the game
+    /// `corrupted`, which freezes every fund-moving instruction until an
+    /// admin clears it.
+    pub fn audit_deck(ctx: Context<AuditDeck>) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        let clock = Clock::get()?;
+
+        match game.game_state.audit() {
+            Ok(()) => emit!(DeckAuditPassed {
+                game_id: game.game_id.clone(),
+                state_hash: game.game_state.state_hash(),
+                timestamp: clock.unix_timestamp,
+            }),
+            Err(violation) => {
+                game.corrupted = true;
+                emit!(DeckIntegrityFailed {
+                    game_id: game.game_id.clone(),
+                    violation,
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn clear_corrupted_flag(ctx: Context<ReviewGame>) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+
+        require!(game.corrupted, SolitaireError::GameNotCorrupted);
+        game.corrupted = false;
+
+        emit!(GameCorruptionCleared {
+            game_id: game.game_id.clone(),
+            admin: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_tournament_schedule(
+        ctx: Context<InitializeTournamentSchedule>,
+        schedule_id: u32,
+        first_start: i64,
+        params: TournamentScheduleParams,
+    ) -> Result<()> {
+        params.validate()?;
+
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.schedule_id = schedule_id;
+        schedule.mint = ctx.accounts.mint.key();
+        schedule.params = params;
+        schedule.next_start = first_start;
+        schedule.epoch_index = 0;
+        schedule.paused = false;
+        schedule.bump = ctx.bumps.schedule;
+
+        emit!(TournamentScheduleUpdated {
+            schedule: schedule.key(),
+            params,
+            next_start: schedule.next_start,
+            paused: schedule.paused,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_tournament_schedule(
+        ctx: Context<UpdateTournamentSchedule>,
+        params: TournamentScheduleParams,
+        next_start: Option<i64>,
+    ) -> Result<()> {
+        params.validate()?;
+
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.params = params;
+        if let Some(next_start) = next_start {
+            schedule.next_start = next_start;
+        }
+
+        emit!(TournamentScheduleUpdated {
+            schedule: schedule.key(),
+            params,
+            next_start: schedule.next_start,
+            paused: schedule.paused,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_tournament_schedule_paused(
+        ctx: Context<UpdateTournamentSchedule>,
+        paused: bool,
+    ) -> Result<()> {
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.paused = paused;
+
+        emit!(TournamentScheduleUpdated {
+            schedule: schedule.key(),
+            params: schedule.params,
+            next_start: schedule.next_start,
+            paused,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless: creates the tournament for `epoch_index` once the
+    /// schedule's `next_start` has passed. A late crank starts the latest
+    /// slot that is due and skips the ones missed, so a backlog can't be
+    /// cranked through for incentives. A second crank of the same epoch
+    /// fails on the tournament PDA init.
+    pub fn crank_schedule(ctx: Context<CrankSchedule>, epoch_index: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        let schedule = &ctx.accounts.schedule;
+
+        require!(!schedule.paused, SolitaireError::SchedulePaused);
+        require!(epoch_index == schedule.epoch_index, SolitaireError::InvalidEpoch);
+        require!(
+            clock.unix_timestamp >= schedule.next_start,
+            SolitaireError::ScheduleNotDue
+        );
+
+        let params = schedule.params;
+        let missed = (clock.unix_timestamp - schedule.next_start) / params.cadence_seconds;
+        let start_time = missed
+            .checked_mul(params.cadence_seconds)
+            .and_then(|skipped| schedule.next_start.checked_add(skipped))
+            .ok_or(SolitaireError::GameStateError)?;
+        let end_time = start_time
+            .checked_add(params.duration_seconds)
+            .ok_or(SolitaireError::GameStateError)?;
+
+        // Crank incentive comes out of the fee reserve, as far as it stretches
+        let incentive = params.crank_incentive.min(ctx.accounts.fee_reserve.amount);
+        if incentive > 0 {
+            let schedule_id = schedule.schedule_id.to_le_bytes();
+            let schedule_seeds = &[
+                b"tournament_schedule".as_ref(),
+                schedule_id.as_ref(),
+                &[schedule.bump],
+            ];
+            let signer = &[&schedule_seeds[..]];
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.fee_reserve.to_account_info(),
+                to: ctx.accounts.caller_token_account.to_account_info(),
+                authority: ctx.accounts.schedule.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, incentive)?;
+        }
+
+        let tournament = &mut ctx.accounts.tournament;
+        tournament.schedule = ctx.accounts.schedule.key();
+        tournament.epoch_index = epoch_index;
+        tournament.start_time = start_time;
+        tournament.end_time = end_time;
+        tournament.entry_fee = params.entry_fee;
+        tournament.payout_curve = params.payout_curve;
+        tournament.bump = ctx.bumps.tournament;
+
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.epoch_index += 1;
+        schedule.next_start = start_time
+            .checked_add(params.cadence_seconds)
+            .ok_or(SolitaireError::GameStateError)?;
+
+        emit!(TournamentCreated {
+            schedule: schedule.key(),
+            tournament: tournament.key(),
+            epoch_index,
+            start_time,
+            end_time,
+            entry_fee: params.entry_fee,
+            cranked_by: ctx.accounts.caller.key(),
+            crank_incentive: incentive,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_player_stats(ctx: Context<InitializePlayerStats>) -> Result<()> {
+        let player_stats = &mut ctx.accounts.player_stats;
+
+        player_stats.init_if_new(ctx.accounts.player.key(), ctx.bumps.player_stats);
+
+        Ok(())
+    }
+
+    pub fn initialize_global_stats(ctx: Context<InitializeGlobalStats>) -> Result<()> {
+        let global_stats = &mut ctx.accounts.global_stats;
+
+        global_stats.fastest_win_seconds = None;
+        global_stats.record_holder = Pubkey::default();
+        global_stats.record_set_at = 0;
+        global_stats.bump = ctx.bumps.global_stats;
+
+        Ok(())
+    }
+
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+        emit!(TreasuryInitialized {
+            mint: ctx.accounts.mint.key(),
+            treasury: ctx.accounts.treasury_token_account.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Immediate admin withdrawal, allowed up to the configured threshold.
+    /// Anything larger, and everything while the threshold is 0, has to be
+    /// queued behind the timelock.
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        let treasury = &ctx.accounts.config.treasury;
+
+        require!(amount > 0, SolitaireError::InvalidAmount);
+        require!(
+            amount <= ctx.accounts.treasury_token_account.amount,
+            SolitaireError::InsufficientTreasuryBalance
+        );
+        require!(
+            amount <= treasury.large_withdrawal_threshold,
+            SolitaireError::WithdrawalRequiresTimelock
+        );
+
+        let treasury_seeds = &[b"treasury_authority".as_ref(), &[ctx.bumps.treasury_authority]];
+        let signer = &[&treasury_seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.treasury_token_account.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.treasury_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        emit!(TreasuryWithdrawn {
+            mint: ctx.accounts.treasury_token_account.mint,
+            destination: ctx.accounts.destination.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn queue_treasury_withdrawal(
+        ctx: Context<QueueTreasuryWithdrawal>,
+        amount: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(amount > 0, SolitaireError::InvalidAmount);
+
+        let pending = &mut ctx.accounts.pending_withdrawal;
+        pending.mint = ctx.accounts.treasury_token_account.mint;
+        pending.destination = ctx.accounts.destination.key();
+        pending.amount = amount;
+        pending.executable_at = clock
+            .unix_timestamp
+            .checked_add(ctx.accounts.config.treasury.withdrawal_timelock_seconds)
+            .ok_or(SolitaireError::GameStateError)?;
+        pending.bump = ctx.bumps.pending_withdrawal;
+
+        emit!(TreasuryWithdrawalQueued {
+            mint: pending.mint,
+            destination: pending.destination,
+            amount,
+            executable_at: pending.executable_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn execute_treasury_withdrawal(ctx: Context<ExecuteTreasuryWithdrawal>) -> Result<()> {
+        let pending = &ctx.accounts.pending_withdrawal;
+        let clock = Clock::get()?;
+
+        require!(
+            clock.unix_timestamp >= pending.executable_at,
+            SolitaireError::WithdrawalTimelocked
+        );
+        require!(
+            pending.amount <= ctx.accounts.treasury_token_account.amount,
+            SolitaireError::InsufficientTreasuryBalance
+        );
+
+        let treasury_seeds = &[b"treasury_authority".as_ref(), &[ctx.bumps.treasury_authority]];
+        let signer = &[&treasury_seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.treasury_token_account.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.treasury_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, pending.amount)?;
+
+        emit!(TreasuryWithdrawn {
+            mint: pending.mint,
+            destination: pending.destination,
+            amount: pending.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_treasury_withdrawal(ctx: Context<CancelTreasuryWithdrawal>) -> Result<()> {
+        let pending = &ctx.accounts.pending_withdrawal;
+
+        emit!(TreasuryWithdrawalCancelled {
+            mint: pending.mint,
+            destination: pending.destination,
+            amount: pending.amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless: emits the balance of every treasury token account
+    /// passed in `remaining_accounts` for off-chain accounting. Each must be
+    /// owned by the treasury authority PDA.
+    pub fn treasury_balance_snapshot(ctx: Context<TreasuryBalanceSnapshot>) -> Result<()> {
+        let treasury_authority = ctx.accounts.treasury_authority.key();
+
+        let mut balances = Vec::with_capacity(ctx.remaining_accounts.len());
+        for info in ctx.remaining_accounts {
+            require_keys_eq!(*info.owner, token::ID, SolitaireError::InvalidTreasuryAccount);
+            let token_account = TokenAccount::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            require_keys_eq!(
+                token_account.owner,
+                treasury_authority,
+                SolitaireError::InvalidTreasuryAccount
+            );
+            balances.push(TreasuryBalance {
+                account: info.key(),
+                mint: token_account.mint,
+                amount: token_account.amount,
+            });
+        }
+
+        emit!(TreasuryBalanceSnapshotTaken {
+            balances,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_insurance_vault(ctx: Context<InitializeInsuranceVault>) -> Result<()> {
+        let insurance_ledger = &mut ctx.accounts.insurance_ledger;
+        insurance_ledger.mint = ctx.accounts.mint.key();
+        insurance_ledger.outstanding = 0;
+        insurance_ledger.bump = ctx.bumps.insurance_ledger;
+
+        emit!(InsuranceVaultInitialized {
+            mint: ctx.accounts.mint.key(),
+            insurance_vault: ctx.accounts.insurance_vault.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn fund_insurance_vault(ctx: Context<FundInsuranceVault>, amount: u64) -> Result<()> {
+        require!(amount > 0, SolitaireError::InvalidAmount);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.admin_token_account.to_account_info(),
+            to: ctx.accounts.insurance_vault.to_account_info(),
+            authority: ctx.accounts.admin.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        emit!(InsuranceVaultFunded {
+            mint: ctx.accounts.insurance_vault.mint,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_insurance_vault(
+        ctx: Context<WithdrawInsuranceVault>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, SolitaireError::InvalidAmount);
+        // Open policies stay covered
+        require!(
+            ctx.accounts.insurance_vault.amount.saturating_sub(amount)
+                >= ctx.accounts.insurance_ledger.outstanding,
+            SolitaireError::InsuranceVaultInsolvent
+        );
+
+        let insurance_seeds = &[b"insurance_authority".as_ref(), &[ctx.bumps.insurance_authority]];
+        let signer = &[&insurance_seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.insurance_vault.to_account_info(),
+            to: ctx.accounts.admin_token_account.to_account_info(),
+            authority: ctx.accounts.insurance_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        emit!(InsuranceVaultWithdrawn {
+            mint: ctx.accounts.insurance_vault.mint,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_promo_vault(ctx: Context<InitializePromoVault>) -> Result<()> {
+        emit!(PromoVaultInitialized {
+            mint: ctx.accounts.mint.key(),
+            promo_vault: ctx.accounts.promo_vault.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn fund_promo_vault(ctx: Context<FundPromoVault>, amount: u64) -> Result<()> {
+        require!(amount > 0, SolitaireError::InvalidAmount);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.admin_token_account.to_account_info(),
+            to: ctx.accounts.promo_vault.to_account_info(),
+            authority: ctx.accounts.admin.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        emit!(PromoVaultFunded {
+            mint: ctx.accounts.promo_vault.mint,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_promo_vault(ctx: Context<WithdrawPromoVault>, amount: u64) -> Result<()> {
+        require!(amount > 0, SolitaireError::InvalidAmount);
+
+        let promo_seeds = &[b"promo_authority".as_ref(), &[ctx.bumps.promo_authority]];
+        let signer = &[&promo_seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.promo_vault.to_account_info(),
+            to: ctx.accounts.admin_token_account.to_account_info(),
+            authority: ctx.accounts.promo_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        emit!(PromoVaultWithdrawn {
+            mint: ctx.accounts.promo_vault.mint,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        config.admin = ctx.accounts.admin.key();
+        config.mode_configs = [ModeConfig::default(); GameMode::COUNT];
+        config.dispute = DisputeConfig::default();
+        config.nft_bonus = NftBonusConfig::default();
+        config.free_game_stake = 0;
+        config.record_bounty = 0;
+        config.fees = FeeConfig::default();
+        config.treasury = TreasuryConfig {
+            large_withdrawal_threshold: 0,
+            withdrawal_timelock_seconds: DEFAULT_TREASURY_TIMELOCK_SECONDS,
+        };
+        config.max_active_games = 0;
+        config.insurance_premium_bps = 0;
+        config.memecoin_boost = MemecoinBoostConfig::default();
+        config.stake_discount = StakeDiscountConfig::default();
+        config.bump = ctx.bumps.config;
+
+        Ok(())
+    }
+
+    pub fn update_dispute_config(
+        ctx: Context<UpdateConfig>,
+        dispute: DisputeConfig,
+    ) -> Result<()> {
+        require!(dispute.window_seconds >= 0, SolitaireError::InvalidConfig);
+        require!(
+            dispute.player_bond_bps <= BPS_DENOMINATOR as u16
+                && dispute.challenge_bond_bps <= BPS_DENOMINATOR as u16
+                && dispute.challenger_bounty_bps <= BPS_DENOMINATOR as u16,
+            SolitaireError::InvalidConfig
+        );
+
+        ctx.accounts.config.dispute = dispute;
+
+        emit!(DisputeConfigUpdated {
+            window_seconds: dispute.window_seconds,
+            player_bond_bps: dispute.player_bond_bps,
+            challenge_bond_bps: dispute.challenge_bond_bps,
+            challenger_bounty_bps: dispute.challenger_bounty_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_nft_bonus_config(
+        ctx: Context<UpdateConfig>,
+        nft_bonus: NftBonusConfig,
+    ) -> Result<()> {
+        require!(
+            nft_bonus.bonus_bps <= BPS_DENOMINATOR as u16,
+            SolitaireError::InvalidConfig
+        );
+
+        ctx.accounts.config.nft_bonus = nft_bonus;
+
+        emit!(NftBonusConfigUpdated {
+            collection: nft_bonus.collection,
+            bonus_bps: nft_bonus.bonus_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_memecoin_boost_config(
+        ctx: Context<UpdateConfig>,
+        memecoin_boost: MemecoinBoostConfig,
+    ) -> Result<()> {
+        require!(
+            memecoin_boost.boost_bps <= BPS_DENOMINATOR as u16,
+            SolitaireError::InvalidConfig
+        );
+
+        ctx.accounts.config.memecoin_boost = memecoin_boost;
+
+        emit!(MemecoinBoostConfigUpdated {
+            mint: memecoin_boost.mint,
+            boost_bps: memecoin_boost.boost_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_stake_discount_config(
+        ctx: Context<UpdateConfig>,
+        stake_discount: StakeDiscountConfig,
+    ) -> Result<()> {
+        require!(
+            stake_discount
+                .tiers
+                .iter()
+                .all(|tier| tier.discount_bps <= BPS_DENOMINATOR as u16),
+            SolitaireError::InvalidConfig
+        );
+
+        ctx.accounts.config.stake_discount = stake_discount;
+
+        emit!(StakeDiscountConfigUpdated {
+            program: stake_discount.program,
+            mint: stake_discount.mint,
+            tiers: stake_discount.tiers,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_free_game_stake(ctx: Context<UpdateConfig>, free_game_stake: u64) -> Result<()> {
+        ctx.accounts.config.free_game_stake = free_game_stake;
+
+        emit!(FreeGameStakeUpdated {
+            free_game_stake,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_fee_config(ctx: Context<UpdateConfig>, fees: FeeConfig) -> Result<()> {
+        require!(
+            fees.protocol_fee_bps <= BPS_DENOMINATOR as u16
+                && fees.referral_share_bps <= BPS_DENOMINATOR as u16,
+            SolitaireError::InvalidConfig
+        );
+
+        ctx.accounts.config.fees = fees;
+
+        emit!(FeeConfigUpdated {
+            protocol_fee_bps: fees.protocol_fee_bps,
+            referral_share_bps: fees.referral_share_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_treasury_config(
+        ctx: Context<UpdateConfig>,
+        treasury: TreasuryConfig,
+    ) -> Result<()> {
+        require!(treasury.withdrawal_timelock_seconds >= 0, SolitaireError::InvalidConfig);
+
+        ctx.accounts.config.treasury = treasury;
+
+        emit!(TreasuryConfigUpdated {
+            large_withdrawal_threshold: treasury.large_withdrawal_threshold,
+            withdrawal_timelock_seconds: treasury.withdrawal_timelock_seconds,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Only checked when a game is created, so lowering the cap never
+    /// affects games already running.
+    pub fn update_max_active_games(
+        ctx: Context<UpdateConfig>,
+        max_active_games: u32,
+    ) -> Result<()> {
+        ctx.accounts.config.max_active_games = max_active_games;
+
+        emit!(MaxActiveGamesUpdated {
+            max_active_games,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_insurance_premium(
+        ctx: Context<UpdateConfig>,
+        insurance_premium_bps: u16,
+    ) -> Result<()> {
+        require!(
+            insurance_premium_bps <= BPS_DENOMINATOR as u16,
+            SolitaireError::InvalidConfig
+        );
+
+        ctx.accounts.config.insurance_premium_bps = insurance_premium_bps;
+
+        emit!(InsurancePremiumUpdated {
+            insurance_premium_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_record_bounty(ctx: Context<UpdateConfig>, record_bounty: u64) -> Result<()> {
+        ctx.accounts.config.record_bounty = record_bounty;
+
+        emit!(RecordBountyUpdated {
+            record_bounty,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_mode_config(
+        ctx: Context<UpdateConfig>,
+        mode: GameMode,
+        mode_config: ModeConfig,
+    ) -> Result<()> {
+        require!(mode_config.move_clock_seconds >= 0, SolitaireError::InvalidConfig);
+
+        let config = &mut ctx.accounts.config;
+
+        config.mode_configs[mode.index()] = mode_config;
+
+        emit!(ModeConfigUpdated {
+            mode,
+            min_move_interval_slots: mode_config.min_move_interval_slots,
+            move_clock_seconds: mode_config.move_clock_seconds,
+            scoring: mode_config.scoring,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: String, stake_amount: u64, reward_mint: Pubkey)]
+pub struct InitializeGame<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = GameAccount::space_for(INITIAL_MOVE_LOG_CAPACITY),
+        seeds = [b"game", authority.key().as_ref(), game_id.as_bytes()],
+        bump
+    )]
+    pub game: Account<'info, GameAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = reward_mint_info,
+        token::authority = escrow_authority,
+        seeds = [b"escrow", game_id.as_bytes()],
+        bump
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"escrow_authority", game_id.as_bytes()],
+        bump
+    )]
+    pub escrow_authority: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(constraint = reward_mint.key() == reward_mint_info.key())]
+    pub reward_mint_info: Account<'info, Mint>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, GlobalConfig>,
+
+    /// Supply to record a referrer, or to credit an already recorded one.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + Referral::SIZE,
+        seeds = [b"referral", authority.key().as_ref()],
+        bump
+    )]
+    pub referral: Option<Account<'info, Referral>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + PlayerStats::SIZE,
+        seeds = [b"player_stats", authority.key().as_ref()],
+        bump
+    )]
+    pub player_stats: Account<'info, PlayerStats>,
+
+    /// Receives the premium when the player buys loss insurance.
+    #[account(mut, seeds = [b"insurance_vault", reward_mint_info.key().as_ref()], bump)]
+    pub insurance_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Reserves the policy against the vault when the player buys insurance.
+    #[account(
+        mut,
+        seeds = [b"insurance_ledger", reward_mint_info.key().as_ref()],
+        bump = insurance_ledger.bump
+    )]
+    pub insurance_ledger: Option<Account<'info, InsuranceLedger>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: String, day: u64)]
+pub struct InitializeFreeGame<'info> {
+    /// One per player per UTC day; a second free game that day fails here.
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + DailyFreeGame::SIZE,
+        seeds = [b"daily_free_game", authority.key().as_ref(), &day.to_le_bytes()],
+        bump
+    )]
+    pub daily_free_game: Account<'info, DailyFreeGame>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = GameAccount::space_for(INITIAL_MOVE_LOG_CAPACITY),
+        seeds = [b"game", authority.key().as_ref(), game_id.as_bytes()],
+        bump
+    )]
+    pub game: Account<'info, GameAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = reward_mint_info,
+        token::authority = escrow_authority,
+        seeds = [b"escrow", game_id.as_bytes()],
+        bump
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA signer for the game escrow
+    #[account(seeds = [b"escrow_authority", game_id.as_bytes()], bump)]
+    pub escrow_authority: AccountInfo<'info>,
+
+    #[account(mut, seeds = [b"promo_vault", reward_mint_info.key().as_ref()], bump)]
+    pub promo_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA signer for the promo vaults
+    #[account(seeds = [b"promo_authority"], bump)]
+    pub promo_authority: AccountInfo<'info>,
+
+    pub reward_mint_info: Account<'info, Mint>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + PlayerStats::SIZE,
+        seeds = [b"player_stats", authority.key().as_ref()],
+        bump
+    )]
+    pub player_stats: Account<'info, PlayerStats>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MakeMove<'info> {
+    #[account(mut)]
+    pub game: Account<'info, GameAccount>,
+
+    /// The game authority or, in co-op games, whichever player is next to move.
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteGame<'info> {
+    #[account(mut)]
+    pub game: Account<'info, GameAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(mut)]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = game.reward_mint,
+        token::authority = game.authority
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    /// Receives half the payout in co-op games; owner checked against `game.partner`.
+    #[account(mut, token::mint = game.reward_mint)]
+    pub partner_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"escrow_authority", game.game_id.as_bytes()], bump)]
+    pub escrow_authority: AccountInfo<'info>,
+
+    /// Receives the referrer's share of the protocol fee; owner checked against
+    /// `game.referrer`. Without it the whole fee goes to the treasury.
+    #[account(mut, token::mint = game.reward_mint)]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Receives the fronted stake when a free game is lost.
+    #[account(mut, seeds = [b"promo_vault", game.reward_mint.as_ref()], bump)]
+    pub promo_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Collection NFT held by the player; checked against the config, never required.
+    pub nft_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: Metaplex metadata for the NFT mint, parsed in `holds_collection_nft`
+    pub nft_metadata: Option<AccountInfo<'info>>,
+
+    #[account(mut, seeds = [b"treasury", game.reward_mint.as_ref()], bump)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: PDA signer for the treasury token accounts
+    #[account(seeds = [b"treasury_authority"], bump)]
+    pub treasury_authority: Option<AccountInfo<'info>>,
+
+    #[account(
+        mut,
+        seeds = [b"player_stats", game.authority.as_ref()],
+        bump = player_stats.bump
+    )]
+    pub player_stats: Option<Account<'info, PlayerStats>>,
+
+    #[account(mut, seeds = [b"global_stats"], bump = global_stats.bump)]
+    pub global_stats: Option<Account<'info, GlobalStats>>,
+
+    /// Pays out insured losses.
+    #[account(mut, seeds = [b"insurance_vault", game.reward_mint.as_ref()], bump)]
+    pub insurance_vault: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: PDA signer for the insurance vaults
+    #[account(seeds = [b"insurance_authority"], bump)]
+    pub insurance_authority: Option<AccountInfo<'info>>,
+
+    /// Required for insured games, to release their reserved liability.
+    #[account(
+        mut,
+        seeds = [b"insurance_ledger", game.reward_mint.as_ref()],
+        bump = insurance_ledger.bump
+    )]
+    pub insurance_ledger: Option<Account<'info, InsuranceLedger>>,
+
+    /// CHECK: the player's gaming-token stake account, parsed in `stake_discount_bps`
+    pub stake_account: Option<AccountInfo<'info>>,
+
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawStake<'info> {
+    #[account(mut)]
+    pub game: Account<'info, GameAccount>,
+
+    #[account(mut)]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"escrow_authority", game.game_id.as_bytes()], bump)]
+    pub escrow_authority: AccountInfo<'info>,
+
+    /// Receives the fronted stake when a free game is abandoned or cancelled.
+    #[account(mut, seeds = [b"promo_vault", game.reward_mint.as_ref()], bump)]
+    pub promo_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Required while the game holds an active-game slot.
+    #[account(
+        mut,
+        seeds = [b"player_stats", game.authority.as_ref()],
+        bump = player_stats.bump
+    )]
+    pub player_stats: Option<Account<'info, PlayerStats>>,
+
+    /// Pays out insured losses.
+    #[account(mut, seeds = [b"insurance_vault", game.reward_mint.as_ref()], bump)]
+    pub insurance_vault: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: PDA signer for the insurance vaults
+    #[account(seeds = [b"insurance_authority"], bump)]
+    pub insurance_authority: Option<AccountInfo<'info>>,
+
+    /// Required for insured games, to release their reserved liability.
+    #[account(
+        mut,
+        seeds = [b"insurance_ledger", game.reward_mint.as_ref()],
+        bump = insurance_ledger.bump
+    )]
+    pub insurance_ledger: Option<Account<'info, InsuranceLedger>>,
+
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ChallengeGame<'info> {
+    #[account(mut)]
+    pub game: Account<'info, GameAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(mut, seeds = [b"escrow", game.game_id.as_bytes()], bump)]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA signer for the game escrow
+    #[account(seeds = [b"escrow_authority", game.game_id.as_bytes()], bump)]
+    pub escrow_authority: AccountInfo<'info>,
+
+    #[account(mut, seeds = [b"treasury", game.reward_mint.as_ref()], bump)]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA signer for the treasury token accounts
+    #[account(seeds = [b"treasury_authority"], bump)]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        token::mint = game.reward_mint,
+        token::authority = challenger
+    )]
+    pub challenger_token_account: Account<'info, TokenAccount>,
+
+    pub challenger: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SweepStaleEscrow<'info> {
+    #[account(mut)]
+    pub game: Account<'info, GameAccount>,
+
+    #[account(mut, seeds = [b"escrow", game.game_id.as_bytes()], bump)]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA signer for the game escrow
+    #[account(seeds = [b"escrow_authority", game.game_id.as_bytes()], bump)]
+    pub escrow_authority: AccountInfo<'info>,
+
+    #[account(mut, seeds = [b"treasury", game.reward_mint.as_ref()], bump)]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    /// Receives a free game's fronted stake instead of the treasury.
+    #[account(mut, seeds = [b"promo_vault", game.reward_mint.as_ref()], bump)]
+    pub promo_vault: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: receives the escrow rent; must be the game authority
+    #[account(mut, address = game.authority)]
+    pub player: AccountInfo<'info>,
+
+    /// Required while the game holds an active-game slot.
+    #[account(
+        mut,
+        seeds = [b"player_stats", game.authority.as_ref()],
+        bump = player_stats.bump
+    )]
+    pub player_stats: Option<Account<'info, PlayerStats>>,
+
+    /// Required for insured games, to release their reserved liability.
+    #[account(
+        mut,
+        seeds = [b"insurance_ledger", game.reward_mint.as_ref()],
+        bump = insurance_ledger.bump
+    )]
+    pub insurance_ledger: Option<Account<'info, InsuranceLedger>>,
+
+    pub caller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTimeout<'info> {
+    #[account(mut)]
+    pub game: Account<'info, GameAccount>,
+
+    #[account(mut, seeds = [b"escrow", game.game_id.as_bytes()], bump)]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA signer for the game escrow
+    #[account(seeds = [b"escrow_authority", game.game_id.as_bytes()], bump)]
+    pub escrow_authority: AccountInfo<'info>,
+
+    #[account(mut, seeds = [b"treasury", game.reward_mint.as_ref()], bump)]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    /// Receives the stake instead of the treasury when a free game times out.
+    #[account(mut, seeds = [b"promo_vault", game.reward_mint.as_ref()], bump)]
+    pub promo_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Required while the game holds an active-game slot.
+    #[account(
+        mut,
+        seeds = [b"player_stats", game.authority.as_ref()],
+        bump = player_stats.bump
+    )]
+    pub player_stats: Option<Account<'info, PlayerStats>>,
+
+    /// Required for insured games, to release their reserved liability.
+    #[account(
+        mut,
+        seeds = [b"insurance_ledger", game.reward_mint.as_ref()],
+        bump = insurance_ledger.bump
+    )]
+    pub insurance_ledger: Option<Account<'info, InsuranceLedger>>,
+
+    /// Pays insured games the covered share of the forfeited stake.
+    #[account(mut, seeds = [b"insurance_vault", game.reward_mint.as_ref()], bump)]
+    pub insurance_vault: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: PDA signer for the insurance vaults
+    #[account(seeds = [b"insurance_authority"], bump)]
+    pub insurance_authority: Option<AccountInfo<'info>>,
+
+    /// Receives the insurance payout; required for insured games.
+    #[account(
+        mut,
+        token::mint = game.reward_mint,
+        token::authority = game.authority
+    )]
+    pub player_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Anyone may resolve an expired shot clock.
+    pub caller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseBond<'info> {
+    #[account(mut)]
+    pub game: Account<'info, GameAccount>,
+
+    #[account(mut, seeds = [b"escrow", game.game_id.as_bytes()], bump)]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA signer for the game escrow
+    #[account(seeds = [b"escrow_authority", game.game_id.as_bytes()], bump)]
+    pub escrow_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        token::mint = game.reward_mint,
+        token::authority = game.authority
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetPartner<'info> {
+    #[account(mut, has_one = authority)]
+    pub game: Account<'info, GameAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GrowGameAccount<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        realloc = GameAccount::grown_space(game.to_account_info().data_len()),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub game: Account<'info, GameAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseGame<'info> {
+    #[account(mut, has_one = authority, close = authority)]
+    pub game: Account<'info, GameAccount>,
+
+    /// CHECK: the game escrow, closed here if it still exists; swept games
+    /// already closed it
+    #[account(mut, seeds = [b"escrow", game.game_id.as_bytes()], bump)]
+    pub escrow_token_account: AccountInfo<'info>,
+
+    /// CHECK: PDA signer for the game escrow
+    #[account(seeds = [b"escrow_authority", game.game_id.as_bytes()], bump)]
+    pub escrow_authority: AccountInfo<'info>,
+
+    /// Receives a staked game's escrow leftovers.
+    #[account(mut, seeds = [b"treasury", game.reward_mint.as_ref()], bump)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Receives a free game's escrow leftovers.
+    #[account(mut, seeds = [b"promo_vault", game.reward_mint.as_ref()], bump)]
+    pub promo_vault: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AuditDeck<'info> {
+    #[account(mut)]
+    pub game: Account<'info, GameAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ReviewGame<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(mut)]
+    pub game: Account<'info, GameAccount>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(schedule_id: u32)]
+pub struct InitializeTournamentSchedule<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + TournamentSchedule::SIZE,
+        seeds = [b"tournament_schedule", schedule_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub schedule: Account<'info, TournamentSchedule>,
+
+    /// Funds crank incentives; owned by the schedule PDA.
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = schedule,
+        seeds = [b"schedule_reserve", schedule.key().as_ref()],
+        bump
+    )]
+    pub fee_reserve: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTournamentSchedule<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"tournament_schedule", schedule.schedule_id.to_le_bytes().as_ref()],
+        bump = schedule.bump
+    )]
+    pub schedule: Account<'info, TournamentSchedule>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_index: u64)]
+pub struct CrankSchedule<'info> {
+    #[account(
+        mut,
+        seeds = [b"tournament_schedule", schedule.schedule_id.to_le_bytes().as_ref()],
+        bump = schedule.bump
+    )]
+    pub schedule: Account<'info, TournamentSchedule>,
+
+    #[account(
+        init,
+        payer = caller,
+        space = 8 + Tournament::SIZE,
+        seeds = [b"tournament", schedule.key().as_ref(), epoch_index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub tournament: Account<'info, Tournament>,
+
+    #[account(mut, seeds = [b"schedule_reserve", schedule.key().as_ref()], bump)]
+    pub fee_reserve: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = schedule.mint)]
+    pub caller_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePlayerStats<'info> {
+    #[account(
+        init,
+        payer = player,
+        space = 8 + PlayerStats::SIZE,
+        seeds = [b"player_stats", player.key().as_ref()],
+        bump
+    )]
+    pub player_stats: Account<'info, PlayerStats>,
+
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeGlobalStats<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + GlobalStats::SIZE,
+        seeds = [b"global_stats"],
+        bump
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = treasury_authority,
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA signer for the treasury token accounts
+    #[account(seeds = [b"treasury_authority"], bump)]
+    pub treasury_authority: AccountInfo<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", treasury_token_account.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA signer for the treasury token accounts
+    #[account(seeds = [b"treasury_authority"], bump)]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(mut, token::mint = treasury_token_account.mint)]
+    pub destination: Account<'info, TokenAccount>,
+
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct QueueTreasuryWithdrawal<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(seeds = [b"treasury", treasury_token_account.mint.as_ref()], bump)]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    /// One queued withdrawal per treasury mint at a time.
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PendingWithdrawal::SIZE,
+        seeds = [b"pending_withdrawal", treasury_token_account.mint.as_ref()],
+        bump
+    )]
+    pub pending_withdrawal: Account<'info, PendingWithdrawal>,
+
+    #[account(token::mint = treasury_token_account.mint)]
+    pub destination: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTreasuryWithdrawal<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"pending_withdrawal", pending_withdrawal.mint.as_ref()],
+        bump = pending_withdrawal.bump,
+        has_one = destination
+    )]
+    pub pending_withdrawal: Account<'info, PendingWithdrawal>,
+
+    #[account(mut, seeds = [b"treasury", pending_withdrawal.mint.as_ref()], bump)]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA signer for the treasury token accounts
+    #[account(seeds = [b"treasury_authority"], bump)]
+    pub treasury_authority: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub destination: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTreasuryWithdrawal<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"pending_withdrawal", pending_withdrawal.mint.as_ref()],
+        bump = pending_withdrawal.bump
+    )]
+    pub pending_withdrawal: Account<'info, PendingWithdrawal>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TreasuryBalanceSnapshot<'info> {
+    /// CHECK: PDA signer for the treasury token accounts
+    #[account(seeds = [b"treasury_authority"], bump)]
+    pub treasury_authority: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeInsuranceVault<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = insurance_authority,
+        seeds = [b"insurance_vault", mint.key().as_ref()],
+        bump
+    )]
+    pub insurance_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + InsuranceLedger::SIZE,
+        seeds = [b"insurance_ledger", mint.key().as_ref()],
+        bump
+    )]
+    pub insurance_ledger: Account<'info, InsuranceLedger>,
+
+    /// CHECK: PDA signer for the insurance vaults
+    #[account(seeds = [b"insurance_authority"], bump)]
+    pub insurance_authority: AccountInfo<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct FundInsuranceVault<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(mut, seeds = [b"insurance_vault", insurance_vault.mint.as_ref()], bump)]
+    pub insurance_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = insurance_vault.mint, token::authority = admin)]
+    pub admin_token_account: Account<'info, TokenAccount>,
+
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawInsuranceVault<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(mut, seeds = [b"insurance_vault", insurance_vault.mint.as_ref()], bump)]
+    pub insurance_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"insurance_ledger", insurance_vault.mint.as_ref()],
+        bump = insurance_ledger.bump
+    )]
+    pub insurance_ledger: Account<'info, InsuranceLedger>,
+
+    /// CHECK: PDA signer for the insurance vaults
+    #[account(seeds = [b"insurance_authority"], bump)]
+    pub insurance_authority: AccountInfo<'info>,
+
+    #[account(mut, token::mint = insurance_vault.mint)]
+    pub admin_token_account: Account<'info, TokenAccount>,
+
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePromoVault<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = promo_authority,
+        seeds = [b"promo_vault", mint.key().as_ref()],
+        bump
+    )]
+    pub promo_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA signer for the promo vaults
+    #[account(seeds = [b"promo_authority"], bump)]
+    pub promo_authority: AccountInfo<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct FundPromoVault<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(mut, seeds = [b"promo_vault", promo_vault.mint.as_ref()], bump)]
+    pub promo_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = promo_vault.mint, token::authority = admin)]
+    pub admin_token_account: Account<'info, TokenAccount>,
+
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawPromoVault<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(mut, seeds = [b"promo_vault", promo_vault.mint.as_ref()], bump)]
+    pub promo_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA signer for the promo vaults
+    #[account(seeds = [b"promo_authority"], bump)]
+    pub promo_authority: AccountInfo<'info>,
+
+    #[account(mut, token::mint = promo_vault.mint)]
+    pub admin_token_account: Account<'info, TokenAccount>,
+
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8
+            + 32
+            + ModeConfig::SIZE * GameMode::COUNT
+            + DisputeConfig::SIZE
+            + NftBonusConfig::SIZE
+            + 8
+            + 8
+            + FeeConfig::SIZE
+            + TreasuryConfig::SIZE
+            + 4
+            + 2
+            + MemecoinBoostConfig::SIZE
+            + StakeDiscountConfig::SIZE
+            + 1,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, GlobalConfig>,
+
+    /// Only the program's upgrade authority may claim the admin seat, so the
+    /// config cannot be front-run between deploy and initialization.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, program::Solitaire>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ SolitaireError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, GlobalConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[account]
+pub struct GlobalConfig {
+    pub admin: Pubkey,
+    pub mode_configs: [ModeConfig; GameMode::COUNT],
+    pub dispute: DisputeConfig,
+    pub nft_bonus: NftBonusConfig,
+    /// Stake fronted by the promo vault for each daily free game; 0 disables them.
+    pub free_game_stake: u64,
+    /// Paid from the treasury for breaking the global fastest-win record; 0 disables it.
+    pub record_bounty: u64,
+    pub fees: FeeConfig,
+    pub treasury: TreasuryConfig,
+    /// Most Active games one wallet may hold at once; 0 means no cap.
+    pub max_active_games: u32,
+    /// Premium for loss insurance as a share of the stake; 0 disables insurance.
+    pub insurance_premium_bps: u16,
+    pub memecoin_boost: MemecoinBoostConfig,
+    pub stake_discount: StakeDiscountConfig,
+    pub bump: u8,
+}
+
+impl GlobalConfig {
+    pub fn mode_config(&self, mode: GameMode) -> &ModeConfig {
+        &self.mode_configs[mode.index()]
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct ModeConfig {
+    /// Minimum slots between consecutive moves on one game; 0 disables the limit.
+    pub min_move_interval_slots: u64,
+    /// Defaults copied onto each new game of this mode.
+    pub scoring: ScoringRules,
+    /// Seconds allowed between moves before the game can be forfeited; 0 disables
+    /// the shot clock. Intended for Blitz and Race.
+    pub move_clock_seconds: i64,
+}
+
+impl ModeConfig {
+    pub const SIZE: usize = 8 + ScoringRules::SIZE + 8;
+}
+
+/// Point values for a game, snapshotted onto `GameAccount` at init so admin
+/// changes to the mode defaults never rescore games already in flight.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ScoringRules {
+    pub waste_to_tableau: u16,
+    /// Awarded for a card reaching a foundation from the waste or a tableau.
+    pub to_foundation: u16,
+    pub card_flip: u16,
+    pub foundation_to_tableau_penalty: u16,
+    pub recycle_penalty: u16,
+    /// Win bonus numerator: `time_bonus_constant / elapsed_seconds`.
+    pub time_bonus_constant: u32,
+}
+
+impl ScoringRules {
+    pub const SIZE: usize = 2 * 5 + 4;
+
+    /// No bonus is paid for games finished in under 30 seconds.
+    pub fn time_bonus(&self, elapsed_seconds: i64) -> u64 {
+        if elapsed_seconds < 30 {
+            return 0;
+        }
+        self.time_bonus_constant as u64 / elapsed_seconds as u64
+    }
+}
+
+impl Default for ScoringRules {
+    /// Standard Windows Klondike scoring.
+    fn default() -> Self {
+        Self {
+            waste_to_tableau: 5,
+            to_foundation: 10,
+            card_flip: 5,
+            foundation_to_tableau_penalty: 15,
+            recycle_penalty: 100,
+            time_bonus_constant: 700_000,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct DisputeConfig {
+    /// Seconds after completion during which moves can be challenged.
+    pub window_seconds: i64,
+    /// Share of the stake held back from the payout until the window closes.
+    pub player_bond_bps: u16,
+    /// Share of the stake a challenger must post; forfeited if the game is honest.
+    pub challenge_bond_bps: u16,
+    /// Share of the stake paid from the treasury for an upheld challenge,
+    /// capped by what the treasury holds.
+    pub challenger_bounty_bps: u16,
+}
+
+impl DisputeConfig {
+    pub const SIZE: usize = 8 + 2 + 2 + 2;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct NftBonusConfig {
+    /// Verified Metaplex collection whose holders earn the bonus; default disables it.
+    pub collection: Pubkey,
+    /// Share of a winning payout added from the treasury for collection holders.
+    pub bonus_bps: u16,
+}
+
+impl NftBonusConfig {
+    pub const SIZE: usize = 32 + 2;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct MemecoinBoostConfig {
+    /// Ecosystem memecoin mint; games staked in it earn the boost.
+    pub mint: Pubkey,
+    /// Share of a winning payout added from the treasury; 0 disables the boost.
+    pub boost_bps: u16,
+}
+
+impl MemecoinBoostConfig {
+    pub const SIZE: usize = 32 + 2;
+
+    /// Boost to snapshot onto a game staked in `reward_mint`.
+    pub fn boost_for(&self, reward_mint: &Pubkey) -> u16 {
+        if self.boost_bps > 0 && *reward_mint == self.mint {
+            self.boost_bps
+        } else {
+            0
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct StakeDiscountTier {
+    /// Gaming tokens one position must hold to reach the tier.
+    pub min_staked: u64,
+    /// Share of the protocol fee waived; 0 leaves the tier unused.
+    pub discount_bps: u16,
+}
+
+impl StakeDiscountTier {
+    pub const SIZE: usize = 8 + 2;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct StakeDiscountConfig {
+    /// Gaming-token program whose stake accounts count; default disables the discount.
+    pub program: Pubkey,
+    /// Staked mint a position must be in.
+    pub mint: Pubkey,
+    pub tiers: [StakeDiscountTier; STAKE_DISCOUNT_TIERS],
+}
+
+impl StakeDiscountConfig {
+    pub const SIZE: usize = 32 + 32 + STAKE_DISCOUNT_TIERS * StakeDiscountTier::SIZE;
+
+    /// Best discount among the tiers `staked` reaches.
+    pub fn discount_for(&self, staked: u64) -> u16 {
+        self.tiers
+            .iter()
+            .filter(|tier| staked >= tier.min_staked)
+            .map(|tier| tier.discount_bps)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Leading fields of a gaming-token `StakeAccount`, which that program keeps
+/// first and in this order from layout `STAKE_VIEW_VERSION` on.
+#[derive(AnchorDeserialize)]
+pub struct StakeAccountView {
+    pub version: u8,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub position_index: u16,
+    pub amount: u64,
+    pub unbonding_since: Option<i64>,
+    pub is_active: bool,
+}
+
+/// First gaming-token `StakeAccount` layout that starts with `StakeAccountView`.
+pub const STAKE_VIEW_VERSION: u8 = 3;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct FeeConfig {
+    /// Share of each completed game's payout kept by the protocol.
+    pub protocol_fee_bps: u16,
+    /// Share of the protocol fee paid to the player's referrer.
+    pub referral_share_bps: u16,
+}
+
+impl FeeConfig {
+    pub const SIZE: usize = 2 + 2;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct TreasuryConfig {
+    /// Largest treasury withdrawal allowed without the timelock; 0 timelocks every one.
+    pub large_withdrawal_threshold: u64,
+    /// Delay between queueing and executing a large withdrawal.
+    pub withdrawal_timelock_seconds: i64,
+}
+
+impl TreasuryConfig {
+    pub const SIZE: usize = 8 + 8;
+}
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+pub const STAKE_DISCOUNT_TIERS: usize = 3;
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Inactivity after which the player may withdraw their own stake.
+pub const WITHDRAWAL_DELAY_SECONDS: i64 = SECONDS_PER_DAY;
+
+/// Treasury withdrawal timelock a new config starts with.
+pub const DEFAULT_TREASURY_TIMELOCK_SECONDS: i64 = SECONDS_PER_DAY;
+
+/// How long after creation an untouched game may still be cancelled.
+pub const CANCEL_GRACE_SECONDS: i64 = 5 * 60;
+
+/// Share of the stake a cancelled staked game forfeits, the same 10% that
+/// `withdraw_stake` charges.
+pub const CANCEL_PENALTY_BPS: u16 = 1_000;
+
+/// Inactivity after which anyone may sweep a game's escrow to the treasury.
+pub const STALE_ESCROW_SECONDS: i64 = 180 * SECONDS_PER_DAY;
+
+// The player's own refund path must always open before the sweep
+const _: () = assert!(STALE_ESCROW_SECONDS > WITHDRAWAL_DELAY_SECONDS);
+
+/// Only a strictly faster time takes a record; ties keep the holder.
+fn beats_record(seconds: u64, record: Option<u64>) -> bool {
+    match record {
+        Some(best) => seconds < best,
+        None => true,
+    }
+}
+
+fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    let value = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(SolitaireError::GameStateError)?
+        / BPS_DENOMINATOR as u128;
+    Ok(value as u64)
+}
+
+/// Pays `amount` from the insurance vault. Policies are reserved against the
+/// vault when bought, so a shortfall means it is insolvent and the payout fails.
+fn pay_insurance<'info>(
+    insurance_vault: &Option<Account<'info, TokenAccount>>,
+    insurance_authority: &Option<AccountInfo<'info>>,
+    insurance_authority_bump: u8,
+    destination: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let (Some(insurance_vault), Some(insurance_authority)) = (insurance_vault, insurance_authority)
+    else {
+        return err!(SolitaireError::InsuranceVaultRequired);
+    };
+    require!(
+        insurance_vault.amount >= amount,
+        SolitaireError::InsuranceVaultInsolvent
+    );
+
+    let insurance_seeds = &[b"insurance_authority".as_ref(), &[insurance_authority_bump]];
+    let signer = &[&insurance_seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: insurance_vault.to_account_info(),
+        to: destination.to_account_info(),
+        authority: insurance_authority.to_account_info(),
+    };
+    let cpi_program = token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+    token::transfer(cpi_ctx, amount)
+}
+
+/// True when `owner` holds exactly one token of a mint whose metadata lists
+/// `collection` as its verified collection. Anything malformed is just `false`.
+fn holds_collection_nft(
+    owner: &Pubkey,
+    collection: &Pubkey,
+    token_account: &TokenAccount,
+    metadata: &AccountInfo,
+) -> bool {
+    if *collection == Pubkey::default()
+        || token_account.owner != *owner
+        || token_account.amount != 1
+        || *metadata.owner != mpl_token_metadata::ID
+    {
+        return false;
+    }
+
+    let Ok(data) = metadata.try_borrow_data() else {
+        return false;
+    };
+    let Ok(metadata) = mpl_token_metadata::accounts::Metadata::safe_deserialize(&data) else {
+        return false;
+    };
+
+    metadata.mint == token_account.mint
+        && metadata
+            .collection
+            .is_some_and(|c| c.verified && c.key == *collection)
+}
+
+/// Fee discount earned by `player`'s gaming-token position. The account must
+/// belong to the configured program, carry the `StakeAccount` discriminator and
+/// a layout with the view, and be the player's open position in the configured
+/// mint; anything else, an unbonding position included, is just no discount.
+fn stake_discount_bps(
+    player: &Pubkey,
+    config: &StakeDiscountConfig,
+    stake_account: &AccountInfo,
+) -> u16 {
+    if config.program == Pubkey::default() || *stake_account.owner != config.program {
+        return 0;
+    }
+
+    let Ok(data) = stake_account.try_borrow_data() else {
+        return 0;
+    };
+    if data.len() < 8 || data[..8] != hash(b"account:StakeAccount").to_bytes()[..8] {
+        return 0;
+    }
+    let Ok(view) = StakeAccountView::deserialize(&mut &data[8..]) else {
+        return 0;
+    };
+
+    // Older layouts (version 0 has no version byte at all) must be migrated first
+    if view.version < STAKE_VIEW_VERSION || view.owner != *player || view.mint != config.mint {
+        return 0;
+    }
+    if !view.is_active || view.unbonding_since.is_some() {
+        return 0;
+    }
+    config.discount_for(view.amount)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameMode {
+    Standard,
+    Vegas,
+    Blitz,
+    Daily,
+    Race,
+}
+
+impl GameMode {
+    pub const COUNT: usize = 5;
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[account]
+pub struct GameAccount {
+    pub authority: Pubkey,
+    pub game_id: String,
+    pub stake_amount: u64,
+    pub reward_mint: Pubkey,
+    pub status: GameStatus,
+    pub moves: u32,
+    pub score: u64,
+    pub is_won: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub game_state: GameState,
+    pub bump: u8,
+    pub mode: GameMode,
+    pub last_move_slot: u64,
+    pub min_move_interval_slots: u64,
+    pub seed: u64,
+    pub move_log: Vec<MoveRecord>,
+    pub bond_amount: u64,
+    pub dispute_deadline: i64,
+    pub fraudulent: bool,
+    pub allow_foundation_to_tableau: bool,
+    pub scoring: ScoringRules,
+    pub partner: Option<Pubkey>,
+    pub next_to_move: Pubkey,
+    /// Stake was fronted by the promo vault; losses return it there.
+    pub free_game: bool,
+    /// Played for practice; never counts towards speed records.
+    pub practice: bool,
+    /// Hints taken through `use_hint`; any at all keeps the game out of the records.
+    pub hints_used: u16,
+    /// Shot clock snapshotted from the mode config; 0 disables it.
+    pub move_clock_seconds: i64,
+    /// Last second at which the next move is accepted; 0 when there is no clock.
+    pub move_deadline: i64,
+    /// Set by a failed `audit_deck`; freezes payouts pending admin review.
+    pub corrupted: bool,
+    /// Player's referrer at game creation, shared in the protocol fee.
+    pub referrer: Option<Pubkey>,
+    /// Counted in the player's `PlayerStats::active_games` until the game ends.
+    pub holds_active_slot: bool,
+    /// Loss insurance bought at creation; losses are refunded to the full stake.
+    pub insured: bool,
+    pub insurance_premium: u64,
+    /// Memecoin boost in bps, snapshotted from the config at creation.
+    pub memecoin_boost_bps: u16,
+}
+
+impl GameAccount {
+    /// Account size with an empty move log; each logged move adds
+    /// `MoveRecord::SIZE` bytes on top.
+    pub const BASE_SPACE: usize = 8 + 32 + (4 + 32) + 8 + 32 + 1 + 4 + 8 + 1 + 8 + 8
+        + GameState::SPACE
+        + 1 + 1 + 8 + 8 + 8
+        + 4
+        + 8 + 8 + 1
+        + 1
+        + ScoringRules::SIZE
+        + (1 + 32) + 32
+        + 1
+        + 1 + 2
+        + 8 + 8
+        + 1
+        + (1 + 32)
+        + 1
+        + 1 + 8
+        + 2;
+
+    pub const MAX_SPACE: usize = Self::space_for(MAX_MOVE_LOG);
+
+    pub const fn space_for(move_log_capacity: usize) -> usize {
+        Self::BASE_SPACE + MoveRecord::SIZE * move_log_capacity
+    }
+
+    /// Number of moves an account of `data_len` bytes can log.
+    pub fn move_log_capacity(data_len: usize) -> usize {
+        data_len.saturating_sub(Self::BASE_SPACE) / MoveRecord::SIZE
+    }
+
+    /// Size after one growth step, never past `MAX_SPACE`, so growing can't
+    /// be used to log more than `MAX_MOVE_LOG` moves.
+    pub fn grown_space(data_len: usize) -> usize {
+        (data_len + MoveRecord::SIZE * MOVE_LOG_GROWTH).min(Self::MAX_SPACE)
+    }
+
+    /// Resets every field for a fresh solo game under `mode`. Callers set the
+    /// stake and per-game options, then `deal`.
+    pub fn start(
+        &mut self,
+        authority: Pubkey,
+        game_id: String,
+        reward_mint: Pubkey,
+        mode: GameMode,
+        config: &GlobalConfig,
+        clock: &Clock,
+    ) {
+        self.authority = authority;
+        self.game_id = game_id;
+        self.stake_amount = 0;
+        self.reward_mint = reward_mint;
+        self.status = GameStatus::Active;
+        self.moves = 0;
+        self.score = 0;
+        self.is_won = false;
+        self.created_at = clock.unix_timestamp;
+        self.updated_at = clock.unix_timestamp;
+        self.mode = mode;
+        self.allow_foundation_to_tableau = true;
+        self.min_move_interval_slots = config.mode_config(mode).min_move_interval_slots;
+        self.scoring = config.mode_config(mode).scoring;
+        self.partner = None;
+        self.next_to_move = authority;
+        self.last_move_slot = clock.slot;
+        self.move_log = Vec::new();
+        self.bond_amount = 0;
+        self.dispute_deadline = 0;
+        self.fraudulent = false;
+        self.free_game = false;
+        self.practice = false;
+        self.hints_used = 0;
+        self.move_clock_seconds = config.mode_config(mode).move_clock_seconds;
+        self.reset_move_clock(clock.unix_timestamp);
+        self.corrupted = false;
+        self.referrer = None;
+        self.holds_active_slot = false;
+        self.insured = false;
+        self.insurance_premium = 0;
+        self.memecoin_boost_bps = 0;
+    }
+
+    /// Whether a win may set a personal or global speed record.
+    pub fn is_record_eligible(&self) -> bool {
+        !self.practice && self.hints_used == 0
+    }
+
+    /// Derives the deal seed from the game key and clock, then deals the board.
+    /// FreeCell seeds are Microsoft deal numbers, so a player may pick one.
+    pub fn deal(
+        &mut self,
+        game_key: &Pubkey,
+        clock: &Clock,
+        variant: GameVariant,
+        deal_number: Option<u32>,
+    ) {
+        // The board is fully determined by the seed
+        let seed_hash = keccak::hashv(&[
+            game_key.as_ref(),
+            &clock.slot.to_le_bytes(),
+            &clock.unix_timestamp.to_le_bytes(),
+        ]);
+        let derived = u64::from_le_bytes(seed_hash.0[..8].try_into().unwrap());
+        self.seed = match variant {
+            GameVariant::Klondike => derived,
+            GameVariant::FreeCell => match deal_number {
+                Some(deal_number) => deal_number as u64,
+                None => derived % FREECELL_DEAL_RANGE + 1,
+            },
+        };
+        self.game_state = GameState::deal(variant, self.authority, self.seed, self.created_at);
+    }
+
+    /// Returns the player's active-game slot once the game leaves Active.
+    /// Safe to call from every exit path; only the first call decrements.
+    pub fn release_active_slot(&mut self, player_stats: Option<&mut PlayerStats>) -> Result<()> {
+        if self.holds_active_slot {
+            let player_stats = player_stats.ok_or(SolitaireError::PlayerStatsRequired)?;
+            player_stats.active_games = player_stats.active_games.saturating_sub(1);
+            self.holds_active_slot = false;
+        }
+        Ok(())
+    }
+
+    /// Most an insured game can draw from the insurance vault: the half of
+    /// the stake a loss forfeits, which also covers a surrender penalty.
+    pub fn insured_liability(&self) -> u64 {
+        if self.insured {
+            self.stake_amount - self.stake_amount / 2
+        } else {
+            0
+        }
+    }
+
+    /// Hands the game's reserved liability back to the insurance ledger once
+    /// it leaves Active. Every exit path calls this exactly once.
+    pub fn release_insurance(&self, insurance_ledger: Option<&mut InsuranceLedger>) -> Result<()> {
+        let liability = self.insured_liability();
+        if liability > 0 {
+            let insurance_ledger =
+                insurance_ledger.ok_or(SolitaireError::InsuranceVaultRequired)?;
+            insurance_ledger.outstanding = insurance_ledger.outstanding.saturating_sub(liability);
+        }
+        Ok(())
+    }
+
+    /// Solo games only accept the authority; co-op games alternate strictly
+    /// between the authority and the partner.
+    pub fn check_turn(&self, signer: &Pubkey) -> Result<()> {
+        match self.partner {
+            None => require_keys_eq!(*signer, self.authority, SolitaireError::Unauthorized),
+            Some(partner) => {
+                require!(
+                    *signer == self.authority || *signer == partner,
+                    SolitaireError::Unauthorized
+                );
+                require_keys_eq!(*signer, self.next_to_move, SolitaireError::NotYourTurn);
+            }
+        }
+        Ok(())
+    }
+
+    /// A move landing exactly on the deadline second is still in time.
+    pub fn check_move_clock(&self, now: i64) -> Result<()> {
+        if self.move_deadline > 0 {
+            require!(now <= self.move_deadline, SolitaireError::MoveClockExpired);
+        }
+        Ok(())
+    }
+
+    pub fn reset_move_clock(&mut self, now: i64) {
+        self.move_deadline = if self.move_clock_seconds > 0 {
+            now.saturating_add(self.move_clock_seconds)
+        } else {
+            0
+        };
+    }
+
+    /// Rejects a move landing fewer than `min_move_interval_slots` slots
+    /// after the previous one. The interval is snapshotted from the mode
+    /// config at init so admin changes don't affect games in flight.
+    pub fn check_move_rate(&self, slot: u64) -> Result<()> {
+        if self.min_move_interval_slots > 0 {
+            let next_allowed = self
+                .last_move_slot
+                .checked_add(self.min_move_interval_slots)
+                .ok_or(SolitaireError::GameStateError)?;
+            require!(slot >= next_allowed, SolitaireError::MovesTooFast);
+        }
+        Ok(())
+    }
+
+    /// Moves the game to `next`, rejecting anything outside the legal
+    /// lifecycle defined by `GameStatus::can_transition_to`.
+    pub fn transition_to(&mut self, next: GameStatus) -> Result<()> {
+        require!(
+            self.status.can_transition_to(&next),
+            SolitaireError::InvalidStatusTransition
+        );
+        self.status = next;
+        Ok(())
+    }
+}
+
+/// A timelocked treasury withdrawal awaiting execution.
+#[account]
+pub struct PendingWithdrawal {
+    pub mint: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub executable_at: i64,
+    pub bump: u8,
+}
+
+impl PendingWithdrawal {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 1;
+}
+
+/// What one mint's insurance vault owes on insured games still Active.
+#[account]
+pub struct InsuranceLedger {
+    pub mint: Pubkey,
+    /// Sum of `insured_liability` over open policies.
+    pub outstanding: u64,
+    pub bump: u8,
+}
+
+impl InsuranceLedger {
+    pub const SIZE: usize = 32 + 8 + 1;
+
+    /// Reserves `liability` for a new policy, which `vault_balance` must
+    /// cover on top of every policy still open.
+    pub fn reserve(&mut self, liability: u64, vault_balance: u64) -> Result<()> {
+        let outstanding = self
+            .outstanding
+            .checked_add(liability)
+            .ok_or(SolitaireError::GameStateError)?;
+        require!(
+            vault_balance >= outstanding,
+            SolitaireError::InsuranceVaultInsolvent
+        );
+        self.outstanding = outstanding;
+        Ok(())
+    }
+}
+
+/// A player's referrer, set by the first `initialize_game` that names one.
+#[account]
+pub struct Referral {
+    pub player: Pubkey,
+    pub referrer: Pubkey,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl Referral {
+    pub const SIZE: usize = 32 + 32 + 8 + 1;
+}
+
+#[account]
+pub struct PlayerStats {
+    pub player: Pubkey,
+    pub games_won: u64,
+    /// Staked non-practice games currently Active, capped by
+    /// `GlobalConfig::max_active_games`.
+    pub active_games: u32,
+    /// Seconds from creation to the winning move of the player's fastest win.
+    pub fastest_win_seconds: Option<u64>,
+    pub bump: u8,
+}
+
+impl PlayerStats {
+    pub const SIZE: usize = 32 + 8 + 4 + (1 + 8) + 1;
+
+    /// Fills in a freshly created account; a no-op on existing ones.
+    pub fn init_if_new(&mut self, player: Pubkey, bump: u8) {
+        if self.player == Pubkey::default() {
+            self.player = player;
+            self.games_won = 0;
+            self.active_games = 0;
+            self.fastest_win_seconds = None;
+            self.bump = bump;
+        }
+    }
+
+    pub fn open_game(&mut self, max_active_games: u32) -> Result<()> {
+        require!(
+            max_active_games == 0 || self.active_games < max_active_games,
+            SolitaireError::TooManyActiveGames
+        );
+        self.active_games += 1;
+        Ok(())
+    }
+}
+
+#[account]
+pub struct GlobalStats {
+    pub fastest_win_seconds: Option<u64>,
+    pub record_holder: Pubkey,
+    pub record_set_at: i64,
+    pub bump: u8,
+}
+
+impl GlobalStats {
+    pub const SIZE: usize = (1 + 8) + 32 + 8 + 1;
+}
+
+pub const MAX_PAYOUT_PLACES: usize = 5;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct TournamentScheduleParams {
+    /// Seconds between consecutive tournament starts.
+    pub cadence_seconds: i64,
+    pub entry_fee: u64,
+    pub duration_seconds: i64,
+    /// Prize pool share per finishing place, in bps; must sum to at most 10000.
+    pub payout_curve: [u16; MAX_PAYOUT_PLACES],
+    /// Paid from the fee reserve to whoever cranks the next tournament.
+    pub crank_incentive: u64,
+}
+
+impl TournamentScheduleParams {
+    pub const SIZE: usize = 8 + 8 + 8 + 2 * MAX_PAYOUT_PLACES + 8;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.cadence_seconds > 0
+                && self.duration_seconds > 0
+                && self.duration_seconds <= self.cadence_seconds,
+            SolitaireError::InvalidConfig
+        );
+        let curve_total: u64 = self.payout_curve.iter().map(|bps| *bps as u64).sum();
+        require!(curve_total <= BPS_DENOMINATOR, SolitaireError::InvalidConfig);
+        Ok(())
+    }
+}
+
+#[account]
+pub struct TournamentSchedule {
+    pub schedule_id: u32,
+    pub mint: Pubkey,
+    pub params: TournamentScheduleParams,
+    pub next_start: i64,
+    /// Epoch the next crank creates.
+    pub epoch_index: u64,
+    pub paused: bool,
+    pub bump: u8,
+}
+
+impl TournamentSchedule {
+    pub const SIZE: usize = 4 + 32 + TournamentScheduleParams::SIZE + 8 + 8 + 1 + 1;
+}
+
+#[account]
+pub struct Tournament {
+    pub schedule: Pubkey,
+    pub epoch_index: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub entry_fee: u64,
+    pub payout_curve: [u16; MAX_PAYOUT_PLACES],
+    pub bump: u8,
+}
+
+impl Tournament {
+    pub const SIZE: usize = 32 + 8 + 8 + 8 + 8 + 2 * MAX_PAYOUT_PLACES + 1;
+}
+
+/// Marks a player's free game for one UTC day (`unix_timestamp / SECONDS_PER_DAY`).
+#[account]
+pub struct DailyFreeGame {
+    pub player: Pubkey,
+    pub day: u64,
+    pub game: Pubkey,
+    pub bump: u8,
+}
+
+impl DailyFreeGame {
+    pub const SIZE: usize = 32 + 8 + 32 + 1;
+}
+
+// New variants must be appended: the discriminant is the variant index, so
+// reordering would reinterpret existing game accounts.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub enum GameStatus {
     Active,
     Completed,
     Abandoned,
+    Expired,
+    Cancelled,
+    Swept,
+}
+
+impl GameStatus {
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, GameStatus::Active)
+    }
+
+    /// The single source of truth for the game lifecycle. Completed,
+    /// Abandoned, Expired, Cancelled and Swept are terminal; any of them but
+    /// Cancelled, and a stale Active game, can still be swept.
+    pub fn can_transition_to(&self, next: &GameStatus) -> bool {
+        matches!(
+            (self, next),
+            (GameStatus::Active, GameStatus::Completed)
+                | (GameStatus::Active, GameStatus::Abandoned)
+                | (GameStatus::Active, GameStatus::Expired)
+                | (GameStatus::Active, GameStatus::Cancelled)
+                | (GameStatus::Active, GameStatus::Swept)
+                | (GameStatus::Completed, GameStatus::Swept)
+                | (GameStatus::Abandoned, GameStatus::Swept)
+                | (GameStatus::Expired, GameStatus::Swept)
+        )
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
@@ -324,38 +3667,580 @@ pub struct GameState {
     pub is_complete: bool,
     pub start_time: i64,
     pub end_time: Option<i64>,
+    pub variant: GameVariant,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameVariant {
+    Klondike,
+    FreeCell,
 }
 
+/// Maximum number of moves recorded on a game; replaying the log from the
+/// seed must reproduce the board, so moves past this bound are rejected.
+pub const MAX_MOVE_LOG: usize = 512;
+
+/// Moves the game account has room for at init; `grow_game_account` adds
+/// `MOVE_LOG_GROWTH` more at a time, paid for by the player.
+pub const INITIAL_MOVE_LOG_CAPACITY: usize = 128;
+pub const MOVE_LOG_GROWTH: usize = 128;
+
+const TABLEAU_PILES: usize = 7;
+const FOUNDATION_PILES: usize = 4;
+const PILE_COUNT: usize = TABLEAU_PILES + FOUNDATION_PILES + 2;
+const MAX_PILE_ID_LEN: usize = 12;
+
+const FREECELL_CASCADES: usize = 8;
+const FREE_CELLS: usize = 4;
+const FREECELL_PILE_COUNT: usize = FREECELL_CASCADES + FREE_CELLS + FOUNDATION_PILES;
+
+/// Random FreeCell deals are drawn from Microsoft deal numbers 1..=1_000_000.
+pub const FREECELL_DEAL_RANGE: u64 = 1_000_000;
+
 impl GameState {
-    pub fn new(player: Pubkey) -> Self {
+    pub const SPACE: usize = 32
+        + 4 + FREECELL_PILE_COUNT * (4 + MAX_PILE_ID_LEN + 1 + 4) + 52 * 3
+        + 4 + 8 + 1 + 1 + 8 + 9
+        + 1;
+
+    pub fn deal(variant: GameVariant, player: Pubkey, seed: u64, start_time: i64) -> Self {
+        match variant {
+            GameVariant::Klondike => Self::new(player, seed, start_time),
+            GameVariant::FreeCell => Self::new_freecell(player, seed as u32, start_time),
+        }
+    }
+
+    /// Deals a Klondike layout from `seed`. The deal is a pure function of
+    /// the seed so any observer can rebuild the board from the move log.
+    pub fn new(player: Pubkey, seed: u64, start_time: i64) -> Self {
+        let mut deck: Vec<CardData> = (0..4u8)
+            .flat_map(|suit| {
+                (1..=13u8).map(move |rank| CardData {
+                    suit,
+                    rank,
+                    face_up: false,
+                })
+            })
+            .collect();
+
+        let mut rng = SplitMix64(seed);
+        for i in (1..deck.len()).rev() {
+            let j = (rng.next() % (i as u64 + 1)) as usize;
+            deck.swap(i, j);
+        }
+
+        let mut piles = Vec::with_capacity(PILE_COUNT);
+        for i in 0..TABLEAU_PILES {
+            let mut cards: Vec<CardData> = deck.drain(..=i).collect();
+            if let Some(top) = cards.last_mut() {
+                top.face_up = true;
+            }
+            piles.push(PileData {
+                id: format!("tableau-{}", i),
+                pile_type: PileType::Tableau,
+                cards,
+            });
+        }
+        for i in 0..FOUNDATION_PILES {
+            piles.push(PileData {
+                id: format!("foundation-{}", i),
+                pile_type: PileType::Foundation,
+                cards: Vec::new(),
+            });
+        }
+        piles.push(PileData {
+            id: "stock".to_string(),
+            pile_type: PileType::Stock,
+            cards: deck,
+        });
+        piles.push(PileData {
+            id: "waste".to_string(),
+            pile_type: PileType::Waste,
+            cards: Vec::new(),
+        });
+
+        Self {
+            player,
+            piles,
+            moves: 0,
+            score: 0,
+            is_won: false,
+            is_complete: false,
+            start_time,
+            end_time: None,
+            variant: GameVariant::Klondike,
+        }
+    }
+
+    /// Deals Microsoft FreeCell game `deal_number`: cards numbered
+    /// `rank * 4 + suit` (clubs, diamonds, hearts, spades) are shuffled with
+    /// the MSVC `rand()` LCG seeded by the deal number, then dealt face up
+    /// row by row across eight cascades. Deal 1 opens `JD 2D 9H JC 5D 7H 7C 5H`.
+    pub fn new_freecell(player: Pubkey, deal_number: u32, start_time: i64) -> Self {
+        // Our suits are hearts, diamonds, clubs, spades
+        const MS_SUIT: [u8; 4] = [2, 1, 0, 3];
+
+        let mut deck: Vec<u8> = (0..52u8).rev().collect();
+        let mut state = deal_number & 0x7fff_ffff;
+        for i in 0..deck.len() {
+            state = state.wrapping_mul(214_013).wrapping_add(2_531_011) & 0x7fff_ffff;
+            let j = 51 - (state >> 16) as usize % (52 - i);
+            deck.swap(i, j);
+        }
+
+        let mut piles = Vec::with_capacity(FREECELL_PILE_COUNT);
+        for i in 0..FREECELL_CASCADES {
+            let cards = deck
+                .iter()
+                .skip(i)
+                .step_by(FREECELL_CASCADES)
+                .map(|card| CardData {
+                    suit: MS_SUIT[(card % 4) as usize],
+                    rank: card / 4 + 1,
+                    face_up: true,
+                })
+                .collect();
+            piles.push(PileData {
+                id: format!("tableau-{}", i),
+                pile_type: PileType::Tableau,
+                cards,
+            });
+        }
+        for i in 0..FREE_CELLS {
+            piles.push(PileData {
+                id: format!("freecell-{}", i),
+                pile_type: PileType::FreeCell,
+                cards: Vec::new(),
+            });
+        }
+        for i in 0..FOUNDATION_PILES {
+            piles.push(PileData {
+                id: format!("foundation-{}", i),
+                pile_type: PileType::Foundation,
+                cards: Vec::new(),
+            });
+        }
+
         Self {
             player,
-            piles: Vec::new(),
+            piles,
             moves: 0,
             score: 0,
             is_won: false,
             is_complete: false,
-            start_time: Clock::get().unwrap().unix_timestamp,
+            start_time,
             end_time: None,
+            variant: GameVariant::FreeCell,
+        }
+    }
+
+    /// Rebuilds the board from `seed` and applies `moves` in order, failing
+    /// on the first move that is not legal.
+    pub fn replay(
+        player: Pubkey,
+        seed: u64,
+        start_time: i64,
+        moves: &[MoveRecord],
+        scoring: &ScoringRules,
+        allow_foundation_to_tableau: bool,
+        variant: GameVariant,
+    ) -> Result<Self> {
+        let mut state = Self::deal(variant, player, seed, start_time);
+        for record in moves {
+            state.apply_move(record, scoring, allow_foundation_to_tableau)?;
+        }
+        Ok(state)
+    }
+
+    pub fn make_move(
+        &mut self,
+        from_pile: &str,
+        to_pile: &str,
+        card_index: u8,
+        scoring: &ScoringRules,
+        allow_foundation_to_tableau: bool,
+    ) -> Result<(MoveRecord, MoveOutcome)> {
+        let record = MoveRecord {
+            from: self.pile_index(from_pile)?,
+            to: self.pile_index(to_pile)?,
+            card_index,
+        };
+        let outcome = self.apply_move(&record, scoring, allow_foundation_to_tableau)?;
+        Ok((record, outcome))
+    }
+
+    /// Validates and executes a single move under the board's variant rules.
+    pub fn apply_move(
+        &mut self,
+        record: &MoveRecord,
+        scoring: &ScoringRules,
+        allow_foundation_to_tableau: bool,
+    ) -> Result<MoveOutcome> {
+        let from = record.from as usize;
+        let to = record.to as usize;
+        let card_index = record.card_index as usize;
+
+        require!(from < self.piles.len() && to < self.piles.len(), SolitaireError::InvalidMove);
+        require!(from != to, SolitaireError::InvalidMove);
+
+        let score_before = self.score;
+        let to_len_before = self.piles[to].cards.len();
+
+        let flipped = match self.variant {
+            GameVariant::Klondike => {
+                self.apply_klondike_move(from, to, card_index, scoring, allow_foundation_to_tableau)?
+            }
+            GameVariant::FreeCell => {
+                self.apply_freecell_move(from, to, card_index, scoring)?;
+                false
+            }
+        };
+
+        self.moves += 1;
+        self.is_won = self.foundation_card_count() == 52;
+        self.is_complete = self.is_won;
+
+        Ok(MoveOutcome {
+            cards_moved: (self.piles[to].cards.len() - to_len_before) as u8,
+            flipped,
+            score_delta: (self.score as i64 - score_before as i64) as i32,
+        })
+    }
+
+    /// Drawing is a stock -> waste move of the top card; recycling is a
+    /// waste -> stock move once the stock is empty. Pulling a card back off a
+    /// foundation is a house rule gated by `allow_foundation_to_tableau`.
+    /// Returns whether a tableau card was turned face up.
+    fn apply_klondike_move(
+        &mut self,
+        from: usize,
+        to: usize,
+        card_index: usize,
+        scoring: &ScoringRules,
+        allow_foundation_to_tableau: bool,
+    ) -> Result<bool> {
+        let mut flipped = false;
+        let from_type = self.piles[from].pile_type;
+        let to_type = self.piles[to].pile_type;
+        let from_len = self.piles[from].cards.len();
+
+        match (from_type, to_type) {
+            (PileType::Stock, PileType::Waste) => {
+                require!(from_len > 0 && card_index == from_len - 1, SolitaireError::InvalidMove);
+                let mut card = self.piles[from].cards.pop().ok_or(SolitaireError::InvalidMove)?;
+                card.face_up = true;
+                self.piles[to].cards.push(card);
+            }
+            (PileType::Waste, PileType::Stock) => {
+                require!(from_len > 0 && card_index == 0, SolitaireError::InvalidMove);
+                require!(self.piles[to].cards.is_empty(), SolitaireError::InvalidMove);
+                let mut recycled: Vec<CardData> = self.piles[from].cards.drain(..).rev().collect();
+                recycled.iter_mut().for_each(|card| card.face_up = false);
+                self.piles[to].cards = recycled;
+                self.score = self.score.saturating_sub(scoring.recycle_penalty as u64);
+            }
+            (PileType::Waste | PileType::Tableau, PileType::Tableau | PileType::Foundation) => {
+                require!(card_index < from_len, SolitaireError::InvalidMove);
+                let card = self.piles[from].cards[card_index];
+                require!(card.face_up, SolitaireError::InvalidMove);
+
+                let moving = from_len - card_index;
+                if from_type == PileType::Waste || to_type == PileType::Foundation {
+                    require!(moving == 1, SolitaireError::InvalidMove);
+                }
+
+                let target = self.piles[to].cards.last();
+                let fits = match to_type {
+                    PileType::Foundation => fits_on_foundation(&card, target),
+                    _ => fits_on_tableau(&card, target),
+                };
+                require!(fits, SolitaireError::InvalidMove);
+
+                let cards: Vec<CardData> = self.piles[from].cards.drain(card_index..).collect();
+                self.piles[to].cards.extend(cards);
+
+                if to_type == PileType::Foundation {
+                    self.score += scoring.to_foundation as u64;
+                } else if from_type == PileType::Waste {
+                    self.score += scoring.waste_to_tableau as u64;
+                }
+
+                if from_type == PileType::Tableau {
+                    if let Some(top) = self.piles[from].cards.last_mut() {
+                        if !top.face_up {
+                            top.face_up = true;
+                            self.score += scoring.card_flip as u64;
+                            flipped = true;
+                        }
+                    }
+                }
+            }
+            (PileType::Foundation, PileType::Tableau) => {
+                require!(
+                    allow_foundation_to_tableau,
+                    SolitaireError::FoundationMovesDisabled
+                );
+                require!(
+                    from_len > 0 && card_index == from_len - 1,
+                    SolitaireError::InvalidMove
+                );
+                let card = self.piles[from].cards[card_index];
+                require!(
+                    fits_on_tableau(&card, self.piles[to].cards.last()),
+                    SolitaireError::InvalidMove
+                );
+
+                self.piles[from].cards.pop();
+                self.piles[to].cards.push(card);
+                self.score = self
+                    .score
+                    .saturating_sub(scoring.foundation_to_tableau_penalty as u64);
+            }
+            _ => return err!(SolitaireError::InvalidMove),
         }
+
+        Ok(flipped)
     }
 
-    pub fn make_move(&mut self, from_pile: &str, to_pile: &str, card_index: u8) -> Result<()> {
-        // Simplified game logic - in a real implementation, this would
-        // contain the full solitaire game state management
-        self.moves += 1;
-        self.score += 10;
+    /// Single cards move from a cascade or free cell to an empty free cell or
+    /// onto their foundation. Runs move onto cascades, capped by
+    /// `max_supermove` so a client can't claim a move the free space
+    /// couldn't make one card at a time. Foundations are never played from.
+    fn apply_freecell_move(
+        &mut self,
+        from: usize,
+        to: usize,
+        card_index: usize,
+        scoring: &ScoringRules,
+    ) -> Result<()> {
+        let from_type = self.piles[from].pile_type;
+        let to_type = self.piles[to].pile_type;
+        let from_len = self.piles[from].cards.len();
+
+        require!(
+            matches!(from_type, PileType::Tableau | PileType::FreeCell),
+            SolitaireError::InvalidMove
+        );
+        require!(card_index < from_len, SolitaireError::InvalidMove);
+
+        let card = self.piles[from].cards[card_index];
+        let moving = from_len - card_index;
+        let target = self.piles[to].cards.last();
+
+        match to_type {
+            PileType::FreeCell => {
+                require!(moving == 1 && target.is_none(), SolitaireError::InvalidMove);
+            }
+            PileType::Foundation => {
+                require!(
+                    moving == 1 && fits_on_foundation(&card, target),
+                    SolitaireError::InvalidMove
+                );
+            }
+            PileType::Tableau => {
+                let run = &self.piles[from].cards[card_index..];
+                require!(
+                    run.windows(2).all(|pair| fits_on_tableau(&pair[1], Some(&pair[0]))),
+                    SolitaireError::InvalidMove
+                );
+                // Any card may start an empty cascade
+                require!(
+                    target.is_none() || fits_on_tableau(&card, target),
+                    SolitaireError::InvalidMove
+                );
+                require!(moving <= self.max_supermove(to), SolitaireError::InvalidMove);
+            }
+            _ => return err!(SolitaireError::InvalidMove),
+        }
+
+        let cards: Vec<CardData> = self.piles[from].cards.drain(card_index..).collect();
+        self.piles[to].cards.extend(cards);
 
-        // Here you would implement the actual solitaire move validation
-        // and state updates
+        if to_type == PileType::Foundation {
+            self.score += scoring.to_foundation as u64;
+        }
 
         Ok(())
     }
 
+    /// Longest run that can move onto pile `to` in FreeCell:
+    /// `(empty free cells + 1) * 2^(empty cascades)`, where an empty target
+    /// cascade doesn't count towards the empty cascades.
+    pub fn max_supermove(&self, to: usize) -> usize {
+        let empty = |pile_type: PileType| {
+            self.piles
+                .iter()
+                .enumerate()
+                .filter(|(i, pile)| {
+                    *i != to && pile.pile_type == pile_type && pile.cards.is_empty()
+                })
+                .count()
+        };
+        (empty(PileType::FreeCell) + 1) << empty(PileType::Tableau)
+    }
+
     pub fn is_won(&self) -> bool {
         // Check win condition - all cards in foundation piles
         self.is_won
     }
+
+    /// Checks the board is one complete deck in the dealt pile layout: 52
+    /// distinct cards, foundation and waste cards face up, stock face down,
+    /// tableau piles with face-down cards only under face-up runs that build
+    /// down in alternating colours, and foundations built up by suit.
+    /// Returns the first violation found.
+    pub fn audit(&self) -> std::result::Result<(), DeckViolation> {
+        let pile_count = match self.variant {
+            GameVariant::Klondike => PILE_COUNT,
+            GameVariant::FreeCell => FREECELL_PILE_COUNT,
+        };
+        if self.piles.len() != pile_count {
+            return Err(DeckViolation::PileLayout { pile: self.piles.len() as u8 });
+        }
+
+        let mut seen = [false; 52];
+        let mut count: u16 = 0;
+        for (p, pile) in self.piles.iter().enumerate() {
+            let pile_index = p as u8;
+            let expected_type = match (self.variant, p) {
+                (GameVariant::Klondike, p) if p < TABLEAU_PILES => PileType::Tableau,
+                (GameVariant::Klondike, p) if p < TABLEAU_PILES + FOUNDATION_PILES => {
+                    PileType::Foundation
+                }
+                (GameVariant::Klondike, p) if p == PILE_COUNT - 2 => PileType::Stock,
+                (GameVariant::Klondike, _) => PileType::Waste,
+                (GameVariant::FreeCell, p) if p < FREECELL_CASCADES => PileType::Tableau,
+                (GameVariant::FreeCell, p) if p < FREECELL_CASCADES + FREE_CELLS => {
+                    PileType::FreeCell
+                }
+                (GameVariant::FreeCell, _) => PileType::Foundation,
+            };
+            if pile.pile_type == PileType::FreeCell && pile.cards.len() > 1 {
+                return Err(DeckViolation::PileLayout { pile: pile_index });
+            }
+            if pile.pile_type != expected_type {
+                return Err(DeckViolation::PileLayout { pile: pile_index });
+            }
+
+            let mut below: Option<&CardData> = None;
+            for (c, card) in pile.cards.iter().enumerate() {
+                let position = c as u8;
+                if card.suit > 3 || !(1..=13).contains(&card.rank) {
+                    return Err(DeckViolation::InvalidCard { pile: pile_index, position });
+                }
+                let slot = card.suit as usize * 13 + card.rank as usize - 1;
+                if seen[slot] {
+                    return Err(DeckViolation::DuplicateCard { pile: pile_index, position });
+                }
+                seen[slot] = true;
+                count += 1;
+
+                let face_ok = match pile.pile_type {
+                    PileType::Foundation | PileType::Waste | PileType::FreeCell => card.face_up,
+                    PileType::Stock => !card.face_up,
+                    PileType::Tableau => match below {
+                        Some(below) => card.face_up || !below.face_up,
+                        None => true,
+                    },
+                };
+                if !face_ok {
+                    return Err(DeckViolation::FaceOrientation { pile: pile_index, position });
+                }
+
+                let sequence_ok = match pile.pile_type {
+                    PileType::Foundation => fits_on_foundation(card, below),
+                    // FreeCell deals every cascade face up in shuffled order
+                    PileType::Tableau if self.variant == GameVariant::FreeCell => true,
+                    PileType::Tableau => match below {
+                        Some(b) if b.face_up => fits_on_tableau(card, below),
+                        _ => true,
+                    },
+                    PileType::Stock | PileType::Waste | PileType::FreeCell => true,
+                };
+                if !sequence_ok {
+                    return Err(DeckViolation::InvalidSequence { pile: pile_index, position });
+                }
+
+                below = Some(card);
+            }
+
+            if pile.pile_type == PileType::Tableau && below.is_some_and(|top| !top.face_up) {
+                let position = (pile.cards.len() - 1) as u8;
+                return Err(DeckViolation::FaceOrientation { pile: pile_index, position });
+            }
+        }
+
+        if count != 52 {
+            return Err(DeckViolation::CardCount { count });
+        }
+        Ok(())
+    }
+
+    fn foundation_card_count(&self) -> usize {
+        self.piles
+            .iter()
+            .filter(|pile| pile.pile_type == PileType::Foundation)
+            .map(|pile| pile.cards.len())
+            .sum()
+    }
+
+    fn pile_index(&self, id: &str) -> Result<u8> {
+        self.piles
+            .iter()
+            .position(|pile| pile.id == id)
+            .map(|index| index as u8)
+            .ok_or_else(|| error!(SolitaireError::InvalidMove))
+    }
+
+    /// 64-bit FNV-1a over the packed board, mirrored by the web client to
+    /// detect desyncs without refetching the account. Input layout, in pile
+    /// order: `pile_type as u8`, `cards.len() as u8`, then `suit, rank,
+    /// face_up as u8` per card; followed by `moves` as little-endian u32.
+    pub fn state_hash(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let mut hash = FNV_OFFSET_BASIS;
+        let mut write = |byte: u8| {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        };
+
+        for pile in &self.piles {
+            write(pile.pile_type as u8);
+            write(pile.cards.len() as u8);
+            for card in &pile.cards {
+                write(card.suit);
+                write(card.rank);
+                write(card.face_up as u8);
+            }
+        }
+        for byte in self.moves.to_le_bytes() {
+            write(byte);
+        }
+
+        hash
+    }
+}
+
+/// What a move did, beyond the record needed to replay it.
+#[derive(Clone, Copy, Debug)]
+pub struct MoveOutcome {
+    pub cards_moved: u8,
+    pub flipped: bool,
+    pub score_delta: i32,
+}
+
+/// A move as recorded in the log, addressed by pile index rather than id.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct MoveRecord {
+    pub from: u8,
+    pub to: u8,
+    pub card_index: u8,
+}
+
+impl MoveRecord {
+    pub const SIZE: usize = 3;
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
@@ -365,15 +4250,60 @@ pub struct PileData {
     pub cards: Vec<CardData>,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+fn is_red(card: &CardData) -> bool {
+    card.suit < 2
+}
+
+fn fits_on_foundation(card: &CardData, target: Option<&CardData>) -> bool {
+    match target {
+        None => card.rank == 1,
+        Some(top) => top.suit == card.suit && top.rank + 1 == card.rank,
+    }
+}
+
+fn fits_on_tableau(card: &CardData, target: Option<&CardData>) -> bool {
+    match target {
+        None => card.rank == 13,
+        Some(top) => top.face_up && is_red(top) != is_red(card) && top.rank == card.rank + 1,
+    }
+}
+
+/// splitmix64; small, fast and fully specified so clients can reproduce
+/// deals off-chain.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// First problem found by `GameState::audit`; `pile` and `position` index
+/// into `GameState::piles` and that pile's cards.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum DeckViolation {
+    PileLayout { pile: u8 },
+    InvalidCard { pile: u8, position: u8 },
+    DuplicateCard { pile: u8, position: u8 },
+    FaceOrientation { pile: u8, position: u8 },
+    InvalidSequence { pile: u8, position: u8 },
+    CardCount { count: u16 },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
 pub enum PileType {
     Tableau,
     Foundation,
     Stock,
     Waste,
+    FreeCell,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
 pub struct CardData {
     pub suit: u8, // 0=hearts, 1=diamonds, 2=clubs, 3=spades
     pub rank: u8, // 1-13 (A-K)
@@ -385,6 +4315,24 @@ pub struct GameStarted {
     pub game_id: String,
     pub player: Pubkey,
     pub stake_amount: u64,
+    pub allow_foundation_to_tableau: bool,
+    pub partner: Option<Pubkey>,
+    pub insured: bool,
+    pub insurance_premium: u64,
+    pub memecoin_boost_bps: u16,
+    pub free_game: bool,
+    pub practice: bool,
+    pub variant: GameVariant,
+    /// Deal seed; the Microsoft deal number for FreeCell games.
+    pub seed: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HintUsed {
+    pub game_id: String,
+    pub player: Pubkey,
+    pub hints_used: u16,
     pub timestamp: i64,
 }
 
@@ -395,7 +4343,16 @@ pub struct MoveMade {
     pub from_pile: String,
     pub to_pile: String,
     pub card_index: u8,
+    pub cards_moved: u8,
+    /// A face-down tableau card was turned up by this move.
+    pub flipped: bool,
+    pub score_delta: i32,
+    /// Source and destination pile sizes after the move.
+    pub from_pile_size: u8,
+    pub to_pile_size: u8,
     pub moves: u32,
+    /// Board hash after the move; see `GameState::state_hash`.
+    pub state_hash: u64,
     pub timestamp: i64,
 }
 
@@ -406,6 +4363,335 @@ pub struct GameCompleted {
     pub won: bool,
     pub score: u64,
     pub moves: u32,
+    pub final_state_hash: u64,
+    pub nft_bonus: u64,
+    pub memecoin_boost: u64,
+    /// Fee taken from the payout, including any referrer share.
+    pub protocol_fee: u64,
+    /// Share of the protocol fee waived for the player's gaming-token stake.
+    pub fee_discount_bps: u16,
+    pub insured: bool,
+    pub insurance_payout: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameCancelled {
+    pub game_id: String,
+    pub player: Pubkey,
+    pub refunded: u64,
+    /// Left in escrow; zero for free games.
+    pub penalty: u64,
+    pub free_game: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ModeConfigUpdated {
+    pub mode: GameMode,
+    pub min_move_interval_slots: u64,
+    pub move_clock_seconds: i64,
+    pub scoring: ScoringRules,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NftBonusConfigUpdated {
+    pub collection: Pubkey,
+    pub bonus_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeDiscountConfigUpdated {
+    pub program: Pubkey,
+    pub mint: Pubkey,
+    pub tiers: [StakeDiscountTier; STAKE_DISCOUNT_TIERS],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MemecoinBoostConfigUpdated {
+    pub mint: Pubkey,
+    pub boost_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FreeGameStakeUpdated {
+    pub free_game_stake: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PromoVaultInitialized {
+    pub mint: Pubkey,
+    pub promo_vault: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PromoVaultFunded {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PromoVaultWithdrawn {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MaxActiveGamesUpdated {
+    pub max_active_games: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InsurancePremiumUpdated {
+    pub insurance_premium_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InsuranceVaultInitialized {
+    pub mint: Pubkey,
+    pub insurance_vault: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InsuranceVaultFunded {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InsuranceVaultWithdrawn {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecordBountyUpdated {
+    pub record_bounty: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NewPersonalBest {
+    pub player: Pubkey,
+    pub game_id: String,
+    pub win_seconds: u64,
+    pub previous_seconds: Option<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NewGlobalRecord {
+    pub player: Pubkey,
+    pub game_id: String,
+    pub win_seconds: u64,
+    pub previous_seconds: Option<u64>,
+    pub bounty: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TournamentScheduleUpdated {
+    pub schedule: Pubkey,
+    pub params: TournamentScheduleParams,
+    pub next_start: i64,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TournamentCreated {
+    pub schedule: Pubkey,
+    pub tournament: Pubkey,
+    pub epoch_index: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub entry_fee: u64,
+    pub cranked_by: Pubkey,
+    pub crank_incentive: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeConfigUpdated {
+    pub protocol_fee_bps: u16,
+    pub referral_share_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralRegistered {
+    pub player: Pubkey,
+    pub referrer: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralPaid {
+    pub game_id: String,
+    pub player: Pubkey,
+    pub referrer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeConfigUpdated {
+    pub window_seconds: i64,
+    pub player_bond_bps: u16,
+    pub challenge_bond_bps: u16,
+    pub challenger_bounty_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryConfigUpdated {
+    pub large_withdrawal_threshold: u64,
+    pub withdrawal_timelock_seconds: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub mint: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawalQueued {
+    pub mint: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub executable_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawalCancelled {
+    pub mint: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TreasuryBalance {
+    pub account: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct TreasuryBalanceSnapshotTaken {
+    pub balances: Vec<TreasuryBalance>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryInitialized {
+    pub mint: Pubkey,
+    pub treasury: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameChallenged {
+    pub game_id: String,
+    pub challenger: Pubkey,
+    pub move_index: u32,
+    pub upheld: bool,
+    pub challenge_bond: u64,
+    pub clawback: u64,
+    pub bounty: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MoveClockExpired {
+    pub game_id: String,
+    pub player: Pubkey,
+    pub move_deadline: i64,
+    pub forfeited: u64,
+    pub insurance_payout: u64,
+    pub claimed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StaleEscrowSwept {
+    pub game_id: String,
+    pub player: Pubkey,
+    pub amount: u64,
+    pub swept_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DeckAuditPassed {
+    pub game_id: String,
+    pub state_hash: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DeckIntegrityFailed {
+    pub game_id: String,
+    pub violation: DeckViolation,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameCorruptionCleared {
+    pub game_id: String,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BondReleased {
+    pub game_id: String,
+    pub player: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PartnerSet {
+    pub game_id: String,
+    pub player: Pubkey,
+    pub partner: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameAccountGrown {
+    pub game_id: String,
+    pub size: u32,
+    pub move_log_capacity: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameClosed {
+    pub game_id: String,
+    pub player: Pubkey,
+    pub refunded_lamports: u64,
+    /// Tokens left in the escrow, moved to the treasury or promo vault.
+    pub escrow_swept: u64,
     pub timestamp: i64,
 }
 
@@ -436,4 +4722,90 @@ pub enum SolitaireError {
     InsufficientFunds,
     #[msg("Game state error")]
     GameStateError,
+    #[msg("Invalid game status transition")]
+    InvalidStatusTransition,
+    #[msg("Game can no longer be cancelled")]
+    CancelWindowClosed,
+    #[msg("Moves submitted too fast")]
+    MovesTooFast,
+    #[msg("Move log is full")]
+    MoveLogFull,
+    #[msg("Invalid configuration")]
+    InvalidConfig,
+    #[msg("Game is not completed")]
+    GameNotCompleted,
+    #[msg("Game already flagged as fraudulent")]
+    GameAlreadyFlagged,
+    #[msg("Dispute window closed")]
+    DisputeWindowClosed,
+    #[msg("Dispute window still open")]
+    DisputeWindowOpen,
+    #[msg("Invalid challenge")]
+    InvalidChallenge,
+    #[msg("No bond held")]
+    NoBondHeld,
+    #[msg("Foundation to tableau moves are disabled for this game")]
+    FoundationMovesDisabled,
+    #[msg("Game account is full; grow it before moving")]
+    GameAccountFull,
+    #[msg("Game cannot be closed yet")]
+    GameNotClosable,
+    #[msg("Invalid co-op partner")]
+    InvalidPartner,
+    #[msg("Partner cannot change after the first move")]
+    PartnerLocked,
+    #[msg("Not your turn")]
+    NotYourTurn,
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Free games are disabled")]
+    FreeGamesDisabled,
+    #[msg("Free game day does not match the current day")]
+    InvalidFreeGameDay,
+    #[msg("Promo vault account required")]
+    PromoVaultRequired,
+    #[msg("Game already won; complete it to settle")]
+    GameAlreadyWon,
+    #[msg("Move clock expired")]
+    MoveClockExpired,
+    #[msg("Move clock has not expired")]
+    MoveClockRunning,
+    #[msg("Tournament schedule is paused")]
+    SchedulePaused,
+    #[msg("Next tournament is not due yet")]
+    ScheduleNotDue,
+    #[msg("Epoch does not match the schedule")]
+    InvalidEpoch,
+    #[msg("Escrow is not stale yet")]
+    EscrowNotStale,
+    #[msg("Game is flagged corrupted pending admin review")]
+    GameCorrupted,
+    #[msg("Game is not flagged corrupted")]
+    GameNotCorrupted,
+    #[msg("Players cannot refer themselves")]
+    SelfReferral,
+    #[msg("Invalid referrer")]
+    InvalidReferrer,
+    #[msg("Treasury account required")]
+    TreasuryRequired,
+    #[msg("Insufficient treasury balance")]
+    InsufficientTreasuryBalance,
+    #[msg("Withdrawal exceeds the threshold; queue it behind the timelock")]
+    WithdrawalRequiresTimelock,
+    #[msg("Withdrawal is still timelocked")]
+    WithdrawalTimelocked,
+    #[msg("Not a treasury token account")]
+    InvalidTreasuryAccount,
+    #[msg("Too many active games")]
+    TooManyActiveGames,
+    #[msg("Player stats account required")]
+    PlayerStatsRequired,
+    #[msg("Loss insurance is not available")]
+    InsuranceUnavailable,
+    #[msg("Insurance vault cannot cover this stake")]
+    InsuranceVaultInsolvent,
+    #[msg("Insurance vault account required")]
+    InsuranceVaultRequired,
+    #[msg("Player token account required")]
+    PlayerTokenAccountRequired,
 }
\ No newline at end of file
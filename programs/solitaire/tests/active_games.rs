@@ -0,0 +1,147 @@
+mod common;
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solitaire::{
+    accounts, instruction, GameMode, ModeConfig, PlayerStats, SolitaireError, STALE_ESCROW_SECONDS,
+};
+
+/// A config capping players at `cap` active games, with a treasury for the
+/// exits that forfeit to it.
+fn capped(cap: u32) -> Solitaire {
+    let mut solitaire = Solitaire::new();
+    solitaire
+        .update_config(instruction::UpdateMaxActiveGames { max_active_games: cap })
+        .unwrap();
+    let mint = solitaire.mint;
+    solitaire.treasury(&mint, 0);
+    solitaire
+}
+
+fn active_games(solitaire: &Solitaire, player: &Player) -> u32 {
+    solitaire
+        .harness
+        .get::<PlayerStats>(&player_stats(&player.wallet))
+        .active_games
+}
+
+fn try_start(solitaire: &mut Solitaire, player: &Player, params: GameParams) -> TxResult {
+    let game = solitaire.game(player);
+    let ix = solitaire.start_ix(&game, params);
+    solitaire.harness.process(&[ix], &[player.wallet])
+}
+
+fn practice() -> GameParams {
+    GameParams {
+        practice: true,
+        ..GameParams::default()
+    }
+}
+
+/// Fills a one-game cap, leaves the game through `exit`, and checks the slot
+/// came back.
+fn assert_exit_frees_the_slot(mode: GameMode, exit: impl FnOnce(&mut Solitaire, &Game)) {
+    let mut solitaire = capped(1);
+    solitaire
+        .update_config(instruction::UpdateModeConfig {
+            mode: GameMode::Blitz,
+            mode_config: ModeConfig {
+                move_clock_seconds: 30,
+                ..ModeConfig::default()
+            },
+        })
+        .unwrap();
+    let player = solitaire.player();
+    let params = GameParams {
+        mode,
+        ..GameParams::default()
+    };
+    let game = solitaire.start(&player, params);
+    assert_error(
+        try_start(&mut solitaire, &player, params),
+        SolitaireError::TooManyActiveGames,
+    );
+
+    exit(&mut solitaire, &game);
+    assert_eq!(active_games(&solitaire, &player), 0);
+    try_start(&mut solitaire, &player, params).unwrap();
+}
+
+#[test]
+fn the_cap_counts_staked_games_but_not_practice() {
+    let mut solitaire = capped(2);
+    let player = solitaire.player();
+    solitaire.start(&player, GameParams::default());
+    solitaire.start(&player, GameParams::default());
+    assert_error(
+        try_start(&mut solitaire, &player, GameParams::default()),
+        SolitaireError::TooManyActiveGames,
+    );
+
+    let practice_game = solitaire.start(&player, practice());
+    assert_eq!(active_games(&solitaire, &player), 2);
+    assert!(!solitaire.game_state(&practice_game).holds_active_slot);
+
+    // Finishing a practice game hands back nothing it didn't take
+    solitaire.complete(&practice_game).unwrap();
+    assert_eq!(active_games(&solitaire, &player), 2);
+}
+
+#[test]
+fn completing_frees_the_slot() {
+    assert_exit_frees_the_slot(GameMode::Standard, |solitaire, game| {
+        solitaire.complete(game).unwrap();
+    });
+}
+
+#[test]
+fn withdrawing_frees_the_slot() {
+    assert_exit_frees_the_slot(GameMode::Standard, |solitaire, game| {
+        solitaire.harness.warp(DAY);
+        solitaire.leave(game, instruction::WithdrawStake {}).unwrap();
+    });
+}
+
+#[test]
+fn cancelling_frees_the_slot() {
+    assert_exit_frees_the_slot(GameMode::Standard, |solitaire, game| {
+        solitaire.leave(game, instruction::CancelGame {}).unwrap();
+    });
+}
+
+#[test]
+fn a_claimed_timeout_frees_the_slot() {
+    assert_exit_frees_the_slot(GameMode::Blitz, |solitaire, game| {
+        solitaire.harness.warp(31);
+        let caller = solitaire.harness.funded_wallet();
+        let ix = Instruction {
+            program_id: solitaire::ID,
+            accounts: accounts::ClaimTimeout {
+                game: game.address(),
+                escrow_token_account: game.escrow(),
+                escrow_authority: game.escrow_authority(),
+                treasury_token_account: treasury(&game.player.mint),
+                promo_vault: None,
+                player_stats: Some(player_stats(&game.player.wallet)),
+                insurance_ledger: None,
+                insurance_vault: None,
+                insurance_authority: None,
+                player_token_account: None,
+                caller,
+                token_program: TOKEN,
+            }
+            .to_account_metas(None),
+            data: instruction::ClaimTimeout {}.data(),
+        };
+        solitaire.harness.process(&[ix], &[caller]).unwrap();
+    });
+}
+
+#[test]
+fn a_sweep_frees_the_slot() {
+    assert_exit_frees_the_slot(GameMode::Standard, |solitaire, game| {
+        solitaire.harness.warp(STALE_ESCROW_SECONDS);
+        solitaire.sweep(game).unwrap();
+    });
+}
@@ -0,0 +1,584 @@
+//! Shared setup for the solitaire program tests.
+#![allow(dead_code, unused_imports)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    bpf_loader_upgradeable, instruction::Instruction, program_error::ProgramError, sysvar,
+};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use program_harness::Harness;
+use solitaire::{accounts, instruction, GameAccount, GameMode, GameVariant, GlobalConfig, PileType};
+
+pub use anchor_spl::token::ID as TOKEN;
+pub use program_harness::{account_bytes, anchor_error, assert_error, FUNDED_LAMPORTS};
+
+pub type TxResult = std::result::Result<(), ProgramError>;
+
+pub const DAY: i64 = 24 * 60 * 60;
+/// Minted to every player `Solitaire::player` creates.
+pub const PLAYER_TOKENS: u64 = 1_000_000_000;
+
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &solitaire::ID).0
+}
+
+/// The upgradeable loader's ProgramData account for the solitaire program.
+pub fn program_data() -> Pubkey {
+    Pubkey::find_program_address(&[solitaire::ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+pub fn treasury(mint: &Pubkey) -> Pubkey {
+    pda(&[b"treasury", mint.as_ref()])
+}
+
+pub fn promo_vault(mint: &Pubkey) -> Pubkey {
+    pda(&[b"promo_vault", mint.as_ref()])
+}
+
+pub fn insurance_vault(mint: &Pubkey) -> Pubkey {
+    pda(&[b"insurance_vault", mint.as_ref()])
+}
+
+pub fn insurance_ledger(mint: &Pubkey) -> Pubkey {
+    pda(&[b"insurance_ledger", mint.as_ref()])
+}
+
+pub fn insurance_authority() -> Pubkey {
+    pda(&[b"insurance_authority"])
+}
+
+pub fn treasury_authority() -> Pubkey {
+    pda(&[b"treasury_authority"])
+}
+
+pub fn player_stats(player: &Pubkey) -> Pubkey {
+    pda(&[b"player_stats", player.as_ref()])
+}
+
+/// A wallet and its token account for one mint.
+#[derive(Clone, Copy, Debug)]
+pub struct Player {
+    pub wallet: Pubkey,
+    pub tokens: Pubkey,
+    pub mint: Pubkey,
+}
+
+/// What `initialize_game` is called with, less the player and game id.
+#[derive(Clone, Copy, Debug)]
+pub struct GameParams {
+    pub stake: u64,
+    pub mode: GameMode,
+    pub variant: GameVariant,
+    pub deal_number: Option<u32>,
+    pub practice: bool,
+    /// Named to `initialize_game`, with the player's referral account.
+    pub referrer: Option<Pubkey>,
+    /// Buys loss insurance; needs the mint's insurance vault.
+    pub insured: bool,
+}
+
+impl Default for GameParams {
+    fn default() -> Self {
+        Self {
+            stake: 1_000_000,
+            mode: GameMode::Standard,
+            variant: GameVariant::Klondike,
+            deal_number: None,
+            practice: false,
+            referrer: None,
+            insured: false,
+        }
+    }
+}
+
+/// A game's keys; everything else is derived from its id and player.
+#[derive(Clone, Debug)]
+pub struct Game {
+    pub id: String,
+    pub player: Player,
+}
+
+impl Game {
+    pub fn address(&self) -> Pubkey {
+        pda(&[b"game", self.player.wallet.as_ref(), self.id.as_bytes()])
+    }
+
+    pub fn escrow(&self) -> Pubkey {
+        pda(&[b"escrow", self.id.as_bytes()])
+    }
+
+    pub fn escrow_authority(&self) -> Pubkey {
+        pda(&[b"escrow_authority", self.id.as_bytes()])
+    }
+}
+
+/// The solitaire program loaded into a harness with its config initialized
+/// and a reward mint the admin can mint.
+pub struct Solitaire {
+    pub harness: Harness,
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    games: u32,
+}
+
+impl Solitaire {
+    pub fn new() -> Self {
+        let mut harness = Harness::new();
+        harness.add_program(solitaire::ID, solitaire::entry);
+        let admin = harness.funded_wallet();
+        harness.set_upgrade_authority(solitaire::ID, Some(admin));
+        let mint = harness.create_mint(&TOKEN, &admin, 6);
+        let mut solitaire = Self {
+            harness,
+            admin,
+            mint,
+            games: 0,
+        };
+        let ix = solitaire.initialize_config_ix(admin);
+        solitaire.admin(ix).expect("config initializes");
+        solitaire
+    }
+
+    pub fn initialize_config_ix(&self, admin: Pubkey) -> Instruction {
+        Instruction {
+            program_id: solitaire::ID,
+            accounts: accounts::InitializeConfig {
+                config: self.config(),
+                admin,
+                program: solitaire::ID,
+                program_data: program_data(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializeConfig {}.data(),
+        }
+    }
+
+    pub fn config(&self) -> Pubkey {
+        pda(&[b"config"])
+    }
+
+    pub fn config_state(&self) -> GlobalConfig {
+        self.harness.get(&self.config())
+    }
+
+    /// Runs `ix` signed by the admin.
+    pub fn admin(&mut self, ix: Instruction) -> TxResult {
+        let admin = self.admin;
+        self.harness.process(&[ix], &[admin])
+    }
+
+    /// Any of the `UpdateConfig` instructions, signed by the admin.
+    pub fn update_config(&mut self, data: impl InstructionData) -> TxResult {
+        let ix = Instruction {
+            program_id: solitaire::ID,
+            accounts: accounts::UpdateConfig {
+                config: self.config(),
+                admin: self.admin,
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        };
+        self.admin(ix)
+    }
+
+    pub fn balance(&self, account: &Pubkey) -> u64 {
+        self.harness.token_balance(account)
+    }
+
+    /// A funded wallet holding `PLAYER_TOKENS` of the reward mint.
+    pub fn player(&mut self) -> Player {
+        let mint = self.mint;
+        self.player_for(&mint)
+    }
+
+    /// Like `player`, for another mint the admin can mint.
+    pub fn player_for(&mut self, mint: &Pubkey) -> Player {
+        let wallet = self.harness.funded_wallet();
+        let tokens = self.harness.create_token_account(mint, &wallet);
+        let admin = self.admin;
+        self.harness.mint_to(mint, &tokens, &admin, PLAYER_TOKENS);
+        Player {
+            wallet,
+            tokens,
+            mint: *mint,
+        }
+    }
+
+    /// Creates the treasury for `mint` and mints `amount` into it.
+    pub fn treasury(&mut self, mint: &Pubkey, amount: u64) -> Pubkey {
+        let account = treasury(mint);
+        let ix = Instruction {
+            program_id: solitaire::ID,
+            accounts: accounts::InitializeTreasury {
+                config: self.config(),
+                treasury_token_account: account,
+                treasury_authority: treasury_authority(),
+                mint: *mint,
+                admin: self.admin,
+                system_program: system_program::ID,
+                token_program: TOKEN,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializeTreasury {}.data(),
+        };
+        self.admin(ix).expect("treasury initializes");
+        if amount > 0 {
+            let admin = self.admin;
+            self.harness.mint_to(mint, &account, &admin, amount);
+        }
+        account
+    }
+
+    /// A game with the next free id; the player signs and pays.
+    pub fn game(&mut self, player: &Player) -> Game {
+        self.games += 1;
+        Game {
+            id: format!("game-{}", self.games),
+            player: *player,
+        }
+    }
+
+    pub fn start_ix(&self, game: &Game, params: GameParams) -> Instruction {
+        Instruction {
+            program_id: solitaire::ID,
+            accounts: accounts::InitializeGame {
+                game: game.address(),
+                escrow_token_account: game.escrow(),
+                escrow_authority: game.escrow_authority(),
+                user_token_account: game.player.tokens,
+                reward_mint_info: game.player.mint,
+                config: self.config(),
+                referral: params
+                    .referrer
+                    .map(|_| pda(&[b"referral", game.player.wallet.as_ref()])),
+                player_stats: player_stats(&game.player.wallet),
+                insurance_vault: params.insured.then(|| insurance_vault(&game.player.mint)),
+                insurance_ledger: params.insured.then(|| insurance_ledger(&game.player.mint)),
+                authority: game.player.wallet,
+                system_program: system_program::ID,
+                token_program: TOKEN,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializeGame {
+                game_id: game.id.clone(),
+                stake_amount: params.stake,
+                reward_mint: game.player.mint,
+                mode: params.mode,
+                allow_foundation_to_tableau: None,
+                partner: None,
+                variant: params.variant,
+                deal_number: params.deal_number,
+                referrer: params.referrer,
+                insurance: params.insured,
+                practice: params.practice,
+            }
+            .data(),
+        }
+    }
+
+    /// Creates the promo vault for `mint` and mints `amount` into it.
+    pub fn promo_vault(&mut self, mint: &Pubkey, amount: u64) -> Pubkey {
+        let account = promo_vault(mint);
+        let ix = Instruction {
+            program_id: solitaire::ID,
+            accounts: accounts::InitializePromoVault {
+                config: self.config(),
+                promo_vault: account,
+                promo_authority: pda(&[b"promo_authority"]),
+                mint: *mint,
+                admin: self.admin,
+                system_program: system_program::ID,
+                token_program: TOKEN,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializePromoVault {}.data(),
+        };
+        self.admin(ix).expect("promo vault initializes");
+        if amount > 0 {
+            let admin = self.admin;
+            self.harness.mint_to(mint, &account, &admin, amount);
+        }
+        account
+    }
+
+    /// Creates the insurance vault and ledger for `mint`, minting `amount`
+    /// into the vault.
+    pub fn insurance_vault(&mut self, mint: &Pubkey, amount: u64) -> Pubkey {
+        let account = insurance_vault(mint);
+        let ix = Instruction {
+            program_id: solitaire::ID,
+            accounts: accounts::InitializeInsuranceVault {
+                config: self.config(),
+                insurance_vault: account,
+                insurance_ledger: insurance_ledger(mint),
+                insurance_authority: insurance_authority(),
+                mint: *mint,
+                admin: self.admin,
+                system_program: system_program::ID,
+                token_program: TOKEN,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializeInsuranceVault {}.data(),
+        };
+        self.admin(ix).expect("insurance vault initializes");
+        if amount > 0 {
+            let admin = self.admin;
+            self.harness.mint_to(mint, &account, &admin, amount);
+        }
+        account
+    }
+
+    /// The promo vault to pass for `game`: only free games need one.
+    pub fn promo_vault_for(&self, game: &Game) -> Option<Pubkey> {
+        self.game_state(game)
+            .free_game
+            .then(|| promo_vault(&game.player.mint))
+    }
+
+    /// Starts today's free game for `player`, staked from the promo vault.
+    /// Needs `free_game_stake` set and a funded promo vault.
+    pub fn start_free(&mut self, player: &Player) -> Game {
+        let game = self.game(player);
+        let day = (self.harness.now() / solitaire::SECONDS_PER_DAY) as u64;
+        let ix = Instruction {
+            program_id: solitaire::ID,
+            accounts: accounts::InitializeFreeGame {
+                daily_free_game: pda(&[
+                    b"daily_free_game",
+                    player.wallet.as_ref(),
+                    &day.to_le_bytes(),
+                ]),
+                game: game.address(),
+                escrow_token_account: game.escrow(),
+                escrow_authority: game.escrow_authority(),
+                promo_vault: promo_vault(&player.mint),
+                promo_authority: pda(&[b"promo_authority"]),
+                reward_mint_info: player.mint,
+                config: self.config(),
+                player_stats: player_stats(&player.wallet),
+                authority: player.wallet,
+                system_program: system_program::ID,
+                token_program: TOKEN,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializeFreeGame {
+                game_id: game.id.clone(),
+                day,
+                mode: GameMode::Standard,
+                allow_foundation_to_tableau: None,
+                variant: GameVariant::Klondike,
+            }
+            .data(),
+        };
+        self.harness
+            .process(&[ix], &[player.wallet])
+            .expect("free game starts");
+        game
+    }
+
+    /// Starts a new game for `player`.
+    pub fn start(&mut self, player: &Player, params: GameParams) -> Game {
+        let game = self.game(player);
+        let ix = self.start_ix(&game, params);
+        self.harness
+            .process(&[ix], &[player.wallet])
+            .expect("game starts");
+        game
+    }
+
+    pub fn game_state(&self, game: &Game) -> GameAccount {
+        self.harness.get(&game.address())
+    }
+
+    /// Rewrites the stored game in place, e.g. to set up a board.
+    pub fn edit_game(&mut self, game: &Game, edit: impl FnOnce(&mut GameAccount)) {
+        let key = game.address();
+        let mut account = self.game_state(game);
+        edit(&mut account);
+        let mut state = self.harness.account(&key).unwrap().clone();
+        let bytes = account_bytes(&account);
+        state.data[..bytes.len()].copy_from_slice(&bytes);
+        self.harness.set_account(key, state);
+    }
+
+    /// Index of the pile with `id` on the game's board.
+    pub fn pile(&self, game: &Game, id: &str) -> usize {
+        self.game_state(game)
+            .game_state
+            .piles
+            .iter()
+            .position(|pile| pile.id == id)
+            .unwrap()
+    }
+
+    pub fn move_ix(&self, game: &Game, from: &str, to: &str, card_index: u8) -> Instruction {
+        Instruction {
+            program_id: solitaire::ID,
+            accounts: accounts::MakeMove {
+                game: game.address(),
+                authority: game.player.wallet,
+            }
+            .to_account_metas(None),
+            data: instruction::MakeMove {
+                from_pile: from.to_string(),
+                to_pile: to.to_string(),
+                card_index,
+            }
+            .data(),
+        }
+    }
+
+    pub fn make_move(&mut self, game: &Game, from: &str, to: &str, card_index: u8) -> TxResult {
+        let ix = self.move_ix(game, from, to, card_index);
+        self.harness.process(&[ix], &[game.player.wallet])
+    }
+
+    /// Turns the top stock card onto the waste.
+    pub fn draw(&mut self, game: &Game) -> TxResult {
+        let stock = self.pile(game, "stock");
+        let top = self.game_state(game).game_state.piles[stock].cards.len() - 1;
+        self.make_move(game, "stock", "waste", top as u8)
+    }
+
+    /// A win pays out double the stake but the escrow only holds the one;
+    /// mints the other into it.
+    pub fn fund_win(&mut self, game: &Game, stake: u64) {
+        let admin = self.admin;
+        self.harness.mint_to(&game.player.mint, &game.escrow(), &admin, stake);
+    }
+
+    /// `complete_game` accounts with the treasury and the player's stats
+    /// supplied and every other optional account left out.
+    pub fn complete_accounts(&self, game: &Game) -> accounts::CompleteGame {
+        let treasury = treasury(&game.player.mint);
+        let has_treasury = self.harness.account(&treasury).is_some();
+        let insured = self.game_state(game).insured;
+        accounts::CompleteGame {
+            game: game.address(),
+            config: self.config(),
+            escrow_token_account: game.escrow(),
+            user_token_account: game.player.tokens,
+            partner_token_account: None,
+            escrow_authority: game.escrow_authority(),
+            referrer_token_account: None,
+            promo_vault: None,
+            nft_token_account: None,
+            nft_metadata: None,
+            treasury_token_account: has_treasury.then_some(treasury),
+            treasury_authority: has_treasury.then(treasury_authority),
+            player_stats: Some(player_stats(&game.player.wallet)),
+            global_stats: None,
+            insurance_vault: insured.then(|| insurance_vault(&game.player.mint)),
+            insurance_authority: insured.then(insurance_authority),
+            insurance_ledger: insured.then(|| insurance_ledger(&game.player.mint)),
+            stake_account: None,
+            authority: game.player.wallet,
+            token_program: TOKEN,
+        }
+    }
+
+    pub fn complete_with(&mut self, game: &Game, accounts: accounts::CompleteGame) -> TxResult {
+        let ix = Instruction {
+            program_id: solitaire::ID,
+            accounts: accounts.to_account_metas(None),
+            data: instruction::CompleteGame {}.data(),
+        };
+        self.harness.process(&[ix], &[game.player.wallet])
+    }
+
+    pub fn complete(&mut self, game: &Game) -> TxResult {
+        let accounts = self.complete_accounts(game);
+        self.complete_with(game, accounts)
+    }
+
+    /// `withdraw_stake` or `cancel_game`, which share their accounts.
+    pub fn leave_ix(&self, game: &Game, data: impl InstructionData) -> Instruction {
+        let insured = self.game_state(game).insured;
+        Instruction {
+            program_id: solitaire::ID,
+            accounts: accounts::WithdrawStake {
+                game: game.address(),
+                escrow_token_account: game.escrow(),
+                user_token_account: game.player.tokens,
+                escrow_authority: game.escrow_authority(),
+                promo_vault: None,
+                player_stats: Some(player_stats(&game.player.wallet)),
+                insurance_vault: insured.then(|| insurance_vault(&game.player.mint)),
+                insurance_authority: insured.then(insurance_authority),
+                insurance_ledger: insured.then(|| insurance_ledger(&game.player.mint)),
+                authority: game.player.wallet,
+                token_program: TOKEN,
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        }
+    }
+
+    pub fn leave(&mut self, game: &Game, data: impl InstructionData) -> TxResult {
+        let ix = self.leave_ix(game, data);
+        self.harness.process(&[ix], &[game.player.wallet])
+    }
+
+    /// `sweep_stale_escrow`, cranked by a stranger.
+    pub fn sweep(&mut self, game: &Game) -> TxResult {
+        let caller = self.harness.funded_wallet();
+        let ix = Instruction {
+            program_id: solitaire::ID,
+            accounts: accounts::SweepStaleEscrow {
+                game: game.address(),
+                escrow_token_account: game.escrow(),
+                escrow_authority: game.escrow_authority(),
+                treasury_token_account: treasury(&game.player.mint),
+                promo_vault: self.promo_vault_for(game),
+                player: game.player.wallet,
+                player_stats: Some(player_stats(&game.player.wallet)),
+                insurance_ledger: self
+                    .game_state(game)
+                    .insured
+                    .then(|| insurance_ledger(&game.player.mint)),
+                caller,
+                token_program: TOKEN,
+            }
+            .to_account_metas(None),
+            data: instruction::SweepStaleEscrow {}.data(),
+        };
+        self.harness.process(&[ix], &[caller])
+    }
+}
+
+/// Sets up a board one move from winning: every card is on its foundation
+/// except the king of spades, which waits face up on `tableau-0`.
+pub fn one_move_from_winning(account: &mut GameAccount) {
+    for pile in account.game_state.piles.iter_mut() {
+        pile.cards.clear();
+    }
+    let foundations: Vec<usize> = account
+        .game_state
+        .piles
+        .iter()
+        .enumerate()
+        .filter(|(_, pile)| pile.pile_type == PileType::Foundation)
+        .map(|(index, _)| index)
+        .collect();
+    for (suit, pile) in foundations.into_iter().enumerate() {
+        let ranks = if suit == 3 { 1..=12 } else { 1..=13 };
+        account.game_state.piles[pile].cards = ranks
+            .map(|rank| solitaire::CardData {
+                suit: suit as u8,
+                rank,
+                face_up: true,
+            })
+            .collect();
+    }
+    account.game_state.piles[0].cards.push(solitaire::CardData {
+        suit: 3,
+        rank: 13,
+        face_up: true,
+    });
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::*;
+use solitaire::{instruction, GameMode, ModeConfig, ScoringRules, SolitaireError};
+
+#[test]
+fn only_the_upgrade_authority_initializes_the_config() {
+    let mut solitaire = Solitaire::new();
+    let config = solitaire.config();
+    solitaire.harness.remove_account(&config);
+
+    let stranger = solitaire.harness.funded_wallet();
+    let ix = solitaire.initialize_config_ix(stranger);
+    assert_error(
+        solitaire.harness.process(&[ix], &[stranger]),
+        SolitaireError::Unauthorized,
+    );
+
+    // A program whose upgrade authority was dropped can never be claimed
+    solitaire.harness.set_upgrade_authority(solitaire::ID, None);
+    let admin = solitaire.admin;
+    let ix = solitaire.initialize_config_ix(admin);
+    assert_error(solitaire.admin(ix), SolitaireError::Unauthorized);
+
+    solitaire.harness.set_upgrade_authority(solitaire::ID, Some(admin));
+    let ix = solitaire.initialize_config_ix(admin);
+    solitaire.admin(ix).unwrap();
+    assert_eq!(solitaire.config_state().admin, admin);
+}
+
+#[test]
+fn moves_wait_out_the_minimum_slot_interval() {
+    let mut solitaire = Solitaire::new();
+    solitaire
+        .update_config(instruction::UpdateModeConfig {
+            mode: GameMode::Standard,
+            mode_config: ModeConfig {
+                min_move_interval_slots: 3,
+                scoring: ScoringRules::default(),
+                move_clock_seconds: 0,
+            },
+        })
+        .unwrap();
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    let dealt_at = solitaire.harness.clock().slot;
+
+    assert_error(solitaire.draw(&game), SolitaireError::MovesTooFast);
+    let mut clock = solitaire.harness.clock();
+    clock.slot = dealt_at + 2;
+    solitaire.harness.set_clock(clock.clone());
+    assert_error(solitaire.draw(&game), SolitaireError::MovesTooFast);
+
+    clock.slot = dealt_at + 3;
+    solitaire.harness.set_clock(clock.clone());
+    solitaire.draw(&game).unwrap();
+
+    // The interval restarts from the move just made
+    assert_error(solitaire.draw(&game), SolitaireError::MovesTooFast);
+    clock.slot = dealt_at + 6;
+    solitaire.harness.set_clock(clock);
+    solitaire.draw(&game).unwrap();
+}
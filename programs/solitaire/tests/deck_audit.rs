@@ -0,0 +1,189 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solitaire::{
+    accounts, instruction, DeckAuditPassed, DeckIntegrityFailed, DeckViolation, GameCorruptionCleared, GameMode,
+    ModeConfig, SolitaireError,
+};
+
+fn audit(solitaire: &mut Solitaire, game: &Game) -> TxResult {
+    let caller = solitaire.harness.funded_wallet();
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::AuditDeck { game: game.address() }.to_account_metas(None),
+        data: instruction::AuditDeck {}.data(),
+    };
+    solitaire.harness.process(&[ix], &[caller])
+}
+
+fn clear_ix(solitaire: &Solitaire, game: &Game, admin: Pubkey) -> Instruction {
+    Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::ReviewGame {
+            config: solitaire.config(),
+            game: game.address(),
+            admin,
+        }
+        .to_account_metas(None),
+        data: instruction::ClearCorruptedFlag {}.data(),
+    }
+}
+
+fn clear(solitaire: &mut Solitaire, game: &Game) -> TxResult {
+    let ix = clear_ix(solitaire, game, solitaire.admin);
+    solitaire.admin(ix)
+}
+
+/// Copies the stock's second card over its first, so one card is there twice.
+fn tamper(solitaire: &mut Solitaire, game: &Game) -> DeckViolation {
+    let stock = solitaire.pile(game, "stock");
+    solitaire.edit_game(game, |account| {
+        let cards = &mut account.game_state.piles[stock].cards;
+        cards[0] = cards[1];
+    });
+    DeckViolation::DuplicateCard {
+        pile: stock as u8,
+        position: 1,
+    }
+}
+
+/// A tampered game flagged by an audit.
+fn flagged(solitaire: &mut Solitaire, game: &Game) {
+    let violation = tamper(solitaire, game);
+    audit(solitaire, game).unwrap();
+    assert_eq!(solitaire.harness.event::<DeckIntegrityFailed>().violation, violation);
+    assert!(solitaire.game_state(game).corrupted);
+}
+
+fn claim_timeout(solitaire: &mut Solitaire, game: &Game) -> TxResult {
+    let caller = solitaire.harness.funded_wallet();
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::ClaimTimeout {
+            game: game.address(),
+            escrow_token_account: game.escrow(),
+            escrow_authority: game.escrow_authority(),
+            treasury_token_account: treasury(&game.player.mint),
+            promo_vault: None,
+            player_stats: Some(player_stats(&game.player.wallet)),
+            insurance_ledger: None,
+            insurance_vault: None,
+            insurance_authority: None,
+            player_token_account: None,
+            caller,
+            token_program: TOKEN,
+        }
+        .to_account_metas(None),
+        data: instruction::ClaimTimeout {}.data(),
+    };
+    solitaire.harness.process(&[ix], &[caller])
+}
+
+fn close(solitaire: &mut Solitaire, game: &Game) -> TxResult {
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::CloseGame {
+            game: game.address(),
+            escrow_token_account: game.escrow(),
+            escrow_authority: game.escrow_authority(),
+            treasury_token_account: Some(treasury(&game.player.mint)),
+            promo_vault: None,
+            authority: game.player.wallet,
+            token_program: TOKEN,
+        }
+        .to_account_metas(None),
+        data: instruction::CloseGame {}.data(),
+    };
+    solitaire.harness.process(&[ix], &[game.player.wallet])
+}
+
+#[test]
+fn a_dealt_deck_passes_its_audit() {
+    let mut solitaire = Solitaire::new();
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+
+    audit(&mut solitaire, &game).unwrap();
+    let passed = solitaire.harness.event::<DeckAuditPassed>();
+    assert_eq!(passed.state_hash, solitaire.game_state(&game).game_state.state_hash());
+    assert!(!solitaire.game_state(&game).corrupted);
+    assert_error(clear(&mut solitaire, &game), SolitaireError::GameNotCorrupted);
+}
+
+#[test]
+fn a_flagged_game_is_not_settled_until_an_admin_clears_it() {
+    let mut solitaire = Solitaire::new();
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    flagged(&mut solitaire, &game);
+
+    assert_error(solitaire.complete(&game), SolitaireError::GameCorrupted);
+    solitaire.harness.warp(DAY);
+    assert_error(
+        solitaire.leave(&game, instruction::WithdrawStake {}),
+        SolitaireError::GameCorrupted,
+    );
+    assert_error(
+        solitaire.leave(&game, instruction::CancelGame {}),
+        SolitaireError::GameCorrupted,
+    );
+
+    // Only the admin can lift the flag
+    let stranger = solitaire.harness.funded_wallet();
+    let ix = clear_ix(&solitaire, &game, stranger);
+    assert_error(solitaire.harness.process(&[ix], &[stranger]), ErrorCode::ConstraintHasOne);
+    clear(&mut solitaire, &game).unwrap();
+    assert_eq!(solitaire.harness.event::<GameCorruptionCleared>().admin, solitaire.admin);
+    solitaire.leave(&game, instruction::WithdrawStake {}).unwrap();
+}
+
+#[test]
+fn a_flagged_timeout_is_not_claimed_until_cleared() {
+    let mut solitaire = Solitaire::new();
+    solitaire
+        .update_config(instruction::UpdateModeConfig {
+            mode: GameMode::Blitz,
+            mode_config: ModeConfig {
+                move_clock_seconds: 30,
+                ..ModeConfig::default()
+            },
+        })
+        .unwrap();
+    let mint = solitaire.mint;
+    solitaire.treasury(&mint, 0);
+    let player = solitaire.player();
+    let params = GameParams {
+        mode: GameMode::Blitz,
+        ..GameParams::default()
+    };
+    let game = solitaire.start(&player, params);
+    flagged(&mut solitaire, &game);
+
+    solitaire.harness.warp(31);
+    assert_error(claim_timeout(&mut solitaire, &game), SolitaireError::GameCorrupted);
+    clear(&mut solitaire, &game).unwrap();
+    claim_timeout(&mut solitaire, &game).unwrap();
+}
+
+#[test]
+fn a_flagged_game_is_not_closed_until_cleared() {
+    let mut solitaire = Solitaire::new();
+    let mint = solitaire.mint;
+    let treasury = solitaire.treasury(&mint, 0);
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    solitaire.leave(&game, instruction::CancelGame {}).unwrap();
+    let leftover = solitaire.balance(&game.escrow());
+    flagged(&mut solitaire, &game);
+
+    solitaire.harness.warp(1);
+    assert_error(close(&mut solitaire, &game), SolitaireError::GameCorrupted);
+    assert_eq!(solitaire.balance(&game.escrow()), leftover);
+    clear(&mut solitaire, &game).unwrap();
+    close(&mut solitaire, &game).unwrap();
+    assert_eq!(solitaire.balance(&treasury), leftover);
+}
@@ -0,0 +1,211 @@
+mod common;
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solitaire::{accounts, instruction, DisputeConfig, GameChallenged, MoveRecord, SolitaireError};
+
+const STAKE: u64 = 1_000_000;
+const TREASURY: u64 = 10 * STAKE;
+/// 20% of the stake held back, 10% posted by challengers, 5% bounty.
+const DISPUTE: DisputeConfig = DisputeConfig {
+    window_seconds: DAY,
+    player_bond_bps: 2_000,
+    challenge_bond_bps: 1_000,
+    challenger_bounty_bps: 500,
+};
+
+fn disputed() -> (Solitaire, Game) {
+    disputed_with(DISPUTE, TREASURY)
+}
+
+fn disputed_with(dispute: DisputeConfig, treasury: u64) -> (Solitaire, Game) {
+    let mut solitaire = Solitaire::new();
+    solitaire
+        .update_config(instruction::UpdateDisputeConfig { dispute })
+        .unwrap();
+    let mint = solitaire.mint;
+    solitaire.treasury(&mint, treasury);
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    (solitaire, game)
+}
+
+fn challenge(solitaire: &mut Solitaire, game: &Game, challenger: &Player, move_index: u32) -> TxResult {
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::ChallengeGame {
+            game: game.address(),
+            config: solitaire.config(),
+            escrow_token_account: game.escrow(),
+            escrow_authority: game.escrow_authority(),
+            treasury_token_account: treasury(&game.player.mint),
+            treasury_authority: treasury_authority(),
+            challenger_token_account: challenger.tokens,
+            challenger: challenger.wallet,
+            token_program: TOKEN,
+        }
+        .to_account_metas(None),
+        data: instruction::ChallengeGame { move_index }.data(),
+    };
+    solitaire.harness.process(&[ix], &[challenger.wallet])
+}
+
+fn release_bond(solitaire: &mut Solitaire, game: &Game) -> TxResult {
+    let caller = solitaire.harness.funded_wallet();
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::ReleaseBond {
+            game: game.address(),
+            escrow_token_account: game.escrow(),
+            escrow_authority: game.escrow_authority(),
+            user_token_account: game.player.tokens,
+            token_program: TOKEN,
+        }
+        .to_account_metas(None),
+        data: instruction::ReleaseBond {}.data(),
+    };
+    solitaire.harness.process(&[ix], &[caller])
+}
+
+#[test]
+fn an_honest_game_keeps_its_bond_and_the_challenger_pays() {
+    let (mut solitaire, game) = disputed();
+    solitaire.draw(&game).unwrap();
+    solitaire.draw(&game).unwrap();
+    solitaire.complete(&game).unwrap();
+    // Half the stake back for a loss, a fifth of the stake held as the bond
+    let bond = STAKE / 5;
+    assert_eq!(solitaire.game_state(&game).bond_amount, bond);
+    assert_eq!(solitaire.balance(&game.player.tokens), PLAYER_TOKENS - STAKE / 2 - bond);
+
+    let challenger = solitaire.player();
+    assert_error(
+        challenge(&mut solitaire, &game, &challenger, 2),
+        SolitaireError::InvalidChallenge,
+    );
+    challenge(&mut solitaire, &game, &challenger, 1).unwrap();
+    assert!(!solitaire.harness.event::<GameChallenged>().upheld);
+    assert_eq!(solitaire.balance(&challenger.tokens), PLAYER_TOKENS - STAKE / 10);
+    assert_eq!(solitaire.balance(&treasury(&game.player.mint)), TREASURY + STAKE / 10);
+    assert!(!solitaire.game_state(&game).fraudulent);
+
+    assert_error(release_bond(&mut solitaire, &game), SolitaireError::DisputeWindowOpen);
+    solitaire.harness.warp(DAY + 1);
+    assert_error(
+        challenge(&mut solitaire, &game, &challenger, 1),
+        SolitaireError::DisputeWindowClosed,
+    );
+    release_bond(&mut solitaire, &game).unwrap();
+    assert_eq!(solitaire.balance(&game.player.tokens), PLAYER_TOKENS - STAKE / 2);
+    assert_eq!(solitaire.game_state(&game).bond_amount, 0);
+    assert_error(release_bond(&mut solitaire, &game), SolitaireError::NoBondHeld);
+}
+
+#[test]
+fn a_board_the_seed_cannot_reach_is_clawed_back() {
+    let (mut solitaire, game) = disputed();
+    solitaire.edit_game(&game, one_move_from_winning);
+    solitaire.fund_win(&game, STAKE);
+    solitaire.make_move(&game, "tableau-0", "foundation-3", 0).unwrap();
+    solitaire.complete(&game).unwrap();
+    let bond = STAKE / 5;
+    assert_eq!(solitaire.balance(&game.escrow()), bond);
+
+    let challenger = solitaire.player();
+    challenge(&mut solitaire, &game, &challenger, 0).unwrap();
+    let challenged = solitaire.harness.event::<GameChallenged>();
+    assert!(challenged.upheld);
+    assert_eq!(challenged.clawback, bond);
+    assert_eq!(challenged.bounty, STAKE / 20);
+
+    // The challenger gets their bond back plus the bounty; the bond funds both
+    assert_eq!(solitaire.balance(&challenger.tokens), PLAYER_TOKENS + STAKE / 20);
+    assert_eq!(solitaire.balance(&game.escrow()), 0);
+    assert_eq!(
+        solitaire.balance(&treasury(&game.player.mint)),
+        TREASURY + bond - STAKE / 20
+    );
+    let account = solitaire.game_state(&game);
+    assert!(account.fraudulent);
+    assert_eq!(account.bond_amount, 0);
+
+    assert_error(
+        challenge(&mut solitaire, &game, &challenger, 0),
+        SolitaireError::GameAlreadyFlagged,
+    );
+    solitaire.harness.warp(DAY + 1);
+    assert_error(release_bond(&mut solitaire, &game), SolitaireError::GameAlreadyFlagged);
+}
+
+#[test]
+fn legal_moves_with_an_inflated_score_are_upheld() {
+    let (mut solitaire, game) = disputed();
+    solitaire.draw(&game).unwrap();
+    // Every logged move replays, but the stored score was never earned
+    solitaire.edit_game(&game, |account| account.game_state.score += 1_000);
+    solitaire.complete(&game).unwrap();
+
+    let challenger = solitaire.player();
+    challenge(&mut solitaire, &game, &challenger, 0).unwrap();
+    assert!(solitaire.harness.event::<GameChallenged>().upheld);
+    assert!(solitaire.game_state(&game).fraudulent);
+}
+
+#[test]
+fn only_the_first_illegal_move_can_be_cited() {
+    let (mut solitaire, game) = disputed();
+    solitaire.draw(&game).unwrap();
+    // A move from a pile onto itself slipped into the log between two draws
+    solitaire.edit_game(&game, |account| {
+        account.move_log.push(MoveRecord {
+            from: 0,
+            to: 0,
+            card_index: 0,
+        })
+    });
+    solitaire.draw(&game).unwrap();
+    solitaire.complete(&game).unwrap();
+
+    // The draw before it is legal and no challenge can reach past it
+    let challenger = solitaire.player();
+    challenge(&mut solitaire, &game, &challenger, 0).unwrap();
+    assert!(!solitaire.harness.event::<GameChallenged>().upheld);
+    assert_error(
+        challenge(&mut solitaire, &game, &challenger, 2),
+        SolitaireError::InvalidChallenge,
+    );
+
+    challenge(&mut solitaire, &game, &challenger, 1).unwrap();
+    let challenged = solitaire.harness.event::<GameChallenged>();
+    assert!(challenged.upheld);
+    assert_eq!(challenged.move_index, 1);
+    assert!(solitaire.game_state(&game).fraudulent);
+    // The first challenge's bond is lost, the second's refunded with the bounty
+    assert_eq!(
+        solitaire.balance(&challenger.tokens),
+        PLAYER_TOKENS - STAKE / 10 + STAKE / 20
+    );
+}
+
+#[test]
+fn the_bounty_is_capped_by_the_treasury() {
+    // No bond held back, so nothing is clawed back to fund the bounty
+    let dispute = DisputeConfig {
+        player_bond_bps: 0,
+        ..DISPUTE
+    };
+    let (mut solitaire, game) = disputed_with(dispute, STAKE / 100);
+    solitaire.edit_game(&game, one_move_from_winning);
+    solitaire.fund_win(&game, STAKE);
+    solitaire.make_move(&game, "tableau-0", "foundation-3", 0).unwrap();
+    solitaire.complete(&game).unwrap();
+
+    let challenger = solitaire.player();
+    challenge(&mut solitaire, &game, &challenger, 0).unwrap();
+    let challenged = solitaire.harness.event::<GameChallenged>();
+    assert!(challenged.upheld);
+    assert_eq!((challenged.clawback, challenged.bounty), (0, STAKE / 100));
+    assert_eq!(solitaire.balance(&challenger.tokens), PLAYER_TOKENS + STAKE / 100);
+    assert_eq!(solitaire.balance(&treasury(&game.player.mint)), 0);
+}
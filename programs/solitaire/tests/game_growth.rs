@@ -0,0 +1,174 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use solitaire::{
+    accounts, instruction, GameAccount, GameAccountGrown, GameClosed, MoveRecord, SolitaireError,
+    INITIAL_MOVE_LOG_CAPACITY, MAX_MOVE_LOG, MOVE_LOG_GROWTH, STALE_ESCROW_SECONDS,
+};
+
+fn grow(solitaire: &mut Solitaire, game: &Game) -> TxResult {
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::GrowGameAccount {
+            game: game.address(),
+            authority: game.player.wallet,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::GrowGameAccount {}.data(),
+    };
+    solitaire.harness.process(&[ix], &[game.player.wallet])
+}
+
+fn close(solitaire: &mut Solitaire, game: &Game) -> TxResult {
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::CloseGame {
+            game: game.address(),
+            escrow_token_account: game.escrow(),
+            escrow_authority: game.escrow_authority(),
+            treasury_token_account: Some(treasury(&game.player.mint)),
+            promo_vault: None,
+            authority: game.player.wallet,
+            token_program: TOKEN,
+        }
+        .to_account_metas(None),
+        data: instruction::CloseGame {}.data(),
+    };
+    solitaire.harness.process(&[ix], &[game.player.wallet])
+}
+
+/// Pads the move log to `len` entries without touching the board.
+fn fill_log(solitaire: &mut Solitaire, game: &Game, len: usize) {
+    solitaire.edit_game(game, |account| {
+        let record = MoveRecord { from: 0, to: 1, card_index: 0 };
+        account.move_log.resize(len, record);
+    });
+}
+
+fn data_len(solitaire: &Solitaire, game: &Game) -> usize {
+    solitaire.harness.account(&game.address()).unwrap().data.len()
+}
+
+#[test]
+fn the_move_that_would_overflow_the_account_is_rejected() {
+    let mut solitaire = Solitaire::new();
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    assert_eq!(data_len(&solitaire, &game), GameAccount::space_for(INITIAL_MOVE_LOG_CAPACITY));
+
+    // The last move that fits, then the first that doesn't
+    fill_log(&mut solitaire, &game, INITIAL_MOVE_LOG_CAPACITY - 1);
+    solitaire.draw(&game).unwrap();
+    assert_eq!(solitaire.game_state(&game).move_log.len(), INITIAL_MOVE_LOG_CAPACITY);
+    assert_error(solitaire.draw(&game), SolitaireError::GameAccountFull);
+
+    grow(&mut solitaire, &game).unwrap();
+    solitaire.draw(&game).unwrap();
+    assert_eq!(solitaire.game_state(&game).move_log.len(), INITIAL_MOVE_LOG_CAPACITY + 1);
+}
+
+#[test]
+fn growing_is_paid_for_by_the_player() {
+    let mut solitaire = Solitaire::new();
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    let (wallet, account) = (
+        solitaire.harness.lamports(&player.wallet),
+        solitaire.harness.lamports(&game.address()),
+    );
+
+    grow(&mut solitaire, &game).unwrap();
+
+    let size = GameAccount::space_for(INITIAL_MOVE_LOG_CAPACITY + MOVE_LOG_GROWTH);
+    let grown = solitaire.harness.event::<GameAccountGrown>();
+    assert_eq!(grown.size as usize, size);
+    assert_eq!(grown.move_log_capacity as usize, INITIAL_MOVE_LOG_CAPACITY + MOVE_LOG_GROWTH);
+    assert_eq!(data_len(&solitaire, &game), size);
+
+    let rent = Rent::default();
+    assert_eq!(solitaire.harness.lamports(&game.address()), rent.minimum_balance(size));
+    let paid = rent.minimum_balance(size) - account;
+    assert_eq!(solitaire.harness.lamports(&player.wallet), wallet - paid);
+}
+
+#[test]
+fn growth_stops_at_the_move_history_cap() {
+    let mut solitaire = Solitaire::new();
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+
+    let steps = (MAX_MOVE_LOG - INITIAL_MOVE_LOG_CAPACITY) / MOVE_LOG_GROWTH;
+    for _ in 0..steps {
+        grow(&mut solitaire, &game).unwrap();
+    }
+    assert_eq!(data_len(&solitaire, &game), GameAccount::MAX_SPACE);
+    // Growing again is a no-op rather than room for more history
+    grow(&mut solitaire, &game).unwrap();
+    assert_eq!(data_len(&solitaire, &game), GameAccount::MAX_SPACE);
+    assert_eq!(solitaire.harness.event::<GameAccountGrown>().move_log_capacity as usize, MAX_MOVE_LOG);
+
+    fill_log(&mut solitaire, &game, MAX_MOVE_LOG - 1);
+    solitaire.draw(&game).unwrap();
+    assert_error(solitaire.draw(&game), SolitaireError::MoveLogFull);
+    assert_error(grow(&mut solitaire, &game), SolitaireError::MoveLogFull);
+}
+
+#[test]
+fn closing_a_grown_game_refunds_all_its_rent() {
+    let mut solitaire = Solitaire::new();
+    let mint = solitaire.mint;
+    solitaire.treasury(&mint, 0);
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    grow(&mut solitaire, &game).unwrap();
+    assert_error(close(&mut solitaire, &game), SolitaireError::GameNotClosable);
+
+    solitaire.complete(&game).unwrap();
+    solitaire.harness.warp(1);
+    let rent = solitaire.harness.lamports(&game.address());
+    let escrow_rent = solitaire.harness.lamports(&game.escrow());
+    let wallet = solitaire.harness.lamports(&player.wallet);
+    close(&mut solitaire, &game).unwrap();
+
+    assert_eq!(solitaire.harness.event::<GameClosed>().refunded_lamports, rent);
+    assert_eq!(solitaire.harness.lamports(&player.wallet), wallet + rent + escrow_rent);
+    assert!(solitaire.harness.account(&game.address()).is_none());
+}
+
+#[test]
+fn closing_moves_escrow_leftovers_to_the_treasury() {
+    let mut solitaire = Solitaire::new();
+    let mint = solitaire.mint;
+    let treasury = solitaire.treasury(&mint, 0);
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    // A loss pays back half the stake; the other half stays in escrow
+    solitaire.complete(&game).unwrap();
+    let stake = GameParams::default().stake;
+    assert_eq!(solitaire.balance(&game.escrow()), stake / 2);
+
+    solitaire.harness.warp(1);
+    close(&mut solitaire, &game).unwrap();
+    assert_eq!(solitaire.harness.event::<GameClosed>().escrow_swept, stake / 2);
+    assert_eq!(solitaire.balance(&treasury), stake / 2);
+    assert!(solitaire.harness.account(&game.escrow()).is_none());
+}
+
+#[test]
+fn a_swept_game_closes_without_its_escrow() {
+    let mut solitaire = Solitaire::new();
+    let mint = solitaire.mint;
+    solitaire.treasury(&mint, 0);
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    solitaire.harness.warp(STALE_ESCROW_SECONDS);
+    solitaire.sweep(&game).unwrap();
+
+    close(&mut solitaire, &game).unwrap();
+    assert_eq!(solitaire.harness.event::<GameClosed>().escrow_swept, 0);
+    assert!(solitaire.harness.account(&game.address()).is_none());
+}
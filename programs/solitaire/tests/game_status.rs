@@ -0,0 +1,171 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::*;
+use solitaire::{
+    instruction, GameCancelled, GameStatus, SolitaireError, CANCEL_PENALTY_BPS,
+    STALE_ESCROW_SECONDS,
+};
+
+/// `GameStatus` as it was before Expired and Cancelled were added.
+#[derive(AnchorSerialize)]
+enum OldGameStatus {
+    Active,
+    Completed,
+    Abandoned,
+}
+
+const ALL: [GameStatus; 6] = [
+    GameStatus::Active,
+    GameStatus::Completed,
+    GameStatus::Abandoned,
+    GameStatus::Expired,
+    GameStatus::Cancelled,
+    GameStatus::Swept,
+];
+
+#[test]
+fn the_old_three_variant_layout_still_deserializes() {
+    let old = [
+        (OldGameStatus::Active, GameStatus::Active),
+        (OldGameStatus::Completed, GameStatus::Completed),
+        (OldGameStatus::Abandoned, GameStatus::Abandoned),
+    ];
+    for (old, new) in &old {
+        let bytes = old.try_to_vec().unwrap();
+        assert_eq!(GameStatus::try_from_slice(&bytes).unwrap(), *new);
+    }
+
+    // An account whose status byte was written by the old enum
+    let mut solitaire = Solitaire::new();
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    let key = game.address();
+    let offset = 8 + 32 + 4 + game.id.len() + 8 + 32;
+    let mut account = solitaire.harness.account(&key).unwrap().clone();
+    account.data[offset..offset + 1]
+        .copy_from_slice(&OldGameStatus::Abandoned.try_to_vec().unwrap());
+    solitaire.harness.set_account(key, account);
+    assert_eq!(solitaire.game_state(&game).status, GameStatus::Abandoned);
+}
+
+#[test]
+fn the_transition_graph_matches_the_lifecycle() {
+    use GameStatus::*;
+    let legal = [
+        (Active, Completed),
+        (Active, Abandoned),
+        (Active, Expired),
+        (Active, Cancelled),
+        (Active, Swept),
+        (Completed, Swept),
+        (Abandoned, Swept),
+        (Expired, Swept),
+    ];
+    for from in &ALL {
+        for to in &ALL {
+            assert_eq!(
+                from.can_transition_to(to),
+                legal.contains(&(from.clone(), to.clone())),
+                "{from:?} -> {to:?}"
+            );
+        }
+    }
+    for status in &ALL {
+        assert_eq!(status.is_terminal(), *status != Active);
+    }
+}
+
+#[test]
+fn a_cancelled_game_cannot_be_swept() {
+    let mut solitaire = Solitaire::new();
+    let mint = solitaire.mint;
+    solitaire.treasury(&mint, 0);
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+
+    solitaire.leave(&game, instruction::CancelGame {}).unwrap();
+    assert_eq!(solitaire.game_state(&game).status, GameStatus::Cancelled);
+
+    solitaire.harness.warp(STALE_ESCROW_SECONDS + 1);
+    assert_error(solitaire.sweep(&game), SolitaireError::InvalidStatusTransition);
+    assert_eq!(solitaire.game_state(&game).status, GameStatus::Cancelled);
+}
+
+#[test]
+fn cancelling_a_dealt_game_forfeits_the_penalty() {
+    let mut solitaire = Solitaire::new();
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    let stake = GameParams::default().stake;
+    let penalty = stake * CANCEL_PENALTY_BPS as u64 / 10_000;
+
+    solitaire.leave(&game, instruction::CancelGame {}).unwrap();
+    let cancelled = solitaire.harness.event::<GameCancelled>();
+    assert_eq!(cancelled.refunded, stake - penalty);
+    assert_eq!(cancelled.penalty, penalty);
+    assert_eq!(solitaire.balance(&player.tokens), PLAYER_TOKENS - penalty);
+    assert_eq!(solitaire.balance(&game.escrow()), penalty);
+}
+
+#[test]
+fn a_winning_move_leaves_the_game_active_until_it_is_completed() {
+    let mut solitaire = Solitaire::new();
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    let stake = GameParams::default().stake;
+    solitaire.edit_game(&game, one_move_from_winning);
+    solitaire.fund_win(&game, stake);
+
+    solitaire.make_move(&game, "tableau-0", "foundation-3", 0).unwrap();
+    let account = solitaire.game_state(&game);
+    assert!(account.is_won);
+    assert_eq!(account.status, GameStatus::Active);
+    assert_error(
+        solitaire.make_move(&game, "foundation-3", "tableau-0", 12),
+        SolitaireError::GameAlreadyWon,
+    );
+
+    solitaire.complete(&game).unwrap();
+    assert_eq!(solitaire.game_state(&game).status, GameStatus::Completed);
+    assert_eq!(solitaire.balance(&player.tokens), PLAYER_TOKENS + stake);
+}
+
+#[test]
+fn a_stale_active_game_is_swept() {
+    let mut solitaire = Solitaire::new();
+    let mint = solitaire.mint;
+    let treasury = solitaire.treasury(&mint, 0);
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+
+    solitaire.harness.warp(STALE_ESCROW_SECONDS - 1);
+    assert_error(solitaire.sweep(&game), SolitaireError::EscrowNotStale);
+    solitaire.harness.warp(2);
+    solitaire.sweep(&game).unwrap();
+    assert_eq!(solitaire.game_state(&game).status, GameStatus::Swept);
+    assert_eq!(solitaire.balance(&treasury), GameParams::default().stake);
+    // Nothing leaves Swept
+    assert!(solitaire.sweep(&game).is_err());
+}
+
+#[test]
+fn a_stale_free_game_is_swept_to_the_promo_vault() {
+    let mut solitaire = Solitaire::new();
+    let mint = solitaire.mint;
+    let treasury = solitaire.treasury(&mint, 0);
+    let stake = GameParams::default().stake;
+    let promo_vault = solitaire.promo_vault(&mint, stake);
+    solitaire
+        .update_config(instruction::UpdateFreeGameStake { free_game_stake: stake })
+        .unwrap();
+    let player = solitaire.player();
+    let game = solitaire.start_free(&player);
+    assert_eq!(solitaire.balance(&promo_vault), 0);
+
+    solitaire.harness.warp(STALE_ESCROW_SECONDS);
+    solitaire.sweep(&game).unwrap();
+    assert_eq!(solitaire.game_state(&game).status, GameStatus::Swept);
+    assert_eq!(solitaire.balance(&promo_vault), stake);
+    assert_eq!(solitaire.balance(&treasury), 0);
+}
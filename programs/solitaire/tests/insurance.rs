@@ -0,0 +1,189 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solitaire::{
+    accounts, instruction, GameCompleted, GameMode, InsuranceLedger, ModeConfig, MoveClockExpired, SolitaireError,
+};
+
+const STAKE: u64 = 1_000_000;
+/// A 5% premium.
+const PREMIUM: u64 = STAKE / 20;
+/// What one policy can cost the vault: the unpaid half of a lost stake.
+const LIABILITY: u64 = STAKE / 2;
+
+fn insured() -> GameParams {
+    GameParams {
+        insured: true,
+        ..GameParams::default()
+    }
+}
+
+/// Insurance at a 5% premium, backed by a vault holding `vault`.
+fn with_insurance(vault: u64) -> Solitaire {
+    let mut solitaire = Solitaire::new();
+    solitaire
+        .update_config(instruction::UpdateInsurancePremium { insurance_premium_bps: 500 })
+        .unwrap();
+    let mint = solitaire.mint;
+    solitaire.treasury(&mint, 0);
+    solitaire.insurance_vault(&mint, vault);
+    solitaire
+}
+
+fn outstanding(solitaire: &Solitaire) -> u64 {
+    solitaire
+        .harness
+        .get::<InsuranceLedger>(&insurance_ledger(&solitaire.mint))
+        .outstanding
+}
+
+fn withdraw_vault(solitaire: &mut Solitaire, amount: u64) -> TxResult {
+    let (mint, admin) = (solitaire.mint, solitaire.admin);
+    let destination = solitaire.harness.create_token_account(&mint, &admin);
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::WithdrawInsuranceVault {
+            config: solitaire.config(),
+            insurance_vault: insurance_vault(&mint),
+            insurance_ledger: insurance_ledger(&mint),
+            insurance_authority: insurance_authority(),
+            admin_token_account: destination,
+            admin,
+            token_program: TOKEN,
+        }
+        .to_account_metas(None),
+        data: instruction::WithdrawInsuranceVault { amount }.data(),
+    };
+    solitaire.admin(ix)
+}
+
+fn claim_timeout(solitaire: &mut Solitaire, game: &Game, player_token_account: Option<Pubkey>) -> TxResult {
+    let caller = solitaire.harness.funded_wallet();
+    let mint = game.player.mint;
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::ClaimTimeout {
+            game: game.address(),
+            escrow_token_account: game.escrow(),
+            escrow_authority: game.escrow_authority(),
+            treasury_token_account: treasury(&mint),
+            promo_vault: None,
+            player_stats: Some(player_stats(&game.player.wallet)),
+            insurance_ledger: Some(insurance_ledger(&mint)),
+            insurance_vault: Some(insurance_vault(&mint)),
+            insurance_authority: Some(insurance_authority()),
+            player_token_account,
+            caller,
+            token_program: TOKEN,
+        }
+        .to_account_metas(None),
+        data: instruction::ClaimTimeout {}.data(),
+    };
+    solitaire.harness.process(&[ix], &[caller])
+}
+
+#[test]
+fn an_insured_loss_is_topped_up_to_the_full_stake() {
+    let mut solitaire = with_insurance(STAKE);
+    let player = solitaire.player();
+    let game = solitaire.start(&player, insured());
+    let vault = insurance_vault(&player.mint);
+    assert_eq!(solitaire.game_state(&game).insurance_premium, PREMIUM);
+    assert_eq!(solitaire.balance(&vault), STAKE + PREMIUM);
+    assert_eq!(outstanding(&solitaire), LIABILITY);
+
+    solitaire.complete(&game).unwrap();
+    assert_eq!(solitaire.harness.event::<GameCompleted>().insurance_payout, LIABILITY);
+    assert_eq!(solitaire.balance(&player.tokens), PLAYER_TOKENS - PREMIUM);
+    assert_eq!(solitaire.balance(&vault), STAKE + PREMIUM - LIABILITY);
+    assert_eq!(outstanding(&solitaire), 0);
+}
+
+#[test]
+fn an_insured_surrender_gets_the_penalty_back() {
+    let mut solitaire = with_insurance(STAKE);
+    let player = solitaire.player();
+    let game = solitaire.start(&player, insured());
+
+    solitaire.harness.warp(DAY);
+    solitaire.leave(&game, instruction::WithdrawStake {}).unwrap();
+    assert_eq!(solitaire.balance(&player.tokens), PLAYER_TOKENS - PREMIUM);
+    assert_eq!(
+        solitaire.balance(&insurance_vault(&player.mint)),
+        STAKE + PREMIUM - STAKE / 10
+    );
+    assert_eq!(outstanding(&solitaire), 0);
+}
+
+#[test]
+fn policies_are_only_sold_while_the_vault_covers_them() {
+    // Room for exactly two open policies
+    let mut solitaire = with_insurance(2 * LIABILITY);
+    let player = solitaire.player();
+    let first = solitaire.start(&player, insured());
+    solitaire.start(&player, insured());
+    assert_eq!(outstanding(&solitaire), 2 * LIABILITY);
+
+    let game = solitaire.game(&player);
+    let ix = solitaire.start_ix(&game, insured());
+    assert_error(
+        solitaire.harness.process(&[ix], &[player.wallet]),
+        SolitaireError::InsuranceVaultInsolvent,
+    );
+
+    // The premiums are surplus, but nothing reserved can be withdrawn
+    assert_error(
+        withdraw_vault(&mut solitaire, 2 * PREMIUM + 1),
+        SolitaireError::InsuranceVaultInsolvent,
+    );
+    withdraw_vault(&mut solitaire, 2 * PREMIUM).unwrap();
+
+    // Closing a policy without a claim frees its reserve
+    solitaire.leave(&first, instruction::CancelGame {}).unwrap();
+    assert_eq!(outstanding(&solitaire), LIABILITY);
+    solitaire.start(&player, insured());
+    assert_eq!(outstanding(&solitaire), 2 * LIABILITY);
+}
+
+#[test]
+fn an_insured_timeout_is_covered_like_a_loss() {
+    let mut solitaire = with_insurance(STAKE);
+    solitaire
+        .update_config(instruction::UpdateModeConfig {
+            mode: GameMode::Blitz,
+            mode_config: ModeConfig {
+                move_clock_seconds: 30,
+                ..ModeConfig::default()
+            },
+        })
+        .unwrap();
+    let player = solitaire.player();
+    let game = solitaire.start(
+        &player,
+        GameParams {
+            mode: GameMode::Blitz,
+            ..insured()
+        },
+    );
+
+    solitaire.harness.warp(31);
+    assert_error(
+        claim_timeout(&mut solitaire, &game, None),
+        SolitaireError::PlayerTokenAccountRequired,
+    );
+    claim_timeout(&mut solitaire, &game, Some(player.tokens)).unwrap();
+    let expired = solitaire.harness.event::<MoveClockExpired>();
+    assert_eq!((expired.forfeited, expired.insurance_payout), (STAKE, LIABILITY));
+    assert_eq!(
+        solitaire.balance(&player.tokens),
+        PLAYER_TOKENS - STAKE - PREMIUM + LIABILITY
+    );
+    assert_eq!(
+        solitaire.balance(&insurance_vault(&player.mint)),
+        STAKE + PREMIUM - LIABILITY
+    );
+    assert_eq!(outstanding(&solitaire), 0);
+}
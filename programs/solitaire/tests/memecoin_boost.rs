@@ -0,0 +1,127 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use solitaire::{instruction, GameCompleted, GameStarted, MemecoinBoostConfig, SolitaireError};
+
+const BOOST_BPS: u16 = 2_500;
+const HOUSE: u64 = 100_000_000;
+
+/// A boost configured for a second, memecoin mint, with both treasuries funded.
+fn boosted() -> (Solitaire, Pubkey) {
+    let mut solitaire = Solitaire::new();
+    let admin = solitaire.admin;
+    let memecoin = solitaire.harness.create_mint(&TOKEN, &admin, 6);
+    set_boost(&mut solitaire, memecoin, BOOST_BPS);
+    let mint = solitaire.mint;
+    solitaire.treasury(&mint, HOUSE);
+    solitaire.treasury(&memecoin, HOUSE);
+    (solitaire, memecoin)
+}
+
+fn set_boost(solitaire: &mut Solitaire, mint: Pubkey, boost_bps: u16) {
+    solitaire
+        .update_config(instruction::UpdateMemecoinBoostConfig {
+            memecoin_boost: MemecoinBoostConfig { mint, boost_bps },
+        })
+        .unwrap();
+}
+
+/// Starts a game for `player` without playing it.
+fn start(solitaire: &mut Solitaire, player: &Player) -> Game {
+    let game = solitaire.start(player, GameParams::default());
+    assert_eq!(
+        solitaire.harness.event::<GameStarted>().memecoin_boost_bps,
+        solitaire.game_state(&game).memecoin_boost_bps
+    );
+    game
+}
+
+/// Wins `game` and returns what the player's balance gained over the whole
+/// game, stake included.
+fn win(solitaire: &mut Solitaire, game: &Game) -> (u64, GameCompleted) {
+    let stake = GameParams::default().stake;
+    let before = solitaire.balance(&game.player.tokens) + stake;
+    solitaire.edit_game(game, one_move_from_winning);
+    solitaire.fund_win(game, stake);
+    solitaire.make_move(game, "tableau-0", "foundation-3", 0).unwrap();
+    solitaire.complete(game).unwrap();
+    let gained = solitaire.balance(&game.player.tokens) + stake - before;
+    (gained, solitaire.harness.event::<GameCompleted>())
+}
+
+#[test]
+fn the_memecoin_game_pays_the_boost_on_top() {
+    let (mut solitaire, memecoin) = boosted();
+    let stake = GameParams::default().stake;
+
+    let player = solitaire.player();
+    let generic = start(&mut solitaire, &player);
+    assert_eq!(solitaire.game_state(&generic).memecoin_boost_bps, 0);
+    let (generic_gain, completed) = win(&mut solitaire, &generic);
+    assert_eq!(completed.memecoin_boost, 0);
+
+    let player = solitaire.player_for(&memecoin);
+    let game = start(&mut solitaire, &player);
+    assert_eq!(solitaire.game_state(&game).memecoin_boost_bps, BOOST_BPS);
+    let (gain, completed) = win(&mut solitaire, &game);
+
+    let boost = 2 * stake * BOOST_BPS as u64 / 10_000;
+    assert_eq!(completed.memecoin_boost, boost);
+    assert_eq!(gain - generic_gain, boost);
+    assert_eq!(solitaire.balance(&treasury(&memecoin)), HOUSE - boost);
+    assert_eq!(solitaire.balance(&treasury(&solitaire.mint)), HOUSE);
+}
+
+#[test]
+fn a_zero_boost_disables_it() {
+    let (mut solitaire, memecoin) = boosted();
+    set_boost(&mut solitaire, memecoin, 0);
+
+    let player = solitaire.player_for(&memecoin);
+    let game = start(&mut solitaire, &player);
+    assert_eq!(solitaire.game_state(&game).memecoin_boost_bps, 0);
+    let (_, completed) = win(&mut solitaire, &game);
+    assert_eq!(completed.memecoin_boost, 0);
+    assert_eq!(solitaire.balance(&treasury(&memecoin)), HOUSE);
+}
+
+#[test]
+fn the_boost_is_fixed_when_the_game_starts() {
+    let (mut solitaire, memecoin) = boosted();
+    let player = solitaire.player_for(&memecoin);
+    let game = start(&mut solitaire, &player);
+    set_boost(&mut solitaire, memecoin, 0);
+
+    let (_, completed) = win(&mut solitaire, &game);
+    assert_eq!(completed.memecoin_boost, 2 * GameParams::default().stake * BOOST_BPS as u64 / 10_000);
+}
+
+#[test]
+fn the_boost_is_capped_by_the_treasury() {
+    let mut solitaire = Solitaire::new();
+    let admin = solitaire.admin;
+    let memecoin = solitaire.harness.create_mint(&TOKEN, &admin, 6);
+    set_boost(&mut solitaire, memecoin, BOOST_BPS);
+    solitaire.treasury(&memecoin, 1_000);
+
+    let player = solitaire.player_for(&memecoin);
+    let game = start(&mut solitaire, &player);
+    let (_, completed) = win(&mut solitaire, &game);
+    assert_eq!(completed.memecoin_boost, 1_000);
+    assert_eq!(solitaire.balance(&treasury(&memecoin)), 0);
+}
+
+#[test]
+fn the_boost_is_at_most_the_whole_payout() {
+    let mut solitaire = Solitaire::new();
+    assert_error(
+        solitaire.update_config(instruction::UpdateMemecoinBoostConfig {
+            memecoin_boost: MemecoinBoostConfig {
+                mint: Pubkey::new_unique(),
+                boost_bps: 10_001,
+            },
+        }),
+        SolitaireError::InvalidConfig,
+    );
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::*;
+use solitaire::{
+    accounts, instruction, GameMode, GameStatus, ModeConfig, MoveClockExpired, ScoringRules, SolitaireError,
+};
+
+const CLOCK: i64 = 30;
+
+/// A Blitz game on a 30-second shot clock, with a treasury to forfeit to.
+fn blitz() -> (Solitaire, Game) {
+    let mut solitaire = Solitaire::new();
+    solitaire
+        .update_config(instruction::UpdateModeConfig {
+            mode: GameMode::Blitz,
+            mode_config: ModeConfig {
+                min_move_interval_slots: 0,
+                scoring: ScoringRules::default(),
+                move_clock_seconds: CLOCK,
+            },
+        })
+        .unwrap();
+    let mint = solitaire.mint;
+    solitaire.treasury(&mint, 0);
+    let player = solitaire.player();
+    let game = solitaire.start(
+        &player,
+        GameParams {
+            mode: GameMode::Blitz,
+            ..GameParams::default()
+        },
+    );
+    (solitaire, game)
+}
+
+fn claim_timeout(solitaire: &mut Solitaire, game: &Game) -> TxResult {
+    let caller = solitaire.harness.funded_wallet();
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::ClaimTimeout {
+            game: game.address(),
+            escrow_token_account: game.escrow(),
+            escrow_authority: game.escrow_authority(),
+            treasury_token_account: treasury(&game.player.mint),
+            promo_vault: None,
+            player_stats: Some(player_stats(&game.player.wallet)),
+            insurance_ledger: None,
+            insurance_vault: None,
+            insurance_authority: None,
+            player_token_account: None,
+            caller,
+            token_program: TOKEN,
+        }
+        .to_account_metas(None),
+        data: instruction::ClaimTimeout {}.data(),
+    };
+    solitaire.harness.process(&[ix], &[caller])
+}
+
+#[test]
+fn a_move_on_the_deadline_second_is_in_time() {
+    let (mut solitaire, game) = blitz();
+    let started = solitaire.harness.now();
+    assert_eq!(solitaire.game_state(&game).move_deadline, started + CLOCK);
+
+    solitaire.harness.set_time(started + CLOCK);
+    solitaire.draw(&game).unwrap();
+    // Each move restarts the clock from when it landed
+    let deadline = started + 2 * CLOCK;
+    assert_eq!(solitaire.game_state(&game).move_deadline, deadline);
+
+    solitaire.harness.set_time(deadline + 1);
+    assert_error(solitaire.draw(&game), SolitaireError::MoveClockExpired);
+}
+
+#[test]
+fn a_timeout_can_only_be_claimed_once_the_clock_runs_out() {
+    let (mut solitaire, game) = blitz();
+    let deadline = solitaire.game_state(&game).move_deadline;
+
+    solitaire.harness.set_time(deadline);
+    assert_error(claim_timeout(&mut solitaire, &game), SolitaireError::MoveClockRunning);
+
+    solitaire.harness.set_time(deadline + 1);
+    claim_timeout(&mut solitaire, &game).unwrap();
+
+    let stake = GameParams::default().stake;
+    let expired = solitaire.harness.event::<MoveClockExpired>();
+    assert_eq!((expired.move_deadline, expired.forfeited), (deadline, stake));
+    assert_eq!(solitaire.game_state(&game).status, GameStatus::Expired);
+    assert_eq!(solitaire.balance(&treasury(&game.player.mint)), stake);
+    assert_eq!(solitaire.balance(&game.escrow()), 0);
+    assert_error(solitaire.draw(&game), SolitaireError::GameNotActive);
+}
+
+#[test]
+fn a_lapsed_clock_can_only_be_forfeited() {
+    let (mut solitaire, game) = blitz();
+    let deadline = solitaire.game_state(&game).move_deadline;
+
+    solitaire.harness.set_time(deadline + 1);
+    assert_error(solitaire.complete(&game), SolitaireError::MoveClockExpired);
+    assert_error(
+        solitaire.leave(&game, instruction::CancelGame {}),
+        SolitaireError::MoveClockExpired,
+    );
+    solitaire.harness.warp(DAY);
+    assert_error(
+        solitaire.leave(&game, instruction::WithdrawStake {}),
+        SolitaireError::MoveClockExpired,
+    );
+
+    claim_timeout(&mut solitaire, &game).unwrap();
+    assert_eq!(solitaire.game_state(&game).status, GameStatus::Expired);
+}
+
+#[test]
+fn a_win_can_be_settled_after_the_clock_runs_out() {
+    let (mut solitaire, game) = blitz();
+    let stake = GameParams::default().stake;
+    solitaire.edit_game(&game, one_move_from_winning);
+    solitaire.fund_win(&game, stake);
+    solitaire.make_move(&game, "tableau-0", "foundation-3", 0).unwrap();
+
+    let deadline = solitaire.game_state(&game).move_deadline;
+    solitaire.harness.set_time(deadline + 1);
+    solitaire.complete(&game).unwrap();
+    assert!(solitaire.game_state(&game).is_won);
+    assert_eq!(solitaire.balance(&game.player.tokens), PLAYER_TOKENS + stake);
+}
+
+#[test]
+fn modes_without_a_clock_never_time_out() {
+    let mut solitaire = Solitaire::new();
+    let mint = solitaire.mint;
+    solitaire.treasury(&mint, 0);
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    assert_eq!(solitaire.game_state(&game).move_deadline, 0);
+
+    solitaire.harness.warp(30 * DAY);
+    assert_error(claim_timeout(&mut solitaire, &game), SolitaireError::MoveClockRunning);
+    solitaire.draw(&game).unwrap();
+}
+
+#[test]
+fn a_negative_clock_is_rejected() {
+    let mut solitaire = Solitaire::new();
+    assert_error(
+        solitaire.update_config(instruction::UpdateModeConfig {
+            mode: GameMode::Race,
+            mode_config: ModeConfig {
+                move_clock_seconds: -1,
+                ..ModeConfig::default()
+            },
+        }),
+        SolitaireError::InvalidConfig,
+    );
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use anchor_lang::AnchorSerialize;
+use common::*;
+use solitaire::{CardData, GameVariant, MoveMade, ScoringRules};
+
+fn card(suit: u8, rank: u8, face_up: bool) -> CardData {
+    CardData { suit, rank, face_up }
+}
+
+#[test]
+fn a_draw_is_described_without_reading_the_account() {
+    let mut solitaire = Solitaire::new();
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    let stock = solitaire.pile(&game, "stock");
+    let stock_len = solitaire.game_state(&game).game_state.piles[stock].cards.len();
+
+    solitaire.draw(&game).unwrap();
+
+    let made = solitaire.harness.event::<MoveMade>();
+    let account = solitaire.game_state(&game);
+    assert_eq!((made.game_id, made.player), (game.id.clone(), player.wallet));
+    assert_eq!((made.from_pile.as_str(), made.to_pile.as_str()), ("stock", "waste"));
+    assert_eq!(made.card_index as usize, stock_len - 1);
+    assert_eq!((made.cards_moved, made.flipped, made.score_delta), (1, false, 0));
+    assert_eq!((made.from_pile_size as usize, made.to_pile_size), (stock_len - 1, 1));
+    assert_eq!(made.moves, 1);
+    assert_eq!(made.state_hash, account.game_state.state_hash());
+    assert_eq!(made.timestamp, solitaire.harness.now());
+}
+
+#[test]
+fn a_run_move_reports_the_cards_moved_and_the_flip() {
+    let mut solitaire = Solitaire::new();
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    solitaire.edit_game(&game, |account| {
+        for pile in account.game_state.piles.iter_mut() {
+            pile.cards = match pile.id.as_str() {
+                "tableau-0" => vec![card(3, 5, false), card(0, 13, true), card(3, 12, true)],
+                _ => vec![],
+            };
+        }
+    });
+    let hash_before = solitaire.game_state(&game).game_state.state_hash();
+
+    solitaire.make_move(&game, "tableau-0", "tableau-1", 1).unwrap();
+
+    let made = solitaire.harness.event::<MoveMade>();
+    assert_eq!((made.cards_moved, made.flipped), (2, true));
+    assert_eq!(made.score_delta, ScoringRules::default().card_flip as i32);
+    assert_eq!((made.from_pile_size, made.to_pile_size), (1, 2));
+    assert_ne!(made.state_hash, hash_before);
+    assert_eq!(made.state_hash, solitaire.game_state(&game).game_state.state_hash());
+}
+
+#[test]
+fn the_event_stays_small_at_its_longest() {
+    let mut solitaire = Solitaire::new();
+    let player = solitaire.player();
+    let game = Game {
+        id: "x".repeat(32),
+        player,
+    };
+    let ix = solitaire.start_ix(
+        &game,
+        GameParams {
+            variant: GameVariant::FreeCell,
+            ..GameParams::default()
+        },
+    );
+    solitaire.harness.process(&[ix], &[player.wallet]).unwrap();
+    let tableau_len = solitaire.game_state(&game).game_state.piles[solitaire.pile(&game, "tableau-0")]
+        .cards
+        .len();
+
+    solitaire
+        .make_move(&game, "tableau-0", "freecell-3", tableau_len as u8 - 1)
+        .unwrap();
+
+    // Discriminator plus borsh body, as it lands in the transaction logs
+    let made = solitaire.harness.event::<MoveMade>();
+    assert_eq!(made.to_pile, "freecell-3");
+    assert!(8 + made.try_to_vec().unwrap().len() <= 160);
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use common::*;
+use solitaire::{instruction, FeeConfig, ReferralPaid, ReferralRegistered, SolitaireError};
+
+const STAKE: u64 = 1_000_000;
+/// A 10% protocol fee, half of which goes to the referrer.
+const FEES: FeeConfig = FeeConfig {
+    protocol_fee_bps: 1_000,
+    referral_share_bps: 5_000,
+};
+/// On a loss the payout is half the stake.
+const PROTOCOL_FEE: u64 = STAKE / 2 / 10;
+const REFERRAL_FEE: u64 = PROTOCOL_FEE / 2;
+
+fn with_fees() -> Solitaire {
+    let mut solitaire = Solitaire::new();
+    solitaire
+        .update_config(instruction::UpdateFeeConfig { fees: FEES })
+        .unwrap();
+    let mint = solitaire.mint;
+    solitaire.treasury(&mint, 0);
+    solitaire
+}
+
+fn referred_by(referrer: &Player) -> GameParams {
+    GameParams {
+        referrer: Some(referrer.wallet),
+        ..GameParams::default()
+    }
+}
+
+/// Completes `game` as a loss, paying the referral share to `fee_account`.
+fn complete_paying(solitaire: &mut Solitaire, game: &Game, fee_account: Option<Pubkey>) -> TxResult {
+    let mut accounts = solitaire.complete_accounts(game);
+    accounts.referrer_token_account = fee_account;
+    solitaire.complete_with(game, accounts)
+}
+
+#[test]
+fn the_referrer_shares_the_protocol_fee() {
+    let mut solitaire = with_fees();
+    let referrer = solitaire.player();
+    let player = solitaire.player();
+    let game = solitaire.start(&player, referred_by(&referrer));
+    let registered = solitaire.harness.event::<ReferralRegistered>();
+    assert_eq!((registered.player, registered.referrer), (player.wallet, referrer.wallet));
+
+    complete_paying(&mut solitaire, &game, Some(referrer.tokens)).unwrap();
+    assert_eq!(solitaire.harness.event::<ReferralPaid>().amount, REFERRAL_FEE);
+    assert_eq!(solitaire.balance(&referrer.tokens), PLAYER_TOKENS + REFERRAL_FEE);
+    assert_eq!(
+        solitaire.balance(&treasury(&player.mint)),
+        PROTOCOL_FEE - REFERRAL_FEE
+    );
+    assert_eq!(
+        solitaire.balance(&player.tokens),
+        PLAYER_TOKENS - STAKE + STAKE / 2 - PROTOCOL_FEE
+    );
+}
+
+#[test]
+fn players_cannot_refer_themselves() {
+    let mut solitaire = with_fees();
+    let player = solitaire.player();
+    let game = solitaire.game(&player);
+    let ix = solitaire.start_ix(&game, referred_by(&player));
+    assert_error(
+        solitaire.harness.process(&[ix], &[player.wallet]),
+        SolitaireError::SelfReferral,
+    );
+}
+
+#[test]
+fn the_first_referrer_is_kept() {
+    let mut solitaire = with_fees();
+    let first = solitaire.player();
+    let second = solitaire.player();
+    let player = solitaire.player();
+    solitaire.start(&player, referred_by(&first));
+
+    let game = solitaire.start(&player, referred_by(&second));
+    assert_eq!(solitaire.game_state(&game).referrer, Some(first.wallet));
+    assert_error(
+        complete_paying(&mut solitaire, &game, Some(second.tokens)),
+        SolitaireError::InvalidReferrer,
+    );
+    complete_paying(&mut solitaire, &game, Some(first.tokens)).unwrap();
+    assert_eq!(solitaire.balance(&first.tokens), PLAYER_TOKENS + REFERRAL_FEE);
+}
+
+#[test]
+fn the_fee_account_must_hold_the_reward_mint() {
+    let mut solitaire = with_fees();
+    let referrer = solitaire.player();
+    let player = solitaire.player();
+    let game = solitaire.start(&player, referred_by(&referrer));
+
+    let admin = solitaire.admin;
+    let other_mint = solitaire.harness.create_mint(&TOKEN, &admin, 6);
+    let wrong_mint = solitaire.harness.create_token_account(&other_mint, &referrer.wallet);
+    assert_error(
+        complete_paying(&mut solitaire, &game, Some(wrong_mint)),
+        ErrorCode::ConstraintTokenMint,
+    );
+}
+
+#[test]
+fn without_the_referrer_account_the_treasury_keeps_the_whole_fee() {
+    let mut solitaire = with_fees();
+    let referrer = solitaire.player();
+    let player = solitaire.player();
+    let game = solitaire.start(&player, referred_by(&referrer));
+
+    complete_paying(&mut solitaire, &game, None).unwrap();
+    assert!(solitaire.harness.events::<ReferralPaid>().is_empty());
+    assert_eq!(solitaire.balance(&treasury(&player.mint)), PROTOCOL_FEE);
+    assert_eq!(solitaire.balance(&referrer.tokens), PLAYER_TOKENS);
+}
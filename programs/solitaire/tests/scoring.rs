@@ -0,0 +1,213 @@
+mod common;
+
+use common::*;
+use solitaire::{
+    instruction, CardData, GameMode, GameState, ModeConfig, MoveMade, MoveRecord, PileType, ScoringRules,
+};
+
+/// Distinct values for every rule, so a misrouted rule shows up in the total.
+const RULES: ScoringRules = ScoringRules {
+    waste_to_tableau: 3,
+    to_foundation: 11,
+    card_flip: 7,
+    foundation_to_tableau_penalty: 13,
+    recycle_penalty: 17,
+    time_bonus_constant: 600_000,
+};
+
+fn card(suit: u8, rank: u8, face_up: bool) -> CardData {
+    CardData { suit, rank, face_up }
+}
+
+fn set_rules(solitaire: &mut Solitaire, mode: GameMode, scoring: ScoringRules) {
+    solitaire
+        .update_config(instruction::UpdateModeConfig {
+            mode,
+            mode_config: ModeConfig {
+                scoring,
+                ..ModeConfig::default()
+            },
+        })
+        .unwrap();
+}
+
+/// What the rules table says `record` is worth on `board`, worked out from
+/// the pile types alone rather than by playing the move.
+fn table_value(board: &GameState, record: &MoveRecord, rules: &ScoringRules) -> i64 {
+    let from = &board.piles[record.from as usize];
+    let to = &board.piles[record.to as usize];
+    let flips = from.pile_type == PileType::Tableau
+        && record.card_index > 0
+        && !from.cards[record.card_index as usize - 1].face_up;
+    let mut value = match (from.pile_type, to.pile_type) {
+        (_, PileType::Foundation) => rules.to_foundation as i64,
+        (PileType::Waste, PileType::Tableau) => rules.waste_to_tableau as i64,
+        (PileType::Waste, PileType::Stock) => -(rules.recycle_penalty as i64),
+        (PileType::Foundation, PileType::Tableau) => -(rules.foundation_to_tableau_penalty as i64),
+        _ => 0,
+    };
+    if flips {
+        value += rules.card_flip as i64;
+    }
+    value
+}
+
+/// Every move the board accepts right now.
+fn legal_moves(board: &GameState, rules: &ScoringRules) -> Vec<MoveRecord> {
+    let piles = board.piles.len() as u8;
+    let mut moves = Vec::new();
+    for from in 0..piles {
+        for to in 0..piles {
+            for card_index in 0..board.piles[from as usize].cards.len().max(1) as u8 {
+                let record = MoveRecord { from, to, card_index };
+                if board.clone().apply_move(&record, rules, true).is_ok() {
+                    moves.push(record);
+                }
+            }
+        }
+    }
+    moves
+}
+
+#[test]
+fn a_scripted_sequence_scores_exactly_by_the_rules_table() {
+    let mut solitaire = Solitaire::new();
+    set_rules(&mut solitaire, GameMode::Standard, RULES);
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    assert_eq!(solitaire.game_state(&game).scoring, RULES);
+
+    solitaire.edit_game(&game, |account| {
+        let board = &mut account.game_state;
+        for pile in board.piles.iter_mut() {
+            pile.cards = match pile.id.as_str() {
+                "waste" => vec![card(0, 1, true)],
+                "stock" => vec![card(2, 11, false), card(0, 12, false)],
+                "tableau-0" => vec![card(3, 9, false), card(1, 1, true)],
+                "tableau-1" => vec![card(3, 13, true)],
+                "tableau-2" => vec![card(3, 2, true)],
+                _ => vec![],
+            };
+        }
+    });
+
+    let script: [(&str, &str, u8, i64); 8] = [
+        // Ace of hearts from the waste
+        ("waste", "foundation-0", 0, RULES.to_foundation as i64),
+        // Ace of diamonds from a tableau, turning the nine of spades
+        ("tableau-0", "foundation-1", 1, (RULES.to_foundation + RULES.card_flip) as i64),
+        ("stock", "waste", 1, 0),
+        // Queen of hearts onto the king of spades
+        ("waste", "tableau-1", 0, RULES.waste_to_tableau as i64),
+        // Ace of hearts back down onto the two of spades
+        ("foundation-0", "tableau-2", 0, -(RULES.foundation_to_tableau_penalty as i64)),
+        ("stock", "waste", 0, 0),
+        ("waste", "stock", 0, -(RULES.recycle_penalty as i64)),
+        ("stock", "waste", 0, 0),
+    ];
+    let mut score = 0;
+    for (from, to, card_index, delta) in script {
+        solitaire.make_move(&game, from, to, card_index).unwrap();
+        score += delta;
+        let made = solitaire.harness.event::<MoveMade>();
+        assert_eq!(made.score_delta as i64, delta, "{from} -> {to}");
+        assert_eq!(solitaire.game_state(&game).score, score as u64, "{from} -> {to}");
+    }
+    assert_eq!(score, 2);
+
+    // A second recycle only takes the score down to zero
+    solitaire.make_move(&game, "waste", "stock", 0).unwrap();
+    assert_eq!(solitaire.harness.event::<MoveMade>().score_delta, -2);
+    assert_eq!(solitaire.game_state(&game).score, 0);
+}
+
+#[test]
+fn random_play_scores_by_the_rules_table() {
+    let mut solitaire = Solitaire::new();
+    set_rules(&mut solitaire, GameMode::Standard, RULES);
+    let player = solitaire.player();
+    // xorshift64; fixed so a failure replays
+    let mut rng = 0x2545_f491_4f6c_dd1d_u64;
+
+    for _ in 0..4 {
+        let game = solitaire.start(&player, GameParams::default());
+        let mut expected = 0i64;
+        for _ in 0..60 {
+            let board = solitaire.game_state(&game).game_state;
+            let moves = legal_moves(&board, &RULES);
+            if moves.is_empty() {
+                break;
+            }
+            rng ^= rng << 13;
+            rng ^= rng >> 7;
+            rng ^= rng << 17;
+            let record = moves[(rng % moves.len() as u64) as usize];
+            expected = (expected + table_value(&board, &record, &RULES)).max(0);
+
+            let (from, to) = (&board.piles[record.from as usize].id, &board.piles[record.to as usize].id);
+            solitaire.make_move(&game, from, to, record.card_index).unwrap();
+            assert_eq!(solitaire.game_state(&game).score as i64, expected, "{from} -> {to}");
+        }
+    }
+}
+
+#[test]
+fn winning_adds_the_time_bonus_from_the_snapshotted_rules() {
+    let mut solitaire = Solitaire::new();
+    set_rules(&mut solitaire, GameMode::Standard, RULES);
+    let player = solitaire.player();
+    let game = solitaire.start(&player, GameParams::default());
+    solitaire.edit_game(&game, one_move_from_winning);
+    // The win pays double the stake; the escrow only holds the one
+    let stake = GameParams::default().stake;
+    let admin = solitaire.admin;
+    solitaire.harness.mint_to(&player.mint, &game.escrow(), &admin, stake);
+
+    solitaire.harness.warp(100);
+    solitaire.make_move(&game, "tableau-0", "foundation-3", 0).unwrap();
+    // Settling later doesn't cost any of the bonus
+    solitaire.harness.warp(1_000);
+    solitaire.complete(&game).unwrap();
+
+    let account = solitaire.game_state(&game);
+    assert!(account.is_won);
+    assert_eq!(account.score, RULES.to_foundation as u64 + 600_000 / 100);
+}
+
+#[test]
+fn updating_a_mode_leaves_games_in_flight_alone() {
+    let mut solitaire = Solitaire::new();
+    set_rules(&mut solitaire, GameMode::Standard, RULES);
+    let player = solitaire.player();
+    let before = solitaire.start(&player, GameParams::default());
+
+    let doubled = ScoringRules {
+        to_foundation: 2 * RULES.to_foundation,
+        ..RULES
+    };
+    set_rules(&mut solitaire, GameMode::Standard, doubled);
+    let after = solitaire.start(&player, GameParams::default());
+    let vegas = solitaire.start(
+        &player,
+        GameParams {
+            mode: GameMode::Vegas,
+            ..GameParams::default()
+        },
+    );
+
+    assert_eq!(solitaire.game_state(&before).scoring, RULES);
+    assert_eq!(solitaire.game_state(&after).scoring, doubled);
+    assert_eq!(solitaire.game_state(&vegas).scoring, ScoringRules::default());
+
+    for game in [&before, &after] {
+        solitaire.edit_game(game, |account| {
+            for pile in account.game_state.piles.iter_mut() {
+                pile.cards.clear();
+            }
+            account.game_state.piles[0].cards.push(card(0, 1, true));
+        });
+        solitaire.make_move(game, "tableau-0", "foundation-0", 0).unwrap();
+    }
+    assert_eq!(solitaire.game_state(&before).score, RULES.to_foundation as u64);
+    assert_eq!(solitaire.game_state(&after).score, doubled.to_foundation as u64);
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use anchor_lang::prelude::{Pubkey, Rent};
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::AnchorSerialize;
+use common::*;
+use program_harness::AccountState;
+use solitaire::{
+    instruction, FeeConfig, GameCompleted, SolitaireError, StakeDiscountConfig, StakeDiscountTier, STAKE_VIEW_VERSION,
+};
+
+const FEE_BPS: u16 = 1_000;
+const SILVER: u64 = 1_000;
+const GOLD: u64 = 10_000;
+
+/// `StakeAccountView` as the gaming-token program lays it out.
+#[derive(AnchorSerialize, Clone, Copy)]
+struct View {
+    version: u8,
+    owner: Pubkey,
+    mint: Pubkey,
+    position_index: u16,
+    amount: u64,
+    unbonding_since: Option<i64>,
+    is_active: bool,
+}
+
+/// A 10% protocol fee, waived 20% from `SILVER` staked and 50% from `GOLD`,
+/// counting positions of `staking_program` in `staked_mint`.
+fn discounted(staking_program: Pubkey, staked_mint: Pubkey) -> Solitaire {
+    let mut solitaire = Solitaire::new();
+    solitaire
+        .update_config(instruction::UpdateFeeConfig {
+            fees: FeeConfig {
+                protocol_fee_bps: FEE_BPS,
+                referral_share_bps: 0,
+            },
+        })
+        .unwrap();
+    let tier = |min_staked, discount_bps| StakeDiscountTier {
+        min_staked,
+        discount_bps,
+    };
+    solitaire
+        .update_config(instruction::UpdateStakeDiscountConfig {
+            stake_discount: StakeDiscountConfig {
+                program: staking_program,
+                mint: staked_mint,
+                tiers: [tier(SILVER, 2_000), tier(GOLD, 5_000), tier(0, 0)],
+            },
+        })
+        .unwrap();
+    let mint = solitaire.mint;
+    solitaire.treasury(&mint, 0);
+    solitaire
+}
+
+/// Stores `view` behind the `StakeAccount` discriminator, owned by `program`,
+/// with trailing bytes standing in for the rest of the account.
+fn store(solitaire: &mut Solitaire, program: Pubkey, view: View) -> Pubkey {
+    let key = Pubkey::new_unique();
+    let mut data = hash(b"account:StakeAccount").to_bytes()[..8].to_vec();
+    data.extend(view.try_to_vec().unwrap());
+    data.extend([0; 64]);
+    solitaire.harness.set_account(
+        key,
+        AccountState {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: program,
+            executable: false,
+        },
+    );
+    key
+}
+
+/// Plays a game to a loss with `stake_account` supplied at settlement.
+fn settle(solitaire: &mut Solitaire, player: &Player, stake_account: Pubkey) -> GameCompleted {
+    let game = solitaire.start(player, GameParams::default());
+    let mut accounts = solitaire.complete_accounts(&game);
+    accounts.stake_account = Some(stake_account);
+    solitaire.complete_with(&game, accounts).unwrap();
+    solitaire.harness.event::<GameCompleted>()
+}
+
+/// The protocol fee on a lost game's half-stake payout after `discount_bps`.
+fn fee_after(discount_bps: u16) -> u64 {
+    let fee_bps = FEE_BPS as u64 - FEE_BPS as u64 * discount_bps as u64 / 10_000;
+    GameParams::default().stake / 2 * fee_bps / 10_000
+}
+
+#[test]
+fn the_discount_steps_at_each_tier_boundary() {
+    let (program, staked_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut solitaire = discounted(program, staked_mint);
+    let player = solitaire.player();
+    let view = |amount| View {
+        version: STAKE_VIEW_VERSION,
+        owner: player.wallet,
+        mint: staked_mint,
+        position_index: 0,
+        amount,
+        unbonding_since: None,
+        is_active: true,
+    };
+
+    for (staked, discount_bps) in [(SILVER - 1, 0), (SILVER, 2_000), (GOLD - 1, 2_000), (GOLD, 5_000)] {
+        let stake_account = store(&mut solitaire, program, view(staked));
+        let completed = settle(&mut solitaire, &player, stake_account);
+        assert_eq!(completed.fee_discount_bps, discount_bps, "{staked} staked");
+        assert_eq!(completed.protocol_fee, fee_after(discount_bps), "{staked} staked");
+    }
+}
+
+#[test]
+fn only_the_players_open_position_counts() {
+    let (program, staked_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut solitaire = discounted(program, staked_mint);
+    let player = solitaire.player();
+    let open = View {
+        version: STAKE_VIEW_VERSION,
+        owner: player.wallet,
+        mint: staked_mint,
+        position_index: 0,
+        amount: GOLD,
+        unbonding_since: None,
+        is_active: true,
+    };
+
+    let cases = [
+        ("closed", program, View { is_active: false, ..open }),
+        ("unbonding", program, View { unbonding_since: Some(1), ..open }),
+        ("someone else's", program, View { owner: Pubkey::new_unique(), ..open }),
+        ("another mint", program, View { mint: Pubkey::new_unique(), ..open }),
+        ("an older layout", program, View { version: STAKE_VIEW_VERSION - 1, ..open }),
+        ("another program", Pubkey::new_unique(), open),
+    ];
+    for (case, owner, view) in cases {
+        let stake_account = store(&mut solitaire, owner, view);
+        let completed = settle(&mut solitaire, &player, stake_account);
+        assert_eq!(completed.fee_discount_bps, 0, "{case}");
+        assert_eq!(completed.protocol_fee, fee_after(0), "{case}");
+    }
+
+    // Without a stake account the full fee applies too
+    let game = solitaire.start(&player, GameParams::default());
+    solitaire.complete(&game).unwrap();
+    assert_eq!(solitaire.harness.event::<GameCompleted>().protocol_fee, fee_after(0));
+}
+
+#[test]
+fn a_discount_over_the_whole_fee_is_rejected() {
+    let mut solitaire = Solitaire::new();
+    let tier = StakeDiscountTier {
+        min_staked: 1,
+        discount_bps: 10_001,
+    };
+    assert_error(
+        solitaire.update_config(instruction::UpdateStakeDiscountConfig {
+            stake_discount: StakeDiscountConfig {
+                program: Pubkey::new_unique(),
+                mint: Pubkey::new_unique(),
+                tiers: [tier; 3],
+            },
+        }),
+        SolitaireError::InvalidConfig,
+    );
+}
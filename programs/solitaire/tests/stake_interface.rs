@@ -0,0 +1,256 @@
+//! Both programs in one harness: solitaire reading real gaming-token stake
+//! accounts, and the gaming-token `assert_min_stake` entry point it can CPI.
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, sysvar};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use gaming_token::{GamingTokenError, StakeCheck, StakingConfig, STAKE_INTERFACE_VERSION};
+use solitaire::{instruction, FeeConfig, GameCompleted, StakeDiscountConfig, StakeDiscountTier};
+
+const STAKED: u64 = 5_000;
+
+fn staking_pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &gaming_token::ID).0
+}
+
+/// Solitaire with the gaming-token program alongside it, one single-token
+/// pool over `staked_mint`, and a player with `STAKED` in position 0.
+struct Ecosystem {
+    solitaire: Solitaire,
+    staked_mint: Pubkey,
+    player: Player,
+}
+
+impl Ecosystem {
+    fn new() -> Self {
+        let mut solitaire = Solitaire::new();
+        solitaire.harness.add_program(gaming_token::ID, gaming_token::entry);
+        let admin = solitaire.admin;
+        let staked_mint = solitaire.harness.create_mint(&TOKEN, &admin, 6);
+
+        let staking_config = staking_pda(&[b"staking_config"]);
+        let registry_page = staking_pda(&[b"pool_registry", &0u32.to_le_bytes()]);
+        let init = Instruction {
+            program_id: gaming_token::ID,
+            accounts: gaming_token::accounts::InitializeStakingConfig {
+                staking_config,
+                admin,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: gaming_token::instruction::InitializeStakingConfig {}.data(),
+        };
+        let page = Instruction {
+            program_id: gaming_token::ID,
+            accounts: gaming_token::accounts::AllocateRegistryPage {
+                staking_config,
+                registry_page,
+                payer: admin,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: gaming_token::instruction::AllocateRegistryPage { page_index: 0 }.data(),
+        };
+        solitaire.admin(init).unwrap();
+        solitaire.admin(page).unwrap();
+
+        let config: StakingConfig = solitaire.harness.get(&staking_config);
+        let mint_pda = |seed: &[u8]| staking_pda(&[seed, staked_mint.as_ref()]);
+        let create_pool = Instruction {
+            program_id: gaming_token::ID,
+            accounts: gaming_token::accounts::CreatePool {
+                staking_config,
+                registry_page,
+                fee_recipient: config.fee_recipient,
+                stake_pool: mint_pda(b"stake_pool"),
+                stake_vault: mint_pda(b"stake_vault"),
+                reward_vault: mint_pda(b"reward_vault"),
+                vault_authority: mint_pda(b"vault_authority"),
+                reward_vault_authority: mint_pda(b"reward_vault_authority"),
+                closed_pool: mint_pda(b"closed_pool"),
+                mint: staked_mint,
+                reward_mint: staked_mint,
+                payer: admin,
+                system_program: system_program::ID,
+                token_program: TOKEN,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: gaming_token::instruction::CreatePool {
+                reward_duration: 100 * DAY,
+                min_stake_amount: 1,
+                min_lock_seconds: 1,
+                max_lock_seconds: 0,
+            }
+            .data(),
+        };
+        solitaire.admin(create_pool).unwrap();
+
+        let player = solitaire.player();
+        let mut ecosystem = Self {
+            solitaire,
+            staked_mint,
+            player,
+        };
+        let tokens = ecosystem.solitaire.harness.create_token_account(&staked_mint, &player.wallet);
+        ecosystem.solitaire.harness.mint_to(&staked_mint, &tokens, &admin, STAKED);
+        let stake = Instruction {
+            program_id: gaming_token::ID,
+            accounts: gaming_token::accounts::StakeTokens {
+                stake_account: ecosystem.stake_account(),
+                stake_positions: staking_pda(&[b"stake_positions", player.wallet.as_ref(), staked_mint.as_ref()]),
+                stake_pool: ecosystem.stake_pool(),
+                stake_vault: mint_pda(b"stake_vault"),
+                user_token_account: tokens,
+                mint: staked_mint,
+                receipt_mint: None,
+                receipt_token_account: None,
+                receipt_authority: None,
+                referral: None,
+                authority: player.wallet,
+                staker_stats: staking_pda(&[
+                    b"staker_stats",
+                    ecosystem.stake_pool().as_ref(),
+                    player.wallet.as_ref(),
+                ]),
+                system_program: system_program::ID,
+                token_program: TOKEN,
+                rent: sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: gaming_token::instruction::StakeTokens {
+                position_index: 0,
+                amount: STAKED,
+                lock_period: DAY,
+                with_receipt: false,
+                referrer: None,
+            }
+            .data(),
+        };
+        ecosystem.solitaire.harness.process(&[stake], &[player.wallet]).unwrap();
+        ecosystem
+    }
+
+    fn stake_pool(&self) -> Pubkey {
+        staking_pda(&[b"stake_pool", self.staked_mint.as_ref()])
+    }
+
+    fn stake_account(&self) -> Pubkey {
+        staking_pda(&[
+            b"stake",
+            self.player.wallet.as_ref(),
+            self.staked_mint.as_ref(),
+            &0u16.to_le_bytes(),
+        ])
+    }
+
+    /// Runs `assert_min_stake` on the player's position and decodes its return data.
+    fn assert_min_stake(
+        &mut self,
+        interface_version: u8,
+        owner: Pubkey,
+        min_amount: u64,
+    ) -> std::result::Result<StakeCheck, ProgramError> {
+        let ix = Instruction {
+            program_id: gaming_token::ID,
+            accounts: gaming_token::accounts::AssertMinStake {
+                stake_pool: self.stake_pool(),
+                stake_account: self.stake_account(),
+            }
+            .to_account_metas(None),
+            data: gaming_token::instruction::AssertMinStake {
+                interface_version,
+                owner,
+                min_amount,
+            }
+            .data(),
+        };
+        let caller = self.solitaire.harness.funded_wallet();
+        let before = self.solitaire.harness.account(&self.stake_account()).unwrap().data.clone();
+        self.solitaire.harness.process(&[ix], &[caller])?;
+        // Read-only: the position is untouched
+        assert_eq!(self.solitaire.harness.account(&self.stake_account()).unwrap().data, before);
+
+        let (program, data) = self.solitaire.harness.return_data().unwrap();
+        assert_eq!(program, gaming_token::ID);
+        Ok(StakeCheck::try_from_slice(&data).unwrap())
+    }
+}
+
+#[test]
+fn assert_min_stake_reports_through_return_data() {
+    let mut ecosystem = Ecosystem::new();
+    let owner = ecosystem.player.wallet;
+    let lock_until = ecosystem.solitaire.harness.now() + DAY;
+
+    let check = ecosystem.assert_min_stake(STAKE_INTERFACE_VERSION, owner, STAKED).unwrap();
+    assert_eq!(check.interface_version, STAKE_INTERFACE_VERSION);
+    assert!(check.meets_minimum);
+    assert_eq!((check.amount, check.lock_until), (STAKED, lock_until));
+
+    let check = ecosystem.assert_min_stake(STAKE_INTERFACE_VERSION, owner, STAKED + 1).unwrap();
+    assert!(!check.meets_minimum);
+    assert_eq!(check.amount, STAKED);
+
+    // Someone else's position counts as nothing
+    let check = ecosystem
+        .assert_min_stake(STAKE_INTERFACE_VERSION, Pubkey::new_unique(), 1)
+        .unwrap();
+    assert!(!check.meets_minimum);
+    assert_eq!(check.amount, 0);
+}
+
+#[test]
+fn a_caller_on_another_interface_version_is_refused() {
+    let mut ecosystem = Ecosystem::new();
+    let owner = ecosystem.player.wallet;
+    for version in [0, STAKE_INTERFACE_VERSION + 1] {
+        assert_error(
+            ecosystem.assert_min_stake(version, owner, 1).map(drop),
+            GamingTokenError::UnsupportedInterfaceVersion,
+        );
+    }
+}
+
+#[test]
+fn a_real_stake_account_earns_the_solitaire_discount() {
+    let mut ecosystem = Ecosystem::new();
+    let (player, stake_account) = (ecosystem.player, ecosystem.stake_account());
+    let staked_mint = ecosystem.staked_mint;
+    let solitaire = &mut ecosystem.solitaire;
+    solitaire
+        .update_config(instruction::UpdateFeeConfig {
+            fees: FeeConfig {
+                protocol_fee_bps: 1_000,
+                referral_share_bps: 0,
+            },
+        })
+        .unwrap();
+    let tier = |min_staked, discount_bps| StakeDiscountTier {
+        min_staked,
+        discount_bps,
+    };
+    solitaire
+        .update_config(instruction::UpdateStakeDiscountConfig {
+            stake_discount: StakeDiscountConfig {
+                program: gaming_token::ID,
+                mint: staked_mint,
+                tiers: [tier(STAKED, 5_000), tier(STAKED + 1, 10_000), tier(0, 0)],
+            },
+        })
+        .unwrap();
+    let mint = solitaire.mint;
+    solitaire.treasury(&mint, 0);
+
+    let game = solitaire.start(&player, GameParams::default());
+    let mut accounts = solitaire.complete_accounts(&game);
+    accounts.stake_account = Some(stake_account);
+    solitaire.complete_with(&game, accounts).unwrap();
+
+    let completed = solitaire.harness.event::<GameCompleted>();
+    assert_eq!(completed.fee_discount_bps, 5_000);
+    // 5% rather than 10% of the half-stake paid on a loss
+    assert_eq!(completed.protocol_fee, GameParams::default().stake / 2 / 20);
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, sysvar};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use common::*;
+use solitaire::{
+    accounts, instruction, SolitaireError, Tournament, TournamentCreated, TournamentSchedule,
+    TournamentScheduleParams,
+};
+
+const SCHEDULE_ID: u32 = 7;
+const INCENTIVE: u64 = 500;
+const PARAMS: TournamentScheduleParams = TournamentScheduleParams {
+    cadence_seconds: DAY,
+    entry_fee: 1_000,
+    duration_seconds: DAY / 2,
+    payout_curve: [5_000, 3_000, 2_000, 0, 0],
+    crank_incentive: INCENTIVE,
+};
+
+fn schedule() -> Pubkey {
+    pda(&[b"tournament_schedule", SCHEDULE_ID.to_le_bytes().as_ref()])
+}
+
+fn fee_reserve() -> Pubkey {
+    pda(&[b"schedule_reserve", schedule().as_ref()])
+}
+
+fn tournament(epoch_index: u64) -> Pubkey {
+    pda(&[b"tournament", schedule().as_ref(), epoch_index.to_le_bytes().as_ref()])
+}
+
+/// A daily schedule whose first tournament starts in an hour, with `reserve`
+/// in its fee reserve. Returns the first start.
+fn daily(solitaire: &mut Solitaire, reserve: u64) -> i64 {
+    let first_start = solitaire.harness.now() + 3_600;
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::InitializeTournamentSchedule {
+            config: solitaire.config(),
+            schedule: schedule(),
+            fee_reserve: fee_reserve(),
+            mint: solitaire.mint,
+            admin: solitaire.admin,
+            system_program: system_program::ID,
+            token_program: TOKEN,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeTournamentSchedule {
+            schedule_id: SCHEDULE_ID,
+            first_start,
+            params: PARAMS,
+        }
+        .data(),
+    };
+    solitaire.admin(ix).unwrap();
+    if reserve > 0 {
+        let (mint, admin) = (solitaire.mint, solitaire.admin);
+        solitaire.harness.mint_to(&mint, &fee_reserve(), &admin, reserve);
+    }
+    first_start
+}
+
+fn crank(solitaire: &mut Solitaire, caller: &Player, epoch_index: u64) -> TxResult {
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::CrankSchedule {
+            schedule: schedule(),
+            tournament: tournament(epoch_index),
+            fee_reserve: fee_reserve(),
+            caller_token_account: caller.tokens,
+            caller: caller.wallet,
+            system_program: system_program::ID,
+            token_program: TOKEN,
+        }
+        .to_account_metas(None),
+        data: instruction::CrankSchedule { epoch_index }.data(),
+    };
+    solitaire.harness.process(&[ix], &[caller.wallet])
+}
+
+fn set_paused(solitaire: &mut Solitaire, paused: bool) {
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::UpdateTournamentSchedule {
+            config: solitaire.config(),
+            schedule: schedule(),
+            admin: solitaire.admin,
+        }
+        .to_account_metas(None),
+        data: instruction::SetTournamentSchedulePaused { paused }.data(),
+    };
+    solitaire.admin(ix).unwrap();
+}
+
+#[test]
+fn each_epoch_cranks_once_when_due() {
+    let mut solitaire = Solitaire::new();
+    let first_start = daily(&mut solitaire, 10 * INCENTIVE);
+    let caller = solitaire.player();
+
+    assert_error(crank(&mut solitaire, &caller, 0), SolitaireError::ScheduleNotDue);
+    solitaire.harness.set_time(first_start);
+    assert_error(crank(&mut solitaire, &caller, 1), SolitaireError::InvalidEpoch);
+    crank(&mut solitaire, &caller, 0).unwrap();
+
+    let created: Tournament = solitaire.harness.get(&tournament(0));
+    assert_eq!(created.start_time, first_start);
+    assert_eq!(created.end_time, first_start + DAY / 2);
+    assert_eq!(solitaire.balance(&caller.tokens), PLAYER_TOKENS + INCENTIVE);
+    let state: TournamentSchedule = solitaire.harness.get(&schedule());
+    assert_eq!((state.epoch_index, state.next_start), (1, first_start + DAY));
+
+    assert!(crank(&mut solitaire, &caller, 0).is_err());
+    assert_error(crank(&mut solitaire, &caller, 1), SolitaireError::ScheduleNotDue);
+    solitaire.harness.set_time(first_start + DAY);
+    crank(&mut solitaire, &caller, 1).unwrap();
+    assert_eq!(solitaire.balance(&caller.tokens), PLAYER_TOKENS + 2 * INCENTIVE);
+}
+
+#[test]
+fn a_late_crank_skips_the_missed_slots() {
+    let mut solitaire = Solitaire::new();
+    let first_start = daily(&mut solitaire, 10 * INCENTIVE);
+    let caller = solitaire.player();
+
+    solitaire.harness.set_time(first_start + 3 * DAY + 10);
+    crank(&mut solitaire, &caller, 0).unwrap();
+    let created = solitaire.harness.event::<TournamentCreated>();
+    assert_eq!(created.start_time, first_start + 3 * DAY);
+
+    // The three missed days can't be cranked for their incentives
+    let state: TournamentSchedule = solitaire.harness.get(&schedule());
+    assert_eq!((state.epoch_index, state.next_start), (1, first_start + 4 * DAY));
+    assert_error(crank(&mut solitaire, &caller, 1), SolitaireError::ScheduleNotDue);
+    assert_eq!(solitaire.balance(&caller.tokens), PLAYER_TOKENS + INCENTIVE);
+}
+
+#[test]
+fn a_paused_schedule_waits_and_the_incentive_is_capped_by_the_reserve() {
+    let mut solitaire = Solitaire::new();
+    let first_start = daily(&mut solitaire, INCENTIVE / 2);
+    let caller = solitaire.player();
+
+    set_paused(&mut solitaire, true);
+    solitaire.harness.set_time(first_start);
+    assert_error(crank(&mut solitaire, &caller, 0), SolitaireError::SchedulePaused);
+
+    set_paused(&mut solitaire, false);
+    crank(&mut solitaire, &caller, 0).unwrap();
+    assert_eq!(solitaire.harness.event::<TournamentCreated>().crank_incentive, INCENTIVE / 2);
+    assert_eq!(solitaire.balance(&fee_reserve()), 0);
+}
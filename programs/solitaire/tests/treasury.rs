@@ -0,0 +1,270 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use common::*;
+use solitaire::{
+    accounts, instruction, SolitaireError, TreasuryBalanceSnapshotTaken, TreasuryConfig, TreasuryWithdrawn,
+    DEFAULT_TREASURY_TIMELOCK_SECONDS,
+};
+
+const BALANCE: u64 = 1_000_000;
+const THRESHOLD: u64 = 100_000;
+
+/// A funded treasury for the reward mint and an admin-owned destination.
+fn funded() -> (Solitaire, Pubkey, Pubkey) {
+    let mut solitaire = Solitaire::new();
+    let (mint, admin) = (solitaire.mint, solitaire.admin);
+    let treasury = solitaire.treasury(&mint, BALANCE);
+    let destination = solitaire.harness.create_token_account(&mint, &admin);
+    (solitaire, treasury, destination)
+}
+
+/// Immediate withdrawals of up to `THRESHOLD`, larger ones after a day.
+fn with_threshold(solitaire: &mut Solitaire) {
+    solitaire
+        .update_config(instruction::UpdateTreasuryConfig {
+            treasury: TreasuryConfig {
+                large_withdrawal_threshold: THRESHOLD,
+                withdrawal_timelock_seconds: DAY,
+            },
+        })
+        .unwrap();
+}
+
+fn pending_withdrawal(mint: &Pubkey) -> Pubkey {
+    pda(&[b"pending_withdrawal", mint.as_ref()])
+}
+
+fn withdraw_ix(solitaire: &Solitaire, treasury: &Pubkey, destination: &Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::WithdrawTreasury {
+            config: solitaire.config(),
+            treasury_token_account: *treasury,
+            treasury_authority: treasury_authority(),
+            destination: *destination,
+            admin: solitaire.admin,
+            token_program: TOKEN,
+        }
+        .to_account_metas(None),
+        data: instruction::WithdrawTreasury { amount }.data(),
+    }
+}
+
+fn queue(solitaire: &mut Solitaire, destination: &Pubkey, amount: u64) -> TxResult {
+    let mint = solitaire.mint;
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::QueueTreasuryWithdrawal {
+            config: solitaire.config(),
+            treasury_token_account: treasury(&mint),
+            pending_withdrawal: pending_withdrawal(&mint),
+            destination: *destination,
+            admin: solitaire.admin,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::QueueTreasuryWithdrawal { amount }.data(),
+    };
+    solitaire.admin(ix)
+}
+
+fn execute(solitaire: &mut Solitaire, destination: &Pubkey) -> TxResult {
+    let mint = solitaire.mint;
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::ExecuteTreasuryWithdrawal {
+            config: solitaire.config(),
+            pending_withdrawal: pending_withdrawal(&mint),
+            treasury_token_account: treasury(&mint),
+            treasury_authority: treasury_authority(),
+            destination: *destination,
+            admin: solitaire.admin,
+            token_program: TOKEN,
+        }
+        .to_account_metas(None),
+        data: instruction::ExecuteTreasuryWithdrawal {}.data(),
+    };
+    solitaire.admin(ix)
+}
+
+fn snapshot(solitaire: &mut Solitaire, treasuries: &[Pubkey]) -> TxResult {
+    let mut accounts = accounts::TreasuryBalanceSnapshot {
+        treasury_authority: treasury_authority(),
+    }
+    .to_account_metas(None);
+    accounts.extend(treasuries.iter().map(|key| AccountMeta::new_readonly(*key, false)));
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts,
+        data: instruction::TreasuryBalanceSnapshot {}.data(),
+    };
+    let caller = solitaire.harness.funded_wallet();
+    solitaire.harness.process(&[ix], &[caller])
+}
+
+#[test]
+fn a_raw_spl_transfer_cannot_move_treasury_funds() {
+    let (mut solitaire, treasury, destination) = funded();
+    let admin = solitaire.admin;
+
+    // The admin key holds no authority over the account
+    let ix = spl_token::instruction::transfer(&TOKEN, &treasury, &destination, &admin, &[], BALANCE).unwrap();
+    assert_eq!(
+        solitaire.harness.process(&[ix], &[admin]),
+        Err(spl_token::error::TokenError::OwnerMismatch.into())
+    );
+
+    // Only the program can sign for the authority PDA
+    let ix = spl_token::instruction::transfer(&TOKEN, &treasury, &destination, &treasury_authority(), &[], BALANCE)
+        .unwrap();
+    assert_eq!(
+        solitaire.harness.process(&[ix], &[admin]),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+
+    // Nor can the admin hand the account to a delegate or a new owner
+    let ix = spl_token::instruction::approve(&TOKEN, &treasury, &admin, &admin, &[], BALANCE).unwrap();
+    assert_eq!(
+        solitaire.harness.process(&[ix], &[admin]),
+        Err(spl_token::error::TokenError::OwnerMismatch.into())
+    );
+    let ix = spl_token::instruction::set_authority(
+        &TOKEN,
+        &treasury,
+        Some(&admin),
+        spl_token::instruction::AuthorityType::AccountOwner,
+        &admin,
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        solitaire.harness.process(&[ix], &[admin]),
+        Err(spl_token::error::TokenError::OwnerMismatch.into())
+    );
+
+    assert_eq!(solitaire.balance(&treasury), BALANCE);
+    assert_eq!(solitaire.harness.token_account(&treasury).owner, treasury_authority());
+}
+
+#[test]
+fn withdrawals_are_admin_only_and_checked_against_the_balance() {
+    let (mut solitaire, treasury, destination) = funded();
+    with_threshold(&mut solitaire);
+
+    let mut ix = withdraw_ix(&solitaire, &treasury, &destination, 1);
+    let stranger = solitaire.harness.funded_wallet();
+    // Sign as someone other than the configured admin
+    ix.accounts[4].pubkey = stranger;
+    assert_error(solitaire.harness.process(&[ix], &[stranger]), ErrorCode::ConstraintHasOne);
+
+    let ix = withdraw_ix(&solitaire, &treasury, &destination, 0);
+    assert_error(solitaire.admin(ix), SolitaireError::InvalidAmount);
+    let ix = withdraw_ix(&solitaire, &treasury, &destination, BALANCE + 1);
+    assert_error(solitaire.admin(ix), SolitaireError::InsufficientTreasuryBalance);
+
+    // The destination must hold the treasury's mint
+    let admin = solitaire.admin;
+    let other_mint = solitaire.harness.create_mint(&TOKEN, &admin, 6);
+    let wrong_mint = solitaire.harness.create_token_account(&other_mint, &admin);
+    let ix = withdraw_ix(&solitaire, &treasury, &wrong_mint, 1);
+    assert_error(solitaire.admin(ix), ErrorCode::ConstraintTokenMint);
+
+    let ix = withdraw_ix(&solitaire, &treasury, &destination, THRESHOLD);
+    solitaire.admin(ix).unwrap();
+    let withdrawn = solitaire.harness.event::<TreasuryWithdrawn>();
+    assert_eq!((withdrawn.destination, withdrawn.amount), (destination, THRESHOLD));
+    assert_eq!(solitaire.balance(&destination), THRESHOLD);
+    assert_eq!(solitaire.balance(&treasury), BALANCE - THRESHOLD);
+}
+
+#[test]
+fn a_new_config_timelocks_every_withdrawal() {
+    let (mut solitaire, treasury, destination) = funded();
+    let ix = withdraw_ix(&solitaire, &treasury, &destination, 1);
+    assert_error(solitaire.admin(ix), SolitaireError::WithdrawalRequiresTimelock);
+
+    queue(&mut solitaire, &destination, BALANCE).unwrap();
+    solitaire.harness.warp(DEFAULT_TREASURY_TIMELOCK_SECONDS - 1);
+    assert_error(execute(&mut solitaire, &destination), SolitaireError::WithdrawalTimelocked);
+    solitaire.harness.warp(1);
+    execute(&mut solitaire, &destination).unwrap();
+    assert_eq!(solitaire.balance(&destination), BALANCE);
+    assert_eq!(solitaire.balance(&treasury), 0);
+}
+
+#[test]
+fn large_withdrawals_wait_out_the_timelock() {
+    let (mut solitaire, treasury, destination) = funded();
+    with_threshold(&mut solitaire);
+
+    let ix = withdraw_ix(&solitaire, &treasury, &destination, THRESHOLD + 1);
+    assert_error(solitaire.admin(ix), SolitaireError::WithdrawalRequiresTimelock);
+    let ix = withdraw_ix(&solitaire, &treasury, &destination, THRESHOLD);
+    solitaire.admin(ix).unwrap();
+
+    queue(&mut solitaire, &destination, 5 * THRESHOLD).unwrap();
+    // One queued withdrawal per mint at a time
+    assert!(queue(&mut solitaire, &destination, 1).is_err());
+    solitaire.harness.warp(DAY - 1);
+    assert_error(execute(&mut solitaire, &destination), SolitaireError::WithdrawalTimelocked);
+
+    solitaire.harness.warp(1);
+    execute(&mut solitaire, &destination).unwrap();
+    assert_eq!(solitaire.balance(&destination), 6 * THRESHOLD);
+    assert_eq!(solitaire.balance(&treasury), BALANCE - 6 * THRESHOLD);
+    assert!(solitaire.harness.account(&pending_withdrawal(&solitaire.mint)).is_none());
+}
+
+#[test]
+fn a_queued_withdrawal_can_be_cancelled() {
+    let (mut solitaire, treasury, destination) = funded();
+    queue(&mut solitaire, &destination, BALANCE).unwrap();
+
+    let mint = solitaire.mint;
+    let ix = Instruction {
+        program_id: solitaire::ID,
+        accounts: accounts::CancelTreasuryWithdrawal {
+            config: solitaire.config(),
+            pending_withdrawal: pending_withdrawal(&mint),
+            admin: solitaire.admin,
+        }
+        .to_account_metas(None),
+        data: instruction::CancelTreasuryWithdrawal {}.data(),
+    };
+    solitaire.admin(ix).unwrap();
+
+    assert!(solitaire.harness.account(&pending_withdrawal(&mint)).is_none());
+    assert!(execute(&mut solitaire, &destination).is_err());
+    assert_eq!(solitaire.balance(&treasury), BALANCE);
+}
+
+#[test]
+fn the_snapshot_reports_every_treasury_balance() {
+    let (mut solitaire, treasury, destination) = funded();
+    let admin = solitaire.admin;
+    let other_mint = solitaire.harness.create_mint(&TOKEN, &admin, 9);
+    let other = solitaire.treasury(&other_mint, 42);
+
+    snapshot(&mut solitaire, &[treasury, other]).unwrap();
+    let taken = solitaire.harness.event::<TreasuryBalanceSnapshotTaken>();
+    let balances: Vec<_> = taken
+        .balances
+        .iter()
+        .map(|balance| (balance.account, balance.mint, balance.amount))
+        .collect();
+    assert_eq!(
+        balances,
+        [(treasury, solitaire.mint, BALANCE), (other, other_mint, 42)]
+    );
+
+    // Only accounts held by the treasury authority count
+    assert_error(
+        snapshot(&mut solitaire, &[treasury, destination]),
+        SolitaireError::InvalidTreasuryAccount,
+    );
+}
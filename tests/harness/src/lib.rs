@@ -0,0 +1,863 @@
+//! In-process runtime for exercising the Sol-itaire programs from `cargo test`.
+//!
+//! There is no validator in the test environment, so this crate plays the
+//! part of one: it keeps an account store, lays each account out in memory the
+//! way the loader does, and installs syscall stubs so that CPIs, sysvars,
+//! return data and `emit!` all work against the programs' native builds. The
+//! system program is a small reimplementation; SPL Token, Token-2022 and the
+//! associated token account program run their real processors.
+//!
+//! Transactions are atomic: every instruction runs against a working copy of
+//! the store, which is committed only when all of them succeed. The runtime
+//! checks signer and writable privileges on every CPI, but it does not enforce
+//! the loader's ownership rules on account data - the programs under test are
+//! trusted to only write what they own, as Anchor's account types guarantee.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    bpf_loader_upgradeable,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    epoch_schedule::EpochSchedule,
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_pack::Pack,
+    program_stubs::{self, SyscallStubs},
+    program_utils::limited_deserialize,
+    system_instruction::{SystemError, SystemInstruction},
+    system_program,
+    sysvar,
+};
+use anchor_lang::{Discriminator, Event};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+
+/// Signature shared by every program entrypoint the runtime can dispatch to.
+pub type Processor =
+    for<'info> fn(&Pubkey, &'info [AccountInfo<'info>], &[u8]) -> ProgramResult;
+
+/// Lamports used to fund accounts the harness creates directly; far above the
+/// rent-exempt minimum of anything the programs allocate.
+pub const FUNDED_LAMPORTS: u64 = 1_000_000_000;
+
+/// A stored account.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccountState {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+/// Execution state the syscall stubs need. It lives in a thread local because
+/// the stubs are process-global while `cargo test` runs tests in parallel.
+#[derive(Default)]
+struct Runtime {
+    programs: HashMap<Pubkey, Processor>,
+    clock: Clock,
+    rent: Rent,
+    call_stack: Vec<Pubkey>,
+    return_data: Option<(Pubkey, Vec<u8>)>,
+    events: Vec<(Pubkey, Vec<u8>)>,
+    logs: Vec<String>,
+}
+
+thread_local! {
+    static RUNTIME: RefCell<Runtime> = RefCell::new(Runtime::default());
+}
+
+fn with_runtime<R>(f: impl FnOnce(&mut Runtime) -> R) -> R {
+    RUNTIME.with(|rt| f(&mut rt.borrow_mut()))
+}
+
+fn current_program() -> Pubkey {
+    with_runtime(|rt| rt.call_stack.last().copied().unwrap_or_default())
+}
+
+/// Runs `processor` as `program_id`, keeping the call stack in step so that
+/// CPIs made from inside know who their caller is.
+fn dispatch(
+    processor: Processor,
+    program_id: &Pubkey,
+    infos: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    with_runtime(|rt| {
+        rt.call_stack.push(*program_id);
+        rt.return_data = None;
+    });
+    // SAFETY: the processor only uses the slice for the duration of the call;
+    // the lifetime is widened because the entrypoint signature ties the slice
+    // and the accounts together.
+    let infos = unsafe { std::mem::transmute::<&[AccountInfo], &[AccountInfo<'static>]>(infos) };
+    let result = processor(program_id, infos, data);
+    with_runtime(|rt| rt.call_stack.pop());
+    if let Err(err) = &result {
+        with_runtime(|rt| rt.logs.push(format!("Program {program_id} failed: {err}")));
+    }
+    result
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, message: &str) {
+        with_runtime(|rt| rt.logs.push(message.to_string()));
+    }
+
+    fn sol_log_compute_units(&self) {}
+
+    fn sol_remaining_compute_units(&self) -> u64 {
+        1_400_000
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let caller = current_program();
+        let pda_signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| ProgramError::InvalidSeeds)?;
+
+        let mut callee_infos = Vec::with_capacity(instruction.accounts.len());
+        for meta in &instruction.accounts {
+            let info = account_infos
+                .iter()
+                .find(|info| info.key == &meta.pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if meta.is_signer && !info.is_signer && !pda_signers.contains(&meta.pubkey) {
+                self.sol_log(&format!("{} signer privilege escalated", meta.pubkey));
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if meta.is_writable && !info.is_writable {
+                self.sol_log(&format!("{} writable privilege escalated", meta.pubkey));
+                return Err(ProgramError::InvalidArgument);
+            }
+            let mut info = info.clone();
+            info.is_signer = meta.is_signer;
+            info.is_writable = meta.is_writable;
+            callee_infos.push(info);
+        }
+
+        let processor = with_runtime(|rt| rt.programs.get(&instruction.program_id).copied())
+            .ok_or(ProgramError::IncorrectProgramId)?;
+        dispatch(processor, &instruction.program_id, &callee_infos, &instruction.data)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = with_runtime(|rt| rt.clock.clone());
+        unsafe { std::ptr::write(var_addr as *mut Clock, clock) };
+        0
+    }
+
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { std::ptr::write(var_addr as *mut EpochSchedule, EpochSchedule::default()) };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let rent = with_runtime(|rt| rt.rent);
+        unsafe { std::ptr::write(var_addr as *mut Rent, rent) };
+        0
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        with_runtime(|rt| rt.return_data.clone())
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        let program = current_program();
+        with_runtime(|rt| {
+            rt.return_data = (!data.is_empty()).then(|| (program, data.to_vec()));
+        });
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let program = current_program();
+        with_runtime(|rt| rt.events.push((program, fields.concat())));
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        with_runtime(|rt| rt.call_stack.len() as u64)
+    }
+}
+
+/// The parts of the system program the programs reach through CPI.
+fn process_system_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let instruction: SystemInstruction =
+        limited_deserialize(data, 1232).map_err(|_| ProgramError::InvalidInstructionData)?;
+    let account = |index: usize| accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys);
+    let require_signer = |info: &AccountInfo| {
+        if info.is_signer {
+            Ok(())
+        } else {
+            Err(ProgramError::MissingRequiredSignature)
+        }
+    };
+    let in_use = ProgramError::Custom(SystemError::AccountAlreadyInUse as u32);
+    let require_fresh = |info: &AccountInfo| {
+        if info.owner == &system_program::ID && info.data_is_empty() {
+            Ok(())
+        } else {
+            Err(in_use.clone())
+        }
+    };
+    let transfer = |from: &AccountInfo, to: &AccountInfo, lamports: u64| {
+        require_signer(from)?;
+        if from.owner != &system_program::ID || !from.data_is_empty() {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let remaining = from
+            .lamports()
+            .checked_sub(lamports)
+            .ok_or(ProgramError::Custom(SystemError::ResultWithNegativeLamports as u32))?;
+        **from.try_borrow_mut_lamports()? = remaining;
+        **to.try_borrow_mut_lamports()? += lamports;
+        Ok(())
+    };
+
+    match instruction {
+        SystemInstruction::CreateAccount { lamports, space, owner } => {
+            let (from, to) = (account(0)?, account(1)?);
+            require_signer(to)?;
+            require_fresh(to)?;
+            if to.lamports() > 0 {
+                return Err(in_use);
+            }
+            transfer(from, to, lamports)?;
+            to.realloc(space as usize, true)?;
+            to.assign(&owner);
+            Ok(())
+        }
+        SystemInstruction::Transfer { lamports } => transfer(account(0)?, account(1)?, lamports),
+        SystemInstruction::Allocate { space } => {
+            let target = account(0)?;
+            require_signer(target)?;
+            require_fresh(target)?;
+            target.realloc(space as usize, true)
+        }
+        SystemInstruction::Assign { owner } => {
+            let target = account(0)?;
+            require_signer(target)?;
+            if target.owner != &system_program::ID {
+                return Err(ProgramError::IllegalOwner);
+            }
+            target.assign(&owner);
+            Ok(())
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn process_spl_token(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    spl_token::processor::Processor::process(program_id, accounts, data)
+}
+
+fn process_token_2022(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    spl_token_2022::processor::Processor::process(program_id, accounts, data)
+}
+
+fn process_associated_token(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    spl_associated_token_account::processor::process_instruction(program_id, accounts, data)
+}
+
+/// Key cell laid out so that the loader's `original_data_len`, stored in the
+/// four bytes before the key, can be read back by `AccountInfo::realloc`.
+#[repr(C, align(8))]
+struct KeyCell {
+    _padding: u32,
+    original_data_len: u32,
+    key: Pubkey,
+}
+
+/// Backing memory for one account while an instruction runs.
+struct Materialized {
+    key: *mut KeyCell,
+    owner: *mut Pubkey,
+    lamports: *mut u64,
+    data: *mut u64,
+    words: usize,
+}
+
+impl Materialized {
+    fn new(key: Pubkey, state: &AccountState) -> Self {
+        let len = state.data.len();
+        let words = (8 + len + MAX_PERMITTED_DATA_INCREASE).div_ceil(8);
+        let data = Box::into_raw(vec![0u64; words].into_boxed_slice()) as *mut u64;
+        unsafe {
+            *data = len as u64;
+            let bytes = (data as *mut u8).add(8);
+            std::ptr::copy_nonoverlapping(state.data.as_ptr(), bytes, len);
+        }
+        Self {
+            key: Box::into_raw(Box::new(KeyCell {
+                _padding: 0,
+                original_data_len: len as u32,
+                key,
+            })),
+            owner: Box::into_raw(Box::new(state.owner)),
+            lamports: Box::into_raw(Box::new(state.lamports)),
+            data,
+            words,
+        }
+    }
+
+    fn info(&self, executable: bool) -> AccountInfo<'static> {
+        unsafe {
+            let len = *self.data as usize;
+            AccountInfo {
+                key: &(*self.key).key,
+                is_signer: false,
+                is_writable: false,
+                lamports: Rc::new(RefCell::new(&mut *self.lamports)),
+                data: Rc::new(RefCell::new(std::slice::from_raw_parts_mut(
+                    (self.data as *mut u8).add(8),
+                    len,
+                ))),
+                owner: &*self.owner,
+                executable,
+                rent_epoch: u64::MAX,
+            }
+        }
+    }
+
+    fn read_back(&self, executable: bool) -> AccountState {
+        unsafe {
+            let len = *self.data as usize;
+            AccountState {
+                lamports: *self.lamports,
+                data: std::slice::from_raw_parts((self.data as *const u8).add(8), len).to_vec(),
+                owner: std::ptr::read_volatile(self.owner),
+                executable,
+            }
+        }
+    }
+}
+
+impl Drop for Materialized {
+    fn drop(&mut self) {
+        unsafe {
+            drop(Box::from_raw(self.key));
+            drop(Box::from_raw(self.owner));
+            drop(Box::from_raw(self.lamports));
+            drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                self.data, self.words,
+            )));
+        }
+    }
+}
+
+/// An account store plus the programs that can run against it.
+pub struct Harness {
+    accounts: HashMap<Pubkey, AccountState>,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Harness {
+    /// A fresh runtime with the system, SPL Token, Token-2022 and associated
+    /// token account programs loaded and the clock at slot 1.
+    pub fn new() -> Self {
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(Stubs));
+        });
+        with_runtime(|rt| *rt = Runtime::default());
+
+        let mut harness = Self {
+            accounts: HashMap::new(),
+        };
+        harness.accounts.insert(
+            system_program::ID,
+            AccountState {
+                lamports: 1,
+                owner: Pubkey::default(),
+                executable: true,
+                ..AccountState::default()
+            },
+        );
+        harness.add_program(system_program::ID, process_system_instruction);
+        harness.add_program(spl_token::ID, process_spl_token);
+        harness.add_program(spl_token_2022::ID, process_token_2022);
+        harness.add_program(
+            spl_associated_token_account::ID,
+            process_associated_token,
+        );
+        harness.set_account(
+            sysvar::rent::ID,
+            AccountState {
+                lamports: 1,
+                data: bincode_rent(&Rent::default()),
+                owner: sysvar::ID,
+                executable: false,
+            },
+        );
+        harness.set_clock(Clock {
+            slot: 1,
+            unix_timestamp: 1_700_000_000,
+            ..Clock::default()
+        });
+        harness
+    }
+
+    /// Loads a program so that it can be invoked at `program_id`.
+    pub fn add_program(&mut self, program_id: Pubkey, processor: Processor) {
+        with_runtime(|rt| rt.programs.insert(program_id, processor));
+        if program_id != system_program::ID {
+            self.accounts.insert(
+                program_id,
+                AccountState {
+                    lamports: 1,
+                    data: Vec::new(),
+                    owner: bpf_loader_upgradeable::ID,
+                    executable: true,
+                },
+            );
+        }
+    }
+
+    /// Points `program_id` at a ProgramData account whose upgrade authority is
+    /// `authority`, as a deploy through the upgradeable loader would. Returns
+    /// the ProgramData address.
+    pub fn set_upgrade_authority(&mut self, program_id: Pubkey, authority: Option<Pubkey>) -> Pubkey {
+        let (program_data, _) =
+            Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::ID);
+
+        // UpgradeableLoaderState's bincode layout: a u32 variant tag, then fields
+        let mut data = 2u32.to_le_bytes().to_vec();
+        data.extend_from_slice(program_data.as_ref());
+        self.accounts.get_mut(&program_id).expect("program added").data = data;
+
+        let mut data = 3u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&0u64.to_le_bytes());
+        match authority {
+            Some(authority) => {
+                data.push(1);
+                data.extend_from_slice(authority.as_ref());
+            }
+            None => data.push(0),
+        }
+        self.accounts.insert(
+            program_data,
+            AccountState {
+                lamports: 1,
+                data,
+                owner: bpf_loader_upgradeable::ID,
+                executable: false,
+            },
+        );
+        program_data
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, state: AccountState) {
+        self.accounts.insert(key, state);
+    }
+
+    pub fn remove_account(&mut self, key: &Pubkey) {
+        self.accounts.remove(key);
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<&AccountState> {
+        self.accounts.get(key)
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.accounts.get(key).map_or(0, |account| account.lamports)
+    }
+
+    /// Funds a system account, creating it if needed.
+    pub fn airdrop(&mut self, key: &Pubkey, lamports: u64) {
+        self.accounts
+            .entry(*key)
+            .or_insert_with(|| AccountState {
+                owner: system_program::ID,
+                ..AccountState::default()
+            })
+            .lamports += lamports;
+    }
+
+    /// A new system account holding `FUNDED_LAMPORTS`.
+    pub fn funded_wallet(&mut self) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.airdrop(&key, FUNDED_LAMPORTS);
+        key
+    }
+
+    /// Deserializes an Anchor account, checking its discriminator.
+    pub fn get<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        let account = self
+            .accounts
+            .get(key)
+            .unwrap_or_else(|| panic!("account {key} does not exist"));
+        T::try_deserialize(&mut account.data.as_slice())
+            .unwrap_or_else(|err| panic!("account {key} does not deserialize: {err}"))
+    }
+
+    pub fn clock(&self) -> Clock {
+        with_runtime(|rt| rt.clock.clone())
+    }
+
+    pub fn set_clock(&mut self, clock: Clock) {
+        let mut data = Vec::with_capacity(40);
+        data.extend_from_slice(&clock.slot.to_le_bytes());
+        data.extend_from_slice(&clock.epoch_start_timestamp.to_le_bytes());
+        data.extend_from_slice(&clock.epoch.to_le_bytes());
+        data.extend_from_slice(&clock.leader_schedule_epoch.to_le_bytes());
+        data.extend_from_slice(&clock.unix_timestamp.to_le_bytes());
+        self.accounts.insert(
+            sysvar::clock::ID,
+            AccountState {
+                lamports: 1,
+                data,
+                owner: sysvar::ID,
+                executable: false,
+            },
+        );
+        with_runtime(|rt| rt.clock = clock);
+    }
+
+    pub fn now(&self) -> i64 {
+        self.clock().unix_timestamp
+    }
+
+    /// Moves the clock to `unix_timestamp`, advancing the slot with it.
+    pub fn set_time(&mut self, unix_timestamp: i64) {
+        let mut clock = self.clock();
+        clock.slot += 1;
+        clock.unix_timestamp = unix_timestamp;
+        self.set_clock(clock);
+    }
+
+    pub fn warp(&mut self, seconds: i64) {
+        self.set_time(self.now() + seconds);
+    }
+
+    /// Runs `instructions` as one transaction signed by `signers`, committing
+    /// the resulting state only if every instruction succeeds.
+    pub fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[Pubkey],
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts = self.execute(instructions, signers)?;
+        self.accounts = accounts;
+        Ok(())
+    }
+
+    /// Runs `instructions` like `process` but discards the resulting state,
+    /// leaving the events, logs and return data of the run readable.
+    pub fn simulate(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[Pubkey],
+    ) -> std::result::Result<(), ProgramError> {
+        self.execute(instructions, signers).map(drop)
+    }
+
+    fn execute(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[Pubkey],
+    ) -> std::result::Result<HashMap<Pubkey, AccountState>, ProgramError> {
+        with_runtime(|rt| {
+            rt.events.clear();
+            rt.logs.clear();
+            rt.return_data = None;
+            rt.call_stack.clear();
+        });
+        let mut working = self.accounts.clone();
+        for instruction in instructions {
+            let result = Self::execute_instruction(&mut working, instruction, signers);
+            if let Err(err) = result {
+                for line in self.logs() {
+                    eprintln!("    {line}");
+                }
+                return Err(err);
+            }
+        }
+        working.retain(|_, account| account.lamports > 0);
+        Ok(working)
+    }
+
+    fn execute_instruction(
+        working: &mut HashMap<Pubkey, AccountState>,
+        instruction: &Instruction,
+        signers: &[Pubkey],
+    ) -> ProgramResult {
+        let processor = with_runtime(|rt| rt.programs.get(&instruction.program_id).copied())
+            .ok_or(ProgramError::IncorrectProgramId)?;
+
+        let mut keys: Vec<Pubkey> = Vec::new();
+        for meta in &instruction.accounts {
+            if meta.is_signer && !signers.contains(&meta.pubkey) {
+                with_runtime(|rt| rt.logs.push(format!("{} did not sign", meta.pubkey)));
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if !keys.contains(&meta.pubkey) {
+                keys.push(meta.pubkey);
+            }
+        }
+
+        let memory: HashMap<Pubkey, (Materialized, bool)> = keys
+            .iter()
+            .map(|key| {
+                let state = working.get(key).cloned().unwrap_or_else(|| AccountState {
+                    owner: system_program::ID,
+                    ..AccountState::default()
+                });
+                (*key, (Materialized::new(*key, &state), state.executable))
+            })
+            .collect();
+        let shared: HashMap<Pubkey, AccountInfo> = memory
+            .iter()
+            .map(|(key, (cell, executable))| (*key, cell.info(*executable)))
+            .collect();
+        let infos: Vec<AccountInfo> = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                let mut info = shared[&meta.pubkey].clone();
+                info.is_signer = meta.is_signer;
+                info.is_writable = meta.is_writable;
+                info
+            })
+            .collect();
+
+        dispatch(processor, &instruction.program_id, &infos, &instruction.data)?;
+        drop(infos);
+        drop(shared);
+
+        for (key, (cell, executable)) in &memory {
+            working.insert(*key, cell.read_back(*executable));
+        }
+        Ok(())
+    }
+
+    pub fn logs(&self) -> Vec<String> {
+        with_runtime(|rt| rt.logs.clone())
+    }
+
+    /// Return data left by the last transaction, if any.
+    pub fn return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        with_runtime(|rt| rt.return_data.clone())
+    }
+
+    /// Every `E` emitted by the last transaction, in order.
+    pub fn events<E: Event>(&self) -> Vec<E> {
+        with_runtime(|rt| {
+            rt.events
+                .iter()
+                .filter(|(_, data)| data.starts_with(&E::DISCRIMINATOR))
+                .map(|(_, data)| {
+                    E::deserialize(&mut &data[8..]).expect("event does not deserialize")
+                })
+                .collect()
+        })
+    }
+
+    /// The single `E` emitted by the last transaction.
+    pub fn event<E: Event>(&self) -> E {
+        let mut events = self.events::<E>();
+        assert_eq!(events.len(), 1, "expected exactly one event");
+        events.remove(0)
+    }
+
+    /// Creates and initializes a mint owned by `token_program`.
+    pub fn create_mint(&mut self, token_program: &Pubkey, authority: &Pubkey, decimals: u8) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        self.create_mint_at(&mint, token_program, authority, decimals);
+        mint
+    }
+
+    /// Creates and initializes a mint at a chosen address.
+    pub fn create_mint_at(
+        &mut self,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        authority: &Pubkey,
+        decimals: u8,
+    ) {
+        self.allocate(mint, token_program, spl_token::state::Mint::LEN);
+        let init = spl_token_2022::instruction::initialize_mint2(
+            token_program,
+            mint,
+            authority,
+            None,
+            decimals,
+        )
+        .unwrap();
+        self.process(&[init], &[]).expect("mint initializes");
+    }
+
+    /// Creates a Token-2022 mint that charges `fee_bps` on every transfer,
+    /// capped at `max_fee`.
+    pub fn create_fee_mint(
+        &mut self,
+        authority: &Pubkey,
+        decimals: u8,
+        fee_bps: u16,
+        max_fee: u64,
+    ) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        let len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::TransferFeeConfig,
+        ])
+        .unwrap();
+        self.allocate(&mint, &spl_token_2022::ID, len);
+        let fee_config =
+            spl_token_2022::extension::transfer_fee::instruction::initialize_transfer_fee_config(
+                &spl_token_2022::ID,
+                &mint,
+                Some(authority),
+                Some(authority),
+                fee_bps,
+                max_fee,
+            )
+            .unwrap();
+        let init = spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::ID,
+            &mint,
+            authority,
+            None,
+            decimals,
+        )
+        .unwrap();
+        self.process(&[fee_config, init], &[]).expect("fee mint initializes");
+        mint
+    }
+
+    /// Creates and initializes a token account for `mint` owned by `owner`.
+    pub fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+        let account = Pubkey::new_unique();
+        self.create_token_account_at(&account, mint, owner);
+        account
+    }
+
+    /// Creates and initializes a token account at a chosen address.
+    pub fn create_token_account_at(&mut self, account: &Pubkey, mint: &Pubkey, owner: &Pubkey) {
+        let token_program = self.accounts[mint].owner;
+        let len = if token_program == spl_token_2022::ID {
+            let data = &self.accounts[mint].data;
+            let state =
+                StateWithExtensions::<spl_token_2022::state::Mint>::unpack(data).unwrap();
+            let extensions = ExtensionType::get_required_init_account_extensions(
+                &state.get_extension_types().unwrap(),
+            );
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(
+                &extensions,
+            )
+            .unwrap()
+        } else {
+            spl_token::state::Account::LEN
+        };
+        self.allocate(account, &token_program, len);
+        let init = spl_token_2022::instruction::initialize_account3(
+            &token_program,
+            account,
+            mint,
+            owner,
+        )
+        .unwrap();
+        self.process(&[init], &[]).expect("token account initializes");
+    }
+
+    /// Mints `amount` of `mint` into `destination`, signed by `authority`.
+    pub fn mint_to(&mut self, mint: &Pubkey, destination: &Pubkey, authority: &Pubkey, amount: u64) {
+        let token_program = self.accounts[mint].owner;
+        let mint_to = spl_token_2022::instruction::mint_to(
+            &token_program,
+            mint,
+            destination,
+            authority,
+            &[],
+            amount,
+        )
+        .unwrap();
+        self.process(&[mint_to], &[*authority]).expect("mint succeeds");
+    }
+
+    /// The base state of a token account under either token program.
+    pub fn token_account(&self, key: &Pubkey) -> spl_token::state::Account {
+        let data = &self.accounts[key].data;
+        spl_token::state::Account::unpack_from_slice(&data[..spl_token::state::Account::LEN])
+            .unwrap()
+    }
+
+    pub fn token_balance(&self, key: &Pubkey) -> u64 {
+        self.token_account(key).amount
+    }
+
+    /// The base state of a mint under either token program.
+    pub fn mint(&self, key: &Pubkey) -> spl_token::state::Mint {
+        let data = &self.accounts[key].data;
+        spl_token::state::Mint::unpack_from_slice(&data[..spl_token::state::Mint::LEN]).unwrap()
+    }
+
+    fn allocate(&mut self, key: &Pubkey, owner: &Pubkey, len: usize) {
+        self.accounts.insert(
+            *key,
+            AccountState {
+                lamports: FUNDED_LAMPORTS,
+                data: vec![0; len],
+                owner: *owner,
+                executable: false,
+            },
+        );
+    }
+}
+
+/// The error code an Anchor error surfaces as.
+pub fn anchor_error(error: impl Into<anchor_lang::error::Error>) -> ProgramError {
+    ProgramError::from(error.into())
+}
+
+/// Asserts that `result` failed with `expected`.
+#[track_caller]
+pub fn assert_error(
+    result: std::result::Result<(), ProgramError>,
+    expected: impl Into<anchor_lang::error::Error>,
+) {
+    assert_eq!(result, Err(anchor_error(expected)));
+}
+
+/// Rent's bincode layout, for the sysvar account.
+fn bincode_rent(rent: &Rent) -> Vec<u8> {
+    let mut data = Vec::with_capacity(17);
+    data.extend_from_slice(&rent.lamports_per_byte_year.to_le_bytes());
+    data.extend_from_slice(&rent.exemption_threshold.to_le_bytes());
+    data.push(rent.burn_percent);
+    data
+}
+
+/// The discriminator-prefixed bytes of an Anchor account value.
+pub fn account_bytes<T: AccountSerialize + Discriminator>(value: &T) -> Vec<u8> {
+    let mut data = Vec::new();
+    value.try_serialize(&mut data).unwrap();
+    data
+}